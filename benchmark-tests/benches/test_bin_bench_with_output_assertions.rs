@@ -0,0 +1,28 @@
+use iai_callgrind::{
+    binary_benchmark_group, main, Arg, BinaryBenchmarkConfig, BinaryBenchmarkGroup, ExitWith, Run,
+};
+
+// The binary benchmarks double as smoke tests. `benchmark-tests-printargs` prints each of its
+// arguments on a separate line and the benchmark fails if the output doesn't match the
+// expectations.
+fn setup_group_tests_output_assertions(group: &mut BinaryBenchmarkGroup) {
+    group
+        .bench(
+            Run::with_arg(Arg::new("contains", ["foo", "bar"]))
+                .exit_with(ExitWith::Success)
+                .assert_stdout_contains("foo\nbar\n"),
+        )
+        .bench(
+            Run::with_arg(Arg::new("matches", ["--count=10"]))
+                .assert_stdout_matches(r"(?m)^--count=\d+$"),
+        )
+        .bench(Run::with_arg(Arg::new("stderr_is_empty", ["foo"])).assert_stderr_matches(r"\A\z"));
+}
+
+binary_benchmark_group!(
+    name = test_output_assertions;
+    config = BinaryBenchmarkConfig::default().assert_stdout_contains("benchmark-tests-printargs");
+    benchmark = |"benchmark-tests-printargs", group: &mut BinaryBenchmarkGroup| setup_group_tests_output_assertions(group)
+);
+
+main!(binary_benchmark_groups = test_output_assertions);
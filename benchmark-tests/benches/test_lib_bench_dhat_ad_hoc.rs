@@ -0,0 +1,43 @@
+use std::hint::black_box;
+
+use iai_callgrind::client_requests::dhat;
+use iai_callgrind::{
+    library_benchmark, library_benchmark_group, main, LibraryBenchmarkConfig, Tool, ValgrindTool,
+};
+
+/// Push `len` values into a vector with the initial `capacity`
+///
+/// Each reallocation of the vector is recorded as ad-hoc event with the new capacity as weight,
+/// so DHAT in ad-hoc mode reports the number of reallocations as `Total events` and the sum of
+/// the new capacities as `Total units`. Outside of valgrind, the client request does nothing.
+/// The `benchmark-tests` only enable the `client_requests_defs` feature, so here the client
+/// request is a no-op and this benchmark checks that DHAT runs in ad-hoc mode.
+fn push_counting_reallocations(capacity: usize, len: u64) -> Vec<u64> {
+    let mut vec = Vec::with_capacity(capacity);
+    for value in 0..len {
+        let old_capacity = vec.capacity();
+        vec.push(value);
+        if vec.capacity() != old_capacity {
+            dhat::ad_hoc_event(vec.capacity());
+        }
+    }
+    vec
+}
+
+#[library_benchmark(
+    config = LibraryBenchmarkConfig::default()
+        .tool(Tool::new(ValgrindTool::DHAT).args(["--mode=ad-hoc"]))
+)]
+#[bench::no_capacity(0)]
+#[bench::half_capacity(500)]
+#[bench::full_capacity(1000)]
+fn bench_push(capacity: usize) -> Vec<u64> {
+    black_box(push_counting_reallocations(capacity, 1000))
+}
+
+library_benchmark_group!(
+    name = dhat_ad_hoc;
+    benchmarks = bench_push
+);
+
+main!(library_benchmark_groups = dhat_ad_hoc);
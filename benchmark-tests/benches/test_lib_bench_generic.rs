@@ -0,0 +1,56 @@
+use std::hint::black_box;
+
+use iai_callgrind::{library_benchmark, library_benchmark_group, main};
+
+/// The values of the benchmarked types for the setup of the arrays
+trait FromIndex {
+    fn from_index(index: u32) -> Self;
+}
+
+impl FromIndex for u32 {
+    fn from_index(index: u32) -> Self {
+        index
+    }
+}
+
+impl FromIndex for u64 {
+    fn from_index(index: u32) -> Self {
+        u64::from(index)
+    }
+}
+
+impl FromIndex for String {
+    fn from_index(index: u32) -> Self {
+        format!("{index:05}")
+    }
+}
+
+fn setup_worst_case_array<T: FromIndex>(len: u32) -> Vec<T> {
+    (0..len).rev().map(T::from_index).collect()
+}
+
+// A generic sort, which is benchmarked for all `types` separately
+fn bubble_sort<T: Ord>(mut array: Vec<T>) -> Vec<T> {
+    for i in 0..array.len() {
+        for j in 0..array.len() - i - 1 {
+            if array[j + 1] < array[j] {
+                array.swap(j, j + 1);
+            }
+        }
+    }
+    array
+}
+
+#[library_benchmark(types = [u32, u64, String])]
+#[bench::small(setup_worst_case_array::<T>(10))]
+#[bench::large(setup_worst_case_array::<T>(100))]
+fn bench_sort<T: Ord>(array: Vec<T>) -> Vec<T> {
+    black_box(bubble_sort(array))
+}
+
+library_benchmark_group!(
+    name = generic;
+    benchmarks = bench_sort
+);
+
+main!(library_benchmark_groups = generic);
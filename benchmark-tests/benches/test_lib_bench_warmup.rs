@@ -0,0 +1,29 @@
+use std::hint::black_box;
+
+use benchmark_tests::{bubble_sort, setup_worst_case_array};
+use iai_callgrind::{library_benchmark, library_benchmark_group, main, LibraryBenchmarkConfig};
+
+#[library_benchmark]
+#[bench::worst_case_10(setup_worst_case_array(10))]
+fn bench_without_warmup(value: Vec<i32>) -> Vec<i32> {
+    black_box(bubble_sort(value))
+}
+
+// The costs of the warmup iterations are discarded, so the comparison with
+// `bench_without_warmup` has to show no change
+#[library_benchmark(config = LibraryBenchmarkConfig::default().warmup(3))]
+#[bench::worst_case_10(setup_worst_case_array(10))]
+fn bench_with_warmup(value: Vec<i32>) -> Vec<i32> {
+    black_box(bubble_sort(value))
+}
+
+library_benchmark_group!(
+    name = bench_warmup;
+    compare_by_id = true;
+    benchmarks = bench_without_warmup, bench_with_warmup
+);
+
+main!(
+    config = LibraryBenchmarkConfig::default().raw_callgrind_args(["--cache-sim=no"]);
+    library_benchmark_groups = bench_warmup
+);
@@ -31,8 +31,8 @@ use quote::{format_ident, quote, ToTokens, TokenStreamExt};
 use syn::parse::Parse;
 use syn::punctuated::Punctuated;
 use syn::{
-    parse2, parse_quote, Attribute, Expr, ExprArray, ExprPath, Ident, ItemFn, MetaList,
-    MetaNameValue, Token,
+    parse2, parse_quote, Attribute, Expr, ExprArray, ExprPath, FnArg, GenericParam, Ident, ItemFn,
+    MetaList, MetaNameValue, Token, Type,
 };
 
 #[derive(Debug, Clone)]
@@ -49,6 +49,7 @@ struct LibBenchAttribute {
 #[derive(Debug, Default)]
 struct LibraryBenchmark {
     config: Option<Expr>,
+    types: Vec<Type>,
     benches: Vec<LibBenchAttribute>,
 }
 
@@ -120,9 +121,13 @@ impl LibBenchAttribute {
         }
     }
 
-    fn render_as_lib_bench(&self) -> TokenStream2 {
+    /// Render the `InternalMacroLibBench` of this benchmark
+    ///
+    /// The functions of the benchmarks of a generic benchmark function are rendered into the
+    /// `module` of the concrete type and the `id_str` is suffixed with the type name.
+    fn render_as_lib_bench(&self, module: Option<&Ident>, id_str: &str) -> TokenStream2 {
         let id = &self.id;
-        let id_str = self.id.to_string();
+        let module = module.map(|module| quote! { #module:: });
         let args = if let Some(setup) = self.setup.as_ref() {
             let exprs = &self.args.0;
             let tokens = quote! {
@@ -143,8 +148,8 @@ impl LibBenchAttribute {
                 iai_callgrind::internal::InternalMacroLibBench {
                     id_display: Some(#id_str),
                     args_display: Some(#args),
-                    func: #id,
-                    config: Some(#conf_ident)
+                    func: #module #id,
+                    config: Some(#module #conf_ident)
                 }
             }
         } else {
@@ -152,7 +157,7 @@ impl LibBenchAttribute {
                 iai_callgrind::internal::InternalMacroLibBench {
                     id_display: Some(#id_str),
                     args_display: Some(#args),
-                    func: #id,
+                    func: #module #id,
                     config: None
                 }
             }
@@ -489,6 +494,189 @@ impl LibraryBenchmark {
         }
     }
 
+    /// Return the concrete types of the type parameters of the `item_fn` for each of the `types`
+    fn concrete_types(&self, item_fn: &ItemFn) -> Vec<(String, Vec<Type>)> {
+        let generics = &item_fn.sig.generics;
+        if let Some(param) = generics.const_params().next() {
+            abort!(
+                param, "Const generic parameters are not supported";
+                help = "The `types` argument can only be used with type parameters"
+            );
+        }
+        let num_params = generics.type_params().count();
+        if num_params == 0 {
+            abort!(
+                item_fn.sig.ident, "The benchmark function has no type parameters";
+                help = "The `types` argument needs a generic benchmark function";
+                note = "#[library_benchmark(types = [u32, u64])] fn bench<T>(...) { ... }"
+            );
+        }
+
+        let mut concrete_types: Vec<(String, Vec<Type>)> = vec![];
+        for ty in &self.types {
+            let types = match ty {
+                Type::Tuple(tuple) if num_params > 1 && tuple.elems.len() == num_params => {
+                    tuple.elems.iter().cloned().collect()
+                }
+                _ if num_params == 1 => vec![ty.clone()],
+                _ => {
+                    abort!(
+                        ty, "Expected a tuple of {} types", num_params;
+                        help = "The benchmark function has {} type parameters", num_params;
+                        note = "#[library_benchmark(types = [(u32, u64), (u64, u32)])]"
+                    );
+                }
+            };
+            let name = type_name(ty);
+            if concrete_types.iter().any(|(other, _)| *other == name) {
+                abort!(
+                    ty, "Duplicate type name: {}", name;
+                    help = "The names of the types need to be unique after replacing all \
+                        characters which are not alphanumeric with `_`"
+                );
+            }
+            concrete_types.push((name, types));
+        }
+        concrete_types
+    }
+
+    /// Render a generic benchmark function with the `types` argument
+    ///
+    /// The benchmarks are rendered for each of the `types` into a separate module with type
+    /// aliases for the type parameters. A non-generic wrapper with the `export_name` calls the
+    /// generic benchmark function with the concrete types, so the benchmark function itself is
+    /// monomorphized by the compiler for each type.
+    #[allow(clippy::too_many_lines)]
+    fn render_generic(self, item_fn: &ItemFn) -> TokenStream2 {
+        let concrete_types = self.concrete_types(item_fn);
+        let new_item_fn = ItemFn {
+            attrs: vec![],
+            vis: syn::Visibility::Inherited,
+            sig: item_fn.sig.clone(),
+            block: item_fn.block.clone(),
+        };
+
+        let mod_name = &item_fn.sig.ident;
+        let callee = &item_fn.sig.ident;
+        let type_params = item_fn
+            .sig
+            .generics
+            .type_params()
+            .map(|param| &param.ident)
+            .collect::<Vec<&Ident>>();
+        let lifetimes = item_fn
+            .sig
+            .generics
+            .params
+            .iter()
+            .filter(|param| matches!(param, GenericParam::Lifetime(_)))
+            .collect::<Vec<&GenericParam>>();
+        let lifetimes = (!lifetimes.is_empty()).then(|| quote! { <#(#lifetimes),*> });
+        let mut inputs = vec![];
+        let mut arg_idents = vec![];
+        for (i, input) in item_fn.sig.inputs.iter().enumerate() {
+            match input {
+                FnArg::Typed(pat_type) => {
+                    inputs.push(&pat_type.ty);
+                    arg_idents.push(format_ident!("arg{i}"));
+                }
+                FnArg::Receiver(receiver) => {
+                    abort!(
+                        receiver,
+                        "A benchmark function can't have a `self` parameter"
+                    );
+                }
+            }
+        }
+        let output = &item_fn.sig.output;
+
+        let is_single = self.benches.is_empty();
+        let benches = if is_single {
+            vec![LibBenchAttribute {
+                id: format_ident!("wrapper"),
+                args: Arguments(vec![]),
+                config: None,
+                setup: None,
+            }]
+        } else {
+            self.benches
+        };
+
+        let mut modules = TokenStream2::new();
+        let mut lib_benches = vec![];
+        for (name, types) in concrete_types {
+            let module = format_ident!("__{name}");
+            let export_name = format!("iai_callgrind::bench::{callee}::{name}");
+            let mut funcs = TokenStream2::new();
+            for bench in &benches {
+                funcs.append_all(bench.render_as_function(callee));
+                if is_single {
+                    lib_benches.push(quote! {
+                        iai_callgrind::internal::InternalMacroLibBench {
+                            id_display: Some(#name),
+                            args_display: None,
+                            func: #module::wrapper,
+                            config: None
+                        }
+                    });
+                } else {
+                    let id_str = format!("{}_{name}", bench.id);
+                    lib_benches.push(bench.render_as_lib_bench(Some(&module), &id_str));
+                }
+            }
+            modules.append_all(quote! {
+                mod #module {
+                    use super::*;
+
+                    #(type #type_params = #types;)*
+
+                    #[inline(never)]
+                    #[export_name = #export_name]
+                    fn #callee #lifetimes(#(#arg_idents: #inputs),*) #output {
+                        super::#callee::<#(#type_params),*>(#(#arg_idents),*)
+                    }
+
+                    #funcs
+                }
+            });
+        }
+
+        let config = if let Some(config) = self.config {
+            quote!(
+                #[inline(never)]
+                pub fn get_config()
+                -> Option<iai_callgrind::internal::InternalLibraryBenchmarkConfig>
+                {
+                    Some(#config.into())
+                }
+            )
+        } else {
+            quote!(
+                #[inline(never)]
+                pub fn get_config()
+                -> Option<iai_callgrind::internal::InternalLibraryBenchmarkConfig> {
+                    None
+                }
+            )
+        };
+        quote! {
+            mod #mod_name {
+                use super::*;
+
+                #[inline(never)]
+                #new_item_fn
+
+                pub const BENCHES: &[iai_callgrind::internal::InternalMacroLibBench]= &[
+                    #(#lib_benches,)*
+                ];
+
+                #config
+
+                #modules
+            }
+        }
+    }
+
     fn render_benches(self, item_fn: &ItemFn) -> TokenStream2 {
         let new_item_fn = ItemFn {
             attrs: vec![],
@@ -504,7 +692,7 @@ impl LibraryBenchmark {
         let mut lib_benches = vec![];
         for bench in self.benches {
             funcs.append_all(bench.render_as_function(callee));
-            lib_benches.push(bench.render_as_lib_bench());
+            lib_benches.push(bench.render_as_lib_bench(None, &bench.id.to_string()));
         }
 
         let config = if let Some(config) = self.config {
@@ -547,31 +735,77 @@ impl LibraryBenchmark {
 
 impl Parse for LibraryBenchmark {
     fn parse(input: syn::parse::ParseStream) -> syn::Result<Self> {
-        if input.is_empty() {
-            Ok(Self::default())
-        } else {
-            let pairs = input.parse_terminated(MetaNameValue::parse, Token![,])?;
-            if pairs.len() > 1 {
+        let mut this = Self::default();
+        let mut has_types = false;
+        while !input.is_empty() {
+            let key = input.parse::<Ident>()?;
+            input.parse::<Token![=]>()?;
+            if key == "config" {
+                if this.config.is_some() {
+                    abort!(
+                        key, "Duplicate argument: `config`";
+                        help = "`config` is allowed only once"
+                    );
+                }
+                this.config = Some(input.parse::<Expr>()?);
+            } else if key == "types" {
+                if has_types {
+                    abort!(
+                        key, "Duplicate argument: `types`";
+                        help = "`types` is allowed only once"
+                    );
+                }
+                let content;
+                syn::bracketed!(content in input);
+                this.types = content
+                    .parse_terminated(Type::parse, Token![,])?
+                    .into_iter()
+                    .collect();
+                has_types = true;
+            } else {
                 abort!(
-                    pairs, "At most one argument is allowed";
-                    help = "#[library_benchmark] or #[library_benchmark(config = ....)]"
+                    key, "Invalid argument: {}", key;
+                    help = "Valid arguments are: `config`, `types`";
+                    note = "#[library_benchmark(config = ...)] or \
+                        #[library_benchmark(types = [u32, String, ...])]"
                 );
-            } else {
-                Ok(pairs.first().map_or_else(Self::default, |pair| {
-                    if pair.path.is_ident("config") {
-                        Self {
-                            config: Some(pair.value.clone()),
-                            ..Default::default()
-                        }
-                    } else {
-                        abort!(
-                            pair, "Only the config argument is allowed";
-                            help = "#[library_benchmark(config = ....)]"
-                        );
-                    }
-                }))
+            }
+
+            if !input.is_empty() {
+                input.parse::<Token![,]>()?;
             }
         }
+        Ok(this)
+    }
+}
+
+/// Return the name of the concrete `ty` which identifies the benchmarks of this type
+///
+/// The name is used in the ids of the benchmarks and therefore in the names of the output files.
+/// All characters which are not ascii alphanumeric are replaced with a single `_`, so `Vec<u8>` is
+/// named `vec_u8` and `&'static str` is named `static_str`.
+fn type_name(ty: &Type) -> String {
+    let name = ty
+        .to_token_stream()
+        .to_string()
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() {
+                c.to_ascii_lowercase()
+            } else {
+                '_'
+            }
+        })
+        .collect::<String>();
+    let name = name
+        .split('_')
+        .filter(|s| !s.is_empty())
+        .collect::<Vec<&str>>()
+        .join("_");
+    if name.is_empty() {
+        "unit".to_owned()
+    } else {
+        name
     }
 }
 
@@ -782,6 +1016,41 @@ impl MultipleArguments {
 /// # fn main() {
 /// # }
 /// ```
+///
+/// Generic benchmark functions are benchmarked for each of the concrete types in the `types`
+/// argument. The benchmarks of each type are named by the type, so the benches below have the ids
+/// `small_u32`, `small_u64`, `large_u32` and `large_u64`. In the `bench` arguments and `setup`
+/// functions, the type parameter refers to the concrete type. Multiple type parameters are
+/// specified as tuple like `types = [(u8, u16), (u32, u64)]`.
+///
+/// ```rust
+/// # use iai_callgrind_macros::library_benchmark;
+/// # mod iai_callgrind {
+/// # pub struct LibraryBenchmarkConfig {}
+/// # pub mod internal {
+/// # pub struct InternalMacroLibBench {
+/// #   pub id_display: Option<&'static str>,
+/// #   pub args_display: Option<&'static str>,
+/// #   pub func: fn(),
+/// #   pub config: Option<fn() -> InternalLibraryBenchmarkConfig>
+/// # }
+/// # pub struct InternalLibraryBenchmarkConfig {}
+/// # }
+/// # }
+/// fn setup_worst_case_array<T: From<u16>>(len: u16) -> Vec<T> {
+///     (0..len).rev().map(T::from).collect()
+/// }
+///
+/// #[library_benchmark(types = [u32, u64])]
+/// #[bench::small(setup_worst_case_array::<T>(10))]
+/// #[bench::large(setup_worst_case_array::<T>(1000))]
+/// fn bench_sort<T: Ord>(mut array: Vec<T>) -> Vec<T> {
+///     array.sort();
+///     std::hint::black_box(array)
+/// }
+/// # fn main() {
+/// # }
+/// ```
 #[proc_macro_attribute]
 #[proc_macro_error]
 pub fn library_benchmark(args: TokenStream, input: TokenStream) -> TokenStream {
@@ -796,7 +1065,9 @@ fn render_library_benchmark(args: TokenStream2, input: TokenStream2) -> syn::Res
     let item_fn = parse2::<ItemFn>(input)?;
 
     library_benchmark.extract_benches(&item_fn)?;
-    if library_benchmark.benches.is_empty() {
+    if !library_benchmark.types.is_empty() {
+        Ok(library_benchmark.render_generic(&item_fn))
+    } else if library_benchmark.benches.is_empty() {
         Ok(library_benchmark.render_single(&item_fn))
     } else {
         Ok(library_benchmark.render_benches(&item_fn))
@@ -1383,4 +1654,164 @@ mod tests {
         .unwrap();
         assert_eq!(actual, expected);
     }
+
+    #[test]
+    fn test_type_name() {
+        for (ty, expected) in [
+            (quote!(u32), "u32"),
+            (quote!(String), "string"),
+            (quote!(Vec<u8>), "vec_u8"),
+            (quote!(&'static str), "static_str"),
+            (quote!([u8; 4]), "u8_4"),
+            (quote!((u32, std::string::String)), "u32_std_string_string"),
+            (quote!(()), "unit"),
+        ] {
+            assert_eq!(type_name(&parse2::<Type>(ty).unwrap()), expected);
+        }
+    }
+
+    #[test]
+    fn test_library_benchmark_with_types() {
+        let input = quote!(
+            #[bench::first(1)]
+            fn some<T: Ord>(var: T) -> T {
+                var
+            }
+        );
+
+        let expected: Model = parse_quote!(
+            mod some {
+                use super::*;
+
+                #[inline(never)]
+                fn some<T: Ord>(var: T) -> T {
+                    var
+                }
+
+                pub const BENCHES: &[iai_callgrind::internal::InternalMacroLibBench]= &[
+                    iai_callgrind::internal::InternalMacroLibBench {
+                        id_display: Some("first_u32"),
+                        args_display: Some("1"),
+                        func: __u32::first,
+                        config: None
+                    },
+                    iai_callgrind::internal::InternalMacroLibBench {
+                        id_display: Some("first_u64"),
+                        args_display: Some("1"),
+                        func: __u64::first,
+                        config: None
+                    },
+                ];
+
+                #[inline(never)]
+                pub fn get_config()
+                -> Option<iai_callgrind::internal::InternalLibraryBenchmarkConfig> {
+                    None
+                }
+
+                mod __u32 {
+                    use super::*;
+
+                    type T = u32;
+
+                    #[inline(never)]
+                    #[export_name = "iai_callgrind::bench::some::u32"]
+                    fn some(arg0: T) -> T {
+                        super::some::<T>(arg0)
+                    }
+
+                    #[inline(never)]
+                    pub fn first() {
+                        let _ = std::hint::black_box(some(std::hint::black_box(1)));
+                    }
+                }
+
+                mod __u64 {
+                    use super::*;
+
+                    type T = u64;
+
+                    #[inline(never)]
+                    #[export_name = "iai_callgrind::bench::some::u64"]
+                    fn some(arg0: T) -> T {
+                        super::some::<T>(arg0)
+                    }
+
+                    #[inline(never)]
+                    pub fn first() {
+                        let _ = std::hint::black_box(some(std::hint::black_box(1)));
+                    }
+                }
+            }
+        );
+        let actual: Model =
+            parse2(render_library_benchmark(quote!(types = [u32, u64]), input).unwrap()).unwrap();
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn test_library_benchmark_with_types_without_bench() {
+        let input = quote!(
+            fn some<A: Default, B: Default>() -> (A, B) {
+                Default::default()
+            }
+        );
+
+        let actual: Model = parse2(
+            render_library_benchmark(
+                quote!(
+                    config = LibraryBenchmarkConfig::default(),
+                    types = [(u8, u16)]
+                ),
+                input,
+            )
+            .unwrap(),
+        )
+        .unwrap();
+        let expected: Model = parse_quote!(
+            mod some {
+                use super::*;
+
+                #[inline(never)]
+                fn some<A: Default, B: Default>() -> (A, B) {
+                    Default::default()
+                }
+
+                pub const BENCHES: &[iai_callgrind::internal::InternalMacroLibBench]= &[
+                    iai_callgrind::internal::InternalMacroLibBench {
+                        id_display: Some("u8_u16"),
+                        args_display: None,
+                        func: __u8_u16::wrapper,
+                        config: None
+                    },
+                ];
+
+                #[inline(never)]
+                pub fn get_config()
+                -> Option<iai_callgrind::internal::InternalLibraryBenchmarkConfig>
+                {
+                    Some(LibraryBenchmarkConfig::default().into())
+                }
+
+                mod __u8_u16 {
+                    use super::*;
+
+                    type A = u8;
+                    type B = u16;
+
+                    #[inline(never)]
+                    #[export_name = "iai_callgrind::bench::some::u8_u16"]
+                    fn some() -> (A, B) {
+                        super::some::<A, B>()
+                    }
+
+                    #[inline(never)]
+                    pub fn wrapper() {
+                        let _ = std::hint::black_box(some());
+                    }
+                }
+            }
+        );
+        assert_eq!(actual, expected);
+    }
 }
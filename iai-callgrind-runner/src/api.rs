@@ -2,6 +2,7 @@
 use std::ffi::OsString;
 use std::fmt::Display;
 use std::path::PathBuf;
+use std::str::FromStr;
 
 #[cfg(feature = "schema")]
 use schemars::JsonSchema;
@@ -34,8 +35,12 @@ pub struct BinaryBenchmarkConfig {
     pub env_clear: Option<bool>,
     pub current_dir: Option<PathBuf>,
     pub entry_point: Option<String>,
+    pub regions: Vec<String>,
     pub exit_with: Option<ExitWith>,
+    pub output_assertions: Vec<OutputAssertion>,
     pub raw_callgrind_args: RawArgs,
+    pub cachegrind: Option<bool>,
+    pub default_tool: Option<ValgrindTool>,
     pub envs: Vec<(OsString, Option<OsString>)>,
     pub flamegraph_config: Option<FlamegraphConfig>,
     pub regression_config: Option<RegressionConfig>,
@@ -74,7 +79,9 @@ pub enum Direction {
 /// Depending on the options passed to Callgrind, these are the events that Callgrind can produce.
 /// See the [Callgrind
 /// documentation](https://valgrind.org/docs/manual/cl-manual.html#cl-manual.options) for details.
-#[derive(Debug, Clone, Copy, Hash, PartialEq, Eq, Serialize, Deserialize)]
+///
+/// The order of the variants is the canonical order of the event kinds.
+#[derive(Debug, Clone, Copy, Hash, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
 #[cfg_attr(feature = "schema", derive(JsonSchema))]
 pub enum EventKind {
     /// The default event. I cache reads (which equals the number of instructions executed)
@@ -111,6 +118,8 @@ pub enum EventKind {
     RamHits,
     /// Derived event showing the total amount of cache reads and writes (--cache-sim=yes)
     TotalRW,
+    /// Derived event showing the total amount of data reads and writes (Dr + Dw) (--cache-sim=yes)
+    DataRW,
     /// Derived event showing estimated CPU cycles (--cache-sim=yes)
     EstimatedCycles,
     /// Conditional branches executed (--branch-sim=yes)
@@ -135,6 +144,8 @@ pub enum EventKind {
     SpLoss1,
     /// Counter showing bad spatial locality for LL caches (--cachuse=yes)
     SpLoss2,
+    /// Synthetic event showing the weighted sum of the events of the [`RegressionConfig::score`]
+    Score,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -144,6 +155,17 @@ pub enum ExitWith {
     Code(i32),
 }
 
+/// An assertion on the captured output of a benchmarked binary
+///
+/// The `String` of the `*Matches` variants is a regular expression.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum OutputAssertion {
+    StdoutContains(String),
+    StdoutMatches(String),
+    StderrContains(String),
+    StderrMatches(String),
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Fixtures {
     pub path: PathBuf,
@@ -160,6 +182,144 @@ pub struct FlamegraphConfig {
     pub title: Option<String>,
     pub subtitle: Option<String>,
     pub min_width: Option<f64>,
+    pub min_cost: Option<u64>,
+    pub max_depth: Option<usize>,
+    pub max_depth_count_inline: Option<bool>,
+    pub symbol_transforms: Option<Vec<SymbolTransform>>,
+    pub sort_stacks: Option<bool>,
+    pub coalesce_stacks: Option<bool>,
+    pub backup: Option<bool>,
+    pub tag: Option<String>,
+    pub frame_label: Option<FrameLabel>,
+    pub percentage_scale: Option<u64>,
+    pub rounding: Option<Rounding>,
+    pub trim_harness: Option<bool>,
+    pub object_marker: Option<ObjectMarker>,
+    pub color_event_kind: Option<EventKind>,
+    pub count_units: Option<Vec<(EventKind, CountUnit)>>,
+    pub svg_header: Option<String>,
+    pub svg_footer: Option<String>,
+    pub palette: Option<Palette>,
+    pub max_frame_name_length: Option<usize>,
+    pub frame_name_ellipsis: Option<Ellipsis>,
+    pub root_frame: Option<RootFrame>,
+}
+
+/// The unit of the costs of an [`EventKind`] shown in the flamegraphs like `instructions`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CountUnit {
+    /// The unit of a cost of exactly `1` like `instruction`
+    pub singular: String,
+    /// The unit of all other costs like `instructions`
+    pub plural: String,
+}
+
+/// Where the characters of a frame name longer than the maximum length are cut in the flamegraphs
+///
+/// The cut characters are replaced with `...`.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Ellipsis {
+    /// Cut the middle of the name like `my_cra...my_func`, keeping the final path segment of the
+    /// name if possible. This is the default.
+    #[default]
+    Middle,
+    /// Cut the end of the name like `my_crate::my_...`
+    End,
+}
+
+/// The cost which is appended to the label of each frame in a regular flamegraph
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum FrameLabel {
+    /// Append the absolute cost like `my_func (12,345)`
+    Cost,
+    /// Append the cost as percentage of the total cost like `my_func (3.2%)`
+    Percentage,
+    /// Append the absolute cost and the percentage like `my_func (12,345 | 3.2%)`
+    CostAndPercentage,
+}
+
+/// The rounding of the costs scaled to percentages in a regular flamegraph
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Rounding {
+    /// Round down. Frames below the precision of the scale vanish.
+    Floor,
+    /// Round to the nearest integer and half-way costs up. This is the default.
+    #[default]
+    Round,
+    /// Round up. Frames with a cost greater than zero never vanish.
+    Ceil,
+}
+
+/// How the object file (the binary or shared library) of a function is marked in the frames of the
+/// flamegraphs
+///
+/// Some tools which read the folded stacks treat the brackets of [`ObjectMarker::Bracketed`]
+/// specially.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ObjectMarker {
+    /// Append the object file in brackets like `my_func [/path/to/my_exe]`. This is the default.
+    #[default]
+    Bracketed,
+    /// Append the object file as suffix like `my_func (in /path/to/my_exe)`
+    Suffix,
+    /// Don't show the object file like `my_func`
+    None,
+}
+
+/// The synthetic root frame of the flamegraphs which identifies the benchmarked process
+///
+/// The root frame is prepended to all stacks, so all frames of the process are grouped below it.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum RootFrame {
+    /// Don't add a root frame. This is the default.
+    #[default]
+    None,
+    /// Add the name of the command like `my-bin`
+    Command,
+    /// Add the name of the command and the pid of the process like `my-bin (pid 1234)`
+    CommandAndPid,
+}
+
+/// The color palette of the frames of the regular flamegraphs
+///
+/// These are the built-in palettes of inferno, which can also be selected by name (like `mem`)
+/// with [`std::str::FromStr`]. The basic palettes choose the colors randomly from a spectrum, the
+/// semantic palettes (`Java`, `Js`, `Perl`, `Python`, `Rust` and `Wakeup`) use different hues for
+/// different kinds of functions.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Palette {
+    /// Colors from a red-yellow spectrum. This is the default.
+    Hot,
+    /// Colors from a green-blue spectrum
+    Mem,
+    /// Colors from a wide blue spectrum
+    Io,
+    /// Colors from a red spectrum
+    Red,
+    /// Colors from a green spectrum
+    Green,
+    /// Colors from a blue spectrum
+    Blue,
+    /// Colors from an aqua-tinted spectrum
+    Aqua,
+    /// Colors from a yellow spectrum
+    Yellow,
+    /// Colors from a purple spectrum
+    Purple,
+    /// Colors from an orange spectrum
+    Orange,
+    /// Colors by the semantics of Java frames
+    Java,
+    /// Colors by the semantics of JavaScript frames
+    Js,
+    /// Colors by the semantics of Perl frames
+    Perl,
+    /// Colors by the semantics of Python frames
+    Python,
+    /// Colors by the semantics of Rust frames
+    Rust,
+    /// Colors from an aqua-tinted spectrum on a blue background
+    Wakeup,
 }
 
 /// The kind of `Flamegraph` which is going to be constructed
@@ -169,6 +329,9 @@ pub enum FlamegraphKind {
     Regular,
     /// A differential flamegraph showing the differences between the new and old callgrind run
     Differential,
+    /// The regular flamegraph and a html page showing the flamegraphs of the old and new
+    /// callgrind run side by side instead of the overlaid `Differential` flamegraph
+    SideBySide,
     /// All flamegraph kinds that can be constructed (`Regular` and `Differential`). This
     /// is the default.
     All,
@@ -201,6 +364,9 @@ pub struct LibraryBenchmarkBenches {
 pub struct LibraryBenchmarkConfig {
     pub env_clear: Option<bool>,
     pub raw_callgrind_args: RawArgs,
+    pub cachegrind: Option<bool>,
+    pub default_tool: Option<ValgrindTool>,
+    pub warmup: Option<usize>,
     pub envs: Vec<(OsString, Option<OsString>)>,
     pub flamegraph_config: Option<FlamegraphConfig>,
     pub regression_config: Option<RegressionConfig>,
@@ -222,7 +388,10 @@ pub struct RawArgs(pub Vec<String>);
 #[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
 pub struct RegressionConfig {
     pub limits: Vec<(EventKind, f64)>,
+    pub min_abs_diffs: Vec<(EventKind, u64)>,
     pub fail_fast: Option<bool>,
+    pub score: Vec<(EventKind, f64)>,
+    pub clear_limits: bool,
 }
 
 #[derive(Debug, Default, Clone, Serialize, Deserialize)]
@@ -232,6 +401,19 @@ pub struct Run {
     pub config: BinaryBenchmarkConfig,
 }
 
+/// A transformation of the symbol names in the stacks of a flamegraph
+///
+/// Symbol names are transformed before equal stacks are merged, so stacks which become equal
+/// after the transformation are merged into a single stack with the summed up costs.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum SymbolTransform {
+    /// Strip the hash suffix (like `::h0123456789abcdef`) of rust symbols
+    StripHash,
+    /// Replace all matches of the regular expression (the first value) with the replacement
+    /// (the second value). The replacement can contain references to capture groups like `$1`.
+    Replace(String, String),
+}
+
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct Tool {
     pub kind: ValgrindTool,
@@ -247,6 +429,10 @@ pub struct Tools(pub Vec<Tool>);
 /// An enum with all possible valgrind tools
 #[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub enum ValgrindTool {
+    /// [Callgrind: a call-graph generating cache and branch prediction profiler](https://valgrind.org/docs/manual/cl-manual.html)
+    Callgrind,
+    /// [Cachegrind: a high-precision tracing profiler](https://valgrind.org/docs/manual/cg-manual.html)
+    Cachegrind,
     /// [Memcheck: a memory error detector](https://valgrind.org/docs/manual/mc-manual.html)
     Memcheck,
     /// [Helgrind: a thread error detector](https://valgrind.org/docs/manual/hg-manual.html)
@@ -272,16 +458,27 @@ impl BinaryBenchmarkConfig {
             self.env_clear = update_option(&self.env_clear, &other.env_clear);
             self.current_dir = update_option(&self.current_dir, &other.current_dir);
             self.entry_point = update_option(&self.entry_point, &other.entry_point);
+            self.regions.extend_from_slice(&other.regions);
             self.exit_with = update_option(&self.exit_with, &other.exit_with);
+            self.output_assertions
+                .extend_from_slice(&other.output_assertions);
 
             self.raw_callgrind_args
                 .extend_ignore_flag(other.raw_callgrind_args.0.iter());
+            self.cachegrind = update_option(&self.cachegrind, &other.cachegrind);
+            self.default_tool = update_option(&self.default_tool, &other.default_tool);
 
             self.envs.extend_from_slice(&other.envs);
-            self.flamegraph_config =
-                update_option(&self.flamegraph_config, &other.flamegraph_config);
-            self.regression_config =
-                update_option(&self.regression_config, &other.regression_config);
+            merge_option(
+                &mut self.flamegraph_config,
+                &other.flamegraph_config,
+                FlamegraphConfig::update_from,
+            );
+            merge_option(
+                &mut self.regression_config,
+                &other.regression_config,
+                RegressionConfig::update_from,
+            );
             if let Some(other_tools) = &other.tools_override {
                 self.tools = other_tools.clone();
             } else if !other.tools.is_empty() {
@@ -320,16 +517,29 @@ impl EventKind {
     /// * [`EventKind::LLhits`]
     /// * [`EventKind::RamHits`]
     /// * [`EventKind::TotalRW`]
+    /// * [`EventKind::DataRW`]
     /// * [`EventKind::EstimatedCycles`]
     pub fn is_derived(&self) -> bool {
-        matches!(
-            self,
-            EventKind::L1hits
-                | EventKind::LLhits
-                | EventKind::RamHits
-                | EventKind::TotalRW
-                | EventKind::EstimatedCycles
-        )
+        !self.requires().is_empty()
+    }
+
+    /// Return the native `EventKind`s this `EventKind` is calculated from
+    ///
+    /// The returned slice is empty for all native (not derived) events. All the required events
+    /// of the derived events are collected by callgrind with the cache simulation
+    /// (`--cache-sim=yes`).
+    pub fn requires(&self) -> &'static [EventKind] {
+        use EventKind::*;
+
+        match self {
+            L1hits => &[Ir, Dr, Dw, I1mr, D1mr, D1mw],
+            LLhits => &[I1mr, D1mr, D1mw, ILmr, DLmr, DLmw],
+            RamHits => &[ILmr, DLmr, DLmw],
+            TotalRW => &[Ir, Dr, Dw],
+            DataRW => &[Dr, Dw],
+            EstimatedCycles => &[Ir, Dr, Dw, I1mr, D1mr, D1mw, ILmr, DLmr, DLmw],
+            _ => &[],
+        }
     }
 
     pub fn from_str_ignore_case(value: &str) -> Option<Self> {
@@ -362,7 +572,9 @@ impl EventKind {
             "llhits" => Some(Self::LLhits),
             "ramhits" => Some(Self::RamHits),
             "totalrw" => Some(Self::TotalRW),
+            "datarw" => Some(Self::DataRW),
             "estimatedcycles" => Some(Self::EstimatedCycles),
+            "score" => Some(Self::Score),
             _ => None,
         }
     }
@@ -380,6 +592,7 @@ impl Display for EventKind {
             EventKind::LLhits => f.write_str("L2 Hits"),
             EventKind::RamHits => f.write_str("RAM Hits"),
             EventKind::TotalRW => f.write_str("Total read+write"),
+            EventKind::DataRW => f.write_str("Data read+write"),
             EventKind::EstimatedCycles => f.write_str("Estimated Cycles"),
             _ => f.write_fmt(format_args!("{self:?}")),
         }
@@ -420,12 +633,60 @@ where
             "LLhits" => Self::LLhits,
             "RamHits" => Self::RamHits,
             "TotalRW" => Self::TotalRW,
+            "DataRW" => Self::DataRW,
             "EstimatedCycles" => Self::EstimatedCycles,
+            "Score" => Self::Score,
             unknown => panic!("Unknown event type: {unknown}"),
         }
     }
 }
 
+impl FlamegraphConfig {
+    /// Update this `FlamegraphConfig` with the values of `other`
+    ///
+    /// Every value of `other` which is present (`Some`) overwrites the value of this
+    /// `FlamegraphConfig`. All other values are kept. The `count_units` of `other` overwrite only
+    /// the units with the same [`EventKind`] and the units of a new [`EventKind`] are added.
+    pub fn update_from(&mut self, other: &Self) {
+        self.kind = update_option(&self.kind, &other.kind);
+        self.negate_differential =
+            update_option(&self.negate_differential, &other.negate_differential);
+        self.normalize_differential =
+            update_option(&self.normalize_differential, &other.normalize_differential);
+        self.event_kinds = update_option(&self.event_kinds, &other.event_kinds);
+        self.direction = update_option(&self.direction, &other.direction);
+        self.title = update_option(&self.title, &other.title);
+        self.subtitle = update_option(&self.subtitle, &other.subtitle);
+        self.min_width = update_option(&self.min_width, &other.min_width);
+        self.min_cost = update_option(&self.min_cost, &other.min_cost);
+        self.max_depth = update_option(&self.max_depth, &other.max_depth);
+        self.max_depth_count_inline =
+            update_option(&self.max_depth_count_inline, &other.max_depth_count_inline);
+        self.symbol_transforms = update_option(&self.symbol_transforms, &other.symbol_transforms);
+        self.sort_stacks = update_option(&self.sort_stacks, &other.sort_stacks);
+        self.coalesce_stacks = update_option(&self.coalesce_stacks, &other.coalesce_stacks);
+        self.backup = update_option(&self.backup, &other.backup);
+        self.tag = update_option(&self.tag, &other.tag);
+        self.frame_label = update_option(&self.frame_label, &other.frame_label);
+        self.percentage_scale = update_option(&self.percentage_scale, &other.percentage_scale);
+        self.rounding = update_option(&self.rounding, &other.rounding);
+        self.trim_harness = update_option(&self.trim_harness, &other.trim_harness);
+        self.object_marker = update_option(&self.object_marker, &other.object_marker);
+        self.color_event_kind = update_option(&self.color_event_kind, &other.color_event_kind);
+        merge_option(&mut self.count_units, &other.count_units, |first, other| {
+            update_by_event_kind(first, other);
+        });
+        self.svg_header = update_option(&self.svg_header, &other.svg_header);
+        self.svg_footer = update_option(&self.svg_footer, &other.svg_footer);
+        self.palette = update_option(&self.palette, &other.palette);
+        self.max_frame_name_length =
+            update_option(&self.max_frame_name_length, &other.max_frame_name_length);
+        self.frame_name_ellipsis =
+            update_option(&self.frame_name_ellipsis, &other.frame_name_ellipsis);
+        self.root_frame = update_option(&self.root_frame, &other.root_frame);
+    }
+}
+
 impl LibraryBenchmarkConfig {
     pub fn update_from_all<'a, T>(mut self, others: T) -> Self
     where
@@ -434,12 +695,21 @@ impl LibraryBenchmarkConfig {
         for other in others.into_iter().flatten() {
             self.raw_callgrind_args
                 .extend_ignore_flag(other.raw_callgrind_args.0.iter());
+            self.cachegrind = update_option(&self.cachegrind, &other.cachegrind);
+            self.default_tool = update_option(&self.default_tool, &other.default_tool);
+            self.warmup = update_option(&self.warmup, &other.warmup);
             self.env_clear = update_option(&self.env_clear, &other.env_clear);
             self.envs.extend_from_slice(&other.envs);
-            self.flamegraph_config =
-                update_option(&self.flamegraph_config, &other.flamegraph_config);
-            self.regression_config =
-                update_option(&self.regression_config, &other.regression_config);
+            merge_option(
+                &mut self.flamegraph_config,
+                &other.flamegraph_config,
+                FlamegraphConfig::update_from,
+            );
+            merge_option(
+                &mut self.regression_config,
+                &other.regression_config,
+                RegressionConfig::update_from,
+            );
             if let Some(other_tools) = &other.tools_override {
                 self.tools = other_tools.clone();
             } else if !other.tools.is_empty() {
@@ -462,6 +732,68 @@ impl LibraryBenchmarkConfig {
     }
 }
 
+impl Palette {
+    /// All palettes in the order of their declaration
+    pub const ALL: [Self; 16] = [
+        Self::Hot,
+        Self::Mem,
+        Self::Io,
+        Self::Red,
+        Self::Green,
+        Self::Blue,
+        Self::Aqua,
+        Self::Yellow,
+        Self::Purple,
+        Self::Orange,
+        Self::Java,
+        Self::Js,
+        Self::Perl,
+        Self::Python,
+        Self::Rust,
+        Self::Wakeup,
+    ];
+
+    /// The name of this palette in inferno like `mem`
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Hot => "hot",
+            Self::Mem => "mem",
+            Self::Io => "io",
+            Self::Red => "red",
+            Self::Green => "green",
+            Self::Blue => "blue",
+            Self::Aqua => "aqua",
+            Self::Yellow => "yellow",
+            Self::Purple => "purple",
+            Self::Orange => "orange",
+            Self::Java => "java",
+            Self::Js => "js",
+            Self::Perl => "perl",
+            Self::Python => "python",
+            Self::Rust => "rust",
+            Self::Wakeup => "wakeup",
+        }
+    }
+}
+
+impl FromStr for Palette {
+    type Err = String;
+
+    /// Parse the palette from its name (case insensitive) like `mem`
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let name = s.trim();
+        Self::ALL
+            .into_iter()
+            .find(|palette| palette.name().eq_ignore_ascii_case(name))
+            .ok_or_else(|| {
+                format!(
+                    "Unknown flamegraph palette '{name}': Valid palettes are {}",
+                    Self::ALL.map(Self::name).join(", ")
+                )
+            })
+    }
+}
+
 impl RawArgs {
     pub fn new(args: Vec<String>) -> Self {
         Self(args)
@@ -515,6 +847,25 @@ where
     }
 }
 
+impl RegressionConfig {
+    /// Update this `RegressionConfig` with the values of `other`
+    ///
+    /// The `limits`, `min_abs_diffs` and `score` weights of `other` overwrite the values of this
+    /// `RegressionConfig` with the same [`EventKind`] and the values with a new [`EventKind`] are
+    /// added. If `clear_limits` is set in `other`, the `limits` of this `RegressionConfig` are
+    /// removed before the `limits` of `other` are added. `fail_fast` is overwritten if present in
+    /// `other`.
+    pub fn update_from(&mut self, other: &Self) {
+        if other.clear_limits {
+            self.limits.clear();
+        }
+        update_by_event_kind(&mut self.limits, &other.limits);
+        update_by_event_kind(&mut self.min_abs_diffs, &other.min_abs_diffs);
+        update_by_event_kind(&mut self.score, &other.score);
+        self.fail_fast = update_option(&self.fail_fast, &other.fail_fast);
+    }
+}
+
 impl Tools {
     /// Return true if `Tools` is empty
     pub fn is_empty(&self) -> bool {
@@ -555,6 +906,27 @@ pub fn update_option<T: Clone>(first: &Option<T>, other: &Option<T>) -> Option<T
     other.clone().or_else(|| first.clone())
 }
 
+/// Merge the `other` value into the `first` value with `update` if both are present
+///
+/// If only one of both values is present, the `first` value is set to this value.
+pub fn merge_option<T: Clone>(first: &mut Option<T>, other: &Option<T>, update: fn(&mut T, &T)) {
+    match (first.as_mut(), other) {
+        (Some(first), Some(other)) => update(first, other),
+        (None, Some(other)) => *first = Some(other.clone()),
+        (_, None) => {}
+    }
+}
+
+fn update_by_event_kind<T: Clone>(first: &mut Vec<(EventKind, T)>, other: &[(EventKind, T)]) {
+    for (event_kind, value) in other {
+        if let Some(pos) = first.iter().position(|(e, _)| e == event_kind) {
+            first[pos].1 = value.clone();
+        } else {
+            first.push((*event_kind, value.clone()));
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use pretty_assertions::assert_eq;
@@ -562,6 +934,40 @@ mod tests {
 
     use super::*;
 
+    #[rstest]
+    #[case::ir(EventKind::Ir, &[])]
+    #[case::dr(EventKind::Dr, &[])]
+    #[case::ge(EventKind::Ge, &[])]
+    #[case::bc(EventKind::Bc, &[])]
+    #[case::l1hits(EventKind::L1hits, &[EventKind::Ir, EventKind::Dr, EventKind::Dw, EventKind::I1mr, EventKind::D1mr, EventKind::D1mw])]
+    #[case::llhits(EventKind::LLhits, &[EventKind::I1mr, EventKind::D1mr, EventKind::D1mw, EventKind::ILmr, EventKind::DLmr, EventKind::DLmw])]
+    #[case::ram_hits(EventKind::RamHits, &[EventKind::ILmr, EventKind::DLmr, EventKind::DLmw])]
+    #[case::total_rw(EventKind::TotalRW, &[EventKind::Ir, EventKind::Dr, EventKind::Dw])]
+    #[case::data_rw(EventKind::DataRW, &[EventKind::Dr, EventKind::Dw])]
+    #[case::estimated_cycles(EventKind::EstimatedCycles, &[EventKind::Ir, EventKind::Dr, EventKind::Dw, EventKind::I1mr, EventKind::D1mr, EventKind::D1mw, EventKind::ILmr, EventKind::DLmr, EventKind::DLmw])]
+    fn test_event_kind_requires(#[case] event_kind: EventKind, #[case] expected: &[EventKind]) {
+        assert_eq!(event_kind.requires(), expected);
+        assert_eq!(event_kind.is_derived(), !expected.is_empty());
+        assert!(event_kind.requires().iter().all(|e| !e.is_derived()));
+    }
+
+    #[test]
+    fn test_binary_benchmark_config_update_from_all_accumulates_regions() {
+        let base = BinaryBenchmarkConfig {
+            regions: vec!["my_exe::parse".to_owned()],
+            ..Default::default()
+        };
+        let other = BinaryBenchmarkConfig {
+            regions: vec!["my_exe::evaluate".to_owned()],
+            ..Default::default()
+        };
+
+        assert_eq!(
+            base.update_from_all([Some(&other)]).regions,
+            vec!["my_exe::parse".to_owned(), "my_exe::evaluate".to_owned()]
+        );
+    }
+
     #[test]
     fn test_library_benchmark_config_update_from_all_when_default() {
         assert_eq!(
@@ -577,6 +983,9 @@ mod tests {
         let other = LibraryBenchmarkConfig {
             env_clear: Some(true),
             raw_callgrind_args: RawArgs(vec!["--just-testing=yes".to_owned()]),
+            cachegrind: Some(true),
+            default_tool: Some(ValgrindTool::Cachegrind),
+            warmup: Some(1),
             envs: vec![(OsString::from("MY_ENV"), Some(OsString::from("value")))],
             flamegraph_config: Some(FlamegraphConfig::default()),
             regression_config: Some(RegressionConfig::default()),
@@ -599,6 +1008,9 @@ mod tests {
         let other = LibraryBenchmarkConfig {
             env_clear: Some(true),
             raw_callgrind_args: RawArgs(vec!["--just-testing=yes".to_owned()]),
+            cachegrind: Some(true),
+            default_tool: Some(ValgrindTool::Cachegrind),
+            warmup: Some(1),
             envs: vec![(OsString::from("MY_ENV"), Some(OsString::from("value")))],
             flamegraph_config: Some(FlamegraphConfig::default()),
             regression_config: Some(RegressionConfig::default()),
@@ -620,6 +1032,147 @@ mod tests {
         assert_eq!(base.update_from_all([Some(&other)]), expected);
     }
 
+    #[test]
+    fn test_library_benchmark_config_update_from_all_merges_nested_configs() {
+        let global = LibraryBenchmarkConfig {
+            flamegraph_config: Some(FlamegraphConfig {
+                title: Some("global".to_owned()),
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+        let group = LibraryBenchmarkConfig {
+            flamegraph_config: Some(FlamegraphConfig {
+                kind: Some(FlamegraphKind::Regular),
+                min_width: Some(1f64),
+                ..Default::default()
+            }),
+            regression_config: Some(RegressionConfig {
+                limits: vec![(EventKind::Ir, 5f64), (EventKind::Dr, 10f64)],
+                min_abs_diffs: vec![(EventKind::Ir, 100)],
+                fail_fast: Some(true),
+                score: vec![(EventKind::Ir, 1f64), (EventKind::EstimatedCycles, 10f64)],
+                clear_limits: false,
+            }),
+            ..Default::default()
+        };
+        let bench = LibraryBenchmarkConfig {
+            flamegraph_config: Some(FlamegraphConfig {
+                min_width: Some(2f64),
+                ..Default::default()
+            }),
+            regression_config: Some(RegressionConfig {
+                limits: vec![(EventKind::Ir, 1f64), (EventKind::Dw, 20f64)],
+                min_abs_diffs: vec![],
+                fail_fast: None,
+                score: vec![(EventKind::EstimatedCycles, 5f64)],
+                clear_limits: false,
+            }),
+            ..Default::default()
+        };
+        let expected = LibraryBenchmarkConfig {
+            flamegraph_config: Some(FlamegraphConfig {
+                kind: Some(FlamegraphKind::Regular),
+                title: Some("global".to_owned()),
+                min_width: Some(2f64),
+                ..Default::default()
+            }),
+            regression_config: Some(RegressionConfig {
+                limits: vec![
+                    (EventKind::Ir, 1f64),
+                    (EventKind::Dr, 10f64),
+                    (EventKind::Dw, 20f64),
+                ],
+                min_abs_diffs: vec![(EventKind::Ir, 100)],
+                fail_fast: Some(true),
+                score: vec![(EventKind::Ir, 1f64), (EventKind::EstimatedCycles, 5f64)],
+                clear_limits: false,
+            }),
+            ..Default::default()
+        };
+
+        assert_eq!(
+            global.update_from_all([Some(&group), None, Some(&bench)]),
+            expected
+        );
+    }
+
+    #[test]
+    fn test_regression_config_update_from_when_clear_limits() {
+        let group = RegressionConfig {
+            limits: vec![(EventKind::Ir, 5f64), (EventKind::Dr, 10f64)],
+            min_abs_diffs: vec![(EventKind::Ir, 100)],
+            fail_fast: Some(true),
+            ..Default::default()
+        };
+        let bench = RegressionConfig {
+            limits: vec![(EventKind::Dw, 20f64)],
+            clear_limits: true,
+            ..Default::default()
+        };
+        let other_bench = RegressionConfig {
+            clear_limits: true,
+            ..Default::default()
+        };
+
+        let mut config = group.clone();
+        config.update_from(&bench);
+        assert_eq!(config.limits, vec![(EventKind::Dw, 20f64)]);
+        assert_eq!(config.min_abs_diffs, vec![(EventKind::Ir, 100)]);
+        assert_eq!(config.fail_fast, Some(true));
+
+        let mut config = group;
+        config.update_from(&other_bench);
+        assert!(config.limits.is_empty());
+    }
+
+    #[test]
+    fn test_flamegraph_config_update_from_merges_count_units() {
+        let unit = |name: &str| CountUnit {
+            singular: name.to_owned(),
+            plural: format!("{name}s"),
+        };
+        let mut config = FlamegraphConfig {
+            count_units: Some(vec![
+                (EventKind::Ir, unit("instr")),
+                (EventKind::Dr, unit("read")),
+            ]),
+            ..Default::default()
+        };
+        let other = FlamegraphConfig {
+            count_units: Some(vec![
+                (EventKind::Dr, unit("load")),
+                (EventKind::Dw, unit("store")),
+            ]),
+            ..Default::default()
+        };
+
+        config.update_from(&other);
+
+        assert_eq!(
+            config.count_units,
+            Some(vec![
+                (EventKind::Ir, unit("instr")),
+                (EventKind::Dr, unit("load")),
+                (EventKind::Dw, unit("store")),
+            ])
+        );
+    }
+
+    #[rstest]
+    #[case::all_none(None, None, None)]
+    #[case::some_and_none(Some(1), None, Some(1))]
+    #[case::none_and_some(None, Some(2), Some(2))]
+    #[case::some_and_some(Some(1), Some(2), Some(3))]
+    fn test_merge_option(
+        #[case] mut first: Option<u64>,
+        #[case] other: Option<u64>,
+        #[case] expected: Option<u64>,
+    ) {
+        merge_option(&mut first, &other, |a, b| *a += b);
+        assert_eq!(first, expected);
+    }
+
     #[rstest]
     #[case::all_none(None, None, None)]
     #[case::some_and_none(Some(true), None, Some(true))]
@@ -4,18 +4,26 @@ use std::os::unix::process::ExitStatusExt;
 use std::path::PathBuf;
 use std::process::Output;
 
+use indexmap::IndexMap;
 use version_compare::Cmp;
 
+use crate::runner::summary::ErrorSummary;
 use crate::runner::tool::ToolOutputPath;
-use crate::util::write_all_to_stderr;
+use crate::util::{signal_name, tail_lines};
+
+/// The number of the last lines of the stderr of a failed process which are part of the
+/// [`Error::ProcessError`]
+pub const STDERR_TAIL_LINES: usize = 20;
 
 #[derive(Debug, PartialEq, Clone)]
 pub enum Error {
     VersionMismatch(version_compare::Cmp, String, String),
     LaunchError(PathBuf, String),
     ProcessError((String, Output, Option<ToolOutputPath>)),
+    ToolErrors((String, ErrorSummary, IndexMap<String, u64>)),
     InvalidCallgrindBoolArgument((String, String)),
     ParseError((PathBuf, String)),
+    ParseLineError((PathBuf, usize, String)),
     RegressionError(bool),
     EnvironmentVariableError((String, String)),
 }
@@ -23,6 +31,7 @@ pub enum Error {
 impl std::error::Error for Error {}
 
 impl Display for Error {
+    #[allow(clippy::too_many_lines)]
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
             Self::VersionMismatch(cmp, runner_version, library_version) => match cmp {
@@ -55,18 +64,56 @@ impl Display for Error {
                         .dump_log(log::Level::Error, &mut stderr())
                         .expect("Printing error output should succeed");
                 }
-                write_all_to_stderr(&output.stderr);
 
                 if let Some(code) = output.status.code() {
-                    write!(f, "Error running '{process}': Exit code was: '{code}'")
+                    write!(f, "Error running '{process}': Exit code was: '{code}'")?;
                 } else if let Some(signal) = output.status.signal() {
                     write!(
                         f,
-                        "Error running '{process}': Terminated by a signal '{signal}'"
-                    )
+                        "Error running '{process}': Terminated by the signal {}",
+                        signal_name(signal)
+                    )?;
                 } else {
-                    write!(f, "Error running '{process}': Terminated abnormally")
+                    write!(f, "Error running '{process}': Terminated abnormally")?;
+                }
+
+                // The reason of the failure, like an unknown valgrind argument, is usually at the
+                // end of stderr. The complete stderr might be long and is only printed with the
+                // debug log level.
+                let (lines, omitted) = tail_lines(&output.stderr, STDERR_TAIL_LINES);
+                if !lines.is_empty() {
+                    if omitted > 0 {
+                        write!(
+                            f,
+                            "\nThe last {} lines of stderr ({omitted} lines omitted):",
+                            lines.len()
+                        )?;
+                    } else {
+                        write!(f, "\nstderr:")?;
+                    }
+                    for line in lines {
+                        write!(f, "\n  | {line}")?;
+                    }
                 }
+                Ok(())
+            }
+            Self::ToolErrors((tool, error_summary, error_kinds)) => {
+                write!(
+                    f,
+                    "Error running '{tool}': Detected {} errors from {} contexts (suppressed: {} \
+                     from {})",
+                    error_summary.errors,
+                    error_summary.contexts,
+                    error_summary.supp_errors,
+                    error_summary.supp_contexts
+                )?;
+                if !error_kinds.is_empty() {
+                    write!(f, "\nError kinds:")?;
+                    for (kind, count) in error_kinds {
+                        write!(f, "\n  | {kind}: {count}")?;
+                    }
+                }
+                Ok(())
             }
             Self::InvalidCallgrindBoolArgument((option, value)) => {
                 write!(
@@ -78,6 +125,13 @@ impl Display for Error {
             Self::ParseError((path, message)) => {
                 write!(f, "Error parsing file '{}': {message}", path.display())
             }
+            Self::ParseLineError((path, line, message)) => {
+                write!(
+                    f,
+                    "Error parsing file '{}' at line {line}: {message}",
+                    path.display()
+                )
+            }
             Self::RegressionError(is_fatal) => {
                 if *is_fatal {
                     write!(f, "Performance has regressed. Aborting ...",)
@@ -3,8 +3,10 @@ use std::io::Write;
 
 use colored::{control, Colorize};
 use env_logger::Env;
+use iai_callgrind_runner::error::Error;
 use iai_callgrind_runner::runner::envs;
-use log::{error, warn};
+use iai_callgrind_runner::util::write_all_to_stderr;
+use log::{debug, error, log_enabled, warn, Level};
 
 /// Print warnings for deprecated usages of environment variables
 fn print_warnings() {
@@ -71,6 +73,13 @@ fn main() {
     match iai_callgrind_runner::runner::run() {
         Ok(()) => {}
         Err(error) => {
+            // The error message contains only the last lines of the stderr of a failed process
+            if let Some(Error::ProcessError((process, output, _))) = error.downcast_ref::<Error>() {
+                if log_enabled!(Level::Debug) && !output.stderr.is_empty() {
+                    debug!("{process}: stderr:");
+                    write_all_to_stderr(&output.stderr);
+                }
+            }
             error!("{}", error.to_string());
             std::process::exit(1)
         }
@@ -0,0 +1,273 @@
+//! Pin the valgrind processes to a set of cpus with `--cpu-affinity`
+//!
+//! The cpu affinity is set with `sched_setaffinity` in the child process right before valgrind is
+//! executed, so only the valgrind processes and their children are pinned and not the runner
+//! itself. Setting the cpu affinity is only supported on Linux.
+use std::fmt::Display;
+use std::process::Command;
+use std::str::FromStr;
+
+use anyhow::Result;
+
+/// The maximum number of cpus. This is the `CPU_SETSIZE` of the `cpu_set_t` of glibc and musl.
+const MAX_CPUS: usize = 1024;
+
+/// The `CpuAffinity` are the cpus the valgrind processes are allowed to run on
+///
+/// A `CpuAffinity` is parsed from a comma separated list of cpu numbers and ranges of cpu numbers
+/// like `taskset --cpu-list` does, for example `0`, `0,2` or `0-3,8`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CpuAffinity(Vec<usize>);
+
+impl CpuAffinity {
+    /// Return true if the cpu affinity can be set on this platform
+    pub fn is_supported() -> bool {
+        cfg!(target_os = "linux")
+    }
+
+    /// Return the cpu numbers in ascending order
+    pub fn cpus(&self) -> &[usize] {
+        &self.0
+    }
+
+    /// Pin the child process of the `command` to the cpus of this `CpuAffinity`
+    ///
+    /// If the cpu affinity can't be set, spawning the `command` fails with the error of
+    /// `sched_setaffinity`.
+    #[cfg(target_os = "linux")]
+    pub fn apply(&self, command: &mut Command) {
+        use std::os::unix::process::CommandExt;
+
+        let cpu_set = self.to_cpu_set();
+        // SAFETY: The closure runs in the forked child process and doesn't allocate. It only calls
+        // `sched_setaffinity` which is async-signal-safe with a `cpu_set_t` owned by the closure.
+        unsafe {
+            command.pre_exec(move || {
+                if libc::sched_setaffinity(0, std::mem::size_of::<libc::cpu_set_t>(), &cpu_set)
+                    == -1_i32
+                {
+                    Err(std::io::Error::last_os_error())
+                } else {
+                    Ok(())
+                }
+            });
+        }
+    }
+
+    /// The cpu affinity can't be set on this platform and the `command` is left unchanged
+    #[cfg(not(target_os = "linux"))]
+    pub fn apply(&self, _command: &mut Command) {}
+
+    /// Check that all cpus of this `CpuAffinity` are available to the current process
+    ///
+    /// The available cpus are restricted for example by `taskset` or the cpuset of a container.
+    ///
+    /// # Errors
+    ///
+    /// If any cpu is not available or the available cpus could not be determined
+    #[cfg(target_os = "linux")]
+    pub fn check_available(&self) -> Result<()> {
+        use anyhow::anyhow;
+
+        // SAFETY: An all-zero `cpu_set_t` is a valid and empty cpu set
+        let mut available: libc::cpu_set_t = unsafe { std::mem::zeroed() };
+        // SAFETY: The pointer points to a valid `cpu_set_t` of the given size
+        let result = unsafe {
+            libc::sched_getaffinity(0, std::mem::size_of::<libc::cpu_set_t>(), &mut available)
+        };
+        if result == -1_i32 {
+            return Err(anyhow!(
+                "Failed to determine the available cpus: {}",
+                std::io::Error::last_os_error()
+            ));
+        }
+
+        let available = Self(
+            (0..MAX_CPUS)
+                // SAFETY: `cpu` is below `CPU_SETSIZE`
+                .filter(|cpu| unsafe { libc::CPU_ISSET(*cpu, &available) })
+                .collect(),
+        );
+        let missing = Self(
+            self.0
+                .iter()
+                .copied()
+                .filter(|cpu| !available.0.contains(cpu))
+                .collect(),
+        );
+        if missing.0.is_empty() {
+            Ok(())
+        } else {
+            Err(anyhow!(
+                "The cpus '{missing}' of the cpu affinity are not available: The available cpus \
+                 are '{available}'"
+            ))
+        }
+    }
+
+    /// The cpu affinity can't be set on this platform, so there is nothing to check
+    #[cfg(not(target_os = "linux"))]
+    pub fn check_available(&self) -> Result<()> {
+        Ok(())
+    }
+
+    #[cfg(target_os = "linux")]
+    fn to_cpu_set(&self) -> libc::cpu_set_t {
+        // SAFETY: An all-zero `cpu_set_t` is a valid and empty cpu set
+        let mut cpu_set: libc::cpu_set_t = unsafe { std::mem::zeroed() };
+        for cpu in &self.0 {
+            // SAFETY: The cpus are below `CPU_SETSIZE` which is checked when parsing
+            unsafe { libc::CPU_SET(*cpu, &mut cpu_set) };
+        }
+        cpu_set
+    }
+}
+
+impl Display for CpuAffinity {
+    /// Format the cpus as comma separated list with consecutive cpus as range like `0-3,8`
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let mut ranges: Vec<(usize, usize)> = vec![];
+        for cpu in &self.0 {
+            match ranges.last_mut() {
+                Some((_, end)) if *end + 1 == *cpu => *end = *cpu,
+                _ => ranges.push((*cpu, *cpu)),
+            }
+        }
+
+        let ranges = ranges
+            .into_iter()
+            .map(|(start, end)| {
+                if start == end {
+                    start.to_string()
+                } else {
+                    format!("{start}-{end}")
+                }
+            })
+            .collect::<Vec<_>>();
+        f.write_str(&ranges.join(","))
+    }
+}
+
+impl FromStr for CpuAffinity {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let parse_cpu = |cpu: &str| -> Result<usize, String> {
+            let cpu = cpu
+                .trim()
+                .parse::<usize>()
+                .map_err(|error| format!("Invalid cpu '{}': {error}", cpu.trim()))?;
+            if cpu < MAX_CPUS {
+                Ok(cpu)
+            } else {
+                Err(format!(
+                    "Invalid cpu '{cpu}': The cpu number must be lower than {MAX_CPUS}"
+                ))
+            }
+        };
+
+        let mut cpus = vec![];
+        for item in s.split(',') {
+            if let Some((start, end)) = item.split_once('-') {
+                let (start, end) = (parse_cpu(start)?, parse_cpu(end)?);
+                if start > end {
+                    return Err(format!(
+                        "Invalid cpu range '{}': The start must not be greater than the end",
+                        item.trim()
+                    ));
+                }
+                cpus.extend(start..=end);
+            } else {
+                cpus.push(parse_cpu(item)?);
+            }
+        }
+        cpus.sort_unstable();
+        cpus.dedup();
+
+        Ok(Self(cpus))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+    use rstest::rstest;
+
+    use super::*;
+
+    #[rstest]
+    #[case::single("0", &[0], "0")]
+    #[case::list("0,2", &[0, 2], "0,2")]
+    #[case::range("0-3", &[0, 1, 2, 3], "0-3")]
+    #[case::mixed("8, 0-2,4", &[0, 1, 2, 4, 8], "0-2,4,8")]
+    #[case::unordered_duplicates("3,1,2,1", &[1, 2, 3], "1-3")]
+    #[case::range_single_cpu("5-5", &[5], "5")]
+    #[case::max("1023", &[1023], "1023")]
+    fn test_cpu_affinity_from_str(
+        #[case] value: &str,
+        #[case] expected: &[usize],
+        #[case] expected_display: &str,
+    ) {
+        let affinity = value.parse::<CpuAffinity>().unwrap();
+        assert_eq!(affinity.cpus(), expected);
+        assert_eq!(affinity.to_string(), expected_display);
+    }
+
+    #[rstest]
+    #[case::empty("", "Invalid cpu '': cannot parse integer from empty string")]
+    #[case::not_a_number("a", "Invalid cpu 'a': invalid digit found in string")]
+    #[case::negative("-1", "Invalid cpu '': cannot parse integer from empty string")]
+    #[case::too_high("1024", "Invalid cpu '1024': The cpu number must be lower than 1024")]
+    #[case::reversed_range(
+        "3-1",
+        "Invalid cpu range '3-1': The start must not be greater than the end"
+    )]
+    fn test_cpu_affinity_from_str_when_invalid(#[case] value: &str, #[case] expected: &str) {
+        assert_eq!(value.parse::<CpuAffinity>().unwrap_err(), expected);
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn test_cpu_affinity_apply_and_check_available() {
+        // The cpu 0 isn't necessarily available for example under `taskset` or in a container
+        // SAFETY: An all-zero `cpu_set_t` is a valid and empty cpu set
+        let mut available: libc::cpu_set_t = unsafe { std::mem::zeroed() };
+        // SAFETY: The pointer points to a valid `cpu_set_t` of the given size
+        let result = unsafe {
+            libc::sched_getaffinity(0, std::mem::size_of::<libc::cpu_set_t>(), &mut available)
+        };
+        assert_eq!(result, 0_i32);
+        let cpu = (0..MAX_CPUS)
+            // SAFETY: `cpu` is below `CPU_SETSIZE`
+            .find(|cpu| unsafe { libc::CPU_ISSET(*cpu, &available) })
+            .unwrap()
+            .to_string();
+
+        let affinity = cpu.parse::<CpuAffinity>().unwrap();
+        affinity.check_available().unwrap();
+
+        let mut command = Command::new("sh");
+        command.args(["-c", "grep Cpus_allowed_list /proc/self/status"]);
+        affinity.apply(&mut command);
+        let output = command.output().unwrap();
+
+        assert!(output.status.success());
+        assert_eq!(
+            String::from_utf8_lossy(&output.stdout)
+                .split_whitespace()
+                .last(),
+            Some(cpu.as_str())
+        );
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn test_cpu_affinity_check_available_when_not_available() {
+        let affinity = "1023".parse::<CpuAffinity>().unwrap();
+        assert!(affinity
+            .check_available()
+            .unwrap_err()
+            .to_string()
+            .starts_with("The cpus '1023' of the cpu affinity are not available"));
+    }
+}
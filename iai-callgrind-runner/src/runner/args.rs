@@ -1,9 +1,12 @@
+use std::path::PathBuf;
 use std::str::FromStr;
 
 use clap::builder::BoolishValueParser;
 use clap::{ArgAction, Parser};
+use regex::Regex;
 
-use super::format::OutputFormat;
+use super::affinity::CpuAffinity;
+use super::format::{DiffFormat, EventFilter, NumberFormat, OutputFormat};
 use super::summary::{BaselineName, SummaryFormat};
 use crate::api::{EventKind, RawArgs, RegressionConfig};
 
@@ -109,6 +112,69 @@ pub struct CommandLineArgs {
     )]
     pub allow_aslr: Option<bool>,
 
+    /// Report the peak resident set size (peak RSS) of each benchmark run
+    ///
+    /// The peak RSS is the maximum resident set size in kilobytes of the valgrind process as
+    /// reported by the operating system after the benchmark has finished. Note this value includes
+    /// the (usually considerable) memory overhead of valgrind itself and is therefore only a rough
+    /// signal for changes in the memory usage of the benchmark. This option is only available on
+    /// unix platforms.
+    #[arg(
+        long = "peak-rss",
+        default_missing_value = "yes",
+        num_args = 0..=1,
+        require_equals = true,
+        value_parser = BoolishValueParser::new(),
+        env = "IAI_CALLGRIND_PEAK_RSS",
+    )]
+    pub peak_rss: Option<bool>,
+
+    /// Report the wall-clock time of the valgrind process of each benchmark run
+    ///
+    /// This is the time from the start to the exit of the instrumented valgrind process including
+    /// the (usually massive) overhead of valgrind. It is useful to plan the duration of CI jobs
+    /// but not representative of the real performance of the benchmark and NOT a benchmark
+    /// result. The time is always recorded in the json summary.
+    #[arg(
+        long = "valgrind-time",
+        default_missing_value = "yes",
+        num_args = 0..=1,
+        require_equals = true,
+        value_parser = BoolishValueParser::new(),
+        env = "IAI_CALLGRIND_VALGRIND_TIME",
+    )]
+    pub valgrind_time: Option<bool>,
+
+    /// Run each benchmark a second time and check that the costs are equal
+    ///
+    /// Callgrind is deterministic, so if the costs of the primary event kind (`--primary-event`)
+    /// of the two runs differ, the benchmark itself is nondeterministic and a warning is printed.
+    /// Note this option doubles the runtime of the benchmarks.
+    #[arg(
+        long = "check-determinism",
+        default_missing_value = "yes",
+        num_args = 0..=1,
+        require_equals = true,
+        value_parser = BoolishValueParser::new(),
+        env = "IAI_CALLGRIND_CHECK_DETERMINISM",
+    )]
+    pub check_determinism: Option<bool>,
+
+    /// Pin the valgrind processes to these cpus (Linux only)
+    ///
+    /// The cpus are a comma separated list of cpu numbers and ranges like `0`, `0,2` or `0-3,8`.
+    /// The instruction counts of callgrind don't depend on the cpu, but pinning the benchmarks to
+    /// a single core can reduce the variance of code depending on the real time and of the wall
+    /// time of the valgrind runs. This option is ignored with a warning on other platforms than
+    /// Linux.
+    #[arg(
+        long = "cpu-affinity",
+        num_args = 1,
+        require_equals = true,
+        env = "IAI_CALLGRIND_CPU_AFFINITY"
+    )]
+    pub cpu_affinity: Option<CpuAffinity>,
+
     /// Set performance regression limits for specific `EventKinds`
     ///
     /// This is a `,` separate list of EventKind=limit (key=value) pairs with the limit being a
@@ -140,6 +206,233 @@ pub struct CommandLineArgs {
     )]
     pub regression_fail_fast: Option<bool>,
 
+    /// Print only benchmarks which changed compared to the old run or baseline
+    ///
+    /// A benchmark is considered changed if any of the events shown in the terminal output
+    /// differs by more than the `--change-tolerance` from the old or baseline value, or if there
+    /// is nothing to compare with. The output of all other benchmarks is suppressed and the number
+    /// of unchanged benchmarks is printed at the end instead. This option only affects the default
+    /// human-readable terminal output. To get the full output again, use `--only-changed=no`.
+    #[arg(
+        long = "only-changed",
+        default_missing_value = "yes",
+        num_args = 0..=1,
+        require_equals = true,
+        value_parser = BoolishValueParser::new(),
+        env = "IAI_CALLGRIND_ONLY_CHANGED",
+    )]
+    pub only_changed: Option<bool>,
+
+    /// Print only regressed benchmarks and a final status line
+    ///
+    /// The terminal output of benchmarks which didn't regress is suppressed. At the end, the total
+    /// number of benchmarks and the number of regressions is printed. Regressions are reported
+    /// and the exit code is the same as without this option. This option only affects the default
+    /// human-readable terminal output and cannot be used together with `--only-changed`.
+    #[arg(
+        long = "quiet",
+        default_missing_value = "yes",
+        num_args = 0..=1,
+        require_equals = true,
+        value_parser = BoolishValueParser::new(),
+        conflicts_with = "only_changed",
+        env = "IAI_CALLGRIND_QUIET",
+    )]
+    pub quiet: Option<bool>,
+
+    /// The tolerance as percentage (default: 0.0) up to which an event is considered unchanged
+    ///
+    /// The tolerance is the absolute value of the difference in percent. This option requires
+    /// `--only-changed` or `IAI_CALLGRIND_ONLY_CHANGED` to be present.
+    ///
+    /// Examples: --change-tolerance=0.1 or --change-tolerance=5
+    #[arg(
+        long = "change-tolerance",
+        requires = "only_changed",
+        num_args = 1,
+        value_parser = parse_change_tolerance,
+        env = "IAI_CALLGRIND_CHANGE_TOLERANCE",
+    )]
+    pub change_tolerance: Option<f64>,
+
+    /// Print the worst regressions of all benchmarks of this benchmark run ranked in a final list
+    ///
+    /// All events of all benchmarks with an increase of the costs compared to the old run or
+    /// baseline are sorted by the difference in percent and then by the absolute difference and
+    /// the top `N` are printed after all benchmarks have finished. If `--primary-event` is given,
+    /// only this event kind is ranked. The events are ranked regardless of any limits of the
+    /// `--regression` option. With `--merge-summary`, all benchmarks merged into the file so far
+    /// are ranked, so the list after the last benchmark binary covers the whole suite. The list is
+    /// only printed with the default human-readable terminal output, see also
+    /// `--top-regressions-markdown`.
+    ///
+    /// Examples: --top-regressions=10
+    #[arg(
+        long = "top-regressions",
+        num_args = 1,
+        require_equals = true,
+        value_parser = parse_top_regressions,
+        env = "IAI_CALLGRIND_TOP_REGRESSIONS"
+    )]
+    pub top_regressions: Option<usize>,
+
+    /// Write the ranked list of `--top-regressions` as Markdown to the file at this path
+    ///
+    /// The file is overwritten after all benchmarks have finished and is meant for the comment of
+    /// a pull request or the job summary of a CI run. A relative path is resolved against the
+    /// workspace root.
+    ///
+    /// Examples: --top-regressions-markdown=target/iai/top-regressions.md
+    #[arg(
+        long = "top-regressions-markdown",
+        num_args = 1,
+        require_equals = true,
+        requires = "top_regressions",
+        env = "IAI_CALLGRIND_TOP_REGRESSIONS_MARKDOWN"
+    )]
+    pub top_regressions_markdown: Option<PathBuf>,
+
+    /// The event kind which matters most, used instead of `Ir` by the features measuring a single
+    /// event kind
+    ///
+    /// The primary event kind is the default limit of the regression checks without explicit
+    /// limits (like `--regression=default`), the default event kind of the flamegraphs without
+    /// explicit event kinds, the event kind compared by `--check-determinism`, and the only event
+    /// kind ranked by `--top-regressions`. It's matched case insensitive and has to be collected
+    /// by the benchmarks. The `Score` requires the weights of the score in the `RegressionConfig`.
+    /// Defaults to `Ir`.
+    ///
+    /// Examples: --primary-event=EstimatedCycles or --primary-event=DLmr
+    #[arg(
+        long = "primary-event",
+        num_args = 1,
+        require_equals = true,
+        value_parser = parse_event_kind,
+        env = "IAI_CALLGRIND_PRIMARY_EVENT"
+    )]
+    pub primary_event: Option<EventKind>,
+
+    /// Hide the events in the terminal output whose costs and difference are insignificant
+    ///
+    /// This is a `,` separated list of bars. A bar with a `%` suffix sets the minimum absolute
+    /// difference in percent between the new and the old cost. A bar without suffix sets the
+    /// minimum new or old cost. An event is shown if it crosses any of the bars. Events without an
+    /// old or new cost to compare with cross the difference bar. The filter applies to all
+    /// benchmarks and only affects the human-readable terminal output. The regression checks, the
+    /// exit code and the machine-readable outputs like the json output and the `summary.json`
+    /// files are not affected and always contain all events.
+    ///
+    /// Examples: --filter-event=1% or --filter-event=1000 or --filter-event='0.5%, 1000'
+    #[arg(
+        long = "filter-event",
+        num_args = 1,
+        require_equals = true,
+        env = "IAI_CALLGRIND_FILTER_EVENT"
+    )]
+    pub filter_event: Option<EventFilter>,
+
+    /// Only create flamegraphs for the benchmarks whose name matches this regular expression
+    ///
+    /// Creating flamegraphs is expensive in big benchmark suites. With this option, the costs of
+    /// all benchmarks are still reported but only the benchmarks whose name matches the regex get
+    /// flamegraphs. The name is the complete name as printed with `--list`, for example
+    /// `my_bench::my_group::bench_fibonacci short:10`. Benchmarks without a `FlamegraphConfig`
+    /// don't create flamegraphs regardless of this option. The number of benchmarks with and
+    /// without flamegraphs is printed at the end of the run.
+    ///
+    /// Examples: --flamegraph-filter=fibonacci or --flamegraph-filter='^benches.*(fib|sort)'
+    #[arg(
+        long = "flamegraph-filter",
+        num_args = 1,
+        require_equals = true,
+        value_parser = parse_regex,
+        env = "IAI_CALLGRIND_FLAMEGRAPH_FILTER"
+    )]
+    pub flamegraph_filter: Option<Regex>,
+
+    /// Fail instead of warn if the new and old output were created with different valgrind
+    /// settings
+    ///
+    /// Comparing the costs of benchmark runs with a different valgrind version, different cache
+    /// settings or different collected events (for example after changing `--cache-sim`) produces
+    /// misleading differences. By default, `iai-callgrind` warns about such a mismatch between the
+    /// new output and the old output or baseline. With this option, the benchmark fails instead.
+    #[arg(
+        long = "fail-on-valgrind-mismatch",
+        default_missing_value = "yes",
+        num_args = 0..=1,
+        require_equals = true,
+        value_parser = BoolishValueParser::new(),
+        env = "IAI_CALLGRIND_FAIL_ON_VALGRIND_MISMATCH",
+    )]
+    pub fail_on_valgrind_mismatch: Option<bool>,
+
+    /// Fail if the benchmark executable changed since the old output or baseline
+    ///
+    /// `iai-callgrind` records a hash of the benchmark executable next to each output. A changed
+    /// executable is expected if the benchmarked code changed, but when comparing the same build
+    /// against a baseline, for example to check the reproducibility of the costs, a changed
+    /// executable means the build changed unexpectedly. With this option, the benchmark fails
+    /// instead of comparing the costs of different builds.
+    #[arg(
+        long = "fail-on-binary-change",
+        default_missing_value = "yes",
+        num_args = 0..=1,
+        require_equals = true,
+        value_parser = BoolishValueParser::new(),
+        env = "IAI_CALLGRIND_FAIL_ON_BINARY_CHANGE",
+    )]
+    pub fail_on_binary_change: Option<bool>,
+
+    /// Fail if a benchmark measured no instructions
+    ///
+    /// A benchmark with an instruction count (`Ir`) of zero or without any instruction count
+    /// doesn't measure anything, for example if the benchmarked code was optimized away or the
+    /// `--toggle-collect` option doesn't match the benchmarked function. By default, such a
+    /// benchmark passes silently. With this option, the benchmark fails instead, which is
+    /// recommended in CI. This check always uses the `Ir` and not the primary event kind, since
+    /// other event kinds like the cache misses can be zero for benchmarks which measure something.
+    #[arg(
+        long = "fail-on-zero",
+        default_missing_value = "yes",
+        num_args = 0..=1,
+        require_equals = true,
+        value_parser = BoolishValueParser::new(),
+        env = "IAI_CALLGRIND_FAIL_ON_ZERO",
+    )]
+    pub fail_on_zero: Option<bool>,
+
+    /// List all benchmarks without running them
+    ///
+    /// The fully qualified names of the benchmarks including the ids and arguments are printed
+    /// one per line in the same order as they would run. Valgrind is not invoked and no files are
+    /// written.
+    #[arg(
+        long = "list",
+        default_missing_value = "yes",
+        num_args = 0..=1,
+        require_equals = true,
+        value_parser = BoolishValueParser::new(),
+        env = "IAI_CALLGRIND_LIST",
+    )]
+    pub list: Option<bool>,
+
+    /// The number of retries (default: 0) of a valgrind run which failed for a transient reason
+    ///
+    /// Only the failures to spawn the valgrind process and the termination of valgrind by a
+    /// `SIGKILL` signal, which is what the OOM killer sends, are retried. Genuine failures of the
+    /// benchmark like a non-zero exit code or a crash with another signal are never retried. The
+    /// delay before each retry doubles starting with 500 milliseconds.
+    ///
+    /// Examples: --retries=2
+    #[arg(
+        long = "retries",
+        num_args = 1,
+        require_equals = true,
+        env = "IAI_CALLGRIND_RETRIES"
+    )]
+    pub retries: Option<u32>,
+
     /// Compare against this baseline if present and then overwrite it
     #[arg(
         long = "save-baseline",
@@ -151,6 +444,25 @@ pub struct CommandLineArgs {
     )]
     pub save_baseline: Option<BaselineName>,
 
+    /// Save only the costs of these event kinds with `--save-baseline`
+    ///
+    /// This is a `,` separated list of `EventKinds`, matched case insensitive. The costs of all
+    /// other event kinds of an existing baseline with the same name are kept as they are, so for
+    /// example the cache misses and the instructions of a baseline can be updated independently.
+    /// Each event kind is compared against the costs of the run which saved it last.
+    ///
+    /// Examples: --save-baseline-events=Ir or --save-baseline-events=D1mr,DLmr
+    #[arg(
+        long = "save-baseline-events",
+        num_args = 1,
+        require_equals = true,
+        value_delimiter = ',',
+        value_parser = parse_event_kind,
+        requires = "save_baseline",
+        env = "IAI_CALLGRIND_SAVE_BASELINE_EVENTS"
+    )]
+    pub save_baseline_events: Option<Vec<EventKind>>,
+
     /// Compare against this baseline if present but do not overwrite it
     #[arg(
         long = "baseline",
@@ -173,6 +485,71 @@ pub struct CommandLineArgs {
     )]
     pub load_baseline: Option<BaselineName>,
 
+    /// Save the new run as the baseline of `--baseline` if this baseline doesn't exist yet
+    ///
+    /// Without a baseline, a benchmark is reported as new and has no regressions. With this
+    /// option, the first run of a benchmark is saved as the baseline, so the next runs are
+    /// compared against it. An existing baseline is never overwritten.
+    #[arg(
+        long = "auto-save-baseline",
+        requires = "baseline",
+        conflicts_with = "LOAD_BASELINE",
+        default_missing_value = "yes",
+        num_args = 0..=1,
+        require_equals = true,
+        value_parser = BoolishValueParser::new(),
+        env = "IAI_CALLGRIND_AUTO_SAVE_BASELINE",
+    )]
+    pub auto_save_baseline: Option<bool>,
+
+    /// Save and load the baselines in this directory instead of the target directory
+    ///
+    /// The target directory is often wiped, for example in CI, so this option allows to keep the
+    /// baselines at a stable location like `benches/baselines` and to check them into version
+    /// control. The directory layout below this directory is the same as below
+    /// `target/iai/$CARGO_PKG_NAME`. A relative path is resolved against the workspace root. The
+    /// output of the new benchmark runs still goes to the target directory.
+    ///
+    /// This option applies to `--save-baseline`, `--baseline` and `--load-baseline`.
+    #[arg(
+        long = "baseline-dir",
+        num_args = 1,
+        require_equals = true,
+        env = "IAI_CALLGRIND_BASELINE_DIR"
+    )]
+    pub baseline_dir: Option<PathBuf>,
+
+    /// Compare against the benchmark summaries in this json file or read from stdin if `-`
+    ///
+    /// The json file contains the `summary.json` files of an earlier benchmark run (created with
+    /// `--save-summary`) either concatenated or as array. The new callgrind costs of these
+    /// summaries are used as old costs of the comparison, so the callgrind output files of the
+    /// earlier run don't need to be present, for example if the baseline is stored in an external
+    /// store in CI. The benchmarks are matched by their module path and id.
+    #[arg(
+        long = "baseline-summary",
+        num_args = 1,
+        require_equals = true,
+        conflicts_with_all = &["save_baseline", "baseline", "LOAD_BASELINE"],
+        env = "IAI_CALLGRIND_BASELINE_SUMMARY"
+    )]
+    pub baseline_summary: Option<PathBuf>,
+
+    /// Merge the summaries of this benchmark run into the json file at this path
+    ///
+    /// The file contains the summaries of all benchmarks merged so far as json array and is
+    /// created if it doesn't exist. The summaries are matched by their module path and id and it
+    /// is an error if a benchmark is already present in the file. This is meant for running the
+    /// benchmarks of different benchmark files or groups in separate CI jobs (shards) with a
+    /// single final summary. A relative path is resolved against the workspace root.
+    #[arg(
+        long = "merge-summary",
+        num_args = 1,
+        require_equals = true,
+        env = "IAI_CALLGRIND_MERGE_SUMMARY"
+    )]
+    pub merge_summary: Option<PathBuf>,
+
     /// The terminal output format in default human-readable format or in machine-readable json
     /// format
     ///
@@ -187,15 +564,97 @@ pub struct CommandLineArgs {
     /// `cargo bench -- --output-format=json | jq -s`
     ///
     /// which transforms `{...}\n{...}` into `[{...},{...}]`
+    ///
+    /// # Multiple Output Formats
+    ///
+    /// More than one output format can be given as comma separated list like
+    /// `--output-format=default,json`. `terminal` is an alias for the `default` human-readable
+    /// format. If the human-readable format is combined with a json format, both are printed to
+    /// stdout.
     #[arg(
         long = "output-format",
         value_enum,
         required = false,
         default_value = "default",
         num_args = 1,
+        value_delimiter = ',',
         env = "IAI_CALLGRIND_OUTPUT_FORMAT"
     )]
-    pub output_format: OutputFormat,
+    pub output_format: Vec<OutputFormat>,
+
+    /// The format of the costs in the human-readable terminal output
+    ///
+    /// Large costs can be formatted with a separator between each group of thousands (`comma`:
+    /// `1,234,567,890`, `underscore`: `1_234_567_890`) or abbreviated with a SI prefix (`si`:
+    /// `1.23G`). The format doesn't depend on the locale. The machine-readable outputs like the
+    /// json output and the `summary.json` files always contain the raw numbers.
+    #[arg(
+        long = "number-format",
+        value_enum,
+        required = false,
+        default_value = "plain",
+        num_args = 1,
+        require_equals = true,
+        env = "IAI_CALLGRIND_NUMBER_FORMAT"
+    )]
+    pub number_format: NumberFormat,
+
+    /// The format of the difference between the new and the old costs in the terminal output
+    ///
+    /// `percentage` shows the difference in percent and the difference factor like `(+5.00000%)
+    /// [+1.05000x]`. `ratio` shows the ratio of the new to the old costs like `(1.05000x)`
+    /// instead. `both` shows the ratio next to the difference in percent like `(+5.00000%)
+    /// [1.05000x]`. If the old costs are zero, the ratio is shown as `∞`.
+    #[arg(
+        long = "diff-format",
+        value_enum,
+        required = false,
+        default_value = "percentage",
+        num_args = 1,
+        require_equals = true,
+        env = "IAI_CALLGRIND_DIFF_FORMAT"
+    )]
+    pub diff_format: DiffFormat,
+
+    /// Bundle the output files of the benchmarks into a `.tar.gz` archive at this path
+    ///
+    /// After the benchmark run, all files in the `target/iai` directory of the package (the
+    /// callgrind output and log files, the `summary.json` files and the flamegraph svg files) are
+    /// written into a single archive, for example to upload it as CI artifact. A relative path is
+    /// resolved against the workspace root. Which files are included can be configured with
+    /// `--bundle-raw` and `--bundle-svg`.
+    #[arg(
+        long = "bundle",
+        num_args = 1,
+        require_equals = true,
+        env = "IAI_CALLGRIND_BUNDLE"
+    )]
+    pub bundle: Option<PathBuf>,
+
+    /// If false, don't include the raw output and log files of the valgrind tools in the
+    /// `--bundle` [default: yes]
+    #[arg(
+        long = "bundle-raw",
+        default_missing_value = "yes",
+        num_args = 0..=1,
+        require_equals = true,
+        value_parser = BoolishValueParser::new(),
+        requires = "bundle",
+        env = "IAI_CALLGRIND_BUNDLE_RAW",
+    )]
+    pub bundle_raw: Option<bool>,
+
+    /// If false, don't include the flamegraph svg files in the `--bundle` [default: yes]
+    #[arg(
+        long = "bundle-svg",
+        default_missing_value = "yes",
+        num_args = 0..=1,
+        require_equals = true,
+        value_parser = BoolishValueParser::new(),
+        requires = "bundle",
+        env = "IAI_CALLGRIND_BUNDLE_SVG",
+    )]
+    pub bundle_svg: Option<bool>,
 }
 
 /// This function parses a space separated list of raw argument strings into [`crate::api::RawArgs`]
@@ -205,6 +664,15 @@ fn parse_args(value: &str) -> Result<RawArgs, String> {
         .map(RawArgs::new)
 }
 
+fn parse_regex(value: &str) -> Result<Regex, String> {
+    Regex::new(value).map_err(|error| format!("Invalid regular expression: {error}"))
+}
+
+fn parse_event_kind(value: &str) -> Result<EventKind, String> {
+    let value = value.trim();
+    EventKind::from_str_ignore_case(value).ok_or_else(|| format!("Unknown event kind: '{value}'"))
+}
+
 fn parse_regression_config(value: &str) -> Result<RegressionConfig, String> {
     let value = value.trim();
     if value.is_empty() {
@@ -242,6 +710,32 @@ fn parse_regression_config(value: &str) -> Result<RegressionConfig, String> {
     Ok(regression_config)
 }
 
+fn parse_change_tolerance(value: &str) -> Result<f64, String> {
+    let tolerance = value
+        .trim()
+        .parse::<f64>()
+        .map_err(|error| format!("Invalid tolerance: {error}"))?;
+    if tolerance.is_finite() && tolerance >= 0f64 {
+        Ok(tolerance)
+    } else {
+        Err(format!(
+            "Invalid tolerance: '{value}'. The tolerance must be a positive percentage"
+        ))
+    }
+}
+
+fn parse_top_regressions(value: &str) -> Result<usize, String> {
+    let num = value
+        .trim()
+        .parse::<usize>()
+        .map_err(|error| format!("Invalid number of top regressions: {error}"))?;
+    if num == 0 {
+        Err("Invalid number of top regressions: The number must be greater than 0".to_owned())
+    } else {
+        Ok(num)
+    }
+}
+
 impl From<&CommandLineArgs> for Option<RegressionConfig> {
     fn from(value: &CommandLineArgs) -> Self {
         let mut config = value.regression.clone();
@@ -285,7 +779,10 @@ mod tests {
     ) {
         let expected = RegressionConfig {
             limits: expected_limits,
+            min_abs_diffs: vec![],
             fail_fast: None,
+            score: vec![],
+            clear_limits: false,
         };
 
         let actual = parse_regression_config(regression_var).unwrap();
@@ -369,6 +866,63 @@ mod tests {
         assert_eq!(result.save_summary, Some(expected));
     }
 
+    #[rstest]
+    #[case::plain("plain", NumberFormat::Plain)]
+    #[case::comma("comma", NumberFormat::Comma)]
+    #[case::underscore("underscore", NumberFormat::Underscore)]
+    #[case::si("si", NumberFormat::Si)]
+    fn test_number_format_cli(#[case] value: &str, #[case] expected: NumberFormat) {
+        let result = CommandLineArgs::parse_from([format!("--number-format={value}")]);
+        assert_eq!(result.number_format, expected);
+    }
+
+    #[rstest]
+    #[case::percentage("percentage", DiffFormat::Percentage)]
+    #[case::ratio("ratio", DiffFormat::Ratio)]
+    #[case::both("both", DiffFormat::Both)]
+    fn test_diff_format_cli(#[case] value: &str, #[case] expected: DiffFormat) {
+        let result = CommandLineArgs::parse_from([format!("--diff-format={value}")]);
+        assert_eq!(result.diff_format, expected);
+    }
+
+    #[rstest]
+    #[case::default("default", &[OutputFormat::Default])]
+    #[case::terminal("terminal", &[OutputFormat::Default])]
+    #[case::json("json", &[OutputFormat::Json])]
+    #[case::pretty_json("pretty-json", &[OutputFormat::PrettyJson])]
+    #[case::multiple("json,terminal", &[OutputFormat::Json, OutputFormat::Default])]
+    fn test_output_format_cli(#[case] value: &str, #[case] expected: &[OutputFormat]) {
+        let result = CommandLineArgs::parse_from([format!("--output-format={value}")]);
+        assert_eq!(result.output_format, expected);
+    }
+
+    #[test]
+    fn test_output_format_cli_default() {
+        let result = CommandLineArgs::parse_from::<[_; 0], &str>([]);
+        assert_eq!(result.output_format, vec![OutputFormat::Default]);
+    }
+
+    #[test]
+    fn test_output_format_cli_when_unknown_then_error() {
+        let error = CommandLineArgs::try_parse_from(["--output-format=json,unknown".to_owned()])
+            .unwrap_err();
+        assert!(error
+            .to_string()
+            .contains("[possible values: default, json, pretty-json]"));
+    }
+
+    #[test]
+    fn test_diff_format_cli_default() {
+        let result = CommandLineArgs::parse_from::<[_; 0], &str>([]);
+        assert_eq!(result.diff_format, DiffFormat::Percentage);
+    }
+
+    #[test]
+    fn test_number_format_cli_default() {
+        let result = CommandLineArgs::parse_from::<[_; 0], &str>([]);
+        assert_eq!(result.number_format, NumberFormat::Plain);
+    }
+
     #[test]
     #[serial_test::serial]
     fn test_allow_aslr_env() {
@@ -389,4 +943,402 @@ mod tests {
         };
         assert_eq!(result.allow_aslr, Some(expected));
     }
+
+    #[test]
+    #[serial_test::serial]
+    fn test_peak_rss_env() {
+        std::env::set_var("IAI_CALLGRIND_PEAK_RSS", "yes");
+        let result = CommandLineArgs::parse_from::<[_; 0], &str>([]);
+        assert_eq!(result.peak_rss, Some(true));
+    }
+
+    #[rstest]
+    #[case::default("", true)]
+    #[case::yes("yes", true)]
+    #[case::no("no", false)]
+    fn test_peak_rss_cli(#[case] value: &str, #[case] expected: bool) {
+        let result = if value.is_empty() {
+            CommandLineArgs::parse_from(["--peak-rss".to_owned()])
+        } else {
+            CommandLineArgs::parse_from([format!("--peak-rss={value}")])
+        };
+        assert_eq!(result.peak_rss, Some(expected));
+    }
+
+    #[rstest]
+    #[case::default("", true)]
+    #[case::yes("yes", true)]
+    #[case::no("no", false)]
+    fn test_valgrind_time_cli(#[case] value: &str, #[case] expected: bool) {
+        let result = if value.is_empty() {
+            CommandLineArgs::parse_from(["--valgrind-time".to_owned()])
+        } else {
+            CommandLineArgs::parse_from([format!("--valgrind-time={value}")])
+        };
+        assert_eq!(result.valgrind_time, Some(expected));
+    }
+
+    #[rstest]
+    #[case::default("", true)]
+    #[case::yes("yes", true)]
+    #[case::no("no", false)]
+    fn test_check_determinism_cli(#[case] value: &str, #[case] expected: bool) {
+        let result = if value.is_empty() {
+            CommandLineArgs::parse_from(["--check-determinism".to_owned()])
+        } else {
+            CommandLineArgs::parse_from([format!("--check-determinism={value}")])
+        };
+        assert_eq!(result.check_determinism, Some(expected));
+    }
+
+    #[rstest]
+    #[case::default("", true)]
+    #[case::yes("yes", true)]
+    #[case::no("no", false)]
+    fn test_auto_save_baseline_cli(#[case] value: &str, #[case] expected: bool) {
+        let result = if value.is_empty() {
+            CommandLineArgs::parse_from(["--baseline=foo", "--auto-save-baseline"])
+        } else {
+            CommandLineArgs::parse_from([
+                "--baseline=foo".to_owned(),
+                format!("--auto-save-baseline={value}"),
+            ])
+        };
+        assert_eq!(result.auto_save_baseline, Some(expected));
+    }
+
+    #[rstest]
+    #[case::without_baseline(&["--auto-save-baseline"])]
+    #[case::with_load_baseline(&["--baseline=foo", "--load-baseline=bar", "--auto-save-baseline"])]
+    fn test_auto_save_baseline_cli_when_invalid(#[case] args: &[&str]) {
+        assert!(CommandLineArgs::try_parse_from(args).is_err());
+    }
+
+    #[rstest]
+    #[case::default("", true)]
+    #[case::yes("yes", true)]
+    #[case::no("no", false)]
+    fn test_fail_on_zero_cli(#[case] value: &str, #[case] expected: bool) {
+        let result = if value.is_empty() {
+            CommandLineArgs::parse_from(["--fail-on-zero".to_owned()])
+        } else {
+            CommandLineArgs::parse_from([format!("--fail-on-zero={value}")])
+        };
+        assert_eq!(result.fail_on_zero, Some(expected));
+    }
+
+    #[rstest]
+    #[case::default("", true)]
+    #[case::yes("yes", true)]
+    #[case::no("no", false)]
+    fn test_only_changed_cli(#[case] value: &str, #[case] expected: bool) {
+        let result = if value.is_empty() {
+            CommandLineArgs::parse_from(["--only-changed".to_owned()])
+        } else {
+            CommandLineArgs::parse_from([format!("--only-changed={value}")])
+        };
+        assert_eq!(result.only_changed, Some(expected));
+    }
+
+    #[rstest]
+    #[case::default("", true)]
+    #[case::yes("yes", true)]
+    #[case::no("no", false)]
+    fn test_quiet_cli(#[case] value: &str, #[case] expected: bool) {
+        let result = if value.is_empty() {
+            CommandLineArgs::parse_from(["--quiet".to_owned()])
+        } else {
+            CommandLineArgs::parse_from([format!("--quiet={value}")])
+        };
+        assert_eq!(result.quiet, Some(expected));
+    }
+
+    #[test]
+    fn test_quiet_cli_conflicts_with_only_changed() {
+        let result =
+            CommandLineArgs::try_parse_from(["--quiet".to_owned(), "--only-changed".to_owned()]);
+        assert!(result.is_err());
+    }
+
+    #[rstest]
+    #[case::zero("0", 0f64)]
+    #[case::integer("5", 5f64)]
+    #[case::float("0.5", 0.5f64)]
+    #[case::with_whitespace(" 1.5 ", 1.5f64)]
+    fn test_change_tolerance_cli(#[case] value: &str, #[case] expected: f64) {
+        let result = CommandLineArgs::parse_from([
+            "--only-changed".to_owned(),
+            format!("--change-tolerance={value}"),
+        ]);
+        assert_eq!(result.change_tolerance, Some(expected));
+    }
+
+    #[rstest]
+    #[case::negative("-1")]
+    #[case::infinite("inf")]
+    #[case::not_a_number("abc")]
+    fn test_change_tolerance_cli_when_invalid(#[case] value: &str) {
+        let result = CommandLineArgs::try_parse_from([
+            "--only-changed".to_owned(),
+            format!("--change-tolerance={value}"),
+        ]);
+        assert!(result.is_err());
+    }
+
+    #[rstest]
+    #[case::diff_pct("1%", None, Some(1f64))]
+    #[case::cost("1000", Some(1000), None)]
+    #[case::both("0.5%,1000", Some(1000), Some(0.5f64))]
+    #[case::with_whitespace(" 1000 , 0.5 % ", Some(1000), Some(0.5f64))]
+    #[case::zero("0%,0", Some(0), Some(0f64))]
+    fn test_filter_event_cli(
+        #[case] value: &str,
+        #[case] min_cost: Option<u64>,
+        #[case] min_diff_pct: Option<f64>,
+    ) {
+        let result = CommandLineArgs::parse_from([format!("--filter-event={value}")]);
+        assert_eq!(
+            result.filter_event,
+            Some(EventFilter {
+                min_cost,
+                min_diff_pct
+            })
+        );
+    }
+
+    #[rstest]
+    #[case::empty("")]
+    #[case::negative_pct("-1%")]
+    #[case::negative_cost("-1")]
+    #[case::float_cost("1.5")]
+    #[case::infinite("inf%")]
+    #[case::twice("1%,2%")]
+    fn test_filter_event_cli_when_invalid(#[case] value: &str) {
+        let result = CommandLineArgs::try_parse_from([format!("--filter-event={value}")]);
+        assert!(result.is_err());
+    }
+
+    #[rstest]
+    #[case::default("", true)]
+    #[case::yes("yes", true)]
+    #[case::no("no", false)]
+    fn test_fail_on_valgrind_mismatch_cli(#[case] value: &str, #[case] expected: bool) {
+        let result = if value.is_empty() {
+            CommandLineArgs::parse_from(["--fail-on-valgrind-mismatch".to_owned()])
+        } else {
+            CommandLineArgs::parse_from([format!("--fail-on-valgrind-mismatch={value}")])
+        };
+        assert_eq!(result.fail_on_valgrind_mismatch, Some(expected));
+    }
+
+    #[rstest]
+    #[case::plain("fibonacci", "my_bench::group::bench_fibonacci short:10", true)]
+    #[case::anchored("^my_bench::group::", "my_bench::group::bench_fibonacci", true)]
+    #[case::no_match("^group", "my_bench::group::bench_fibonacci", false)]
+    fn test_flamegraph_filter_cli(#[case] value: &str, #[case] name: &str, #[case] expected: bool) {
+        let result = CommandLineArgs::parse_from([format!("--flamegraph-filter={value}")]);
+        assert_eq!(result.flamegraph_filter.unwrap().is_match(name), expected);
+    }
+
+    #[test]
+    fn test_flamegraph_filter_cli_when_invalid() {
+        let result = CommandLineArgs::try_parse_from(["--flamegraph-filter=(".to_owned()]);
+        assert!(result.is_err());
+    }
+
+    #[rstest]
+    #[case::default("", true)]
+    #[case::yes("yes", true)]
+    #[case::no("no", false)]
+    fn test_fail_on_binary_change_cli(#[case] value: &str, #[case] expected: bool) {
+        let result = if value.is_empty() {
+            CommandLineArgs::parse_from(["--fail-on-binary-change".to_owned()])
+        } else {
+            CommandLineArgs::parse_from([format!("--fail-on-binary-change={value}")])
+        };
+        assert_eq!(result.fail_on_binary_change, Some(expected));
+    }
+
+    #[rstest]
+    #[case::default("", true)]
+    #[case::yes("yes", true)]
+    #[case::no("no", false)]
+    fn test_list_cli(#[case] value: &str, #[case] expected: bool) {
+        let result = if value.is_empty() {
+            CommandLineArgs::parse_from(["--list".to_owned()])
+        } else {
+            CommandLineArgs::parse_from([format!("--list={value}")])
+        };
+        assert_eq!(result.list, Some(expected));
+    }
+
+    #[rstest]
+    #[case::relative("benches/baselines")]
+    #[case::absolute("/some/baselines")]
+    fn test_baseline_dir_cli(#[case] value: &str) {
+        let result = CommandLineArgs::parse_from([format!("--baseline-dir={value}")]);
+        assert_eq!(result.baseline_dir, Some(PathBuf::from(value)));
+    }
+
+    #[rstest]
+    #[case::file("baseline.json")]
+    #[case::stdin("-")]
+    fn test_baseline_summary_cli(#[case] value: &str) {
+        let result = CommandLineArgs::parse_from([format!("--baseline-summary={value}")]);
+        assert_eq!(result.baseline_summary, Some(PathBuf::from(value)));
+    }
+
+    #[test]
+    fn test_bundle_cli() {
+        let result = CommandLineArgs::parse_from(["--bundle=artifacts/iai.tar.gz"]);
+        assert_eq!(result.bundle, Some(PathBuf::from("artifacts/iai.tar.gz")));
+        assert_eq!(result.bundle_raw, None);
+        assert_eq!(result.bundle_svg, None);
+    }
+
+    #[rstest]
+    #[case::default("", true)]
+    #[case::yes("yes", true)]
+    #[case::no("no", false)]
+    fn test_bundle_raw_and_svg_cli(#[case] value: &str, #[case] expected: bool) {
+        let (raw, svg) = if value.is_empty() {
+            ("--bundle-raw".to_owned(), "--bundle-svg".to_owned())
+        } else {
+            (
+                format!("--bundle-raw={value}"),
+                format!("--bundle-svg={value}"),
+            )
+        };
+        let result = CommandLineArgs::parse_from(["--bundle=iai.tar.gz".to_owned(), raw, svg]);
+        assert_eq!(result.bundle_raw, Some(expected));
+        assert_eq!(result.bundle_svg, Some(expected));
+    }
+
+    #[test]
+    fn test_bundle_raw_cli_requires_bundle() {
+        let result = CommandLineArgs::try_parse_from(["--bundle-raw=no"]);
+        assert!(result.is_err());
+    }
+
+    #[rstest]
+    #[case::single("0", &[0])]
+    #[case::list_and_range("0,2-3", &[0, 2, 3])]
+    fn test_cpu_affinity_cli(#[case] value: &str, #[case] expected: &[usize]) {
+        let result = CommandLineArgs::parse_from([format!("--cpu-affinity={value}")]);
+        assert_eq!(result.cpu_affinity.unwrap().cpus(), expected);
+    }
+
+    #[test]
+    fn test_cpu_affinity_cli_when_invalid() {
+        let result = CommandLineArgs::try_parse_from(["--cpu-affinity=0-a"]);
+        assert!(result.is_err());
+    }
+
+    #[rstest]
+    #[case::relative("summaries.json")]
+    #[case::absolute("/some/summaries.json")]
+    fn test_merge_summary_cli(#[case] value: &str) {
+        let result = CommandLineArgs::parse_from([format!("--merge-summary={value}")]);
+        assert_eq!(result.merge_summary, Some(PathBuf::from(value)));
+    }
+
+    #[test]
+    fn test_baseline_summary_cli_when_save_baseline() {
+        let result = CommandLineArgs::try_parse_from([
+            "--baseline-summary=baseline.json",
+            "--save-baseline=foo",
+        ]);
+        assert!(result.is_err());
+    }
+
+    #[rstest]
+    #[case::single("Ir", vec![Ir])]
+    #[case::multiple("d1mr, DLmr", vec![D1mr, DLmr])]
+    fn test_save_baseline_events_cli(#[case] value: &str, #[case] expected: Vec<EventKind>) {
+        let result = CommandLineArgs::parse_from([
+            "--save-baseline=foo".to_owned(),
+            format!("--save-baseline-events={value}"),
+        ]);
+        assert_eq!(result.save_baseline_events, Some(expected));
+    }
+
+    #[rstest]
+    #[case::unknown_event_kind(&["--save-baseline", "--save-baseline-events=Ir,Unknown"])]
+    #[case::without_save_baseline(&["--save-baseline-events=Ir"])]
+    fn test_save_baseline_events_cli_when_invalid(#[case] args: &[&str]) {
+        let result = CommandLineArgs::try_parse_from(args);
+        assert!(result.is_err());
+    }
+
+    #[rstest]
+    #[case::zero("0", 0)]
+    #[case::some("3", 3)]
+    fn test_retries_cli(#[case] value: &str, #[case] expected: u32) {
+        let result = CommandLineArgs::parse_from([format!("--retries={value}")]);
+        assert_eq!(result.retries, Some(expected));
+    }
+
+    #[rstest]
+    #[case::negative("-1")]
+    #[case::not_a_number("abc")]
+    fn test_retries_cli_when_invalid(#[case] value: &str) {
+        let result = CommandLineArgs::try_parse_from([format!("--retries={value}")]);
+        assert!(result.is_err());
+    }
+
+    #[rstest]
+    #[case::one("1", 1)]
+    #[case::some("10", 10)]
+    fn test_top_regressions_cli(#[case] value: &str, #[case] expected: usize) {
+        let result = CommandLineArgs::parse_from([format!("--top-regressions={value}")]);
+        assert_eq!(result.top_regressions, Some(expected));
+    }
+
+    #[test]
+    fn test_top_regressions_markdown_cli() {
+        let result = CommandLineArgs::parse_from([
+            "--top-regressions=5",
+            "--top-regressions-markdown=target/top.md",
+        ]);
+        assert_eq!(
+            result.top_regressions_markdown,
+            Some(PathBuf::from("target/top.md"))
+        );
+    }
+
+    #[test]
+    fn test_top_regressions_markdown_cli_requires_top_regressions() {
+        let result = CommandLineArgs::try_parse_from(["--top-regressions-markdown=target/top.md"]);
+        assert!(result.is_err());
+    }
+
+    #[rstest]
+    #[case::zero("0")]
+    #[case::negative("-1")]
+    #[case::not_a_number("abc")]
+    fn test_top_regressions_cli_when_invalid(#[case] value: &str) {
+        let result = CommandLineArgs::try_parse_from([format!("--top-regressions={value}")]);
+        assert!(result.is_err());
+    }
+
+    #[rstest]
+    #[case::exact("EstimatedCycles", EventKind::EstimatedCycles)]
+    #[case::ignore_case("dlmr", EventKind::DLmr)]
+    #[case::score("score", EventKind::Score)]
+    fn test_primary_event_cli(#[case] value: &str, #[case] expected: EventKind) {
+        let result = CommandLineArgs::parse_from([format!("--primary-event={value}")]);
+        assert_eq!(result.primary_event, Some(expected));
+    }
+
+    #[test]
+    fn test_primary_event_cli_when_unknown() {
+        let result = CommandLineArgs::try_parse_from(["--primary-event=Unknown".to_owned()]);
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_change_tolerance_cli_requires_only_changed() {
+        let result = CommandLineArgs::try_parse_from(["--change-tolerance=1".to_owned()]);
+        assert!(result.is_err());
+    }
 }
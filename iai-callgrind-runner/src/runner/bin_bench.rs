@@ -1,7 +1,7 @@
 use std::ffi::OsString;
 use std::fmt::Display;
 use std::io::stderr;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::process::Command;
 
 use anyhow::{anyhow, Context, Result};
@@ -9,26 +9,33 @@ use log::{debug, info, log_enabled, trace, Level};
 use tempfile::TempDir;
 
 use super::callgrind::args::Args;
+use super::callgrind::event_baseline::{apply_event_baseline, EventBaseline};
 use super::callgrind::flamegraph::{
-    BaselineFlamegraphGenerator, Config as FlamegraphConfig, Flamegraph, FlamegraphGenerator,
-    LoadBaselineFlamegraphGenerator, SaveBaselineFlamegraphGenerator,
+    BaselineFlamegraphGenerator, Config as FlamegraphConfig, FlamegraphBuilder,
+    FlamegraphGenerator, LoadBaselineFlamegraphGenerator, SaveBaselineFlamegraphGenerator,
 };
+use super::callgrind::metadata_parser::MetadataParser;
 use super::callgrind::model::Costs;
 use super::callgrind::parser::Sentinel;
-use super::callgrind::sentinel_parser::SentinelParser;
-use super::callgrind::summary_parser::SummaryParser;
-use super::callgrind::{CallgrindCommand, RegressionConfig};
-use super::format::{tool_headline, Header, OutputFormat, VerticalFormat};
+use super::callgrind::{
+    add_score, check_determinism, check_not_zero, CallgrindCommand, RegressionConfig,
+};
+use super::format::{
+    format_region, is_inline_output, print_deterministic, print_peak_rss, print_valgrind_time,
+    tool_headline, BenchmarkCounts, Header, VerticalFormat,
+};
 use super::meta::Metadata;
 use super::summary::{
     BaselineKind, BaselineName, BenchmarkKind, BenchmarkSummary, CallgrindRegressionSummary,
-    CallgrindSummary, CostsSummary, SummaryOutput,
+    CallgrindSummary, CostsSummary, RegressionsOutput, SummaryOutput, ToolSummary,
 };
 use super::tool::{
-    Parser, RunOptions, ToolConfigs, ToolOutputPath, ToolOutputPathKind, ValgrindTool,
+    filter_flamegraph_config, resolve_flamegraph_config, resolve_regression_config, resolve_tool,
+    OutputAssertion, Parser, RunOptions, ToolConfigs, ToolOutput, ToolOutputPath,
+    ToolOutputPathKind, ValgrindTool,
 };
-use super::Config;
-use crate::api::{self, BinaryBenchmark, BinaryBenchmarkConfig};
+use super::{Config, RunOutcome};
+use crate::api::{self, BinaryBenchmark, BinaryBenchmarkConfig, EventKind};
 use crate::error::Error;
 use crate::util::{copy_directory, write_all_to_stderr};
 
@@ -71,10 +78,14 @@ struct BinBench {
     command: PathBuf,
     command_args: Vec<OsString>,
     run_options: RunOptions,
+    regions: Vec<String>,
     callgrind_args: Args,
     flamegraph_config: Option<FlamegraphConfig>,
+    /// True if the flamegraphs of this benchmark are skipped by the `--flamegraph-filter`
+    flamegraph_skipped: bool,
     regression_config: Option<RegressionConfig>,
     tools: ToolConfigs,
+    tool: ValgrindTool,
 }
 
 #[derive(Debug)]
@@ -109,9 +120,18 @@ struct Sandbox {
     temp_dir: TempDir,
 }
 
+/// A region of a [`BinBench`] of which the costs are collected in a separate callgrind run
+#[derive(Debug)]
+struct Region {
+    name: String,
+    out_path: ToolOutputPath,
+}
+
 #[derive(Debug)]
 struct SaveBaselineBenchmark {
     baseline: BaselineName,
+    /// The event kinds to save with `--save-baseline-events` or all if `None`
+    events: Option<Vec<EventKind>>,
 }
 
 trait Benchmark: std::fmt::Debug {
@@ -135,6 +155,7 @@ trait Benchmarkable {
     fn executable(&self, config: &Config) -> PathBuf;
     fn executable_args(&self, config: &Config, group: &Group) -> Vec<OsString>;
     fn flamegraph_config(&self) -> Option<&FlamegraphConfig>;
+    fn is_assistant(&self) -> bool;
     fn name(&self) -> String;
     fn run_options(&self, config: &Config) -> RunOptions;
     fn tools(&self) -> &ToolConfigs;
@@ -147,11 +168,17 @@ trait Benchmarkable {
     fn check_and_print_regressions(
         &self,
         costs_summary: &CostsSummary,
+        out_path: &ToolOutputPath,
     ) -> Vec<CallgrindRegressionSummary>;
     fn parse(&self, config: &Config, out_path: &ToolOutputPath) -> Result<CostsSummary>;
     fn parse_costs(&self, config: &Config, out_path: &ToolOutputPath) -> Result<Costs>;
+    /// Like `parse_costs` but the costs of the event kinds pinned with `--save-baseline-events`
+    /// replace the costs of the output file
+    fn parse_baseline_costs(&self, config: &Config, out_path: &ToolOutputPath) -> Result<Costs>;
     fn print_header(&self, meta: &Metadata, group: &Group) -> Header;
+    fn regions(&self) -> &[String];
     fn sentinel(&self, config: &Config) -> Option<Sentinel>;
+    fn tool(&self) -> ValgrindTool;
 }
 
 impl Assistant {
@@ -269,6 +296,10 @@ impl Benchmarkable for Assistant {
         self.flamegraph_config.as_ref()
     }
 
+    fn is_assistant(&self) -> bool {
+        true
+    }
+
     fn name(&self) -> String {
         format!("{}.{}", &self.name, self.kind.id())
     }
@@ -315,21 +346,23 @@ impl Benchmarkable for Assistant {
     fn check_and_print_regressions(
         &self,
         costs_summary: &CostsSummary,
+        out_path: &ToolOutputPath,
     ) -> Vec<CallgrindRegressionSummary> {
         if let Some(regression_config) = &self.regression_config {
-            regression_config.check_and_print(costs_summary)
+            regression_config.check_and_print(costs_summary, out_path)
         } else {
             vec![]
         }
     }
 
     fn parse(&self, config: &Config, out_path: &ToolOutputPath) -> Result<CostsSummary> {
-        let new_costs = self.parse_costs(config, out_path)?;
+        let new_costs = self.parse_baseline_costs(config, out_path)?;
 
         let old_path = out_path.to_base_path();
         #[allow(clippy::if_then_some_else_none)]
         let old_costs = if old_path.exists() {
-            Some(self.parse_costs(config, &old_path)?)
+            MetadataParser.check(&config.meta, out_path, &old_path)?;
+            Some(self.parse_baseline_costs(config, &old_path)?)
         } else {
             None
         };
@@ -340,7 +373,17 @@ impl Benchmarkable for Assistant {
     fn parse_costs(&self, config: &Config, out_path: &ToolOutputPath) -> Result<Costs> {
         // This unwrap is safe because `sentinel()` always returns Some
         let sentinel = self.sentinel(config).unwrap();
-        SentinelParser::new(&sentinel).parse(out_path)
+        let mut costs = ValgrindTool::Callgrind
+            .to_costs_parser(Some(&sentinel))?
+            .parse_costs(out_path)?;
+        add_score(self.regression_config.as_ref(), &mut costs)?;
+        Ok(costs)
+    }
+
+    fn parse_baseline_costs(&self, config: &Config, out_path: &ToolOutputPath) -> Result<Costs> {
+        let mut costs = self.parse_costs(config, out_path)?;
+        apply_event_baseline(out_path, self.regression_config.as_ref(), &mut costs)?;
+        Ok(costs)
     }
 
     fn print_header(&self, meta: &Metadata, group: &Group) -> Header {
@@ -350,7 +393,7 @@ impl Benchmarkable for Assistant {
             None,
         );
 
-        if meta.args.output_format == OutputFormat::Default {
+        if is_inline_output(meta) {
             header.print();
             if self.tools.has_tools_enabled() {
                 println!("{}", tool_headline(ValgrindTool::Callgrind));
@@ -360,9 +403,17 @@ impl Benchmarkable for Assistant {
         header
     }
 
+    fn regions(&self) -> &[String] {
+        &[]
+    }
+
     fn sentinel(&self, config: &Config) -> Option<Sentinel> {
         Some(Sentinel::from_path(&config.module, &self.name))
     }
+
+    fn tool(&self) -> ValgrindTool {
+        ValgrindTool::Callgrind
+    }
 }
 
 impl AssistantKind {
@@ -393,6 +444,13 @@ impl Default for BenchmarkAssistants {
     }
 }
 
+impl BinBench {
+    /// The [`Header`] identifying this benchmark in the terminal output
+    fn to_header(&self, group: &Group) -> Header {
+        Header::new(&group.module_path, self.id.clone(), self.to_string())
+    }
+}
+
 impl Benchmarkable for BinBench {
     fn callgrind_args(&self) -> &Args {
         &self.callgrind_args
@@ -410,6 +468,10 @@ impl Benchmarkable for BinBench {
         self.flamegraph_config.as_ref()
     }
 
+    fn is_assistant(&self) -> bool {
+        false
+    }
+
     fn name(&self) -> String {
         format!("{}.{}", self.display, self.id)
     }
@@ -452,21 +514,23 @@ impl Benchmarkable for BinBench {
     fn check_and_print_regressions(
         &self,
         costs_summary: &CostsSummary,
+        out_path: &ToolOutputPath,
     ) -> Vec<CallgrindRegressionSummary> {
         if let Some(regression_config) = &self.regression_config {
-            regression_config.check_and_print(costs_summary)
+            regression_config.check_and_print(costs_summary, out_path)
         } else {
             vec![]
         }
     }
 
     fn parse(&self, config: &Config, out_path: &ToolOutputPath) -> Result<CostsSummary> {
-        let new_costs = self.parse_costs(config, out_path)?;
+        let new_costs = self.parse_baseline_costs(config, out_path)?;
 
         let old_path = out_path.to_base_path();
         #[allow(clippy::if_then_some_else_none)]
         let old_costs = if old_path.exists() {
-            Some(self.parse_costs(config, &old_path)?)
+            MetadataParser.check(&config.meta, out_path, &old_path)?;
+            Some(self.parse_baseline_costs(config, &old_path)?)
         } else {
             None
         };
@@ -475,25 +539,41 @@ impl Benchmarkable for BinBench {
     }
 
     fn parse_costs(&self, _config: &Config, out_path: &ToolOutputPath) -> Result<Costs> {
-        SummaryParser.parse(out_path)
+        let mut costs = self.tool.to_costs_parser(None)?.parse_costs(out_path)?;
+        add_score(self.regression_config.as_ref(), &mut costs)?;
+        Ok(costs)
+    }
+
+    fn parse_baseline_costs(&self, config: &Config, out_path: &ToolOutputPath) -> Result<Costs> {
+        let mut costs = self.parse_costs(config, out_path)?;
+        apply_event_baseline(out_path, self.regression_config.as_ref(), &mut costs)?;
+        Ok(costs)
     }
 
     fn print_header(&self, meta: &Metadata, group: &Group) -> Header {
-        let header = Header::new(&group.module_path, self.id.clone(), self.to_string());
+        let header = self.to_header(group);
 
-        if meta.args.output_format == OutputFormat::Default {
+        if is_inline_output(meta) {
             header.print();
-            if self.tools.has_tools_enabled() {
-                println!("{}", tool_headline(ValgrindTool::Callgrind));
+            if self.tool.has_costs() && self.tools.has_tools_enabled() {
+                println!("{}", tool_headline(self.tool));
             }
         }
 
         header
     }
 
+    fn regions(&self) -> &[String] {
+        &self.regions
+    }
+
     fn sentinel(&self, _config: &Config) -> Option<Sentinel> {
         self.run_options.entry_point.as_ref().map(Sentinel::new)
     }
+
+    fn tool(&self) -> ValgrindTool {
+        self.tool
+    }
 }
 
 impl Display for BinBench {
@@ -516,7 +596,8 @@ impl Group {
         &self,
         benchmark: &dyn Benchmark,
         assistant: &mut Assistant,
-        is_regressed: &mut bool,
+        outcome: &mut RunOutcome,
+        regressions: &mut RegressionsOutput,
         config: &Config,
     ) -> Result<()> {
         let fail_fast = assistant
@@ -524,17 +605,41 @@ impl Group {
             .as_ref()
             .map_or(false, |r| r.fail_fast);
         if let Some(summary) = assistant.run(benchmark, config, self)? {
+            // The assistants are not benchmarks and are not counted in the final status lines
+            summary.print_deferred(&config.meta, &benchmark.baselines())?;
             summary.print_and_save(&config.meta.args.output_format)?;
-            summary.check_regression(is_regressed, fail_fast)?;
+            regressions.add(&summary)?;
+            summary.check_tool_errors()?;
+            outcome.add(summary, fail_fast)?;
         }
 
         Ok(())
     }
 
+    /// Run a benchmark again with the state prepared by the `teardown` and `setup` assistants
+    ///
+    /// The first run of a benchmark is surrounded by the `setup` and `teardown` in [`Group::run`],
+    /// so the state left by the first run is cleaned up with the `teardown` and prepared again with
+    /// the `setup` before the `run`. The assistants are not benchmarked.
+    fn rerun<T, F>(&self, config: &Config, run: F) -> Result<T>
+    where
+        F: FnOnce() -> Result<T>,
+    {
+        if let Some(teardown) = &self.assists.teardown {
+            teardown.run_plain(config, self)?;
+        }
+        if let Some(setup) = &self.assists.setup {
+            setup.run_plain(config, self)?;
+        }
+        run()
+    }
+
     fn run(
         &self,
         benchmark: &dyn Benchmark,
-        is_regressed: &mut bool,
+        outcome: &mut RunOutcome,
+        counts: &mut BenchmarkCounts,
+        regressions: &mut RegressionsOutput,
         config: &Config,
     ) -> Result<()> {
         let sandbox = if self.sandbox {
@@ -551,12 +656,12 @@ impl Group {
         let mut assists = self.assists.clone();
 
         if let Some(before) = assists.before.as_mut() {
-            self.run_assistant(benchmark, before, is_regressed, config)?;
+            self.run_assistant(benchmark, before, outcome, regressions, config)?;
         }
 
         for bench in &self.benches {
             if let Some(setup) = assists.setup.as_mut() {
-                self.run_assistant(benchmark, setup, is_regressed, config)?;
+                self.run_assistant(benchmark, setup, outcome, regressions, config)?;
             }
 
             let fail_fast = bench
@@ -564,16 +669,20 @@ impl Group {
                 .as_ref()
                 .map_or(false, |r| r.fail_fast);
             let summary = benchmark.run(bench, config, self)?;
+            counts.print_and_count(&config.meta, &summary, &benchmark.baselines())?;
+            counts.count_flamegraphs(&summary, bench.flamegraph_skipped);
             summary.print_and_save(&config.meta.args.output_format)?;
-            summary.check_regression(is_regressed, fail_fast)?;
+            regressions.add(&summary)?;
+            summary.check_tool_errors()?;
+            outcome.add(summary, fail_fast)?;
 
             if let Some(teardown) = assists.teardown.as_mut() {
-                self.run_assistant(benchmark, teardown, is_regressed, config)?;
+                self.run_assistant(benchmark, teardown, outcome, regressions, config)?;
             }
         }
 
         if let Some(after) = assists.after.as_mut() {
-            self.run_assistant(benchmark, after, is_regressed, config)?;
+            self.run_assistant(benchmark, after, outcome, regressions, config)?;
         }
 
         if let Some(sandbox) = sandbox {
@@ -586,6 +695,7 @@ impl Group {
 }
 
 impl Groups {
+    #[allow(clippy::too_many_lines)]
     fn parse_runs(
         module_path: &str,
         cmd: &Option<api::Cmd>,
@@ -616,13 +726,63 @@ impl Groups {
             };
             let config = group_config.clone().update_from_all([Some(&run.config)]);
             let envs = config.resolve_envs();
-            let flamegraph_config = config.flamegraph_config.map(std::convert::Into::into);
-            let regression_config =
-                api::update_option(&config.regression_config, &meta.regression_config)
-                    .map(std::convert::Into::into);
+            let tool = resolve_tool(
+                config.default_tool,
+                config.cachegrind,
+                run.config.default_tool,
+                run.config.cachegrind,
+            );
+            let add_context = |error: anyhow::Error| anyhow!("{module_path}: {error}");
+            let flamegraph_config = resolve_flamegraph_config(
+                tool,
+                config.flamegraph_config,
+                run.config.flamegraph_config.is_none(),
+                meta.primary_event_kind(),
+                module_path,
+            )
+            .map_err(add_context)?;
+            let regression_config = resolve_regression_config(
+                tool,
+                &config.regression_config,
+                &meta.regression_config,
+                run.config.regression_config.is_none(),
+                meta.primary_event_kind(),
+                module_path,
+            )
+            .map_err(add_context)?;
             let callgrind_args =
                 Args::from_raw_args(&[&config.raw_callgrind_args, &meta_callgrind_args])?;
-            let tools = ToolConfigs(config.tools.0.into_iter().map(Into::into).collect());
+            let tools = ToolConfigs::new(config.tools, tool).map_err(add_context)?;
+            let output_assertions = config
+                .output_assertions
+                .iter()
+                .map(OutputAssertion::try_from)
+                .collect::<Result<Vec<_>>>()
+                .map_err(add_context)?;
+            match tool {
+                ValgrindTool::Callgrind => callgrind_args
+                    .check_collectible(regression_config.as_ref(), flamegraph_config.as_ref()),
+                ValgrindTool::Cachegrind => {
+                    callgrind_args.check_cachegrind_collectible(regression_config.as_ref())
+                }
+                _ => Ok(()),
+            }
+            .map_err(add_context)?;
+            callgrind_args
+                .check_primary_collectible(
+                    tool,
+                    &meta.primary_event_kind(),
+                    regression_config.as_ref(),
+                )
+                .map_err(add_context)?;
+            if tool != ValgrindTool::Callgrind && !config.regions.is_empty() {
+                return Err(anyhow!(
+                    "{module_path}: Regions are not supported by {}: {:?}",
+                    tool.id(),
+                    config.regions
+                ));
+            }
+            let regions = Region::dedup(&config.regions).map_err(add_context)?;
             for args in run.args {
                 let id = if let Some(id) = args.id {
                     id
@@ -631,7 +791,7 @@ impl Groups {
                     counter += 1;
                     id
                 };
-                benches.push(BinBench {
+                let mut bench = BinBench {
                     id,
                     display: orig.clone(),
                     command: command.clone(),
@@ -641,13 +801,25 @@ impl Groups {
                         current_dir: config.current_dir.clone(),
                         entry_point: config.entry_point.clone(),
                         exit_with: config.exit_with.clone(),
+                        output_assertions: output_assertions.clone(),
                         envs: envs.clone(),
                     },
+                    regions: regions.clone(),
                     callgrind_args: callgrind_args.clone(),
                     flamegraph_config: flamegraph_config.clone(),
+                    flamegraph_skipped: false,
                     regression_config: regression_config.clone(),
                     tools: tools.clone(),
-                });
+                    tool,
+                };
+                let name =
+                    Header::new(module_path, bench.id.clone(), bench.to_string()).to_list_entry();
+                bench.flamegraph_skipped = filter_flamegraph_config(
+                    &mut bench.flamegraph_config,
+                    meta.args.flamegraph_filter.as_ref(),
+                    &name,
+                );
+                benches.push(bench);
             }
         }
         Ok(benches)
@@ -735,6 +907,31 @@ impl Groups {
                 Self::parse_runs(&module_path, &group.cmd, group.benches, &group_config, meta)?;
             let callgrind_args =
                 Args::from_raw_args(&[&group_config.raw_callgrind_args, &meta_callgrind_args])?;
+            let regression_config =
+                api::update_option(&group_config.regression_config, &meta.regression_config)
+                    .map(|config| RegressionConfig::from_api(config, meta.primary_event_kind()));
+            let flamegraph_config = resolve_flamegraph_config(
+                ValgrindTool::Callgrind,
+                group_config.flamegraph_config,
+                true,
+                meta.primary_event_kind(),
+                &module_path,
+            )?;
+            if group.assists.iter().any(|assist| assist.bench) {
+                callgrind_args
+                    .check_collectible(regression_config.as_ref(), flamegraph_config.as_ref())
+                    .and_then(|()| {
+                        callgrind_args.check_primary_collectible(
+                            ValgrindTool::Callgrind,
+                            &meta.primary_event_kind(),
+                            regression_config.as_ref(),
+                        )
+                    })
+                    .map_err(|error| anyhow!("{module_path}: {error}"))?;
+            }
+            // The assistants always run with callgrind
+            let tools = ToolConfigs::new(group_config.tools, ValgrindTool::Callgrind)
+                .map_err(|error| anyhow!("{module_path}: {error}"))?;
             let config = Group {
                 id: group.id,
                 module_path,
@@ -744,11 +941,9 @@ impl Groups {
                 assists: Self::parse_assists(
                     group.assists,
                     &callgrind_args,
-                    api::update_option(&group_config.regression_config, &meta.regression_config)
-                        .map(std::convert::Into::into)
-                        .as_ref(),
-                    group_config.flamegraph_config.map(Into::into).as_ref(),
-                    &ToolConfigs(group_config.tools.0.into_iter().map(Into::into).collect()),
+                    regression_config.as_ref(),
+                    flamegraph_config.as_ref(),
+                    &tools,
                 ),
             };
             groups.push(config);
@@ -756,24 +951,43 @@ impl Groups {
         Ok(Self(groups))
     }
 
+    /// Print the names of all [`BinBench`] benchmarks without running them
+    ///
+    /// The assistants are not listed since they are not benchmarks on their own.
+    fn list(&self) {
+        for group in &self.0 {
+            for bench in &group.benches {
+                println!("{}", bench.to_header(group).to_list_entry());
+            }
+        }
+    }
+
     /// Run all [`Group`] benchmarks
     ///
     /// # Errors
     ///
     /// Return an [`anyhow::Error`] with sources:
     ///
-    /// * [`Error::RegressionError`] if a regression occurred.
-    fn run(&self, benchmark: &dyn Benchmark, config: &Config) -> Result<()> {
-        let mut is_regressed = false;
+    /// * [`Error::RegressionError`] if a fatal regression occurred.
+    fn run(&self, benchmark: &dyn Benchmark, config: &Config) -> Result<RunOutcome> {
+        let mut outcome = RunOutcome::default();
+        let mut counts = BenchmarkCounts::default();
+        let mut regressions =
+            RegressionsOutput::new(&config.meta.target_dir.join(&config.meta.bench_name));
+        regressions.init()?;
         for group in &self.0 {
-            group.run(benchmark, &mut is_regressed, config)?;
+            group.run(
+                benchmark,
+                &mut outcome,
+                &mut counts,
+                &mut regressions,
+                config,
+            )?;
         }
 
-        if is_regressed {
-            Err(Error::RegressionError(false).into())
-        } else {
-            Ok(())
-        }
+        counts.print(&config.meta);
+
+        Ok(outcome)
     }
 }
 
@@ -786,9 +1000,10 @@ impl Benchmark for BaselineBenchmark {
     ) -> ToolOutputPath {
         ToolOutputPath::new(
             ToolOutputPathKind::Out,
-            ValgrindTool::Callgrind,
+            benchmarkable.tool(),
             &self.baseline_kind,
             &config.meta.target_dir,
+            &config.meta.baseline_dir,
             &group.module_path,
             &benchmarkable.name(),
         )
@@ -801,28 +1016,40 @@ impl Benchmark for BaselineBenchmark {
         }
     }
 
+    #[allow(clippy::too_many_lines)]
     fn run(
         &self,
         benchmarkable: &dyn Benchmarkable,
         config: &Config,
         group: &Group,
     ) -> Result<BenchmarkSummary> {
-        let callgrind_command = CallgrindCommand::new(&config.meta);
+        let callgrind_command = CallgrindCommand::new(&config.meta, benchmarkable.tool());
         let executable = benchmarkable.executable(config);
         let executable_args = benchmarkable.executable_args(config, group);
         let run_options = benchmarkable.run_options(config);
         let out_path = self.output_path(benchmarkable, config, group);
+        if !benchmarkable.tool().has_costs() {
+            return run_tools_only(benchmarkable, config, group, &out_path, || {
+                benchmarkable.tools().shift(&out_path)?;
+                benchmarkable.tools().run(
+                    &config.meta,
+                    &executable,
+                    &executable_args,
+                    &run_options,
+                    &out_path,
+                    false,
+                )
+            });
+        }
         out_path.init()?;
         out_path.shift()?;
 
         let old_path = out_path.to_base_path();
         let log_path = out_path.to_log_output();
         log_path.shift()?;
+        out_path.to_meta_output().shift()?;
 
-        for path in benchmarkable.tools().output_paths(&out_path) {
-            path.shift()?;
-            path.to_log_output().shift()?;
-        }
+        benchmarkable.tools().shift(&out_path)?;
 
         let mut benchmark_summary =
             benchmarkable.create_benchmark_summary(config, group, &out_path)?;
@@ -837,41 +1064,79 @@ impl Benchmark for BaselineBenchmark {
             &out_path,
         )?;
 
-        let costs_summary = benchmarkable.parse(config, &out_path)?;
+        let costs_summary = if let Some(baseline_summaries) = &config.meta.baseline_summaries {
+            CostsSummary::new(
+                &benchmarkable.parse_costs(config, &out_path)?,
+                baseline_summaries.costs(
+                    &benchmark_summary.module_path,
+                    benchmark_summary.id.as_deref(),
+                ),
+            )
+        } else {
+            benchmarkable.parse(config, &out_path)?
+        };
         VerticalFormat::default().print(&config.meta, self.baselines(), &costs_summary)?;
+        print_peak_rss(&config.meta, output.peak_rss);
+        print_valgrind_time(&config.meta, output.wall_time);
+
+        if config.meta.args.fail_on_zero.unwrap_or_default() {
+            check_not_zero(&costs_summary, &out_path)?;
+        }
+
+        let deterministic = run_determinism_check(benchmarkable, config, group, &out_path)?;
+        print_deterministic(&config.meta, deterministic);
 
         output.dump_log(log::Level::Info);
         log_path.dump_log(log::Level::Info, &mut stderr())?;
 
-        let regressions = benchmarkable.check_and_print_regressions(&costs_summary);
+        let regressions = benchmarkable.check_and_print_regressions(&costs_summary, &out_path);
 
         let callgrind_summary = benchmark_summary
             .callgrind_summary
             .insert(CallgrindSummary::new(
+                out_path.tool,
                 log_path.real_paths()?,
                 out_path.real_paths()?,
             ));
 
-        callgrind_summary.add_summary(
-            &executable,
-            &executable_args,
-            &old_path,
-            costs_summary,
-            regressions,
-        );
+        callgrind_summary
+            .add_summary(
+                &executable,
+                &executable_args,
+                &old_path,
+                costs_summary,
+                regressions,
+                Some(&output),
+            )
+            .deterministic = deterministic;
+
+        summarize_regions(
+            benchmarkable,
+            config,
+            group,
+            &out_path,
+            &self.baselines(),
+            None,
+            true,
+            callgrind_summary,
+        )?;
 
         if let Some(flamegraph_config) = benchmarkable.flamegraph_config().cloned() {
             callgrind_summary.flamegraphs = BaselineFlamegraphGenerator {
                 baseline_kind: self.baseline_kind.clone(),
             }
             .create(
-                &Flamegraph::new(header.to_title(), flamegraph_config),
+                &FlamegraphBuilder::with_config(header.to_title(), flamegraph_config).build(),
                 &out_path,
                 benchmarkable.sentinel(config).as_ref(),
                 &config.meta.project_root,
             )?;
         }
 
+        if config.meta.args.auto_save_baseline.unwrap_or_default() && !old_path.exists() {
+            out_path.save_as_baseline()?;
+        }
+
         benchmark_summary.tool_summaries = benchmarkable.tools().run(
             &config.meta,
             &executable,
@@ -894,9 +1159,10 @@ impl Benchmark for LoadBaselineBenchmark {
     ) -> ToolOutputPath {
         ToolOutputPath::new(
             ToolOutputPathKind::Base(self.loaded_baseline.to_string()),
-            ValgrindTool::Callgrind,
+            benchmarkable.tool(),
             &BaselineKind::Name(self.baseline.clone()),
             &config.meta.target_dir,
+            &config.meta.baseline_dir,
             &group.module_path,
             &benchmarkable.name(),
         )
@@ -918,6 +1184,13 @@ impl Benchmark for LoadBaselineBenchmark {
         let executable = benchmarkable.executable(config);
         let executable_args = benchmarkable.executable_args(config, group);
         let out_path = self.output_path(benchmarkable, config, group);
+        if !benchmarkable.tool().has_costs() {
+            return run_tools_only(benchmarkable, config, group, &out_path, || {
+                benchmarkable
+                    .tools()
+                    .run_loaded_vs_base(&config.meta, &out_path)
+            });
+        }
         let base_path = out_path.to_base_path();
         let log_path = out_path.to_log_output();
 
@@ -931,11 +1204,12 @@ impl Benchmark for LoadBaselineBenchmark {
 
         log_path.dump_log(log::Level::Info, &mut stderr())?;
 
-        let regressions = benchmarkable.check_and_print_regressions(&costs_summary);
+        let regressions = benchmarkable.check_and_print_regressions(&costs_summary, &out_path);
 
         let callgrind_summary = benchmark_summary
             .callgrind_summary
             .insert(CallgrindSummary::new(
+                out_path.tool,
                 log_path.real_paths()?,
                 out_path.real_paths()?,
             ));
@@ -946,15 +1220,27 @@ impl Benchmark for LoadBaselineBenchmark {
             &base_path,
             costs_summary,
             regressions,
+            None,
         );
 
+        summarize_regions(
+            benchmarkable,
+            config,
+            group,
+            &out_path,
+            &self.baselines(),
+            None,
+            false,
+            callgrind_summary,
+        )?;
+
         if let Some(flamegraph_config) = benchmarkable.flamegraph_config().cloned() {
             callgrind_summary.flamegraphs = LoadBaselineFlamegraphGenerator {
                 loaded_baseline: self.loaded_baseline.clone(),
                 baseline: self.baseline.clone(),
             }
             .create(
-                &Flamegraph::new(header.to_title(), flamegraph_config),
+                &FlamegraphBuilder::with_config(header.to_title(), flamegraph_config).build(),
                 &out_path,
                 benchmarkable.sentinel(config).as_ref(),
                 &config.meta.project_root,
@@ -977,6 +1263,7 @@ impl Runner {
             if let Some(baseline_name) = &config.meta.args.save_baseline {
                 Box::new(SaveBaselineBenchmark {
                     baseline: baseline_name.clone(),
+                    events: config.meta.args.save_baseline_events.clone(),
                 })
             } else if let Some(baseline_name) = &config.meta.args.load_baseline {
                 Box::new(LoadBaselineBenchmark {
@@ -1007,8 +1294,13 @@ impl Runner {
         })
     }
 
-    fn run(&self) -> Result<()> {
-        self.groups.run(self.benchmark.as_ref(), &self.config)
+    fn run(&self) -> Result<RunOutcome> {
+        if self.config.meta.args.list.unwrap_or_default() {
+            self.groups.list();
+            Ok(RunOutcome::default())
+        } else {
+            self.groups.run(self.benchmark.as_ref(), &self.config)
+        }
     }
 }
 
@@ -1072,9 +1364,10 @@ impl Benchmark for SaveBaselineBenchmark {
     ) -> ToolOutputPath {
         ToolOutputPath::new(
             ToolOutputPathKind::Base(self.baseline.to_string()),
-            ValgrindTool::Callgrind,
+            benchmarkable.tool(),
             &BaselineKind::Name(self.baseline.clone()),
             &config.meta.target_dir,
+            &config.meta.baseline_dir,
             &group.module_path,
             &benchmarkable.name(),
         )
@@ -1087,27 +1380,50 @@ impl Benchmark for SaveBaselineBenchmark {
         )
     }
 
+    #[allow(clippy::too_many_lines)]
     fn run(
         &self,
         benchmarkable: &dyn Benchmarkable,
         config: &Config,
         group: &Group,
     ) -> Result<BenchmarkSummary> {
-        let callgrind_command = CallgrindCommand::new(&config.meta);
+        let callgrind_command = CallgrindCommand::new(&config.meta, benchmarkable.tool());
         let executable = benchmarkable.executable(config);
         let executable_args = benchmarkable.executable_args(config, group);
         let run_options = benchmarkable.run_options(config);
         let out_path = self.output_path(benchmarkable, config, group);
+        if !benchmarkable.tool().has_costs() {
+            return run_tools_only(benchmarkable, config, group, &out_path, || {
+                benchmarkable.tools().run(
+                    &config.meta,
+                    &executable,
+                    &executable_args,
+                    &run_options,
+                    &out_path,
+                    true,
+                )
+            });
+        }
         out_path.init()?;
 
         #[allow(clippy::if_then_some_else_none)]
-        let old_costs = if out_path.exists() {
-            let old_costs = benchmarkable.parse_costs(config, &out_path)?;
+        let (old_metadata, old_costs, old_region_costs) = if out_path.exists() {
+            let old_metadata = MetadataParser.parse(&out_path)?;
+            let old_costs = benchmarkable.parse_baseline_costs(config, &out_path)?;
+            let old_region_costs = parse_region_costs(benchmarkable, config, &out_path)?;
             out_path.clear()?;
-            Some(old_costs)
+            (Some(old_metadata), Some(old_costs), old_region_costs)
         } else {
-            None
+            (None, None, vec![])
         };
+        EventBaseline::update(&out_path, old_costs.as_ref(), self.events.as_deref())?;
+        for (index, name) in benchmarkable.regions().iter().enumerate() {
+            EventBaseline::update(
+                &Region::new(name, &out_path).out_path,
+                old_region_costs.get(index).and_then(Option::as_ref),
+                self.events.as_deref(),
+            )?;
+        }
 
         let log_path = out_path.to_log_output();
         log_path.clear()?;
@@ -1125,36 +1441,67 @@ impl Benchmark for SaveBaselineBenchmark {
             &out_path,
         )?;
 
+        if let Some(old_metadata) = &old_metadata {
+            MetadataParser.parse(&out_path)?.check_mismatch(
+                old_metadata,
+                &out_path,
+                &config.meta,
+            )?;
+        }
         let new_costs = benchmarkable.parse_costs(config, &out_path)?;
         let costs_summary = CostsSummary::new(&new_costs, old_costs.as_ref());
         VerticalFormat::default().print(&config.meta, self.baselines(), &costs_summary)?;
+        print_peak_rss(&config.meta, output.peak_rss);
+        print_valgrind_time(&config.meta, output.wall_time);
+
+        if config.meta.args.fail_on_zero.unwrap_or_default() {
+            check_not_zero(&costs_summary, &out_path)?;
+        }
+
+        let deterministic = run_determinism_check(benchmarkable, config, group, &out_path)?;
+        print_deterministic(&config.meta, deterministic);
 
         output.dump_log(log::Level::Info);
         log_path.dump_log(log::Level::Info, &mut stderr())?;
 
-        let regressions = benchmarkable.check_and_print_regressions(&costs_summary);
+        let regressions = benchmarkable.check_and_print_regressions(&costs_summary, &out_path);
 
         let callgrind_summary = benchmark_summary
             .callgrind_summary
             .insert(CallgrindSummary::new(
+                out_path.tool,
                 log_path.real_paths()?,
                 out_path.real_paths()?,
             ));
 
-        callgrind_summary.add_summary(
-            &executable,
-            &executable_args,
+        callgrind_summary
+            .add_summary(
+                &executable,
+                &executable_args,
+                &out_path,
+                costs_summary,
+                regressions,
+                Some(&output),
+            )
+            .deterministic = deterministic;
+
+        summarize_regions(
+            benchmarkable,
+            config,
+            group,
             &out_path,
-            costs_summary,
-            regressions,
-        );
+            &self.baselines(),
+            Some(old_region_costs),
+            true,
+            callgrind_summary,
+        )?;
 
         if let Some(flamegraph_config) = benchmarkable.flamegraph_config().cloned() {
             callgrind_summary.flamegraphs = SaveBaselineFlamegraphGenerator {
                 baseline: self.baseline.clone(),
             }
             .create(
-                &Flamegraph::new(header.to_title(), flamegraph_config),
+                &FlamegraphBuilder::with_config(header.to_title(), flamegraph_config).build(),
                 &out_path,
                 benchmarkable.sentinel(config).as_ref(),
                 &config.meta.project_root,
@@ -1174,6 +1521,346 @@ impl Benchmark for SaveBaselineBenchmark {
     }
 }
 
-pub fn run(binary_benchmark: BinaryBenchmark, config: Config) -> Result<()> {
+impl Region {
+    /// Create a new `Region` with the output files of the `out_path` and the region as modifier
+    fn new(name: &str, out_path: &ToolOutputPath) -> Self {
+        Self {
+            name: name.to_owned(),
+            out_path: out_path.with_modifiers(["region", Self::modifier(name).as_str()]),
+        }
+    }
+
+    /// The modifier of the output files of the region with this `name`
+    ///
+    /// All characters of the `name` which are not allowed in file names are replaced with `_`.
+    fn modifier(name: &str) -> String {
+        name.chars()
+            .map(|c| {
+                if c.is_ascii_alphanumeric() || c == '_' || c == '-' {
+                    c
+                } else {
+                    '_'
+                }
+            })
+            .collect()
+    }
+
+    /// Remove the duplicates of the `regions` keeping the first occurrence of each region
+    ///
+    /// The regions of all config levels are accumulated, so the same region may occur more than
+    /// once.
+    ///
+    /// # Errors
+    ///
+    /// If two different regions have the same modifier like `a::b` and `a__b` and would share the
+    /// same output files
+    fn dedup(regions: &[String]) -> Result<Vec<String>> {
+        let mut modifiers: Vec<(String, &str)> = Vec::with_capacity(regions.len());
+        for name in regions {
+            let modifier = Self::modifier(name);
+            if let Some((_, other)) = modifiers.iter().find(|(m, _)| *m == modifier) {
+                if other == name {
+                    continue;
+                }
+                return Err(anyhow!(
+                    "The regions '{other}' and '{name}' would have the same output files. Each \
+                     region must be unique after replacing the characters which are not \
+                     alphanumeric, '_' or '-' with '_'"
+                ));
+            }
+            modifiers.push((modifier, name));
+        }
+        Ok(modifiers
+            .into_iter()
+            .map(|(_, name)| name.to_owned())
+            .collect())
+    }
+
+    /// Run callgrind collecting the events only within this region instead of the entry point
+    fn run(
+        &self,
+        benchmarkable: &dyn Benchmarkable,
+        config: &Config,
+        executable: &Path,
+        executable_args: &[OsString],
+    ) -> Result<ToolOutput> {
+        let mut run_options = benchmarkable.run_options(config);
+        run_options.entry_point = Some(self.name.clone());
+
+        let output = CallgrindCommand::new(&config.meta, benchmarkable.tool()).run(
+            benchmarkable.callgrind_args().clone(),
+            executable,
+            executable_args,
+            run_options,
+            &self.out_path,
+        )?;
+
+        output.dump_log(log::Level::Info);
+        self.out_path
+            .to_log_output()
+            .dump_log(log::Level::Info, &mut stderr())?;
+
+        Ok(output)
+    }
+
+    /// Parse the costs of this region in the callgrind output file at `path` if it exists
+    ///
+    /// The existence of the output file is checked with its exact path, since the output files of
+    /// the whole benchmark run and all regions share the same prefix. Like the costs of the whole
+    /// benchmark run, the costs in a baseline are replaced with the costs pinned with
+    /// `--save-baseline-events` if `pinned` is true.
+    fn parse_costs(
+        benchmarkable: &dyn Benchmarkable,
+        config: &Config,
+        path: &ToolOutputPath,
+        pinned: bool,
+    ) -> Result<Option<Costs>> {
+        if !path.to_path().exists() {
+            Ok(None)
+        } else if pinned {
+            benchmarkable.parse_baseline_costs(config, path).map(Some)
+        } else {
+            benchmarkable.parse_costs(config, path).map(Some)
+        }
+    }
+}
+
+/// Parse the costs of all regions of the `benchmarkable` in the output files of the `out_path`
+///
+/// The returned vector has an entry for each region in the order of the regions.
+fn parse_region_costs(
+    benchmarkable: &dyn Benchmarkable,
+    config: &Config,
+    out_path: &ToolOutputPath,
+) -> Result<Vec<Option<Costs>>> {
+    benchmarkable
+        .regions()
+        .iter()
+        .map(|name| {
+            let region = Region::new(name, out_path);
+            Region::parse_costs(benchmarkable, config, &region.out_path, true)
+        })
+        .collect()
+}
+
+/// Run the `benchmarkable` for each of its regions, print the costs of each region and add them to
+/// the `callgrind_summary`
+///
+/// If `old_costs` is present (`--save-baseline`), these are the costs of the regions of the
+/// previous run parsed with [`parse_region_costs`] before the output files were overwritten.
+/// Otherwise, the costs of each region are compared with the old output file of the region if it
+/// exists. If `run` is false (`--load-baseline`), callgrind isn't run and only the existing output
+/// files are compared. Like the determinism check, each run of a region is prepared with the
+/// `setup` and `teardown` assistants of the `group` (see [`Group::rerun`]).
+#[allow(clippy::too_many_arguments)]
+fn summarize_regions(
+    benchmarkable: &dyn Benchmarkable,
+    config: &Config,
+    group: &Group,
+    out_path: &ToolOutputPath,
+    baselines: &(Option<String>, Option<String>),
+    old_costs: Option<Vec<Option<Costs>>>,
+    run: bool,
+    callgrind_summary: &mut CallgrindSummary,
+) -> Result<()> {
+    if benchmarkable.regions().is_empty() {
+        return Ok(());
+    }
+
+    let executable = benchmarkable.executable(config);
+    let executable_args = benchmarkable.executable_args(config, group);
+    let mut old_costs = old_costs.map(Vec::into_iter);
+    for region in benchmarkable
+        .regions()
+        .iter()
+        .map(|name| Region::new(name, out_path))
+    {
+        let output = if run {
+            Some(group.rerun(config, || {
+                region.run(benchmarkable, config, &executable, &executable_args)
+            })?)
+        } else {
+            None
+        };
+
+        let (old_path, old) = if let Some(old_costs) = old_costs.as_mut() {
+            (region.out_path.clone(), old_costs.next().flatten())
+        } else {
+            let old_path = region.out_path.to_base_path();
+            let old = Region::parse_costs(benchmarkable, config, &old_path, true)?;
+            (old_path, old)
+        };
+        // The new costs of a `--save-baseline` run are never pinned like the costs of the whole
+        // benchmark run
+        let new =
+            Region::parse_costs(benchmarkable, config, &region.out_path, old_costs.is_none())?
+                .ok_or_else(|| {
+                    anyhow!(
+                        "Missing the callgrind output file of the region '{}': '{}'",
+                        region.name,
+                        region.out_path.to_path().display()
+                    )
+                })?;
+
+        let costs_summary = CostsSummary::new(&new, old.as_ref());
+        if is_inline_output(&config.meta) {
+            println!("{}", format_region(&region.name));
+        }
+        VerticalFormat::default().print(&config.meta, baselines.clone(), &costs_summary)?;
+        let (peak_rss, wall_time) = output
+            .as_ref()
+            .map_or((None, None), |output| (output.peak_rss, output.wall_time));
+        print_peak_rss(&config.meta, peak_rss);
+        print_valgrind_time(&config.meta, wall_time);
+
+        let regressions =
+            benchmarkable.check_and_print_regressions(&costs_summary, &region.out_path);
+        let summary = callgrind_summary.add_summary(
+            &executable,
+            &executable_args,
+            &old_path,
+            costs_summary,
+            regressions,
+            output.as_ref(),
+        );
+        summary.region = Some(region.name);
+        if old.is_none() {
+            summary.baseline = None;
+        }
+    }
+
+    callgrind_summary.log_paths = out_path.to_log_output().real_paths()?;
+    callgrind_summary.out_paths = out_path.real_paths()?;
+
+    Ok(())
+}
+
+/// Run only the [`ToolConfigs`] of the `benchmarkable` if its default tool doesn't report costs
+///
+/// Without callgrind or cachegrind, there are no costs, regressions, regions or flamegraphs. The
+/// `run_tools` function runs the tools or loads their output depending on the [`Benchmark`].
+fn run_tools_only<F>(
+    benchmarkable: &dyn Benchmarkable,
+    config: &Config,
+    group: &Group,
+    out_path: &ToolOutputPath,
+    run_tools: F,
+) -> Result<BenchmarkSummary>
+where
+    F: FnOnce() -> Result<Vec<ToolSummary>>,
+{
+    out_path.init()?;
+    let mut benchmark_summary = benchmarkable.create_benchmark_summary(config, group, out_path)?;
+    benchmarkable.print_header(&config.meta, group);
+    benchmark_summary.tool_summaries = run_tools()?;
+
+    Ok(benchmark_summary)
+}
+
+/// Run the `benchmarkable` a second time and compare the primary event kind if
+/// `--check-determinism` is set
+///
+/// The second run of a benchmark is prepared with the `setup` and `teardown` assistants of the
+/// `group` like the first run (see [`Group::rerun`]). Returns `None` if the determinism check is
+/// disabled.
+fn run_determinism_check(
+    benchmarkable: &dyn Benchmarkable,
+    config: &Config,
+    group: &Group,
+    out_path: &ToolOutputPath,
+) -> Result<Option<bool>> {
+    if !config.meta.args.check_determinism.unwrap_or_default() {
+        return Ok(None);
+    }
+
+    let costs = benchmarkable.parse_costs(config, out_path)?;
+    let event_kind = config.meta.primary_event_kind();
+    check_determinism(out_path, &costs, &event_kind, |path| {
+        let run = || {
+            CallgrindCommand::new(&config.meta, benchmarkable.tool()).run(
+                benchmarkable.callgrind_args().clone(),
+                &benchmarkable.executable(config),
+                &benchmarkable.executable_args(config, group),
+                benchmarkable.run_options(config),
+                path,
+            )
+        };
+        if benchmarkable.is_assistant() {
+            run()?;
+        } else {
+            group.rerun(config, run)?;
+        }
+        benchmarkable.parse_costs(config, path)
+    })
+    .map(Some)
+}
+
+pub fn run(binary_benchmark: BinaryBenchmark, config: Config) -> Result<RunOutcome> {
     Runner::new(binary_benchmark, config)?.run()
 }
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+    use rstest::rstest;
+
+    use super::*;
+
+    fn tool_output_path(dir: &Path) -> ToolOutputPath {
+        ToolOutputPath::new(
+            ToolOutputPathKind::Out,
+            ValgrindTool::Callgrind,
+            &BaselineKind::Old,
+            dir,
+            dir,
+            "my_bench::my_group",
+            "my-exe.foo",
+        )
+    }
+
+    #[rstest]
+    #[case::simple("main", "callgrind.my-exe.foo.out.region.main")]
+    #[case::path("my_exe::parse", "callgrind.my-exe.foo.out.region.my_exe__parse")]
+    #[case::wildcard("*::parse*", "callgrind.my-exe.foo.out.region.___parse_")]
+    fn test_region_new(#[case] name: &str, #[case] expected: &str) {
+        let region = Region::new(name, &tool_output_path(Path::new("/tmp")));
+
+        assert_eq!(region.name, name);
+        assert_eq!(
+            region.out_path.to_path().file_name().unwrap().to_str(),
+            Some(expected)
+        );
+    }
+
+    #[rstest]
+    #[case::empty(&[], &[])]
+    #[case::unique(&["main", "my_exe::parse"], &["main", "my_exe::parse"])]
+    #[case::duplicate(&["main", "foo", "main"], &["main", "foo"])]
+    fn test_region_dedup(#[case] regions: &[&str], #[case] expected: &[&str]) {
+        let regions = regions.iter().map(|r| (*r).to_owned()).collect::<Vec<_>>();
+        assert_eq!(Region::dedup(&regions).unwrap(), expected);
+    }
+
+    #[test]
+    fn test_region_dedup_when_same_modifier_then_error() {
+        let regions = vec!["main".to_owned(), "a::b".to_owned(), "a__b".to_owned()];
+        let error = Region::dedup(&regions).unwrap_err();
+        assert!(error.to_string().contains("'a::b' and 'a__b'"));
+    }
+
+    #[test]
+    fn test_region_output_is_shifted_with_benchmark_output() {
+        let dir = tempfile::tempdir().unwrap();
+        let out_path = tool_output_path(dir.path());
+        out_path.init().unwrap();
+        let region = Region::new("my_exe::parse", &out_path);
+        std::fs::write(out_path.to_path(), "").unwrap();
+        std::fs::write(region.out_path.to_path(), "").unwrap();
+
+        out_path.shift().unwrap();
+
+        assert!(!region.out_path.to_path().exists());
+        assert!(region.out_path.to_base_path().to_path().exists());
+        assert!(out_path.to_base_path().to_path().exists());
+    }
+}
@@ -0,0 +1,239 @@
+use std::ffi::OsStr;
+use std::fs::File;
+use std::path::PathBuf;
+
+use anyhow::{Context, Result};
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use glob::{glob, Pattern};
+use log::debug;
+
+use super::meta::Metadata;
+
+/// The `Bundle` of the output files of a benchmark run which is written to a `.tar.gz` archive
+/// with `--bundle`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Bundle {
+    /// The path to the `.tar.gz` archive
+    pub path: PathBuf,
+    /// The directory with the files to bundle. The paths in the archive are relative to it.
+    pub dir: PathBuf,
+    /// If true, include the raw output and log files of the valgrind tools
+    pub raw: bool,
+    /// If true, include the flamegraph svg files and the html pages with the flamegraphs side by
+    /// side
+    pub svg: bool,
+}
+
+impl Bundle {
+    /// Create a new `Bundle` if `--bundle` was given and the benchmarks are not only listed
+    ///
+    /// The bundle contains the files in the `target/iai` directory of the package. A relative
+    /// path of the archive is resolved against the project root.
+    pub fn from_meta(meta: &Metadata) -> Option<Self> {
+        if meta.args.list.unwrap_or_default() {
+            return None;
+        }
+
+        meta.args.bundle.as_ref().map(|path| Self {
+            path: meta.project_root.join(path),
+            dir: meta.target_dir.clone(),
+            raw: meta.args.bundle_raw.unwrap_or(true),
+            svg: meta.args.bundle_svg.unwrap_or(true),
+        })
+    }
+
+    /// Return the sorted paths of all files in the `dir` which are included in this bundle
+    ///
+    /// The paths are relative to the `dir`. Json files like the `summary.json` files are always
+    /// included, svg and html files only if `svg` is true and all other files only if `raw` is
+    /// true.
+    pub fn files(&self) -> Result<Vec<PathBuf>> {
+        let pattern = PathBuf::from(Pattern::escape(&self.dir.to_string_lossy()))
+            .join("**")
+            .join("*");
+        let mut files = vec![];
+        for entry in glob(&pattern.to_string_lossy()).expect("Glob pattern should be valid") {
+            let path = entry?;
+            if !path.is_file() || path == self.path {
+                continue;
+            }
+
+            let is_included = match path.extension().and_then(OsStr::to_str) {
+                Some("json") => true,
+                Some("svg" | "html") => self.svg,
+                _ => self.raw,
+            };
+            if is_included {
+                files.push(
+                    path.strip_prefix(&self.dir)
+                        .expect("The file should be in the bundle directory")
+                        .to_path_buf(),
+                );
+            }
+        }
+
+        files.sort();
+        Ok(files)
+    }
+
+    /// Create the `.tar.gz` archive of this bundle
+    ///
+    /// An existing archive is overwritten.
+    ///
+    /// # Errors
+    ///
+    /// If creating the archive or adding a file to it fails
+    pub fn create(&self) -> Result<()> {
+        let files = self.files()?;
+        if let Some(parent) = self.path.parent() {
+            std::fs::create_dir_all(parent)
+                .with_context(|| format!("Failed to create directory '{}'", parent.display()))?;
+        }
+
+        debug!(
+            "Bundling {} files of '{}' into '{}'",
+            files.len(),
+            self.dir.display(),
+            self.path.display()
+        );
+
+        let add_context = || format!("Failed to create the bundle '{}'", self.path.display());
+        let file = File::create(&self.path).with_context(add_context)?;
+        let mut builder = tar::Builder::new(GzEncoder::new(file, Compression::default()));
+        for file in &files {
+            builder
+                .append_path_with_name(self.dir.join(file), file)
+                .with_context(|| {
+                    format!(
+                        "Failed to add '{}' to the bundle '{}'",
+                        file.display(),
+                        self.path.display()
+                    )
+                })?;
+        }
+        builder
+            .into_inner()
+            .and_then(GzEncoder::finish)
+            .with_context(add_context)?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use flate2::read::GzDecoder;
+    use pretty_assertions::assert_eq;
+    use rstest::rstest;
+
+    use super::*;
+
+    fn create_files(dir: &Path) {
+        for file in [
+            "bench/group/func/callgrind.func.out",
+            "bench/group/func/callgrind.func.out.old",
+            "bench/group/func/callgrind.func.log",
+            "bench/group/func/callgrind.func.Ir.flamegraph.svg",
+            "bench/group/func/callgrind.func.flamegraph.Ir.diff.old.html",
+            "bench/group/func/summary.json",
+            "bench/regressions.json",
+        ] {
+            let path = dir.join(file);
+            std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+            std::fs::write(path, file).unwrap();
+        }
+    }
+
+    #[rstest]
+    #[case::all(
+        true,
+        true,
+        &[
+            "bench/group/func/callgrind.func.Ir.flamegraph.svg",
+            "bench/group/func/callgrind.func.flamegraph.Ir.diff.old.html",
+            "bench/group/func/callgrind.func.log",
+            "bench/group/func/callgrind.func.out",
+            "bench/group/func/callgrind.func.out.old",
+            "bench/group/func/summary.json",
+            "bench/regressions.json",
+        ]
+    )]
+    #[case::no_raw(
+        false,
+        true,
+        &[
+            "bench/group/func/callgrind.func.Ir.flamegraph.svg",
+            "bench/group/func/callgrind.func.flamegraph.Ir.diff.old.html",
+            "bench/group/func/summary.json",
+            "bench/regressions.json",
+        ]
+    )]
+    #[case::no_svg(
+        true,
+        false,
+        &[
+            "bench/group/func/callgrind.func.log",
+            "bench/group/func/callgrind.func.out",
+            "bench/group/func/callgrind.func.out.old",
+            "bench/group/func/summary.json",
+            "bench/regressions.json",
+        ]
+    )]
+    #[case::json_only(
+        false,
+        false,
+        &["bench/group/func/summary.json", "bench/regressions.json"]
+    )]
+    fn test_bundle_files(#[case] raw: bool, #[case] svg: bool, #[case] expected: &[&str]) {
+        let dir = tempfile::tempdir().unwrap();
+        create_files(dir.path());
+        let bundle = Bundle {
+            path: dir.path().join("bundle.tar.gz"),
+            dir: dir.path().to_path_buf(),
+            raw,
+            svg,
+        };
+
+        assert_eq!(
+            bundle.files().unwrap(),
+            expected.iter().map(PathBuf::from).collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn test_bundle_create() {
+        let dir = tempfile::tempdir().unwrap();
+        create_files(&dir.path().join("iai"));
+        let bundle = Bundle {
+            path: dir.path().join("artifacts").join("bundle.tar.gz"),
+            dir: dir.path().join("iai"),
+            raw: false,
+            svg: true,
+        };
+
+        bundle.create().unwrap();
+
+        let mut archive = tar::Archive::new(GzDecoder::new(File::open(&bundle.path).unwrap()));
+        let mut actual = archive
+            .entries()
+            .unwrap()
+            .map(|entry| {
+                let entry = entry.unwrap();
+                entry.path().unwrap().to_string_lossy().into_owned()
+            })
+            .collect::<Vec<_>>();
+        actual.sort();
+        assert_eq!(
+            actual,
+            vec![
+                "bench/group/func/callgrind.func.Ir.flamegraph.svg",
+                "bench/group/func/callgrind.func.flamegraph.Ir.diff.old.html",
+                "bench/group/func/summary.json",
+                "bench/regressions.json",
+            ]
+        );
+    }
+}
@@ -0,0 +1,63 @@
+use anyhow::Result;
+use log::{debug, trace};
+
+use crate::error::Error;
+use crate::runner::callgrind::model::Costs;
+use crate::runner::tool::{Parser, ToolOutputPath, ToolParser, ValgrindTool};
+
+/// Parse the `summary:` line of a cachegrind output file into [`Costs`]
+///
+/// Cachegrind doesn't collect a call graph, so the summary line is the only interesting
+/// information. The events are taken from the `events:` line of the header.
+pub struct SummaryParser;
+
+impl Parser for SummaryParser {
+    type Output = Costs;
+
+    fn parse(&self, output_path: &ToolOutputPath) -> Result<Self::Output>
+    where
+        Self: std::marker::Sized,
+    {
+        debug!(
+            "Parsing cachegrind output file '{}' for a summary",
+            output_path
+        );
+
+        let mut costs: Option<Costs> = None;
+        for (index, line) in output_path.lines()?.enumerate() {
+            if let Some(stripped) = line.strip_prefix("events:") {
+                trace!("Using events from line: '{line}'");
+                costs = Some(stripped.split_ascii_whitespace().collect());
+            } else if let Some(stripped) = line.strip_prefix("summary:") {
+                trace!("Found line with summary: '{}'", line);
+                let mut costs = costs.ok_or_else(|| {
+                    Error::ParseError((
+                        output_path.to_path(),
+                        "Header field 'events' must be present before the summary".to_owned(),
+                    ))
+                })?;
+                costs
+                    .try_add_iter_str(stripped.split_ascii_whitespace())
+                    .map_err(|error| {
+                        Error::ParseLineError((output_path.to_path(), index + 1, error.to_string()))
+                    })?;
+                trace!("Updated counters to:\n{costs}");
+                return Ok(costs);
+            } else {
+                // ignore all other lines
+            }
+        }
+
+        Err(Error::ParseError((output_path.to_path(), "No summary line found".to_owned())).into())
+    }
+}
+
+impl ToolParser for SummaryParser {
+    fn tool(&self) -> ValgrindTool {
+        ValgrindTool::Cachegrind
+    }
+
+    fn parse_costs(&self, output_path: &ToolOutputPath) -> Result<Costs> {
+        self.parse(output_path)
+    }
+}
@@ -2,12 +2,14 @@ use std::collections::VecDeque;
 use std::ffi::OsString;
 use std::path::{Path, PathBuf};
 
-use anyhow::Result;
+use anyhow::{anyhow, Result};
 use log::{log_enabled, warn};
 
-use crate::api::RawArgs;
+use super::flamegraph::Config as FlamegraphConfig;
+use super::RegressionConfig;
+use crate::api::{EventKind, FlamegraphKind, RawArgs};
 use crate::error::Error;
-use crate::runner::tool::ToolOutputPath;
+use crate::runner::tool::{ToolOutputPath, ValgrindTool};
 use crate::util::{bool_to_yesno, yesno_to_bool};
 
 #[allow(clippy::struct_excessive_bools)]
@@ -17,6 +19,12 @@ pub struct Args {
     d1: String,
     ll: String,
     cache_sim: bool,
+    branch_sim: Option<bool>,
+    collect_bus: Option<bool>,
+    collect_systime: Option<String>,
+    simulate_wb: Option<bool>,
+    cacheuse: Option<bool>,
+    separate_callers: Option<u64>,
     pub(crate) collect_atstart: bool,
     other: Vec<String>,
     toggle_collect: VecDeque<String>,
@@ -37,6 +45,7 @@ impl Args {
         Ok(default)
     }
 
+    #[allow(clippy::too_many_lines)]
     pub fn update<'a, T: Iterator<Item = &'a String>>(&mut self, args: T) -> Result<()> {
         for arg in args {
             match arg
@@ -47,6 +56,42 @@ impl Args {
                 Some(("--I1", value)) => value.clone_into(&mut self.i1),
                 Some(("--D1", value)) => value.clone_into(&mut self.d1),
                 Some(("--LL", value)) => value.clone_into(&mut self.ll),
+                Some((key @ "--cache-sim", value)) => {
+                    self.cache_sim = yesno_to_bool(value).ok_or_else(|| {
+                        Error::InvalidCallgrindBoolArgument((key.to_owned(), value.to_owned()))
+                    })?;
+                }
+                Some((key @ "--branch-sim", value)) => {
+                    self.branch_sim = Some(yesno_to_bool(value).ok_or_else(|| {
+                        Error::InvalidCallgrindBoolArgument((key.to_owned(), value.to_owned()))
+                    })?);
+                }
+                Some((key @ "--collect-bus", value)) => {
+                    self.collect_bus = Some(yesno_to_bool(value).ok_or_else(|| {
+                        Error::InvalidCallgrindBoolArgument((key.to_owned(), value.to_owned()))
+                    })?);
+                }
+                Some(("--collect-systime", value)) => {
+                    self.collect_systime = Some(value.to_owned());
+                }
+                Some((key @ "--simulate-wb", value)) => {
+                    self.simulate_wb = Some(yesno_to_bool(value).ok_or_else(|| {
+                        Error::InvalidCallgrindBoolArgument((key.to_owned(), value.to_owned()))
+                    })?);
+                }
+                Some((key @ "--cacheuse", value)) => {
+                    self.cacheuse = Some(yesno_to_bool(value).ok_or_else(|| {
+                        Error::InvalidCallgrindBoolArgument((key.to_owned(), value.to_owned()))
+                    })?);
+                }
+                Some((key @ "--separate-callers", value)) => {
+                    self.separate_callers = Some(value.parse().map_err(|_| {
+                        anyhow!(
+                            "Invalid value for callgrind argument '{key}': Expected the number of \
+                             callers but found '{value}'"
+                        )
+                    })?);
+                }
                 Some((key @ "--collect-atstart", value)) => {
                     self.collect_atstart = yesno_to_bool(value).ok_or_else(|| {
                         Error::InvalidCallgrindBoolArgument((key.to_owned(), value.to_owned()))
@@ -111,6 +156,10 @@ impl Args {
         self.toggle_collect.push_front(arg.to_owned());
     }
 
+    pub fn insert_zero_before(&mut self, arg: &str) {
+        self.other.push(format!("--zero-before={arg}"));
+    }
+
     pub fn set_output_file<T>(&mut self, arg: T)
     where
         T: AsRef<Path>,
@@ -125,6 +174,224 @@ impl Args {
         self.log_arg = Some(arg);
     }
 
+    /// Return the callgrind argument needed to collect the [`EventKind`] if it is not collected
+    /// with these arguments
+    ///
+    /// A derived [`EventKind`] is collected if all the events it [`EventKind::requires`] are
+    /// collected.
+    pub fn missing_collect_arg(&self, event_kind: &EventKind) -> Option<&'static str> {
+        use EventKind::*;
+
+        let is_enabled = |value: Option<bool>| value.unwrap_or(false);
+        let collect_systime = self
+            .collect_systime
+            .as_deref()
+            .map_or("no", |value| value.trim());
+
+        if event_kind.is_derived() {
+            return event_kind
+                .requires()
+                .iter()
+                .find_map(|required| self.missing_collect_arg(required));
+        }
+
+        match event_kind {
+            Dr | Dw | I1mr | D1mr | D1mw | ILmr | DLmr | DLmw if !self.cache_sim => {
+                Some("--cache-sim=yes")
+            }
+            SysCount | SysTime if collect_systime == "no" => Some("--collect-systime=yes"),
+            SysCpuTime if collect_systime != "nsec" => Some("--collect-systime=nsec"),
+            Ge if !is_enabled(self.collect_bus) => Some("--collect-bus=yes"),
+            Bc | Bcm | Bi | Bim if !is_enabled(self.branch_sim) => Some("--branch-sim=yes"),
+            ILdmr | DLdmr | DLdmw if !self.cache_sim || !is_enabled(self.simulate_wb) => {
+                Some("--cache-sim=yes --simulate-wb=yes")
+            }
+            AcCost1 | AcCost2 | SpLoss1 | SpLoss2
+                if !self.cache_sim || !is_enabled(self.cacheuse) =>
+            {
+                Some("--cache-sim=yes --cacheuse=yes")
+            }
+            _ => None,
+        }
+    }
+
+    /// Check that all [`EventKind`]s which are used by the regression checks and flamegraphs are
+    /// collected by callgrind with these arguments
+    ///
+    /// The event kinds of the flamegraphs include the `color_event_kind`.
+    ///
+    /// # Errors
+    ///
+    /// Return an error naming the first event kind which is not collected and the callgrind
+    /// argument needed to collect it
+    pub fn check_collectible(
+        &self,
+        regression_config: Option<&RegressionConfig>,
+        flamegraph_config: Option<&FlamegraphConfig>,
+    ) -> Result<()> {
+        let regression_event_kinds = regression_config
+            .into_iter()
+            .flat_map(|config| config.limits.iter().map(|(event_kind, _)| event_kind))
+            .map(|event_kind| (event_kind, "regression check"));
+        let score_event_kinds = regression_config
+            .into_iter()
+            .flat_map(|config| config.score.iter().map(|(event_kind, _)| event_kind))
+            .map(|event_kind| (event_kind, "score"));
+        let flamegraph_config =
+            flamegraph_config.filter(|config| config.kind != FlamegraphKind::None);
+        let flamegraph_event_kinds = flamegraph_config
+            .into_iter()
+            .flat_map(|config| config.event_kinds.iter())
+            .map(|event_kind| (event_kind, "flamegraph"))
+            .chain(
+                flamegraph_config
+                    .and_then(|config| config.color_event_kind.as_ref())
+                    .map(|event_kind| (event_kind, "flamegraph colors")),
+            );
+
+        if flamegraph_event_kinds
+            .clone()
+            .any(|(event_kind, _)| *event_kind == EventKind::Score)
+        {
+            return Err(anyhow!(
+                "The event kind '{}' is not available in flamegraphs",
+                EventKind::Score
+            ));
+        }
+
+        for (event_kind, usage) in regression_event_kinds
+            .chain(score_event_kinds)
+            .chain(flamegraph_event_kinds)
+        {
+            if let Some(arg) = self.missing_collect_arg(event_kind) {
+                return Err(anyhow!(
+                    "The event kind '{event_kind}' of the {usage} is not collected by callgrind. \
+                     Collecting this event kind requires the callgrind argument(s) '{arg}'"
+                ));
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Check that all [`EventKind`]s which are used by the regression checks are collected by
+    /// cachegrind with these arguments
+    ///
+    /// Cachegrind can only collect the instructions, the cache simulation events and the branch
+    /// simulation events (and the events derived from them).
+    ///
+    /// # Errors
+    ///
+    /// Return an error naming the first event kind which is not collected
+    pub fn check_cachegrind_collectible(
+        &self,
+        regression_config: Option<&RegressionConfig>,
+    ) -> Result<()> {
+        for event_kind in regression_config.into_iter().flat_map(|config| {
+            config
+                .limits
+                .iter()
+                .chain(config.score.iter())
+                .map(|(event_kind, _)| event_kind)
+        }) {
+            if !is_cachegrind_event_kind(*event_kind) {
+                return Err(anyhow!(
+                    "The event kind '{event_kind}' of the regression check cannot be collected by \
+                     cachegrind"
+                ));
+            }
+            if let Some(arg) = self.missing_collect_arg(event_kind) {
+                return Err(anyhow!(
+                    "The event kind '{event_kind}' of the regression check is not collected by \
+                     cachegrind. Collecting this event kind requires the argument(s) '{arg}'"
+                ));
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Check that the primary [`EventKind`] of `--primary-event` is collected by the `tool` with
+    /// these arguments
+    ///
+    /// The [`EventKind::Score`] is only available if the `regression_config` has the weights of
+    /// the score. Tools other than callgrind and cachegrind have no costs, so there is nothing to
+    /// check.
+    ///
+    /// # Errors
+    ///
+    /// Return an error if the primary event kind is not collected
+    pub fn check_primary_collectible(
+        &self,
+        tool: ValgrindTool,
+        event_kind: &EventKind,
+        regression_config: Option<&RegressionConfig>,
+    ) -> Result<()> {
+        let tool_id = match tool {
+            ValgrindTool::Callgrind | ValgrindTool::Cachegrind => tool.id(),
+            _ => return Ok(()),
+        };
+        if *event_kind == EventKind::Score {
+            return if regression_config.map_or(false, |config| !config.score.is_empty()) {
+                Ok(())
+            } else {
+                Err(anyhow!(
+                    "The primary event kind '{event_kind}' requires the weights of the score in \
+                     the RegressionConfig"
+                ))
+            };
+        }
+        if tool == ValgrindTool::Cachegrind && !is_cachegrind_event_kind(*event_kind) {
+            return Err(anyhow!(
+                "The primary event kind '{event_kind}' cannot be collected by cachegrind"
+            ));
+        }
+        if let Some(arg) = self.missing_collect_arg(event_kind) {
+            return Err(anyhow!(
+                "The primary event kind '{event_kind}' is not collected by {tool_id}. Collecting \
+                 this event kind requires the argument(s) '{arg}'"
+            ));
+        }
+
+        Ok(())
+    }
+
+    /// Convert these arguments into the arguments of a cachegrind run
+    ///
+    /// Cachegrind understands only the cache and branch simulation arguments of callgrind. The
+    /// other callgrind specific arguments (like `--toggle-collect`) are dropped. All unknown
+    /// arguments are passed to cachegrind as they are.
+    pub fn to_cachegrind_vec(&self) -> Vec<String> {
+        let mut args = vec![
+            format!("--I1={}", &self.i1),
+            format!("--D1={}", &self.d1),
+            format!("--LL={}", &self.ll),
+            format!("--cache-sim={}", bool_to_yesno(self.cache_sim)),
+        ];
+
+        if let Some(branch_sim) = self.branch_sim {
+            args.push(format!("--branch-sim={}", bool_to_yesno(branch_sim)));
+        }
+
+        if self.verbose {
+            args.push(String::from("--verbose"));
+        }
+
+        if let Some(output_file) = &self.callgrind_out_file {
+            args.push(format!(
+                "--cachegrind-out-file={}",
+                output_file.to_string_lossy(),
+            ));
+        }
+
+        if let Some(log_arg) = &self.log_arg {
+            args.push(log_arg.to_string_lossy().to_string());
+        }
+
+        args.extend_from_slice(self.other.as_slice());
+        args
+    }
+
     pub fn to_vec(&self) -> Vec<String> {
         let mut args = vec![
             format!("--I1={}", &self.i1),
@@ -142,6 +409,25 @@ impl Args {
             format!("--combine-dumps={}", bool_to_yesno(self.combine_dumps)),
         ];
 
+        if let Some(branch_sim) = self.branch_sim {
+            args.push(format!("--branch-sim={}", bool_to_yesno(branch_sim)));
+        }
+        if let Some(collect_bus) = self.collect_bus {
+            args.push(format!("--collect-bus={}", bool_to_yesno(collect_bus)));
+        }
+        if let Some(collect_systime) = &self.collect_systime {
+            args.push(format!("--collect-systime={collect_systime}"));
+        }
+        if let Some(simulate_wb) = self.simulate_wb {
+            args.push(format!("--simulate-wb={}", bool_to_yesno(simulate_wb)));
+        }
+        if let Some(cacheuse) = self.cacheuse {
+            args.push(format!("--cacheuse={}", bool_to_yesno(cacheuse)));
+        }
+        if let Some(separate_callers) = self.separate_callers {
+            args.push(format!("--separate-callers={separate_callers}"));
+        }
+
         if self.verbose {
             args.push(String::from("--verbose"));
         }
@@ -180,6 +466,12 @@ impl Default for Args {
             d1: String::from("32768,8,64"),
             ll: String::from("8388608,16,64"),
             cache_sim: true,
+            branch_sim: Option::default(),
+            collect_bus: Option::default(),
+            collect_systime: Option::default(),
+            simulate_wb: Option::default(),
+            cacheuse: Option::default(),
+            separate_callers: Option::default(),
             collect_atstart: false,
             compress_pos: false,
             compress_strings: false,
@@ -194,3 +486,340 @@ impl Default for Args {
         }
     }
 }
+
+/// Return true if cachegrind can collect the [`EventKind`]
+///
+/// Cachegrind can only collect the instructions, the cache simulation events and the branch
+/// simulation events (and the events derived from them).
+fn is_cachegrind_event_kind(event_kind: EventKind) -> bool {
+    use EventKind::*;
+
+    matches!(
+        event_kind,
+        Ir | Dr | Dw | I1mr | D1mr | D1mw | ILmr | DLmr | DLmw | Bc | Bcm | Bi | Bim | Score
+    ) || event_kind.is_derived()
+}
+
+#[cfg(test)]
+mod tests {
+    use rstest::rstest;
+    use EventKind::*;
+
+    use super::*;
+
+    fn args_from(args: &[&str]) -> Args {
+        Args::from_raw_args(&[&RawArgs::new(args.iter().map(|s| (*s).to_owned()).collect())])
+            .unwrap()
+    }
+
+    #[rstest]
+    #[case::ir_default(&[], Ir, None)]
+    #[case::ir_no_cache_sim(&["--cache-sim=no"], Ir, None)]
+    #[case::dr_default(&[], Dr, None)]
+    #[case::dr_no_cache_sim(&["--cache-sim=no"], Dr, Some("--cache-sim=yes"))]
+    #[case::estimated_cycles_no_cache_sim(
+        &["--cache-sim=no"],
+        EstimatedCycles,
+        Some("--cache-sim=yes")
+    )]
+    #[case::estimated_cycles_default(&[], EstimatedCycles, None)]
+    #[case::ram_hits_no_cache_sim(&["--cache-sim=no"], RamHits, Some("--cache-sim=yes"))]
+    #[case::sys_count_default(&[], SysCount, Some("--collect-systime=yes"))]
+    #[case::sys_count_systime(&["--collect-systime=msec"], SysCount, None)]
+    #[case::sys_cpu_time_systime_yes(
+        &["--collect-systime=yes"],
+        SysCpuTime,
+        Some("--collect-systime=nsec")
+    )]
+    #[case::sys_cpu_time_systime_nsec(&["--collect-systime=nsec"], SysCpuTime, None)]
+    #[case::ge_default(&[], Ge, Some("--collect-bus=yes"))]
+    #[case::ge_collect_bus(&["--collect-bus=yes"], Ge, None)]
+    #[case::bc_default(&[], Bc, Some("--branch-sim=yes"))]
+    #[case::bc_branch_sim_no(&["--branch-sim=no"], Bc, Some("--branch-sim=yes"))]
+    #[case::bc_branch_sim(&["--branch-sim=yes"], Bc, None)]
+    #[case::ildmr_default(&[], ILdmr, Some("--cache-sim=yes --simulate-wb=yes"))]
+    #[case::ildmr_simulate_wb(&["--simulate-wb=yes"], ILdmr, None)]
+    #[case::ildmr_no_cache_sim(
+        &["--simulate-wb=yes", "--cache-sim=no"],
+        ILdmr,
+        Some("--cache-sim=yes --simulate-wb=yes")
+    )]
+    #[case::ac_cost1_default(&[], AcCost1, Some("--cache-sim=yes --cacheuse=yes"))]
+    #[case::ac_cost1_cacheuse(&["--cacheuse=yes"], AcCost1, None)]
+    fn test_missing_collect_arg(
+        #[case] args: &[&str],
+        #[case] event_kind: EventKind,
+        #[case] expected: Option<&str>,
+    ) {
+        assert_eq!(args_from(args).missing_collect_arg(&event_kind), expected);
+    }
+
+    #[rstest]
+    #[case::default(&[], &["--cache-sim=yes"])]
+    #[case::no_cache_sim(&["--cache-sim=no"], &["--cache-sim=no"])]
+    #[case::collection_args(
+        &["--branch-sim=yes", "--collect-bus=no", "--collect-systime=nsec"],
+        &["--cache-sim=yes", "--branch-sim=yes", "--collect-bus=no", "--collect-systime=nsec"]
+    )]
+    fn test_to_vec_collection_args(#[case] args: &[&str], #[case] expected: &[&str]) {
+        let args = args_from(args).to_vec();
+        let collection_args = args
+            .iter()
+            .filter(|arg| {
+                [
+                    "--cache-sim=",
+                    "--branch-sim=",
+                    "--collect-bus=",
+                    "--collect-systime=",
+                    "--simulate-wb=",
+                    "--cacheuse=",
+                ]
+                .iter()
+                .any(|prefix| arg.starts_with(prefix))
+            })
+            .map(String::as_str)
+            .collect::<Vec<&str>>();
+
+        assert_eq!(collection_args, expected);
+    }
+
+    #[rstest]
+    #[case::default(&[], None)]
+    #[case::zero(&["--separate-callers=0"], Some("--separate-callers=0"))]
+    #[case::two(&["--separate-callers=2"], Some("--separate-callers=2"))]
+    #[case::last_wins(
+        &["--separate-callers=2", "--separate-callers=3"],
+        Some("--separate-callers=3")
+    )]
+    fn test_to_vec_separate_callers(#[case] args: &[&str], #[case] expected: Option<&str>) {
+        let args = args_from(args).to_vec();
+        let separate_callers = args
+            .iter()
+            .filter(|arg| arg.starts_with("--separate-callers"))
+            .map(String::as_str)
+            .collect::<Vec<&str>>();
+
+        assert_eq!(separate_callers, expected.into_iter().collect::<Vec<_>>());
+    }
+
+    #[rstest]
+    #[case::not_a_number("--separate-callers=many")]
+    #[case::negative("--separate-callers=-1")]
+    #[case::empty("--separate-callers=")]
+    fn test_update_when_invalid_separate_callers(#[case] arg: &str) {
+        let result = Args::from_raw_args(&[&RawArgs::new(vec![arg.to_owned()])]);
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_update_when_invalid_bool_argument() {
+        let result = Args::from_raw_args(&[&RawArgs::new(vec!["--branch-sim=maybe".to_owned()])]);
+        assert!(result.is_err());
+    }
+
+    #[rstest]
+    #[case::no_configs(&[], None, None, true)]
+    #[case::regression_ok(&[], Some(vec![Ir, Dr]), None, true)]
+    #[case::regression_not_collected(&[], Some(vec![Ir, Bc]), None, false)]
+    #[case::flamegraph_ok(&["--branch-sim=yes"], None, Some(vec![Bc]), true)]
+    #[case::flamegraph_not_collected(&["--cache-sim=no"], None, Some(vec![Ir, Dr]), false)]
+    #[case::data_rw_ok(&[], Some(vec![Dr, Dw, DataRW]), Some(vec![DataRW]), true)]
+    #[case::data_rw_not_collected(&["--cache-sim=no"], Some(vec![DataRW]), None, false)]
+    fn test_check_collectible(
+        #[case] args: &[&str],
+        #[case] regression_event_kinds: Option<Vec<EventKind>>,
+        #[case] flamegraph_event_kinds: Option<Vec<EventKind>>,
+        #[case] expected: bool,
+    ) {
+        let regression_config = regression_event_kinds.map(|event_kinds| RegressionConfig {
+            limits: event_kinds.into_iter().map(|e| (e, 0f64)).collect(),
+            min_abs_diffs: vec![],
+            fail_fast: false,
+            score: vec![],
+        });
+        let flamegraph_config = flamegraph_event_kinds.map(|event_kinds| {
+            FlamegraphConfig::try_from(crate::api::FlamegraphConfig {
+                event_kinds: Some(event_kinds),
+                ..Default::default()
+            })
+            .unwrap()
+        });
+
+        assert_eq!(
+            args_from(args)
+                .check_collectible(regression_config.as_ref(), flamegraph_config.as_ref())
+                .is_ok(),
+            expected
+        );
+    }
+
+    #[rstest]
+    #[case::no_config(&[], None, true)]
+    #[case::cache_sim_and_derived(&[], Some(vec![Ir, Dr, EstimatedCycles]), true)]
+    #[case::no_cache_sim(&["--cache-sim=no"], Some(vec![Dr]), false)]
+    #[case::branch_sim(&["--branch-sim=yes"], Some(vec![Bc]), true)]
+    #[case::no_branch_sim(&[], Some(vec![Bc]), false)]
+    #[case::not_available(&["--collect-bus=yes"], Some(vec![Ge]), false)]
+    #[case::not_available_systime(&["--collect-systime=yes"], Some(vec![SysCount]), false)]
+    fn test_check_cachegrind_collectible(
+        #[case] args: &[&str],
+        #[case] regression_event_kinds: Option<Vec<EventKind>>,
+        #[case] expected: bool,
+    ) {
+        let regression_config = regression_event_kinds.map(|event_kinds| RegressionConfig {
+            limits: event_kinds.into_iter().map(|e| (e, 0f64)).collect(),
+            min_abs_diffs: vec![],
+            fail_fast: false,
+            score: vec![],
+        });
+
+        assert_eq!(
+            args_from(args)
+                .check_cachegrind_collectible(regression_config.as_ref())
+                .is_ok(),
+            expected
+        );
+    }
+
+    #[rstest]
+    #[case::ir(ValgrindTool::Callgrind, &[], Ir, None, true)]
+    #[case::collected(ValgrindTool::Callgrind, &[], Dr, None, true)]
+    #[case::not_collected(ValgrindTool::Callgrind, &["--cache-sim=no"], Dr, None, false)]
+    #[case::cachegrind(ValgrindTool::Cachegrind, &[], Dr, None, true)]
+    #[case::cachegrind_ge(ValgrindTool::Cachegrind, &["--collect-bus=yes"], Ge, None, false)]
+    #[case::score_without_weights(ValgrindTool::Callgrind, &[], Score, None, false)]
+    #[case::score_with_weights(ValgrindTool::Callgrind, &[], Score, Some(vec![(Ir, 1f64)]), true)]
+    #[case::other_tool(ValgrindTool::DHAT, &["--cache-sim=no"], Dr, None, true)]
+    fn test_check_primary_collectible(
+        #[case] tool: ValgrindTool,
+        #[case] args: &[&str],
+        #[case] event_kind: EventKind,
+        #[case] score: Option<Vec<(EventKind, f64)>>,
+        #[case] expected: bool,
+    ) {
+        let regression_config = score.map(|score| RegressionConfig {
+            limits: vec![],
+            min_abs_diffs: vec![],
+            fail_fast: false,
+            score,
+        });
+
+        assert_eq!(
+            args_from(args)
+                .check_primary_collectible(tool, &event_kind, regression_config.as_ref())
+                .is_ok(),
+            expected
+        );
+    }
+
+    #[test]
+    fn test_to_cachegrind_vec() {
+        let mut args = args_from(&[
+            "--branch-sim=yes",
+            "--collect-bus=yes",
+            "--toggle-collect=something",
+            "--dump-instr=yes",
+            "--separate-callers=2",
+        ]);
+        args.verbose = false;
+        args.set_output_file("some.out");
+
+        assert_eq!(
+            args.to_cachegrind_vec(),
+            vec![
+                "--I1=32768,8,64",
+                "--D1=32768,8,64",
+                "--LL=8388608,16,64",
+                "--cache-sim=yes",
+                "--branch-sim=yes",
+                "--cachegrind-out-file=some.out",
+            ]
+        );
+    }
+
+    #[rstest]
+    #[case::collected(&[], vec![(Ir, 1f64), (EstimatedCycles, 10f64)], true)]
+    #[case::not_collected(&[], vec![(Ir, 1f64), (Bc, 10f64)], false)]
+    #[case::branch_sim(&["--branch-sim=yes"], vec![(Bc, 10f64)], true)]
+    fn test_check_collectible_of_score(
+        #[case] args: &[&str],
+        #[case] score: Vec<(EventKind, f64)>,
+        #[case] expected: bool,
+    ) {
+        let regression_config = RegressionConfig {
+            limits: vec![(Score, 5f64)],
+            score,
+            ..Default::default()
+        };
+
+        assert_eq!(
+            args_from(args)
+                .check_collectible(Some(&regression_config), None)
+                .is_ok(),
+            expected
+        );
+        assert_eq!(
+            args_from(args)
+                .check_cachegrind_collectible(Some(&regression_config))
+                .is_ok(),
+            expected
+        );
+    }
+
+    #[test]
+    fn test_check_collectible_when_flamegraph_of_score_then_error() {
+        let flamegraph_config = FlamegraphConfig::try_from(crate::api::FlamegraphConfig {
+            event_kinds: Some(vec![Score]),
+            ..Default::default()
+        })
+        .unwrap();
+
+        assert_eq!(
+            args_from(&[])
+                .check_collectible(None, Some(&flamegraph_config))
+                .unwrap_err()
+                .to_string(),
+            "The event kind 'Score' is not available in flamegraphs"
+        );
+    }
+
+    #[rstest]
+    #[case::not_collected(
+        Bc,
+        "The event kind 'Bc' of the flamegraph colors is not collected by callgrind. Collecting \
+         this event kind requires the callgrind argument(s) '--branch-sim=yes'"
+    )]
+    #[case::score(Score, "The event kind 'Score' is not available in flamegraphs")]
+    fn test_check_collectible_when_color_event_kind_then_error(
+        #[case] color_event_kind: EventKind,
+        #[case] expected: &str,
+    ) {
+        let flamegraph_config = FlamegraphConfig::try_from(crate::api::FlamegraphConfig {
+            color_event_kind: Some(color_event_kind),
+            ..Default::default()
+        })
+        .unwrap();
+
+        assert_eq!(
+            args_from(&[])
+                .check_collectible(None, Some(&flamegraph_config))
+                .unwrap_err()
+                .to_string(),
+            expected
+        );
+    }
+
+    #[test]
+    fn test_check_collectible_when_flamegraph_kind_is_none() {
+        let flamegraph_config = FlamegraphConfig::try_from(crate::api::FlamegraphConfig {
+            kind: Some(FlamegraphKind::None),
+            event_kinds: Some(vec![Bc]),
+            ..Default::default()
+        })
+        .unwrap();
+
+        assert!(args_from(&[])
+            .check_collectible(None, Some(&flamegraph_config))
+            .is_ok());
+    }
+}
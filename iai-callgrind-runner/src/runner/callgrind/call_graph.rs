@@ -0,0 +1,350 @@
+use std::cell::Cell;
+use std::ffi::OsStr;
+use std::path::{Component, Path, PathBuf};
+
+use anyhow::Result;
+use indexmap::map::Entry;
+use indexmap::IndexMap;
+use log::debug;
+
+use super::hashmap_parser::{Id, SourcePath};
+use super::model::Costs;
+use super::parser::parse_header;
+use crate::error::Error;
+use crate::runner::tool::{Parser, ToolOutputPath};
+
+/// The call graph of a callgrind output file
+///
+/// The nodes of the call graph are the functions with their [`Function`] costs and the edges are
+/// the calls from a caller to a callee with the number of calls and the costs of these calls.
+/// Functions and calls are ordered by their first occurrence in the callgrind output file.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct CallGraph {
+    /// All functions of the call graph including the functions which are only called
+    pub functions: IndexMap<Id, Function>,
+    /// The calls from a caller (the first [`Id`]) to a callee (the second [`Id`])
+    pub calls: IndexMap<(Id, Id), Call>,
+}
+
+/// The calls from a caller to a callee in the [`CallGraph`]
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct Call {
+    /// The number of calls
+    pub count: u64,
+    /// The inclusive costs of all calls
+    pub costs: Costs,
+}
+
+/// The costs of a function in the [`CallGraph`]
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct Function {
+    /// The costs of the function itself excluding the costs of the functions it calls
+    pub self_costs: Costs,
+    /// The costs of the function including the costs of the functions it calls
+    pub inclusive_costs: Costs,
+    /// True if these are the costs of code which was inlined from another source file
+    ///
+    /// The cost lines of inlined code follow an `fi=` or `fe=` line with a source file other
+    /// than the file of the function (`fl=`). Such parts of a function have their own [`Id`] but
+    /// aren't a real call.
+    pub inlined: bool,
+}
+
+/// Parse a callgrind output file into a [`CallGraph`]
+#[derive(Debug, Default)]
+pub struct CallGraphParser {
+    pub project_root: PathBuf,
+}
+
+#[derive(Debug, Default)]
+struct CallRecord {
+    obj: Option<SourcePath>,
+    file: Option<SourcePath>,
+    id: Option<Id>,
+    count: u64,
+}
+
+impl CallGraph {
+    pub fn is_empty(&self) -> bool {
+        self.functions.is_empty()
+    }
+
+    /// Return the [`Function`] with this [`Id`]
+    pub fn function(&self, id: &Id) -> Option<&Function> {
+        self.functions.get(id)
+    }
+
+    /// Return all callers of the function with this [`Id`] and their calls to this function
+    pub fn callers<'a>(&'a self, id: &'a Id) -> impl Iterator<Item = (&'a Id, &'a Call)> + 'a {
+        self.calls
+            .iter()
+            .filter(move |((_, callee), _)| callee == id)
+            .map(|((caller, _), call)| (caller, call))
+    }
+
+    /// Return all callees of the function with this [`Id`] and the calls of this function
+    pub fn callees<'a>(&'a self, id: &'a Id) -> impl Iterator<Item = (&'a Id, &'a Call)> + 'a {
+        self.calls
+            .iter()
+            .filter(move |((caller, _), _)| caller == id)
+            .map(|((_, callee), call)| (callee, call))
+    }
+
+    /// Return the total number of calls of the function with this [`Id`] from all its callers
+    pub fn total_calls(&self, id: &Id) -> u64 {
+        self.callers(id).map(|(_, call)| call.count).sum()
+    }
+
+    /// Insert the function with zero costs if not present and return its index
+    fn insert_function(&mut self, id: Id, costs_prototype: &Costs) -> usize {
+        let entry = self.functions.entry(id);
+        let index = entry.index();
+        entry.or_insert_with(|| Function {
+            self_costs: costs_prototype.clone(),
+            inclusive_costs: costs_prototype.clone(),
+            inlined: false,
+        });
+        index
+    }
+}
+
+impl CallGraphParser {
+    /// Parse the [`CallGraph`] and the costs of the `summary:` or `totals:` line in one pass
+    ///
+    /// Like the [`super::summary_parser::SummaryParser`], the first `summary:` or `totals:` line
+    /// is used. The costs are `None` if there is neither a summary nor a totals line.
+    #[allow(clippy::too_many_lines)]
+    pub fn parse_with_totals(
+        &self,
+        output_path: &ToolOutputPath,
+    ) -> Result<(CallGraph, Option<Costs>)> {
+        debug!(
+            "Parsing call graph of callgrind output file '{}'",
+            output_path
+        );
+
+        let line_number = Cell::new(0);
+        let mut iter = output_path
+            .lines()?
+            .inspect(|_| line_number.set(line_number.get() + 1));
+        let config = parse_header(&mut iter)
+            .map_err(|error| Error::ParseError((output_path.to_path(), error.to_string())))?;
+
+        let mut current_id = CurrentId::default();
+        // The source file of the current function as opposed to the file of inlined code
+        let mut function_file = None;
+        let mut call_record = None;
+
+        // The sum of all cost lines of a function. This is the inclusive cost of functions without
+        // callers.
+        let mut fn_totals = IndexMap::<Id, Costs>::new();
+        let mut graph = CallGraph::default();
+        let mut totals = None;
+
+        // We start within the header
+        let mut is_header = true;
+        for line in iter {
+            let line = line.trim();
+
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            if totals.is_none() {
+                if let Some(stripped) = line
+                    .strip_prefix("summary:")
+                    .or_else(|| line.strip_prefix("totals:"))
+                {
+                    let mut costs = config.costs_prototype.clone();
+                    costs
+                        .try_add_iter_str(stripped.split_ascii_whitespace())
+                        .map_err(|error| {
+                            Error::ParseLineError((
+                                output_path.to_path(),
+                                line_number.get(),
+                                error.to_string(),
+                            ))
+                        })?;
+                    totals = Some(costs);
+                    continue;
+                }
+            }
+
+            // The first line which can be split around '=' is a non header line
+            let split = if is_header {
+                if let Some(split) = line.split_once('=') {
+                    is_header = false;
+                    Some(split)
+                } else {
+                    continue;
+                }
+            } else {
+                line.split_once('=')
+            };
+
+            match split {
+                Some(("ob", obj)) => {
+                    current_id.obj = Some(make_path(&self.project_root, obj));
+                }
+                Some(("fl", file)) => {
+                    current_id.file = Some(make_path(&self.project_root, file));
+                    function_file.clone_from(&current_id.file);
+                }
+                Some(("fn", func)) => {
+                    current_id.func = Some(func.to_owned());
+                }
+                Some(("fi" | "fe", inline)) => {
+                    current_id.file = Some(make_path(&self.project_root, inline));
+                }
+                Some(("cob", cob)) => {
+                    let record = call_record.get_or_insert(CallRecord::default());
+                    record.obj = Some(make_path(&self.project_root, cob));
+                }
+                Some(("cfi" | "cfl", inline)) => {
+                    let record = call_record.get_or_insert(CallRecord::default());
+                    record.file = Some(make_path(&self.project_root, inline));
+                }
+                Some(("cfn", cfn)) => {
+                    let record = call_record.get_or_insert(CallRecord::default());
+                    record.id = Some(Id {
+                        obj: record.obj.take().or(current_id.obj.clone()),
+                        func: cfn.to_owned(),
+                        file: record.file.take().or(current_id.file.clone()),
+                    });
+                }
+                Some(("calls", calls)) => {
+                    let record = call_record.as_mut().expect("Valid calls line");
+                    record.count = calls
+                        .split_ascii_whitespace()
+                        .take(1)
+                        .map(|s| s.parse::<u64>().unwrap())
+                        .sum();
+                }
+                None if line.starts_with(|c: char| c.is_ascii_digit()) => {
+                    let mut costs = config.costs_prototype.clone();
+                    costs
+                        .try_add_iter_str(
+                            line.split_whitespace()
+                                .skip(config.positions_prototype.len()),
+                        )
+                        .map_err(|error| {
+                            Error::ParseLineError((
+                                output_path.to_path(),
+                                line_number.get(),
+                                error.to_string(),
+                            ))
+                        })?;
+
+                    let id = current_id.to_id();
+                    let function = graph.insert_function(id.clone(), &config.costs_prototype);
+                    if current_id.file != function_file {
+                        graph.functions[function].inlined = true;
+                    }
+
+                    if let Some(record) = call_record.take() {
+                        let callee = record.id.expect("cfn record id must be present");
+                        graph.insert_function(callee.clone(), &config.costs_prototype);
+                        match graph.calls.entry((id.clone(), callee)) {
+                            Entry::Occupied(mut entry) => {
+                                let call = entry.get_mut();
+                                call.count += record.count;
+                                call.costs.add(&costs);
+                            }
+                            Entry::Vacant(entry) => {
+                                entry.insert(Call {
+                                    count: record.count,
+                                    costs: costs.clone(),
+                                });
+                            }
+                        }
+                    } else {
+                        graph.functions[function].self_costs.add(&costs);
+                    }
+
+                    match fn_totals.entry(id) {
+                        Entry::Occupied(mut entry) => entry.get_mut().add(&costs),
+                        Entry::Vacant(entry) => {
+                            entry.insert(costs);
+                        }
+                    }
+                }
+                Some(("jump" | "jcnd" | "jfi" | "jfn", _)) => {
+                    // we ignore these
+                }
+                None if line.starts_with("totals:") || line.starts_with("summary:") => {
+                    // we ignore these
+                }
+                Some(_) | None => panic!("Malformed line: '{line}'"),
+            }
+        }
+
+        // Like `callgrind_annotate`, the inclusive costs of a function are the costs of all calls
+        // of this function. Only the functions without callers, like `main`, use the sum of
+        // their cost lines.
+        let mut called = IndexMap::<Id, Costs>::new();
+        for ((_, callee), call) in &graph.calls {
+            match called.entry(callee.clone()) {
+                Entry::Occupied(mut entry) => entry.get_mut().add(&call.costs),
+                Entry::Vacant(entry) => {
+                    entry.insert(call.costs.clone());
+                }
+            }
+        }
+        for (id, function) in &mut graph.functions {
+            if let Some(costs) = called.swap_remove(id).or_else(|| fn_totals.swap_remove(id)) {
+                function.inclusive_costs = costs;
+            }
+        }
+
+        Ok((graph, totals))
+    }
+}
+
+impl Parser for CallGraphParser {
+    type Output = CallGraph;
+
+    fn parse(&self, output_path: &ToolOutputPath) -> Result<Self::Output> {
+        self.parse_with_totals(output_path).map(|(graph, _)| graph)
+    }
+}
+
+#[derive(Debug, Default, Clone)]
+struct CurrentId {
+    obj: Option<SourcePath>,
+    file: Option<SourcePath>,
+    func: Option<String>,
+}
+
+impl CurrentId {
+    fn to_id(&self) -> Id {
+        Id {
+            obj: self.obj.clone(),
+            file: self.file.clone(),
+            func: self.func.clone().expect("A valid id"),
+        }
+    }
+}
+
+fn make_path(root: &Path, source: &str) -> SourcePath {
+    if source == "???" {
+        SourcePath::Unknown
+    } else {
+        let path = PathBuf::from(source);
+        match path.strip_prefix(root).ok() {
+            Some(stripped) => SourcePath::Relative(stripped.to_owned()),
+            None if path.is_absolute() => {
+                let mut components = path.components().skip(1);
+                if components.next() == Some(Component::Normal(OsStr::new("rustc"))) {
+                    let mut new_path = PathBuf::from("/rustc");
+                    if let Some(Component::Normal(string)) = components.next() {
+                        new_path.push(string.to_string_lossy().chars().take(8).collect::<String>());
+                    }
+                    SourcePath::Rust(new_path.join(components.collect::<PathBuf>()))
+                } else {
+                    SourcePath::Absolute(path)
+                }
+            }
+            None => SourcePath::Relative(path),
+        }
+    }
+}
@@ -0,0 +1,139 @@
+//! Compare the costs of the same benchmark on two different architectures
+//!
+//! The costs of different architectures depend on the instruction set, the calling conventions
+//! and the simulated caches. More instructions on one architecture don't mean the benchmark is
+//! slower on this architecture, so a [`CrossArchComparison`] only shows the differences between
+//! the costs and knows nothing about regressions.
+use std::fmt::{Display, Write};
+
+use anyhow::{anyhow, Result};
+
+use super::metadata_parser::MetadataParser;
+use super::model::Costs;
+use super::output_parser::CallgrindOutput;
+use crate::error::Error;
+use crate::runner::format::{NumberFormat, NOT_AVAILABLE};
+use crate::runner::summary::CostsSummary;
+use crate::runner::tool::Parser;
+use crate::util::to_string_signed_short;
+
+/// The comparison of the total costs of a benchmark on two different architectures
+///
+/// The architecture of an output is recorded in the `*.meta` file next to the output file. The
+/// outputs of the same benchmark on different machines can be compared by copying the outputs
+/// (including the `*.meta` files) of one machine into another directory.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CrossArchComparison {
+    /// The architecture of the output, like `aarch64`
+    pub arch: String,
+    /// The architecture of the other output, like `x86_64`
+    pub other_arch: String,
+    /// The total costs of the output (new) compared to the total costs of the other output (old)
+    pub costs_summary: CostsSummary,
+}
+
+impl CrossArchComparison {
+    /// Compare the total costs of the `output` with the `other` output of another architecture
+    ///
+    /// # Errors
+    ///
+    /// If any of the outputs could not be parsed, if the architecture of any output is unknown
+    /// (the outputs of earlier versions of iai-callgrind don't record it) or if both outputs were
+    /// created on the same architecture
+    pub fn new(output: &CallgrindOutput, other: &CallgrindOutput) -> Result<Self> {
+        let arch = parse_arch(output)?;
+        let other_arch = parse_arch(other)?;
+        if arch == other_arch {
+            return Err(anyhow!(
+                "The outputs '{}' and '{}' were both created on '{arch}': Comparing the costs of \
+                 the same architecture is not a cross-architecture comparison",
+                output.path,
+                other.path
+            ));
+        }
+
+        let costs = parse_totals(output)?;
+        let other_costs = parse_totals(other)?;
+        Ok(Self {
+            arch,
+            other_arch,
+            costs_summary: CostsSummary::new(&costs, Some(&other_costs)),
+        })
+    }
+
+    /// Format the comparison for the terminal with the costs formatted with the `number_format`
+    ///
+    /// In contrast to the output of a regular benchmark run, the differences are not colored, since
+    /// neither an increase nor a decrease of the costs is better.
+    pub fn format(&self, number_format: NumberFormat) -> String {
+        let mut result = String::new();
+        writeln!(
+            result,
+            "  Cross-architecture comparison: The costs of different architectures are not \
+             comparable as faster or slower"
+        )
+        .unwrap();
+        writeln!(
+            result,
+            "  {:<18}{:>15}|{}",
+            "Architectures:", self.arch, self.other_arch
+        )
+        .unwrap();
+
+        for (event_kind, diff) in self.costs_summary.all_diffs() {
+            let description = format!("{event_kind}:");
+            let cost = diff
+                .new
+                .map_or_else(|| NOT_AVAILABLE.to_owned(), |c| number_format.format(c));
+            let other_cost = diff
+                .old
+                .map_or_else(|| NOT_AVAILABLE.to_owned(), |c| number_format.format(c));
+            let diff_string = match (diff.diff_pct, diff.factor) {
+                (Some(pct), Some(factor)) => format!(
+                    "({:^9}) [{:^9}]",
+                    format!("{}%", to_string_signed_short(pct)),
+                    format!("{}x", to_string_signed_short(factor))
+                ),
+                _ => format!("({:^9})", "*********"),
+            };
+            writeln!(
+                result,
+                "  {description:<18}{cost:>15}|{other_cost:<15} {diff_string}"
+            )
+            .unwrap();
+        }
+        result
+    }
+}
+
+impl Display for CrossArchComparison {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.format(NumberFormat::Plain))
+    }
+}
+
+/// Return the total costs of the `output` from the [`CallgrindOutput::parse`] of the whole output
+fn parse_totals(output: &CallgrindOutput) -> Result<Costs> {
+    output.parse()?.totals.ok_or_else(|| {
+        Error::ParseError((
+            output.path.to_path(),
+            "No summary or totals line found".to_owned(),
+        ))
+        .into()
+    })
+}
+
+/// Return the architecture recorded in the run metadata of the `output`
+fn parse_arch(output: &CallgrindOutput) -> Result<String> {
+    MetadataParser
+        .parse(&output.path)?
+        .run
+        .and_then(|run| run.arch)
+        .ok_or_else(|| {
+            anyhow!(
+                "The architecture of the output '{}' is unknown: The output was created by an \
+                 earlier version of iai-callgrind or the '*.meta' file is missing",
+                output.path
+            )
+        })
+}
@@ -0,0 +1,301 @@
+//! The costs of event kinds which are pinned to an earlier run of a named baseline
+//!
+//! A `--save-baseline=NAME` run with `--save-baseline-events=EVENTS` updates only the costs of
+//! the listed event kinds. The costs of all other event kinds of the baseline before this run are
+//! pinned in a json file next to the baseline output file, so they survive the overwrite of the
+//! output file. Whenever the costs of the baseline are read, the pinned costs replace the costs
+//! of the output file.
+use std::fs::File;
+use std::io::BufWriter;
+use std::path::PathBuf;
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+
+use super::model::Costs;
+use super::{add_score, RegressionConfig};
+use crate::api::EventKind;
+use crate::runner::tool::{ToolOutputPath, ToolOutputPathKind};
+
+/// The pinned costs of the event kinds of a named baseline
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct EventBaseline {
+    /// The pinned costs. The costs of all other event kinds are the costs of the output file.
+    pub costs: Costs,
+}
+
+impl EventBaseline {
+    /// Pin the costs of all event kinds of the `costs` which are not in `events`
+    ///
+    /// The [`EventKind::Score`] is never pinned because it is calculated from the other costs.
+    pub fn pin(costs: &Costs, events: &[EventKind]) -> Self {
+        Self {
+            costs: Costs::with_event_kinds(
+                costs
+                    .pairs()
+                    .filter(|(kind, _)| *kind != EventKind::Score && !events.contains(kind)),
+            ),
+        }
+    }
+
+    /// Replace the costs of the pinned event kinds in `costs`
+    ///
+    /// Pinned event kinds which are not present in the `costs` are ignored.
+    pub fn apply(&self, costs: &mut Costs) {
+        for (kind, pinned) in &self.costs {
+            if let Some(cost) = costs.0.get_mut(kind) {
+                *cost = *pinned;
+            }
+        }
+    }
+
+    /// Return the path of the file with the `EventBaseline` of the `output_path`
+    ///
+    /// Only the output of a named baseline ([`ToolOutputPathKind::Base`]) can have pinned costs.
+    /// The modifiers of the `output_path`, for example of a region, are part of the file name.
+    pub fn path(output_path: &ToolOutputPath) -> Option<PathBuf> {
+        match &output_path.kind {
+            ToolOutputPathKind::Base(name) => {
+                let modifiers = output_path
+                    .modifiers
+                    .iter()
+                    .fold(String::new(), |acc, modifier| acc + "." + modifier);
+                Some(output_path.kind_dir().join(format!(
+                    "{}.{}.events{modifiers}.base@{name}.json",
+                    output_path.tool.id(),
+                    output_path.name
+                )))
+            }
+            _ => None,
+        }
+    }
+
+    /// Load the `EventBaseline` of the `output_path` if present
+    ///
+    /// # Errors
+    ///
+    /// If the file exists but could not be read or deserialized
+    pub fn load(output_path: &ToolOutputPath) -> Result<Option<Self>> {
+        let Some(path) = Self::path(output_path).filter(|path| path.exists()) else {
+            return Ok(None);
+        };
+
+        let file = File::open(&path)
+            .with_context(|| format!("Failed to open event baseline '{}'", path.display()))?;
+        serde_json::from_reader(file)
+            .map(Some)
+            .with_context(|| format!("Failed to read event baseline '{}'", path.display()))
+    }
+
+    /// Save this `EventBaseline` for the `output_path`
+    ///
+    /// # Errors
+    ///
+    /// If the file could not be written
+    pub fn save(&self, output_path: &ToolOutputPath) -> Result<()> {
+        let Some(path) = Self::path(output_path) else {
+            return Ok(());
+        };
+
+        let file = File::create(&path)
+            .with_context(|| format!("Failed to create event baseline '{}'", path.display()))?;
+        serde_json::to_writer(BufWriter::new(file), self)
+            .with_context(|| format!("Failed to write event baseline '{}'", path.display()))
+    }
+
+    /// Update the `EventBaseline` of the `output_path` before it is overwritten by a new run
+    ///
+    /// The `old_costs` are the costs of the baseline before the new run (including the former
+    /// pinned costs). If `events` is `None`, all event kinds are updated by the new run and the
+    /// file with the pinned costs is removed. Otherwise, the old costs of all event kinds not in
+    /// `events` are pinned. Without `old_costs` there's nothing to pin.
+    ///
+    /// # Errors
+    ///
+    /// If the file with the pinned costs could not be written or removed
+    pub fn update(
+        output_path: &ToolOutputPath,
+        old_costs: Option<&Costs>,
+        events: Option<&[EventKind]>,
+    ) -> Result<()> {
+        if let (Some(old_costs), Some(events)) = (old_costs, events) {
+            let event_baseline = Self::pin(old_costs, events);
+            if !event_baseline.costs.is_empty() {
+                return event_baseline.save(output_path);
+            }
+        }
+
+        match Self::path(output_path) {
+            Some(path) if path.exists() => std::fs::remove_file(&path)
+                .with_context(|| format!("Failed to remove event baseline '{}'", path.display())),
+            _ => Ok(()),
+        }
+    }
+}
+
+/// Replace the costs of the event kinds in `costs` which are pinned for the `output_path`
+///
+/// The `costs` are expected to be parsed from the output file of the `output_path` including the
+/// [`EventKind::Score`] of the `regression_config`. Since the score is calculated from the other
+/// costs, it is calculated again if there are pinned costs.
+///
+/// # Errors
+///
+/// If the [`EventBaseline`] exists but could not be loaded or the score could not be calculated
+pub fn apply_event_baseline(
+    output_path: &ToolOutputPath,
+    regression_config: Option<&RegressionConfig>,
+    costs: &mut Costs,
+) -> Result<()> {
+    if let Some(event_baseline) = EventBaseline::load(output_path)? {
+        event_baseline.apply(costs);
+        add_score(regression_config, costs)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use pretty_assertions::assert_eq;
+    use rstest::rstest;
+    use EventKind::*;
+
+    use super::*;
+    use crate::runner::summary::{BaselineKind, BaselineName};
+    use crate::runner::tool::ValgrindTool;
+
+    fn costs(costs: &[(EventKind, u64)]) -> Costs {
+        Costs::with_event_kinds(costs.iter().copied())
+    }
+
+    fn base_output_path(dir: &Path) -> ToolOutputPath {
+        let baseline: BaselineName = "foo".parse().unwrap();
+        ToolOutputPath::with_init(
+            ToolOutputPathKind::Base(baseline.to_string()),
+            ValgrindTool::Callgrind,
+            &BaselineKind::Name(baseline),
+            dir,
+            dir,
+            "bench::group",
+            "func",
+        )
+        .unwrap()
+    }
+
+    #[rstest]
+    #[case::none(&[], &[(Ir, 1), (Dr, 2), (D1mr, 3)])]
+    #[case::one(&[Ir], &[(Dr, 2), (D1mr, 3)])]
+    #[case::all(&[Ir, Dr, D1mr], &[])]
+    #[case::unknown(&[ILmr], &[(Ir, 1), (Dr, 2), (D1mr, 3)])]
+    fn test_event_baseline_pin(
+        #[case] events: &[EventKind],
+        #[case] expected: &[(EventKind, u64)],
+    ) {
+        let old_costs = costs(&[(Ir, 1), (Dr, 2), (D1mr, 3), (Score, 4)]);
+        assert_eq!(
+            EventBaseline::pin(&old_costs, events),
+            EventBaseline {
+                costs: costs(expected)
+            }
+        );
+    }
+
+    #[test]
+    fn test_event_baseline_apply() {
+        let event_baseline = EventBaseline {
+            costs: costs(&[(Dr, 20), (ILmr, 30)]),
+        };
+        let mut actual = costs(&[(Ir, 1), (Dr, 2), (D1mr, 3)]);
+        event_baseline.apply(&mut actual);
+        assert_eq!(actual, costs(&[(Ir, 1), (Dr, 20), (D1mr, 3)]));
+    }
+
+    #[test]
+    fn test_event_baseline_path_when_not_base() {
+        let dir = tempfile::tempdir().unwrap();
+        let output_path = ToolOutputPath {
+            kind: ToolOutputPathKind::Out,
+            ..base_output_path(dir.path())
+        };
+        assert_eq!(EventBaseline::path(&output_path), None);
+    }
+
+    #[test]
+    fn test_event_baseline_update_with_fresh_and_stale_events() {
+        let dir = tempfile::tempdir().unwrap();
+        let output_path = base_output_path(dir.path());
+
+        // The first baseline has nothing to pin
+        EventBaseline::update(&output_path, None, Some(&[Ir])).unwrap();
+        assert_eq!(EventBaseline::load(&output_path).unwrap(), None);
+
+        // Only `Ir` is saved, so the cache misses of the first baseline are kept
+        let first = costs(&[(Ir, 100), (D1mr, 10), (DLmr, 1)]);
+        EventBaseline::update(&output_path, Some(&first), Some(&[Ir])).unwrap();
+
+        let mut second = costs(&[(Ir, 200), (D1mr, 20), (DLmr, 2)]);
+        apply_event_baseline(&output_path, None, &mut second).unwrap();
+        assert_eq!(second, costs(&[(Ir, 200), (D1mr, 10), (DLmr, 1)]));
+
+        // Only `D1mr` is saved, so the `Ir` of the second and the `DLmr` of the first baseline
+        // are kept
+        EventBaseline::update(&output_path, Some(&second), Some(&[D1mr])).unwrap();
+
+        let mut third = costs(&[(Ir, 300), (D1mr, 30), (DLmr, 3)]);
+        apply_event_baseline(&output_path, None, &mut third).unwrap();
+        assert_eq!(third, costs(&[(Ir, 200), (D1mr, 30), (DLmr, 1)]));
+
+        // Saving all events removes the pinned costs
+        EventBaseline::update(&output_path, Some(&third), None).unwrap();
+        assert_eq!(EventBaseline::load(&output_path).unwrap(), None);
+
+        let mut fourth = costs(&[(Ir, 400), (D1mr, 40), (DLmr, 4)]);
+        apply_event_baseline(&output_path, None, &mut fourth).unwrap();
+        assert_eq!(fourth, costs(&[(Ir, 400), (D1mr, 40), (DLmr, 4)]));
+    }
+
+    #[test]
+    fn test_event_baseline_path_with_modifiers() {
+        let dir = tempfile::tempdir().unwrap();
+        let output_path = base_output_path(dir.path()).with_modifiers(["region", "main"]);
+        assert_eq!(
+            EventBaseline::path(&output_path),
+            Some(
+                output_path
+                    .kind_dir()
+                    .join("callgrind.func.events.region.main.base@foo.json")
+            )
+        );
+    }
+
+    #[test]
+    fn test_apply_event_baseline_recalculates_score() {
+        let dir = tempfile::tempdir().unwrap();
+        let output_path = base_output_path(dir.path());
+        let regression_config = RegressionConfig {
+            score: vec![(Ir, 1f64), (Dr, 10f64)],
+            ..Default::default()
+        };
+
+        EventBaseline::update(&output_path, Some(&costs(&[(Ir, 1), (Dr, 2)])), Some(&[Ir]))
+            .unwrap();
+
+        let mut actual = costs(&[(Ir, 10), (Dr, 20), (Score, 210)]);
+        apply_event_baseline(&output_path, Some(&regression_config), &mut actual).unwrap();
+        assert_eq!(actual, costs(&[(Ir, 10), (Dr, 2), (Score, 30)]));
+    }
+
+    #[test]
+    fn test_event_baseline_file_is_not_an_output_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let output_path = base_output_path(dir.path());
+        EventBaseline::update(&output_path, Some(&costs(&[(Ir, 1), (Dr, 2)])), Some(&[Ir]))
+            .unwrap();
+
+        assert!(!output_path.exists());
+        output_path.clear().unwrap();
+        assert!(EventBaseline::load(&output_path).unwrap().is_some());
+    }
+}
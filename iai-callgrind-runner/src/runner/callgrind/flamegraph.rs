@@ -1,15 +1,70 @@
+use std::borrow::Cow;
+use std::collections::HashSet;
+use std::fmt::{Display, Write as FmtWrite};
 use std::fs::File;
-use std::io::{BufWriter, Cursor, Write as IoWrite};
+use std::io::{self, BufWriter, Cursor, Write as IoWrite};
 use std::path::{Path, PathBuf};
+use std::time::Instant;
 
 use anyhow::{anyhow, Context, Result};
+use indexmap::map::Entry;
+use indexmap::IndexMap;
+use inferno::flamegraph::color::{BasicPalette, Color, MultiPalette, PaletteMap};
 use inferno::flamegraph::{Direction, Options};
+use lazy_static::lazy_static;
+use log::{debug, log_enabled, warn, Level};
+use regex::Regex;
 
-use super::flamegraph_parser::{FlamegraphMap, FlamegraphParser};
+use super::flamegraph_parser::FlamegraphMap;
+use super::metadata_parser::{MetadataParser, OutputMetadata};
+use super::model::Costs;
+use super::output_parser::CallgrindOutput;
 use super::parser::Sentinel;
-use crate::api::{self, EventKind, FlamegraphKind};
+use crate::api::{self, EventKind, FlamegraphKind, FrameLabel, ObjectMarker, Rounding};
 use crate::runner::summary::{BaselineKind, BaselineName, FlamegraphSummary};
 use crate::runner::tool::{Parser, ToolOutputPath, ToolOutputPathKind};
+use crate::util::{to_string_thousands, truncate_str_utf8};
+
+/// The frame which replaces the frames of a stack beyond the maximum depth
+pub const TRUNCATED_FRAME: &str = "...";
+
+/// The replacement of the cut characters of a truncated frame name
+const ELLIPSIS: &str = "...";
+
+/// The number of the most duplicated frame paths shown in the [`StacksReport`]
+const NUM_DUPLICATED_PATHS: usize = 5;
+
+/// The maximum length of a frame path in the [`StacksReport`]
+const MAX_PATH_LENGTH: usize = 200;
+
+/// The maximum number of frames of the repeated frame paths searched for the [`StacksReport`]
+///
+/// This is enough to find the recursions of a single function and the mutual recursions of a few
+/// functions without searching all sub-paths of deep stacks.
+const MAX_PATH_FRAMES: usize = 4;
+
+/// The color of the frames with the lowest density of the `color_event_kind`
+const LOW_DENSITY_COLOR: Color = Color {
+    r: 255,
+    g: 240,
+    b: 200,
+};
+
+/// The color of the frames with the highest density of the `color_event_kind`
+const HIGH_DENSITY_COLOR: Color = Color { r: 200, g: 0, b: 0 };
+
+/// The color of the frames without costs for which no density can be calculated
+const NO_DENSITY_COLOR: Color = Color {
+    r: 200,
+    g: 200,
+    b: 200,
+};
+
+lazy_static! {
+    // The hash suffix of demangled (legacy) rust symbols like `core::ptr::drop::h0123456789abcdef`
+    static ref HASH_SUFFIX_RE: Regex =
+        Regex::new(r"::h[0-9a-f]{16}\b").expect("Regex should compile");
+}
 
 #[derive(Debug)]
 pub struct BaselineFlamegraphGenerator {
@@ -27,6 +82,34 @@ pub struct Config {
     pub title: Option<String>,
     pub subtitle: Option<String>,
     pub min_width: f64,
+    pub min_cost: u64,
+    pub max_depth: Option<usize>,
+    /// If false, the frames of inlined code don't count toward the `max_depth`
+    pub max_depth_count_inline: bool,
+    /// The compiled symbol transforms
+    pub symbol_transforms: Vec<SymbolTransform>,
+    pub sort_stacks: bool,
+    pub coalesce_stacks: bool,
+    pub backup: bool,
+    pub tag: Option<String>,
+    pub frame_label: Option<FrameLabel>,
+    pub percentage_scale: Option<u64>,
+    pub rounding: Rounding,
+    pub trim_harness: bool,
+    pub object_marker: ObjectMarker,
+    /// The [`EventKind`] whose density relative to the width of a frame determines the color of
+    /// the frames of the regular flamegraphs
+    pub color_event_kind: Option<EventKind>,
+    pub count_units: Vec<(EventKind, api::CountUnit)>,
+    pub svg_header: Option<String>,
+    pub svg_footer: Option<String>,
+    /// The color palette of the regular flamegraphs or the default palette of inferno if `None`
+    pub palette: Option<api::Palette>,
+    /// The maximum number of characters of the displayed frame names or no limit if `None`
+    pub max_frame_name_length: Option<usize>,
+    pub frame_name_ellipsis: api::Ellipsis,
+    /// The synthetic root frame prepended to all stacks
+    pub root_frame: api::RootFrame,
 }
 
 #[derive(Debug, Clone)]
@@ -34,6 +117,34 @@ pub struct Flamegraph {
     pub config: Config,
 }
 
+/// A builder for a [`Flamegraph`] with all the options of the [`Config`]
+///
+/// All options which are not set use the same defaults as an empty [`api::FlamegraphConfig`].
+/// The parsed callgrind output file is a [`FlamegraphMap`] which can be created from a
+/// [`CallgrindOutput`] with `FlamegraphMap::try_from(&callgrind_output)` and converted into
+/// [`Stacks`] with [`Flamegraph::to_stacks`].
+///
+/// # Examples
+///
+/// ```rust
+/// use iai_callgrind_runner::api::{EventKind, FlamegraphKind};
+/// use iai_callgrind_runner::runner::callgrind::flamegraph::FlamegraphBuilder;
+///
+/// let flamegraph = FlamegraphBuilder::new("my_benchmark")
+///     .kind(FlamegraphKind::Regular)
+///     .event_kinds([EventKind::Ir, EventKind::Dr])
+///     .min_width(0.5)
+///     .build();
+///
+/// assert!(flamegraph.is_regular());
+/// assert_eq!(flamegraph.config.title.as_deref(), Some("my_benchmark"));
+/// ```
+#[derive(Debug, Clone)]
+pub struct FlamegraphBuilder {
+    heading: String,
+    config: Config,
+}
+
 #[derive(Debug)]
 pub struct LoadBaselineFlamegraphGenerator {
     pub loaded_baseline: BaselineName,
@@ -46,7 +157,12 @@ struct OutputPath {
     pub event_kind: EventKind,
     pub baseline_kind: BaselineKind,
     pub dir: PathBuf,
+    /// The directory of the flamegraphs of the named baselines (`--baseline-dir`)
+    pub baseline_dir: PathBuf,
     pub name: String,
+    pub tag: Option<String>,
+    /// If true, the file is a html page and not a svg file
+    pub is_html: bool,
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -64,6 +180,44 @@ pub struct SaveBaselineFlamegraphGenerator {
     pub baseline: BaselineName,
 }
 
+/// A single stack of frames and the (self) costs of its innermost frame
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Stack {
+    /// The frames of this stack from the outermost (the root) to the innermost frame
+    pub frames: Vec<String>,
+    /// The costs of the innermost frame excluding the costs of its callees
+    pub costs: Costs,
+}
+
+/// The `Stacks` used as input for the creation of flamegraphs
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct Stacks(pub Vec<Stack>);
+
+/// A diagnostic report about the size of [`Stacks`] created with [`Stacks::report`]
+///
+/// A huge number of stacks, for example from a deep recursion, makes the rendering of the
+/// flamegraphs slow. The report helps to decide whether to coalesce the stacks, limit the
+/// `max_depth` or raise the `min_cost` or `min_width`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StacksReport {
+    /// The number of stacks
+    pub num_stacks: usize,
+    /// The number of frames of the deepest stack
+    pub max_depth: usize,
+    /// The frame paths (the frames joined with `;`) which are repeated within a stack and the
+    /// number of their repetitions in all stacks sorted by the descending number of repetitions
+    pub duplicated: Vec<(String, usize)>,
+}
+
+/// The compiled form of an [`api::SymbolTransform`]
+#[derive(Debug, Clone)]
+pub enum SymbolTransform {
+    /// Strip the hash suffix of rust symbols
+    StripHash,
+    /// Replace all matches of the regex with the replacement
+    Replace(Regex, String),
+}
+
 pub trait FlamegraphGenerator {
     fn create(
         &self,
@@ -74,9 +228,19 @@ pub trait FlamegraphGenerator {
     ) -> Result<Vec<FlamegraphSummary>>;
 }
 
-impl From<api::FlamegraphConfig> for Config {
-    fn from(value: api::FlamegraphConfig) -> Self {
-        Self {
+impl TryFrom<api::FlamegraphConfig> for Config {
+    type Error = anyhow::Error;
+
+    /// Convert the [`api::FlamegraphConfig`] and compile the regexes of the symbol transforms
+    ///
+    /// A `percentage_scale` of `0` is rejected, since all costs would be zero.
+    fn try_from(value: api::FlamegraphConfig) -> std::result::Result<Self, Self::Error> {
+        if value.percentage_scale == Some(0) {
+            return Err(anyhow!(
+                "Invalid flamegraph percentage scale '0': The scale must be greater than 0"
+            ));
+        }
+        Ok(Self {
             kind: value.kind.unwrap_or(FlamegraphKind::All),
             negate_differential: value.negate_differential.unwrap_or_default(),
             normalize_differential: value.normalize_differential.unwrap_or(false),
@@ -87,7 +251,41 @@ impl From<api::FlamegraphConfig> for Config {
             title: value.title.clone(),
             subtitle: value.subtitle.clone(),
             min_width: value.min_width.unwrap_or(0.1f64),
-        }
+            min_cost: value.min_cost.unwrap_or(0),
+            max_depth: value.max_depth,
+            max_depth_count_inline: value.max_depth_count_inline.unwrap_or(true),
+            symbol_transforms: value
+                .symbol_transforms
+                .unwrap_or_default()
+                .iter()
+                .map(SymbolTransform::try_from)
+                .collect::<Result<Vec<_>>>()?,
+            sort_stacks: value.sort_stacks.unwrap_or(false),
+            coalesce_stacks: value.coalesce_stacks.unwrap_or(false),
+            backup: value.backup.unwrap_or(true),
+            tag: value.tag.as_deref().and_then(sanitize_tag),
+            frame_label: value.frame_label,
+            percentage_scale: value.percentage_scale,
+            rounding: value.rounding.unwrap_or_default(),
+            trim_harness: value.trim_harness.unwrap_or(false),
+            object_marker: value.object_marker.unwrap_or_default(),
+            color_event_kind: value.color_event_kind,
+            count_units: value.count_units.unwrap_or_default(),
+            svg_header: value.svg_header,
+            svg_footer: value.svg_footer,
+            palette: value.palette,
+            max_frame_name_length: value.max_frame_name_length,
+            frame_name_ellipsis: value.frame_name_ellipsis.unwrap_or_default(),
+            root_frame: value.root_frame.unwrap_or_default(),
+        })
+    }
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        api::FlamegraphConfig::default()
+            .try_into()
+            .expect("The default flamegraph config should be valid")
     }
 }
 
@@ -100,6 +298,30 @@ impl From<api::Direction> for Direction {
     }
 }
 
+impl From<api::Palette> for inferno::flamegraph::color::Palette {
+    fn from(value: api::Palette) -> Self {
+        use inferno::flamegraph::color::Palette;
+        match value {
+            api::Palette::Hot => Palette::Basic(BasicPalette::Hot),
+            api::Palette::Mem => Palette::Basic(BasicPalette::Mem),
+            api::Palette::Io => Palette::Basic(BasicPalette::Io),
+            api::Palette::Red => Palette::Basic(BasicPalette::Red),
+            api::Palette::Green => Palette::Basic(BasicPalette::Green),
+            api::Palette::Blue => Palette::Basic(BasicPalette::Blue),
+            api::Palette::Aqua => Palette::Basic(BasicPalette::Aqua),
+            api::Palette::Yellow => Palette::Basic(BasicPalette::Yellow),
+            api::Palette::Purple => Palette::Basic(BasicPalette::Purple),
+            api::Palette::Orange => Palette::Basic(BasicPalette::Orange),
+            api::Palette::Java => Palette::Multi(MultiPalette::Java),
+            api::Palette::Js => Palette::Multi(MultiPalette::Js),
+            api::Palette::Perl => Palette::Multi(MultiPalette::Perl),
+            api::Palette::Python => Palette::Multi(MultiPalette::Python),
+            api::Palette::Rust => Palette::Multi(MultiPalette::Rust),
+            api::Palette::Wakeup => Palette::Multi(MultiPalette::Wakeup),
+        }
+    }
+}
+
 impl FlamegraphGenerator for BaselineFlamegraphGenerator {
     fn create(
         &self,
@@ -110,10 +332,12 @@ impl FlamegraphGenerator for BaselineFlamegraphGenerator {
     ) -> Result<Vec<FlamegraphSummary>> {
         // We need the dummy path just to cleanup and organize the output files independently from
         // the EventKind of the OutputPath
-        let mut output_path = OutputPath::new(tool_output_path, EventKind::Ir);
+        let mut output_path = OutputPath::new(tool_output_path, EventKind::Ir)
+            .with_tag(flamegraph.config.tag.clone());
         output_path.init()?;
         output_path.to_diff_path().clear(true)?;
-        output_path.shift(true)?;
+        output_path.to_side_by_side_path().clear(true)?;
+        output_path.shift(true, flamegraph.config.backup)?;
 
         if flamegraph.config.kind == FlamegraphKind::None
             || flamegraph.config.event_kinds.is_empty()
@@ -123,43 +347,68 @@ impl FlamegraphGenerator for BaselineFlamegraphGenerator {
 
         let (map, base_map) = flamegraph.parse(tool_output_path, sentinel, project_root, false)?;
 
-        let mut flamegraph_summaries = vec![];
-        for event_kind in &flamegraph.config.event_kinds {
-            let mut flamegraph_summary = FlamegraphSummary::new(*event_kind);
-            output_path.set_event_kind(*event_kind);
+        create_each_event_kind(&flamegraph.config.event_kinds, |event_kind| {
+            let mut flamegraph_summary = FlamegraphSummary::new(event_kind);
+            output_path.set_event_kind(event_kind);
 
-            let stacks_lines = map.to_stack_format(event_kind)?;
+            let stacks = flamegraph.to_stacks(&map, &event_kind)?;
+            let stacks_lines = stacks.to_stack_format(&event_kind)?;
+            let total = stacks.total(&event_kind);
 
             if flamegraph.is_regular() {
-                Flamegraph::write(
+                let mut palette_map = flamegraph.to_color_palette(&map, &event_kind, &stacks)?;
+                let mut options = flamegraph.options(event_kind, output_path.file_name(), total);
+                options.count_name = flamegraph.regular_count_name(event_kind, total);
+                flamegraph.set_color_palette(&mut options, event_kind, palette_map.as_mut());
+                flamegraph.write(
                     &output_path,
-                    &mut flamegraph.options(*event_kind, output_path.file_name()),
-                    stacks_lines.iter().map(std::string::String::as_str),
+                    &mut options,
+                    flamegraph
+                        .to_regular_stack_format(&stacks, &event_kind, &stacks_lines)?
+                        .iter()
+                        .map(std::string::String::as_str),
                 )?;
                 flamegraph_summary.regular_path = Some(output_path.to_path());
             }
 
-            // Is Some if FlamegraphKind::Differential or FlamegraphKind::All
+            // Is Some if FlamegraphKind::Differential, FlamegraphKind::SideBySide or
+            // FlamegraphKind::All
             if let Some(base_map) = base_map.as_ref() {
-                Flamegraph::create_differential(
-                    &output_path,
-                    &mut flamegraph.options(*event_kind, output_path.to_diff_path().file_name()),
-                    base_map,
-                    // This unwrap is safe since we always have differential options if the
-                    // flamegraph kind is differential
-                    flamegraph.differential_options().unwrap(),
-                    *event_kind,
-                    &stacks_lines,
-                )?;
+                let base_stacks = flamegraph.to_stacks(base_map, &event_kind)?;
+                if flamegraph.is_side_by_side() {
+                    flamegraph.create_side_by_side(
+                        &output_path,
+                        &base_stacks,
+                        &stacks,
+                        event_kind,
+                    )?;
+                    flamegraph_summary.diff_path =
+                        Some(output_path.to_side_by_side_path().to_path());
+                } else {
+                    flamegraph.create_differential(
+                        &output_path,
+                        &mut flamegraph.options(
+                            event_kind,
+                            output_path.to_diff_path().file_name(),
+                            total,
+                        ),
+                        &base_stacks,
+                        // This unwrap is safe since we always have differential options if the
+                        // flamegraph kind is differential
+                        flamegraph.differential_options().unwrap(),
+                        event_kind,
+                        &stacks_lines,
+                    )?;
+                    flamegraph_summary.diff_path = Some(output_path.to_diff_path().to_path());
+                }
 
-                flamegraph_summary.base_path = Some(output_path.to_base_path().to_path());
-                flamegraph_summary.diff_path = Some(output_path.to_diff_path().to_path());
+                // The old flamegraph doesn't exist if the backup was disabled or skipped
+                let base_path = output_path.to_base_path().to_path();
+                flamegraph_summary.base_path = base_path.exists().then_some(base_path);
             }
 
-            flamegraph_summaries.push(flamegraph_summary);
-        }
-
-        Ok(flamegraph_summaries)
+            Ok(flamegraph_summary)
+        })
     }
 }
 
@@ -175,18 +424,62 @@ impl Flamegraph {
     pub fn is_differential(&self) -> bool {
         matches!(
             self.config.kind,
-            FlamegraphKind::Differential | FlamegraphKind::All
+            FlamegraphKind::Differential | FlamegraphKind::SideBySide | FlamegraphKind::All
         )
     }
 
+    /// Return true if the flamegraphs of the old and new run are shown side by side instead of a
+    /// differential flamegraph
+    pub fn is_side_by_side(&self) -> bool {
+        self.config.kind == FlamegraphKind::SideBySide
+    }
+
     pub fn is_regular(&self) -> bool {
         matches!(
             self.config.kind,
-            FlamegraphKind::Regular | FlamegraphKind::All
+            FlamegraphKind::Regular | FlamegraphKind::SideBySide | FlamegraphKind::All
         )
     }
 
-    pub fn options(&self, event_kind: EventKind, subtitle: String) -> Options {
+    /// Return the unit of the costs of the [`EventKind`] used as count name in the flamegraphs
+    ///
+    /// The unit configured with `count_units` takes precedence over the built-in unit of the
+    /// `EventKind`. If there's no unit at all, the count name is the `EventKind` itself. The
+    /// singular is only used if the `total` cost of the flamegraph is `1`, since inferno uses the
+    /// same count name for all frames.
+    pub fn count_name(&self, event_kind: EventKind, total: u64) -> String {
+        self.config
+            .count_units
+            .iter()
+            .rev()
+            .find(|(kind, _)| *kind == event_kind)
+            .map(|(_, unit)| (unit.singular.as_str(), unit.plural.as_str()))
+            .or_else(|| default_count_unit(event_kind))
+            .map_or_else(
+                || event_kind.to_string(),
+                |(singular, plural)| {
+                    if total == 1 {
+                        singular.to_owned()
+                    } else {
+                        plural.to_owned()
+                    }
+                },
+            )
+    }
+
+    /// Return the count name of the regular flamegraph
+    ///
+    /// If the costs are scaled to percentages, the counts are the percentages multiplied with the
+    /// `percentage_scale`.
+    pub fn regular_count_name(&self, event_kind: EventKind, total: u64) -> String {
+        match self.config.percentage_scale {
+            Some(1) => "percent".to_owned(),
+            Some(scale) => format!("percent x {scale}"),
+            None => self.count_name(event_kind, total),
+        }
+    }
+
+    pub fn options(&self, event_kind: EventKind, subtitle: String, total: u64) -> Options {
         let mut options = Options::default();
         options.negate_differentials = self.config.negate_differential;
         options.direction = self.config.direction;
@@ -204,10 +497,74 @@ impl Flamegraph {
         };
 
         options.min_width = self.config.min_width;
-        options.count_name = event_kind.to_string();
+        if let Some(palette) = self.config.palette {
+            options.colors = palette.into();
+        }
+        options.count_name = self.count_name(event_kind, total);
+        // Inferno doesn't need to sort the lines again if we've already sorted the stacks
+        options.no_sort = self.config.sort_stacks;
         options
     }
 
+    /// Return the [`PaletteMap`] coloring the frames of the regular flamegraph of the `stacks` by
+    /// the density of the `color_event_kind` if configured
+    ///
+    /// The `stacks` are the stacks of the `event_kind` which determines the width of the frames.
+    /// Returns `None` if the `color_event_kind` is not configured or equal to the `event_kind`.
+    /// The frame labels change the names of the frames, so there are no colors if a
+    /// `frame_label` is configured, too.
+    ///
+    /// # Errors
+    ///
+    /// If the `color_event_kind` is not present in the `map`
+    pub fn to_color_palette(
+        &self,
+        map: &FlamegraphMap,
+        event_kind: &EventKind,
+        stacks: &Stacks,
+    ) -> Result<Option<PaletteMap>> {
+        let Some(color_event_kind) = self
+            .config
+            .color_event_kind
+            .filter(|color_event_kind| color_event_kind != event_kind)
+        else {
+            return Ok(None);
+        };
+        if self.config.frame_label.is_some() {
+            warn!(
+                "The frames of the flamegraphs can't be colored by '{color_event_kind}' with a \
+                 frame label"
+            );
+            return Ok(None);
+        }
+
+        let color_stacks = self.to_stacks(map, &color_event_kind)?;
+        Ok(Some(stacks.to_density_palette(
+            event_kind,
+            &color_stacks,
+            &color_event_kind,
+        )))
+    }
+
+    /// Color the frames with the `palette_map` and name the `color_event_kind` in the subtitle
+    pub fn set_color_palette<'a>(
+        &self,
+        options: &mut Options<'a>,
+        event_kind: EventKind,
+        palette_map: Option<&'a mut PaletteMap>,
+    ) {
+        if let (Some(color_event_kind), Some(palette_map)) =
+            (self.config.color_event_kind, palette_map)
+        {
+            options.subtitle = Some(format!(
+                "{} (width: {event_kind}, color: {color_event_kind} per {event_kind}, darker is \
+                 denser)",
+                options.subtitle.as_deref().unwrap_or_default()
+            ));
+            options.palette_map = Some(palette_map);
+        }
+    }
+
     pub fn differential_options(&self) -> Option<inferno::differential::Options> {
         self.is_differential()
             .then(|| inferno::differential::Options {
@@ -226,9 +583,16 @@ impl Flamegraph {
     where
         P: Into<PathBuf>,
     {
-        let parser = FlamegraphParser::new(sentinel, project_root);
+        let project_root = project_root.into();
+        let parse = |path: &ToolOutputPath| {
+            FlamegraphMap::try_from(
+                &CallgrindOutput::new(path.clone())
+                    .sentinel(sentinel)
+                    .project_root(project_root.clone()),
+            )
+        };
         // We need this map in all remaining cases of `FlamegraphKinds`
-        let mut map = parser.parse(tool_output_path)?;
+        let mut map = parse(tool_output_path)?;
         if map.is_empty() {
             return Err(anyhow!("Unable to create a flamegraph: No stacks found"));
         }
@@ -236,11 +600,30 @@ impl Flamegraph {
         let base_path = tool_output_path.to_base_path();
         #[allow(clippy::if_then_some_else_none)]
         let mut base_map = if !no_differential && self.is_differential() && base_path.exists() {
-            Some(parser.parse(&base_path)?)
+            Some(parse(&base_path)?)
         } else {
             None
         };
 
+        // The base map uses the root frame of the new map, so the stacks of the differential
+        // flamegraphs are comparable even if the pids differ
+        if self.config.root_frame != api::RootFrame::None {
+            let root_frame = self
+                .config
+                .root_frame
+                .to_frame(&MetadataParser.parse(tool_output_path)?);
+            if root_frame.is_none() {
+                warn!(
+                    "Unable to add the root frame to the flamegraphs: The command of \
+                     '{tool_output_path}' is unknown"
+                );
+            }
+            if let Some(map) = base_map.as_mut() {
+                map.set_root_frame(root_frame.clone());
+            }
+            map.set_root_frame(root_frame);
+        }
+
         if self.config.event_kinds.iter().any(EventKind::is_derived) {
             map.make_summary()?;
             if let Some(map) = base_map.as_mut() {
@@ -251,15 +634,117 @@ impl Flamegraph {
         Ok((map, base_map))
     }
 
+    /// Apply all configured symbol transforms in order to the `symbol`
+    fn transform_symbol(&self, symbol: &str) -> String {
+        let mut symbol = symbol.to_owned();
+        for transform in &self.config.symbol_transforms {
+            symbol = transform.apply(&symbol).into_owned();
+        }
+        symbol
+    }
+
+    /// Convert the `map` into [`Stacks`] for this [`EventKind`] applying the configured symbol
+    /// transforms, coalescing the stacks with equal frames, truncating the stacks at the
+    /// `max_depth`, pruning the stacks below the `min_cost` and sorting the stacks if configured
+    ///
+    /// The frame names are truncated to the `max_frame_name_length` last, so all other steps see
+    /// the full names. Only the root frame of the `map` is prepended after that, so it is not
+    /// changed by any transformation.
+    ///
+    /// # Errors
+    ///
+    /// If the [`EventKind`] is not present in the `map`
+    pub fn to_stacks(&self, map: &FlamegraphMap, event_kind: &EventKind) -> Result<Stacks> {
+        let start = Instant::now();
+        let mut stacks = map.to_stacks(
+            event_kind,
+            self.config.trim_harness,
+            self.config.object_marker,
+        )?;
+        if !self.config.symbol_transforms.is_empty() {
+            stacks.transform_symbols(|symbol| self.transform_symbol(symbol));
+        }
+        if self.config.coalesce_stacks {
+            stacks.coalesce();
+        }
+        match self.config.max_depth {
+            Some(max_depth) if self.config.max_depth_count_inline => stacks.truncate(max_depth),
+            Some(max_depth) => {
+                let inlined = map
+                    .inlined_frames(self.config.object_marker)
+                    .map(|frame| self.transform_symbol(&frame))
+                    .collect::<HashSet<_>>();
+                stacks.truncate_with(max_depth, |frame| !inlined.contains(frame));
+            }
+            None => {}
+        }
+        if self.config.min_cost > 0 {
+            stacks.prune(event_kind, self.config.min_cost);
+        }
+        if self.config.sort_stacks {
+            stacks.sort();
+        }
+        if let Some(max_length) = self.config.max_frame_name_length {
+            stacks.truncate_frame_names(max_length, self.config.frame_name_ellipsis);
+        }
+        if let Some(root_frame) = map.root_frame() {
+            stacks.prepend_frame(root_frame);
+        }
+
+        if log_enabled!(Level::Debug) {
+            debug!(
+                "Created the flamegraph stacks of {event_kind} in {:?}: {}",
+                start.elapsed(),
+                stacks.report(NUM_DUPLICATED_PATHS)
+            );
+        }
+        Ok(stacks)
+    }
+
+    /// Return the lines of the folded stacks format for the regular flamegraph
+    ///
+    /// The `stacks_lines` are the lines of the `stacks` without labels. If a [`FrameLabel`] is
+    /// configured, the frames are labeled with their costs. If a `percentage_scale` is
+    /// configured, the costs are scaled to percentages after the labeling, so the labels still
+    /// show the original costs. Differential flamegraphs always use the `stacks_lines` without
+    /// labels because the labels of the new and old stacks differ.
+    ///
+    /// # Errors
+    ///
+    /// If the [`EventKind`] is not present in the costs of a stack
+    pub fn to_regular_stack_format<'a>(
+        &self,
+        stacks: &Stacks,
+        event_kind: &EventKind,
+        stacks_lines: &'a [String],
+    ) -> Result<Cow<'a, [String]>> {
+        let total = stacks.total(event_kind);
+        let labeled = self
+            .config
+            .frame_label
+            .map(|frame_label| stacks.to_labeled(event_kind, frame_label, total));
+        match (labeled, self.config.percentage_scale) {
+            (labeled, Some(scale)) => labeled
+                .as_ref()
+                .unwrap_or(stacks)
+                .to_scaled_percentages(event_kind, total, scale, self.config.rounding)
+                .to_stack_format(event_kind)
+                .map(Cow::Owned),
+            (Some(labeled), None) => labeled.to_stack_format(event_kind).map(Cow::Owned),
+            (None, None) => Ok(Cow::Borrowed(stacks_lines)),
+        }
+    }
+
     fn create_differential(
+        &self,
         output_path: &OutputPath,
         options: &mut inferno::flamegraph::Options,
-        base_map: &FlamegraphMap,
+        base_stacks: &Stacks,
         differential_options: inferno::differential::Options,
         event_kind: EventKind,
         stacks_lines: &[String],
     ) -> Result<()> {
-        let base_stacks_lines = base_map.to_stack_format(&event_kind)?;
+        let base_stacks_lines = base_stacks.to_stack_format(&event_kind)?;
 
         let cursor = Cursor::new(stacks_lines.join("\n"));
         let base_cursor = Cursor::new(base_stacks_lines.join("\n"));
@@ -268,23 +753,118 @@ impl Flamegraph {
         inferno::differential::from_readers(differential_options, base_cursor, cursor, &mut result)
             .context("Failed creating a differential flamegraph")?;
 
+        // The lines of the differential folded stacks are not sorted
+        options.no_sort = false;
         let diff_output_path = output_path.to_diff_path();
-        Flamegraph::write(
+        self.write(
             &diff_output_path,
             options,
             String::from_utf8_lossy(result.get_ref()).lines(),
         )
     }
 
+    /// Create the html page with the regular flamegraphs of the `base_stacks` and the `stacks`
+    /// side by side
+    ///
+    /// The flamegraphs are rendered like the regular flamegraphs and embedded in the html page, so
+    /// the page doesn't depend on the svg files which might have been removed or not created at
+    /// all (without `backup` for example).
+    fn create_side_by_side(
+        &self,
+        output_path: &OutputPath,
+        base_stacks: &Stacks,
+        stacks: &Stacks,
+        event_kind: EventKind,
+    ) -> Result<()> {
+        let render = |output_path: &OutputPath, stacks: &Stacks| -> Result<(String, String)> {
+            let stacks_lines = stacks.to_stack_format(&event_kind)?;
+            let total = stacks.total(&event_kind);
+            let mut options = self.options(event_kind, output_path.file_name(), total);
+            options.count_name = self.regular_count_name(event_kind, total);
+            let svg = self.render_svg(
+                &mut options,
+                self.to_regular_stack_format(stacks, &event_kind, &stacks_lines)?
+                    .iter()
+                    .map(String::as_str),
+            )?;
+            Ok((output_path.file_name(), svg))
+        };
+
+        let path = output_path.to_side_by_side_path();
+        let base = render(&output_path.to_base_path(), base_stacks)
+            .context("Failed creating the flamegraph of the baseline")?;
+        let new = render(output_path, stacks)?;
+        let html = side_by_side_html(
+            self.config.title.as_deref().unwrap_or_default(),
+            &[base, new],
+        );
+
+        let mut writer = BufWriter::new(path.create()?);
+        writer
+            .write_all(html.as_bytes())
+            .and_then(|()| writer.flush())
+            .with_context(|| format!("Failed writing content to '{}'", path.to_path().display()))
+    }
+
+    /// Render the flamegraph svg of the `stacks` with inferno and inject the `svg_header` and
+    /// `svg_footer` if configured
+    fn render_svg<'stacks>(
+        &self,
+        options: &mut Options<'_>,
+        stacks: impl Iterator<Item = &'stacks str>,
+    ) -> Result<String> {
+        let mut svg = vec![];
+        inferno::flamegraph::from_lines(options, stacks, &mut svg)?;
+        let svg = String::from_utf8_lossy(&svg);
+        if self.config.svg_header.is_none() && self.config.svg_footer.is_none() {
+            Ok(svg.into_owned())
+        } else {
+            inject_svg(
+                &svg,
+                self.config.svg_header.as_deref(),
+                self.config.svg_footer.as_deref(),
+            )
+        }
+    }
+
+    /// Write the flamegraph svg created by inferno from the `stacks` to the `output_path`
+    ///
+    /// If configured, the `svg_header` is injected right after the opening `<svg>` tag and the
+    /// `svg_footer` right before the closing `</svg>` tag of the svg created by inferno. If
+    /// creating the flamegraph fails, the incomplete svg file is removed.
     fn write<'stacks>(
+        &self,
+        output_path: &OutputPath,
+        options: &mut Options<'_>,
+        stacks: impl Iterator<Item = &'stacks str>,
+    ) -> Result<()> {
+        let result = self.write_svg(output_path, options, stacks);
+        if result.is_err() {
+            // Ignore the error since the file might not have been created at all
+            let _ = std::fs::remove_file(output_path.to_path());
+        }
+        result
+    }
+
+    fn write_svg<'stacks>(
+        &self,
         output_path: &OutputPath,
         options: &mut Options<'_>,
         stacks: impl Iterator<Item = &'stacks str>,
     ) -> Result<()> {
         let path = output_path.to_path();
         let mut writer = BufWriter::new(output_path.create()?);
-        inferno::flamegraph::from_lines(options, stacks, &mut writer)
-            .with_context(|| format!("Failed creating a flamegraph at '{}'", path.display()))?;
+        if self.config.svg_header.is_none() && self.config.svg_footer.is_none() {
+            inferno::flamegraph::from_lines(options, stacks, &mut writer)
+                .with_context(|| format!("Failed creating a flamegraph at '{}'", path.display()))?;
+        } else {
+            let svg = self
+                .render_svg(options, stacks)
+                .with_context(|| format!("Failed creating a flamegraph at '{}'", path.display()))?;
+            writer
+                .write_all(svg.as_bytes())
+                .with_context(|| format!("Failed writing content to '{}'", path.display()))?;
+        }
 
         writer
             .flush()
@@ -292,6 +872,257 @@ impl Flamegraph {
     }
 }
 
+impl FlamegraphBuilder {
+    /// Create a new `FlamegraphBuilder`
+    ///
+    /// The `heading` is used as title of the flamegraph if no title is set.
+    pub fn new<T>(heading: T) -> Self
+    where
+        T: Into<String>,
+    {
+        Self::with_config(heading, Config::default())
+    }
+
+    /// Create a new `FlamegraphBuilder` starting with the options of this [`Config`]
+    pub fn with_config<T>(heading: T, config: Config) -> Self
+    where
+        T: Into<String>,
+    {
+        Self {
+            heading: heading.into(),
+            config,
+        }
+    }
+
+    /// The kind of flamegraphs to create (Default: [`FlamegraphKind::All`])
+    pub fn kind(mut self, kind: FlamegraphKind) -> Self {
+        self.config.kind = kind;
+        self
+    }
+
+    /// The [`EventKind`]s for which flamegraphs are created (Default: [`EventKind::Ir`])
+    pub fn event_kinds<T>(mut self, event_kinds: T) -> Self
+    where
+        T: IntoIterator<Item = EventKind>,
+    {
+        self.config.event_kinds = event_kinds.into_iter().collect();
+        self
+    }
+
+    /// The direction of the flamegraph (Default: [`api::Direction::TopToBottom`])
+    pub fn direction(mut self, direction: api::Direction) -> Self {
+        self.config.direction = direction.into();
+        self
+    }
+
+    /// The title of the flamegraph (Default: the heading)
+    pub fn title<T>(mut self, title: T) -> Self
+    where
+        T: Into<String>,
+    {
+        self.config.title = Some(title.into());
+        self
+    }
+
+    /// The subtitle of the flamegraph (Default: the file name of the flamegraph)
+    pub fn subtitle<T>(mut self, subtitle: T) -> Self
+    where
+        T: Into<String>,
+    {
+        self.config.subtitle = Some(subtitle.into());
+        self
+    }
+
+    /// The minimum width of a frame in pixels (Default: `0.1`)
+    pub fn min_width(mut self, min_width: f64) -> Self {
+        self.config.min_width = min_width;
+        self
+    }
+
+    /// The minimum absolute cost of a stack (Default: `0`)
+    ///
+    /// See also [`Stacks::prune`].
+    pub fn min_cost(mut self, min_cost: u64) -> Self {
+        self.config.min_cost = min_cost;
+        self
+    }
+
+    /// The content injected right after the opening `<svg>` tag (Default: None)
+    ///
+    /// See also [`inject_svg`].
+    pub fn svg_header<T>(mut self, header: T) -> Self
+    where
+        T: Into<String>,
+    {
+        self.config.svg_header = Some(header.into());
+        self
+    }
+
+    /// The content injected right before the closing `</svg>` tag (Default: None)
+    ///
+    /// See also [`inject_svg`].
+    pub fn svg_footer<T>(mut self, footer: T) -> Self
+    where
+        T: Into<String>,
+    {
+        self.config.svg_footer = Some(footer.into());
+        self
+    }
+
+    /// The maximum number of frames of a stack (Default: no limit)
+    ///
+    /// See also [`Stacks::truncate`].
+    pub fn max_depth(mut self, max_depth: usize) -> Self {
+        self.config.max_depth = Some(max_depth);
+        self
+    }
+
+    /// If false, the frames of inlined code don't count toward the `max_depth` (Default: true)
+    ///
+    /// See also [`Stacks::truncate_with`].
+    pub fn max_depth_count_inline(mut self, value: bool) -> Self {
+        self.config.max_depth_count_inline = value;
+        self
+    }
+
+    /// If true, negate the differential flamegraphs (Default: false)
+    pub fn negate_differential(mut self, value: bool) -> Self {
+        self.config.negate_differential = value;
+        self
+    }
+
+    /// If true, normalize the differential flamegraphs (Default: false)
+    pub fn normalize_differential(mut self, value: bool) -> Self {
+        self.config.normalize_differential = value;
+        self
+    }
+
+    /// Add a [`SymbolTransform`] applied to the symbols of the frames
+    pub fn symbol_transform(mut self, symbol_transform: SymbolTransform) -> Self {
+        self.config.symbol_transforms.push(symbol_transform);
+        self
+    }
+
+    /// If true, sort the stacks before creating the flamegraph (Default: false)
+    pub fn sort_stacks(mut self, value: bool) -> Self {
+        self.config.sort_stacks = value;
+        self
+    }
+
+    /// If true, merge the stacks with equal frames before creating the flamegraph (Default: false)
+    ///
+    /// See also [`Stacks::coalesce`].
+    pub fn coalesce_stacks(mut self, value: bool) -> Self {
+        self.config.coalesce_stacks = value;
+        self
+    }
+
+    /// If true, move the flamegraphs of the last run to the `.old` flamegraphs (Default: true)
+    pub fn backup(mut self, value: bool) -> Self {
+        self.config.backup = value;
+        self
+    }
+
+    /// Tag the file names of the flamegraphs with this tag like a commit hash (Default: None)
+    ///
+    /// The tag is sanitized with [`sanitize_tag`].
+    pub fn tag<T>(mut self, tag: T) -> Self
+    where
+        T: AsRef<str>,
+    {
+        self.config.tag = sanitize_tag(tag.as_ref());
+        self
+    }
+
+    /// Append the cost in this [`FrameLabel`] format to each frame of a regular flamegraph
+    pub fn frame_label(mut self, frame_label: FrameLabel) -> Self {
+        self.config.frame_label = Some(frame_label);
+        self
+    }
+
+    /// Scale the costs of the regular flamegraph to percentages multiplied with the `scale`
+    ///
+    /// See also [`Stacks::to_scaled_percentages`].
+    pub fn percentage_scale(mut self, scale: u64) -> Self {
+        self.config.percentage_scale = Some(scale);
+        self
+    }
+
+    /// The [`Rounding`] of the costs scaled to percentages (Default: [`Rounding::Round`])
+    pub fn rounding(mut self, rounding: Rounding) -> Self {
+        self.config.rounding = rounding;
+        self
+    }
+
+    /// If true, remove the frames of the benchmark harness above the entry point (Default:
+    /// false)
+    pub fn trim_harness(mut self, value: bool) -> Self {
+        self.config.trim_harness = value;
+        self
+    }
+
+    /// The [`ObjectMarker`] of the frames (Default: [`ObjectMarker::Bracketed`])
+    pub fn object_marker(mut self, object_marker: ObjectMarker) -> Self {
+        self.config.object_marker = object_marker;
+        self
+    }
+
+    /// Color the frames of the regular flamegraphs by the density of this [`EventKind`]
+    ///
+    /// See also [`Stacks::to_density_palette`].
+    pub fn color_event_kind(mut self, color_event_kind: EventKind) -> Self {
+        self.config.color_event_kind = Some(color_event_kind);
+        self
+    }
+
+    /// The color [`api::Palette`] of the regular flamegraphs (Default: the default of inferno)
+    pub fn palette(mut self, palette: api::Palette) -> Self {
+        self.config.palette = Some(palette);
+        self
+    }
+
+    /// Truncate the displayed frame names to this number of characters
+    ///
+    /// See also [`Stacks::truncate_frame_names`].
+    pub fn max_frame_name_length(mut self, max_length: usize) -> Self {
+        self.config.max_frame_name_length = Some(max_length);
+        self
+    }
+
+    /// The [`api::Ellipsis`] of the truncated frame names (Default: [`api::Ellipsis::Middle`])
+    pub fn frame_name_ellipsis(mut self, ellipsis: api::Ellipsis) -> Self {
+        self.config.frame_name_ellipsis = ellipsis;
+        self
+    }
+
+    /// The [`api::RootFrame`] prepended to all stacks (Default: [`api::RootFrame::None`])
+    pub fn root_frame(mut self, root_frame: api::RootFrame) -> Self {
+        self.config.root_frame = root_frame;
+        self
+    }
+
+    /// Use this unit of the costs of the [`EventKind`] as count name
+    pub fn count_unit<T, U>(mut self, event_kind: EventKind, singular: T, plural: U) -> Self
+    where
+        T: Into<String>,
+        U: Into<String>,
+    {
+        self.config.count_units.push((
+            event_kind,
+            api::CountUnit {
+                singular: singular.into(),
+                plural: plural.into(),
+            },
+        ));
+        self
+    }
+
+    /// Build the [`Flamegraph`]
+    pub fn build(self) -> Flamegraph {
+        Flamegraph::new(self.heading, self.config)
+    }
+}
+
 impl FlamegraphGenerator for LoadBaselineFlamegraphGenerator {
     fn create(
         &self,
@@ -302,8 +1133,10 @@ impl FlamegraphGenerator for LoadBaselineFlamegraphGenerator {
     ) -> Result<Vec<FlamegraphSummary>> {
         // We need the dummy path just to cleanup and organize the output files independently from
         // the EventKind of the OutputPath
-        let mut output_path = OutputPath::new(tool_output_path, EventKind::Ir);
+        let mut output_path = OutputPath::new(tool_output_path, EventKind::Ir)
+            .with_tag(flamegraph.config.tag.clone());
         output_path.to_diff_path().clear(true)?;
+        output_path.to_side_by_side_path().clear(true)?;
 
         if flamegraph.config.kind == FlamegraphKind::None
             || flamegraph.config.event_kinds.is_empty()
@@ -316,29 +1149,46 @@ impl FlamegraphGenerator for LoadBaselineFlamegraphGenerator {
             .parse(tool_output_path, sentinel, project_root, false)
             .map(|(m, b)| (m, b.unwrap()))?;
 
-        let mut flamegraph_summaries = vec![];
-        for event_kind in &flamegraph.config.event_kinds {
-            let mut flamegraph_summary = FlamegraphSummary::new(*event_kind);
-            output_path.set_event_kind(*event_kind);
+        create_each_event_kind(&flamegraph.config.event_kinds, |event_kind| {
+            let mut flamegraph_summary = FlamegraphSummary::new(event_kind);
+            output_path.set_event_kind(event_kind);
+
+            let stacks = flamegraph.to_stacks(&map, &event_kind)?;
+            if flamegraph.is_side_by_side() {
+                flamegraph.create_side_by_side(
+                    &output_path,
+                    &flamegraph.to_stacks(&base_map, &event_kind)?,
+                    &stacks,
+                    event_kind,
+                )?;
+
+                flamegraph_summary.regular_path = Some(output_path.to_path());
+                flamegraph_summary.base_path = Some(output_path.to_base_path().to_path());
+                flamegraph_summary.diff_path = Some(output_path.to_side_by_side_path().to_path());
+
+                return Ok(flamegraph_summary);
+            }
 
-            Flamegraph::create_differential(
+            flamegraph.create_differential(
                 &output_path,
-                &mut flamegraph.options(*event_kind, output_path.to_diff_path().file_name()),
-                &base_map,
+                &mut flamegraph.options(
+                    event_kind,
+                    output_path.to_diff_path().file_name(),
+                    stacks.total(&event_kind),
+                ),
+                &flamegraph.to_stacks(&base_map, &event_kind)?,
                 // This unwrap is safe since we always produce a differential flamegraph
                 flamegraph.differential_options().unwrap(),
-                *event_kind,
-                &map.to_stack_format(event_kind)?,
+                event_kind,
+                &stacks.to_stack_format(&event_kind)?,
             )?;
 
             flamegraph_summary.regular_path = Some(output_path.to_path());
             flamegraph_summary.base_path = Some(output_path.to_base_path().to_path());
             flamegraph_summary.diff_path = Some(output_path.to_diff_path().to_path());
 
-            flamegraph_summaries.push(flamegraph_summary);
-        }
-
-        Ok(flamegraph_summaries)
+            Ok(flamegraph_summary)
+        })
     }
 }
 
@@ -346,28 +1196,50 @@ impl OutputPath {
     pub fn new(tool_output_path: &ToolOutputPath, event_kind: EventKind) -> Self {
         Self {
             kind: match &tool_output_path.kind {
-                ToolOutputPathKind::Out | ToolOutputPathKind::Log => OutputPathKind::Regular,
-                ToolOutputPathKind::OldOut | ToolOutputPathKind::OldLog => OutputPathKind::Old,
-                ToolOutputPathKind::BaseLog(name) | ToolOutputPathKind::Base(name) => {
-                    OutputPathKind::Base(name.clone())
+                ToolOutputPathKind::Out | ToolOutputPathKind::Log | ToolOutputPathKind::Meta => {
+                    OutputPathKind::Regular
                 }
+                ToolOutputPathKind::OldOut
+                | ToolOutputPathKind::OldLog
+                | ToolOutputPathKind::OldMeta => OutputPathKind::Old,
+                ToolOutputPathKind::BaseLog(name)
+                | ToolOutputPathKind::Base(name)
+                | ToolOutputPathKind::BaseMeta(name) => OutputPathKind::Base(name.clone()),
             },
             event_kind,
             baseline_kind: tool_output_path.baseline_kind.clone(),
             dir: tool_output_path.dir.clone(),
+            baseline_dir: tool_output_path.baseline_dir.clone(),
             name: tool_output_path.name.clone(),
+            tag: None,
+            is_html: false,
         }
     }
 
+    pub fn with_tag(mut self, tag: Option<String>) -> Self {
+        self.tag = tag;
+        self
+    }
+
     pub fn init(&self) -> Result<()> {
-        std::fs::create_dir_all(&self.dir)
-            .with_context(|| {
-                format!(
-                    "Failed creating flamegraph directory '{}'",
-                    self.dir.display()
-                )
-            })
-            .map_err(Into::into)
+        for dir in [&self.dir, &self.baseline_dir] {
+            std::fs::create_dir_all(dir).with_context(|| {
+                format!("Failed creating flamegraph directory '{}'", dir.display())
+            })?;
+        }
+        Ok(())
+    }
+
+    /// Return the directory of the flamegraphs of this [`OutputPathKind`]
+    ///
+    /// Like the other files of a named baseline, its flamegraphs are stored in the
+    /// `baseline_dir`. The differential flamegraphs are the result of a comparison and not part of
+    /// a baseline, so they stay in the `dir`.
+    pub fn kind_dir(&self) -> &Path {
+        match &self.kind {
+            OutputPathKind::Base(_) => &self.baseline_dir,
+            _ => &self.dir,
+        }
     }
 
     pub fn create(&self) -> Result<File> {
@@ -386,19 +1258,22 @@ impl OutputPath {
 
     pub fn clear_diff(&self) -> Result<()> {
         let extension = match &self.baseline_kind {
-            BaselineKind::Old => "diff.old.svg".to_owned(),
-            BaselineKind::Name(name) => format!("diff.base@{name}.svg"),
+            BaselineKind::Old => "diff.old".to_owned(),
+            BaselineKind::Name(name) => format!("diff.base@{name}"),
         };
         for entry in std::fs::read_dir(&self.dir)
             .with_context(|| format!("Failed reading directory '{}'", self.dir.display()))?
         {
             let entry = entry?;
             let file_name = entry.file_name().to_string_lossy().to_string();
-            if let Some(suffix) =
-                file_name.strip_prefix(format!("callgrind.{}.flamegraph.", &self.name).as_str())
+            if let Some(suffix) = file_name
+                .strip_prefix(format!("callgrind.{}.{}.", &self.name, self.stem()).as_str())
             {
                 let path = entry.path();
-                if suffix.ends_with(extension.as_str()) {
+                if [".svg", ".html"]
+                    .iter()
+                    .any(|ext| suffix.ends_with(format!("{extension}{ext}").as_str()))
+                {
                     std::fs::remove_file(&path).with_context(|| {
                         format!("Failed removing flamegraph file: '{}'", path.display())
                     })?;
@@ -420,19 +1295,41 @@ impl OutputPath {
         Ok(())
     }
 
-    pub fn shift(&self, ignore_event_kind: bool) -> Result<()> {
+    /// Move the flamegraphs of the last run to the `.old` flamegraphs if `backup` is true
+    ///
+    /// Without `backup`, the flamegraphs of the last run are removed instead. Creating the backup
+    /// is not essential, so if removing the previous backup, creating the new one or removing the
+    /// flamegraphs without backup fails because the file system is read-only or full, we only log
+    /// a warning and go on with the creation of the new flamegraphs.
+    pub fn shift(&self, ignore_event_kind: bool, backup: bool) -> Result<()> {
         match &self.baseline_kind {
             BaselineKind::Old => {
-                self.to_base_path().clear(ignore_event_kind)?;
+                for path in self.to_base_path().real_paths(ignore_event_kind)? {
+                    remove_flamegraph_file(&path)?;
+                }
                 for path in self.real_paths(ignore_event_kind)? {
+                    if !backup {
+                        remove_flamegraph_file(&path)?;
+                        continue;
+                    }
+
                     let new_path = path.with_extension("old.svg");
-                    std::fs::rename(&path, &new_path).with_context(|| {
-                        format!(
-                            "Failed moving flamegraph file from '{}' to '{}'",
-                            path.display(),
-                            new_path.display()
-                        )
-                    })?;
+                    match std::fs::rename(&path, &new_path) {
+                        Ok(()) => {}
+                        Err(error) if is_read_only_or_full(&error) => warn!(
+                            "Skipping the backup of the flamegraph file '{}': {error}",
+                            path.display()
+                        ),
+                        Err(error) => {
+                            return Err(error).with_context(|| {
+                                format!(
+                                    "Failed moving flamegraph file from '{}' to '{}'",
+                                    path.display(),
+                                    new_path.display()
+                                )
+                            });
+                        }
+                    }
                 }
                 Ok(())
             }
@@ -458,6 +1355,15 @@ impl OutputPath {
         }
     }
 
+    /// The path of the html page with the flamegraphs side by side which replaces the
+    /// differential flamegraph
+    pub fn to_side_by_side_path(&self) -> Self {
+        Self {
+            is_html: true,
+            ..self.to_diff_path()
+        }
+    }
+
     pub fn to_base_path(&self) -> Self {
         Self {
             kind: match &self.baseline_kind {
@@ -468,50 +1374,56 @@ impl OutputPath {
         }
     }
 
-    pub fn extension(&self) -> String {
+    /// The part of the file name which identifies the flamegraph like `flamegraph` or with a tag
+    /// `flamegraph@tag`
+    pub fn stem(&self) -> String {
+        self.tag.as_ref().map_or_else(
+            || "flamegraph".to_owned(),
+            |tag| format!("flamegraph@{tag}"),
+        )
+    }
+
+    /// The part of the file name after the [`OutputPath::stem`] like `Ir.svg`, `Ir.old.svg` or
+    /// `Ir.diff.old.html`
+    pub fn suffix(&self) -> String {
+        let event_kind = self.event_kind.to_name();
+        let extension = if self.is_html { "html" } else { "svg" };
         match &self.kind {
-            OutputPathKind::Regular => format!("flamegraph.{}.svg", self.event_kind.to_name()),
-            OutputPathKind::Old => format!("flamegraph.{}.old.svg", self.event_kind.to_name()),
-            OutputPathKind::Base(name) => {
-                format!("flamegraph.{}.base@{name}.svg", self.event_kind.to_name())
-            }
-            OutputPathKind::DiffOld => {
-                format!("flamegraph.{}.diff.old.svg", self.event_kind.to_name())
-            }
-            OutputPathKind::DiffBase(name) => {
-                format!(
-                    "flamegraph.{}.diff.base@{name}.svg",
-                    self.event_kind.to_name()
-                )
-            }
+            OutputPathKind::Regular => format!("{event_kind}.{extension}"),
+            OutputPathKind::Old => format!("{event_kind}.old.{extension}"),
+            OutputPathKind::Base(name) => format!("{event_kind}.base@{name}.{extension}"),
+            OutputPathKind::DiffOld => format!("{event_kind}.diff.old.{extension}"),
+            OutputPathKind::DiffBase(name) => format!("{event_kind}.diff.base@{name}.{extension}"),
             OutputPathKind::DiffBases(name, base) => {
-                format!(
-                    "flamegraph.{}.base@{name}.diff.base@{base}.svg",
-                    self.event_kind.to_name()
-                )
+                format!("{event_kind}.base@{name}.diff.base@{base}.{extension}")
             }
         }
     }
 
+    pub fn extension(&self) -> String {
+        format!("{}.{}", self.stem(), self.suffix())
+    }
+
     pub fn set_event_kind(&mut self, event_kind: EventKind) {
         self.event_kind = event_kind;
     }
 
     pub fn real_paths(&self, ignore_event_kind: bool) -> Result<Vec<PathBuf>> {
         let mut paths = vec![];
-        let extension = self.extension();
+        let path_suffix = self.suffix();
         let to_match = if ignore_event_kind {
-            extension.splitn(3, '.').last().unwrap()
+            path_suffix.split_once('.').unwrap().1
         } else {
-            extension.strip_prefix("flamegraph.").unwrap()
+            path_suffix.as_str()
         };
-        for entry in std::fs::read_dir(&self.dir)
-            .with_context(|| format!("Failed reading directory '{}'", self.dir.display()))?
+        let dir = self.kind_dir();
+        for entry in std::fs::read_dir(dir)
+            .with_context(|| format!("Failed reading directory '{}'", dir.display()))?
         {
             let path = entry?;
             let file_name = path.file_name().to_string_lossy().to_string();
-            if let Some(suffix) =
-                file_name.strip_prefix(format!("callgrind.{}.flamegraph.", &self.name).as_str())
+            if let Some(suffix) = file_name
+                .strip_prefix(format!("callgrind.{}.{}.", &self.name, self.stem()).as_str())
             {
                 let is_match = if ignore_event_kind {
                     suffix
@@ -533,7 +1445,7 @@ impl OutputPath {
     }
 
     pub fn to_path(&self) -> PathBuf {
-        self.dir.join(self.file_name())
+        self.kind_dir().join(self.file_name())
     }
 }
 
@@ -547,7 +1459,8 @@ impl FlamegraphGenerator for SaveBaselineFlamegraphGenerator {
     ) -> Result<Vec<FlamegraphSummary>> {
         // We need the dummy path just to cleanup and organize the output files independently from
         // the EventKind of the OutputPath
-        let mut output_path = OutputPath::new(tool_output_path, EventKind::Ir);
+        let mut output_path = OutputPath::new(tool_output_path, EventKind::Ir)
+            .with_tag(flamegraph.config.tag.clone());
         output_path.init()?;
         output_path.clear(true)?;
         output_path.clear_diff()?;
@@ -561,21 +1474,2731 @@ impl FlamegraphGenerator for SaveBaselineFlamegraphGenerator {
 
         let (map, _) = flamegraph.parse(tool_output_path, sentinel, project_root, true)?;
 
-        let mut flamegraph_summaries = vec![];
-        for event_kind in &flamegraph.config.event_kinds {
-            let mut flamegraph_summary = FlamegraphSummary::new(*event_kind);
-            output_path.set_event_kind(*event_kind);
+        create_each_event_kind(&flamegraph.config.event_kinds, |event_kind| {
+            let mut flamegraph_summary = FlamegraphSummary::new(event_kind);
+            output_path.set_event_kind(event_kind);
 
-            Flamegraph::write(
+            let stacks = flamegraph.to_stacks(&map, &event_kind)?;
+            let stacks_lines = stacks.to_stack_format(&event_kind)?;
+            let total = stacks.total(&event_kind);
+            let mut palette_map = flamegraph.to_color_palette(&map, &event_kind, &stacks)?;
+            let mut options = flamegraph.options(event_kind, output_path.file_name(), total);
+            options.count_name = flamegraph.regular_count_name(event_kind, total);
+            flamegraph.set_color_palette(&mut options, event_kind, palette_map.as_mut());
+            flamegraph.write(
                 &output_path,
-                &mut flamegraph.options(*event_kind, output_path.file_name()),
-                map.to_stack_format(event_kind)?.iter().map(String::as_str),
+                &mut options,
+                flamegraph
+                    .to_regular_stack_format(&stacks, &event_kind, &stacks_lines)?
+                    .iter()
+                    .map(String::as_str),
             )?;
 
             flamegraph_summary.regular_path = Some(output_path.to_path());
-            flamegraph_summaries.push(flamegraph_summary);
-        }
+            Ok(flamegraph_summary)
+        })
+    }
+}
 
-        Ok(flamegraph_summaries)
+impl Stack {
+    /// Create a new `Stack` from the `frames` (outermost frame first) and the `costs` of the
+    /// innermost frame
+    pub fn new<I, T>(frames: I, costs: Costs) -> Self
+    where
+        I: IntoIterator<Item = T>,
+        T: Into<String>,
+    {
+        Self {
+            frames: frames.into_iter().map(Into::into).collect(),
+            costs,
+        }
+    }
+
+    /// Return the cost of the innermost frame for this [`EventKind`] if present
+    pub fn cost(&self, event_kind: &EventKind) -> Option<u64> {
+        self.costs.cost_by_kind(event_kind)
+    }
+
+    /// Merge the `other` `Stack` into this `Stack` adding its costs to the costs of this `Stack`
+    ///
+    /// Costs of [`EventKind`]s which are only present in `other` are added, too.
+    ///
+    /// # Panics
+    ///
+    /// If the frames of the `other` `Stack` are not equal to the frames of this `Stack`
+    pub fn merge(&mut self, other: &Stack) {
+        assert_eq!(
+            self.frames, other.frames,
+            "Only stacks with equal frames can be merged"
+        );
+        self.costs.add(&other.costs);
+    }
+
+    /// Convert this `Stack` into a line of the folded stacks format for this [`EventKind`]
+    ///
+    /// # Errors
+    ///
+    /// If this stack has no frames or the [`EventKind`] is not present in the costs
+    pub fn to_stack_format(&self, event_kind: &EventKind) -> Result<String> {
+        if self.frames.is_empty() {
+            return Err(anyhow!(
+                "Failed creating flamegraph stack: A stack needs at least one frame"
+            ));
+        }
+        let cost = self.cost(event_kind).ok_or_else(|| {
+            anyhow!("Failed creating flamegraph stack: Missing event type '{event_kind}'")
+        })?;
+
+        Ok(format!("{} {cost}", self.frames.join(";")))
+    }
+}
+
+impl Stacks {
+    /// Create `Stacks` from `(frames, costs)` tuples without going through a parser
+    ///
+    /// The frames of each tuple are ordered from the outermost to the innermost frame and the
+    /// costs are the costs of the innermost frame. This constructor is useful to create known
+    /// inputs for flamegraphs for example in tests.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use iai_callgrind_runner::api::EventKind;
+    /// use iai_callgrind_runner::runner::callgrind::flamegraph::Stacks;
+    /// use iai_callgrind_runner::runner::callgrind::model::Costs;
+    ///
+    /// let stacks = Stacks::from_costs_map([
+    ///     (vec!["main"], Costs::with_event_kinds([(EventKind::Ir, 1)])),
+    ///     (vec!["main", "foo"], Costs::with_event_kinds([(EventKind::Ir, 2)])),
+    /// ]);
+    ///
+    /// assert_eq!(
+    ///     stacks.to_stack_format(&EventKind::Ir).unwrap(),
+    ///     vec!["main 1".to_owned(), "main;foo 2".to_owned()]
+    /// );
+    /// ```
+    pub fn from_costs_map<I, F, T>(iter: I) -> Self
+    where
+        I: IntoIterator<Item = (F, Costs)>,
+        F: IntoIterator<Item = T>,
+        T: Into<String>,
+    {
+        Self(
+            iter.into_iter()
+                .map(|(frames, costs)| Stack::new(frames, costs))
+                .collect(),
+        )
+    }
+
+    pub fn push(&mut self, stack: Stack) {
+        self.0.push(stack);
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    pub fn iter(&self) -> std::slice::Iter<'_, Stack> {
+        self.0.iter()
+    }
+
+    /// Return the hottest [`Stack`] for this [`EventKind`]
+    ///
+    /// The hottest stack is the stack with the highest (self) costs of its innermost frame. Its
+    /// `frames` are the call chain from the root to this innermost frame. If multiple stacks have
+    /// the same costs, the stack with the lexicographically smallest frames wins, so the result
+    /// doesn't depend on the order of the stacks. Stacks without frames or without costs for the
+    /// [`EventKind`] are ignored.
+    ///
+    /// Returns `None` if there is no such stack.
+    pub fn hot_path(&self, event_kind: &EventKind) -> Option<&Stack> {
+        self.iter()
+            .filter(|stack| !stack.frames.is_empty())
+            .filter_map(|stack| stack.cost(event_kind).map(|cost| (cost, stack)))
+            .min_by(|(cost_a, stack_a), (cost_b, stack_b)| {
+                cost_b
+                    .cmp(cost_a)
+                    .then_with(|| stack_a.frames.cmp(&stack_b.frames))
+            })
+            .map(|(_, stack)| stack)
+    }
+
+    /// Remove the stacks with an inclusive cost below `min_cost` for this [`EventKind`]
+    ///
+    /// Only the leaves, the stacks without callees, are removed and their costs are added to the
+    /// stack of their caller (the stack without the innermost frame). This is repeated starting
+    /// with the deepest stacks, so the caller may be removed in turn if its costs including the
+    /// costs of its removed callees are still below `min_cost`. A stack with a remaining callee
+    /// is never removed, even if its self cost is below `min_cost`. Stacks with a single frame are
+    /// never removed, so the [`Stacks::total`] doesn't change. Stacks with equal frames are
+    /// merged. Missing costs count as `0`.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use iai_callgrind_runner::api::EventKind;
+    /// use iai_callgrind_runner::runner::callgrind::flamegraph::Stacks;
+    /// use iai_callgrind_runner::runner::callgrind::model::Costs;
+    ///
+    /// let mut stacks = Stacks::from_costs_map([
+    ///     (vec!["main"], Costs::with_event_kinds([(EventKind::Ir, 10)])),
+    ///     (vec!["main", "foo"], Costs::with_event_kinds([(EventKind::Ir, 200)])),
+    ///     (vec!["main", "bar"], Costs::with_event_kinds([(EventKind::Ir, 5)])),
+    /// ]);
+    /// stacks.prune(&EventKind::Ir, 100);
+    ///
+    /// assert_eq!(
+    ///     stacks.to_stack_format(&EventKind::Ir).unwrap(),
+    ///     vec!["main 15".to_owned(), "main;foo 200".to_owned()]
+    /// );
+    /// ```
+    pub fn prune(&mut self, event_kind: &EventKind, min_cost: u64) {
+        let mut inclusive: IndexMap<&[String], u64> = IndexMap::new();
+        for stack in &self.0 {
+            let cost = stack.cost(event_kind).unwrap_or(0);
+            for index in 1..=stack.frames.len() {
+                *inclusive.entry(&stack.frames[..index]).or_insert(0) += cost;
+            }
+        }
+
+        // The inclusive cost of a caller is never below the inclusive cost of its callees, so each
+        // stack is folded into its innermost caller with an inclusive cost of at least `min_cost`
+        let pruned = self.transform(|stack| {
+            (stack.frames.len() == 1 || inclusive[stack.frames.as_slice()] >= min_cost)
+                .then(|| stack.clone())
+        });
+        *self = pruned;
+    }
+
+    /// Truncate the stacks with more than `max_depth` frames
+    ///
+    /// The frames of a stack beyond the `max_depth` are replaced by a single [`TRUNCATED_FRAME`]
+    /// and the stacks which are equal after the truncation are merged, so the costs of all
+    /// truncated callees are folded into this frame and the [`Stacks::total`] doesn't change.
+    /// Every frame counts toward the depth including the frames of inlined code, which are
+    /// separate frames of the source file the code was inlined from (See
+    /// [`Stacks::truncate_with`]). A `max_depth` of `0` is treated like `1`.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use iai_callgrind_runner::api::EventKind;
+    /// use iai_callgrind_runner::runner::callgrind::flamegraph::Stacks;
+    /// use iai_callgrind_runner::runner::callgrind::model::Costs;
+    ///
+    /// let mut stacks = Stacks::from_costs_map([
+    ///     (vec!["main"], Costs::with_event_kinds([(EventKind::Ir, 10)])),
+    ///     (vec!["main", "foo"], Costs::with_event_kinds([(EventKind::Ir, 20)])),
+    ///     (vec!["main", "foo", "bar"], Costs::with_event_kinds([(EventKind::Ir, 5)])),
+    /// ]);
+    /// stacks.truncate(1);
+    ///
+    /// assert_eq!(
+    ///     stacks.to_stack_format(&EventKind::Ir).unwrap(),
+    ///     vec!["main 10".to_owned(), "main;... 25".to_owned()]
+    /// );
+    /// ```
+    pub fn truncate(&mut self, max_depth: usize) {
+        self.truncate_with(max_depth, |_| true);
+    }
+
+    /// Truncate the stacks with more than `max_depth` frames for which `counts` returns true
+    ///
+    /// This is [`Stacks::truncate`] but only the frames for which `counts` returns true count
+    /// toward the depth, like the frames which aren't inlined code. A stack is cut before its
+    /// first counted frame beyond the `max_depth`, so the frames which don't count and directly
+    /// follow the last counted frame are kept.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use iai_callgrind_runner::api::EventKind;
+    /// use iai_callgrind_runner::runner::callgrind::flamegraph::Stacks;
+    /// use iai_callgrind_runner::runner::callgrind::model::Costs;
+    ///
+    /// let mut stacks = Stacks::from_costs_map([
+    ///     (vec!["main"], Costs::with_event_kinds([(EventKind::Ir, 10)])),
+    ///     (vec!["main", "inlined"], Costs::with_event_kinds([(EventKind::Ir, 20)])),
+    ///     (vec!["main", "inlined", "bar"], Costs::with_event_kinds([(EventKind::Ir, 5)])),
+    /// ]);
+    /// stacks.truncate_with(1, |frame| frame != "inlined");
+    ///
+    /// assert_eq!(
+    ///     stacks.to_stack_format(&EventKind::Ir).unwrap(),
+    ///     vec![
+    ///         "main 10".to_owned(),
+    ///         "main;inlined 20".to_owned(),
+    ///         "main;inlined;... 5".to_owned()
+    ///     ]
+    /// );
+    /// ```
+    pub fn truncate_with<F>(&mut self, max_depth: usize, mut counts: F)
+    where
+        F: FnMut(&str) -> bool,
+    {
+        let max_depth = max_depth.max(1);
+        if self.0.iter().all(|stack| stack.frames.len() <= max_depth) {
+            return;
+        }
+
+        *self = self.transform(|stack| {
+            let mut stack = stack.clone();
+            let mut depth = 0;
+            let cut = stack.frames.iter().position(|frame| {
+                if counts(frame) {
+                    depth += 1;
+                }
+                depth > max_depth
+            });
+            if let Some(cut) = cut {
+                stack.frames.truncate(cut);
+                stack.frames.push(TRUNCATED_FRAME.to_owned());
+            }
+            Some(stack)
+        });
+    }
+
+    /// Truncate the names of all frames longer than `max_length` characters with the `ellipsis`
+    ///
+    /// The truncation only changes how the frames are displayed. In contrast to
+    /// [`Stacks::transform_symbols`], the stacks are not merged even if their frames are equal
+    /// after the truncation, so it should be the last step before the creation of the
+    /// flamegraphs. Note that inferno still shows adjacent frames with equal names as a single
+    /// frame.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use iai_callgrind_runner::api::{Ellipsis, EventKind};
+    /// use iai_callgrind_runner::runner::callgrind::flamegraph::Stacks;
+    /// use iai_callgrind_runner::runner::callgrind::model::Costs;
+    ///
+    /// let mut stacks = Stacks::from_costs_map([(
+    ///     vec!["main", "my_crate::my_mod::my_func"],
+    ///     Costs::with_event_kinds([(EventKind::Ir, 10)]),
+    /// )]);
+    /// stacks.truncate_frame_names(16, Ellipsis::Middle);
+    ///
+    /// assert_eq!(
+    ///     stacks.to_stack_format(&EventKind::Ir).unwrap(),
+    ///     vec!["main;my_cra...my_func 10".to_owned()]
+    /// );
+    /// ```
+    pub fn truncate_frame_names(&mut self, max_length: usize, ellipsis: api::Ellipsis) {
+        for frame in self.0.iter_mut().flat_map(|stack| stack.frames.iter_mut()) {
+            if let Cow::Owned(truncated) = ellipsis.truncate(frame, max_length) {
+                *frame = truncated;
+            }
+        }
+    }
+
+    /// Prepend the `frame` to all stacks as new root frame
+    ///
+    /// The costs of the stacks are unchanged, so the `frame` has the total costs of all stacks as
+    /// inclusive costs. This is a [`Stacks::transform`], so stacks with equal frames are merged.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use iai_callgrind_runner::api::EventKind;
+    /// use iai_callgrind_runner::runner::callgrind::flamegraph::Stacks;
+    /// use iai_callgrind_runner::runner::callgrind::model::Costs;
+    ///
+    /// let mut stacks = Stacks::from_costs_map([
+    ///     (vec!["main"], Costs::with_event_kinds([(EventKind::Ir, 1)])),
+    ///     (
+    ///         vec!["main", "foo"],
+    ///         Costs::with_event_kinds([(EventKind::Ir, 2)]),
+    ///     ),
+    /// ]);
+    /// stacks.prepend_frame("my-bin");
+    ///
+    /// assert_eq!(
+    ///     stacks.to_stack_format(&EventKind::Ir).unwrap(),
+    ///     vec!["my-bin;main 1".to_owned(), "my-bin;main;foo 2".to_owned()]
+    /// );
+    /// ```
+    pub fn prepend_frame(&mut self, frame: &str) {
+        *self = self.transform(|stack| {
+            let mut stack = stack.clone();
+            stack.frames.insert(0, frame.to_owned());
+            Some(stack)
+        });
+    }
+
+    /// Merge all stacks with equal frames into a single stack summing up their costs
+    ///
+    /// This is a [`Stacks::transform`] which keeps all stacks unchanged, so the costs of the stacks
+    /// with equal frames are added with [`Costs::add`]. The merged stack takes the position of the
+    /// first of the merged stacks, so the order of the stacks otherwise doesn't change. Stacks
+    /// with equal frames appear for example after [`Stacks::transform_symbols`] stripped the
+    /// details which distinguished them. Depending on the order of the stacks, inferno shows
+    /// such stacks as separate slivers.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use iai_callgrind_runner::api::EventKind;
+    /// use iai_callgrind_runner::runner::callgrind::flamegraph::Stacks;
+    /// use iai_callgrind_runner::runner::callgrind::model::Costs;
+    ///
+    /// let mut stacks = Stacks::from_costs_map([
+    ///     (vec!["main", "foo"], Costs::with_event_kinds([(EventKind::Ir, 1)])),
+    ///     (vec!["main"], Costs::with_event_kinds([(EventKind::Ir, 10)])),
+    ///     (vec!["main", "foo"], Costs::with_event_kinds([(EventKind::Ir, 2)])),
+    /// ]);
+    /// stacks.coalesce();
+    ///
+    /// assert_eq!(
+    ///     stacks.to_stack_format(&EventKind::Ir).unwrap(),
+    ///     vec!["main;foo 3".to_owned(), "main 10".to_owned()]
+    /// );
+    /// ```
+    pub fn coalesce(&mut self) {
+        *self = self.transform(|stack| Some(stack.clone()));
+    }
+
+    /// Return the [`StacksReport`] with at most `limit` of the most duplicated frame paths
+    ///
+    /// A frame path is a sequence of at most 4 consecutive frames of a stack. It is duplicated if
+    /// it occurs more than once within the same stack, like the frames of a recursion. Each
+    /// occurrence after the first one counts as repetition and the repetitions of all stacks are
+    /// summed up. Frame paths with the same number of repetitions are sorted lexicographically.
+    /// This method doesn't change the stacks.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use iai_callgrind_runner::api::EventKind;
+    /// use iai_callgrind_runner::runner::callgrind::flamegraph::Stacks;
+    /// use iai_callgrind_runner::runner::callgrind::model::Costs;
+    ///
+    /// let stacks = Stacks::from_costs_map([
+    ///     (vec!["main"], Costs::with_event_kinds([(EventKind::Ir, 1)])),
+    ///     (vec!["main", "fib"], Costs::with_event_kinds([(EventKind::Ir, 2)])),
+    ///     (vec!["main", "fib", "fib"], Costs::with_event_kinds([(EventKind::Ir, 3)])),
+    /// ]);
+    /// let report = stacks.report(5);
+    ///
+    /// assert_eq!(report.num_stacks, 3);
+    /// assert_eq!(report.max_depth, 3);
+    /// assert_eq!(report.duplicated, vec![("fib".to_owned(), 1)]);
+    /// ```
+    pub fn report(&self, limit: usize) -> StacksReport {
+        let mut paths: IndexMap<&[String], usize> = IndexMap::new();
+        for stack in self {
+            let mut occurrences: IndexMap<&[String], usize> = IndexMap::new();
+            for length in 1..=MAX_PATH_FRAMES.min(stack.frames.len()) {
+                for path in stack.frames.windows(length) {
+                    *occurrences.entry(path).or_default() += 1;
+                }
+            }
+            for (path, count) in occurrences {
+                if count > 1 {
+                    *paths.entry(path).or_default() += count - 1;
+                }
+            }
+        }
+
+        let mut duplicated = paths
+            .into_iter()
+            .map(|(frames, count)| (frames.join(";"), count))
+            .collect::<Vec<_>>();
+        duplicated.sort_by(|(path_a, count_a), (path_b, count_b)| {
+            count_b.cmp(count_a).then_with(|| path_a.cmp(path_b))
+        });
+        duplicated.truncate(limit);
+
+        StacksReport {
+            num_stacks: self.len(),
+            max_depth: self
+                .iter()
+                .map(|stack| stack.frames.len())
+                .max()
+                .unwrap_or(0),
+            duplicated,
+        }
+    }
+
+    /// Return the inclusive cost of each frame for this [`EventKind`]
+    ///
+    /// The inclusive cost of a frame is the sum of the costs of all stacks containing this frame.
+    /// A frame occurring multiple times in a stack, like in a recursion, counts once per stack.
+    pub fn inclusive_costs(&self, event_kind: &EventKind) -> IndexMap<&str, u64> {
+        let mut costs: IndexMap<&str, u64> = IndexMap::new();
+        for stack in self {
+            let cost = stack.cost(event_kind).unwrap_or(0);
+            let mut frames = stack.frames.iter().map(String::as_str).collect::<Vec<_>>();
+            frames.sort_unstable();
+            frames.dedup();
+            for frame in frames {
+                *costs.entry(frame).or_default() += cost;
+            }
+        }
+        costs
+    }
+
+    /// Return the [`PaletteMap`] coloring each frame of these `Stacks` by the density of the
+    /// `color_event_kind`
+    ///
+    /// These stacks contain the costs of the `event_kind` which determines the width of the
+    /// frames, the `color_stacks` the costs of the `color_event_kind`. The density of a frame is
+    /// the ratio of its inclusive costs of the `color_event_kind` to its inclusive costs of the
+    /// `event_kind`, for example the cache misses per instruction. The frames with the highest
+    /// density are dark red and the frames with the lowest density light yellow. Frames whose
+    /// inclusive costs of the `event_kind` are zero are grey.
+    pub fn to_density_palette(
+        &self,
+        event_kind: &EventKind,
+        color_stacks: &Stacks,
+        color_event_kind: &EventKind,
+    ) -> PaletteMap {
+        let color_costs = color_stacks.inclusive_costs(color_event_kind);
+        #[allow(clippy::cast_precision_loss)]
+        let densities = self
+            .inclusive_costs(event_kind)
+            .into_iter()
+            .map(|(frame, cost)| {
+                let density = (cost > 0)
+                    .then(|| color_costs.get(frame).copied().unwrap_or(0) as f64 / cost as f64);
+                (frame, density)
+            })
+            .collect::<Vec<_>>();
+        let max_density = densities
+            .iter()
+            .filter_map(|(_, density)| *density)
+            .fold(0f64, f64::max);
+
+        let mut palette_map = PaletteMap::default();
+        for (frame, density) in densities {
+            let color = density.map_or(NO_DENSITY_COLOR, |density| {
+                density_color(if max_density > 0f64 {
+                    density / max_density
+                } else {
+                    0f64
+                })
+            });
+            palette_map.insert(frame, color);
+        }
+        palette_map
+    }
+
+    /// Return the sum of the costs of all stacks for this [`EventKind`]
+    ///
+    /// This is the total cost of the root of the flamegraph. Missing costs count as `0`.
+    pub fn total(&self, event_kind: &EventKind) -> u64 {
+        self.iter().filter_map(|stack| stack.cost(event_kind)).sum()
+    }
+
+    /// Return new `Stacks` with each frame labeled with its cost for this [`EventKind`]
+    ///
+    /// The cost of a frame is the inclusive cost of all stacks starting with the same frames as the
+    /// frame and its callers, so the labels of equal frames are equal across all stacks and
+    /// inferno can still merge them. The percentage is calculated relative to the `total`. The
+    /// costs of the stacks are not changed.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use iai_callgrind_runner::api::{EventKind, FrameLabel};
+    /// use iai_callgrind_runner::runner::callgrind::flamegraph::Stacks;
+    /// use iai_callgrind_runner::runner::callgrind::model::Costs;
+    ///
+    /// let stacks = Stacks::from_costs_map([
+    ///     (vec!["main"], Costs::with_event_kinds([(EventKind::Ir, 1000)])),
+    ///     (vec!["main", "foo"], Costs::with_event_kinds([(EventKind::Ir, 3000)])),
+    /// ]);
+    ///
+    /// assert_eq!(
+    ///     stacks
+    ///         .to_labeled(&EventKind::Ir, FrameLabel::CostAndPercentage, 4000)
+    ///         .to_stack_format(&EventKind::Ir)
+    ///         .unwrap(),
+    ///     vec![
+    ///         "main (4,000 | 100.0%) 1000".to_owned(),
+    ///         "main (4,000 | 100.0%);foo (3,000 | 75.0%) 3000".to_owned()
+    ///     ]
+    /// );
+    /// ```
+    pub fn to_labeled(&self, event_kind: &EventKind, frame_label: FrameLabel, total: u64) -> Self {
+        let mut inclusive: IndexMap<&[String], u64> = IndexMap::new();
+        for stack in &self.0 {
+            let cost = stack.cost(event_kind).unwrap_or(0);
+            for index in 1..=stack.frames.len() {
+                *inclusive.entry(&stack.frames[..index]).or_insert(0) += cost;
+            }
+        }
+
+        Self(
+            self.iter()
+                .map(|stack| Stack {
+                    frames: (1..=stack.frames.len())
+                        .map(|index| {
+                            let cost = inclusive[&stack.frames[..index]];
+                            format!(
+                                "{} ({})",
+                                stack.frames[index - 1],
+                                frame_label.format(cost, total)
+                            )
+                        })
+                        .collect(),
+                    costs: stack.costs.clone(),
+                })
+                .collect(),
+        )
+    }
+
+    /// Return new `Stacks` with the costs of this [`EventKind`] scaled to percentages of the
+    /// `total`
+    ///
+    /// Inferno needs integer counts, so the percentages are multiplied with the `scale` and
+    /// rounded with the [`Rounding`]. The `scale` is the effective precision: A `scale` of `100`
+    /// resolves `0.01%`, a `scale` of `1000` resolves `0.001%`. Frames with a cost below the
+    /// precision vanish with [`Rounding::Floor`] and may vanish with [`Rounding::Round`] but are
+    /// kept with a count of at least `1` with [`Rounding::Ceil`]. The result is calculated with
+    /// integers, so there are no floating point errors. Only the costs of the [`EventKind`] are
+    /// kept, and if the `total` is zero, all costs are zero.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use iai_callgrind_runner::api::{EventKind, Rounding};
+    /// use iai_callgrind_runner::runner::callgrind::flamegraph::Stacks;
+    /// use iai_callgrind_runner::runner::callgrind::model::Costs;
+    ///
+    /// let stacks = Stacks::from_costs_map([
+    ///     (vec!["main"], Costs::with_event_kinds([(EventKind::Ir, 3)])),
+    ///     (vec!["main", "foo"], Costs::with_event_kinds([(EventKind::Ir, 1)])),
+    /// ]);
+    ///
+    /// assert_eq!(
+    ///     stacks
+    ///         .to_scaled_percentages(&EventKind::Ir, 4, 10, Rounding::Round)
+    ///         .to_stack_format(&EventKind::Ir)
+    ///         .unwrap(),
+    ///     vec!["main 750".to_owned(), "main;foo 250".to_owned()]
+    /// );
+    /// ```
+    pub fn to_scaled_percentages(
+        &self,
+        event_kind: &EventKind,
+        total: u64,
+        scale: u64,
+        rounding: Rounding,
+    ) -> Self {
+        Self(
+            self.iter()
+                .map(|stack| Stack {
+                    frames: stack.frames.clone(),
+                    costs: Costs::with_event_kinds([(
+                        *event_kind,
+                        rounding.scale(stack.cost(event_kind).unwrap_or(0), total, scale),
+                    )]),
+                })
+                .collect(),
+        )
+    }
+
+    /// Return new `Stacks` with each stack replaced by the result of the `transform` function
+    ///
+    /// This is the building block of the other transformations of the stacks like
+    /// [`Stacks::map_frames`], [`Stacks::truncate`] or [`Stacks::coalesce`]. Stacks with equal
+    /// frames after the transformation are merged into the first of these stacks and their costs
+    /// are summed up. The order of the stacks is preserved otherwise.
+    ///
+    /// If the `transform` returns `None`, the stack is dropped and its costs are re-parented to
+    /// its caller: The `transform` is called again with the stack without its innermost frame and
+    /// the same costs until it returns a stack or there are no frames left. So, as long as the
+    /// `transform` doesn't change the costs, no costs get lost unless even the root frame of a
+    /// stack is dropped.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use iai_callgrind_runner::api::EventKind;
+    /// use iai_callgrind_runner::runner::callgrind::flamegraph::Stacks;
+    /// use iai_callgrind_runner::runner::callgrind::model::Costs;
+    ///
+    /// let stacks = Stacks::from_costs_map([
+    ///     (vec!["main"], Costs::with_event_kinds([(EventKind::Ir, 10)])),
+    ///     (vec!["main", "foo"], Costs::with_event_kinds([(EventKind::Ir, 20)])),
+    ///     (vec!["main", "foo", "drop"], Costs::with_event_kinds([(EventKind::Ir, 5)])),
+    /// ]);
+    ///
+    /// // Drop the stacks of `drop` and fold their costs into the caller
+    /// let transformed = stacks.transform(|stack| {
+    ///     (stack.frames.last().map(String::as_str) != Some("drop")).then(|| stack.clone())
+    /// });
+    /// assert_eq!(
+    ///     transformed.to_stack_format(&EventKind::Ir).unwrap(),
+    ///     vec!["main 10".to_owned(), "main;foo 25".to_owned()]
+    /// );
+    /// ```
+    pub fn transform<F>(&self, mut transform: F) -> Self
+    where
+        F: FnMut(&Stack) -> Option<Stack>,
+    {
+        let mut merged: IndexMap<Vec<String>, Stack> = IndexMap::new();
+        for stack in self {
+            let mut current = Cow::Borrowed(stack);
+            let transformed = loop {
+                if let Some(transformed) = transform(&current) {
+                    break Some(transformed);
+                }
+                if current.frames.len() <= 1 {
+                    break None;
+                }
+                current.to_mut().frames.pop();
+            };
+
+            if let Some(transformed) = transformed {
+                match merged.entry(transformed.frames.clone()) {
+                    Entry::Occupied(mut entry) => entry.get_mut().merge(&transformed),
+                    Entry::Vacant(entry) => {
+                        entry.insert(transformed);
+                    }
+                }
+            }
+        }
+
+        Self(merged.into_values().collect())
+    }
+
+    /// Return new `Stacks` with each frame replaced by the result of the `transform` function
+    ///
+    /// If the `transform` returns `None`, the frame is removed from the stack. If this was the
+    /// innermost frame, the costs of the stack become the costs of its caller. Stacks whose
+    /// frames are all removed are dropped with their costs. Like with [`Stacks::transform`],
+    /// stacks with equal frames after the transformation are merged.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use iai_callgrind_runner::api::EventKind;
+    /// use iai_callgrind_runner::runner::callgrind::flamegraph::Stacks;
+    /// use iai_callgrind_runner::runner::callgrind::model::Costs;
+    ///
+    /// let stacks = Stacks::from_costs_map([
+    ///     (vec!["main", "inlined", "foo"], Costs::with_event_kinds([(EventKind::Ir, 1)])),
+    ///     (vec!["main", "foo"], Costs::with_event_kinds([(EventKind::Ir, 2)])),
+    ///     (vec!["main", "inlined"], Costs::with_event_kinds([(EventKind::Ir, 3)])),
+    /// ]);
+    ///
+    /// let transformed = stacks.map_frames(|frame| (frame != "inlined").then(|| frame.to_owned()));
+    /// assert_eq!(
+    ///     transformed.to_stack_format(&EventKind::Ir).unwrap(),
+    ///     vec!["main;foo 3".to_owned(), "main 3".to_owned()]
+    /// );
+    /// ```
+    pub fn map_frames<F>(&self, mut transform: F) -> Self
+    where
+        F: FnMut(&str) -> Option<String>,
+    {
+        self.transform(|stack| {
+            let frames = stack
+                .frames
+                .iter()
+                .filter_map(|frame| transform(frame))
+                .collect::<Vec<_>>();
+            (!frames.is_empty()).then(|| Stack {
+                frames,
+                costs: stack.costs.clone(),
+            })
+        })
+    }
+
+    /// Transform the symbol names of all frames with the `transform` function
+    ///
+    /// This is [`Stacks::map_frames`] without removing frames. Stacks with equal frames after the
+    /// transformation are merged into the first of these stacks and their costs are summed up, so
+    /// no costs get lost. The order of the stacks is preserved otherwise.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use iai_callgrind_runner::api::EventKind;
+    /// use iai_callgrind_runner::runner::callgrind::flamegraph::{Stacks, SymbolTransform};
+    /// use iai_callgrind_runner::runner::callgrind::model::Costs;
+    ///
+    /// let mut stacks = Stacks::from_costs_map([
+    ///     (vec!["main", "foo::h0123456789abcdef"], Costs::with_event_kinds([(EventKind::Ir, 1)])),
+    ///     (vec!["main", "foo::hfedcba9876543210"], Costs::with_event_kinds([(EventKind::Ir, 2)])),
+    /// ]);
+    ///
+    /// stacks.transform_symbols(|symbol| SymbolTransform::StripHash.apply(symbol).into_owned());
+    /// assert_eq!(
+    ///     stacks.to_stack_format(&EventKind::Ir).unwrap(),
+    ///     vec!["main;foo 3".to_owned()]
+    /// );
+    /// ```
+    pub fn transform_symbols<F>(&mut self, mut transform: F)
+    where
+        F: FnMut(&str) -> String,
+    {
+        *self = self.map_frames(|frame| Some(transform(frame)));
+    }
+
+    /// Sort these `Stacks` lexicographically by their frames
+    ///
+    /// Stacks sharing a common prefix of frames are adjacent after sorting, which is what inferno
+    /// needs to merge the frames. The sort is stable, so the order of the stacks is deterministic.
+    pub fn sort(&mut self) {
+        self.0.sort_by(|a, b| a.frames.cmp(&b.frames));
+    }
+
+    /// Convert these `Stacks` into the lines of the folded stacks format for this [`EventKind`]
+    ///
+    /// # Errors
+    ///
+    /// If a stack has no frames or the [`EventKind`] is not present in the costs of a stack
+    pub fn to_stack_format(&self, event_kind: &EventKind) -> Result<Vec<String>> {
+        self.iter()
+            .map(|stack| stack.to_stack_format(event_kind))
+            .collect()
+    }
+}
+
+impl FrameLabel {
+    /// Format the `cost` and its percentage of the `total` as configured by this `FrameLabel`
+    fn format(self, cost: u64, total: u64) -> String {
+        #[allow(clippy::cast_precision_loss)]
+        let percentage = if total == 0 {
+            0f64
+        } else {
+            (cost as f64 / total as f64) * 100.0f64
+        };
+        match self {
+            FrameLabel::Cost => to_string_thousands(cost),
+            FrameLabel::Percentage => format!("{percentage:.1}%"),
+            FrameLabel::CostAndPercentage => {
+                format!("{} | {percentage:.1}%", to_string_thousands(cost))
+            }
+        }
+    }
+}
+
+impl Rounding {
+    /// Return the `cost` as percentage of the `total` multiplied with the `scale` and rounded
+    fn scale(self, cost: u64, total: u64, scale: u64) -> u64 {
+        if total == 0 {
+            return 0;
+        }
+
+        let numerator = u128::from(cost) * 100 * u128::from(scale);
+        let total = u128::from(total);
+        let scaled = match self {
+            Rounding::Floor => numerator / total,
+            Rounding::Round => (numerator + total / 2) / total,
+            Rounding::Ceil => (numerator + total - 1) / total,
+        };
+        u64::try_from(scaled).unwrap_or(u64::MAX)
+    }
+}
+
+impl api::Ellipsis {
+    /// Truncate the `name` to at most `max_length` characters replacing the cut characters with
+    /// `...`
+    ///
+    /// With [`api::Ellipsis::Middle`], the final path segment of the `name` (like `my_func` of
+    /// `my_crate::my_mod::my_func`) is kept if it fits, otherwise the characters of both halves are
+    /// kept. A `max_length` too short for the ellipsis cuts the `name` without ellipsis.
+    pub fn truncate(self, name: &str, max_length: usize) -> Cow<'_, str> {
+        let length = name.chars().count();
+        if length <= max_length {
+            return Cow::Borrowed(name);
+        }
+        if max_length <= ELLIPSIS.len() {
+            return Cow::Owned(name.chars().take(max_length).collect());
+        }
+
+        let budget = max_length - ELLIPSIS.len();
+        let (head, tail) = match self {
+            Self::Middle => {
+                let half = budget - budget / 2;
+                let segment = final_segment_length(name);
+                let tail = if segment <= budget {
+                    segment.max(half)
+                } else {
+                    half
+                };
+                (budget - tail, tail)
+            }
+            Self::End => (budget, 0),
+        };
+
+        let mut truncated: String = name.chars().take(head).collect();
+        truncated.push_str(ELLIPSIS);
+        truncated.extend(name.chars().skip(length - tail));
+        Cow::Owned(truncated)
+    }
+}
+
+impl api::RootFrame {
+    /// Return the root frame of the process described by the `metadata` of its output file
+    ///
+    /// The command is the file name of the executable without the arguments of the `cmd` line.
+    /// The `cmd` line separates the executable and the arguments with spaces, so the executable
+    /// recorded in the [`super::metadata_parser::RunMetadata`] is used if the `cmd` line starts
+    /// with it. Otherwise, the executable is the first word of the `cmd` line. Return `None` for
+    /// [`api::RootFrame::None`] or if the command is unknown.
+    pub fn to_frame(self, metadata: &OutputMetadata) -> Option<String> {
+        if self == Self::None {
+            return None;
+        }
+
+        let cmd = metadata.cmd.as_deref()?;
+        let executable = metadata
+            .run
+            .as_ref()
+            .and_then(|run| run.executable.as_deref())
+            .filter(|executable| {
+                !executable.is_empty()
+                    && cmd
+                        .strip_prefix(executable)
+                        .map_or(false, |rest| rest.is_empty() || rest.starts_with(' '))
+            })
+            .or_else(|| cmd.split_ascii_whitespace().next())?;
+        let command = Path::new(executable).file_name().map_or_else(
+            || executable.to_owned(),
+            |name| name.to_string_lossy().to_string(),
+        );
+        match (self, metadata.pid) {
+            (Self::CommandAndPid, Some(pid)) => Some(format!("{command} (pid {pid})")),
+            _ => Some(command),
+        }
+    }
+}
+
+impl SymbolTransform {
+    /// Apply this transformation to the `symbol`
+    pub fn apply<'a>(&self, symbol: &'a str) -> Cow<'a, str> {
+        match self {
+            Self::StripHash => HASH_SUFFIX_RE.replace_all(symbol, ""),
+            Self::Replace(regex, replacement) => regex.replace_all(symbol, replacement.as_str()),
+        }
+    }
+}
+
+impl TryFrom<&api::SymbolTransform> for SymbolTransform {
+    type Error = anyhow::Error;
+
+    fn try_from(value: &api::SymbolTransform) -> std::result::Result<Self, Self::Error> {
+        match value {
+            api::SymbolTransform::StripHash => Ok(Self::StripHash),
+            api::SymbolTransform::Replace(pattern, replacement) => Regex::new(pattern)
+                .map(|regex| Self::Replace(regex, replacement.clone()))
+                .map_err(|error| {
+                    anyhow!("Invalid regex '{pattern}' in flamegraph symbol transform: {error}")
+                }),
+        }
+    }
+}
+
+/// Return the number of characters of the final path segment of the `name`
+///
+/// The path separators (`::`) within generic arguments like in `Vec<alloc::string::String>` don't
+/// separate the segments of the `name`.
+fn final_segment_length(name: &str) -> usize {
+    let mut depth = 0usize;
+    let mut start = 0;
+    let mut previous = None;
+    let mut length = 0;
+    for (index, char) in name.chars().enumerate() {
+        match char {
+            '<' => depth += 1,
+            '>' => depth = depth.saturating_sub(1),
+            ':' if depth == 0 && previous == Some(':') => start = index + 1,
+            _ => {}
+        }
+        previous = Some(char);
+        length = index + 1;
+    }
+    length - start
+}
+
+/// Return the color of the relative `density` between `0` (the lowest) and `1` (the highest)
+///
+/// The color is interpolated linearly between the [`LOW_DENSITY_COLOR`] and the
+/// [`HIGH_DENSITY_COLOR`].
+fn density_color(density: f64) -> Color {
+    #[allow(
+        clippy::cast_possible_truncation,
+        clippy::cast_sign_loss,
+        clippy::cast_lossless
+    )]
+    let interpolate = |low: u8, high: u8| -> u8 {
+        (low as f64 + (high as f64 - low as f64) * density.clamp(0f64, 1f64)).round() as u8
+    };
+    Color {
+        r: interpolate(LOW_DENSITY_COLOR.r, HIGH_DENSITY_COLOR.r),
+        g: interpolate(LOW_DENSITY_COLOR.g, HIGH_DENSITY_COLOR.g),
+        b: interpolate(LOW_DENSITY_COLOR.b, HIGH_DENSITY_COLOR.b),
+    }
+}
+
+impl Display for StacksReport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "Number of stacks: {}, maximum depth: {}",
+            self.num_stacks, self.max_depth
+        )?;
+        if self.duplicated.is_empty() {
+            return Ok(());
+        }
+
+        write!(f, ". The most duplicated frame paths are:")?;
+        for (path, count) in &self.duplicated {
+            let truncated = truncate_str_utf8(path, MAX_PATH_LENGTH);
+            let ellipsis = if truncated.len() < path.len() {
+                TRUNCATED_FRAME
+            } else {
+                ""
+            };
+            write!(f, "\n  {count}x {truncated}{ellipsis}")?;
+        }
+        Ok(())
+    }
+}
+
+impl<'a> IntoIterator for &'a Stacks {
+    type Item = &'a Stack;
+
+    type IntoIter = std::slice::Iter<'a, Stack>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+/// Inject the `header` right after the opening `<svg>` tag and the `footer` right before the
+/// closing `</svg>` tag of the `svg`
+///
+/// The xml declaration and doctype in front of the `<svg>` element are left untouched, so the
+/// injected content like a `<style>` or `<script>` element is always a child of the `<svg>`
+/// element.
+///
+/// # Errors
+///
+/// If the `svg` doesn't have an opening `<svg>` or closing `</svg>` tag
+pub fn inject_svg(svg: &str, header: Option<&str>, footer: Option<&str>) -> Result<String> {
+    let start = svg
+        .find("<svg ")
+        .or_else(|| svg.find("<svg>"))
+        .and_then(|index| svg[index..].find('>').map(|end| index + end + 1))
+        .ok_or_else(|| anyhow!("Missing the opening <svg> tag"))?;
+    let end = svg
+        .rfind("</svg>")
+        .filter(|end| *end >= start)
+        .ok_or_else(|| anyhow!("Missing the closing </svg> tag"))?;
+
+    let mut result =
+        String::with_capacity(svg.len() + header.map_or(0, str::len) + footer.map_or(0, str::len));
+    result.push_str(&svg[..start]);
+    if let Some(header) = header {
+        result.push_str(header);
+    }
+    result.push_str(&svg[start..end]);
+    if let Some(footer) = footer {
+        result.push_str(footer);
+    }
+    result.push_str(&svg[end..]);
+    Ok(result)
+}
+
+/// Create the html page with the `flamegraphs` side by side
+///
+/// The `flamegraphs` are pairs of a heading and the svg of the flamegraph. Each svg is embedded in
+/// its own `iframe`, so the scripts of the svgs which address the elements by id don't interfere
+/// with each other.
+pub fn side_by_side_html(title: &str, flamegraphs: &[(String, String)]) -> String {
+    const STYLE: &str = "body { margin: 0; font-family: Verdana, sans-serif; }
+.flamegraphs { display: flex; }
+.flamegraph { flex: 1; min-width: 0; }
+.flamegraph h2 { font-size: 14px; text-align: center; }
+.flamegraph iframe { width: 100%; height: 90vh; border: none; }";
+
+    let mut html = String::new();
+    writeln!(
+        html,
+        "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<title>{}</title>",
+        escape_html(title)
+    )
+    .unwrap();
+    writeln!(html, "<style>\n{STYLE}\n</style>\n</head>\n<body>").unwrap();
+    writeln!(html, "<div class=\"flamegraphs\">").unwrap();
+    for (heading, svg) in flamegraphs {
+        writeln!(
+            html,
+            "<div class=\"flamegraph\">\n<h2>{}</h2>\n<iframe srcdoc=\"{}\"></iframe>\n</div>",
+            escape_html(heading),
+            escape_html(svg)
+        )
+        .unwrap();
+    }
+    writeln!(html, "</div>\n</body>\n</html>").unwrap();
+    html
+}
+
+/// Escape the characters of the `value` which have a special meaning in html text and attributes
+fn escape_html(value: &str) -> Cow<'_, str> {
+    if !value.contains(['&', '<', '>', '"', '\'']) {
+        return Cow::Borrowed(value);
+    }
+
+    let mut escaped = String::with_capacity(value.len());
+    for c in value.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            c => escaped.push(c),
+        }
+    }
+    Cow::Owned(escaped)
+}
+
+/// Return true if the `error` is caused by a read-only or full file system
+///
+/// `io::ErrorKind::ReadOnlyFilesystem` and `io::ErrorKind::StorageFull` are not stable with our
+/// minimum supported rust version.
+fn is_read_only_or_full(error: &io::Error) -> bool {
+    matches!(error.raw_os_error(), Some(libc::ENOSPC | libc::EROFS))
+}
+
+/// Remove the flamegraph file at `path` but only warn if the file system is read-only or full
+fn remove_flamegraph_file(path: &Path) -> Result<()> {
+    match std::fs::remove_file(path) {
+        Ok(()) => Ok(()),
+        Err(error) if is_read_only_or_full(&error) => {
+            warn!(
+                "Skipping the removal of the flamegraph file '{}': {error}",
+                path.display()
+            );
+            Ok(())
+        }
+        Err(error) => Err(error)
+            .with_context(|| format!("Failed removing flamegraph file: '{}'", path.display())),
+    }
+}
+
+/// Create the flamegraphs of each of the `event_kinds` independently with `create`
+///
+/// A failure of an event kind doesn't prevent the creation of the flamegraphs of the other event
+/// kinds. The event kinds which failed are reported as warning and the summaries of the
+/// successful event kinds are returned. Only if all event kinds failed, this function returns an
+/// error.
+fn create_each_event_kind<F>(
+    event_kinds: &[EventKind],
+    mut create: F,
+) -> Result<Vec<FlamegraphSummary>>
+where
+    F: FnMut(EventKind) -> Result<FlamegraphSummary>,
+{
+    let mut flamegraph_summaries = vec![];
+    let mut failures = vec![];
+    for event_kind in event_kinds {
+        match create(*event_kind) {
+            Ok(flamegraph_summary) => flamegraph_summaries.push(flamegraph_summary),
+            Err(error) => failures.push(format!("{event_kind}: {error:#}")),
+        }
+    }
+
+    if failures.is_empty() {
+        Ok(flamegraph_summaries)
+    } else if flamegraph_summaries.is_empty() {
+        Err(anyhow!(
+            "Failed creating the flamegraphs of all event kinds:\n{}",
+            failures.join("\n")
+        ))
+    } else {
+        warn!(
+            "Failed creating the flamegraphs of {} out of {} event kinds:\n{}",
+            failures.len(),
+            event_kinds.len(),
+            failures.join("\n")
+        );
+        Ok(flamegraph_summaries)
+    }
+}
+
+/// Sanitize the `tag` of the flamegraph file names
+///
+/// Characters which are invalid in file names are replaced with `_` and the tag is truncated to
+/// 100 bytes. Return `None` if the tag is empty.
+pub fn sanitize_tag(tag: &str) -> Option<String> {
+    let sanitized = sanitize_filename::sanitize_with_options(
+        tag.trim(),
+        sanitize_filename::Options {
+            windows: false,
+            truncate: false,
+            replacement: "_",
+        },
+    );
+    let sanitized = truncate_str_utf8(&sanitized, 100);
+    (!sanitized.is_empty()).then(|| sanitized.to_owned())
+}
+
+/// Return the built-in singular and plural unit of the costs of the [`EventKind`] if present
+fn default_count_unit(event_kind: EventKind) -> Option<(&'static str, &'static str)> {
+    let unit = match event_kind {
+        EventKind::Ir => ("instruction", "instructions"),
+        EventKind::SysCount => ("system call", "system calls"),
+        EventKind::Ge => ("global bus event", "global bus events"),
+        EventKind::Dr => ("data read", "data reads"),
+        EventKind::Dw => ("data write", "data writes"),
+        EventKind::I1mr => ("L1 instruction read miss", "L1 instruction read misses"),
+        EventKind::ILmr => ("LL instruction read miss", "LL instruction read misses"),
+        EventKind::D1mr => ("L1 data read miss", "L1 data read misses"),
+        EventKind::DLmr => ("LL data read miss", "LL data read misses"),
+        EventKind::D1mw => ("L1 data write miss", "L1 data write misses"),
+        EventKind::DLmw => ("LL data write miss", "LL data write misses"),
+        EventKind::L1hits => ("L1 hit", "L1 hits"),
+        EventKind::LLhits => ("LL hit", "LL hits"),
+        EventKind::RamHits => ("RAM hit", "RAM hits"),
+        EventKind::TotalRW => ("memory access", "memory accesses"),
+        EventKind::DataRW => ("data access", "data accesses"),
+        EventKind::EstimatedCycles => ("estimated cycle", "estimated cycles"),
+        EventKind::Bc => ("conditional branch", "conditional branches"),
+        EventKind::Bcm => (
+            "mispredicted conditional branch",
+            "mispredicted conditional branches",
+        ),
+        EventKind::Bi => ("indirect branch", "indirect branches"),
+        EventKind::Bim => (
+            "mispredicted indirect branch",
+            "mispredicted indirect branches",
+        ),
+        _ => return None,
+    };
+    Some(unit)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use inferno::flamegraph::color::Palette;
+    use pretty_assertions::assert_eq;
+    use rstest::rstest;
+
+    use super::super::metadata_parser::RunMetadata;
+    use super::*;
+
+    /// Create the `Stacks` from the `frames` and their cost of [`EventKind::Ir`]
+    fn ir_stacks<'a>(stacks: impl IntoIterator<Item = (Vec<&'a str>, u64)>) -> Stacks {
+        Stacks::from_costs_map(
+            stacks
+                .into_iter()
+                .map(|(frames, cost)| (frames, Costs::with_event_kinds([(EventKind::Ir, cost)]))),
+        )
+    }
+
+    #[rstest]
+    #[case::empty(vec![], vec![])]
+    #[case::single(vec![(vec!["main"], 10)], vec!["main 10"])]
+    #[case::chain(
+        vec![(vec!["main"], 1), (vec!["main", "foo"], 2), (vec!["main", "foo", "bar"], 3)],
+        vec!["main 1", "main;foo 2", "main;foo;bar 3"]
+    )]
+    #[case::siblings(
+        vec![(vec!["main", "foo"], 2), (vec!["main", "bar"], 0)],
+        vec!["main;foo 2", "main;bar 0"]
+    )]
+    fn test_stacks_from_costs_map_to_stack_format(
+        #[case] input: Vec<(Vec<&str>, u64)>,
+        #[case] expected: Vec<&str>,
+    ) {
+        let stacks = ir_stacks(input);
+
+        assert_eq!(
+            stacks.to_stack_format(&EventKind::Ir).unwrap(),
+            expected.into_iter().map(ToOwned::to_owned).collect::<Vec<_>>()
+        );
+    }
+
+    #[rstest]
+    #[case::empty(vec![], None)]
+    #[case::single(vec![(vec!["main"], 10)], Some(vec!["main"]))]
+    #[case::leaf_is_hottest(
+        vec![(vec!["main"], 1), (vec!["main", "foo"], 2), (vec!["main", "foo", "bar"], 3)],
+        Some(vec!["main", "foo", "bar"])
+    )]
+    #[case::inner_is_hottest(
+        vec![(vec!["main"], 1), (vec!["main", "foo"], 5), (vec!["main", "foo", "bar"], 3)],
+        Some(vec!["main", "foo"])
+    )]
+    #[case::tie_is_lexicographically_smallest(
+        vec![(vec!["main", "foo"], 2), (vec!["main", "bar"], 2), (vec!["main"], 1)],
+        Some(vec!["main", "bar"])
+    )]
+    #[case::tie_is_independent_of_order(
+        vec![(vec!["main", "bar"], 2), (vec!["main", "foo"], 2)],
+        Some(vec!["main", "bar"])
+    )]
+    #[case::ignores_empty_frames(vec![(vec![], 10), (vec!["main"], 1)], Some(vec!["main"]))]
+    fn test_stacks_hot_path(
+        #[case] input: Vec<(Vec<&str>, u64)>,
+        #[case] expected: Option<Vec<&str>>,
+    ) {
+        let stacks = ir_stacks(input);
+
+        assert_eq!(
+            stacks
+                .hot_path(&EventKind::Ir)
+                .map(|stack| stack.frames.clone()),
+            expected.map(|frames| frames.into_iter().map(ToOwned::to_owned).collect())
+        );
+    }
+
+    #[rstest]
+    #[case::empty(vec![], 0, 0, vec![])]
+    #[case::no_duplicates(
+        vec![vec!["main"], vec!["main", "foo"]],
+        2,
+        2,
+        vec![]
+    )]
+    #[case::no_repetition_within_stack(
+        vec![
+            vec!["main"],
+            vec!["main", "foo"],
+            vec!["main", "bar"],
+            vec!["main", "foo", "bar"],
+        ],
+        4,
+        3,
+        vec![]
+    )]
+    #[case::recursion(
+        vec![
+            vec!["main"],
+            vec!["main", "fib"],
+            vec!["main", "fib", "fib"],
+            vec!["main", "fib", "fib", "fib"],
+            vec!["main", "fib", "fib", "fib", "fib"],
+        ],
+        5,
+        5,
+        vec![("fib", 6), ("fib;fib", 3)]
+    )]
+    #[case::mutual_recursion(
+        vec![
+            vec!["main", "even", "odd", "even", "odd"],
+            vec!["main", "even", "odd", "even"],
+            vec!["main", "even", "odd"],
+        ],
+        3,
+        5,
+        vec![("even", 2), ("even;odd", 1)]
+    )]
+    #[case::deeper_than_max_path_frames(
+        vec![vec!["a", "b", "c", "d", "e", "a", "b", "c", "d", "e"]],
+        1,
+        10,
+        vec![("a", 1), ("a;b", 1)]
+    )]
+    fn test_stacks_report(
+        #[case] frames: Vec<Vec<&str>>,
+        #[case] num_stacks: usize,
+        #[case] max_depth: usize,
+        #[case] duplicated: Vec<(&str, usize)>,
+    ) {
+        let stacks = ir_stacks(frames.into_iter().map(|frames| (frames, 1)));
+
+        assert_eq!(
+            stacks.report(2),
+            StacksReport {
+                num_stacks,
+                max_depth,
+                duplicated: duplicated
+                    .into_iter()
+                    .map(|(path, count)| (path.to_owned(), count))
+                    .collect()
+            }
+        );
+    }
+
+    #[test]
+    fn test_stacks_report_display() {
+        let long_frame = "a".repeat(MAX_PATH_LENGTH);
+        let report = StacksReport {
+            num_stacks: 10,
+            max_depth: 4,
+            duplicated: vec![
+                ("main;foo".to_owned(), 3),
+                (format!("main;{long_frame}"), 2),
+            ],
+        };
+
+        assert_eq!(
+            report.to_string(),
+            format!(
+                "Number of stacks: 10, maximum depth: 4. The most duplicated frame paths are:\n  \
+                 3x main;foo\n  2x main;{}...",
+                &long_frame[..MAX_PATH_LENGTH - 5]
+            )
+        );
+        assert_eq!(
+            StacksReport {
+                num_stacks: 1,
+                max_depth: 1,
+                duplicated: vec![]
+            }
+            .to_string(),
+            "Number of stacks: 1, maximum depth: 1"
+        );
+    }
+
+    #[test]
+    fn test_stacks_hot_path_when_event_kind_is_missing() {
+        let stacks = Stacks::from_costs_map([
+            (vec!["main"], Costs::with_event_kinds([(EventKind::Ir, 1)])),
+            (
+                vec!["main", "foo"],
+                Costs::with_event_kinds([(EventKind::Dr, 10)]),
+            ),
+        ]);
+
+        assert_eq!(
+            stacks.hot_path(&EventKind::Dr).unwrap().frames,
+            vec!["main", "foo"]
+        );
+        assert_eq!(stacks.hot_path(&EventKind::Dw), None);
+    }
+
+    #[rstest]
+    #[case::present(EventKind::Dr, Some(2))]
+    #[case::missing(EventKind::Dw, None)]
+    fn test_stack_cost(#[case] event_kind: EventKind, #[case] expected: Option<u64>) {
+        let stack = Stack::new(
+            ["main"],
+            Costs::with_event_kinds([(EventKind::Ir, 1), (EventKind::Dr, 2)]),
+        );
+
+        assert_eq!(stack.cost(&event_kind), expected);
+    }
+
+    #[rstest]
+    #[case::cost(FrameLabel::Cost, vec![
+        "main (10,000) 1000",
+        "main (10,000);foo (6,000) 6000",
+        "main (10,000);bar (3,000) 1000",
+        "main (10,000);bar (3,000);foo (2,000) 2000",
+    ])]
+    #[case::percentage(FrameLabel::Percentage, vec![
+        "main (100.0%) 1000",
+        "main (100.0%);foo (60.0%) 6000",
+        "main (100.0%);bar (30.0%) 1000",
+        "main (100.0%);bar (30.0%);foo (20.0%) 2000",
+    ])]
+    #[case::cost_and_percentage(FrameLabel::CostAndPercentage, vec![
+        "main (10,000 | 100.0%) 1000",
+        "main (10,000 | 100.0%);foo (6,000 | 60.0%) 6000",
+        "main (10,000 | 100.0%);bar (3,000 | 30.0%) 1000",
+        "main (10,000 | 100.0%);bar (3,000 | 30.0%);foo (2,000 | 20.0%) 2000",
+    ])]
+    fn test_stacks_to_labeled(#[case] frame_label: FrameLabel, #[case] expected: Vec<&str>) {
+        let stacks = ir_stacks([
+            (vec!["main"], 1000),
+            (vec!["main", "foo"], 6000),
+            (vec!["main", "bar"], 1000),
+            (vec!["main", "bar", "foo"], 2000),
+        ]);
+        let total = stacks.total(&EventKind::Ir);
+
+        assert_eq!(total, 10000);
+        assert_eq!(
+            stacks
+                .to_labeled(&EventKind::Ir, frame_label, total)
+                .to_stack_format(&EventKind::Ir)
+                .unwrap(),
+            expected.into_iter().map(ToOwned::to_owned).collect::<Vec<_>>()
+        );
+    }
+
+    // The frame `foo` has a cost of 0.005% of the total cost
+    #[rstest]
+    #[case::precise_scale(1000, Rounding::Round, vec!["main 99995", "main;foo 5"])]
+    #[case::round_half_up(100, Rounding::Round, vec!["main 10000", "main;foo 1"])]
+    #[case::round(10, Rounding::Round, vec!["main 1000", "main;foo 0"])]
+    #[case::floor(10, Rounding::Floor, vec!["main 999", "main;foo 0"])]
+    #[case::ceil(10, Rounding::Ceil, vec!["main 1000", "main;foo 1"])]
+    fn test_stacks_to_scaled_percentages(
+        #[case] scale: u64,
+        #[case] rounding: Rounding,
+        #[case] expected: Vec<&str>,
+    ) {
+        let stacks = ir_stacks([(vec!["main"], 99995), (vec!["main", "foo"], 5)]);
+
+        assert_eq!(
+            stacks
+                .to_scaled_percentages(&EventKind::Ir, 100_000, scale, rounding)
+                .to_stack_format(&EventKind::Ir)
+                .unwrap(),
+            expected
+        );
+    }
+
+    #[rstest]
+    #[case::labeled(
+        |stacks: &Stacks| stacks.to_labeled(&EventKind::Ir, FrameLabel::CostAndPercentage, 0),
+        "main (0 | 0.0%) 0"
+    )]
+    #[case::scaled_percentages(
+        |stacks: &Stacks| stacks.to_scaled_percentages(&EventKind::Ir, 0, 100, Rounding::Ceil),
+        "main 0"
+    )]
+    fn test_stacks_when_total_is_zero(
+        #[case] transform: fn(&Stacks) -> Stacks,
+        #[case] expected: &str,
+    ) {
+        let stacks = ir_stacks([(vec!["main"], 0)]);
+
+        assert_eq!(
+            transform(&stacks).to_stack_format(&EventKind::Ir).unwrap(),
+            vec![expected.to_owned()]
+        );
+    }
+
+    #[test]
+    fn test_config_try_from_when_percentage_scale_is_zero_then_error() {
+        let error = Config::try_from(api::FlamegraphConfig {
+            percentage_scale: Some(0),
+            ..Default::default()
+        })
+        .unwrap_err();
+
+        assert_eq!(
+            error.to_string(),
+            "Invalid flamegraph percentage scale '0': The scale must be greater than 0"
+        );
+    }
+
+    #[test]
+    fn test_flamegraph_to_regular_stack_format_when_labeled_and_scaled() {
+        let flamegraph = FlamegraphBuilder::new("heading")
+            .frame_label(FrameLabel::Cost)
+            .percentage_scale(1)
+            .build();
+        let stacks = ir_stacks([(vec!["main"], 1000), (vec!["main", "foo"], 3000)]);
+        let stacks_lines = stacks.to_stack_format(&EventKind::Ir).unwrap();
+
+        assert_eq!(
+            flamegraph
+                .to_regular_stack_format(&stacks, &EventKind::Ir, &stacks_lines)
+                .unwrap()
+                .into_owned(),
+            vec![
+                "main (4,000) 25".to_owned(),
+                "main (4,000);foo (3,000) 75".to_owned()
+            ]
+        );
+        assert_eq!(flamegraph.regular_count_name(EventKind::Ir, 4000), "percent");
+    }
+
+    #[rstest]
+    #[case::event_kind_is_missing(vec!["main"], EventKind::Dr)]
+    #[case::frames_are_empty(vec![], EventKind::Ir)]
+    fn test_stacks_to_stack_format_then_error(
+        #[case] frames: Vec<&str>,
+        #[case] event_kind: EventKind,
+    ) {
+        let stacks = ir_stacks([(frames, 1)]);
+
+        assert!(stacks.to_stack_format(&event_kind).is_err());
+    }
+
+    #[rstest]
+    #[case::strip_hash(
+        api::SymbolTransform::StripHash,
+        "core::ptr::drop_in_place<foo::Bar>::h0123456789abcdef",
+        "core::ptr::drop_in_place<foo::Bar>"
+    )]
+    #[case::strip_hash_when_no_hash(
+        api::SymbolTransform::StripHash,
+        "foo::heavy_work",
+        "foo::heavy_work"
+    )]
+    #[case::strip_hash_with_obj(
+        api::SymbolTransform::StripHash,
+        "src/lib.rs:foo::bar::h0123456789abcdef [/path/to/bench]",
+        "src/lib.rs:foo::bar [/path/to/bench]"
+    )]
+    #[case::replace(
+        api::SymbolTransform::Replace(r"::\{\{closure\}\}".to_owned(), String::new()),
+        "foo::bar::{{closure}}::{{closure}}",
+        "foo::bar"
+    )]
+    #[case::replace_with_capture_group(
+        api::SymbolTransform::Replace(r"<(\w+) as .*>".to_owned(), "$1".to_owned()),
+        "<Foo as core::fmt::Debug>::fmt",
+        "Foo::fmt"
+    )]
+    fn test_symbol_transform_apply(
+        #[case] transform: api::SymbolTransform,
+        #[case] symbol: &str,
+        #[case] expected: &str,
+    ) {
+        let transform = SymbolTransform::try_from(&transform).unwrap();
+        assert_eq!(transform.apply(symbol), expected);
+    }
+
+    #[test]
+    fn test_symbol_transform_when_regex_is_invalid() {
+        let transform = api::SymbolTransform::Replace("(".to_owned(), String::new());
+        assert!(SymbolTransform::try_from(&transform).is_err());
+    }
+
+    #[rstest]
+    #[case::empty(vec![], vec![])]
+    #[case::no_merge(
+        vec![(vec!["main"], 1), (vec!["main", "foo::h0123456789abcdef"], 2)],
+        vec!["main 1", "main;foo 2"]
+    )]
+    #[case::merge(
+        vec![
+            (vec!["main"], 1),
+            (vec!["main", "foo::h0123456789abcdef"], 2),
+            (vec!["main", "bar"], 3),
+            (vec!["main", "foo::hfedcba9876543210"], 4),
+        ],
+        vec!["main 1", "main;foo 6", "main;bar 3"]
+    )]
+    #[case::merge_inner_frames(
+        vec![
+            (vec!["main", "foo::h0123456789abcdef", "baz"], 2),
+            (vec!["main", "foo::hfedcba9876543210", "baz"], 4),
+        ],
+        vec!["main;foo;baz 6"]
+    )]
+    fn test_stacks_transform_symbols_strip_hash(
+        #[case] input: Vec<(Vec<&str>, u64)>,
+        #[case] expected: Vec<&str>,
+    ) {
+        let mut stacks = ir_stacks(input);
+
+        stacks.transform_symbols(|symbol| SymbolTransform::StripHash.apply(symbol).into_owned());
+
+        assert_eq!(
+            stacks.to_stack_format(&EventKind::Ir).unwrap(),
+            expected.into_iter().map(ToOwned::to_owned).collect::<Vec<_>>()
+        );
+    }
+
+    #[rstest]
+    #[case::empty(vec![], vec![])]
+    #[case::already_sorted(
+        vec![(vec!["main"], 1), (vec!["main", "foo"], 2)],
+        vec!["main 1", "main;foo 2"]
+    )]
+    #[case::shared_prefixes_are_adjacent(
+        vec![
+            (vec!["main", "foo", "bar"], 1),
+            (vec!["main", "baz"], 2),
+            (vec!["main", "foo"], 3),
+            (vec!["main"], 4),
+        ],
+        vec!["main 4", "main;baz 2", "main;foo 3", "main;foo;bar 1"]
+    )]
+    #[case::stable_when_equal(
+        vec![(vec!["main", "foo"], 2), (vec!["main"], 1), (vec!["main", "foo"], 3)],
+        vec!["main 1", "main;foo 2", "main;foo 3"]
+    )]
+    fn test_stacks_sort(#[case] input: Vec<(Vec<&str>, u64)>, #[case] expected: Vec<&str>) {
+        let mut stacks = ir_stacks(input);
+
+        stacks.sort();
+
+        assert_eq!(
+            stacks.to_stack_format(&EventKind::Ir).unwrap(),
+            expected.into_iter().map(ToOwned::to_owned).collect::<Vec<_>>()
+        );
+    }
+
+    #[rstest]
+    #[case::builtin_plural(EventKind::Ir, &[], 2, "instructions")]
+    #[case::builtin_singular(EventKind::Ir, &[], 1, "instruction")]
+    #[case::builtin_when_zero(EventKind::Dr, &[], 0, "data reads")]
+    #[case::without_builtin(EventKind::SpLoss1, &[], 2, "SpLoss1")]
+    #[case::configured(
+        EventKind::Ir,
+        &[(EventKind::Ir, "instr", "instrs")],
+        2,
+        "instrs"
+    )]
+    #[case::configured_singular(
+        EventKind::Ir,
+        &[(EventKind::Ir, "instr", "instrs")],
+        1,
+        "instr"
+    )]
+    #[case::configured_without_builtin(
+        EventKind::SpLoss1,
+        &[(EventKind::SpLoss1, "loss", "losses")],
+        2,
+        "losses"
+    )]
+    #[case::configured_other_event_kind(
+        EventKind::Ir,
+        &[(EventKind::Dr, "read", "reads")],
+        2,
+        "instructions"
+    )]
+    #[case::last_configured_wins(
+        EventKind::Ir,
+        &[(EventKind::Ir, "a", "as"), (EventKind::Ir, "b", "bs")],
+        2,
+        "bs"
+    )]
+    fn test_flamegraph_count_name(
+        #[case] event_kind: EventKind,
+        #[case] count_units: &[(EventKind, &str, &str)],
+        #[case] total: u64,
+        #[case] expected: &str,
+    ) {
+        let flamegraph = Flamegraph::new(
+            "heading".to_owned(),
+            Config::try_from(api::FlamegraphConfig {
+                count_units: Some(
+                    count_units
+                        .iter()
+                        .map(|(kind, singular, plural)| {
+                            (
+                                *kind,
+                                api::CountUnit {
+                                    singular: (*singular).to_owned(),
+                                    plural: (*plural).to_owned(),
+                                },
+                            )
+                        })
+                        .collect(),
+                ),
+                ..Default::default()
+            })
+            .unwrap(),
+        );
+
+        assert_eq!(flamegraph.count_name(event_kind, total), expected);
+    }
+
+    #[test]
+    fn test_flamegraph_builder() {
+        let flamegraph = FlamegraphBuilder::new("heading")
+            .kind(FlamegraphKind::Differential)
+            .event_kinds([EventKind::Ir, EventKind::Dr])
+            .direction(api::Direction::BottomToTop)
+            .subtitle("subtitle")
+            .min_width(2f64)
+            .normalize_differential(true)
+            .sort_stacks(true)
+            .frame_label(FrameLabel::Cost)
+            .trim_harness(true)
+            .count_unit(EventKind::Dr, "read", "reads")
+            .build();
+
+        assert_eq!(flamegraph.config.kind, FlamegraphKind::Differential);
+        assert_eq!(
+            flamegraph.config.event_kinds,
+            vec![EventKind::Ir, EventKind::Dr]
+        );
+        assert_eq!(flamegraph.config.direction, Direction::Straight);
+        assert_eq!(flamegraph.config.title.as_deref(), Some("heading"));
+        assert_eq!(flamegraph.config.subtitle.as_deref(), Some("subtitle"));
+        assert!((flamegraph.config.min_width - 2f64).abs() < f64::EPSILON);
+        assert!(!flamegraph.config.negate_differential);
+        assert!(flamegraph.config.normalize_differential);
+        assert!(flamegraph.config.sort_stacks);
+        assert_eq!(flamegraph.config.frame_label, Some(FrameLabel::Cost));
+        assert!(flamegraph.config.trim_harness);
+        assert_eq!(flamegraph.count_name(EventKind::Dr, 2), "reads");
+        assert!(flamegraph.differential_options().unwrap().normalize);
+    }
+
+    #[test]
+    fn test_flamegraph_builder_title_overrides_heading() {
+        let flamegraph = FlamegraphBuilder::new("heading").title("title").build();
+        assert_eq!(flamegraph.config.title.as_deref(), Some("title"));
+    }
+
+    #[rstest]
+    #[case::zero(0, vec!["main 10", "main;foo 200", "main;foo;baz 3", "main;bar 5"])]
+    #[case::below_all(3, vec!["main 10", "main;foo 200", "main;foo;baz 3", "main;bar 5"])]
+    #[case::leaf(4, vec!["main 10", "main;foo 203", "main;bar 5"])]
+    #[case::fold_into_caller(100, vec!["main 15", "main;foo 203"])]
+    #[case::fold_recursively(1000, vec!["main 218"])]
+    fn test_stacks_prune(#[case] min_cost: u64, #[case] expected: Vec<&str>) {
+        let mut stacks = ir_stacks([
+            (vec!["main"], 10),
+            (vec!["main", "foo"], 200),
+            (vec!["main", "foo", "baz"], 3),
+            (vec!["main", "bar"], 5),
+        ]);
+        let total = stacks.total(&EventKind::Ir);
+
+        stacks.prune(&EventKind::Ir, min_cost);
+
+        assert_eq!(stacks.total(&EventKind::Ir), total);
+        assert_eq!(
+            stacks.to_stack_format(&EventKind::Ir).unwrap(),
+            expected
+                .into_iter()
+                .map(ToOwned::to_owned)
+                .collect::<Vec<String>>()
+        );
+    }
+
+    #[test]
+    fn test_stacks_prune_keeps_callers_with_remaining_callees() {
+        let mut stacks = ir_stacks([
+            (vec!["main"], 10),
+            (vec!["main", "foo"], 1),
+            (vec!["main", "foo", "bar"], 200),
+            (vec!["main", "foo", "baz"], 2),
+            (vec!["main", "qux"], 1),
+            (vec!["main", "qux", "quux"], 3),
+        ]);
+
+        stacks.prune(&EventKind::Ir, 100);
+
+        assert_eq!(
+            stacks.to_stack_format(&EventKind::Ir).unwrap(),
+            vec!["main 14", "main;foo 3", "main;foo;bar 200"]
+        );
+    }
+
+    #[test]
+    fn test_stack_merge() {
+        let mut stack = Stack::new(
+            ["main", "foo"],
+            Costs::with_event_kinds([(EventKind::Ir, 1), (EventKind::Dr, 2)]),
+        );
+        stack.merge(&Stack::new(
+            ["main", "foo"],
+            Costs::with_event_kinds([(EventKind::Ir, 10), (EventKind::Dr, 3)]),
+        ));
+
+        assert_eq!(
+            stack,
+            Stack::new(
+                ["main", "foo"],
+                Costs::with_event_kinds([(EventKind::Ir, 11), (EventKind::Dr, 5)]),
+            )
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "Only stacks with equal frames can be merged")]
+    fn test_stack_merge_when_frames_differ_then_panic() {
+        let mut stack = Stack::new(["main", "foo"], Costs::with_event_kinds([(EventKind::Ir, 1)]));
+        stack.merge(&Stack::new(
+            ["main", "bar"],
+            Costs::with_event_kinds([(EventKind::Ir, 1)]),
+        ));
+    }
+
+    #[rstest]
+    #[case::empty(vec![], vec![])]
+    #[case::no_duplicates(
+        vec![(vec!["main"], 1), (vec!["main", "foo"], 2)],
+        vec!["main 1", "main;foo 2"]
+    )]
+    #[case::duplicates(
+        vec![
+            (vec!["main", "foo"], 1),
+            (vec!["main"], 10),
+            (vec!["main", "foo"], 2),
+            (vec!["main", "bar"], 5),
+            (vec!["main", "foo"], 3)
+        ],
+        vec!["main;foo 6", "main 10", "main;bar 5"]
+    )]
+    fn test_stacks_coalesce(#[case] input: Vec<(Vec<&str>, u64)>, #[case] expected: Vec<&str>) {
+        let mut stacks = ir_stacks(input);
+        let total = stacks.total(&EventKind::Ir);
+
+        stacks.coalesce();
+
+        assert_eq!(stacks.total(&EventKind::Ir), total);
+        assert_eq!(
+            stacks.to_stack_format(&EventKind::Ir).unwrap(),
+            expected
+                .into_iter()
+                .map(ToOwned::to_owned)
+                .collect::<Vec<String>>()
+        );
+    }
+
+    #[test]
+    fn test_stacks_coalesce_after_strip_hash() {
+        let stacks = ir_stacks([
+            (vec!["main", "foo::h0123456789abcdef"], 1),
+            (vec!["main", "foo::hfedcba9876543210"], 2),
+        ]);
+        let mut expected = stacks.clone();
+        expected.transform_symbols(|symbol| SymbolTransform::StripHash.apply(symbol).into_owned());
+
+        expected.coalesce();
+
+        assert_eq!(
+            expected.to_stack_format(&EventKind::Ir).unwrap(),
+            vec!["main;foo 3".to_owned()]
+        );
+    }
+
+    fn transform_input() -> Stacks {
+        Stacks::from_costs_map([
+            (
+                vec!["main"],
+                Costs::with_event_kinds([(EventKind::Ir, 10), (EventKind::Dr, 1)]),
+            ),
+            (
+                vec!["main", "foo"],
+                Costs::with_event_kinds([(EventKind::Ir, 200), (EventKind::Dr, 2)]),
+            ),
+            (
+                vec!["main", "foo", "drop"],
+                Costs::with_event_kinds([(EventKind::Ir, 3), (EventKind::Dr, 3)]),
+            ),
+            (
+                vec!["main", "foo", "drop", "free"],
+                Costs::with_event_kinds([(EventKind::Ir, 1), (EventKind::Dr, 4)]),
+            ),
+            (
+                vec!["main", "drop"],
+                Costs::with_event_kinds([(EventKind::Ir, 5), (EventKind::Dr, 5)]),
+            ),
+        ])
+    }
+
+    #[rstest]
+    #[case::keep_all(&[], vec!["main 10", "main;foo 200", "main;foo;drop 3", "main;foo;drop;free 1", "main;drop 5"])]
+    #[case::drop_leaf(&["free"], vec!["main 10", "main;foo 200", "main;foo;drop 4", "main;drop 5"])]
+    #[case::drop_inner(&["drop"], vec!["main 15", "main;foo 204"])]
+    #[case::drop_caller(&["foo"], vec!["main 214", "main;drop 5"])]
+    fn test_stacks_transform_conserves_costs(
+        #[case] dropped: &[&str],
+        #[case] expected: Vec<&str>,
+    ) {
+        let stacks = transform_input();
+
+        // Drop all stacks which contain one of the `dropped` frames
+        let transformed = stacks.transform(|stack| {
+            (!stack
+                .frames
+                .iter()
+                .any(|frame| dropped.contains(&frame.as_str())))
+            .then(|| stack.clone())
+        });
+
+        for event_kind in [EventKind::Ir, EventKind::Dr] {
+            assert_eq!(transformed.total(&event_kind), stacks.total(&event_kind));
+        }
+        assert_eq!(
+            transformed.to_stack_format(&EventKind::Ir).unwrap(),
+            expected
+                .into_iter()
+                .map(ToOwned::to_owned)
+                .collect::<Vec<String>>()
+        );
+    }
+
+    #[test]
+    fn test_stacks_transform_when_root_is_dropped() {
+        let stacks = ir_stacks([
+            (vec!["main"], 10),
+            (vec!["other"], 20),
+            (vec!["other", "foo"], 5),
+        ]);
+
+        let transformed =
+            stacks.transform(|stack| (stack.frames[0] == "main").then(|| stack.clone()));
+
+        assert_eq!(
+            transformed.to_stack_format(&EventKind::Ir).unwrap(),
+            vec!["main 10".to_owned()]
+        );
+    }
+
+    #[rstest]
+    #[case::keep_all(&[], vec!["main 10", "main;foo 200", "main;foo;drop 3", "main;foo;drop;free 1", "main;drop 5"], (0, 0))]
+    #[case::remove_leaf(&["free"], vec!["main 10", "main;foo 200", "main;foo;drop 4", "main;drop 5"], (0, 0))]
+    #[case::remove_inner(&["drop"], vec!["main 15", "main;foo 203", "main;foo;free 1"], (0, 0))]
+    // The stack `main` has no frames left, so its costs are dropped
+    #[case::remove_root(&["main"], vec!["foo 200", "foo;drop 3", "foo;drop;free 1", "drop 5"], (10, 1))]
+    fn test_stacks_map_frames_conserves_costs(
+        #[case] removed: &[&str],
+        #[case] expected: Vec<&str>,
+        #[case] lost: (u64, u64),
+    ) {
+        let stacks = transform_input();
+
+        let transformed =
+            stacks.map_frames(|frame| (!removed.contains(&frame)).then(|| frame.to_owned()));
+
+        for (event_kind, lost) in [(EventKind::Ir, lost.0), (EventKind::Dr, lost.1)] {
+            assert_eq!(
+                transformed.total(&event_kind),
+                stacks.total(&event_kind) - lost
+            );
+        }
+        assert_eq!(
+            transformed.to_stack_format(&EventKind::Ir).unwrap(),
+            expected
+                .into_iter()
+                .map(ToOwned::to_owned)
+                .collect::<Vec<String>>()
+        );
+    }
+
+    #[test]
+    fn test_stacks_prune_conserves_costs_of_other_event_kinds() {
+        let mut stacks = Stacks::from_costs_map([
+            (
+                vec!["main"],
+                Costs::with_event_kinds([(EventKind::Ir, 10), (EventKind::Dr, 1)]),
+            ),
+            (
+                vec!["main", "foo"],
+                Costs::with_event_kinds([(EventKind::Ir, 5), (EventKind::Dr, 100)]),
+            ),
+        ]);
+
+        stacks.prune(&EventKind::Ir, 100);
+
+        assert_eq!(
+            stacks,
+            Stacks::from_costs_map([(
+                vec!["main"],
+                Costs::with_event_kinds([(EventKind::Ir, 15), (EventKind::Dr, 101)])
+            )])
+        );
+    }
+
+    #[test]
+    fn test_stacks_inclusive_costs() {
+        let stacks = ir_stacks([
+            (vec!["main"], 10),
+            (vec!["main", "foo"], 5),
+            (vec!["main", "foo", "foo"], 3),
+            (vec!["main", "bar"], 2),
+        ]);
+
+        assert_eq!(
+            stacks.inclusive_costs(&EventKind::Ir),
+            IndexMap::from([("main", 20), ("foo", 8), ("bar", 2)])
+        );
+    }
+
+    #[test]
+    fn test_stacks_to_density_palette() {
+        let stacks = ir_stacks([
+            (vec!["main"], 10),
+            (vec!["main", "foo"], 10),
+            (vec!["main", "bar"], 0),
+        ]);
+        let color_stacks = Stacks::from_costs_map([
+            (vec!["main"], Costs::with_event_kinds([(EventKind::DLmr, 0)])),
+            (vec!["main", "foo"], Costs::with_event_kinds([(EventKind::DLmr, 10)])),
+            (vec!["main", "bar"], Costs::with_event_kinds([(EventKind::DLmr, 0)])),
+        ]);
+
+        let palette_map =
+            stacks.to_density_palette(&EventKind::Ir, &color_stacks, &EventKind::DLmr);
+
+        assert_eq!(palette_map.get("foo"), Some(HIGH_DENSITY_COLOR));
+        assert_eq!(palette_map.get("main"), Some(density_color(0.5)));
+        assert_eq!(palette_map.get("bar"), Some(NO_DENSITY_COLOR));
+    }
+
+    #[rstest]
+    #[case::low(0f64, LOW_DENSITY_COLOR)]
+    #[case::half(0.5f64, Color { r: 228, g: 120, b: 100 })]
+    #[case::high(1f64, HIGH_DENSITY_COLOR)]
+    #[case::clamped(2f64, HIGH_DENSITY_COLOR)]
+    fn test_density_color(#[case] density: f64, #[case] expected: Color) {
+        assert_eq!(density_color(density), expected);
+    }
+
+    #[test]
+    fn test_flamegraph_to_color_palette_when_not_configured() {
+        let map = FlamegraphMap::default();
+        let stacks = Stacks::default();
+
+        let flamegraph = FlamegraphBuilder::new("some").build();
+        assert!(flamegraph
+            .to_color_palette(&map, &EventKind::Ir, &stacks)
+            .unwrap()
+            .is_none());
+
+        let flamegraph = FlamegraphBuilder::new("some")
+            .color_event_kind(EventKind::Ir)
+            .build();
+        assert!(flamegraph
+            .to_color_palette(&map, &EventKind::Ir, &stacks)
+            .unwrap()
+            .is_none());
+    }
+
+    #[rstest]
+    #[case::hot("hot", api::Palette::Hot, Palette::Basic(BasicPalette::Hot))]
+    #[case::mem("mem", api::Palette::Mem, Palette::Basic(BasicPalette::Mem))]
+    #[case::io("io", api::Palette::Io, Palette::Basic(BasicPalette::Io))]
+    #[case::red("red", api::Palette::Red, Palette::Basic(BasicPalette::Red))]
+    #[case::green("green", api::Palette::Green, Palette::Basic(BasicPalette::Green))]
+    #[case::blue("blue", api::Palette::Blue, Palette::Basic(BasicPalette::Blue))]
+    #[case::aqua("aqua", api::Palette::Aqua, Palette::Basic(BasicPalette::Aqua))]
+    #[case::yellow("yellow", api::Palette::Yellow, Palette::Basic(BasicPalette::Yellow))]
+    #[case::purple("purple", api::Palette::Purple, Palette::Basic(BasicPalette::Purple))]
+    #[case::orange("orange", api::Palette::Orange, Palette::Basic(BasicPalette::Orange))]
+    #[case::java("java", api::Palette::Java, Palette::Multi(MultiPalette::Java))]
+    #[case::js("js", api::Palette::Js, Palette::Multi(MultiPalette::Js))]
+    #[case::perl("perl", api::Palette::Perl, Palette::Multi(MultiPalette::Perl))]
+    #[case::python("python", api::Palette::Python, Palette::Multi(MultiPalette::Python))]
+    #[case::rust("rust", api::Palette::Rust, Palette::Multi(MultiPalette::Rust))]
+    #[case::wakeup("wakeup", api::Palette::Wakeup, Palette::Multi(MultiPalette::Wakeup))]
+    fn test_palette_from_name(
+        #[case] name: &str,
+        #[case] expected: api::Palette,
+        #[case] expected_inferno: Palette,
+    ) {
+        assert_eq!(name.parse::<api::Palette>().unwrap(), expected);
+        assert_eq!(
+            name.to_uppercase().parse::<api::Palette>().unwrap(),
+            expected
+        );
+        assert_eq!(expected.name(), name);
+        assert_eq!(Palette::from(expected), expected_inferno);
+        assert_eq!(Palette::from_str(name).unwrap(), expected_inferno);
+    }
+
+    #[test]
+    fn test_palette_from_name_when_unknown() {
+        assert_eq!(
+            "unknown".parse::<api::Palette>().unwrap_err().to_string(),
+            "Unknown flamegraph palette 'unknown': Valid palettes are hot, mem, io, red, green, \
+             blue, aqua, yellow, purple, orange, java, js, perl, python, rust, wakeup"
+        );
+    }
+
+    #[test]
+    fn test_flamegraph_options_palette() {
+        let flamegraph = FlamegraphBuilder::new("some").build();
+        assert_eq!(
+            flamegraph
+                .options(EventKind::Ir, "subtitle".to_owned(), 1)
+                .colors,
+            Palette::default()
+        );
+
+        let flamegraph = FlamegraphBuilder::new("some")
+            .palette(api::Palette::Mem)
+            .build();
+        assert_eq!(
+            flamegraph
+                .options(EventKind::Ir, "subtitle".to_owned(), 1)
+                .colors,
+            Palette::Basic(BasicPalette::Mem)
+        );
+    }
+
+    #[rstest]
+    #[case::middle_short(api::Ellipsis::Middle, "my_func", 16, "my_func")]
+    #[case::middle_exact(api::Ellipsis::Middle, "my_crate::my_func", 17, "my_crate::my_func")]
+    #[case::middle_keeps_segment(
+        api::Ellipsis::Middle,
+        "my_crate::my_mod::my_func",
+        16,
+        "my_cra...my_func"
+    )]
+    #[case::middle_segment_too_long(
+        api::Ellipsis::Middle,
+        "my_crate::my_very_long_function",
+        13,
+        "my_cr...ction"
+    )]
+    #[case::middle_without_segments(api::Ellipsis::Middle, "my_long_function", 10, "my_...tion")]
+    #[case::middle_generics(
+        api::Ellipsis::Middle,
+        "core::ptr::drop_in_place<alloc::vec::Vec<u8>>",
+        40,
+        "cor...drop_in_place<alloc::vec::Vec<u8>>"
+    )]
+    #[case::middle_utf8(api::Ellipsis::Middle, "a::ü::ö::äöü", 9, "a::...äöü")]
+    #[case::end(
+        api::Ellipsis::End,
+        "my_crate::my_mod::my_func",
+        16,
+        "my_crate::my_..."
+    )]
+    #[case::end_short(api::Ellipsis::End, "my_func", 7, "my_func")]
+    #[case::too_short_for_ellipsis(api::Ellipsis::Middle, "my_func", 3, "my_")]
+    #[case::zero(api::Ellipsis::End, "my_func", 0, "")]
+    fn test_ellipsis_truncate(
+        #[case] ellipsis: api::Ellipsis,
+        #[case] name: &str,
+        #[case] max_length: usize,
+        #[case] expected: &str,
+    ) {
+        let truncated = ellipsis.truncate(name, max_length);
+        assert_eq!(truncated, expected);
+        assert!(truncated.chars().count() <= max_length);
+    }
+
+    #[rstest]
+    #[case::none(api::RootFrame::None, Some("/path/to/my-bin --foo"), Some(1234i32), None)]
+    #[case::command(
+        api::RootFrame::Command,
+        Some("/path/to/my-bin --foo"),
+        Some(1234i32),
+        Some("my-bin")
+    )]
+    #[case::command_and_pid(
+        api::RootFrame::CommandAndPid,
+        Some("/path/to/my-bin --foo"),
+        Some(1234i32),
+        Some("my-bin (pid 1234)")
+    )]
+    #[case::without_pid(
+        api::RootFrame::CommandAndPid,
+        Some("target/release/my-bin"),
+        None,
+        Some("my-bin")
+    )]
+    #[case::without_cmd(api::RootFrame::CommandAndPid, None, Some(1234i32), None)]
+    #[case::empty_cmd(api::RootFrame::Command, Some(""), None, None)]
+    fn test_root_frame_to_frame(
+        #[case] root_frame: api::RootFrame,
+        #[case] cmd: Option<&str>,
+        #[case] pid: Option<i32>,
+        #[case] expected: Option<&str>,
+    ) {
+        let metadata = OutputMetadata {
+            cmd: cmd.map(ToOwned::to_owned),
+            pid,
+            ..Default::default()
+        };
+        assert_eq!(root_frame.to_frame(&metadata).as_deref(), expected);
+    }
+
+    #[rstest]
+    #[case::with_spaces("/path/to my/my bin", "/path/to my/my bin --foo", "my bin")]
+    #[case::without_args("/path/to my/my bin", "/path/to my/my bin", "my bin")]
+    #[case::other_executable("/path/to/other", "/path/to/my-bin --foo", "my-bin")]
+    #[case::prefix_of_executable("/path/to/my", "/path/to/my-bin --foo", "my-bin")]
+    #[case::empty("", "/path/to/my-bin --foo", "my-bin")]
+    fn test_root_frame_to_frame_with_executable(
+        #[case] executable: &str,
+        #[case] cmd: &str,
+        #[case] expected: &str,
+    ) {
+        let metadata = OutputMetadata {
+            cmd: Some(cmd.to_owned()),
+            run: Some(RunMetadata {
+                executable: Some(executable.to_owned()),
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+        assert_eq!(
+            api::RootFrame::Command.to_frame(&metadata).as_deref(),
+            Some(expected)
+        );
+    }
+
+    #[test]
+    fn test_stacks_truncate_frame_names_does_not_merge() {
+        let mut stacks = ir_stacks([
+            (vec!["main", "my_crate::first::my_func"], 10),
+            (vec!["main", "my_crate::other::my_func"], 20),
+        ]);
+
+        stacks.truncate_frame_names(14, api::Ellipsis::Middle);
+
+        assert_eq!(
+            stacks.to_stack_format(&EventKind::Ir).unwrap(),
+            vec![
+                "main;my_c...my_func 10".to_owned(),
+                "main;my_c...my_func 20".to_owned()
+            ]
+        );
+    }
+
+    #[rstest]
+    #[case::zero(0, vec!["main 10", "main;... 209"])]
+    #[case::one(1, vec!["main 10", "main;... 209"])]
+    #[case::two(2, vec!["main 10", "main;foo 200", "main;foo;... 4", "main;bar 5"])]
+    #[case::three(
+        3,
+        vec!["main 10", "main;foo 200", "main;foo;baz 3", "main;foo;baz;... 1", "main;bar 5"]
+    )]
+    #[case::max(
+        4,
+        vec!["main 10", "main;foo 200", "main;foo;baz 3", "main;foo;baz;qux 1", "main;bar 5"]
+    )]
+    fn test_stacks_truncate(#[case] max_depth: usize, #[case] expected: Vec<&str>) {
+        let mut stacks = ir_stacks([
+            (vec!["main"], 10),
+            (vec!["main", "foo"], 200),
+            (vec!["main", "foo", "baz"], 3),
+            (vec!["main", "foo", "baz", "qux"], 1),
+            (vec!["main", "bar"], 5),
+        ]);
+        let total = stacks.total(&EventKind::Ir);
+
+        stacks.truncate(max_depth);
+
+        assert_eq!(stacks.total(&EventKind::Ir), total);
+        assert_eq!(
+            stacks.to_stack_format(&EventKind::Ir).unwrap(),
+            expected
+                .into_iter()
+                .map(ToOwned::to_owned)
+                .collect::<Vec<String>>()
+        );
+    }
+
+    #[rstest]
+    #[case::nothing(None, None, "<?xml?><svg a=\"b\"><g/></svg>")]
+    #[case::header(Some("<style/>"), None, "<?xml?><svg a=\"b\"><style/><g/></svg>")]
+    #[case::footer(None, Some("<script/>"), "<?xml?><svg a=\"b\"><g/><script/></svg>")]
+    #[case::both(
+        Some("<style/>"),
+        Some("<script/>"),
+        "<?xml?><svg a=\"b\"><style/><g/><script/></svg>"
+    )]
+    fn test_inject_svg(
+        #[case] header: Option<&str>,
+        #[case] footer: Option<&str>,
+        #[case] expected: &str,
+    ) {
+        assert_eq!(
+            inject_svg("<?xml?><svg a=\"b\"><g/></svg>", header, footer).unwrap(),
+            expected
+        );
+    }
+
+    #[rstest]
+    #[case::no_svg("<g/>")]
+    #[case::no_closing_tag("<svg a=\"b\"><g/>")]
+    #[case::closing_tag_only("</svg>")]
+    fn test_inject_svg_when_invalid(#[case] svg: &str) {
+        assert!(inject_svg(svg, Some("<style/>"), None).is_err());
+    }
+
+    fn write_test_flamegraph(flamegraph: &Flamegraph, dir: &Path) -> (String, String) {
+        let tool_output_path = ToolOutputPath::new(
+            ToolOutputPathKind::Out,
+            crate::runner::tool::ValgrindTool::Callgrind,
+            &BaselineKind::Old,
+            dir,
+            dir,
+            "module",
+            "name",
+        );
+        let output_path = OutputPath::new(&tool_output_path, EventKind::Ir);
+        output_path.init().unwrap();
+        let stacks = ["main 1", "main;foo 2"];
+
+        // The colors of the frames are random if not deterministic
+        let options = || {
+            let mut options = flamegraph.options(EventKind::Ir, "subtitle".to_owned(), 3);
+            options.deterministic = true;
+            options
+        };
+
+        flamegraph
+            .write(&output_path, &mut options(), stacks.iter().copied())
+            .unwrap();
+
+        let mut inferno_svg = vec![];
+        inferno::flamegraph::from_lines(&mut options(), stacks.iter().copied(), &mut inferno_svg)
+            .unwrap();
+
+        (
+            std::fs::read_to_string(output_path.to_path()).unwrap(),
+            String::from_utf8(inferno_svg).unwrap(),
+        )
+    }
+
+    #[test]
+    fn test_flamegraph_write_by_default_is_unchanged() {
+        let dir = tempfile::tempdir().unwrap();
+        let flamegraph = FlamegraphBuilder::new("heading").build();
+
+        let (actual, expected) = write_test_flamegraph(&flamegraph, dir.path());
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn test_flamegraph_write_with_svg_header_and_footer() {
+        let dir = tempfile::tempdir().unwrap();
+        let header = "<style>text { fill: red; }</style>";
+        let footer = "<script type=\"text/ecmascript\">init();</script>";
+        let flamegraph = FlamegraphBuilder::new("heading")
+            .svg_header(header)
+            .svg_footer(footer)
+            .build();
+
+        let (actual, inferno_svg) = write_test_flamegraph(&flamegraph, dir.path());
+        assert_eq!(
+            actual,
+            inject_svg(&inferno_svg, Some(header), Some(footer)).unwrap()
+        );
+        assert!(actual.starts_with("<?xml"));
+        assert!(actual.trim_end().ends_with("</svg>"));
+        let svg_start = actual.find("<svg ").unwrap();
+        let header_start = actual.find(header).unwrap();
+        assert_eq!(actual[svg_start..header_start].matches('>').count(), 1);
+        assert_eq!(
+            actual.rfind(footer).unwrap() + footer.len(),
+            actual.rfind("</svg>").unwrap()
+        );
+    }
+
+    /// Write the flamegraphs of the `event_kinds` where the costs of `EventKind::Ge` are zero
+    ///
+    /// Inferno fails to create a flamegraph without any counts, so the `EventKind::Ge` fails.
+    fn create_each_test_flamegraph(
+        dir: &Path,
+        event_kinds: &[EventKind],
+    ) -> (OutputPath, Result<Vec<FlamegraphSummary>>) {
+        let flamegraph = FlamegraphBuilder::new("heading").build();
+        let stacks = Stacks::from_costs_map([
+            (
+                vec!["main"],
+                Costs::with_event_kinds([(EventKind::Ir, 1), (EventKind::Ge, 0)]),
+            ),
+            (
+                vec!["main", "foo"],
+                Costs::with_event_kinds([(EventKind::Ir, 2), (EventKind::Ge, 0)]),
+            ),
+        ]);
+        let mut output_path = create_test_svgs(dir, &[]);
+
+        let result = create_each_event_kind(event_kinds, |event_kind| {
+            output_path.set_event_kind(event_kind);
+            let stacks_lines = stacks.to_stack_format(&event_kind)?;
+            let mut options =
+                flamegraph.options(event_kind, output_path.file_name(), stacks.total(&event_kind));
+            flamegraph.write(
+                &output_path,
+                &mut options,
+                stacks_lines.iter().map(String::as_str),
+            )?;
+
+            let mut flamegraph_summary = FlamegraphSummary::new(event_kind);
+            flamegraph_summary.regular_path = Some(output_path.to_path());
+            Ok(flamegraph_summary)
+        });
+        (output_path, result)
+    }
+
+    #[test]
+    fn test_create_each_event_kind_when_one_event_kind_fails() {
+        let dir = tempfile::tempdir().unwrap();
+
+        let (output_path, result) =
+            create_each_test_flamegraph(dir.path(), &[EventKind::Ge, EventKind::Ir]);
+
+        let flamegraph_summaries = result.unwrap();
+        assert_eq!(flamegraph_summaries.len(), 1);
+        assert_eq!(flamegraph_summaries[0].event_kind, EventKind::Ir);
+        assert_eq!(
+            svg_files(&output_path),
+            vec!["callgrind.name.flamegraph.Ir.svg"]
+        );
+    }
+
+    #[test]
+    fn test_create_each_event_kind_when_all_event_kinds_fail() {
+        let dir = tempfile::tempdir().unwrap();
+
+        let (output_path, result) = create_each_test_flamegraph(dir.path(), &[EventKind::Ge]);
+
+        let error = result.unwrap_err().to_string();
+        assert!(error.starts_with("Failed creating the flamegraphs of all event kinds:\nGe: "));
+        assert!(svg_files(&output_path).is_empty());
+    }
+
+    fn create_test_svgs(dir: &Path, files: &[&str]) -> OutputPath {
+        let tool_output_path = ToolOutputPath::new(
+            ToolOutputPathKind::Out,
+            crate::runner::tool::ValgrindTool::Callgrind,
+            &BaselineKind::Old,
+            dir,
+            dir,
+            "module",
+            "name",
+        );
+        let output_path = OutputPath::new(&tool_output_path, EventKind::Ir);
+        output_path.init().unwrap();
+        for file in files {
+            std::fs::write(output_path.dir.join(file), file).unwrap();
+        }
+        output_path
+    }
+
+    #[test]
+    fn test_output_path_of_named_baseline_in_baseline_dir() {
+        let tool_output_path = ToolOutputPath::new(
+            ToolOutputPathKind::Out,
+            crate::runner::tool::ValgrindTool::Callgrind,
+            &BaselineKind::Name("foo".parse().unwrap()),
+            Path::new("/target"),
+            Path::new("/baselines"),
+            "module",
+            "name",
+        );
+        let output_path = OutputPath::new(&tool_output_path, EventKind::Ir);
+
+        assert_eq!(
+            output_path.to_path(),
+            PathBuf::from("/target/module/name/callgrind.name.flamegraph.Ir.svg")
+        );
+        assert_eq!(
+            output_path.to_base_path().to_path(),
+            PathBuf::from("/baselines/module/name/callgrind.name.flamegraph.Ir.base@foo.svg")
+        );
+        assert_eq!(
+            output_path.to_diff_path().to_path(),
+            PathBuf::from("/target/module/name/callgrind.name.flamegraph.Ir.diff.base@foo.svg")
+        );
+    }
+
+    fn svg_files(output_path: &OutputPath) -> Vec<String> {
+        let mut files = std::fs::read_dir(&output_path.dir)
+            .unwrap()
+            .map(|e| e.unwrap().file_name().to_string_lossy().to_string())
+            .collect::<Vec<_>>();
+        files.sort();
+        files
+    }
+
+    #[rstest]
+    #[case::backup(
+        true,
+        vec![
+            "callgrind.name.flamegraph.Dr.old.svg",
+            "callgrind.name.flamegraph.Ir.old.svg",
+        ]
+    )]
+    #[case::no_backup(false, vec![])]
+    fn test_output_path_shift(#[case] backup: bool, #[case] expected: Vec<&str>) {
+        let dir = tempfile::tempdir().unwrap();
+        let output_path = create_test_svgs(
+            dir.path(),
+            &[
+                "callgrind.name.flamegraph.Ir.svg",
+                "callgrind.name.flamegraph.Ir.old.svg",
+                "callgrind.name.flamegraph.Dr.svg",
+            ],
+        );
+
+        output_path.shift(true, backup).unwrap();
+        assert_eq!(svg_files(&output_path), expected);
+    }
+
+    #[test]
+    fn test_output_path_shift_keeps_content() {
+        let dir = tempfile::tempdir().unwrap();
+        let output_path = create_test_svgs(dir.path(), &["callgrind.name.flamegraph.Ir.svg"]);
+
+        output_path.shift(false, true).unwrap();
+        assert_eq!(
+            std::fs::read_to_string(output_path.to_base_path().to_path()).unwrap(),
+            "callgrind.name.flamegraph.Ir.svg"
+        );
+    }
+
+    #[test]
+    fn test_output_path_shift_with_tag() {
+        let dir = tempfile::tempdir().unwrap();
+        let output_path = create_test_svgs(
+            dir.path(),
+            &[
+                "callgrind.name.flamegraph.Ir.svg",
+                "callgrind.name.flamegraph@abc123.Ir.svg",
+                "callgrind.name.flamegraph@abc123.Ir.old.svg",
+                "callgrind.name.flamegraph@abc123.Ir.diff.old.svg",
+                "callgrind.name.flamegraph@def456.Ir.svg",
+            ],
+        )
+        .with_tag(Some("abc123".to_owned()));
+
+        output_path.to_diff_path().clear(true).unwrap();
+        output_path.shift(true, true).unwrap();
+        assert_eq!(
+            svg_files(&output_path),
+            vec![
+                "callgrind.name.flamegraph.Ir.svg",
+                "callgrind.name.flamegraph@abc123.Ir.old.svg",
+                "callgrind.name.flamegraph@def456.Ir.svg",
+            ]
+        );
+    }
+
+    #[rstest]
+    #[case::regular(OutputPathKind::Regular, "callgrind.name.flamegraph@v1.2.Ir.svg")]
+    #[case::old(OutputPathKind::Old, "callgrind.name.flamegraph@v1.2.Ir.old.svg")]
+    #[case::base(
+        OutputPathKind::Base("foo".to_owned()),
+        "callgrind.name.flamegraph@v1.2.Ir.base@foo.svg"
+    )]
+    #[case::diff_old(
+        OutputPathKind::DiffOld,
+        "callgrind.name.flamegraph@v1.2.Ir.diff.old.svg"
+    )]
+    #[case::diff_bases(
+        OutputPathKind::DiffBases("foo".to_owned(), "bar".to_owned()),
+        "callgrind.name.flamegraph@v1.2.Ir.base@foo.diff.base@bar.svg"
+    )]
+    fn test_output_path_file_name_with_tag(#[case] kind: OutputPathKind, #[case] expected: &str) {
+        let dir = tempfile::tempdir().unwrap();
+        let mut output_path = create_test_svgs(dir.path(), &[]).with_tag(Some("v1.2".to_owned()));
+        output_path.kind = kind;
+
+        assert_eq!(output_path.file_name(), expected);
+    }
+
+    #[rstest]
+    #[case::old(BaselineKind::Old, "callgrind.name.flamegraph.Ir.diff.old.html")]
+    #[case::base(
+        BaselineKind::Name("foo".parse().unwrap()),
+        "callgrind.name.flamegraph.Ir.diff.base@foo.html"
+    )]
+    fn test_output_path_to_side_by_side_path(
+        #[case] baseline_kind: BaselineKind,
+        #[case] expected: &str,
+    ) {
+        let dir = tempfile::tempdir().unwrap();
+        let mut output_path = create_test_svgs(dir.path(), &[]);
+        output_path.baseline_kind = baseline_kind;
+
+        assert_eq!(output_path.to_side_by_side_path().file_name(), expected);
+    }
+
+    #[test]
+    fn test_output_path_clear_side_by_side() {
+        let dir = tempfile::tempdir().unwrap();
+        let output_path = create_test_svgs(
+            dir.path(),
+            &[
+                "callgrind.name.flamegraph.Ir.svg",
+                "callgrind.name.flamegraph.Ir.diff.old.svg",
+                "callgrind.name.flamegraph.Ir.diff.old.html",
+                "callgrind.name.flamegraph.Dr.diff.old.html",
+            ],
+        );
+
+        output_path.to_side_by_side_path().clear(true).unwrap();
+        assert_eq!(
+            svg_files(&output_path),
+            vec![
+                "callgrind.name.flamegraph.Ir.diff.old.svg",
+                "callgrind.name.flamegraph.Ir.svg",
+            ]
+        );
+
+        output_path.clear_diff().unwrap();
+        assert_eq!(svg_files(&output_path), vec!["callgrind.name.flamegraph.Ir.svg"]);
+    }
+
+    #[test]
+    fn test_create_side_by_side() {
+        let dir = tempfile::tempdir().unwrap();
+        let output_path = create_test_svgs(dir.path(), &[]);
+        let flamegraph = FlamegraphBuilder::new("bench & heading")
+            .kind(FlamegraphKind::SideBySide)
+            .build();
+        let base_stacks = ir_stacks([(vec!["main", "old_func"], 2)]);
+        let stacks = ir_stacks([(vec!["main", "new_func"], 3)]);
+
+        flamegraph
+            .create_side_by_side(&output_path, &base_stacks, &stacks, EventKind::Ir)
+            .unwrap();
+
+        assert_eq!(
+            svg_files(&output_path),
+            vec!["callgrind.name.flamegraph.Ir.diff.old.html"]
+        );
+        let html = std::fs::read_to_string(output_path.to_side_by_side_path().to_path()).unwrap();
+        assert!(html.contains("<title>bench &amp; heading</title>"));
+        assert_eq!(html.matches("<iframe srcdoc=").count(), 2);
+        let base = html
+            .find("<h2>callgrind.name.flamegraph.Ir.old.svg</h2>")
+            .unwrap();
+        let new = html
+            .find("<h2>callgrind.name.flamegraph.Ir.svg</h2>")
+            .unwrap();
+        assert!(base < new);
+        assert!(html[base..new].contains("old_func"));
+        assert!(html[new..].contains("new_func"));
+    }
+
+    #[test]
+    fn test_side_by_side_html() {
+        let html = side_by_side_html(
+            "title",
+            &[
+                ("old".to_owned(), "<svg a=\"b\"/>".to_owned()),
+                ("new".to_owned(), "<svg/>".to_owned()),
+            ],
+        );
+
+        assert!(html.starts_with("<!DOCTYPE html>\n"));
+        assert!(html
+            .contains("<h2>old</h2>\n<iframe srcdoc=\"&lt;svg a=&quot;b&quot;/&gt;\"></iframe>\n"));
+        assert!(html.contains("<h2>new</h2>\n<iframe srcdoc=\"&lt;svg/&gt;\"></iframe>\n"));
+        assert!(html.ends_with("</html>\n"));
+    }
+
+    #[rstest]
+    #[case::plain("func", "func")]
+    #[case::special(
+        "<a href=\"b\">'c' & d</a>",
+        "&lt;a href=&quot;b&quot;&gt;&#39;c&#39; &amp; d&lt;/a&gt;"
+    )]
+    fn test_escape_html(#[case] value: &str, #[case] expected: &str) {
+        assert_eq!(escape_html(value), expected);
+    }
+
+    #[rstest]
+    #[case::plain("abc123", Some("abc123"))]
+    #[case::baseline("base@main", Some("base@main"))]
+    #[case::path_separator("feature/foo", Some("feature_foo"))]
+    #[case::trimmed(" v1.2 ", Some("v1.2"))]
+    #[case::empty("", None)]
+    #[case::whitespace("  ", None)]
+    fn test_sanitize_tag(#[case] tag: &str, #[case] expected: Option<&str>) {
+        assert_eq!(sanitize_tag(tag).as_deref(), expected);
+    }
+
+    #[rstest]
+    #[case::read_only(io::Error::from_raw_os_error(libc::EROFS), true)]
+    #[case::full(io::Error::from_raw_os_error(libc::ENOSPC), true)]
+    #[case::not_found(io::Error::from_raw_os_error(libc::ENOENT), false)]
+    #[case::other(io::Error::new(io::ErrorKind::Other, "other"), false)]
+    fn test_is_read_only_or_full(#[case] error: io::Error, #[case] expected: bool) {
+        assert_eq!(is_read_only_or_full(&error), expected);
+    }
+
+    #[test]
+    fn test_remove_flamegraph_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("callgrind.bench.flamegraph.Ir.svg");
+        std::fs::write(&path, "<svg></svg>").unwrap();
+
+        remove_flamegraph_file(&path).unwrap();
+
+        assert!(!path.exists());
+        // Only a read-only or full file system is tolerated
+        assert!(remove_flamegraph_file(&path).is_err());
     }
 }
@@ -5,13 +5,20 @@ use std::path::PathBuf;
 use anyhow::{anyhow, Result};
 use log::debug;
 
-use super::hashmap_parser::{CallgrindMap, HashMapParser, SourcePath};
+use super::flamegraph::{Stack, Stacks};
+use super::hashmap_parser::{CallgrindMap, HashMapParser, Id, SourcePath};
+use super::model::Costs;
+use super::output_parser::CallgrindOutput;
 use super::parser::Sentinel;
-use crate::api::EventKind;
+use crate::api::{EventKind, ObjectMarker};
 use crate::runner::tool::{Parser, ToolOutputPath};
 
 #[derive(Debug, Default, PartialEq, Eq)]
-pub struct FlamegraphMap(CallgrindMap);
+pub struct FlamegraphMap {
+    map: CallgrindMap,
+    /// The synthetic root frame which is prepended to all stacks of the flamegraphs
+    root_frame: Option<String>,
+}
 
 #[derive(Debug)]
 pub struct FlamegraphParser {
@@ -23,15 +30,42 @@ pub struct FlamegraphParser {
 struct HeapElem {
     source: String,
     cost: u64,
+    /// The callers of the entry point come first, then the entry point and then its callees
+    rank: u8,
 }
 
 impl FlamegraphMap {
     pub fn is_empty(&self) -> bool {
-        self.0.is_empty()
+        self.map.is_empty()
+    }
+
+    /// Return the frames of the parts of functions which were inlined from another source file
+    ///
+    /// The frames are formatted like the frames of [`FlamegraphMap::to_stacks`] with the
+    /// `object_marker`.
+    pub fn inlined_frames(&self, object_marker: ObjectMarker) -> impl Iterator<Item = String> + '_ {
+        self.map
+            .inlined_keys
+            .iter()
+            .map(move |id| format_frame(id, object_marker))
+    }
+
+    /// The synthetic root frame of the stacks if any
+    pub fn root_frame(&self) -> Option<&str> {
+        self.root_frame.as_deref()
+    }
+
+    /// Set the synthetic root frame which is prepended to all stacks of the flamegraphs
+    ///
+    /// The root frame is not part of the stacks created with [`FlamegraphMap::to_stacks`] but is
+    /// added by [`super::flamegraph::Flamegraph::to_stacks`] after all transformations of the
+    /// stacks.
+    pub fn set_root_frame(&mut self, root_frame: Option<String>) {
+        self.root_frame = root_frame;
     }
 
     pub fn make_summary(&mut self) -> Result<()> {
-        for value in self.0.map.values_mut() {
+        for value in self.map.map.values_mut() {
             if value.costs.can_summarize() {
                 value
                     .costs
@@ -44,112 +78,115 @@ impl FlamegraphMap {
         Ok(())
     }
 
-    // Convert to stacks string format for this `EventType`
+    // Convert to [`Stacks`] for this `EventType`
+    //
+    // The costs of each [`super::flamegraph::Stack`] contain only the costs of this `EventType`.
+    //
+    // If `trim_harness` is true, the frames above the entry point (the sentinel or "main") are
+    // removed, so the stacks start with the entry point. These are the frames of the benchmark
+    // harness like `std::rt::lang_start` which call the entry point as recorded in the
+    // `harness_keys` of the map. Frames below the entry point are never removed, even if they
+    // have the same inclusive cost as the entry point.
     //
     // # Errors
     //
     // If the event type was not present in the stacks
-    #[allow(clippy::too_many_lines)]
-    pub fn to_stack_format(&self, event_kind: &EventKind) -> Result<Vec<String>> {
-        if self.0.map.is_empty() {
-            return Ok(vec![]);
+    pub fn to_stacks(
+        &self,
+        event_kind: &EventKind,
+        trim_harness: bool,
+        object_marker: ObjectMarker,
+    ) -> Result<Stacks> {
+        if self.map.map.is_empty() {
+            return Ok(Stacks::default());
         }
 
         // Let's find our entry point which defaults to "main"
-        let reference_cost = if let Some(key) = &self.0.sentinel_key {
-            self.0
-                .map
-                .get(key)
+        let (reference_id, reference) = if let Some(key) = &self.map.sentinel_key {
+            self.map
+                .get_key_value(key)
                 .expect("Resolved sentinel must be present in map")
-                .costs
-                .cost_by_kind(event_kind)
-                .ok_or_else(|| {
-                    anyhow!("Failed creating flamegraph stack: Missing event type '{event_kind}'")
-                })?
         } else {
-            self.0
-                .map
-                .iter()
-                .find(|(k, _)| k.func == "main")
+            self.map
+                .entry_point()
+                .and_then(|key| self.map.get_key_value(key))
                 .expect("'main' function must be present in callgrind output")
-                .1
-                .costs
-                .cost_by_kind(event_kind)
-                .ok_or_else(|| {
-                    anyhow!("Failed creating flamegraph stack: Missing event type '{event_kind}'")
-                })?
         };
+        let reference_cost = reference.cost(event_kind).ok_or_else(|| {
+            anyhow!("Failed creating flamegraph stack: Missing event type '{event_kind}'")
+        })?;
 
         let mut heap = BinaryHeap::new();
-        for (id, value) in &self.0.map {
-            let cost = value.costs.cost_by_kind(event_kind).ok_or_else(|| {
+        for (id, value) in &self.map.map {
+            let cost = value.cost(event_kind).ok_or_else(|| {
                 anyhow!("Failed creating flamegraph stack: Missing event type '{event_kind}'")
             })?;
+            let is_harness = self.map.harness_keys.contains(id);
+            // The harness frames have the same inclusive cost as the entry point, so no costs
+            // get lost
+            if trim_harness && is_harness {
+                continue;
+            }
             if cost <= reference_cost {
-                let mut source = String::new();
-                if let Some(file) = &id.file {
-                    match file {
-                        SourcePath::Unknown => write!(source, "{}", id.func).unwrap(),
-                        SourcePath::Rust(path)
-                        | SourcePath::Relative(path)
-                        | SourcePath::Absolute(path) => {
-                            write!(source, "{}:{}", path.display(), id.func).unwrap();
-                        }
-                    }
-                } else {
-                    write!(source, "{}", id.func).unwrap();
-                };
-                if let Some(path) = &id.obj {
-                    match path {
-                        SourcePath::Unknown => {}
-                        SourcePath::Rust(path)
-                        | SourcePath::Relative(path)
-                        | SourcePath::Absolute(path) => {
-                            write!(source, " [{}]", path.display()).unwrap();
-                        }
-                    }
-                }
-                heap.push(HeapElem { source, cost });
+                heap.push(HeapElem {
+                    source: format_frame(id, object_marker),
+                    cost,
+                    rank: if is_harness {
+                        0
+                    } else if id == reference_id {
+                        1
+                    } else {
+                        2
+                    },
+                });
             }
         }
 
-        let mut stacks: Vec<String> = vec![];
-        let len = heap.len();
-        if len > 1 {
-            for window in heap.into_sorted_vec().windows(2) {
-                // There is only the slice size of 2 possible due to the window size of 2
-                if let [h1, h2] = window {
-                    let stack = if let Some(last) = stacks.last() {
-                        // This unwrap is safe since the space must be present due to stack format
-                        let (split, _) = last.rsplit_once(' ').unwrap();
-                        format!("{split};{} {}", h1.source, h1.cost - h2.cost)
-                    } else {
-                        format!("{} {}", h1.source, h1.cost - h2.cost)
-                    };
-
-                    stacks.push(stack);
-
-                    // The last window needs to push the last element too
-                    if stacks.len() == len - 1 {
-                        // This unwrap is safe since we have a last element the moment we enter the
-                        // `if` statement
-                        let last = stacks.last().unwrap();
-                        let (split, _) = last.rsplit_once(' ').unwrap();
-
-                        let stack = format!("{split};{} {}", h2.source, h2.cost);
-                        stacks.push(stack);
-                    }
-                }
-            }
-        } else {
-            // This unwrap is safe since heap.len() == 1 here
-            let elem = heap.pop().unwrap();
-            stacks.push(format!("{} {}", elem.source, elem.cost));
+        // The elements are sorted by descending cost. Each element is called by its predecessor,
+        // so the self cost of an element is the difference to the cost of its successor.
+        let sorted = heap.into_sorted_vec();
+        let mut stacks = Stacks::default();
+        let mut frames: Vec<String> = vec![];
+        for (index, elem) in sorted.iter().enumerate() {
+            frames.push(elem.source.clone());
+            let cost = sorted
+                .get(index + 1)
+                .map_or(elem.cost, |next| elem.cost - next.cost);
+            stacks.push(Stack::new(
+                frames.clone(),
+                Costs::with_event_kinds([(*event_kind, cost)]),
+            ));
         }
         Ok(stacks)
     }
 }
 
+/// Format the frame of the function with this `id` marking the object file with the
+/// `object_marker`
+fn format_frame(id: &Id, object_marker: ObjectMarker) -> String {
+    let mut source = String::new();
+    if let Some(file) = &id.file {
+        match file {
+            SourcePath::Unknown => write!(source, "{}", id.func).unwrap(),
+            SourcePath::Rust(path) | SourcePath::Relative(path) | SourcePath::Absolute(path) => {
+                write!(source, "{}:{}", path.display(), id.func).unwrap();
+            }
+        }
+    } else {
+        write!(source, "{}", id.func).unwrap();
+    };
+    if let Some(SourcePath::Rust(path) | SourcePath::Relative(path) | SourcePath::Absolute(path)) =
+        &id.obj
+    {
+        match object_marker {
+            ObjectMarker::Bracketed => write!(source, " [{}]", path.display()).unwrap(),
+            ObjectMarker::Suffix => write!(source, " (in {})", path.display()).unwrap(),
+            ObjectMarker::None => {}
+        }
+    }
+    source
+}
+
 impl FlamegraphParser {
     pub fn new<P>(sentinel: Option<&Sentinel>, project_root: P) -> Self
     where
@@ -173,7 +210,27 @@ impl Parser for FlamegraphParser {
             sentinel: self.sentinel.clone(),
         };
 
-        parser.parse(output_path).map(FlamegraphMap)
+        parser.parse(output_path).map(FlamegraphMap::from)
+    }
+}
+
+impl From<CallgrindMap> for FlamegraphMap {
+    fn from(value: CallgrindMap) -> Self {
+        Self {
+            map: value,
+            root_frame: None,
+        }
+    }
+}
+
+impl TryFrom<&CallgrindOutput> for FlamegraphMap {
+    type Error = anyhow::Error;
+
+    /// Parse the stacks of the callgrind output file with its sentinel and project root in one
+    /// step
+    fn try_from(value: &CallgrindOutput) -> Result<Self> {
+        FlamegraphParser::new(value.sentinel.as_ref(), value.project_root.clone())
+            .parse(&value.path)
     }
 }
 
@@ -182,6 +239,7 @@ impl Ord for HeapElem {
         self.cost
             .cmp(&other.cost)
             .reverse()
+            .then_with(|| self.rank.cmp(&other.rank))
             .then_with(|| self.source.cmp(&other.source))
     }
 }
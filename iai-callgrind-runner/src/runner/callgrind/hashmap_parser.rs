@@ -1,16 +1,16 @@
 use std::cmp::Ordering;
 use std::collections::hash_map::Iter;
-use std::collections::HashMap;
-use std::ffi::OsStr;
-use std::path::{Component, Path, PathBuf};
+use std::collections::{HashMap, HashSet};
+use std::path::PathBuf;
 
 use anyhow::Result;
 use log::trace;
 use serde::{Deserialize, Serialize};
 
+use super::call_graph::{CallGraph, CallGraphParser};
 use super::model::Costs;
-use super::parser::{parse_header, Sentinel};
-use crate::error::Error;
+use super::parser::Sentinel;
+use crate::api::EventKind;
 use crate::runner::tool::{Parser, ToolOutputPath};
 
 #[derive(Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
@@ -18,27 +18,24 @@ pub struct CallgrindMap {
     pub map: HashMap<Id, Value>,
     pub sentinel: Option<Sentinel>,
     pub sentinel_key: Option<Id>,
-}
-
-#[derive(Debug, Default)]
-struct CfnRecord {
-    obj: Option<SourcePath>,
-    file: Option<SourcePath>,
-    id: Option<Id>,
-    calls: u64,
-}
-
-#[derive(Debug, Default, Clone)]
-struct CurrentId {
-    obj: Option<SourcePath>,
-    file: Option<SourcePath>,
-    func: Option<String>,
+    /// The keys of all functions which (directly or indirectly) call the entry point
+    ///
+    /// The entry point is the function of the `sentinel_key` or else `main`. These functions are
+    /// the frames of the benchmark harness like `std::rt::lang_start`.
+    #[serde(default)]
+    pub harness_keys: HashSet<Id>,
+    /// The keys of the parts of functions which were inlined from another source file
+    ///
+    /// See [`super::call_graph::Function::inlined`]
+    #[serde(default)]
+    pub inlined_keys: HashSet<Id>,
 }
 
 /// Parse a callgrind outfile into a `HashMap`
 ///
 /// This parser is a based on `callgrind_annotate` and how the summarize the function inclusive
-/// costs.
+/// costs. The `HashMap` contains the inclusive costs of the functions of the
+/// [`super::call_graph::CallGraph`].
 #[derive(Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
 pub struct HashMapParser {
     pub sentinel: Option<Sentinel>,
@@ -65,7 +62,124 @@ pub struct Value {
     pub costs: Costs,
 }
 
+impl Id {
+    /// The name of the function without the calling context
+    ///
+    /// With callgrind's `--separate-callers=N`, the name of a function is qualified with up to
+    /// `N` callers separated by `'` like `foo'bar'main` for the function `foo` called by `bar`
+    /// which was called by `main`. The costs of `foo` in each calling context are separate
+    /// functions (and frames in the flamegraphs). Without separated callers, this is the `func`.
+    ///
+    /// The `'` of a lifetime like in `for<'a>` or `&'a str` is not a separator. A separator always
+    /// follows the end of a function name.
+    pub fn name(&self) -> &str {
+        self.func
+            .char_indices()
+            .zip(self.func.chars().skip(1))
+            .find(|((_, previous), next)| {
+                *next == '\''
+                    && (previous.is_alphanumeric()
+                        || matches!(previous, '_' | '>' | ')' | ']' | '}'))
+            })
+            .map_or(self.func.as_str(), |((index, previous), _)| {
+                &self.func[..index + previous.len_utf8()]
+            })
+    }
+}
+
+impl Value {
+    /// Return the inclusive cost of the function for this [`EventKind`] if present
+    pub fn cost(&self, event_kind: &EventKind) -> Option<u64> {
+        self.costs.cost_by_kind(event_kind)
+    }
+}
+
 impl CallgrindMap {
+    /// Create a new `CallgrindMap` from the `functions` and their inclusive costs
+    ///
+    /// The key of the last function matching the `sentinel` is the `sentinel_key`.
+    pub fn from_functions<I>(functions: I, sentinel: Option<&Sentinel>) -> Self
+    where
+        I: IntoIterator<Item = (Id, Costs)>,
+    {
+        let mut sentinel_key = None;
+        let map = functions
+            .into_iter()
+            .inspect(|(id, _)| {
+                if sentinel.map_or(false, |sentinel| sentinel.matches(&id.func)) {
+                    trace!("Found sentinel: {}", id.func);
+                    sentinel_key = Some(id.clone());
+                }
+            })
+            .map(|(id, costs)| (id, Value { costs }))
+            .collect::<HashMap<_, _>>();
+
+        Self {
+            map,
+            sentinel: sentinel.cloned(),
+            sentinel_key,
+            harness_keys: HashSet::new(),
+            inlined_keys: HashSet::new(),
+        }
+    }
+
+    /// Create a new `CallgrindMap` from the inclusive costs of the functions of the `call_graph`
+    ///
+    /// In contrast to [`CallgrindMap::from_functions`], the callers of the entry point are
+    /// resolved with the calls of the `call_graph` and stored in the `harness_keys`. The parts of
+    /// a function in other (inlined) source files have separate keys, so all keys of a function
+    /// with the same name and object file are considered to be the same function.
+    pub fn from_call_graph(call_graph: &CallGraph, sentinel: Option<&Sentinel>) -> Self {
+        let mut map = Self::from_functions(
+            call_graph
+                .functions
+                .iter()
+                .map(|(id, function)| (id.clone(), function.inclusive_costs.clone())),
+            sentinel,
+        );
+        map.inlined_keys = call_graph
+            .functions
+            .iter()
+            .filter(|(_, function)| function.inlined)
+            .map(|(id, _)| id.clone())
+            .collect();
+
+        let Some(entry_point) = map.entry_point().cloned() else {
+            return map;
+        };
+        let is_same_function = |a: &Id, b: &Id| a.func == b.func && a.obj == b.obj;
+
+        let mut queue = vec![entry_point.clone()];
+        while let Some(id) = queue.pop() {
+            let callers = call_graph
+                .calls
+                .keys()
+                .filter(|(_, callee)| is_same_function(callee, &id));
+            for (caller, _) in callers {
+                if is_same_function(caller, &entry_point) || map.harness_keys.contains(caller) {
+                    continue;
+                }
+                map.harness_keys.extend(
+                    call_graph
+                        .functions
+                        .keys()
+                        .filter(|key| is_same_function(key, caller))
+                        .cloned(),
+                );
+                queue.push(caller.clone());
+            }
+        }
+
+        map
+    }
+
+    /// Return the key of the entry point, the function of the `sentinel_key` or else `main`
+    pub fn entry_point(&self) -> Option<&Id> {
+        self.sentinel_key
+            .as_ref()
+            .or_else(|| self.map.keys().find(|id| id.name() == "main"))
+    }
+
     pub fn is_empty(&self) -> bool {
         self.map.is_empty()
     }
@@ -89,161 +203,19 @@ impl<'a> IntoIterator for &'a CallgrindMap {
     }
 }
 
-impl From<Id> for CurrentId {
-    fn from(value: Id) -> Self {
-        CurrentId {
-            obj: value.obj,
-            file: value.file,
-            func: Some(value.func),
-        }
-    }
-}
-
-impl TryFrom<CurrentId> for Id {
-    type Error = String;
-
-    fn try_from(value: CurrentId) -> std::result::Result<Self, Self::Error> {
-        match value.func {
-            Some(func) => Ok(Id {
-                obj: value.obj,
-                file: value.file,
-                func,
-            }),
-            None => Err("Missing function".to_owned()),
-        }
-    }
-}
-
 impl Parser for HashMapParser {
     type Output = CallgrindMap;
 
-    #[allow(clippy::too_many_lines)]
-    #[allow(clippy::similar_names)]
     fn parse(&self, output_path: &ToolOutputPath) -> Result<Self::Output> {
-        let mut iter = output_path.lines()?;
-        let config = parse_header(&mut iter)
-            .map_err(|error| Error::ParseError((output_path.to_path(), error.to_string())))?;
-
-        let mut current_id = CurrentId::default();
-        let mut cfn_record = None;
-
-        let mut cfn_totals = HashMap::<Id, Value>::new();
-        let mut fn_totals = HashMap::<Id, Value>::new();
-
-        let mut sentinel_key = None;
-
-        // We start within he header
-        let mut is_header = true;
-        for line in iter {
-            let line = line.trim();
-
-            if line.is_empty() || line.starts_with('#') {
-                continue;
-            }
-
-            // The first line which can be split around '=' is a non header line
-            let split = if is_header {
-                if let Some(split) = line.split_once('=') {
-                    is_header = false;
-                    Some(split)
-                } else {
-                    continue;
-                }
-            } else {
-                line.split_once('=')
-            };
-
-            match split {
-                Some(("ob", obj)) => {
-                    current_id.obj = Some(make_path(&self.project_root, obj));
-                }
-                Some(("fl", file)) => {
-                    current_id.file = Some(make_path(&self.project_root, file));
-                }
-                Some(("fn", func)) => {
-                    current_id.func = Some(func.to_owned());
-
-                    if self
-                        .sentinel
-                        .as_ref()
-                        .map_or(false, |sentinel| sentinel.matches(func))
-                    {
-                        trace!("Found sentinel: {}", func);
-                        sentinel_key = Some(current_id.clone().try_into().expect("A valid id"));
-                    }
-                }
-                Some(("fi" | "fe", inline)) => {
-                    current_id.file = Some(make_path(&self.project_root, inline));
-                }
-                Some(("cob", cob)) => {
-                    let record = cfn_record.get_or_insert(CfnRecord::default());
-                    record.obj = Some(make_path(&self.project_root, cob));
-                }
-                Some(("cfi" | "cfl", inline)) => {
-                    let record = cfn_record.get_or_insert(CfnRecord::default());
-                    record.file = Some(make_path(&self.project_root, inline));
-                }
-                Some(("cfn", cfn)) => {
-                    let record = cfn_record.get_or_insert(CfnRecord::default());
-                    record.id = Some(Id {
-                        obj: record.obj.take().or(current_id.obj.clone()),
-                        func: cfn.to_owned(),
-                        file: record.file.take().or(current_id.file.clone()),
-                    });
-                }
-                Some(("calls", calls)) => {
-                    let record = cfn_record.as_mut().expect("Valid calls line");
-                    record.calls = calls
-                        .split_ascii_whitespace()
-                        .take(1)
-                        .map(|s| s.parse::<u64>().unwrap())
-                        .sum();
-                }
-                None if line.starts_with(|c: char| c.is_ascii_digit()) => {
-                    let mut costs = config.costs_prototype.clone();
-                    costs.add_iter_str(
-                        line.split_whitespace()
-                            .skip(config.positions_prototype.len()),
-                    );
-
-                    if let Some(cfn_record) = cfn_record.take() {
-                        cfn_totals
-                            .entry(cfn_record.id.expect("cfn record id must be present"))
-                            .and_modify(|value| value.costs.add(&costs))
-                            .or_insert(Value {
-                                costs: costs.clone(),
-                            });
-                    }
-
-                    let id = current_id.try_into().expect("A valid id");
-                    match fn_totals.get_mut(&id) {
-                        Some(value) => value.costs.add(&costs),
-                        None => {
-                            fn_totals.insert(id.clone(), Value { costs });
-                        }
-                    }
-                    current_id = id.into();
-                }
-                Some(("jump" | "jcnd" | "jfi" | "jfn", _)) => {
-                    // we ignore these
-                }
-                None if line.starts_with("totals:") || line.starts_with("summary:") => {
-                    // we ignore these
-                }
-                Some(_) | None => panic!("Malformed line: '{line}'"),
-            }
+        let call_graph = CallGraphParser {
+            project_root: self.project_root.clone(),
         }
+        .parse(output_path)?;
 
-        // Correct inclusive totals
-        for (key, value) in cfn_totals {
-            fn_totals.insert(key, value);
-        }
-
-        Ok(CallgrindMap {
-            map: fn_totals,
-            sentinel: self.sentinel.clone(),
-            sentinel_key,
-        })
+        Ok(CallgrindMap::from_call_graph(
+            &call_graph,
+            self.sentinel.as_ref(),
+        ))
     }
 }
 
@@ -268,27 +240,3 @@ impl PartialOrd for SourcePath {
         Some(self.cmp(other))
     }
 }
-
-fn make_path(root: &Path, source: &str) -> SourcePath {
-    if source == "???" {
-        SourcePath::Unknown
-    } else {
-        let path = PathBuf::from(source);
-        match path.strip_prefix(root).ok() {
-            Some(stripped) => SourcePath::Relative(stripped.to_owned()),
-            None if path.is_absolute() => {
-                let mut components = path.components().skip(1);
-                if components.next() == Some(Component::Normal(OsStr::new("rustc"))) {
-                    let mut new_path = PathBuf::from("/rustc");
-                    if let Some(Component::Normal(string)) = components.next() {
-                        new_path.push(string.to_string_lossy().chars().take(8).collect::<String>());
-                    }
-                    SourcePath::Rust(new_path.join(components.collect::<PathBuf>()))
-                } else {
-                    SourcePath::Absolute(path)
-                }
-            }
-            None => SourcePath::Relative(path),
-        }
-    }
-}
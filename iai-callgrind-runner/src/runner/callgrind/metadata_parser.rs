@@ -0,0 +1,560 @@
+use std::fs::File;
+use std::io::BufWriter;
+use std::path::Path;
+
+use anyhow::{anyhow, Context, Result};
+use log::{debug, warn};
+use serde::{Deserialize, Serialize};
+
+use crate::error::Error;
+use crate::runner::meta::Metadata;
+use crate::runner::tool::{Parser, RunOptions, ToolOutputPath};
+use crate::util::{hash_bytes, hash_file};
+
+/// The properties of a callgrind or cachegrind output file which influence the collected costs
+///
+/// Costs of output files with different `OutputMetadata` are not comparable, for example after an
+/// update of valgrind or a change of the `--cache-sim` setting.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct OutputMetadata {
+    /// The valgrind tool and version from the `creator` line, like `callgrind-3.22.0`
+    pub creator: Option<String>,
+    /// The `desc` lines describing the simulated caches, like `I1 cache: 32768 B, 64 B, 8-way
+    /// associative`
+    pub caches: Vec<String>,
+    /// The collected events
+    pub events: Vec<String>,
+    /// The command line of the process from the `cmd` line, like `/path/to/my-bin --foo`
+    pub cmd: Option<String>,
+    /// The pid of the process from the `pid` line
+    pub pid: Option<i32>,
+    /// The [`RunMetadata`] if it was recorded
+    pub run: Option<RunMetadata>,
+}
+
+/// The properties of the benchmark run which are not recorded by valgrind in the output file
+///
+/// The `RunMetadata` is stored next to the output file in a `*.meta` file.
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RunMetadata {
+    /// True if the inherited environment variables were cleared
+    pub env_clear: bool,
+    /// The environment variables which were set in the environment of the benchmark run
+    pub envs: Vec<(String, String)>,
+    /// The hash of the content of the benchmarked executable
+    pub binary_hash: Option<String>,
+    /// The hash of the effective arguments of the valgrind tool without the paths of the output
+    /// files
+    pub config_hash: Option<String>,
+    /// The architecture of the machine the benchmark ran on, like `x86_64` or `aarch64`
+    pub arch: Option<String>,
+    /// The cpus the valgrind process was pinned to with `--cpu-affinity`, like `0-3,8`
+    pub cpu_affinity: Option<String>,
+    /// The path of the benchmarked executable as passed to valgrind
+    ///
+    /// Unlike the `cmd` line of the output file, the path is not mixed up with the arguments, so
+    /// it's unambiguous even if the path contains spaces.
+    pub executable: Option<String>,
+}
+
+pub struct MetadataParser;
+
+impl OutputMetadata {
+    /// Return a description of each property which differs between `self` (new) and `old`
+    pub fn mismatches(&self, old: &Self) -> Vec<String> {
+        let mut mismatches = vec![];
+        if self.creator != old.creator {
+            mismatches.push(format!(
+                "valgrind version '{}' (old: '{}')",
+                self.creator.as_deref().unwrap_or("unknown"),
+                old.creator.as_deref().unwrap_or("unknown")
+            ));
+        }
+        if self.caches != old.caches {
+            mismatches.push(format!(
+                "caches '{}' (old: '{}')",
+                self.caches.join("; "),
+                old.caches.join("; ")
+            ));
+        }
+        if self.events != old.events {
+            mismatches.push(format!(
+                "events '{}' (old: '{}')",
+                self.events.join(" "),
+                old.events.join(" ")
+            ));
+        }
+        // The run metadata is missing in the outputs of earlier versions of iai-callgrind
+        if let (Some(new), Some(old)) = (&self.run, &old.run) {
+            if new.env_clear != old.env_clear {
+                mismatches.push(format!(
+                    "cleared environment '{}' (old: '{}')",
+                    new.env_clear, old.env_clear
+                ));
+            }
+            if new.envs != old.envs {
+                mismatches.push(format!(
+                    "environment variables '{}' (old: '{}')",
+                    new.envs_to_string(),
+                    old.envs_to_string()
+                ));
+            }
+            // The architecture is missing in the run metadata of earlier versions of iai-callgrind
+            if let (Some(new_arch), Some(old_arch)) = (&new.arch, &old.arch) {
+                if new_arch != old_arch {
+                    mismatches.push(format!("architecture '{new_arch}' (old: '{old_arch}')"));
+                }
+            }
+            // The hashes are missing in the run metadata of earlier versions of iai-callgrind
+            if let (Some(new_hash), Some(old_hash)) = (&new.config_hash, &old.config_hash) {
+                if new_hash != old_hash {
+                    mismatches.push(format!(
+                        "valgrind arguments with hash '{new_hash}' (old: '{old_hash}')"
+                    ));
+                }
+            }
+        }
+        mismatches
+    }
+
+    /// Return the hashes of the benchmarked executable of `self` (new) and `old` if they differ
+    ///
+    /// Return `None` if the hashes are equal or if any of the hashes is unknown.
+    pub fn binary_change<'a>(&'a self, old: &'a Self) -> Option<(&'a str, &'a str)> {
+        let new_hash = self.run.as_ref()?.binary_hash.as_deref()?;
+        let old_hash = old.run.as_ref()?.binary_hash.as_deref()?;
+        (new_hash != old_hash).then_some((new_hash, old_hash))
+    }
+
+    /// Check if the benchmarked executable changed between the `old` and the new output of the
+    /// `output_path`
+    ///
+    /// A changed executable is usually expected, since the benchmarked code changed, so it is only
+    /// a warning. But if the costs of the same build are compared, for example against a baseline
+    /// which was created with the same executable, a changed executable means the comparison is
+    /// unreliable.
+    ///
+    /// # Errors
+    ///
+    /// If the executable changed and `--fail-on-binary-change` is set
+    pub fn check_binary_change(
+        &self,
+        old: &Self,
+        output_path: &ToolOutputPath,
+        meta: &Metadata,
+    ) -> Result<()> {
+        let Some((new_hash, old_hash)) = self.binary_change(old) else {
+            debug!("{output_path}: The benchmarked executable is unchanged or unknown");
+            return Ok(());
+        };
+
+        if meta.args.fail_on_binary_change.unwrap_or_default() {
+            Err(anyhow!(
+                "The output '{output_path}' was created with a different benchmark executable \
+                 (hash '{new_hash}', old: '{old_hash}') than the output it is compared with. \
+                 Refusing to compare the costs of different builds."
+            ))
+        } else {
+            warn!(
+                "{output_path}: The benchmarked executable changed (hash '{new_hash}', old: \
+                 '{old_hash}')"
+            );
+            Ok(())
+        }
+    }
+
+    /// Warn about differences between the new output of the `output_path` and the `old` output
+    ///
+    /// # Errors
+    ///
+    /// If there are differences and `--fail-on-valgrind-mismatch` is set or if the benchmarked
+    /// executable changed and `--fail-on-binary-change` is set
+    pub fn check_mismatch(
+        &self,
+        old: &Self,
+        output_path: &ToolOutputPath,
+        meta: &Metadata,
+    ) -> Result<()> {
+        self.check_binary_change(old, output_path, meta)?;
+
+        let mismatches = self.mismatches(old);
+        if mismatches.is_empty() {
+            return Ok(());
+        }
+
+        let message = format!(
+            "The output '{output_path}' was created with a different valgrind version, different \
+             flags or a different environment than the output it is compared with: {}",
+            mismatches.join(", ")
+        );
+        if meta.args.fail_on_valgrind_mismatch.unwrap_or_default() {
+            Err(anyhow!(message))
+        } else {
+            warn!("{message}. The differences in the costs might be misleading.");
+            Ok(())
+        }
+    }
+}
+
+impl MetadataParser {
+    /// Parse the metadata of the `new` and `old` output and check them for mismatches
+    ///
+    /// # Errors
+    ///
+    /// If parsing fails or if there is a mismatch and `--fail-on-valgrind-mismatch` is set
+    pub fn check(&self, meta: &Metadata, new: &ToolOutputPath, old: &ToolOutputPath) -> Result<()> {
+        let old_metadata = self.parse(old)?;
+        self.parse(new)?.check_mismatch(&old_metadata, new, meta)
+    }
+}
+
+impl RunMetadata {
+    /// Create the `RunMetadata` from the [`RunOptions`] of the benchmark run
+    pub fn from_options(options: &RunOptions) -> Self {
+        let mut envs = options
+            .envs
+            .iter()
+            .map(|(key, value)| {
+                (
+                    key.to_string_lossy().to_string(),
+                    value.to_string_lossy().to_string(),
+                )
+            })
+            .collect::<Vec<(String, String)>>();
+        envs.sort();
+        Self {
+            env_clear: options.env_clear,
+            envs,
+            binary_hash: None,
+            config_hash: None,
+            arch: Some(std::env::consts::ARCH.to_owned()),
+            cpu_affinity: None,
+            executable: None,
+        }
+    }
+
+    /// Record the hashes of the content of the benchmarked `executable` and of the valgrind
+    /// `tool_args`
+    ///
+    /// The `tool_args` must not contain the paths of the output files, which differ between the
+    /// new output and the old output or a baseline. If the executable can't be hashed, only a
+    /// warning is logged, since the hash is not essential.
+    pub fn record_hashes(&mut self, executable: &Path, tool_args: &[String]) {
+        self.binary_hash = hash_file(executable)
+            .map_err(|error| warn!("Unable to hash the benchmarked executable: {error:#}"))
+            .ok();
+        self.config_hash = Some(hash_bytes(tool_args.join("\0").as_bytes()));
+    }
+
+    /// Load the `RunMetadata` of the `output_path` if present
+    ///
+    /// # Errors
+    ///
+    /// If the file with the `RunMetadata` exists but could not be read or deserialized
+    pub fn load(output_path: &ToolOutputPath) -> Result<Option<Self>> {
+        let meta_path = output_path.to_meta_output();
+        if !meta_path.exists() {
+            return Ok(None);
+        }
+
+        let path = meta_path.to_path();
+        let file = meta_path.open()?;
+        serde_json::from_reader(file)
+            .map(Some)
+            .with_context(|| format!("Failed to read run metadata from '{}'", path.display()))
+    }
+
+    /// Save the `RunMetadata` for the `output_path`
+    ///
+    /// # Errors
+    ///
+    /// If the file for the `RunMetadata` could not be written
+    pub fn save(&self, output_path: &ToolOutputPath) -> Result<()> {
+        let path = output_path.to_meta_output().to_path();
+        let file = File::create(&path)
+            .with_context(|| format!("Failed to create run metadata file '{}'", path.display()))?;
+        serde_json::to_writer(BufWriter::new(file), self)
+            .with_context(|| format!("Failed to write run metadata to '{}'", path.display()))
+    }
+
+    fn envs_to_string(&self) -> String {
+        self.envs
+            .iter()
+            .map(|(key, value)| format!("{key}={value}"))
+            .collect::<Vec<String>>()
+            .join(" ")
+    }
+}
+
+impl Parser for MetadataParser {
+    type Output = OutputMetadata;
+
+    fn parse(&self, output_path: &ToolOutputPath) -> Result<Self::Output> {
+        debug!("Parsing metadata of output file '{}'", output_path);
+
+        let mut metadata = OutputMetadata::default();
+        let mut found = false;
+        for line in output_path.lines()? {
+            match line.split_once(':').map(|(k, v)| (k.trim(), v.trim())) {
+                Some(("creator", creator)) => metadata.creator = Some(creator.to_owned()),
+                Some(("cmd", cmd)) => metadata.cmd = Some(cmd.to_owned()),
+                Some(("pid", pid)) => metadata.pid = pid.parse().ok(),
+                // Cachegrind aligns the values of the cache descriptions with whitespace
+                Some(("desc", desc)) if desc.contains("cache:") => {
+                    metadata
+                        .caches
+                        .push(desc.split_ascii_whitespace().collect::<Vec<_>>().join(" "));
+                }
+                // The events line is the last line of the header we're interested in
+                Some(("events", events)) => {
+                    metadata.events = events.split_ascii_whitespace().map(Into::into).collect();
+                    found = true;
+                    break;
+                }
+                _ => {}
+            }
+        }
+
+        if found {
+            metadata.run = RunMetadata::load(output_path)?;
+            Ok(metadata)
+        } else {
+            Err(Error::ParseError((
+                output_path.to_path(),
+                "Header field 'events' must be present".to_owned(),
+            ))
+            .into())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+    use rstest::rstest;
+
+    use super::*;
+
+    fn metadata(creator: Option<&str>, caches: &[&str], events: &str) -> OutputMetadata {
+        OutputMetadata {
+            creator: creator.map(ToOwned::to_owned),
+            caches: caches.iter().map(|c| (*c).to_owned()).collect(),
+            events: events.split_ascii_whitespace().map(Into::into).collect(),
+            cmd: None,
+            pid: None,
+            run: None,
+        }
+    }
+
+    fn with_run(
+        mut metadata: OutputMetadata,
+        env_clear: bool,
+        envs: &[(&str, &str)],
+    ) -> OutputMetadata {
+        metadata.run = Some(RunMetadata {
+            env_clear,
+            envs: envs
+                .iter()
+                .map(|(k, v)| ((*k).to_owned(), (*v).to_owned()))
+                .collect(),
+            binary_hash: None,
+            config_hash: None,
+            arch: None,
+            cpu_affinity: None,
+            executable: None,
+        });
+        metadata
+    }
+
+    fn with_arch(mut metadata: OutputMetadata, arch: &str) -> OutputMetadata {
+        metadata.run.get_or_insert_with(RunMetadata::default).arch = Some(arch.to_owned());
+        metadata
+    }
+
+    fn with_hashes(
+        mut metadata: OutputMetadata,
+        binary_hash: Option<&str>,
+        config_hash: Option<&str>,
+    ) -> OutputMetadata {
+        let run = metadata.run.get_or_insert_with(RunMetadata::default);
+        run.binary_hash = binary_hash.map(ToOwned::to_owned);
+        run.config_hash = config_hash.map(ToOwned::to_owned);
+        metadata
+    }
+
+    #[rstest]
+    #[case::equal(
+        metadata(Some("callgrind-3.22.0"), &["I1 cache: 32768 B"], "Ir"),
+        metadata(Some("callgrind-3.22.0"), &["I1 cache: 32768 B"], "Ir"),
+        vec![]
+    )]
+    #[case::version(
+        metadata(Some("callgrind-3.22.0"), &[], "Ir"),
+        metadata(Some("callgrind-3.21.0"), &[], "Ir"),
+        vec!["valgrind version 'callgrind-3.22.0' (old: 'callgrind-3.21.0')"]
+    )]
+    #[case::unknown_version(
+        metadata(Some("callgrind-3.22.0"), &[], "Ir"),
+        metadata(None, &[], "Ir"),
+        vec!["valgrind version 'callgrind-3.22.0' (old: 'unknown')"]
+    )]
+    #[case::caches(
+        metadata(None, &["I1 cache: 32768 B", "D1 cache: 32768 B"], "Ir"),
+        metadata(None, &["I1 cache: 65536 B", "D1 cache: 32768 B"], "Ir"),
+        vec!["caches 'I1 cache: 32768 B; D1 cache: 32768 B' (old: 'I1 cache: 65536 B; D1 cache: 32768 B')"]
+    )]
+    #[case::events(
+        metadata(None, &[], "Ir Dr Dw"),
+        metadata(None, &[], "Ir"),
+        vec!["events 'Ir Dr Dw' (old: 'Ir')"]
+    )]
+    #[case::all(
+        metadata(Some("callgrind-3.22.0"), &["I1 cache: 32768 B"], "Ir Dr"),
+        metadata(Some("callgrind-3.21.0"), &[], "Ir"),
+        vec![
+            "valgrind version 'callgrind-3.22.0' (old: 'callgrind-3.21.0')",
+            "caches 'I1 cache: 32768 B' (old: '')",
+            "events 'Ir Dr' (old: 'Ir')"
+        ]
+    )]
+    #[case::equal_run(
+        with_run(metadata(None, &[], "Ir"), true, &[("FOO", "BAR")]),
+        with_run(metadata(None, &[], "Ir"), true, &[("FOO", "BAR")]),
+        vec![]
+    )]
+    #[case::run_missing_in_old(
+        with_run(metadata(None, &[], "Ir"), true, &[("FOO", "BAR")]),
+        metadata(None, &[], "Ir"),
+        vec![]
+    )]
+    #[case::env_clear(
+        with_run(metadata(None, &[], "Ir"), true, &[]),
+        with_run(metadata(None, &[], "Ir"), false, &[]),
+        vec!["cleared environment 'true' (old: 'false')"]
+    )]
+    #[case::envs(
+        with_run(metadata(None, &[], "Ir"), true, &[("BAR", "BAZ"), ("FOO", "BAR")]),
+        with_run(metadata(None, &[], "Ir"), true, &[("FOO", "OTHER")]),
+        vec!["environment variables 'BAR=BAZ FOO=BAR' (old: 'FOO=OTHER')"]
+    )]
+    #[case::equal_config_hash(
+        with_hashes(metadata(None, &[], "Ir"), None, Some("abc")),
+        with_hashes(metadata(None, &[], "Ir"), None, Some("abc")),
+        vec![]
+    )]
+    #[case::config_hash(
+        with_hashes(metadata(None, &[], "Ir"), None, Some("abc")),
+        with_hashes(metadata(None, &[], "Ir"), None, Some("def")),
+        vec!["valgrind arguments with hash 'abc' (old: 'def')"]
+    )]
+    #[case::config_hash_missing_in_old(
+        with_hashes(metadata(None, &[], "Ir"), None, Some("abc")),
+        with_hashes(metadata(None, &[], "Ir"), None, None),
+        vec![]
+    )]
+    #[case::binary_hash_is_no_mismatch(
+        with_hashes(metadata(None, &[], "Ir"), Some("abc"), None),
+        with_hashes(metadata(None, &[], "Ir"), Some("def"), None),
+        vec![]
+    )]
+    #[case::equal_arch(
+        with_arch(metadata(None, &[], "Ir"), "x86_64"),
+        with_arch(metadata(None, &[], "Ir"), "x86_64"),
+        vec![]
+    )]
+    #[case::arch(
+        with_arch(metadata(None, &[], "Ir"), "aarch64"),
+        with_arch(metadata(None, &[], "Ir"), "x86_64"),
+        vec!["architecture 'aarch64' (old: 'x86_64')"]
+    )]
+    #[case::arch_missing_in_old(
+        with_arch(metadata(None, &[], "Ir"), "aarch64"),
+        with_run(metadata(None, &[], "Ir"), false, &[]),
+        vec![]
+    )]
+    fn test_output_metadata_mismatches(
+        #[case] new: OutputMetadata,
+        #[case] old: OutputMetadata,
+        #[case] expected: Vec<&str>,
+    ) {
+        assert_eq!(new.mismatches(&old), expected);
+    }
+
+    #[rstest]
+    #[case::equal(Some("abc"), Some("abc"), None)]
+    #[case::changed(Some("abc"), Some("def"), Some(("abc", "def")))]
+    #[case::missing_in_old(Some("abc"), None, None)]
+    #[case::missing_in_new(None, Some("def"), None)]
+    fn test_output_metadata_binary_change(
+        #[case] new_hash: Option<&str>,
+        #[case] old_hash: Option<&str>,
+        #[case] expected: Option<(&str, &str)>,
+    ) {
+        let new = with_hashes(metadata(None, &[], "Ir"), new_hash, None);
+        let old = with_hashes(metadata(None, &[], "Ir"), old_hash, None);
+        assert_eq!(new.binary_change(&old), expected);
+    }
+
+    #[test]
+    fn test_output_metadata_binary_change_without_run_metadata() {
+        let new = with_hashes(metadata(None, &[], "Ir"), Some("abc"), None);
+        assert_eq!(new.binary_change(&metadata(None, &[], "Ir")), None);
+    }
+
+    #[test]
+    fn test_run_metadata_record_hashes() {
+        let dir = tempfile::tempdir().unwrap();
+        let executable = dir.path().join("bench");
+        std::fs::write(&executable, "binary").unwrap();
+
+        let mut run_metadata = RunMetadata::default();
+        run_metadata.record_hashes(&executable, &["--a=b".to_owned(), "--c".to_owned()]);
+        let expected = run_metadata.clone();
+
+        // A rebuild with different content changes only the binary hash
+        std::fs::write(&executable, "rebuilt binary").unwrap();
+        run_metadata.record_hashes(&executable, &["--a=b".to_owned(), "--c".to_owned()]);
+        assert_eq!(run_metadata.config_hash, expected.config_hash);
+        assert_ne!(run_metadata.binary_hash, expected.binary_hash);
+
+        // The arguments are separated, so `--a=b --c` and `--a=b--c` have different hashes
+        run_metadata.record_hashes(&executable, &["--a=b--c".to_owned()]);
+        assert_ne!(run_metadata.config_hash, expected.config_hash);
+    }
+
+    #[test]
+    fn test_run_metadata_record_hashes_when_executable_is_missing() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut run_metadata = RunMetadata::default();
+        run_metadata.record_hashes(&dir.path().join("missing"), &[]);
+
+        assert_eq!(run_metadata.binary_hash, None);
+        assert!(run_metadata.config_hash.is_some());
+    }
+
+    #[test]
+    fn test_run_metadata_load_without_hashes() {
+        let run_metadata: RunMetadata =
+            serde_json::from_str(r#"{"env_clear":true,"envs":[["FOO","BAR"]]}"#).unwrap();
+        assert_eq!(
+            run_metadata,
+            RunMetadata {
+                env_clear: true,
+                envs: vec![("FOO".to_owned(), "BAR".to_owned())],
+                binary_hash: None,
+                config_hash: None,
+                arch: None,
+                cpu_affinity: None,
+                executable: None,
+            }
+        );
+    }
+
+    #[test]
+    fn test_run_metadata_from_options_records_arch() {
+        assert_eq!(
+            RunMetadata::from_options(&RunOptions::default()).arch,
+            Some(std::env::consts::ARCH.to_owned())
+        );
+    }
+}
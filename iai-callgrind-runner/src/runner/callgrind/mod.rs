@@ -1,31 +1,53 @@
 pub mod args;
+pub mod call_graph;
+pub mod cross_arch;
+pub mod event_baseline;
 pub mod flamegraph;
 pub mod flamegraph_parser;
 pub mod hashmap_parser;
+pub mod metadata_parser;
 pub mod model;
+pub mod output_parser;
 pub mod parser;
+pub mod self_cost_parser;
 pub mod sentinel_parser;
 pub mod summary_parser;
 
+use std::borrow::Cow;
 use std::ffi::OsString;
 use std::path::{Path, PathBuf};
 use std::process::{Command, Stdio};
+use std::time::Instant;
 
-use anyhow::Result;
+use anyhow::{anyhow, Context, Result};
 use colored::Colorize;
-use log::debug;
+use indexmap::IndexMap;
+use log::{debug, warn};
 
 use self::args::Args;
+use self::metadata_parser::RunMetadata;
 use self::model::Costs;
+use self::self_cost_parser::{FrameDiff, SelfCostParser, SelfCosts};
+use super::costs::Summarize;
 use super::meta::Metadata;
 use super::summary::{CallgrindRegressionSummary, CostsSummary};
-use super::tool::{check_exit, RunOptions, ToolOutput, ToolOutputPath, ValgrindTool};
+use super::tool::{
+    check_exit, check_output, output_with_retries, Parser, RunOptions, ToolOutput, ToolOutputPath,
+    ValgrindTool,
+};
 use crate::api::{self, EventKind};
 use crate::error::Error;
-use crate::util::{resolve_binary_path, to_string_signed_short};
+use crate::util::{output_with_peak_rss, resolve_binary_path, to_string_signed_short};
+
+/// The maximum number of functions printed beneath a regression
+const NUM_TOP_FRAMES: usize = 5;
 
 pub struct CallgrindCommand {
     command: Command,
+    cpu_affinity: Option<String>,
+    peak_rss: bool,
+    retries: u32,
+    tool: ValgrindTool,
 }
 
 #[derive(Clone, Debug)]
@@ -34,19 +56,34 @@ pub struct CacheSummary {
     l3_hits: u64,
     ram_hits: u64,
     total_memory_rw: u64,
+    data_rw: u64,
     cycles: u64,
 }
 
 #[derive(Debug, Clone)]
 pub struct RegressionConfig {
     pub limits: Vec<(EventKind, f64)>,
+    pub min_abs_diffs: Vec<(EventKind, u64)>,
     pub fail_fast: bool,
+    /// The weights of the events of the [`EventKind::Score`]
+    pub score: Vec<(EventKind, f64)>,
 }
 
 impl CallgrindCommand {
-    pub fn new(meta: &Metadata) -> Self {
+    /// Create a new `CallgrindCommand` running the [`ValgrindTool`]
+    ///
+    /// The `tool` is either [`ValgrindTool::Callgrind`] or [`ValgrindTool::Cachegrind`].
+    pub fn new(meta: &Metadata, tool: ValgrindTool) -> Self {
+        debug_assert!(matches!(
+            tool,
+            ValgrindTool::Callgrind | ValgrindTool::Cachegrind
+        ));
         Self {
             command: meta.into(),
+            cpu_affinity: meta.cpu_affinity.as_ref().map(ToString::to_string),
+            peak_rss: meta.args.peak_rss.unwrap_or_default(),
+            retries: meta.args.retries.unwrap_or_default(),
+            tool,
         }
     }
 
@@ -60,21 +97,23 @@ impl CallgrindCommand {
     ) -> Result<ToolOutput> {
         let mut command = self.command;
         debug!(
-            "Running callgrind with executable '{}'",
+            "Running {} with executable '{}'",
+            self.tool.id(),
             executable.display()
         );
+        let mut run_metadata = RunMetadata::from_options(&options);
+        run_metadata.cpu_affinity = self.cpu_affinity;
+        run_metadata.executable = Some(executable.to_string_lossy().to_string());
         let RunOptions {
             env_clear,
             current_dir,
             exit_with,
+            output_assertions,
             entry_point,
             envs,
         } = options;
 
-        if env_clear {
-            debug!("Clearing environment variables");
-            command.env_clear();
-        }
+        prepare_envs(&mut command, env_clear, envs);
         if let Some(dir) = current_dir {
             debug!("Setting current directory to '{}'", dir.display());
             command.current_dir(dir);
@@ -86,39 +125,72 @@ impl CallgrindCommand {
         } else {
             callgrind_args.collect_atstart = true;
         }
+        let executable = resolve_binary_path(executable)?;
+        // The paths of the output files differ between the new and old output, so the hash of the
+        // arguments is calculated without them
+        run_metadata.record_hashes(
+            &executable,
+            &if self.tool == ValgrindTool::Cachegrind {
+                callgrind_args.to_cachegrind_vec()
+            } else {
+                callgrind_args.to_vec()
+            },
+        );
+
         callgrind_args.set_output_file(&output_path.to_path());
         callgrind_args.set_log_arg(output_path);
 
-        let callgrind_args = callgrind_args.to_vec();
-        debug!("Callgrind arguments: {}", &callgrind_args.join(" "));
-
-        let executable = resolve_binary_path(executable)?;
+        let callgrind_args = if self.tool == ValgrindTool::Cachegrind {
+            callgrind_args.to_cachegrind_vec()
+        } else {
+            callgrind_args.to_vec()
+        };
+        debug!(
+            "{} arguments: {}",
+            self.tool.id(),
+            &callgrind_args.join(" ")
+        );
 
-        let output = command
-            .arg("--tool=callgrind")
+        command
+            .arg(format!("--tool={}", self.tool.id()))
             .args(callgrind_args)
             .arg(&executable)
             .args(executable_args)
-            .envs(envs)
             .stdout(Stdio::piped())
-            .stderr(Stdio::piped())
-            .output()
-            .map_err(|error| {
-                Error::LaunchError(PathBuf::from("valgrind"), error.to_string()).into()
-            })
-            .and_then(|output| {
-                check_exit(
-                    ValgrindTool::Callgrind,
-                    &executable,
-                    output,
-                    &output_path.to_log_output(),
-                    exit_with.as_ref(),
-                )
-            })?;
+            .stderr(Stdio::piped());
+
+        let (output, (peak_rss, wall_time)) = output_with_retries(self.tool, self.retries, || {
+            let start = Instant::now();
+            if self.peak_rss {
+                output_with_peak_rss(&mut command)
+            } else {
+                command.output().map(|output| (output, None))
+            }
+            .map(|(output, peak_rss)| (output, (peak_rss, start.elapsed())))
+        })
+        .map_err(|error| Error::LaunchError(PathBuf::from("valgrind"), error.to_string()))?;
+        debug!(
+            "{}: The instrumented run of '{}' took {wall_time:?} (wall time including the \
+             overhead of valgrind)",
+            self.tool.id(),
+            executable.display()
+        );
+
+        let output = check_exit(
+            self.tool,
+            &executable,
+            output,
+            &output_path.to_log_output(),
+            exit_with.as_ref(),
+        )?;
+        check_output(self.tool, &executable, &output, &output_assertions)?;
+        run_metadata.save(output_path)?;
 
         Ok(ToolOutput {
-            tool: ValgrindTool::Callgrind,
+            tool: self.tool,
             output,
+            peak_rss,
+            wall_time: Some(wall_time),
         })
     }
 }
@@ -148,7 +220,8 @@ impl TryFrom<&Costs> for CacheSummary {
         let l3_accesses = l1_miss;
         let l3_hits = l3_accesses - ram_hits;
 
-        let total_memory_rw = instructions + total_data_cache_reads + total_data_cache_writes;
+        let data_rw = total_data_cache_reads + total_data_cache_writes;
+        let total_memory_rw = instructions + data_rw;
         let l1_hits = total_memory_rw - ram_hits - l3_hits;
 
         // Uses Itamar Turner-Trauring's formula from https://pythonspeed.com/articles/consistent-benchmarking-in-ci/
@@ -159,6 +232,7 @@ impl TryFrom<&Costs> for CacheSummary {
             l3_hits,
             ram_hits,
             total_memory_rw,
+            data_rw,
             cycles,
         })
     }
@@ -174,8 +248,20 @@ impl RegressionConfig {
     ///
     /// Returns an [`anyhow::Error`] with the only source [`Error::RegressionError`] if a regression
     /// error occurred
-    pub fn check_and_print(&self, costs_summary: &CostsSummary) -> Vec<CallgrindRegressionSummary> {
+    ///
+    /// Beneath each regression, the functions of the callgrind `output_path` with the largest
+    /// change of their self cost compared to the old output are printed.
+    pub fn check_and_print(
+        &self,
+        costs_summary: &CostsSummary,
+        output_path: &ToolOutputPath,
+    ) -> Vec<CallgrindRegressionSummary> {
         let regression_summaries = self.check(costs_summary);
+        let self_costs = if regression_summaries.is_empty() {
+            None
+        } else {
+            parse_self_costs(output_path)
+        };
 
         for CallgrindRegressionSummary {
             event_kind,
@@ -206,19 +292,113 @@ impl RegressionConfig {
                     to_string_signed_short(*limit).bright_black()
                 );
             }
+
+            if let Some((new_self_costs, old_self_costs)) = &self_costs {
+                print_top_frames(
+                    &new_self_costs.top_diffs(
+                        old_self_costs,
+                        event_kind,
+                        limit.is_sign_positive(),
+                        NUM_TOP_FRAMES,
+                    ),
+                    *event_kind,
+                    limit.is_sign_positive(),
+                );
+            }
         }
 
         regression_summaries
     }
 
+    /// Return the minimum absolute difference of this [`EventKind`] for a regression
+    ///
+    /// Differences which are equal to or smaller than this value are not considered to be a
+    /// regression even if the percentage limit is exceeded. The default is `0`.
+    pub fn min_abs_diff(&self, event_kind: &EventKind) -> u64 {
+        self.min_abs_diffs
+            .iter()
+            .rev()
+            .find_map(|(e, min)| (e == event_kind).then_some(*min))
+            .unwrap_or(0)
+    }
+
+    /// Check that the weights of the [`EventKind::Score`] are valid
+    ///
+    /// # Errors
+    ///
+    /// If a weight is negative or not finite, if the `Score` is weighted itself or if there's a
+    /// limit for the `Score` without any weights
+    pub fn validate_score(&self) -> Result<()> {
+        for (event_kind, weight) in &self.score {
+            if *event_kind == EventKind::Score {
+                return Err(anyhow!(
+                    "Invalid weight of the score: The score can't be weighted itself"
+                ));
+            }
+            if !weight.is_finite() || weight.is_sign_negative() {
+                return Err(anyhow!(
+                    "Invalid weight of the score for '{event_kind}': '{weight}'. The weight must \
+                     be zero or a positive finite number"
+                ));
+            }
+        }
+
+        if self.score.is_empty() && self.limits.iter().any(|(e, _)| *e == EventKind::Score) {
+            return Err(anyhow!(
+                "A limit for the score requires the weights of the score. Configure the weights \
+                 with RegressionConfig::score"
+            ));
+        }
+
+        Ok(())
+    }
+
+    /// Add the [`EventKind::Score`], the weighted sum of the `score` events, to the `costs`
+    ///
+    /// Nothing is added if there are no weights configured. The derived events like
+    /// [`EventKind::EstimatedCycles`] can be weighted too. The score is rounded to the nearest
+    /// integer. Scores are only comparable if they were calculated with the same weights.
+    ///
+    /// # Errors
+    ///
+    /// If one of the weighted events is not present in the `costs`
+    pub fn add_score(&self, costs: &mut Costs) -> Result<()> {
+        if self.score.is_empty() {
+            return Ok(());
+        }
+
+        let mut summarized = Cow::Borrowed(&*costs);
+        EventKind::summarize(&mut summarized);
+
+        // The last weight of an event kind wins
+        let weights = self.score.iter().copied().collect::<IndexMap<_, _>>();
+        let mut score = 0f64;
+        for (event_kind, weight) in weights {
+            let cost = summarized.cost_by_kind(&event_kind).ok_or_else(|| {
+                anyhow!("Failed calculating the score: Missing event kind '{event_kind}'")
+            })?;
+            #[allow(clippy::cast_precision_loss)]
+            let cost = cost as f64;
+            score += weight * cost;
+        }
+
+        #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+        let score = score.round() as u64;
+        costs.0.insert(EventKind::Score, score);
+        Ok(())
+    }
+
     pub fn check(&self, costs_summary: &CostsSummary) -> Vec<CallgrindRegressionSummary> {
         let mut regressions = vec![];
         for (event_kind, limit) in &self.limits {
+            let min_abs_diff = self.min_abs_diff(event_kind);
             if let Some((new_cost, old_cost, pct)) = costs_summary
                 .diff_by_kind(event_kind)
                 .filter(|d| d.diff_pct.is_some())
                 // These unwraps are safe since if diff_pct is present new and old are also present
                 .map(|d| (d.new.unwrap(), d.old.unwrap(), d.diff_pct.unwrap()))
+                // A regression has to exceed the percentage limit and the absolute minimum
+                .filter(|(new_cost, old_cost, _)| new_cost.abs_diff(*old_cost) > min_abs_diff)
             {
                 if limit.is_sign_positive() {
                     if pct > *limit {
@@ -249,25 +429,204 @@ impl RegressionConfig {
     }
 }
 
-impl From<api::RegressionConfig> for RegressionConfig {
-    fn from(value: api::RegressionConfig) -> Self {
-        let api::RegressionConfig { limits, fail_fast } = value;
+/// Add the [`EventKind::Score`] of the `regression_config` to the `costs` if present
+///
+/// # Errors
+///
+/// See [`RegressionConfig::add_score`]
+pub fn add_score(regression_config: Option<&RegressionConfig>, costs: &mut Costs) -> Result<()> {
+    regression_config.map_or(Ok(()), |config| config.add_score(costs))
+}
+
+/// Parse the [`SelfCosts`] of the new and old callgrind output if both are present
+///
+/// The old output is not available if the new output overwrote it, like it is the case with
+/// `--save-baseline`. Since the self costs are only informational, errors are logged and not
+/// returned.
+fn parse_self_costs(output_path: &ToolOutputPath) -> Option<(SelfCosts, SelfCosts)> {
+    let old_path = output_path.to_base_path();
+    if output_path.tool != ValgrindTool::Callgrind || old_path == *output_path || !old_path.exists()
+    {
+        return None;
+    }
+
+    match SelfCostParser
+        .parse(output_path)
+        .and_then(|new| SelfCostParser.parse(&old_path).map(|old| (new, old)))
+    {
+        Ok(self_costs) => Some(self_costs),
+        Err(error) => {
+            warn!("Failed to parse the self costs of the functions: {error}");
+            None
+        }
+    }
+}
+
+/// Run the benchmark a second time with `run` and return true if the `event_kind` of both runs is
+/// equal
+///
+/// Callgrind is deterministic, so different costs of two runs of the same benchmark
+/// point to a nondeterminism in the benchmark itself. `run` has to run the benchmark with the
+/// output path it receives and return the parsed [`Costs`], which are then compared to the `costs`
+/// of the first run of the `output_path`. Both costs are summarized before the comparison, so the
+/// `event_kind` can be a derived event kind like [`EventKind::EstimatedCycles`]. The output of the
+/// second run is written to a temporary directory, so the output files of the first run are
+/// untouched. If the benchmark is nondeterministic, a warning with the common causes is logged.
+///
+/// # Errors
+///
+/// If the temporary directory couldn't be created, `run` returns an error or the `event_kind` is
+/// missing in the costs of one of the runs
+pub fn check_determinism<F>(
+    output_path: &ToolOutputPath,
+    costs: &Costs,
+    event_kind: &EventKind,
+    run: F,
+) -> Result<bool>
+where
+    F: FnOnce(&ToolOutputPath) -> Result<Costs>,
+{
+    let temp_dir = tempfile::tempdir()
+        .context("Failed to create a temporary directory for the determinism check")?;
+    let second_path = ToolOutputPath {
+        dir: temp_dir.path().to_owned(),
+        baseline_dir: temp_dir.path().to_owned(),
+        ..output_path.clone()
+    };
+
+    let mut first = Cow::Borrowed(costs);
+    EventKind::summarize(&mut first);
+    let mut second = Cow::Owned(run(&second_path)?);
+    EventKind::summarize(&mut second);
+
+    let (Some(first), Some(second)) = (
+        first.cost_by_kind(event_kind),
+        second.cost_by_kind(event_kind),
+    ) else {
+        return Err(anyhow!(
+            "Failed to check the determinism of the benchmark '{output_path}': Missing event kind \
+             '{event_kind}'"
+        ));
+    };
+    if first == second {
+        return Ok(true);
+    }
+
+    warn!(
+        "The {event_kind} of two runs of the benchmark '{output_path}' differ ({first} and \
+         {second}). The benchmark is nondeterministic. Common causes are reading the clock, \
+         random numbers, the iteration order of a HashMap with a random seed or reading the \
+         environment and the file system, which can all be kept out of the measured code with a \
+         setup function."
+    );
+    Ok(false)
+}
+
+/// Return an error if the new `Ir` of the `costs_summary` is zero or missing
+///
+/// Unlike the other checks, this check doesn't consult the primary event kind. Every benchmark
+/// which measures anything executes instructions, but other event kinds like the cache misses
+/// (`DLmr`) are legitimately zero for many benchmarks.
+///
+/// # Errors
+///
+/// If the benchmark of the `output_path` didn't measure any instructions
+pub fn check_not_zero(costs_summary: &CostsSummary, output_path: &ToolOutputPath) -> Result<()> {
+    let cost = costs_summary
+        .diff_by_kind(&EventKind::Ir)
+        .and_then(|diff| diff.new);
+    if cost.map_or(true, |cost| cost == 0) {
+        Err(anyhow!(
+            "The benchmark '{output_path}' didn't measure any instructions (Ir: {}). Likely \
+             causes are benchmarked code which was optimized away, because the inputs or the \
+             result are not wrapped in a `black_box`, or a `--toggle-collect` option which \
+             doesn't match the benchmarked function",
+            cost.map_or_else(|| "missing".to_owned(), |cost| cost.to_string())
+        ))
+    } else {
+        Ok(())
+    }
+}
+
+/// Prepare the environment variables of the `command` which runs the benchmark
+///
+/// If `env_clear` is true, the inherited environment variables are cleared before the `envs` are
+/// set.
+fn prepare_envs<I>(command: &mut Command, env_clear: bool, envs: I)
+where
+    I: IntoIterator<Item = (OsString, OsString)>,
+{
+    if env_clear {
+        debug!("Clearing environment variables");
+        command.env_clear();
+    }
+    command.envs(envs);
+}
+
+/// Print the functions with the largest change of their self cost beneath a regression
+fn print_top_frames(frame_diffs: &[FrameDiff], event_kind: EventKind, increased: bool) {
+    if frame_diffs.is_empty() {
+        return;
+    }
+
+    eprintln!(
+        "  Functions with the largest self cost {} of {}:",
+        if increased { "increase" } else { "decrease" },
+        event_kind.to_string().bold()
+    );
+    for frame_diff in frame_diffs {
+        let details = match (frame_diff.new, frame_diff.old) {
+            (Some(new), Some(old)) => format!("({old} -> {new})"),
+            (Some(_), None) => "(new)".to_owned(),
+            (None, _) => "(removed)".to_owned(),
+        };
+        eprintln!(
+            "  {:>+16} {} {}",
+            frame_diff.diff(),
+            frame_diff.func,
+            details.bright_black()
+        );
+    }
+}
+
+impl RegressionConfig {
+    /// Create the `RegressionConfig` from the [`api::RegressionConfig`]
+    ///
+    /// Without any limits, the costs of the `default_event_kind` are limited to `10%`.
+    pub fn from_api(value: api::RegressionConfig, default_event_kind: EventKind) -> Self {
+        let api::RegressionConfig {
+            limits,
+            min_abs_diffs,
+            fail_fast,
+            score,
+            ..
+        } = value;
         RegressionConfig {
             limits: if limits.is_empty() {
-                vec![(EventKind::Ir, 10f64)]
+                vec![(default_event_kind, 10f64)]
             } else {
                 limits
             },
+            min_abs_diffs,
             fail_fast: fail_fast.unwrap_or(false),
+            score,
         }
     }
 }
 
+impl From<api::RegressionConfig> for RegressionConfig {
+    fn from(value: api::RegressionConfig) -> Self {
+        Self::from_api(value, EventKind::Ir)
+    }
+}
+
 impl Default for RegressionConfig {
     fn default() -> Self {
         Self {
             limits: vec![(EventKind::Ir, 10f64)],
+            min_abs_diffs: Vec::default(),
             fail_fast: Default::default(),
+            score: Vec::default(),
         }
     }
 }
@@ -278,6 +637,9 @@ mod tests {
     use EventKind::*;
 
     use super::*;
+    use crate::runner::format::{DiffFormat, EventFilter, Formatter, NumberFormat, VerticalFormat};
+    use crate::runner::summary::BaselineKind;
+    use crate::runner::tool::ToolOutputPathKind;
 
     fn cachesim_costs(costs: [u64; 9]) -> Costs {
         Costs::with_event_kinds([
@@ -385,4 +747,307 @@ mod tests {
 
         assert_eq!(regression.check(&summary), expected);
     }
+
+    #[rstest]
+    #[case::default_is_zero(10f64, vec![], 2, 1, true)]
+    #[case::pct_and_abs_exceeded(10f64, vec![(Ir, 1)], 3, 1, true)]
+    #[case::only_pct_exceeded(10f64, vec![(Ir, 1)], 2, 1, false)]
+    #[case::only_abs_exceeded(10f64, vec![(Ir, 1)], 103, 100, false)]
+    #[case::abs_is_exclusive(10f64, vec![(Ir, 2)], 4, 2, false)]
+    #[case::negative_limit(-10f64, vec![(Ir, 1)], 1, 3, true)]
+    #[case::negative_limit_only_pct_exceeded(-10f64, vec![(Ir, 1)], 1, 2, false)]
+    #[case::other_event_kind(10f64, vec![(Dr, 100)], 3, 1, true)]
+    #[case::last_one_wins(10f64, vec![(Ir, 100), (Ir, 1)], 3, 1, true)]
+    fn test_regression_check_with_min_abs_diffs(
+        #[case] limit: f64,
+        #[case] min_abs_diffs: Vec<(EventKind, u64)>,
+        #[case] new_ir: u64,
+        #[case] old_ir: u64,
+        #[case] expected: bool,
+    ) {
+        let regression = RegressionConfig {
+            limits: vec![(Ir, limit)],
+            min_abs_diffs,
+            ..Default::default()
+        };
+
+        let new = cachesim_costs([new_ir, 0, 0, 0, 0, 0, 0, 0, 0]);
+        let old = Some(cachesim_costs([old_ir, 0, 0, 0, 0, 0, 0, 0, 0]));
+        let summary = CostsSummary::new(&new, old.as_ref());
+
+        assert_eq!(!regression.check(&summary).is_empty(), expected);
+    }
+
+    #[test]
+    fn test_regression_check_when_event_is_hidden_by_event_filter() {
+        let regression = RegressionConfig {
+            limits: vec![(Ir, 0f64)],
+            ..Default::default()
+        };
+        let filter = EventFilter {
+            min_cost: Some(1000),
+            min_diff_pct: Some(5f64),
+        };
+
+        let new = cachesim_costs([101, 0, 0, 0, 0, 0, 0, 0, 0]);
+        let old = Some(cachesim_costs([100, 0, 0, 0, 0, 0, 0, 0, 0]));
+        let summary = CostsSummary::new(&new, old.as_ref());
+
+        let formatted = VerticalFormat::default()
+            .format(
+                (None, None),
+                &summary,
+                NumberFormat::Plain,
+                DiffFormat::Percentage,
+                Some(&filter),
+            )
+            .unwrap();
+        assert!(!formatted.contains("Instructions:"));
+        assert_eq!(
+            regression.check(&summary),
+            vec![CallgrindRegressionSummary {
+                event_kind: Ir,
+                new: 101,
+                old: 100,
+                diff_pct: 1f64,
+                limit: 0f64,
+            }]
+        );
+    }
+
+    #[rstest]
+    #[case::no_weights(vec![], None)]
+    #[case::ir(vec![(Ir, 1f64)], Some(96))]
+    #[case::weighted_sum(vec![(Ir, 1f64), (Dr, 0.5f64)], Some(108))]
+    #[case::derived(vec![(Ir, 1f64), (EstimatedCycles, 10f64)], Some(4196))]
+    #[case::rounded(vec![(Dw, 0.25f64)], Some(5))]
+    #[case::last_one_wins(vec![(Ir, 2f64), (Ir, 1f64)], Some(96))]
+    #[case::zero_weight(vec![(Ir, 0f64)], Some(0))]
+    fn test_add_score(#[case] score: Vec<(EventKind, f64)>, #[case] expected: Option<u64>) {
+        let regression = RegressionConfig {
+            score,
+            ..Default::default()
+        };
+        let mut costs = cachesim_costs([96, 24, 18, 6, 0, 2, 6, 0, 2]);
+
+        regression.add_score(&mut costs).unwrap();
+        assert_eq!(costs.cost_by_kind(&Score), expected);
+    }
+
+    #[test]
+    fn test_add_score_when_missing_event_kind_then_error() {
+        let regression = RegressionConfig {
+            score: vec![(Bc, 1f64)],
+            ..Default::default()
+        };
+        let mut costs = cachesim_costs([96, 24, 18, 6, 0, 2, 6, 0, 2]);
+
+        assert_eq!(
+            regression.add_score(&mut costs).unwrap_err().to_string(),
+            "Failed calculating the score: Missing event kind 'Bc'"
+        );
+    }
+
+    #[test]
+    fn test_regression_check_of_score() {
+        let regression = RegressionConfig {
+            limits: vec![(Score, 5f64)],
+            score: vec![(Ir, 1f64), (Dr, 1f64)],
+            ..Default::default()
+        };
+        let mut new = cachesim_costs([110, 10, 0, 0, 0, 0, 0, 0, 0]);
+        let mut old = cachesim_costs([100, 10, 0, 0, 0, 0, 0, 0, 0]);
+        regression.add_score(&mut new).unwrap();
+        regression.add_score(&mut old).unwrap();
+
+        let summary = CostsSummary::new(&new, Some(&old));
+        let regressions = regression.check(&summary);
+        assert_eq!(regressions.len(), 1);
+        assert_eq!(regressions[0].event_kind, Score);
+        assert_eq!((regressions[0].new, regressions[0].old), (120, 110));
+    }
+
+    #[rstest]
+    #[case::no_score(vec![], vec![], true)]
+    #[case::valid(vec![(Ir, 1f64), (EstimatedCycles, 0f64)], vec![(Score, 5f64)], true)]
+    #[case::negative(vec![(Ir, -1f64)], vec![], false)]
+    #[case::negative_zero(vec![(Ir, -0f64)], vec![], false)]
+    #[case::nan(vec![(Ir, f64::NAN)], vec![], false)]
+    #[case::infinite(vec![(Ir, f64::INFINITY)], vec![], false)]
+    #[case::score_weighted(vec![(Score, 1f64)], vec![], false)]
+    #[case::limit_without_weights(vec![], vec![(Score, 5f64)], false)]
+    fn test_validate_score(
+        #[case] score: Vec<(EventKind, f64)>,
+        #[case] limits: Vec<(EventKind, f64)>,
+        #[case] expected: bool,
+    ) {
+        let mut regression = RegressionConfig {
+            score,
+            ..Default::default()
+        };
+        if !limits.is_empty() {
+            regression.limits = limits;
+        }
+
+        assert_eq!(regression.validate_score().is_ok(), expected);
+    }
+
+    #[rstest]
+    #[case::env_clear(true)]
+    #[case::no_env_clear(false)]
+    fn test_prepare_envs_child_sees_envs(#[case] env_clear: bool) {
+        let mut command = Command::new(resolve_binary_path("env").unwrap());
+        prepare_envs(
+            &mut command,
+            env_clear,
+            [
+                (
+                    OsString::from("IAI_CALLGRIND_TEST_FOO"),
+                    OsString::from("BAR"),
+                ),
+                (
+                    OsString::from("IAI_CALLGRIND_TEST_BAR"),
+                    OsString::from("BAZ"),
+                ),
+            ],
+        );
+        let output = command.output().unwrap();
+        assert!(output.status.success());
+
+        let stdout = String::from_utf8(output.stdout).unwrap();
+        let envs = stdout.lines().collect::<Vec<&str>>();
+        assert!(envs.contains(&"IAI_CALLGRIND_TEST_FOO=BAR"));
+        assert!(envs.contains(&"IAI_CALLGRIND_TEST_BAR=BAZ"));
+        if env_clear {
+            assert_eq!(envs.len(), 2);
+        } else {
+            assert!(envs.len() > 2);
+        }
+    }
+
+    #[rstest]
+    #[case::equal(Ir, 10, true)]
+    #[case::different(Ir, 11, false)]
+    #[case::other_event_kind_equal(Dr, 5, true)]
+    #[case::other_event_kind_different(Dr, 10, false)]
+    fn test_check_determinism(
+        #[case] event_kind: EventKind,
+        #[case] second: u64,
+        #[case] expected: bool,
+    ) {
+        let output_path = ToolOutputPath::new(
+            ToolOutputPathKind::Out,
+            ValgrindTool::Callgrind,
+            &BaselineKind::Old,
+            Path::new("/target"),
+            Path::new("/target"),
+            "module",
+            "bench",
+        );
+        let costs = Costs::with_event_kinds([(Ir, 10), (Dr, 5)]);
+
+        let actual = check_determinism(&output_path, &costs, &event_kind, |path| {
+            assert_ne!(path.dir, output_path.dir);
+            assert_eq!(path.name, output_path.name);
+            let cost = |kind, cost| if kind == event_kind { second } else { cost };
+            Ok(Costs::with_event_kinds([
+                (Ir, cost(Ir, 10)),
+                (Dr, cost(Dr, 5)),
+            ]))
+        })
+        .unwrap();
+        assert_eq!(actual, expected);
+    }
+
+    #[rstest]
+    #[case::equal(0, true)]
+    #[case::different(1, false)]
+    fn test_check_determinism_when_derived_event_kind(
+        #[case] additional_misses: u64,
+        #[case] expected: bool,
+    ) {
+        let output_path = ToolOutputPath::new(
+            ToolOutputPathKind::Out,
+            ValgrindTool::Callgrind,
+            &BaselineKind::Old,
+            Path::new("/target"),
+            Path::new("/target"),
+            "module",
+            "bench",
+        );
+        let costs = |dlmr| {
+            Costs::with_event_kinds([
+                (Ir, 10),
+                (Dr, 5),
+                (Dw, 2),
+                (I1mr, 1),
+                (D1mr, 2),
+                (D1mw, 1),
+                (ILmr, 1),
+                (DLmr, dlmr),
+                (DLmw, 1),
+            ])
+        };
+
+        let actual = check_determinism(&output_path, &costs(1), &EstimatedCycles, |_| {
+            Ok(costs(1 + additional_misses))
+        })
+        .unwrap();
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn test_check_determinism_when_missing_event_kind_then_error() {
+        let output_path = ToolOutputPath::new(
+            ToolOutputPathKind::Out,
+            ValgrindTool::Callgrind,
+            &BaselineKind::Old,
+            Path::new("/target"),
+            Path::new("/target"),
+            "module",
+            "bench",
+        );
+        let costs = Costs::with_event_kinds([(Ir, 10)]);
+
+        let error = check_determinism(
+            &output_path,
+            &costs,
+            &EstimatedCycles,
+            |_| Ok(costs.clone()),
+        )
+        .unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            format!(
+                "Failed to check the determinism of the benchmark '{output_path}': Missing event \
+                 kind 'Estimated Cycles'"
+            )
+        );
+    }
+
+    #[rstest]
+    #[case::non_zero(Some(1), None)]
+    #[case::zero(Some(0), Some("Ir: 0"))]
+    #[case::missing(None, Some("Ir: missing"))]
+    fn test_check_not_zero(#[case] ir: Option<u64>, #[case] expected_error: Option<&str>) {
+        let output_path = ToolOutputPath::new(
+            ToolOutputPathKind::Out,
+            ValgrindTool::Callgrind,
+            &BaselineKind::Old,
+            Path::new("/target"),
+            Path::new("/target"),
+            "module",
+            "bench",
+        );
+        let costs = ir.map_or_else(
+            || Costs::with_event_kinds([(Dr, 1)]),
+            |ir| Costs::with_event_kinds([(Ir, ir)]),
+        );
+
+        let result = check_not_zero(&CostsSummary::new(&costs, None), &output_path);
+        match expected_error {
+            Some(expected) => assert!(result.unwrap_err().to_string().contains(expected)),
+            None => result.unwrap(),
+        }
+    }
 }
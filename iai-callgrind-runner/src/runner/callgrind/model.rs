@@ -54,6 +54,7 @@ impl Costs {
             l3_hits,
             ram_hits,
             total_memory_rw,
+            data_rw,
             cycles,
         } = (&*self).try_into()?;
 
@@ -61,6 +62,7 @@ impl Costs {
         self.0.insert(EventKind::LLhits, l3_hits);
         self.0.insert(EventKind::RamHits, ram_hits);
         self.0.insert(EventKind::TotalRW, total_memory_rw);
+        self.0.insert(EventKind::DataRW, data_rw);
         self.0.insert(EventKind::EstimatedCycles, cycles);
 
         Ok(())
@@ -75,10 +77,14 @@ impl Costs {
 
     /// Return true if costs can be summarized
     ///
-    /// This method probes for [`EventKind::I1mr`] which is present if callgrind was run with the
-    /// cache simulation (`--cache-sim=yes`) enabled.
+    /// This is the case if all events required by [`EventKind::EstimatedCycles`] are present
+    /// which requires that callgrind was run with the cache simulation (`--cache-sim=yes`)
+    /// enabled.
     pub fn can_summarize(&self) -> bool {
-        self.cost_by_kind(&EventKind::I1mr).is_some()
+        EventKind::EstimatedCycles
+            .requires()
+            .iter()
+            .all(|event_kind| self.cost_by_kind(event_kind).is_some())
     }
 }
 
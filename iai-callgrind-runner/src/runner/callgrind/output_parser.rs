@@ -0,0 +1,148 @@
+use std::path::PathBuf;
+
+use anyhow::Result;
+use log::debug;
+
+use super::call_graph::{CallGraph, CallGraphParser};
+use super::flamegraph::Stacks;
+use super::flamegraph_parser::FlamegraphMap;
+use super::hashmap_parser::CallgrindMap;
+use super::model::Costs;
+use super::parser::Sentinel;
+use crate::api::{EventKind, ObjectMarker};
+use crate::runner::tool::ToolOutputPath;
+
+/// A callgrind output file which is parsed into all its parts in a single pass
+///
+/// The [`super::summary_parser::SummaryParser`], the [`CallGraphParser`] and the
+/// [`super::flamegraph_parser::FlamegraphParser`] each read the whole file. If more than one of
+/// their outputs is needed, parsing a `CallgrindOutput` is faster, especially for large files.
+/// The flamegraphs use the stacks and the cross-architecture comparison uses the totals. The
+/// costs of a benchmark are still parsed with the [`super::sentinel_parser::SentinelParser`],
+/// since they are the costs of the sentinel and not the totals of the whole program.
+///
+/// # Examples
+///
+/// ```rust,no_run
+/// use std::path::Path;
+///
+/// use iai_callgrind_runner::api::{EventKind, ObjectMarker};
+/// use iai_callgrind_runner::runner::callgrind::output_parser::CallgrindOutput;
+/// use iai_callgrind_runner::runner::summary::BaselineKind;
+/// use iai_callgrind_runner::runner::tool::{ToolOutputPath, ToolOutputPathKind, ValgrindTool};
+///
+/// let path = ToolOutputPath::new(
+///     ToolOutputPathKind::Out,
+///     ValgrindTool::Callgrind,
+///     &BaselineKind::Old,
+///     Path::new("target/iai"),
+///     Path::new("target/iai"),
+///     "my_bench",
+///     "my_func",
+/// );
+/// let parsed = CallgrindOutput::new(path).parse().unwrap();
+///
+/// let stacks = parsed
+///     .to_stacks(&EventKind::Ir, false, ObjectMarker::Bracketed)
+///     .unwrap();
+/// println!("{} stacks", stacks.len());
+/// if let Some(totals) = &parsed.totals {
+///     println!(
+///         "Total instructions: {:?}",
+///         totals.cost_by_kind(&EventKind::Ir)
+///     );
+/// }
+/// ```
+#[derive(Debug, Clone)]
+pub struct CallgrindOutput {
+    /// The path of the callgrind output file
+    pub path: ToolOutputPath,
+    /// The entry point of the stacks. Without a sentinel, the stacks start at `main`.
+    pub sentinel: Option<Sentinel>,
+    /// The paths of the source files are made relative to this directory if possible
+    pub project_root: PathBuf,
+}
+
+/// The [`CallgrindOutput`] after parsing
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct Parsed {
+    /// The call graph with the self and inclusive costs of all functions
+    pub call_graph: CallGraph,
+    /// The inclusive costs of all functions from which the [`Stacks`] are created
+    pub map: FlamegraphMap,
+    /// The costs of the `summary:` or `totals:` line which are the costs of the whole program
+    ///
+    /// This is `None` if the file has neither line like the output files of partial dumps. The
+    /// call graph and the stacks don't depend on the totals.
+    pub totals: Option<Costs>,
+}
+
+impl CallgrindOutput {
+    /// Create a new `CallgrindOutput` without a sentinel and project root
+    pub fn new(path: ToolOutputPath) -> Self {
+        Self {
+            path,
+            sentinel: None,
+            project_root: PathBuf::new(),
+        }
+    }
+
+    /// Set the sentinel at which the stacks start
+    #[must_use]
+    pub fn sentinel(mut self, sentinel: Option<&Sentinel>) -> Self {
+        self.sentinel = sentinel.cloned();
+        self
+    }
+
+    /// Set the project root to which the paths of the source files are made relative
+    #[must_use]
+    pub fn project_root<P>(mut self, project_root: P) -> Self
+    where
+        P: Into<PathBuf>,
+    {
+        self.project_root = project_root.into();
+        self
+    }
+
+    /// Parse the callgrind output file in a single pass
+    ///
+    /// # Errors
+    ///
+    /// If the file couldn't be read or is malformed
+    pub fn parse(&self) -> Result<Parsed> {
+        debug!(
+            "Parsing callgrind output file '{}' in a single pass",
+            self.path
+        );
+
+        let (call_graph, totals) = CallGraphParser {
+            project_root: self.project_root.clone(),
+        }
+        .parse_with_totals(&self.path)?;
+        let map = CallgrindMap::from_call_graph(&call_graph, self.sentinel.as_ref());
+
+        Ok(Parsed {
+            call_graph,
+            map: map.into(),
+            totals,
+        })
+    }
+}
+
+impl Parsed {
+    /// Convert the parsed inclusive costs into [`Stacks`] for this [`EventKind`]
+    ///
+    /// See also [`FlamegraphMap::to_stacks`].
+    ///
+    /// # Errors
+    ///
+    /// If the [`EventKind`] is not present in the costs
+    pub fn to_stacks(
+        &self,
+        event_kind: &EventKind,
+        trim_harness: bool,
+        object_marker: ObjectMarker,
+    ) -> Result<Stacks> {
+        self.map.to_stacks(event_kind, trim_harness, object_marker)
+    }
+}
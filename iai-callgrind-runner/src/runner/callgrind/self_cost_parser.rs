@@ -0,0 +1,270 @@
+use std::cell::Cell;
+use std::cmp::Ordering;
+
+use anyhow::Result;
+use indexmap::IndexMap;
+use log::debug;
+
+use super::model::Costs;
+use super::parser::parse_header;
+use crate::api::EventKind;
+use crate::error::Error;
+use crate::runner::tool::{Parser, ToolOutputPath};
+
+/// The difference of the self cost of a function between the new and the old callgrind run
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FrameDiff {
+    /// The name of the function
+    pub func: String,
+    /// The self cost in the new run or `None` if the function was removed
+    pub new: Option<u64>,
+    /// The self cost in the old run or `None` if the function is new
+    pub old: Option<u64>,
+}
+
+/// The self costs of all functions of a callgrind output file
+///
+/// The self cost of a function are the costs of the function itself excluding the costs of the
+/// functions it calls. Functions with the same name are merged into a single entry even if they
+/// are located in different files or objects.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct SelfCosts(pub IndexMap<String, Costs>);
+
+/// Parse the self costs of all functions of a callgrind output file into [`SelfCosts`]
+pub struct SelfCostParser;
+
+impl FrameDiff {
+    /// Return the difference `new - old` where a missing cost counts as `0`
+    pub fn diff(&self) -> i128 {
+        i128::from(self.new.unwrap_or(0)) - i128::from(self.old.unwrap_or(0))
+    }
+}
+
+impl SelfCosts {
+    /// Return the self cost of the `func` for the [`EventKind`]
+    pub fn cost(&self, func: &str, event_kind: &EventKind) -> Option<u64> {
+        self.0
+            .get(func)
+            .and_then(|costs| costs.cost_by_kind(event_kind))
+    }
+
+    /// Return up to `limit` functions with the largest differences of the self cost
+    ///
+    /// If `increased` is true, only functions with a grown self cost are returned ordered by the
+    /// largest increase first. This includes new functions which are only present in this
+    /// `SelfCosts`. If `increased` is false, only functions with a decreased self cost, including
+    /// the removed functions only present in `old`, are returned ordered by the largest decrease
+    /// first. Functions with the same difference are ordered by their name.
+    pub fn top_diffs(
+        &self,
+        old: &Self,
+        event_kind: &EventKind,
+        increased: bool,
+        limit: usize,
+    ) -> Vec<FrameDiff> {
+        let mut diffs = self
+            .0
+            .keys()
+            .chain(old.0.keys().filter(|func| !self.0.contains_key(*func)))
+            .map(|func| FrameDiff {
+                func: func.clone(),
+                new: self.cost(func, event_kind),
+                old: old.cost(func, event_kind),
+            })
+            .filter(|diff| {
+                let value = diff.diff();
+                (increased && value > 0) || (!increased && value < 0)
+            })
+            .collect::<Vec<FrameDiff>>();
+
+        diffs.sort_by(|a, b| {
+            let ordering = if increased {
+                b.diff().cmp(&a.diff())
+            } else {
+                a.diff().cmp(&b.diff())
+            };
+            match ordering {
+                Ordering::Equal => a.func.cmp(&b.func),
+                ordering => ordering,
+            }
+        });
+        diffs.truncate(limit);
+        diffs
+    }
+}
+
+impl Parser for SelfCostParser {
+    type Output = SelfCosts;
+
+    fn parse(&self, output_path: &ToolOutputPath) -> Result<Self::Output> {
+        debug!(
+            "Parsing self costs of callgrind output file '{}'",
+            output_path
+        );
+
+        let line_number = Cell::new(0);
+        let mut iter = output_path
+            .lines()?
+            .inspect(|_| line_number.set(line_number.get() + 1));
+        let properties = parse_header(&mut iter)
+            .map_err(|error| Error::ParseError((output_path.to_path(), error.to_string())))?;
+
+        let mut self_costs = SelfCosts::default();
+        let mut current_func: Option<String> = None;
+        // The cost line following a `calls=` line contains the inclusive cost of the call and is
+        // not part of the self cost of the current function
+        let mut is_call = false;
+        for line in iter {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            if let Some(func) = line.strip_prefix("fn=") {
+                current_func = Some(func.to_owned());
+                is_call = false;
+            } else if line.starts_with("calls=") {
+                is_call = true;
+            } else if line.starts_with(|c: char| c.is_ascii_digit()) {
+                if is_call {
+                    is_call = false;
+                    continue;
+                }
+                if let Some(func) = &current_func {
+                    let mut costs = properties.costs_prototype.clone();
+                    costs
+                        .try_add_iter_str(
+                            line.split_ascii_whitespace()
+                                .skip(properties.positions_prototype.len()),
+                        )
+                        .map_err(|error| {
+                            Error::ParseLineError((
+                                output_path.to_path(),
+                                line_number.get(),
+                                error.to_string(),
+                            ))
+                        })?;
+                    if let Some(value) = self_costs.0.get_mut(func) {
+                        value.add(&costs);
+                    } else {
+                        self_costs.0.insert(func.clone(), costs);
+                    }
+                }
+            } else {
+                // ignore all other lines
+            }
+        }
+
+        for costs in self_costs.0.values_mut() {
+            if costs.can_summarize() {
+                costs.make_summary()?;
+            }
+        }
+
+        Ok(self_costs)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+    use rstest::rstest;
+
+    use super::*;
+
+    fn self_costs(costs: &[(&str, u64)]) -> SelfCosts {
+        SelfCosts(
+            costs
+                .iter()
+                .map(|(func, cost)| {
+                    (
+                        (*func).to_owned(),
+                        Costs::with_event_kinds([(EventKind::Ir, *cost)]),
+                    )
+                })
+                .collect(),
+        )
+    }
+
+    fn frame_diff(func: &str, new: Option<u64>, old: Option<u64>) -> FrameDiff {
+        FrameDiff {
+            func: func.to_owned(),
+            new,
+            old,
+        }
+    }
+
+    #[rstest]
+    #[case::both(Some(10), Some(4), 6)]
+    #[case::new_function(Some(10), None, 10)]
+    #[case::removed_function(None, Some(4), -4)]
+    #[case::none(None, None, 0)]
+    fn test_frame_diff_diff(
+        #[case] new: Option<u64>,
+        #[case] old: Option<u64>,
+        #[case] expected: i128,
+    ) {
+        assert_eq!(frame_diff("func", new, old).diff(), expected);
+    }
+
+    #[rstest]
+    #[case::empty(&[], &[], true, vec![])]
+    #[case::equal(&[("a", 1)], &[("a", 1)], true, vec![])]
+    #[case::increased(
+        &[("a", 10), ("b", 30), ("c", 5)],
+        &[("a", 5), ("b", 10), ("c", 10)],
+        true,
+        vec![frame_diff("b", Some(30), Some(10)), frame_diff("a", Some(10), Some(5))]
+    )]
+    #[case::decreased(
+        &[("a", 10), ("b", 30), ("c", 5)],
+        &[("a", 5), ("b", 10), ("c", 10)],
+        false,
+        vec![frame_diff("c", Some(5), Some(10))]
+    )]
+    #[case::new_function(
+        &[("a", 10), ("new", 7)],
+        &[("a", 5)],
+        true,
+        vec![frame_diff("new", Some(7), None), frame_diff("a", Some(10), Some(5))]
+    )]
+    #[case::removed_function(
+        &[("a", 10)],
+        &[("a", 5), ("removed", 7)],
+        false,
+        vec![frame_diff("removed", None, Some(7))]
+    )]
+    #[case::removed_function_when_increased(
+        &[("a", 10)],
+        &[("a", 5), ("removed", 7)],
+        true,
+        vec![frame_diff("a", Some(10), Some(5))]
+    )]
+    #[case::same_diff_ordered_by_name(
+        &[("b", 10), ("a", 10)],
+        &[],
+        true,
+        vec![frame_diff("a", Some(10), None), frame_diff("b", Some(10), None)]
+    )]
+    #[case::limit(
+        &[("a", 1), ("b", 2), ("c", 3), ("d", 4)],
+        &[],
+        true,
+        vec![
+            frame_diff("d", Some(4), None),
+            frame_diff("c", Some(3), None),
+            frame_diff("b", Some(2), None)
+        ]
+    )]
+    fn test_self_costs_top_diffs(
+        #[case] new: &[(&str, u64)],
+        #[case] old: &[(&str, u64)],
+        #[case] increased: bool,
+        #[case] expected: Vec<FrameDiff>,
+    ) {
+        assert_eq!(
+            self_costs(new).top_diffs(&self_costs(old), &EventKind::Ir, increased, 3),
+            expected
+        );
+    }
+}
@@ -1,10 +1,12 @@
+use std::cell::Cell;
+
 use anyhow::Result;
 use log::{debug, trace};
 
 use super::model::Costs;
 use super::parser::{parse_header, Sentinel};
 use crate::error::Error;
-use crate::runner::tool::{Parser, ToolOutputPath};
+use crate::runner::tool::{Parser, ToolOutputPath, ToolParser, ValgrindTool};
 
 #[rustfmt::skip]
 pub const ERROR_MESSAGE_DEBUG_SYMBOLS: &str = "
@@ -37,7 +39,10 @@ impl Parser for SentinelParser {
             output_path, self.sentinel
         );
 
-        let mut iter = output_path.lines()?;
+        let line_number = Cell::new(0);
+        let mut iter = output_path
+            .lines()?
+            .inspect(|_| line_number.set(line_number.get() + 1));
         let properties = parse_header(&mut iter)
             .map_err(|error| Error::ParseError((output_path.to_path(), error.to_string())))?;
 
@@ -70,12 +75,19 @@ impl Parser for SentinelParser {
                 // > If a cost line specifies less event counts than given in the "events" line, the
                 // > rest is assumed to be zero.
                 trace!("Found line with counters: '{}'", line);
-                costs.add_iter_str(
-                    line
-                    .split_ascii_whitespace()
-                    // skip the positions
-                    .skip(properties.positions_prototype.len()),
-                );
+                costs
+                    .try_add_iter_str(
+                        line.split_ascii_whitespace()
+                            // skip the positions
+                            .skip(properties.positions_prototype.len()),
+                    )
+                    .map_err(|error| {
+                        Error::ParseLineError((
+                            output_path.to_path(),
+                            line_number.get(),
+                            error.to_string(),
+                        ))
+                    })?;
                 trace!("Updated counters to '{:?}'", &costs);
             } else {
                 trace!("Skipping line: '{}'", line);
@@ -96,3 +108,13 @@ impl Parser for SentinelParser {
         }
     }
 }
+
+impl ToolParser for SentinelParser {
+    fn tool(&self) -> ValgrindTool {
+        ValgrindTool::Callgrind
+    }
+
+    fn parse_costs(&self, output_path: &ToolOutputPath) -> Result<Costs> {
+        self.parse(output_path)
+    }
+}
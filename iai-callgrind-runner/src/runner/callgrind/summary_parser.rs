@@ -1,10 +1,12 @@
+use std::cell::Cell;
+
 use anyhow::Result;
 use log::{debug, trace};
 
 use super::model::Costs;
 use super::parser::parse_header;
 use crate::error::Error;
-use crate::runner::tool::{Parser, ToolOutputPath};
+use crate::runner::tool::{Parser, ToolOutputPath, ToolParser, ValgrindTool};
 
 pub struct SummaryParser;
 
@@ -20,7 +22,10 @@ impl Parser for SummaryParser {
             output_path
         );
 
-        let mut iter = output_path.lines()?;
+        let line_number = Cell::new(0);
+        let mut iter = output_path
+            .lines()?
+            .inspect(|_| line_number.set(line_number.get() + 1));
         let config = parse_header(&mut iter)
             .map_err(|error| Error::ParseError((output_path.to_path(), error.to_string())))?;
 
@@ -29,16 +34,32 @@ impl Parser for SummaryParser {
         for line in iter {
             if let Some(stripped) = line.strip_prefix("summary:") {
                 trace!("Found line with summary: '{}'", line);
-                costs.add_iter_str(stripped.split_ascii_whitespace());
-                trace!("Updated counters to '{:?}'", &costs);
+                costs
+                    .try_add_iter_str(stripped.split_ascii_whitespace())
+                    .map_err(|error| {
+                        Error::ParseLineError((
+                            output_path.to_path(),
+                            line_number.get(),
+                            error.to_string(),
+                        ))
+                    })?;
+                trace!("Updated counters to:\n{costs}");
                 found = true;
                 break;
             }
 
             if let Some(stripped) = line.strip_prefix("totals:") {
                 trace!("Found line with totals: '{}'", line);
-                costs.add_iter_str(stripped.split_ascii_whitespace());
-                trace!("Updated counters to '{:?}'", &costs);
+                costs
+                    .try_add_iter_str(stripped.split_ascii_whitespace())
+                    .map_err(|error| {
+                        Error::ParseLineError((
+                            output_path.to_path(),
+                            line_number.get(),
+                            error.to_string(),
+                        ))
+                    })?;
+                trace!("Updated counters to:\n{costs}");
                 found = true;
                 break;
             }
@@ -55,3 +76,13 @@ impl Parser for SummaryParser {
         }
     }
 }
+
+impl ToolParser for SummaryParser {
+    fn tool(&self) -> ValgrindTool {
+        ValgrindTool::Callgrind
+    }
+
+    fn parse_costs(&self, output_path: &ToolOutputPath) -> Result<Costs> {
+        self.parse(output_path)
+    }
+}
@@ -7,6 +7,8 @@ use indexmap::map::Iter;
 use indexmap::{IndexMap, IndexSet};
 use serde::{Deserialize, Serialize};
 
+use crate::api::EventKind;
+
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct Costs<K: Hash + Eq>(pub IndexMap<K, u64>);
 
@@ -19,17 +21,38 @@ impl<K: Hash + Eq + Display + Clone> Costs<K> {
         Self(kinds.into_iter().collect())
     }
 
-    pub fn add_iter_str<I, T>(&mut self, iter: T)
+    /// Add the costs of a cost line given as strings to these costs
+    ///
+    /// It's an error if there are more costs than event kinds or if a cost is not a number. Less
+    /// costs than event kinds are valid and the missing costs are zero.
+    ///
+    /// # Errors
+    ///
+    /// If the costs don't match the event kinds
+    pub fn try_add_iter_str<I, T>(&mut self, iter: T) -> Result<()>
     where
         I: AsRef<str>,
         T: IntoIterator<Item = I>,
     {
+        let costs = iter.into_iter().collect::<Vec<_>>();
+        if costs.len() > self.0.len() {
+            return Err(anyhow!(
+                "Expected at most {} costs as in the 'events' line but found {}",
+                self.0.len(),
+                costs.len()
+            ));
+        }
+
         // From the documentation of the callgrind format:
         // > If a cost line specifies less event counts than given in the "events" line, the
         // > rest is assumed to be zero.
-        for ((_, old), cost) in self.0.iter_mut().zip(iter.into_iter()) {
-            *old += cost.as_ref().parse::<u64>().unwrap();
+        for ((_, old), cost) in self.0.iter_mut().zip(costs) {
+            let cost = cost.as_ref();
+            *old += cost
+                .parse::<u64>()
+                .map_err(|_| anyhow!("Invalid cost: '{cost}'"))?;
         }
+        Ok(())
     }
 
     pub fn add(&mut self, other: &Self) {
@@ -54,7 +77,7 @@ impl<K: Hash + Eq + Display + Clone> Costs<K> {
 
     pub fn try_cost_by_kind(&self, kind: &K) -> Result<u64> {
         self.cost_by_kind(kind)
-            .ok_or_else(|| anyhow!("Missing event type '{kind}"))
+            .ok_or_else(|| anyhow!("Missing event type '{kind}'"))
     }
 
     pub fn event_kinds(&self) -> Vec<K> {
@@ -71,6 +94,14 @@ impl<K: Hash + Eq + Display + Clone> Costs<K> {
         self.0.iter()
     }
 
+    /// Return an iterator over the owned `(kind, cost)` pairs in the order of insertion
+    ///
+    /// In contrast to [`Costs::iter`], the pairs are not references, so they can be collected or
+    /// compared without dereferencing.
+    pub fn pairs(&self) -> impl Iterator<Item = (K, u64)> + '_ {
+        self.0.iter().map(|(k, c)| (k.clone(), *c))
+    }
+
     pub fn empty() -> Self {
         Costs(IndexMap::new())
     }
@@ -80,6 +111,31 @@ impl<K: Hash + Eq + Display + Clone> Costs<K> {
     }
 }
 
+/// Print the costs as `kind: cost` pairs with aligned columns, one pair per line
+///
+/// The pairs are printed in the canonical order of the [`EventKind`]s and not in the order of the
+/// `events` line of the output file, so the costs of different output files can be compared line
+/// by line. The names are left-aligned and the costs right-aligned, so the costs can be read at a
+/// glance in debug output and bug reports. In contrast to the [`Debug`] output, this output is
+/// meant for humans and not for parsing.
+impl Display for Costs<EventKind> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let mut pairs = self.0.iter().collect::<Vec<_>>();
+        pairs.sort_by_key(|(kind, _)| **kind);
+        fmt_aligned(f, &pairs)
+    }
+}
+
+/// Print the costs as `kind: cost` pairs with aligned columns, one pair per line
+///
+/// The costs of the tools other than callgrind and cachegrind have no canonical order, so the
+/// pairs are printed in the order of the log file.
+impl Display for Costs<String> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        fmt_aligned(f, &self.0.iter().collect::<Vec<_>>())
+    }
+}
+
 pub trait Summarize: Hash + Eq + Clone {
     fn summarize(_: &mut Cow<Costs<Self>>) {}
 }
@@ -96,6 +152,31 @@ impl<'a, K: Hash + Eq + Display + Clone> IntoIterator for &'a Costs<K> {
     }
 }
 
+/// Write the `pairs` with left-aligned names and right-aligned costs, one pair per line
+fn fmt_aligned<K: Display>(
+    f: &mut std::fmt::Formatter<'_>,
+    pairs: &[(&K, &u64)],
+) -> std::fmt::Result {
+    let kinds = pairs
+        .iter()
+        .map(|(kind, _)| format!("{kind}:"))
+        .collect::<Vec<_>>();
+    let kind_width = kinds.iter().map(String::len).max().unwrap_or_default();
+    let cost_width = pairs
+        .iter()
+        .map(|(_, cost)| cost.to_string().len())
+        .max()
+        .unwrap_or_default();
+
+    for (index, (kind, (_, cost))) in kinds.iter().zip(pairs).enumerate() {
+        if index > 0 {
+            writeln!(f)?;
+        }
+        write!(f, "{kind:<kind_width$} {cost:>cost_width$}")?;
+    }
+    Ok(())
+}
+
 impl<I, K: Hash + Eq + From<I>> FromIterator<I> for Costs<K> {
     fn from_iter<T>(iter: T) -> Self
     where
@@ -108,3 +189,80 @@ impl<I, K: Hash + Eq + From<I>> FromIterator<I> for Costs<K> {
         )
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+    use rstest::rstest;
+
+    use super::*;
+
+    #[test]
+    fn test_costs_display() {
+        let costs = Costs::with_event_kinds([
+            (EventKind::Ir, 1_234_567),
+            (EventKind::Dr, 89),
+            (EventKind::D1mr, 0),
+            (EventKind::EstimatedCycles, 2_345_678),
+        ]);
+        assert_eq!(
+            costs.to_string(),
+            [
+                "Instructions:     1234567",
+                "Dr:                    89",
+                "D1mr:                   0",
+                "Estimated Cycles: 2345678",
+            ]
+            .join("\n")
+        );
+    }
+
+    #[test]
+    fn test_costs_display_in_canonical_order() {
+        let costs = Costs::with_event_kinds([
+            (EventKind::EstimatedCycles, 30),
+            (EventKind::Dw, 2),
+            (EventKind::Ir, 10),
+            (EventKind::Dr, 1),
+        ]);
+        assert_eq!(
+            costs.to_string(),
+            [
+                "Instructions:     10",
+                "Dr:                1",
+                "Dw:                2",
+                "Estimated Cycles: 30",
+            ]
+            .join("\n")
+        );
+    }
+
+    #[test]
+    fn test_costs_pairs_in_insertion_order() {
+        let costs =
+            Costs::with_event_kinds([(EventKind::Dr, 2), (EventKind::Ir, 1), (EventKind::Dw, 3)]);
+
+        assert_eq!(
+            costs.pairs().collect::<Vec<_>>(),
+            vec![(EventKind::Dr, 2), (EventKind::Ir, 1), (EventKind::Dw, 3)]
+        );
+    }
+
+    #[rstest]
+    #[case::empty(&[], "")]
+    #[case::single(&[("Total bytes", 12)], "Total bytes: 12")]
+    #[case::keeps_order(
+        &[("Total units", 5), ("Total events", 1000)],
+        "Total units:     5\nTotal events: 1000"
+    )]
+    fn test_costs_display_with_string_keys(#[case] costs: &[(&str, u64)], #[case] expected: &str) {
+        let costs = Costs::with_event_kinds(costs.iter().map(|(k, c)| ((*k).to_owned(), *c)));
+        assert_eq!(costs.to_string(), expected);
+    }
+
+    #[test]
+    fn test_costs_debug_is_unchanged() {
+        let costs = Costs::with_event_kinds([("Ir".to_owned(), 1)]);
+        assert_eq!(format!("{costs:?}"), "Costs({\"Ir\": 1})");
+    }
+}
@@ -4,6 +4,7 @@ use std::iter;
 use std::path::PathBuf;
 
 use anyhow::{Context, Result};
+use indexmap::IndexMap;
 use lazy_static::lazy_static;
 use regex::Regex;
 
@@ -152,6 +153,7 @@ impl LogfileParser for DhatLogfileParser {
             fields: vec![],
             details,
             error_summary: None,
+            error_kinds: IndexMap::default(),
             log_path: make_relative(&self.root_dir, path),
             costs,
         })
@@ -1,13 +1,17 @@
+use std::cmp::Ordering;
 use std::fmt::{Display, Write};
+use std::str::FromStr;
+use std::time::Duration;
 
-use anyhow::Result;
+use anyhow::{Context, Result};
 use colored::{ColoredString, Colorize};
 
 use super::meta::Metadata;
-use super::summary::{CostsDiff, CostsSummary};
+use super::report::is_terminal_output;
+use super::summary::{BenchmarkSummary, CostsDiff, CostsSummary, RegressionsReportEntry};
 use super::tool::ValgrindTool;
 use crate::api::EventKind;
-use crate::util::{to_string_signed_short, truncate_str_utf8};
+use crate::util::{to_string_separated, to_string_si, to_string_signed_short, truncate_str_utf8};
 
 pub struct ComparisonHeader {
     pub function_name: String,
@@ -37,20 +41,144 @@ pub trait Formatter {
         }
     }
 
+    /// Format the `costs_summary` without the events which are hidden by the `event_filter`
     fn format(
         &self,
         baselines: (Option<String>, Option<String>),
         costs_summary: &CostsSummary,
+        number_format: NumberFormat,
+        diff_format: DiffFormat,
+        event_filter: Option<&EventFilter>,
     ) -> Result<String>;
 }
 
+/// The output formats of `--output-format`
+///
+/// Each `OutputFormat` has a [`super::report::Reporter`].
 #[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
 pub enum OutputFormat {
+    /// The human-readable terminal output
+    #[value(alias = "terminal")]
     Default,
+    /// A single line of json for each benchmark
     Json,
+    /// Pretty printed json for each benchmark
     PrettyJson,
 }
 
+/// The format of the costs in the human-readable terminal output
+///
+/// The machine-readable outputs like the json summaries always contain the raw numbers.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum NumberFormat {
+    /// The raw number, like `1234567890`
+    #[default]
+    Plain,
+    /// A `,` between each group of thousands, like `1,234,567,890`
+    Comma,
+    /// A `_` between each group of thousands, like `1_234_567_890`
+    Underscore,
+    /// Abbreviated with a SI prefix and three significant digits, like `1.23G`
+    Si,
+}
+
+/// The format of the difference between the new and the old costs in the terminal output
+///
+/// The machine-readable outputs like the json summaries always contain the difference in percent
+/// and the difference factor.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum DiffFormat {
+    /// The difference in percent and the difference factor, like `(+5.00000%) [+1.05000x]`
+    #[default]
+    Percentage,
+    /// The ratio of the new to the old costs instead of the difference in percent, like
+    /// `(1.05000x)`
+    Ratio,
+    /// The ratio next to the difference in percent, like `(+5.00000%) [1.05000x]`
+    Both,
+}
+
+/// The filter of `--filter-event` which hides insignificant events in the terminal output
+///
+/// An event is shown if its new or old cost is at least the `min_cost` or if the absolute value of
+/// its difference in percent is at least the `min_diff_pct`. An event without an old or new cost
+/// to compare with always crosses the `min_diff_pct`. Without any bar all events are shown. The
+/// filter only hides events in the human-readable terminal output and has no influence on the
+/// regression checks or the machine-readable outputs.
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct EventFilter {
+    /// The minimum new or old cost of a shown event
+    pub min_cost: Option<u64>,
+    /// The minimum absolute difference in percent of a shown event
+    pub min_diff_pct: Option<f64>,
+}
+
+impl EventFilter {
+    /// Return true if the event with this [`CostsDiff`] crosses any of the bars
+    pub fn is_shown(&self, diff: &CostsDiff) -> bool {
+        if self.min_cost.is_none() && self.min_diff_pct.is_none() {
+            return true;
+        }
+
+        let crosses_min_cost = self.min_cost.map_or(false, |min_cost| {
+            diff.new
+                .max(diff.old)
+                .map_or(false, |cost| cost >= min_cost)
+        });
+        let crosses_min_diff_pct = self.min_diff_pct.map_or(false, |min_diff_pct| {
+            match (diff.new, diff.old, diff.diff_pct) {
+                (Some(new), Some(old), _) if new == old => false,
+                (Some(_), Some(_), Some(diff_pct)) => diff_pct.abs() >= min_diff_pct,
+                _ => true,
+            }
+        });
+
+        crosses_min_cost || crosses_min_diff_pct
+    }
+}
+
+impl FromStr for EventFilter {
+    type Err = String;
+
+    /// Parse a `,` separated list of bars
+    ///
+    /// A bar with a `%` suffix like `5%` is the `min_diff_pct`, a bar without suffix like `1000`
+    /// the `min_cost`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut filter = Self::default();
+        for bar in s.split(',').map(str::trim) {
+            if let Some(pct) = bar.strip_suffix('%') {
+                let pct = pct
+                    .trim()
+                    .parse::<f64>()
+                    .ok()
+                    .filter(|pct| pct.is_finite() && *pct >= 0f64)
+                    .ok_or_else(|| {
+                        format!(
+                            "Invalid difference: '{bar}'. The difference must be zero or a \
+                             positive percentage like '5%'"
+                        )
+                    })?;
+                if filter.min_diff_pct.replace(pct).is_some() {
+                    return Err(format!("The difference is given more than once: '{s}'"));
+                }
+            } else {
+                let cost = bar.parse::<u64>().map_err(|error| {
+                    format!(
+                        "Invalid cost: '{bar}': {error}. The cost must be zero or a positive \
+                         integer"
+                    )
+                })?;
+                if filter.min_cost.replace(cost).is_some() {
+                    return Err(format!("The cost is given more than once: '{s}'"));
+                }
+            }
+        }
+
+        Ok(filter)
+    }
+}
+
 #[derive(Clone)]
 pub struct VerticalFormat {
     event_kinds: Vec<EventKind>,
@@ -152,6 +280,15 @@ impl Header {
         }
         output
     }
+
+    /// The plain and untruncated name of the benchmark as printed with `--list`
+    pub fn to_list_entry(&self) -> String {
+        match (&self.id, &self.description) {
+            (Some(id), Some(description)) => format!("{} {id}:{description}", self.module_path),
+            (Some(id), None) => format!("{} {id}", self.module_path),
+            (None, _) => self.module_path.clone(),
+        }
+    }
 }
 
 impl Display for Header {
@@ -179,6 +316,18 @@ impl Display for Header {
     }
 }
 
+impl NumberFormat {
+    /// Format the number `n` in this `NumberFormat`
+    pub fn format(self, n: u64) -> String {
+        match self {
+            Self::Plain => n.to_string(),
+            Self::Comma => to_string_separated(n, ','),
+            Self::Underscore => to_string_separated(n, '_'),
+            Self::Si => to_string_si(n),
+        }
+    }
+}
+
 impl VerticalFormat {
     pub fn print(
         &self,
@@ -186,11 +335,34 @@ impl VerticalFormat {
         baselines: (Option<String>, Option<String>),
         costs_summary: &CostsSummary,
     ) -> Result<()> {
-        if meta.args.output_format == OutputFormat::Default {
-            print!("{}", self.format(baselines, costs_summary)?);
+        if is_inline_output(meta) {
+            print!(
+                "{}",
+                self.format(
+                    baselines,
+                    costs_summary,
+                    meta.args.number_format,
+                    meta.args.diff_format,
+                    meta.args.filter_event.as_ref()
+                )?
+            );
         }
         Ok(())
     }
+
+    /// Return true if any of the shown events changed by more than the `tolerance` in percent
+    ///
+    /// An event without an old or new cost to compare with is considered changed.
+    pub fn is_changed(&self, costs_summary: &CostsSummary, tolerance: f64) -> bool {
+        self.event_kinds
+            .iter()
+            .filter_map(|e| costs_summary.diff_by_kind(e))
+            .any(|diff| match (diff.new, diff.old, diff.diff_pct) {
+                (Some(new), Some(old), _) if new == old => false,
+                (Some(_), Some(_), Some(diff_pct)) => diff_pct.abs() > tolerance,
+                _ => true,
+            })
+    }
 }
 
 impl Default for VerticalFormat {
@@ -204,6 +376,7 @@ impl Default for VerticalFormat {
                 RamHits,
                 TotalRW,
                 EstimatedCycles,
+                Score,
                 SysCount,
                 SysTime,
                 SysCpuTime,
@@ -229,21 +402,50 @@ impl Formatter for VerticalFormat {
         &self,
         baselines: (Option<String>, Option<String>),
         costs_summary: &CostsSummary,
+        number_format: NumberFormat,
+        diff_format: DiffFormat,
+        event_filter: Option<&EventFilter>,
     ) -> Result<String> {
         format_vertical(
             baselines,
-            self.event_kinds
-                .iter()
-                .filter_map(|e| costs_summary.diff_by_kind(e).map(|d| (e, d))),
+            self.event_kinds.iter().filter_map(|e| {
+                costs_summary
+                    .diff_by_kind(e)
+                    .filter(|d| event_filter.map_or(true, |filter| filter.is_shown(d)))
+                    .map(|d| (e, d))
+            }),
+            number_format,
+            diff_format,
         )
     }
 }
 
 pub const NOT_AVAILABLE: &str = "N/A";
 
+/// Format the ratio of the `new` to the `old` costs like `1.05000x`
+///
+/// The ratio is red if the costs increased and green if they decreased. If the `old` costs are
+/// zero, the ratio is infinite and shown as `∞`.
+pub fn format_ratio(new: u64, old: u64) -> ColoredString {
+    if old == 0 {
+        return "∞".bright_red().bold();
+    }
+
+    #[allow(clippy::cast_precision_loss)]
+    let ratio = new as f64 / old as f64;
+    let string = format!("{}x", to_string_signed_short(ratio).trim_start_matches('+'));
+    match new.cmp(&old) {
+        Ordering::Greater => string.bright_red().bold(),
+        Ordering::Less => string.bright_green().bold(),
+        Ordering::Equal => string.bright_black(),
+    }
+}
+
 pub fn format_vertical<'a, K: Display + 'a>(
     baselines: (Option<String>, Option<String>),
     costs_summary: impl Iterator<Item = (&'a K, &'a CostsDiff)>,
+    number_format: NumberFormat,
+    diff_format: DiffFormat,
 ) -> Result<String> {
     let mut result = String::new();
 
@@ -263,9 +465,13 @@ pub fn format_vertical<'a, K: Display + 'a>(
         }
     }
 
+    let mut is_new = None;
     for (event_kind, diff) in costs_summary {
+        *is_new.get_or_insert(true) &= diff.old.is_none();
         let description = format!("{event_kind}:");
-        match (diff.new, diff.old) {
+        let new_cost = diff.new.map(|cost| number_format.format(cost));
+        let old_cost = diff.old.map(|cost| number_format.format(cost));
+        match (new_cost, old_cost) {
             (None, Some(old_cost)) => writeln!(
                 result,
                 "  {description:<18}{:>15}|{old_cost:<15} ({:^9})",
@@ -275,42 +481,66 @@ pub fn format_vertical<'a, K: Display + 'a>(
             (Some(new_cost), None) => writeln!(
                 result,
                 "  {description:<18}{:>15}|{NOT_AVAILABLE:<15} ({:^9})",
-                new_cost.to_string().bold(),
+                new_cost.bold(),
                 unknown.bright_black()
             )?,
-            (Some(new_cost), Some(old_cost)) if new_cost == old_cost => writeln!(
+            (Some(new_cost), Some(old_cost)) if diff.new == diff.old => writeln!(
                 result,
                 "  {description:<18}{:>15}|{old_cost:<15} ({:^9})",
-                new_cost.to_string().bold(),
+                new_cost.bold(),
                 no_change.bright_black()
             )?,
             (Some(new_cost), Some(old_cost)) => {
-                let pct_string = {
+                let pct_string = || {
                     let pct = diff.diff_pct.expect(
                         "If there are new costs and old costs there should be a difference in \
                          percent",
                     );
                     VerticalFormat::format_float(pct, "%")
                 };
-                let factor_string = {
+                let factor_string = || {
                     let factor = diff.factor.expect(
                         "If there are new costs and old costs there should be a difference factor",
                     );
                     VerticalFormat::format_float(factor, "x")
                 };
+                let ratio_string = || {
+                    format_ratio(
+                        diff.new.expect("The new costs should be present"),
+                        diff.old.expect("The old costs should be present"),
+                    )
+                };
+                let diff_string = match diff_format {
+                    DiffFormat::Percentage => {
+                        format!("({:^9}) [{:^9}]", pct_string(), factor_string())
+                    }
+                    DiffFormat::Ratio => format!("({:^9})", ratio_string()),
+                    DiffFormat::Both => format!("({:^9}) [{:^9}]", pct_string(), ratio_string()),
+                };
                 writeln!(
                     result,
-                    "  {description:<18}{:>15}|{old_cost:<15} ({pct_string:^9}) \
-                     [{factor_string:^9}]",
-                    new_cost.to_string().bold(),
+                    "  {description:<18}{:>15}|{old_cost:<15} {diff_string}",
+                    new_cost.bold(),
                 )?;
             }
             _ => {}
         }
     }
+
+    if is_new.unwrap_or_default() {
+        writeln!(result, "{}", format_new())?;
+    }
     Ok(result)
 }
 
+/// Format the status of a benchmark without any old costs to compare with
+///
+/// Without a baseline, there are no differences and no regressions, so the benchmark is shown as
+/// new instead.
+pub fn format_new() -> String {
+    format!("  {:<18}{}", "Status:", "new (no baseline)".yellow().bold())
+}
+
 pub fn tool_headline(tool: ValgrindTool) -> String {
     let id = tool.id();
     format!(
@@ -321,3 +551,711 @@ pub fn tool_headline(tool: ValgrindTool) -> String {
         // "=".repeat(34 - tool.id().len()).bright_black()
     )
 }
+
+/// Print the peak resident set size if present and the output format is the default format
+pub fn print_peak_rss(meta: &Metadata, peak_rss: Option<u64>) {
+    if is_inline_output(meta) {
+        if let Some(peak_rss) = peak_rss {
+            println!("{}", format_peak_rss(peak_rss));
+        }
+    }
+}
+
+pub fn format_peak_rss(peak_rss: u64) -> String {
+    format!(
+        "  {:<18}{:>15}",
+        "Peak RSS (KiB):",
+        peak_rss.to_string().bold()
+    )
+}
+
+/// Print the wall-clock time of the valgrind process if present, `--valgrind-time` is given and
+/// the output format is the default format
+pub fn print_valgrind_time(meta: &Metadata, wall_time: Option<Duration>) {
+    if meta.args.valgrind_time.unwrap_or_default() && is_inline_output(meta) {
+        if let Some(wall_time) = wall_time {
+            println!("{}", format_valgrind_time(wall_time.as_secs_f64()));
+        }
+    }
+}
+
+/// Format the wall-clock time in seconds of the valgrind process
+///
+/// The time is explicitly labeled as instrumented to not mistake it for a benchmark result.
+pub fn format_valgrind_time(wall_time: f64) -> String {
+    format!(
+        "  {:<18}{:>15} {}",
+        "Valgrind time:",
+        format!("{wall_time:.3}s").bold(),
+        "(instrumented, not a benchmark result)".bright_black()
+    )
+}
+
+/// Format the name of a region of a binary benchmark shown above the costs of the region
+pub fn format_region(region: &str) -> String {
+    format!("  {:<18}{}", "Region:", region.bold())
+}
+
+/// Print the result of the determinism check if present and the output format is the default
+/// format
+pub fn print_deterministic(meta: &Metadata, deterministic: Option<bool>) {
+    if is_inline_output(meta) {
+        if let Some(deterministic) = deterministic {
+            println!("{}", format_deterministic(deterministic));
+        }
+    }
+}
+
+pub fn format_deterministic(deterministic: bool) -> String {
+    format!(
+        "  {:<18}{:>15}",
+        "Deterministic:",
+        if deterministic {
+            "yes".green().bold()
+        } else {
+            "no".red().bold()
+        }
+    )
+}
+
+/// The number of benchmarks for the final status line of `--only-changed` and `--quiet`
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct BenchmarkCounts {
+    pub total: usize,
+    pub not_shown: usize,
+    pub regressed: usize,
+    pub new: usize,
+    pub flamegraphs: usize,
+    pub flamegraphs_skipped: usize,
+}
+
+impl BenchmarkCounts {
+    /// Print the deferred terminal output of the `summary` if necessary and count the benchmark
+    pub fn print_and_count(
+        &mut self,
+        meta: &Metadata,
+        summary: &BenchmarkSummary,
+        baselines: &(Option<String>, Option<String>),
+    ) -> Result<()> {
+        self.total += 1;
+        if summary.is_regressed() {
+            self.regressed += 1;
+        } else if summary.is_new() {
+            self.new += 1;
+        } else {
+            // The benchmark is neither regressed nor new
+        }
+        if !summary.print_deferred(meta, baselines)? {
+            self.not_shown += 1;
+        }
+        Ok(())
+    }
+
+    /// Count the benchmark as benchmark with flamegraphs or whose flamegraphs are skipped by the
+    /// `--flamegraph-filter`
+    pub fn count_flamegraphs(&mut self, summary: &BenchmarkSummary, skipped: bool) {
+        if skipped {
+            self.flamegraphs_skipped += 1;
+        } else if summary
+            .callgrind_summary
+            .as_ref()
+            .map_or(false, |callgrind_summary| {
+                !callgrind_summary.flamegraphs.is_empty()
+            })
+        {
+            self.flamegraphs += 1;
+        } else {
+            // The benchmark doesn't create flamegraphs
+        }
+    }
+
+    /// Print the number of unchanged benchmarks if only changed benchmarks are printed or the
+    /// final status line if `--quiet` is given
+    ///
+    /// With the `--flamegraph-filter`, the number of benchmarks with and without flamegraphs is
+    /// printed, too.
+    pub fn print(&self, meta: &Metadata) {
+        if !is_terminal_output(&meta.args.output_format) {
+            return;
+        }
+
+        if meta.args.flamegraph_filter.is_some() {
+            println!(
+                "{}",
+                format_flamegraph_status(self.flamegraphs, self.flamegraphs_skipped).bright_black()
+            );
+        }
+
+        if meta.args.quiet.unwrap_or_default() {
+            println!(
+                "{}",
+                format_quiet_status(self.total, self.new, self.regressed)
+            );
+            return;
+        }
+
+        if meta.args.only_changed.unwrap_or_default() {
+            println!(
+                "{}",
+                format!(
+                    "{} unchanged benchmark{} not shown",
+                    self.not_shown,
+                    if self.not_shown == 1 { "" } else { "s" }
+                )
+                .bright_black()
+            );
+        }
+    }
+}
+
+/// Return true if the human-readable terminal output is printed while the benchmarks are running
+///
+/// This is not the case for the machine-readable output formats and if only changed
+/// (`--only-changed`) or regressed (`--quiet`) benchmarks are printed. In the latter cases, the
+/// output is printed after the benchmark has finished and only if the benchmark changed or
+/// regressed.
+pub fn is_inline_output(meta: &Metadata) -> bool {
+    is_terminal_output(&meta.args.output_format)
+        && !meta.args.only_changed.unwrap_or_default()
+        && !meta.args.quiet.unwrap_or_default()
+}
+
+/// Format the status line of the `--flamegraph-filter`
+pub fn format_flamegraph_status(created: usize, skipped: usize) -> String {
+    format!(
+        "Flamegraphs created for {created} benchmark{}, skipped for {skipped} benchmark{}",
+        if created == 1 { "" } else { "s" },
+        if skipped == 1 { "" } else { "s" }
+    )
+}
+
+/// Format the final status line of `--quiet`
+///
+/// The number of `new` benchmarks without baseline is only shown if there are any.
+pub fn format_quiet_status(total: usize, new: usize, regressed: usize) -> String {
+    let regressions = format!(
+        "{regressed} regression{}",
+        if regressed == 1 { "" } else { "s" }
+    );
+    format!(
+        "{total} benchmark{}, {}{}",
+        if total == 1 { "" } else { "s" },
+        if new == 0 {
+            String::new()
+        } else {
+            format!("{new} new, ")
+        },
+        if regressed == 0 {
+            regressions.green().bold()
+        } else {
+            regressions.red().bold()
+        }
+    )
+}
+
+/// Print the top regressions of the `summaries` if requested with `--top-regressions`
+///
+/// The ranked list is also written to the file of `--top-regressions-markdown` if given.
+///
+/// # Errors
+///
+/// If the Markdown file could not be written
+pub fn print_top_regressions(meta: &Metadata, summaries: &[BenchmarkSummary]) -> Result<()> {
+    let Some(num) = meta.args.top_regressions else {
+        return Ok(());
+    };
+
+    let entries = RegressionsReportEntry::top(summaries, num, meta.args.primary_event);
+    if is_terminal_output(&meta.args.output_format) {
+        print!(
+            "{}",
+            format_top_regressions(&entries, meta.args.number_format)
+        );
+    }
+
+    if let Some(path) = &meta.args.top_regressions_markdown {
+        let path = meta.project_root.join(path);
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)
+                .with_context(|| format!("Failed to create directory '{}'", parent.display()))?;
+        }
+        std::fs::write(
+            &path,
+            format_top_regressions_markdown(&entries, meta.args.number_format),
+        )
+        .with_context(|| {
+            format!(
+                "Failed to write the top regressions to '{}'",
+                path.display()
+            )
+        })?;
+    }
+
+    Ok(())
+}
+
+/// Format the ranked list of the top regressions as Markdown table
+///
+/// The columns are the same as in [`format_top_regressions`] but without colors.
+pub fn format_top_regressions_markdown(
+    entries: &[RegressionsReportEntry],
+    number_format: NumberFormat,
+) -> String {
+    let mut result = "## Top regressions\n\n".to_owned();
+    if entries.is_empty() {
+        result.push_str("No regressions\n");
+        return result;
+    }
+
+    result.push_str("| Rank | Event | New | Old | Diff | Benchmark |\n");
+    result.push_str("| ---: | :--- | ---: | ---: | ---: | :--- |\n");
+    for (index, entry) in entries.iter().enumerate() {
+        writeln!(
+            result,
+            "| {} | {} | {} | {} | {}% | `{}` |",
+            index + 1,
+            entry.event,
+            number_format.format(entry.new),
+            number_format.format(entry.baseline),
+            to_string_signed_short(entry.pct),
+            entry.benchmark
+        )
+        .unwrap();
+    }
+    result
+}
+
+/// Format the ranked list of the top regressions
+///
+/// Each line shows the rank, the event with the new and old cost, the difference in percent and
+/// the benchmark.
+pub fn format_top_regressions(
+    entries: &[RegressionsReportEntry],
+    number_format: NumberFormat,
+) -> String {
+    let mut result = format!("{}\n", "Top regressions:".bold());
+    if entries.is_empty() {
+        writeln!(result, "  {}", "No regressions".bright_black()).unwrap();
+    }
+    for (index, entry) in entries.iter().enumerate() {
+        writeln!(
+            result,
+            "  {:>3} {:<18}{:>15}|{:<15} ({:^9}) {}",
+            format!("{}.", index + 1),
+            format!("{}:", entry.event),
+            number_format.format(entry.new).bold(),
+            number_format.format(entry.baseline),
+            VerticalFormat::format_float(entry.pct, "%"),
+            entry.benchmark
+        )
+        .unwrap();
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use rstest::rstest;
+
+    use super::*;
+    use crate::runner::callgrind::model::Costs;
+
+    #[rstest]
+    #[case::plain(NumberFormat::Plain, "1234567890")]
+    #[case::comma(NumberFormat::Comma, "1,234,567,890")]
+    #[case::underscore(NumberFormat::Underscore, "1_234_567_890")]
+    #[case::si(NumberFormat::Si, "1.23G")]
+    fn test_number_format(#[case] number_format: NumberFormat, #[case] expected: &str) {
+        assert_eq!(number_format.format(1_234_567_890), expected);
+    }
+
+    #[rstest]
+    #[case::increase(105, 100, "1.05000x".bright_red().bold())]
+    #[case::decrease(50, 100, "0.50000x".bright_green().bold())]
+    #[case::to_zero(0, 100, "0.00000x".bright_green().bold())]
+    #[case::large(123_456, 1, "123456x".bright_red().bold())]
+    #[case::equal(10, 10, "1.00000x".bright_black())]
+    #[case::old_is_zero(10, 0, "∞".bright_red().bold())]
+    fn test_format_ratio(#[case] new: u64, #[case] old: u64, #[case] expected: ColoredString) {
+        assert_eq!(format_ratio(new, old), expected);
+    }
+
+    #[rstest]
+    #[case::percentage(DiffFormat::Percentage)]
+    #[case::ratio(DiffFormat::Ratio)]
+    #[case::both(DiffFormat::Both)]
+    fn test_format_vertical_when_equal(#[case] diff_format: DiffFormat) {
+        let costs = Costs::with_event_kinds([(EventKind::Ir, 10)]);
+        let costs_summary = CostsSummary::new(&costs, Some(&costs));
+
+        assert_eq!(
+            format_vertical(
+                (None, None),
+                costs_summary.all_diffs(),
+                NumberFormat::Plain,
+                diff_format
+            )
+            .unwrap(),
+            format!(
+                "  {:<18}{:>15}|{:<15} ({:^9})\n",
+                format!("{}:", EventKind::Ir),
+                "10".bold(),
+                "10",
+                "No change".bright_black()
+            )
+        );
+    }
+
+    #[rstest]
+    #[case::percentage(
+        DiffFormat::Percentage,
+        format!(
+            "({:^9}) [{:^9}]",
+            VerticalFormat::format_float(f64::INFINITY, "%"),
+            VerticalFormat::format_float(f64::INFINITY, "x")
+        )
+    )]
+    #[case::ratio(DiffFormat::Ratio, format!("({:^9})", "∞".bright_red().bold()))]
+    #[case::both(
+        DiffFormat::Both,
+        format!(
+            "({:^9}) [{:^9}]",
+            VerticalFormat::format_float(f64::INFINITY, "%"),
+            "∞".bright_red().bold()
+        )
+    )]
+    fn test_format_vertical_when_old_is_zero(
+        #[case] diff_format: DiffFormat,
+        #[case] expected_diff: String,
+    ) {
+        let new_costs = Costs::with_event_kinds([(EventKind::Ir, 10)]);
+        let old_costs = Costs::with_event_kinds([(EventKind::Ir, 0)]);
+        let costs_summary = CostsSummary::new(&new_costs, Some(&old_costs));
+
+        assert_eq!(
+            format_vertical(
+                (None, None),
+                costs_summary.all_diffs(),
+                NumberFormat::Plain,
+                diff_format
+            )
+            .unwrap(),
+            format!(
+                "  {:<18}{:>15}|{:<15} {expected_diff}\n",
+                format!("{}:", EventKind::Ir),
+                "10".bold(),
+                "0"
+            )
+        );
+    }
+
+    #[test]
+    fn test_format_vertical_when_no_old_then_new() {
+        let costs = Costs::with_event_kinds([(EventKind::Ir, 10)]);
+        let costs_summary = CostsSummary::new(&costs, None);
+
+        assert_eq!(
+            format_vertical(
+                (None, Some("foo".to_owned())),
+                costs_summary.all_diffs(),
+                NumberFormat::Plain,
+                DiffFormat::Percentage
+            )
+            .unwrap(),
+            format!(
+                "  {:<33}|foo\n  {:<18}{:>15}|{:<15} ({:^9})\n{}\n",
+                "Baselines:",
+                format!("{}:", EventKind::Ir),
+                "10".bold(),
+                NOT_AVAILABLE,
+                "*********".bright_black(),
+                format_new()
+            )
+        );
+    }
+
+    #[test]
+    fn test_format_vertical_when_some_old_then_not_new() {
+        let new_costs = Costs::with_event_kinds([(EventKind::Ir, 10), (EventKind::Dr, 5)]);
+        let old_costs = Costs::with_event_kinds([(EventKind::Ir, 10)]);
+        let costs_summary = CostsSummary::new(&new_costs, Some(&old_costs));
+
+        let formatted = format_vertical(
+            (None, None),
+            costs_summary.all_diffs(),
+            NumberFormat::Plain,
+            DiffFormat::Percentage,
+        )
+        .unwrap();
+        assert!(!formatted.contains(&format_new()));
+    }
+
+    #[test]
+    fn test_format_vertical_when_ratio() {
+        let new_costs = Costs::with_event_kinds([(EventKind::Ir, 21), (EventKind::Dr, 5)]);
+        let old_costs = Costs::with_event_kinds([(EventKind::Ir, 20), (EventKind::Dr, 10)]);
+        let costs_summary = CostsSummary::new(&new_costs, Some(&old_costs));
+
+        assert_eq!(
+            format_vertical(
+                (None, None),
+                costs_summary.all_diffs(),
+                NumberFormat::Plain,
+                DiffFormat::Ratio
+            )
+            .unwrap(),
+            format!(
+                "  {:<18}{:>15}|{:<15} ({:^9})\n  {:<18}{:>15}|{:<15} ({:^9})\n",
+                format!("{}:", EventKind::Ir),
+                "21".bold(),
+                "20",
+                "1.05000x".bright_red().bold(),
+                format!("{}:", EventKind::Dr),
+                "5".bold(),
+                "10",
+                "0.50000x".bright_green().bold(),
+            )
+        );
+    }
+
+    #[rstest]
+    #[case::no_regressions(2, 0, 0, "2 benchmarks, ", "0 regressions", false)]
+    #[case::one_regression(2, 0, 1, "2 benchmarks, ", "1 regression", true)]
+    #[case::singular(1, 0, 1, "1 benchmark, ", "1 regression", true)]
+    #[case::plural(3, 0, 2, "3 benchmarks, ", "2 regressions", true)]
+    #[case::new(3, 2, 0, "3 benchmarks, 2 new, ", "0 regressions", false)]
+    #[case::new_and_regressed(3, 1, 1, "3 benchmarks, 1 new, ", "1 regression", true)]
+    fn test_format_quiet_status(
+        #[case] total: usize,
+        #[case] new: usize,
+        #[case] regressed: usize,
+        #[case] expected_benchmarks: &str,
+        #[case] expected_regressions: &str,
+        #[case] is_red: bool,
+    ) {
+        let expected_regressions = if is_red {
+            expected_regressions.red().bold()
+        } else {
+            expected_regressions.green().bold()
+        };
+        assert_eq!(
+            format_quiet_status(total, new, regressed),
+            format!("{expected_benchmarks}{expected_regressions}")
+        );
+    }
+
+    #[rstest]
+    #[case::empty(&[], "## Top regressions\n\nNo regressions\n")]
+    #[case::entries(
+        &[("bench::a", EventKind::Ir, 1000, 2000), ("bench::b id", EventKind::Dr, 10, 11)],
+        "## Top regressions\n\n| Rank | Event | New | Old | Diff | Benchmark |\n| ---: | :--- | \
+         ---: | ---: | ---: | :--- |\n| 1 | Instructions | 2,000 | 1,000 | +100.000% | `bench::a` \
+         |\n| 2 | Dr | 11 | 10 | +10.0000% | `bench::b id` |\n"
+    )]
+    fn test_format_top_regressions_markdown(
+        #[case] entries: &[(&str, EventKind, u64, u64)],
+        #[case] expected: &str,
+    ) {
+        let entries = entries
+            .iter()
+            .map(|(benchmark, event, baseline, new)| RegressionsReportEntry {
+                benchmark: (*benchmark).to_owned(),
+                event: *event,
+                baseline: *baseline,
+                new: *new,
+                pct: crate::util::percentage_diff(*new, *baseline),
+            })
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            format_top_regressions_markdown(&entries, NumberFormat::Comma),
+            expected
+        );
+    }
+
+    #[test]
+    fn test_format_top_regressions() {
+        let entry = |benchmark: &str, event, baseline, new| RegressionsReportEntry {
+            benchmark: benchmark.to_owned(),
+            event,
+            baseline,
+            new,
+            pct: crate::util::percentage_diff(new, baseline),
+        };
+        let entries = [
+            entry("bench::a", EventKind::Ir, 1000, 2000),
+            entry("bench::b id", EventKind::Dr, 10, 11),
+        ];
+
+        assert_eq!(
+            format_top_regressions(&entries, NumberFormat::Comma),
+            format!(
+                "{}\n  {:>3} {:<18}{:>15}|{:<15} ({:^9}) bench::a\n  {:>3} {:<18}{:>15}|{:<15} \
+                 ({:^9}) bench::b id\n",
+                "Top regressions:".bold(),
+                "1.",
+                "Instructions:",
+                "2,000".bold(),
+                "1,000",
+                "+100.000%".bright_red().bold(),
+                "2.",
+                "Dr:",
+                "11".bold(),
+                "10",
+                "+10.0000%".bright_red().bold(),
+            )
+        );
+    }
+
+    #[test]
+    fn test_format_top_regressions_when_empty() {
+        assert_eq!(
+            format_top_regressions(&[], NumberFormat::Plain),
+            format!(
+                "{}\n  {}\n",
+                "Top regressions:".bold(),
+                "No regressions".bright_black()
+            )
+        );
+    }
+
+    #[rstest]
+    #[case::none(0, 0, "Flamegraphs created for 0 benchmarks, skipped for 0 benchmarks")]
+    #[case::singular(1, 1, "Flamegraphs created for 1 benchmark, skipped for 1 benchmark")]
+    #[case::plural(2, 3, "Flamegraphs created for 2 benchmarks, skipped for 3 benchmarks")]
+    fn test_format_flamegraph_status(
+        #[case] created: usize,
+        #[case] skipped: usize,
+        #[case] expected: &str,
+    ) {
+        assert_eq!(format_flamegraph_status(created, skipped), expected);
+    }
+
+    #[rstest]
+    #[case::no_change(10, Some(10), 0f64, false)]
+    #[case::increase(11, Some(10), 0f64, true)]
+    #[case::decrease(9, Some(10), 0f64, true)]
+    #[case::within_tolerance(11, Some(10), 10f64, false)]
+    #[case::beyond_tolerance(12, Some(10), 10f64, true)]
+    #[case::negative_within_tolerance(9, Some(10), 10f64, false)]
+    #[case::from_zero(1, Some(0), 10f64, true)]
+    #[case::no_old(10, None, 0f64, true)]
+    fn test_vertical_format_is_changed(
+        #[case] new: u64,
+        #[case] old: Option<u64>,
+        #[case] tolerance: f64,
+        #[case] expected: bool,
+    ) {
+        let new_costs = Costs::with_event_kinds([(EventKind::Ir, new)]);
+        let old_costs = old.map(|old| Costs::with_event_kinds([(EventKind::Ir, old)]));
+        let costs_summary = CostsSummary::new(&new_costs, old_costs.as_ref());
+
+        assert_eq!(
+            VerticalFormat::default().is_changed(&costs_summary, tolerance),
+            expected
+        );
+    }
+
+    #[rstest]
+    #[case::no_bars(None, None, 10, Some(10), true)]
+    #[case::below_min_cost(Some(100), None, 10, Some(11), false)]
+    #[case::new_at_min_cost(Some(100), None, 100, Some(10), true)]
+    #[case::old_at_min_cost(Some(100), None, 10, Some(100), true)]
+    #[case::below_min_diff_pct(None, Some(10f64), 105, Some(100), false)]
+    #[case::at_min_diff_pct(None, Some(10f64), 110, Some(100), true)]
+    #[case::negative_diff_pct(None, Some(10f64), 80, Some(100), true)]
+    #[case::no_change(None, Some(0f64), 100, Some(100), false)]
+    #[case::no_old(None, Some(10f64), 100, None, true)]
+    #[case::no_old_below_min_cost(Some(1000), None, 100, None, false)]
+    #[case::only_min_cost_crossed(Some(100), Some(10f64), 105, Some(100), true)]
+    #[case::only_min_diff_pct_crossed(Some(1000), Some(10f64), 200, Some(100), true)]
+    #[case::none_crossed(Some(1000), Some(10f64), 105, Some(100), false)]
+    fn test_event_filter_is_shown(
+        #[case] min_cost: Option<u64>,
+        #[case] min_diff_pct: Option<f64>,
+        #[case] new: u64,
+        #[case] old: Option<u64>,
+        #[case] expected: bool,
+    ) {
+        let new_costs = Costs::with_event_kinds([(EventKind::Ir, new)]);
+        let old_costs = old.map(|old| Costs::with_event_kinds([(EventKind::Ir, old)]));
+        let costs_summary = CostsSummary::new(&new_costs, old_costs.as_ref());
+        let filter = EventFilter {
+            min_cost,
+            min_diff_pct,
+        };
+
+        assert_eq!(
+            filter.is_shown(costs_summary.diff_by_kind(&EventKind::Ir).unwrap()),
+            expected
+        );
+    }
+
+    #[test]
+    fn test_vertical_format_with_event_filter() {
+        let new_costs = Costs::with_event_kinds([(EventKind::Ir, 110), (EventKind::Dr, 101)]);
+        let old_costs = Costs::with_event_kinds([(EventKind::Ir, 100), (EventKind::Dr, 100)]);
+        let costs_summary = CostsSummary::new(&new_costs, Some(&old_costs));
+        let filter = EventFilter {
+            min_cost: None,
+            min_diff_pct: Some(5f64),
+        };
+        let format = VerticalFormat {
+            event_kinds: vec![EventKind::Ir, EventKind::Dr],
+        };
+
+        let filtered = format
+            .format(
+                (None, None),
+                &costs_summary,
+                NumberFormat::Plain,
+                DiffFormat::Percentage,
+                Some(&filter),
+            )
+            .unwrap();
+        let unfiltered = format
+            .format(
+                (None, None),
+                &costs_summary,
+                NumberFormat::Plain,
+                DiffFormat::Percentage,
+                None,
+            )
+            .unwrap();
+
+        assert!(filtered.contains("Instructions:"));
+        assert!(!filtered.contains("Dr:"));
+        assert!(unfiltered.contains("Instructions:"));
+        assert!(unfiltered.contains("Dr:"));
+    }
+
+    #[rstest]
+    #[case::only_module_path("bench::group::func", None, None, "bench::group::func")]
+    #[case::with_id("bench::group::func", Some("id"), None, "bench::group::func id")]
+    #[case::with_id_and_args(
+        "bench::group::func",
+        Some("id"),
+        Some("1, 2"),
+        "bench::group::func id:1, 2"
+    )]
+    #[case::long_args_are_not_truncated(
+        "func",
+        Some("id"),
+        Some("a very long description with more than thirty seven characters"),
+        "func id:a very long description with more than thirty seven characters"
+    )]
+    #[case::args_without_id("func", None, Some("1, 2"), "func")]
+    fn test_header_to_list_entry(
+        #[case] module_path: &str,
+        #[case] id: Option<&str>,
+        #[case] description: Option<&str>,
+        #[case] expected: &str,
+    ) {
+        let header = Header::new(
+            module_path,
+            id.map(ToOwned::to_owned),
+            description.map(ToOwned::to_owned),
+        );
+        assert_eq!(header.to_list_entry(), expected);
+    }
+}
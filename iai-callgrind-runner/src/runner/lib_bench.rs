@@ -5,27 +5,35 @@ use std::collections::HashMap;
 use std::ffi::OsString;
 use std::io::stderr;
 
-use anyhow::Result;
+use anyhow::{anyhow, Result};
 
 use super::callgrind::args::Args;
+use super::callgrind::event_baseline::{apply_event_baseline, EventBaseline};
 use super::callgrind::flamegraph::{
-    BaselineFlamegraphGenerator, Config as FlamegraphConfig, Flamegraph, FlamegraphGenerator,
-    LoadBaselineFlamegraphGenerator, SaveBaselineFlamegraphGenerator,
+    BaselineFlamegraphGenerator, Config as FlamegraphConfig, FlamegraphBuilder,
+    FlamegraphGenerator, LoadBaselineFlamegraphGenerator, SaveBaselineFlamegraphGenerator,
 };
+use super::callgrind::metadata_parser::MetadataParser;
+use super::callgrind::model::Costs;
 use super::callgrind::parser::Sentinel;
-use super::callgrind::sentinel_parser::SentinelParser;
-use super::callgrind::{CallgrindCommand, RegressionConfig};
-use super::format::{tool_headline, Header, OutputFormat, VerticalFormat};
+use super::callgrind::{
+    add_score, check_determinism, check_not_zero, CallgrindCommand, RegressionConfig,
+};
+use super::format::{
+    is_inline_output, print_deterministic, print_peak_rss, print_valgrind_time, tool_headline,
+    BenchmarkCounts, Header, VerticalFormat,
+};
 use super::meta::Metadata;
 use super::summary::{
     BaselineKind, BaselineName, BenchmarkKind, BenchmarkSummary, CallgrindRegressionSummary,
-    CallgrindSummary, CostsSummary, SummaryOutput,
+    CallgrindSummary, CostsSummary, RegressionsOutput, SummaryOutput, ToolSummary,
 };
 use super::tool::{
+    filter_flamegraph_config, resolve_flamegraph_config, resolve_regression_config, resolve_tool,
     Parser, RunOptions, ToolConfigs, ToolOutputPath, ToolOutputPathKind, ValgrindTool,
 };
-use super::{Config, Error};
-use crate::api::{self, LibraryBenchmark};
+use super::{Config, RunOutcome};
+use crate::api::{EventKind, LibraryBenchmark, LibraryBenchmarkConfig};
 
 /// Implements [`Benchmark`] to run a [`LibBench`] and compare against a earlier [`BenchmarkKind`]
 #[derive(Debug)]
@@ -56,11 +64,17 @@ struct LibBench {
     id: Option<String>,
     function: String,
     args: Option<String>,
+    /// The number of calls of the benchmark function without collecting events before the
+    /// measured call
+    warmup: usize,
     options: RunOptions,
     callgrind_args: Args,
     flamegraph_config: Option<FlamegraphConfig>,
+    /// True if the flamegraphs of this benchmark are skipped by the `--flamegraph-filter`
+    flamegraph_skipped: bool,
     regression_config: Option<RegressionConfig>,
     tools: ToolConfigs,
+    tool: ValgrindTool,
 }
 
 /// Implements [`Benchmark`] to load a [`LibBench`] baseline run and compare against another
@@ -86,6 +100,8 @@ struct Runner {
 #[derive(Debug)]
 struct SaveBaselineBenchmark {
     baseline: BaselineName,
+    /// The event kinds to save with `--save-baseline-events` or all if `None`
+    events: Option<Vec<EventKind>>,
 }
 
 /// This trait needs to be implemented to actually run a [`LibBench`]
@@ -103,9 +119,10 @@ impl Benchmark for BaselineBenchmark {
     fn output_path(&self, lib_bench: &LibBench, config: &Config, group: &Group) -> ToolOutputPath {
         ToolOutputPath::new(
             ToolOutputPathKind::Out,
-            ValgrindTool::Callgrind,
+            lib_bench.tool,
             &self.baseline_kind,
             &config.meta.target_dir,
+            &config.meta.baseline_dir,
             &group.module,
             &lib_bench.name(),
         )
@@ -118,28 +135,40 @@ impl Benchmark for BaselineBenchmark {
         }
     }
 
+    #[allow(clippy::too_many_lines)]
     fn run(
         &self,
         lib_bench: &LibBench,
         config: &Config,
         group: &Group,
     ) -> Result<BenchmarkSummary> {
-        let callgrind_command = CallgrindCommand::new(&config.meta);
+        let callgrind_command = CallgrindCommand::new(&config.meta, lib_bench.tool);
         let bench_args = lib_bench.bench_args(group);
 
         let sentinel = Sentinel::new("iai_callgrind::bench::");
         let out_path = self.output_path(lib_bench, config, group);
+        if !lib_bench.tool.has_costs() {
+            return lib_bench.run_tools_only(config, group, &out_path, || {
+                lib_bench.tools.shift(&out_path)?;
+                lib_bench.tools.run(
+                    &config.meta,
+                    &config.bench_bin,
+                    &bench_args,
+                    &lib_bench.options,
+                    &out_path,
+                    false,
+                )
+            });
+        }
         out_path.init()?;
         out_path.shift()?;
 
         let old_path = out_path.to_base_path();
         let log_path = out_path.to_log_output();
         log_path.shift()?;
+        out_path.to_meta_output().shift()?;
 
-        for path in lib_bench.tools.output_paths(&out_path) {
-            path.shift()?;
-            path.to_log_output().shift()?;
-        }
+        lib_bench.tools.shift(&out_path)?;
 
         let mut benchmark_summary = lib_bench.create_benchmark_summary(config, group, &out_path)?;
 
@@ -153,50 +182,76 @@ impl Benchmark for BaselineBenchmark {
             &out_path,
         )?;
 
-        let new_costs = SentinelParser::new(&sentinel).parse(&out_path)?;
+        let new_costs = lib_bench.parse_costs(&sentinel, &out_path)?;
 
         #[allow(clippy::if_then_some_else_none)]
-        let old_costs = if old_path.exists() {
-            Some(SentinelParser::new(&sentinel).parse(&old_path)?)
+        let old_costs = if let Some(baseline_summaries) = &config.meta.baseline_summaries {
+            baseline_summaries
+                .costs(
+                    &benchmark_summary.module_path,
+                    benchmark_summary.id.as_deref(),
+                )
+                .cloned()
+        } else if old_path.exists() {
+            MetadataParser.check(&config.meta, &out_path, &old_path)?;
+            Some(lib_bench.parse_baseline_costs(&sentinel, &old_path)?)
         } else {
             None
         };
 
         let costs_summary = CostsSummary::new(&new_costs, old_costs.as_ref());
         VerticalFormat::default().print(&config.meta, self.baselines(), &costs_summary)?;
+        print_peak_rss(&config.meta, output.peak_rss);
+        print_valgrind_time(&config.meta, output.wall_time);
+
+        if config.meta.args.fail_on_zero.unwrap_or_default() {
+            check_not_zero(&costs_summary, &out_path)?;
+        }
+
+        let deterministic =
+            lib_bench.check_determinism(config, group, &sentinel, &out_path, &new_costs)?;
+        print_deterministic(&config.meta, deterministic);
 
         output.dump_log(log::Level::Info);
         log_path.dump_log(log::Level::Info, &mut stderr())?;
 
-        let regressions = lib_bench.check_and_print_regressions(&costs_summary);
+        let regressions = lib_bench.check_and_print_regressions(&costs_summary, &out_path);
 
         let callgrind_summary = benchmark_summary
             .callgrind_summary
             .insert(CallgrindSummary::new(
+                out_path.tool,
                 log_path.real_paths()?,
                 out_path.real_paths()?,
             ));
 
-        callgrind_summary.add_summary(
-            &config.bench_bin,
-            &bench_args,
-            &old_path,
-            costs_summary,
-            regressions,
-        );
+        callgrind_summary
+            .add_summary(
+                &config.bench_bin,
+                &bench_args,
+                &old_path,
+                costs_summary,
+                regressions,
+                Some(&output),
+            )
+            .deterministic = deterministic;
 
         if let Some(flamegraph_config) = lib_bench.flamegraph_config.clone() {
             callgrind_summary.flamegraphs = BaselineFlamegraphGenerator {
                 baseline_kind: self.baseline_kind.clone(),
             }
             .create(
-                &Flamegraph::new(header.to_title(), flamegraph_config),
+                &FlamegraphBuilder::with_config(header.to_title(), flamegraph_config).build(),
                 &out_path,
                 Some(&sentinel),
                 &config.meta.project_root,
             )?;
         }
 
+        if config.meta.args.auto_save_baseline.unwrap_or_default() && !old_path.exists() {
+            out_path.save_as_baseline()?;
+        }
+
         benchmark_summary.tool_summaries = lib_bench.tools.run(
             &config.meta,
             &config.bench_bin,
@@ -212,6 +267,7 @@ impl Benchmark for BaselineBenchmark {
 
 impl Groups {
     /// Create this `Groups` from a [`LibraryBenchmark`] submitted by the benchmarking harness
+    #[allow(clippy::too_many_lines)]
     fn from_library_benchmark(
         module: &str,
         benchmark: LibraryBenchmark,
@@ -247,15 +303,57 @@ impl Groups {
                         library_benchmark_bench.config.as_ref(),
                     ]);
                     let envs = config.resolve_envs();
-                    let callgrind_args =
+                    let mut callgrind_args =
                         Args::from_raw_args(&[&config.raw_callgrind_args, &meta_callgrind_args])?;
-                    let flamegraph_config = config.flamegraph_config.map(Into::into);
-                    let lib_bench = LibBench {
+                    let warmup = config.warmup.unwrap_or_default();
+                    if warmup > 0 {
+                        callgrind_args.insert_zero_before("iai_callgrind::measure");
+                    }
+                    // The configuration of the benchmark itself without the inherited
+                    // configuration of the group and the `main!` macro
+                    let bench_config = LibraryBenchmarkConfig::default().update_from_all([
+                        library_benchmark_benches.config.as_ref(),
+                        library_benchmark_bench.config.as_ref(),
+                    ]);
+                    let tool = resolve_tool(
+                        config.default_tool,
+                        config.cachegrind,
+                        bench_config.default_tool,
+                        bench_config.cachegrind,
+                    );
+                    let bench_path =
+                        format!("{}::{}", &group.module, &library_benchmark_bench.bench);
+                    let add_context = |error: anyhow::Error| anyhow!("{bench_path}: {error}");
+                    let flamegraph_config = resolve_flamegraph_config(
+                        tool,
+                        config.flamegraph_config.map(|mut flamegraph_config| {
+                            // The flamegraphs of library benchmarks start with the benchmark
+                            // function by default
+                            flamegraph_config.trim_harness.get_or_insert(true);
+                            flamegraph_config
+                        }),
+                        bench_config.flamegraph_config.is_none(),
+                        meta.primary_event_kind(),
+                        &bench_path,
+                    )
+                    .map_err(add_context)?;
+                    let regression_config = resolve_regression_config(
+                        tool,
+                        &config.regression_config,
+                        &meta.regression_config,
+                        bench_config.regression_config.is_none(),
+                        meta.primary_event_kind(),
+                        &bench_path,
+                    )
+                    .map_err(add_context)?;
+                    let tools = ToolConfigs::new(config.tools, tool).map_err(add_context)?;
+                    let mut lib_bench = LibBench {
                         bench_index,
                         index,
                         id: library_benchmark_bench.id,
                         function: library_benchmark_bench.bench,
                         args: library_benchmark_bench.args,
+                        warmup,
                         options: RunOptions {
                             env_clear: config.env_clear.unwrap_or(true),
                             entry_point: Some("iai_callgrind::bench::*".to_owned()),
@@ -264,13 +362,36 @@ impl Groups {
                         },
                         callgrind_args,
                         flamegraph_config,
-                        regression_config: api::update_option(
-                            &config.regression_config,
-                            &meta.regression_config,
-                        )
-                        .map(Into::into),
-                        tools: ToolConfigs(config.tools.0.into_iter().map(Into::into).collect()),
+                        flamegraph_skipped: false,
+                        regression_config,
+                        tools,
+                        tool,
                     };
+                    match tool {
+                        ValgrindTool::Callgrind => lib_bench.callgrind_args.check_collectible(
+                            lib_bench.regression_config.as_ref(),
+                            lib_bench.flamegraph_config.as_ref(),
+                        ),
+                        ValgrindTool::Cachegrind => lib_bench
+                            .callgrind_args
+                            .check_cachegrind_collectible(lib_bench.regression_config.as_ref()),
+                        _ => Ok(()),
+                    }
+                    .map_err(add_context)?;
+                    lib_bench
+                        .callgrind_args
+                        .check_primary_collectible(
+                            tool,
+                            &meta.primary_event_kind(),
+                            lib_bench.regression_config.as_ref(),
+                        )
+                        .map_err(add_context)?;
+                    let name = lib_bench.to_header(&group).to_list_entry();
+                    lib_bench.flamegraph_skipped = filter_flamegraph_config(
+                        &mut lib_bench.flamegraph_config,
+                        meta.args.flamegraph_filter.as_ref(),
+                        &name,
+                    );
                     group.benches.push(lib_bench);
                 }
             }
@@ -281,12 +402,26 @@ impl Groups {
         Ok(Self(groups))
     }
 
+    /// Print the names of all [`LibBench`] benchmarks without running them
+    fn list(&self) {
+        for group in &self.0 {
+            for bench in &group.benches {
+                println!("{}", bench.to_header(group).to_list_entry());
+            }
+        }
+    }
+
     /// Run all [`LibBench`] benchmarks
-    fn run(&self, benchmark: &dyn Benchmark, config: &Config) -> Result<()> {
-        let mut is_regressed = false;
+    fn run(&self, benchmark: &dyn Benchmark, config: &Config) -> Result<RunOutcome> {
+        let mut outcome = RunOutcome::default();
+        let mut counts = BenchmarkCounts::default();
+        let mut regressions =
+            RegressionsOutput::new(&config.meta.target_dir.join(&config.meta.bench_name));
+        regressions.init()?;
 
         for group in &self.0 {
-            let mut summaries: HashMap<String, Vec<BenchmarkSummary>> =
+            // The indices of the summaries in the `outcome` with the same id
+            let mut summaries: HashMap<String, Vec<usize>> =
                 HashMap::with_capacity(group.benches.len());
             for bench in &group.benches {
                 let fail_fast = bench
@@ -294,29 +429,33 @@ impl Groups {
                     .as_ref()
                     .map_or(false, |r| r.fail_fast);
                 let summary = benchmark.run(bench, config, group)?;
+                counts.print_and_count(&config.meta, &summary, &benchmark.baselines())?;
+                counts.count_flamegraphs(&summary, bench.flamegraph_skipped);
                 summary.print_and_save(&config.meta.args.output_format)?;
-                summary.check_regression(&mut is_regressed, fail_fast)?;
+                regressions.add(&summary)?;
+                summary.check_tool_errors()?;
 
-                if group.compare && config.meta.args.output_format == OutputFormat::Default {
+                if group.compare && is_inline_output(&config.meta) {
                     if let Some(id) = &summary.id {
-                        if let Some(sums) = summaries.get_mut(id) {
-                            for sum in sums.iter() {
+                        let index = outcome.summaries.len();
+                        if let Some(indices) = summaries.get_mut(id) {
+                            for sum in indices.iter().map(|i| &outcome.summaries[*i]) {
                                 sum.compare_and_print(id, &config.meta, &summary)?;
                             }
-                            sums.push(summary);
+                            indices.push(index);
                         } else {
-                            summaries.insert(id.clone(), vec![summary]);
+                            summaries.insert(id.clone(), vec![index]);
                         }
                     }
                 }
+
+                outcome.add(summary, fail_fast)?;
             }
         }
 
-        if is_regressed {
-            Err(Error::RegressionError(false).into())
-        } else {
-            Ok(())
-        }
+        counts.print(&config.meta);
+
+        Ok(outcome)
     }
 }
 
@@ -344,17 +483,78 @@ impl LibBench {
                 OsString::from(group_id),
                 OsString::from(self.bench_index.to_string()),
                 OsString::from(self.index.to_string()),
+                OsString::from(self.warmup.to_string()),
                 OsString::from(format!("{}::{}", group.module, self.function)),
             ]
         } else {
             vec![
                 OsString::from("--iai-run".to_owned()),
                 OsString::from(self.index.to_string()),
+                OsString::from(self.warmup.to_string()),
                 OsString::from(format!("{}::{}", group.module, self.function)),
             ]
         }
     }
 
+    /// Parse the [`Costs`] of the benchmark function from the output of callgrind or cachegrind
+    ///
+    /// Cachegrind doesn't support the `sentinel` and the costs are the costs of the whole
+    /// benchmark run.
+    fn parse_costs(&self, sentinel: &Sentinel, output_path: &ToolOutputPath) -> Result<Costs> {
+        let mut costs = self
+            .tool
+            .to_costs_parser(Some(sentinel))?
+            .parse_costs(output_path)?;
+        add_score(self.regression_config.as_ref(), &mut costs)?;
+        Ok(costs)
+    }
+
+    /// Like [`LibBench::parse_costs`] but for the output of a baseline
+    ///
+    /// The costs of the event kinds pinned with `--save-baseline-events` replace the costs of the
+    /// output file.
+    fn parse_baseline_costs(
+        &self,
+        sentinel: &Sentinel,
+        output_path: &ToolOutputPath,
+    ) -> Result<Costs> {
+        let mut costs = self.parse_costs(sentinel, output_path)?;
+        apply_event_baseline(output_path, self.regression_config.as_ref(), &mut costs)?;
+        Ok(costs)
+    }
+
+    /// Run the benchmark a second time and compare the primary event kind if
+    /// `--check-determinism` is set
+    ///
+    /// The `setup` function of a library benchmark runs in the same process as the benchmark
+    /// function, so the second run runs it again, too. Returns `None` if the determinism check is
+    /// disabled.
+    fn check_determinism(
+        &self,
+        config: &Config,
+        group: &Group,
+        sentinel: &Sentinel,
+        output_path: &ToolOutputPath,
+        costs: &Costs,
+    ) -> Result<Option<bool>> {
+        if !config.meta.args.check_determinism.unwrap_or_default() {
+            return Ok(None);
+        }
+
+        let event_kind = config.meta.primary_event_kind();
+        check_determinism(output_path, costs, &event_kind, |path| {
+            CallgrindCommand::new(&config.meta, self.tool).run(
+                self.callgrind_args.clone(),
+                &config.bench_bin,
+                &self.bench_args(group),
+                self.options.clone(),
+                path,
+            )?;
+            self.parse_costs(sentinel, path)
+        })
+        .map(Some)
+    }
+
     /// This method creates the initial [`BenchmarkSummary`]
     fn create_benchmark_summary(
         &self,
@@ -383,34 +583,62 @@ impl LibBench {
         ))
     }
 
+    /// The [`Header`] identifying this benchmark in the terminal output
+    fn to_header(&self, group: &Group) -> Header {
+        Header::from_segments(
+            [&group.module, &self.function],
+            self.id.clone(),
+            self.args.clone(),
+        )
+    }
+
     /// Print the headline of the terminal output for this benchmark run
     ///
     /// If there are more tools than the usual callgrind run, this method also prints the tool
     /// summary header
     fn print_header(&self, meta: &Metadata, group: &Group) -> Header {
-        let header = Header::from_segments(
-            [&group.module, &self.function],
-            self.id.clone(),
-            self.args.clone(),
-        );
+        let header = self.to_header(group);
 
-        if meta.args.output_format == OutputFormat::Default {
+        if is_inline_output(meta) {
             header.print();
-            if self.tools.has_tools_enabled() {
-                println!("{}", tool_headline(ValgrindTool::Callgrind));
+            if self.tool.has_costs() && self.tools.has_tools_enabled() {
+                println!("{}", tool_headline(self.tool));
             }
         }
         header
     }
 
+    /// Run only the [`ToolConfigs`] of this benchmark if the default tool doesn't report costs
+    ///
+    /// Without callgrind or cachegrind, there are no costs, regressions or flamegraphs. The
+    /// `run_tools` function runs the tools or loads their output depending on the [`Benchmark`].
+    fn run_tools_only<F>(
+        &self,
+        config: &Config,
+        group: &Group,
+        out_path: &ToolOutputPath,
+        run_tools: F,
+    ) -> Result<BenchmarkSummary>
+    where
+        F: FnOnce() -> Result<Vec<ToolSummary>>,
+    {
+        out_path.init()?;
+        let mut benchmark_summary = self.create_benchmark_summary(config, group, out_path)?;
+        self.print_header(&config.meta, group);
+        benchmark_summary.tool_summaries = run_tools()?;
+
+        Ok(benchmark_summary)
+    }
+
     /// Check for regressions as defined in [`RegressionConfig`] and print an error if a regression
     /// occurred
     fn check_and_print_regressions(
         &self,
         costs_summary: &CostsSummary,
+        out_path: &ToolOutputPath,
     ) -> Vec<CallgrindRegressionSummary> {
         if let Some(regression_config) = &self.regression_config {
-            regression_config.check_and_print(costs_summary)
+            regression_config.check_and_print(costs_summary, out_path)
         } else {
             vec![]
         }
@@ -421,9 +649,10 @@ impl Benchmark for LoadBaselineBenchmark {
     fn output_path(&self, lib_bench: &LibBench, config: &Config, group: &Group) -> ToolOutputPath {
         ToolOutputPath::new(
             ToolOutputPathKind::Base(self.loaded_baseline.to_string()),
-            ValgrindTool::Callgrind,
+            lib_bench.tool,
             &BaselineKind::Name(self.baseline.clone()),
             &config.meta.target_dir,
+            &config.meta.baseline_dir,
             &group.module,
             &lib_bench.name(),
         )
@@ -445,23 +674,30 @@ impl Benchmark for LoadBaselineBenchmark {
         let bench_args = lib_bench.bench_args(group);
         let sentinel = Sentinel::new("iai_callgrind::bench::");
         let out_path = self.output_path(lib_bench, config, group);
+        if !lib_bench.tool.has_costs() {
+            return lib_bench.run_tools_only(config, group, &out_path, || {
+                lib_bench.tools.run_loaded_vs_base(&config.meta, &out_path)
+            });
+        }
         let old_path = out_path.to_base_path();
         let log_path = out_path.to_log_output();
         let mut benchmark_summary = lib_bench.create_benchmark_summary(config, group, &out_path)?;
 
         let header = lib_bench.print_header(&config.meta, group);
 
-        let new_costs = SentinelParser::new(&sentinel).parse(&out_path)?;
-        let old_costs = Some(SentinelParser::new(&sentinel).parse(&old_path)?);
+        MetadataParser.check(&config.meta, &out_path, &old_path)?;
+        let new_costs = lib_bench.parse_baseline_costs(&sentinel, &out_path)?;
+        let old_costs = Some(lib_bench.parse_baseline_costs(&sentinel, &old_path)?);
         let costs_summary = CostsSummary::new(&new_costs, old_costs.as_ref());
 
         VerticalFormat::default().print(&config.meta, self.baselines(), &costs_summary)?;
 
-        let regressions = lib_bench.check_and_print_regressions(&costs_summary);
+        let regressions = lib_bench.check_and_print_regressions(&costs_summary, &out_path);
 
         let callgrind_summary = benchmark_summary
             .callgrind_summary
             .insert(CallgrindSummary::new(
+                out_path.tool,
                 log_path.real_paths()?,
                 out_path.real_paths()?,
             ));
@@ -472,6 +708,7 @@ impl Benchmark for LoadBaselineBenchmark {
             &old_path,
             costs_summary,
             regressions,
+            None,
         );
 
         if let Some(flamegraph_config) = lib_bench.flamegraph_config.clone() {
@@ -480,7 +717,7 @@ impl Benchmark for LoadBaselineBenchmark {
                 baseline: self.baseline.clone(),
             }
             .create(
-                &Flamegraph::new(header.to_title(), flamegraph_config),
+                &FlamegraphBuilder::with_config(header.to_title(), flamegraph_config).build(),
                 &out_path,
                 Some(&sentinel),
                 &config.meta.project_root,
@@ -505,6 +742,7 @@ impl Runner {
             if let Some(baseline_name) = &config.meta.args.save_baseline {
                 Box::new(SaveBaselineBenchmark {
                     baseline: baseline_name.clone(),
+                    events: config.meta.args.save_baseline_events.clone(),
                 })
             } else if let Some(baseline_name) = &config.meta.args.load_baseline {
                 Box::new(LoadBaselineBenchmark {
@@ -535,9 +773,14 @@ impl Runner {
         })
     }
 
-    /// Run all benchmarks in all groups
-    fn run(&self) -> Result<()> {
-        self.groups.run(self.benchmark.as_ref(), &self.config)
+    /// Run all benchmarks in all groups or only list them with `--list`
+    fn run(&self) -> Result<RunOutcome> {
+        if self.config.meta.args.list.unwrap_or_default() {
+            self.groups.list();
+            Ok(RunOutcome::default())
+        } else {
+            self.groups.run(self.benchmark.as_ref(), &self.config)
+        }
     }
 }
 
@@ -545,9 +788,10 @@ impl Benchmark for SaveBaselineBenchmark {
     fn output_path(&self, lib_bench: &LibBench, config: &Config, group: &Group) -> ToolOutputPath {
         ToolOutputPath::new(
             ToolOutputPathKind::Base(self.baseline.to_string()),
-            ValgrindTool::Callgrind,
+            lib_bench.tool,
             &BaselineKind::Name(self.baseline.clone()),
             &config.meta.target_dir,
+            &config.meta.baseline_dir,
             &group.module,
             &lib_bench.name(),
         )
@@ -560,28 +804,47 @@ impl Benchmark for SaveBaselineBenchmark {
         )
     }
 
+    #[allow(clippy::too_many_lines)]
     fn run(
         &self,
         lib_bench: &LibBench,
         config: &Config,
         group: &Group,
     ) -> Result<BenchmarkSummary> {
-        let callgrind_command = CallgrindCommand::new(&config.meta);
+        let callgrind_command = CallgrindCommand::new(&config.meta, lib_bench.tool);
         let bench_args = lib_bench.bench_args(group);
         let baselines = self.baselines();
 
         let sentinel = Sentinel::new("iai_callgrind::bench::");
         let out_path = self.output_path(lib_bench, config, group);
+        if !lib_bench.tool.has_costs() {
+            return lib_bench.run_tools_only(config, group, &out_path, || {
+                lib_bench.tools.run(
+                    &config.meta,
+                    &config.bench_bin,
+                    &bench_args,
+                    &lib_bench.options,
+                    &out_path,
+                    true,
+                )
+            });
+        }
         out_path.init()?;
 
         #[allow(clippy::if_then_some_else_none)]
-        let old_costs = if out_path.exists() {
-            let old_costs = SentinelParser::new(&sentinel).parse(&out_path)?;
+        let old = if out_path.exists() {
+            let old_metadata = MetadataParser.parse(&out_path)?;
+            let old_costs = lib_bench.parse_baseline_costs(&sentinel, &out_path)?;
             out_path.clear()?;
-            Some(old_costs)
+            Some((old_metadata, old_costs))
         } else {
             None
         };
+        EventBaseline::update(
+            &out_path,
+            old.as_ref().map(|(_, costs)| costs),
+            self.events.as_deref(),
+        )?;
 
         let log_path = out_path.to_log_output();
         log_path.clear()?;
@@ -598,36 +861,57 @@ impl Benchmark for SaveBaselineBenchmark {
             &out_path,
         )?;
 
-        let new_costs = SentinelParser::new(&sentinel).parse(&out_path)?;
-        let costs_summary = CostsSummary::new(&new_costs, old_costs.as_ref());
+        if let Some((old_metadata, _)) = &old {
+            MetadataParser.parse(&out_path)?.check_mismatch(
+                old_metadata,
+                &out_path,
+                &config.meta,
+            )?;
+        }
+        let new_costs = lib_bench.parse_costs(&sentinel, &out_path)?;
+        let costs_summary = CostsSummary::new(&new_costs, old.as_ref().map(|(_, costs)| costs));
         VerticalFormat::default().print(&config.meta, baselines.clone(), &costs_summary)?;
+        print_peak_rss(&config.meta, output.peak_rss);
+        print_valgrind_time(&config.meta, output.wall_time);
+
+        if config.meta.args.fail_on_zero.unwrap_or_default() {
+            check_not_zero(&costs_summary, &out_path)?;
+        }
+
+        let deterministic =
+            lib_bench.check_determinism(config, group, &sentinel, &out_path, &new_costs)?;
+        print_deterministic(&config.meta, deterministic);
 
         output.dump_log(log::Level::Info);
         log_path.dump_log(log::Level::Info, &mut stderr())?;
 
-        let regressions = lib_bench.check_and_print_regressions(&costs_summary);
+        let regressions = lib_bench.check_and_print_regressions(&costs_summary, &out_path);
 
         let callgrind_summary = benchmark_summary
             .callgrind_summary
             .insert(CallgrindSummary::new(
+                out_path.tool,
                 log_path.real_paths()?,
                 out_path.real_paths()?,
             ));
 
-        callgrind_summary.add_summary(
-            &config.bench_bin,
-            &bench_args,
-            &out_path,
-            costs_summary,
-            regressions,
-        );
+        callgrind_summary
+            .add_summary(
+                &config.bench_bin,
+                &bench_args,
+                &out_path,
+                costs_summary,
+                regressions,
+                Some(&output),
+            )
+            .deterministic = deterministic;
 
         if let Some(flamegraph_config) = lib_bench.flamegraph_config.clone() {
             callgrind_summary.flamegraphs = SaveBaselineFlamegraphGenerator {
                 baseline: self.baseline.clone(),
             }
             .create(
-                &Flamegraph::new(header.to_title(), flamegraph_config),
+                &FlamegraphBuilder::with_config(header.to_title(), flamegraph_config).build(),
                 &out_path,
                 Some(&sentinel),
                 &config.meta.project_root,
@@ -648,6 +932,6 @@ impl Benchmark for SaveBaselineBenchmark {
 }
 
 /// The top-level method which should be used to initiate running all benchmarks
-pub fn run(library_benchmark: LibraryBenchmark, config: Config) -> Result<()> {
+pub fn run(library_benchmark: LibraryBenchmark, config: Config) -> Result<RunOutcome> {
     Runner::new(library_benchmark, config)?.run()
 }
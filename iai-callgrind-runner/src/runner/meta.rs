@@ -2,13 +2,15 @@ use std::ffi::OsString;
 use std::path::{Path, PathBuf};
 use std::process::Command;
 
-use anyhow::Result;
+use anyhow::{anyhow, Result};
 use clap::Parser;
-use log::debug;
+use log::{debug, warn};
 
+use super::affinity::CpuAffinity;
 use super::args::CommandLineArgs;
 use super::envs;
-use crate::api::RegressionConfig;
+use super::summary::BaselineSummaries;
+use crate::api::{EventKind, RegressionConfig};
 use crate::util::resolve_binary_path;
 
 #[derive(Debug, Clone)]
@@ -24,14 +26,21 @@ pub struct Metadata {
     pub arch: String,
     pub project_root: PathBuf,
     pub target_dir: PathBuf,
+    /// The directory of the baselines which is the `target_dir` unless `--baseline-dir` is given
+    pub baseline_dir: PathBuf,
     pub valgrind: Cmd,
     pub valgrind_wrapper: Option<Cmd>,
     pub regression_config: Option<RegressionConfig>,
+    /// The [`BaselineSummaries`] loaded from the file given with `--baseline-summary`
+    pub baseline_summaries: Option<BaselineSummaries>,
+    /// The [`CpuAffinity`] of the valgrind processes if given with `--cpu-affinity` and supported
+    pub cpu_affinity: Option<CpuAffinity>,
     pub args: CommandLineArgs,
     pub bench_name: String,
 }
 
 impl Metadata {
+    #[allow(clippy::too_many_lines)]
     pub fn new(
         raw_command_line_args: &[String],
         package_name: &str,
@@ -63,13 +72,31 @@ impl Metadata {
         let project_root = meta.workspace_root.into_std_path_buf();
         debug!("Detected project root: '{}'", project_root.display());
 
-        let target_dir = std::env::var_os(envs::CARGO_TARGET_DIR)
-            .map_or_else(|| meta.target_directory.into_std_path_buf(), PathBuf::from)
-            .join("iai")
-            .join(std::env::var_os(envs::CARGO_PKG_NAME).map_or_else(PathBuf::new, PathBuf::from));
+        let package_dir =
+            std::env::var_os(envs::CARGO_PKG_NAME).map_or_else(PathBuf::new, PathBuf::from);
+        let target_dir = resolve_target_dir(
+            std::env::var_os(envs::CARGO_TARGET_DIR),
+            meta.target_directory.into_std_path_buf(),
+            &std::env::current_dir()
+                .map_err(|error| anyhow!("Failed to detect the current directory: {error}"))?,
+        )
+        .join("iai")
+        .join(&package_dir);
 
         debug!("Detected target directory: '{}'", target_dir.display());
 
+        let baseline_dir = args.baseline_dir.as_ref().map_or_else(
+            || target_dir.clone(),
+            |dir| project_root.join(dir).join(&package_dir),
+        );
+        debug!("Using baseline directory: '{}'", baseline_dir.display());
+
+        let baseline_summaries = args
+            .baseline_summary
+            .as_ref()
+            .map(|path| load_baseline_summaries(path, &project_root))
+            .transpose()?;
+
         // Invoke Valgrind, disabling ASLR if possible because ASLR could noise up the results a bit
         let valgrind_path = resolve_binary_path("valgrind")?;
         let valgrind_wrapper = if args.allow_aslr.unwrap_or_default() {
@@ -120,6 +147,7 @@ impl Metadata {
         Ok(Self {
             arch,
             target_dir,
+            baseline_dir,
             valgrind: Cmd {
                 bin: valgrind_path,
                 args: vec![],
@@ -127,15 +155,87 @@ impl Metadata {
             valgrind_wrapper,
             project_root,
             regression_config: Into::<Option<RegressionConfig>>::into(&args),
+            baseline_summaries,
+            cpu_affinity: resolve_cpu_affinity(args.cpu_affinity.as_ref())?,
             args,
             bench_name,
         })
     }
+
+    /// The primary [`EventKind`] of `--primary-event` or [`EventKind::Ir`] if not given
+    pub fn primary_event_kind(&self) -> EventKind {
+        self.args.primary_event.unwrap_or(EventKind::Ir)
+    }
+}
+
+/// Resolve the cargo target directory from the `CARGO_TARGET_DIR` environment variable
+///
+/// Without the environment variable, the target directory as reported by `cargo metadata` is used.
+/// A relative `CARGO_TARGET_DIR` is resolved against the `current_dir` like cargo does, so the
+/// resulting path is absolute and stays the same no matter which directory it is used from later.
+fn resolve_target_dir(
+    env_target_dir: Option<OsString>,
+    cargo_target_dir: PathBuf,
+    current_dir: &Path,
+) -> PathBuf {
+    env_target_dir.map_or(cargo_target_dir, |target_dir| {
+        let target_dir = PathBuf::from(target_dir);
+        if target_dir.is_relative() {
+            current_dir.join(target_dir)
+        } else {
+            target_dir
+        }
+    })
+}
+
+/// Return the [`CpuAffinity`] given with `--cpu-affinity` if the platform supports it
+///
+/// On other platforms than Linux, the cpu affinity is ignored with a warning.
+fn resolve_cpu_affinity(cpu_affinity: Option<&CpuAffinity>) -> Result<Option<CpuAffinity>> {
+    let Some(cpu_affinity) = cpu_affinity else {
+        return Ok(None);
+    };
+
+    if CpuAffinity::is_supported() {
+        cpu_affinity.check_available()?;
+        debug!("Running valgrind with the cpu affinity '{cpu_affinity}'");
+        Ok(Some(cpu_affinity.clone()))
+    } else {
+        warn!(
+            "Setting the cpu affinity with --cpu-affinity is only supported on Linux. Running \
+             without cpu affinity"
+        );
+        Ok(None)
+    }
+}
+
+/// Load the [`BaselineSummaries`] from the `path` given with `--baseline-summary`
+///
+/// A relative `path` is resolved against the `project_root`. `-` is stdin and not a path.
+///
+/// # Errors
+///
+/// Stdin can be read only once, but `cargo bench` runs the runner once for each benchmark binary.
+/// An empty stdin is therefore an error, because all benchmarks would silently have no baseline.
+fn load_baseline_summaries(path: &Path, project_root: &Path) -> Result<BaselineSummaries> {
+    if path == Path::new("-") {
+        let summaries = BaselineSummaries::load(path)?;
+        if summaries.is_empty() {
+            return Err(anyhow!(
+                "No baseline summaries were read from stdin. Stdin can be read only by the first \
+                 benchmark binary, so use a file with '--baseline-summary=PATH' to run multiple \
+                 benchmark binaries"
+            ));
+        }
+        Ok(summaries)
+    } else {
+        BaselineSummaries::load(&project_root.join(path))
+    }
 }
 
 impl From<&Metadata> for Command {
     fn from(meta: &Metadata) -> Self {
-        meta.valgrind_wrapper.as_ref().map_or_else(
+        let mut command = meta.valgrind_wrapper.as_ref().map_or_else(
             || {
                 let meta_cmd = &meta.valgrind;
                 let mut cmd = Command::new(&meta_cmd.bin);
@@ -147,6 +247,53 @@ impl From<&Metadata> for Command {
                 cmd.args(&meta_cmd.args);
                 cmd
             },
-        )
+        );
+        if let Some(cpu_affinity) = &meta.cpu_affinity {
+            cpu_affinity.apply(&mut command);
+        }
+        command
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+    use rstest::rstest;
+
+    use super::*;
+
+    #[rstest]
+    #[case::no_env(None, "/workspace/target")]
+    #[case::absolute(Some("/other/target"), "/other/target")]
+    #[case::relative(Some("my-target"), "/workspace/my-target")]
+    #[case::relative_with_parent(Some("../my-target"), "/workspace/../my-target")]
+    fn test_resolve_target_dir(#[case] env_target_dir: Option<&str>, #[case] expected: &str) {
+        assert_eq!(
+            resolve_target_dir(
+                env_target_dir.map(OsString::from),
+                PathBuf::from("/workspace/target"),
+                Path::new("/workspace")
+            ),
+            PathBuf::from(expected)
+        );
+    }
+
+    #[rstest]
+    #[case::no_env(None, "/workspace/target")]
+    #[case::absolute(Some("/other/target"), "/other/target")]
+    #[case::relative(Some("my-target"), "/workspace/crates/my-package/my-target")]
+    #[case::relative_to_root(Some("../../target"), "/workspace/crates/my-package/../../target")]
+    fn test_resolve_target_dir_from_subdirectory(
+        #[case] env_target_dir: Option<&str>,
+        #[case] expected: &str,
+    ) {
+        assert_eq!(
+            resolve_target_dir(
+                env_target_dir.map(OsString::from),
+                PathBuf::from("/workspace/target"),
+                Path::new("/workspace/crates/my-package")
+            ),
+            PathBuf::from(expected)
+        );
     }
 }
@@ -1,22 +1,28 @@
+mod affinity;
 mod args;
 mod bin_bench;
+mod bundle;
+pub mod cachegrind;
 pub mod callgrind;
 pub mod costs;
 pub mod dhat;
 mod format;
 mod lib_bench;
 mod meta;
+mod report;
 pub mod summary;
 pub mod tool;
 
 use std::io::{stdin, Read};
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
 use anyhow::{Context, Result};
 use log::debug;
 
+use self::bundle::Bundle;
+use self::format::print_top_regressions;
 use self::meta::Metadata;
-use self::summary::BenchmarkKind;
+use self::summary::{BenchmarkKind, BenchmarkSummary, MergedSummaries};
 use crate::api::{BinaryBenchmark, LibraryBenchmark};
 use crate::error::Error;
 
@@ -29,6 +35,48 @@ pub mod envs {
     pub const CARGO_TERM_COLOR: &str = "CARGO_TERM_COLOR";
 }
 
+/// The benchmarks of a benchmark file as sent by the `main!` macro of iai-callgrind
+#[derive(Debug)]
+pub enum Benchmarks {
+    /// The library benchmarks of a benchmark file
+    Library(LibraryBenchmark),
+    /// The binary benchmarks of a benchmark file
+    Binary(BinaryBenchmark),
+}
+
+/// The input of [`run_and_compare`] describing the benchmark file to run
+#[derive(Debug)]
+pub struct RunInput {
+    /// The directory of the package of the benchmark file
+    pub package_dir: PathBuf,
+    /// The name of the package of the benchmark file
+    pub package_name: String,
+    /// The path to the benchmark file relative to the `package_dir`
+    pub bench_file: PathBuf,
+    /// The module path of the benchmark file
+    pub module: String,
+    /// The path to the compiled and executable benchmark file
+    pub bench_bin: PathBuf,
+    /// The benchmarks to run
+    pub benchmarks: Benchmarks,
+    /// The version of the iai-callgrind library which created the `benchmarks`
+    ///
+    /// This version has to be the same as the version of this runner.
+    pub library_version: String,
+}
+
+/// The outcome of a run of all benchmarks of a benchmark file with [`run_and_compare`]
+#[derive(Debug, Default)]
+pub struct RunOutcome {
+    /// The summaries of all benchmarks in the order they were run
+    ///
+    /// Each summary contains the costs of the new run compared to the old run or baseline and the
+    /// detected performance regressions.
+    pub summaries: Vec<BenchmarkSummary>,
+    /// True if any of the benchmarks regressed
+    pub regressed: bool,
+}
+
 #[derive(Debug)]
 pub struct Config {
     package_dir: PathBuf,
@@ -62,6 +110,57 @@ fn compare_versions(runner_version: String, library_version: String) -> Result<(
     Ok(())
 }
 
+/// Create the [`Bundle`] after the benchmark run if `--bundle` was given
+///
+/// The bundle is also created if the benchmark run failed, for example because of a fatal
+/// performance regression. The error of the benchmark run takes precedence over an error creating
+/// the bundle.
+fn create_bundle<T>(result: Result<T>, bundle: Option<&Bundle>) -> Result<T> {
+    if let Some(bundle) = bundle {
+        let bundle_result = bundle.create();
+        let value = result?;
+        bundle_result.map(|()| value)
+    } else {
+        result
+    }
+}
+
+/// Merge the `summaries` of this benchmark run into the file at `path` given with
+/// `--merge-summary` and return all summaries merged so far
+fn merge_summaries(path: &Path, summaries: &[BenchmarkSummary]) -> Result<MergedSummaries> {
+    let mut merged = MergedSummaries::load(path)?;
+    merged.merge(summaries).with_context(|| {
+        format!(
+            "Failed to merge the summaries into '{}': A benchmark can only be merged once",
+            path.display()
+        )
+    })?;
+    merged.save(path)?;
+    Ok(merged)
+}
+
+impl RunOutcome {
+    /// Return the summaries of the benchmarks which regressed
+    pub fn regressions(&self) -> impl Iterator<Item = &BenchmarkSummary> {
+        self.summaries
+            .iter()
+            .filter(|summary| summary.is_regressed())
+    }
+
+    /// Add the `summary` of a benchmark and check it for performance regressions
+    ///
+    /// # Errors
+    ///
+    /// Return an [`anyhow::Error`] with sources:
+    ///
+    /// * [`Error::RegressionError`] if the benchmark regressed and `fail_fast` is true.
+    fn add(&mut self, summary: BenchmarkSummary, fail_fast: bool) -> Result<()> {
+        summary.check_regression(&mut self.regressed, fail_fast)?;
+        self.summaries.push(summary);
+        Ok(())
+    }
+}
+
 /// Method to read, decode and deserialize the data sent by iai-callgrind
 ///
 /// iai-callgrind uses elements from the [`crate::api`], so the runner can understand which elements
@@ -88,6 +187,91 @@ where
     Ok(benchmark)
 }
 
+/// Run all benchmarks of a benchmark file and compare them with the old run or baseline
+///
+/// This is the entry point to embed iai-callgrind into other programs, for example a custom `main`
+/// or a build script. Like with the `iai-callgrind-runner` binary, the `command_line_args` of the
+/// [`RunInput::benchmarks`] and the `IAI_CALLGRIND_*` environment variables configure the run. In
+/// contrast to [`run`], a non-fatal performance regression is not an error but reported with
+/// [`RunOutcome::regressed`].
+///
+/// # Thread-safety
+///
+/// This function must not be called concurrently. The sandbox of binary benchmarks changes the
+/// current directory of the whole process with [`std::env::set_current_dir`], which affects all
+/// other threads. In addition, the benchmark runs of the same package share the output directory
+/// in `target/iai` including the files of the old runs and baselines. Calling it from multiple
+/// threads one after another is fine.
+///
+/// # Errors
+///
+/// If the [`RunInput::library_version`] doesn't match the version of the runner, running the
+/// benchmarks fails or a benchmark regressed and the `RegressionConfig` is configured with
+/// `fail_fast`.
+pub fn run_and_compare(input: RunInput) -> Result<RunOutcome> {
+    let RunInput {
+        package_dir,
+        package_name,
+        bench_file,
+        module,
+        bench_bin,
+        benchmarks,
+        library_version,
+    } = input;
+
+    let command_line_args = match &benchmarks {
+        Benchmarks::Library(benchmark) => &benchmark.command_line_args,
+        Benchmarks::Binary(benchmark) => &benchmark.command_line_args,
+    };
+    let meta = Metadata::new(command_line_args, &package_name, &bench_file)?;
+    if meta
+        .args
+        .filter
+        .as_ref()
+        .map_or(false, |filter| !filter.apply(&meta.bench_name))
+    {
+        debug!("Benchmark '{}' is filtered out", bench_file.display());
+        return Ok(RunOutcome::default());
+    }
+
+    let bundle = Bundle::from_meta(&meta);
+    let merge_summary = meta
+        .args
+        .merge_summary
+        .as_ref()
+        .map(|path| meta.project_root.join(path));
+    let top_regressions_meta = meta.args.top_regressions.is_some().then(|| meta.clone());
+    let config = Config {
+        package_dir,
+        bench_file,
+        module,
+        bench_bin,
+        meta,
+    };
+
+    compare_versions(env!("CARGO_PKG_VERSION").to_owned(), library_version)?;
+    let result = match benchmarks {
+        Benchmarks::Library(benchmark) => lib_bench::run(benchmark, config),
+        Benchmarks::Binary(benchmark) => bin_bench::run(benchmark, config),
+    };
+    let result = result.and_then(|outcome| {
+        let merged = merge_summary
+            .as_ref()
+            .map(|path| merge_summaries(path, &outcome.summaries))
+            .transpose()?;
+        if let Some(meta) = &top_regressions_meta {
+            // With `--merge-summary`, the benchmarks of the previous runs are ranked, too
+            if let Some(merged) = &merged {
+                print_top_regressions(meta, &merged.summaries()?)?;
+            } else {
+                print_top_regressions(meta, &outcome.summaries)?;
+            }
+        }
+        Ok(outcome)
+    });
+    create_bundle(result, bundle.as_ref())
+}
+
 pub fn run() -> Result<()> {
     let mut args_iter = std::env::args_os();
 
@@ -98,7 +282,6 @@ pub fn run() -> Result<()> {
     // The following unwraps are safe because these arguments are assuredly submitted by the
     // iai_callgrind::main macro
     let library_version = args_iter.next().unwrap().to_str().unwrap().to_owned();
-    let runner_version = env!("CARGO_PKG_VERSION").to_owned();
     let bench_kind = match args_iter.next().unwrap().to_str().unwrap() {
         "--lib-bench" => BenchmarkKind::LibraryBenchmark,
         "--bin-bench" => BenchmarkKind::BinaryBenchmark,
@@ -117,54 +300,149 @@ pub fn run() -> Result<()> {
         .parse::<usize>()
         .unwrap();
 
-    match bench_kind {
-        BenchmarkKind::LibraryBenchmark => {
-            let benchmark: LibraryBenchmark = receive_benchmark(num_bytes)?;
-            let meta = Metadata::new(&benchmark.command_line_args, &package_name, &bench_file)?;
-            if meta
-                .args
-                .filter
-                .as_ref()
-                .map_or(false, |filter| !filter.apply(&meta.bench_name))
-            {
-                debug!("Benchmark '{}' is filtered out", bench_file.display());
-                return Ok(());
-            }
+    let benchmarks = match bench_kind {
+        BenchmarkKind::LibraryBenchmark => Benchmarks::Library(receive_benchmark(num_bytes)?),
+        BenchmarkKind::BinaryBenchmark => Benchmarks::Binary(receive_benchmark(num_bytes)?),
+    };
+    let outcome = run_and_compare(RunInput {
+        package_dir,
+        package_name,
+        bench_file,
+        module,
+        bench_bin,
+        benchmarks,
+        library_version,
+    })?;
+    if outcome.regressed {
+        Err(Error::RegressionError(false).into())
+    } else {
+        Ok(())
+    }
+}
 
-            let config = Config {
-                package_dir,
-                bench_file,
-                module,
-                bench_bin,
-                meta,
-            };
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+    use rstest::rstest;
 
-            compare_versions(runner_version, library_version)?;
-            lib_bench::run(benchmark, config)
-        }
-        BenchmarkKind::BinaryBenchmark => {
-            let benchmark: BinaryBenchmark = receive_benchmark(num_bytes)?;
-            let meta = Metadata::new(&benchmark.command_line_args, &package_name, &bench_file)?;
-            if meta
-                .args
-                .filter
-                .as_ref()
-                .map_or(false, |filter| !filter.apply(&meta.bench_name))
-            {
-                debug!("Benchmark '{}' is filtered out", bench_file.display());
-                return Ok(());
-            }
+    use super::*;
+    use crate::api::EventKind;
+    use crate::runner::callgrind::model::Costs;
+    use crate::runner::summary::{
+        CallgrindRegressionSummary, CallgrindRunSummary, CallgrindSummary, CostsSummary,
+    };
+    use crate::runner::tool::ValgrindTool;
+
+    fn benchmark_summary(function: &str, is_regressed: bool) -> BenchmarkSummary {
+        let mut summary = BenchmarkSummary::new(
+            BenchmarkKind::LibraryBenchmark,
+            PathBuf::from("/root"),
+            PathBuf::from("/root/package"),
+            PathBuf::from("benches/bench.rs"),
+            PathBuf::from("/root/target/bench"),
+            &["bench", "group", function],
+            None,
+            None,
+            None,
+        );
+        let regressions = if is_regressed {
+            vec![CallgrindRegressionSummary {
+                event_kind: EventKind::Ir,
+                new: 20,
+                old: 10,
+                diff_pct: 100.0,
+                limit: 10.0,
+            }]
+        } else {
+            vec![]
+        };
+        summary.callgrind_summary = Some(CallgrindSummary {
+            tool: ValgrindTool::Callgrind,
+            log_paths: vec![],
+            out_paths: vec![],
+            flamegraphs: vec![],
+            summaries: vec![CallgrindRunSummary {
+                command: "bench".to_owned(),
+                baseline: None,
+                events: CostsSummary::new(
+                    &Costs::with_event_kinds([(EventKind::Ir, 20)]),
+                    Some(&Costs::with_event_kinds([(EventKind::Ir, 10)])),
+                ),
+                regressions,
+                peak_rss: None,
+                valgrind_wall_time: None,
+                deterministic: None,
+                region: None,
+                is_new: false,
+            }],
+        });
+        summary
+    }
 
-            let config = Config {
-                package_dir,
-                bench_file,
-                module,
-                bench_bin,
-                meta,
-            };
+    fn function_names(summaries: &[BenchmarkSummary]) -> Vec<&str> {
+        summaries
+            .iter()
+            .map(|summary| summary.function_name.as_str())
+            .collect()
+    }
 
-            compare_versions(runner_version, library_version)?;
-            bin_bench::run(benchmark, config)
+    #[rstest]
+    #[case::none(&[], false)]
+    #[case::not_regressed(&[false, false], false)]
+    #[case::first_regressed(&[true, false], true)]
+    #[case::last_regressed(&[false, true], true)]
+    #[case::all_regressed(&[true, true], true)]
+    fn test_run_outcome_add(#[case] regressions: &[bool], #[case] expected: bool) {
+        let mut outcome = RunOutcome::default();
+        let names = (0..regressions.len())
+            .map(|index| format!("func_{index}"))
+            .collect::<Vec<_>>();
+        for (name, is_regressed) in names.iter().zip(regressions) {
+            outcome
+                .add(benchmark_summary(name, *is_regressed), false)
+                .unwrap();
         }
+
+        assert_eq!(outcome.regressed, expected);
+        assert_eq!(
+            function_names(&outcome.summaries),
+            names.iter().map(String::as_str).collect::<Vec<_>>()
+        );
+        assert_eq!(
+            outcome.regressions().count(),
+            regressions.iter().filter(|r| **r).count()
+        );
+    }
+
+    #[test]
+    fn test_run_outcome_add_when_fail_fast() {
+        let mut outcome = RunOutcome::default();
+        outcome
+            .add(benchmark_summary("first", false), true)
+            .unwrap();
+
+        let error = outcome
+            .add(benchmark_summary("second", true), true)
+            .unwrap_err();
+
+        assert_eq!(
+            error.downcast_ref::<Error>(),
+            Some(&Error::RegressionError(true))
+        );
+        assert!(!outcome.regressed);
+        assert_eq!(function_names(&outcome.summaries), vec!["first"]);
+    }
+
+    #[test]
+    fn test_run_outcome_add_without_callgrind_summary() {
+        let mut outcome = RunOutcome::default();
+        let mut summary = benchmark_summary("func", true);
+        summary.callgrind_summary = None;
+
+        outcome.add(summary, true).unwrap();
+
+        assert!(!outcome.regressed);
+        assert_eq!(outcome.regressions().count(), 0);
+        assert_eq!(outcome.summaries.len(), 1);
     }
 }
@@ -0,0 +1,105 @@
+use std::io::{stdout, Write};
+
+use anyhow::{Context, Result};
+
+use super::format::OutputFormat;
+use super::summary::BenchmarkSummary;
+
+/// A `Reporter` reports the [`BenchmarkSummary`] of each benchmark in an [`OutputFormat`]
+///
+/// The reporters are selected with `--output-format`, which takes a comma separated list of the
+/// [`OutputFormat`] names. Each requested reporter receives the summary of each benchmark after
+/// the benchmark has finished.
+pub trait Reporter {
+    /// Report the `summary` of a single benchmark
+    fn report(&self, summary: &BenchmarkSummary) -> Result<()>;
+}
+
+/// The human-readable terminal output
+///
+/// The terminal output is printed while the benchmarks are running or, with `--only-changed` and
+/// `--quiet`, by the [`super::format::BenchmarkCounts`] after each benchmark, so there's
+/// nothing left to report after the benchmark.
+#[derive(Debug, Clone, Copy)]
+pub struct TerminalReporter;
+
+/// The machine-readable output to stdout with the same schema as the `summary.json` files
+///
+/// If not `pretty`, the summary of each benchmark is a single line of json.
+#[derive(Debug, Clone, Copy)]
+pub struct JsonReporter {
+    pub pretty: bool,
+}
+
+impl Reporter for TerminalReporter {
+    fn report(&self, _summary: &BenchmarkSummary) -> Result<()> {
+        Ok(())
+    }
+}
+
+impl Reporter for JsonReporter {
+    fn report(&self, summary: &BenchmarkSummary) -> Result<()> {
+        let value =
+            serde_json::to_value(summary).with_context(|| "Failed to serialize summary to json")?;
+
+        let output = stdout();
+        let mut writer = output.lock();
+        let result = if self.pretty {
+            serde_json::to_writer_pretty(&mut writer, &value)
+        } else {
+            serde_json::to_writer(&mut writer, &value)
+        };
+        result.with_context(|| "Failed to print json to stdout")?;
+        writeln!(writer).with_context(|| "Failed to print json to stdout")
+    }
+}
+
+impl OutputFormat {
+    /// Return the [`Reporter`] of this `OutputFormat`
+    pub fn reporter(self) -> Box<dyn Reporter> {
+        match self {
+            OutputFormat::Default => Box::new(TerminalReporter),
+            OutputFormat::Json => Box::new(JsonReporter { pretty: false }),
+            OutputFormat::PrettyJson => Box::new(JsonReporter { pretty: true }),
+        }
+    }
+}
+
+/// Return true if the human-readable terminal output is one of the `output_formats`
+pub fn is_terminal_output(output_formats: &[OutputFormat]) -> bool {
+    output_formats.contains(&OutputFormat::Default)
+}
+
+/// Report the `summary` with the [`Reporter`] of each of the `output_formats`
+///
+/// Each reporter reports only once even if its [`OutputFormat`] is given more than once.
+///
+/// # Errors
+///
+/// Returns the error of the first failing reporter
+pub fn report(output_formats: &[OutputFormat], summary: &BenchmarkSummary) -> Result<()> {
+    let mut reported = Vec::with_capacity(output_formats.len());
+    for format in output_formats {
+        if !reported.contains(format) {
+            format.reporter().report(summary)?;
+            reported.push(*format);
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use rstest::rstest;
+
+    use super::*;
+
+    #[rstest]
+    #[case::empty(&[], false)]
+    #[case::default(&[OutputFormat::Default], true)]
+    #[case::json(&[OutputFormat::Json], false)]
+    #[case::json_and_default(&[OutputFormat::Json, OutputFormat::Default], true)]
+    fn test_is_terminal_output(#[case] output_formats: &[OutputFormat], #[case] expected: bool) {
+        assert_eq!(is_terminal_output(output_formats), expected);
+    }
+}
@@ -1,13 +1,15 @@
 use std::borrow::Cow;
+use std::collections::HashMap;
 use std::ffi::OsString;
 use std::fmt::{Debug, Display};
 use std::fs::File;
 use std::hash::Hash;
-use std::io::stdout;
+use std::io::{stdin, BufReader, Read};
 use std::path::{Path, PathBuf};
 use std::str::FromStr;
 
 use anyhow::{anyhow, Context, Result};
+use colored::Colorize;
 use glob::glob;
 use indexmap::{indexmap, IndexMap};
 use lazy_static::lazy_static;
@@ -17,13 +19,24 @@ use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
 use super::costs::Costs;
-use super::format::{ComparisonHeader, OutputFormat, VerticalFormat};
+use super::format::{
+    format_deterministic, format_peak_rss, format_region, format_valgrind_time, tool_headline,
+    ComparisonHeader, Formatter, Header, OutputFormat, VerticalFormat,
+};
 use super::meta::Metadata;
-use super::tool::{ToolOutputPath, ValgrindTool};
+use super::report::{is_terminal_output, report};
+use super::tool::format::ToolRunSummaryFormatter;
+use super::tool::{ToolOutput, ToolOutputPath, ValgrindTool};
 use crate::api::EventKind;
 use crate::error::Error;
 use crate::runner::costs::Summarize;
-use crate::util::{factor_diff, make_absolute, percentage_diff};
+use crate::util::{factor_diff, make_absolute, make_relative, percentage_diff};
+
+/// The version of the format of the [`BenchmarkSummary`]
+pub const SUMMARY_VERSION: &str = "2";
+
+/// The version of the format of the [`RegressionsReport`]
+pub const REGRESSIONS_VERSION: &str = "1";
 
 lazy_static! {
     static ref EXTRACT_ERROR_SUMMARY_RE: Regex = regex::Regex::new(
@@ -48,6 +61,12 @@ pub struct Baseline {
 #[cfg_attr(feature = "schema", derive(JsonSchema))]
 pub struct BaselineName(String);
 
+/// The new callgrind costs of the [`BenchmarkSummary`]s used as baseline with `--baseline-summary`
+///
+/// The costs are identified by the `module_path` and `id` of the benchmark.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct BaselineSummaries(HashMap<(String, Option<String>), Costs<EventKind>>);
+
 /// The `BaselineKind` describing the baseline
 ///
 /// Currently, iai-callgrind can only compare callgrind output with `.old` files.
@@ -133,12 +152,39 @@ pub struct CallgrindRunSummary {
     pub events: CostsSummary<EventKind>,
     /// All detected performance regressions
     pub regressions: Vec<CallgrindRegressionSummary>,
+    /// The peak resident set size in kilobytes of the valgrind process if it was recorded
+    ///
+    /// This value includes the memory overhead of valgrind itself.
+    pub peak_rss: Option<u64>,
+    /// The wall-clock time in seconds of the valgrind process from the start to the exit
+    ///
+    /// This is the time of the instrumented run including the overhead of valgrind. It is not
+    /// representative of the real performance of the benchmark and not a benchmark result.
+    pub valgrind_wall_time: Option<f64>,
+    /// True if two runs of the benchmark had the same costs of the primary event kind if checked
+    ///
+    /// This value is only present if the determinism check (`--check-determinism`) was enabled.
+    pub deterministic: Option<bool>,
+    /// The region of a binary benchmark if only the costs of this region were collected
+    pub region: Option<String>,
+    /// True if there was no old output or baseline to compare the new costs with
+    ///
+    /// This is usually the case in the first run of a new benchmark. A new benchmark is never
+    /// regressed.
+    #[serde(default)]
+    pub is_new: bool,
 }
 
 /// The `CallgrindSummary` summarizes all callgrind runs
 #[derive(Debug, PartialEq, Serialize, Deserialize)]
 #[cfg_attr(feature = "schema", derive(JsonSchema))]
 pub struct CallgrindSummary {
+    /// The tool which was run. This is callgrind or cachegrind if configured.
+    ///
+    /// Summaries written before the cachegrind mode existed don't have this field and are always
+    /// summaries of callgrind.
+    #[serde(default = "default_callgrind_summary_tool")]
+    pub tool: ValgrindTool,
     /// The paths to the `*.log` files
     pub log_paths: Vec<PathBuf>,
     /// The paths to the `*.old` files
@@ -201,10 +247,19 @@ pub struct FlamegraphSummary {
     pub regular_path: Option<PathBuf>,
     /// If present, the path to the file of the old regular (non-differential) flamegraph
     pub base_path: Option<PathBuf>,
-    /// If present, the path to the file of the differential flamegraph
+    /// If present, the path to the file of the differential flamegraph or the html page with the
+    /// flamegraphs side by side
     pub diff_path: Option<PathBuf>,
 }
 
+/// The [`BenchmarkSummary`]s of multiple benchmark runs merged into a single json file with
+/// `--merge-summary`
+///
+/// The summaries are identified by the `module_path` and `id` of the benchmark and keep the order
+/// in which they were merged.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct MergedSummaries(IndexMap<(String, Option<String>), serde_json::Value>);
+
 /// The format (json, ...) in which the summary file should be saved or printed
 #[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum, Serialize, Deserialize)]
 #[cfg_attr(feature = "schema", derive(JsonSchema))]
@@ -225,6 +280,46 @@ pub struct SummaryOutput {
     path: PathBuf,
 }
 
+/// The `RegressionsReport` listing all performance regressions of all benchmarks of a benchmark
+/// file
+///
+/// Unlike the [`BenchmarkSummary`], this report contains only the regressions and is meant for
+/// scripts gating on performance regressions. The `regressions` are empty if no benchmark
+/// regressed.
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(JsonSchema))]
+pub struct RegressionsReport {
+    /// The version of this format. Only backwards incompatible cause an increase of the version
+    pub version: String,
+    /// All detected performance regressions
+    pub regressions: Vec<RegressionsReportEntry>,
+}
+
+/// A single performance regression of the [`RegressionsReport`]
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(JsonSchema))]
+pub struct RegressionsReportEntry {
+    /// The benchmark in the form `bench_file::group::bench id` as printed with `--list`
+    pub benchmark: String,
+    /// The [`EventKind`] which is affected by the performance regression
+    pub event: EventKind,
+    /// The value of the baseline (old) benchmark run
+    pub baseline: u64,
+    /// The value of the new benchmark run
+    pub new: u64,
+    /// The difference between new and baseline in percent
+    pub pct: f64,
+}
+
+/// Manage the `regressions.json` file with this `RegressionsOutput`
+#[derive(Debug, PartialEq)]
+pub struct RegressionsOutput {
+    /// The path to the `regressions.json` file
+    path: PathBuf,
+    /// The [`RegressionsReport`] which is written to the `path`
+    report: RegressionsReport,
+}
+
 /// The `ToolRunSummary` which contains all information about a single tool run process
 ///
 /// There's a separate process and therefore `ToolRunSummary` for the parent process and each child
@@ -252,6 +347,12 @@ pub struct ToolRunSummary {
     /// `4 errors from 3 contexts (suppressed: 2 from 1)`
     /// results in `ErrorSummary {errors: 4, contexts: 3, supp_errors: 2, supp_contexts: 1}`
     pub error_summary: Option<ErrorSummary>,
+    /// The number of error contexts of each kind of error like `Invalid read of size 4`
+    ///
+    /// The kinds of the errors are extracted from the log files of tools with an error summary
+    /// like Memcheck, DRD and Helgrind in the order of their first occurrence.
+    #[serde(default)]
+    pub error_kinds: IndexMap<String, u64>,
     /// The tool specific cost summary extracted from Valgrind output
     pub costs_summary: Option<CostsSummary<String>>,
     /// The path to the full logfile from the tool run
@@ -279,6 +380,51 @@ pub struct ToolSummary {
     pub out_paths: Vec<PathBuf>,
     /// All [`ToolRunSummary`]s
     pub summaries: Vec<ToolRunSummary>,
+    /// The wall-clock time in seconds of the valgrind process from the start to the exit
+    ///
+    /// This is the time of the instrumented run including the overhead of valgrind. It is not
+    /// representative of the real performance of the benchmark and not a benchmark result. This
+    /// value is only present if the tool was run and not loaded from a baseline.
+    #[serde(default)]
+    pub valgrind_wall_time: Option<f64>,
+    /// True if the tool exited with the exit code of `--error-exitcode` because of its errors
+    ///
+    /// The errors are reported with [`BenchmarkSummary::check_tool_errors`] after the summary was
+    /// saved. This value is not part of the json output.
+    #[serde(skip)]
+    pub is_error_exit: bool,
+}
+
+impl ToolSummary {
+    /// Return the sum of the [`ErrorSummary`]s of all [`ToolRunSummary`]s
+    pub fn error_summary(&self) -> ErrorSummary {
+        self.summaries
+            .iter()
+            .filter_map(|summary| summary.error_summary.as_ref())
+            .fold(
+                ErrorSummary {
+                    errors: 0,
+                    contexts: 0,
+                    supp_errors: 0,
+                    supp_contexts: 0,
+                },
+                |acc, error_summary| ErrorSummary {
+                    errors: acc.errors + error_summary.errors,
+                    contexts: acc.contexts + error_summary.contexts,
+                    supp_errors: acc.supp_errors + error_summary.supp_errors,
+                    supp_contexts: acc.supp_contexts + error_summary.supp_contexts,
+                },
+            )
+    }
+
+    /// Return the error kinds of all [`ToolRunSummary`]s
+    pub fn error_kinds(&self) -> IndexMap<String, u64> {
+        let mut error_kinds = IndexMap::new();
+        for (kind, count) in self.summaries.iter().flat_map(|s| s.error_kinds.iter()) {
+            *error_kinds.entry(kind.clone()).or_insert(0) += count;
+        }
+        error_kinds
+    }
 }
 
 impl FromStr for BaselineName {
@@ -303,6 +449,61 @@ impl Display for BaselineName {
     }
 }
 
+impl BaselineSummaries {
+    /// Load the `BaselineSummaries` from the file at `path` or from stdin if `path` is `-`
+    ///
+    /// # Errors
+    ///
+    /// If the file could not be read or [`BaselineSummaries::from_reader`] fails
+    pub fn load(path: &Path) -> Result<Self> {
+        if path == Path::new("-") {
+            Self::from_reader(stdin().lock())
+                .context("Failed to read the baseline summary from stdin")
+        } else {
+            let file = File::open(path).with_context(|| {
+                format!("Failed to open the baseline summary '{}'", path.display())
+            })?;
+            Self::from_reader(BufReader::new(file)).with_context(|| {
+                format!("Failed to read the baseline summary '{}'", path.display())
+            })
+        }
+    }
+
+    /// Read the `BaselineSummaries` from JSON [`BenchmarkSummary`]s
+    ///
+    /// The JSON is either a single `BenchmarkSummary` like the `summary.json` files, an array of
+    /// `BenchmarkSummary`s or multiple concatenated `BenchmarkSummary`s (for example `cat
+    /// target/iai/**/summary.json`).
+    ///
+    /// # Errors
+    ///
+    /// If the JSON is invalid or the `version` of a `BenchmarkSummary` is not the supported
+    /// version
+    pub fn from_reader<R: Read>(reader: R) -> Result<Self> {
+        let mut summaries = Self::default();
+        for summary in BenchmarkSummary::read_all(reader)? {
+            if let Some(costs) = summary
+                .callgrind_summary
+                .and_then(|callgrind_summary| callgrind_summary.summaries.into_iter().next())
+                .and_then(|run_summary| run_summary.events.extract_costs().0)
+            {
+                summaries.0.insert((summary.module_path, summary.id), costs);
+            }
+        }
+        Ok(summaries)
+    }
+
+    /// Return the costs of the benchmark with the `module_path` and `id` if present
+    pub fn costs(&self, module_path: &str, id: Option<&str>) -> Option<&Costs<EventKind>> {
+        self.0
+            .get(&(module_path.to_owned(), id.map(ToOwned::to_owned)))
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+}
+
 impl BenchmarkSummary {
     /// Create a new `BenchmarkSummary`
     ///
@@ -320,7 +521,7 @@ impl BenchmarkSummary {
         output: Option<SummaryOutput>,
     ) -> Self {
         Self {
-            version: "2".to_owned(),
+            version: SUMMARY_VERSION.to_owned(),
             kind,
             benchmark_file: make_absolute(&project_root, benchmark_file),
             benchmark_exe: make_absolute(&project_root, benchmark_exe),
@@ -336,34 +537,31 @@ impl BenchmarkSummary {
         }
     }
 
-    pub fn print_and_save(&self, output_format: &OutputFormat) -> Result<()> {
-        let value = match (output_format, &self.summary_output) {
-            (OutputFormat::Default, None) => return Ok(()),
-            _ => {
-                serde_json::to_value(self).with_context(|| "Failed to serialize summary to json")?
-            }
-        };
+    /// Return an [`Error::ToolErrors`] for the first [`ToolSummary`] of a tool which exited with an
+    /// error
+    ///
+    /// The tool errors fail the benchmark run only after this summary was saved, so the errors
+    /// found by the tool are part of the saved summary.
+    pub fn check_tool_errors(&self) -> Result<()> {
+        if let Some(tool_summary) = self.tool_summaries.iter().find(|s| s.is_error_exit) {
+            return Err(Error::ToolErrors((
+                tool_summary.tool.id(),
+                tool_summary.error_summary(),
+                tool_summary.error_kinds(),
+            ))
+            .into());
+        }
+        Ok(())
+    }
 
-        let result = match output_format {
-            OutputFormat::Default => Ok(()),
-            OutputFormat::Json => {
-                let output = stdout();
-                let writer = output.lock();
-                let result = serde_json::to_writer(writer, &value);
-                println!();
-                result
-            }
-            OutputFormat::PrettyJson => {
-                let output = stdout();
-                let writer = output.lock();
-                let result = serde_json::to_writer_pretty(writer, &value);
-                println!();
-                result
-            }
-        };
-        result.with_context(|| "Failed to print json to stdout")?;
+    /// Report this summary with the reporters of the `output_formats` and save it to the
+    /// `summary.json` file if `--save-summary` is given
+    pub fn print_and_save(&self, output_formats: &[OutputFormat]) -> Result<()> {
+        report(output_formats, self)?;
 
         if let Some(output) = &self.summary_output {
+            let value = serde_json::to_value(self)
+                .with_context(|| "Failed to serialize summary to json")?;
             let file = output.create()?;
 
             let result = if matches!(output.format, SummaryFormat::PrettyJson) {
@@ -401,6 +599,186 @@ impl BenchmarkSummary {
         Ok(())
     }
 
+    /// Print the deferred terminal output of this benchmark if only changed or regressed
+    /// benchmarks are printed
+    ///
+    /// If `--only-changed` is given, the terminal output of a benchmark isn't printed while the
+    /// benchmark is running but by this method and only if any event of any callgrind run changed
+    /// by more than the `--change-tolerance`. If `--quiet` is given, the terminal output is only
+    /// printed if the benchmark regressed. Returns false if nothing was printed for these reasons.
+    /// In all other cases this method doesn't print anything and returns true.
+    pub fn print_deferred(
+        &self,
+        meta: &Metadata,
+        baselines: &(Option<String>, Option<String>),
+    ) -> Result<bool> {
+        if !is_terminal_output(&meta.args.output_format) {
+            return Ok(true);
+        }
+
+        if meta.args.quiet.unwrap_or_default() {
+            if !self.is_regressed() {
+                return Ok(false);
+            }
+        } else if meta.args.only_changed.unwrap_or_default() {
+            if let Some(callgrind_summary) = &self.callgrind_summary {
+                let tolerance = meta.args.change_tolerance.unwrap_or(0f64);
+                if !callgrind_summary
+                    .summaries
+                    .iter()
+                    .any(|summary| VerticalFormat::default().is_changed(&summary.events, tolerance))
+                {
+                    return Ok(false);
+                }
+            }
+        } else {
+            return Ok(true);
+        }
+
+        self.print_terminal_output(meta, baselines)?;
+        Ok(true)
+    }
+
+    /// Return true if any callgrind run of this benchmark regressed
+    pub fn is_regressed(&self) -> bool {
+        self.callgrind_summary
+            .as_ref()
+            .map_or(false, CallgrindSummary::is_regressed)
+    }
+
+    /// Return true if any callgrind run of this benchmark had no old output or baseline to
+    /// compare with
+    pub fn is_new(&self) -> bool {
+        self.callgrind_summary
+            .as_ref()
+            .map_or(false, CallgrindSummary::is_new)
+    }
+
+    /// Return the name of this benchmark in the form `bench_file::group::bench id` as printed with
+    /// `--list`
+    pub fn name(&self) -> String {
+        self.id.as_ref().map_or_else(
+            || self.module_path.clone(),
+            |id| format!("{} {id}", self.module_path),
+        )
+    }
+
+    /// Read all `BenchmarkSummary`s from JSON
+    ///
+    /// The JSON is either a single `BenchmarkSummary` like the `summary.json` files, an array of
+    /// `BenchmarkSummary`s or multiple concatenated `BenchmarkSummary`s.
+    ///
+    /// # Errors
+    ///
+    /// If the JSON is invalid or the `version` of a `BenchmarkSummary` is not the supported
+    /// version
+    pub fn read_all<R: Read>(reader: R) -> Result<Vec<Self>> {
+        let mut summaries = vec![];
+        for value in serde_json::Deserializer::from_reader(reader).into_iter::<serde_json::Value>()
+        {
+            match value.context("Invalid json")? {
+                serde_json::Value::Array(values) => {
+                    for value in values {
+                        summaries.push(Self::from_value(value)?);
+                    }
+                }
+                value => summaries.push(Self::from_value(value)?),
+            }
+        }
+        Ok(summaries)
+    }
+
+    /// Deserialize a `BenchmarkSummary` from the json `value` after checking its `version`
+    fn from_value(value: serde_json::Value) -> Result<Self> {
+        match value.get("version").and_then(serde_json::Value::as_str) {
+            Some(SUMMARY_VERSION) => {}
+            Some(version) => {
+                return Err(anyhow!(
+                    "Unsupported version '{version}' of the summary format. Expected version \
+                     '{SUMMARY_VERSION}'"
+                ));
+            }
+            None => return Err(anyhow!("Missing version of the summary format")),
+        }
+
+        serde_json::from_value(value).context("Invalid benchmark summary")
+    }
+
+    /// Print the human-readable terminal output of this benchmark after it has finished
+    fn print_terminal_output(
+        &self,
+        meta: &Metadata,
+        baselines: &(Option<String>, Option<String>),
+    ) -> Result<()> {
+        let format = VerticalFormat::default();
+        Header::new(
+            self.module_path.clone(),
+            self.id.clone(),
+            self.details.clone(),
+        )
+        .print();
+        if let Some(callgrind_summary) = &self.callgrind_summary {
+            if !self.tool_summaries.is_empty() {
+                println!("{}", tool_headline(callgrind_summary.tool));
+            }
+            for summary in &callgrind_summary.summaries {
+                if let Some(region) = &summary.region {
+                    println!("{}", format_region(region));
+                }
+                print!(
+                    "{}",
+                    format.format(
+                        baselines.clone(),
+                        &summary.events,
+                        meta.args.number_format,
+                        meta.args.diff_format,
+                        meta.args.filter_event.as_ref()
+                    )?
+                );
+                if let Some(peak_rss) = summary.peak_rss {
+                    println!("{}", format_peak_rss(peak_rss));
+                }
+                if let Some(wall_time) = summary
+                    .valgrind_wall_time
+                    .filter(|_| meta.args.valgrind_time.unwrap_or_default())
+                {
+                    println!("{}", format_valgrind_time(wall_time));
+                }
+                if let Some(deterministic) = summary.deterministic {
+                    println!("{}", format_deterministic(deterministic));
+                }
+            }
+        }
+
+        for tool_summary in &self.tool_summaries {
+            println!("{}", tool_headline(tool_summary.tool));
+            for summary in &tool_summary.summaries {
+                ToolRunSummaryFormatter::print(
+                    summary,
+                    false,
+                    tool_summary.summaries.len() > 1,
+                    tool_summary.tool == ValgrindTool::BBV,
+                    meta.args.number_format,
+                    meta.args.diff_format,
+                    meta.args.filter_event.as_ref(),
+                )?;
+            }
+            for path in tool_summary
+                .out_paths
+                .iter()
+                .map(|p| make_relative(&meta.project_root, p))
+            {
+                println!(
+                    "  {:<18}{}",
+                    "Outfile:",
+                    path.display().to_string().blue().bold()
+                );
+            }
+        }
+
+        Ok(())
+    }
+
     pub fn compare_and_print(&self, id: &str, meta: &Metadata, other: &Self) -> Result<()> {
         if let (Some(callgrind_summary), Some(other_callgrind_summary)) =
             (&self.callgrind_summary, &other.callgrind_summary)
@@ -429,8 +807,13 @@ impl BenchmarkSummary {
 
 impl CallgrindSummary {
     /// Create a new `CallgrindSummary`
-    pub fn new(log_paths: Vec<PathBuf>, out_paths: Vec<PathBuf>) -> CallgrindSummary {
+    pub fn new(
+        tool: ValgrindTool,
+        log_paths: Vec<PathBuf>,
+        out_paths: Vec<PathBuf>,
+    ) -> CallgrindSummary {
         Self {
+            tool,
             log_paths,
             out_paths,
             flamegraphs: Vec::default(),
@@ -443,7 +826,14 @@ impl CallgrindSummary {
         self.summaries.iter().any(|r| !r.regressions.is_empty())
     }
 
+    /// Return true if any [`CallgrindRunSummary`] in this `CallgrindSummary` is new
+    pub fn is_new(&self) -> bool {
+        self.summaries.iter().any(|r| r.is_new)
+    }
+
     /// Create and add a [`CallgrindRunSummary`] to this `CallgrindSummary`
+    ///
+    /// Returns the added `CallgrindRunSummary`.
     pub fn add_summary(
         &mut self,
         bench_bin: &Path,
@@ -451,7 +841,8 @@ impl CallgrindSummary {
         old_path: &ToolOutputPath,
         events: CostsSummary,
         regressions: Vec<CallgrindRegressionSummary>,
-    ) {
+        output: Option<&ToolOutput>,
+    ) -> &mut CallgrindRunSummary {
         self.summaries.push(CallgrindRunSummary {
             command: format!(
                 "{} {}",
@@ -471,9 +862,19 @@ impl CallgrindSummary {
                 kind: old_path.baseline_kind.clone(),
                 path: old_path.to_path(),
             }),
+            is_new: events.is_new(),
             events,
             regressions,
+            peak_rss: output.and_then(|output| output.peak_rss),
+            valgrind_wall_time: output
+                .and_then(|output| output.wall_time)
+                .map(|wall_time| wall_time.as_secs_f64()),
+            deterministic: None,
+            region: None,
         });
+        self.summaries
+            .last_mut()
+            .expect("A summary should have been pushed")
     }
 }
 
@@ -545,6 +946,11 @@ impl<K: Hash + Eq + Summarize + Display + Clone> CostsSummary<K> {
         self.0.iter()
     }
 
+    /// Return true if there are new costs but none of them has old costs to compare with
+    pub fn is_new(&self) -> bool {
+        !self.0.is_empty() && self.0.values().all(|diff| diff.old.is_none())
+    }
+
     pub fn extract_costs(&self) -> (Option<Costs<K>>, Option<Costs<K>>) {
         let mut new_costs: Costs<K> = Costs::empty();
         let mut old_costs: Costs<K> = Costs::empty();
@@ -610,6 +1016,251 @@ impl FlamegraphSummary {
     }
 }
 
+impl MergedSummaries {
+    /// Load the `MergedSummaries` from the file at `path`
+    ///
+    /// If the file doesn't exist, the `MergedSummaries` are empty.
+    ///
+    /// # Errors
+    ///
+    /// If the file could not be read or [`MergedSummaries::from_reader`] fails
+    pub fn load(path: &Path) -> Result<Self> {
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+        let file = File::open(path)
+            .with_context(|| format!("Failed to open the merged summary '{}'", path.display()))?;
+        Self::from_reader(BufReader::new(file))
+            .with_context(|| format!("Failed to read the merged summary '{}'", path.display()))
+    }
+
+    /// Read the `MergedSummaries` from JSON [`BenchmarkSummary`]s
+    ///
+    /// See [`BenchmarkSummary::read_all`] for the supported JSON.
+    ///
+    /// # Errors
+    ///
+    /// If [`BenchmarkSummary::read_all`] fails or the JSON contains the same benchmark more than
+    /// once
+    pub fn from_reader<R: Read>(reader: R) -> Result<Self> {
+        let mut merged = Self::default();
+        merged.merge(&BenchmarkSummary::read_all(reader)?)?;
+        Ok(merged)
+    }
+
+    /// Merge the `summaries` into these `MergedSummaries`
+    ///
+    /// Either all or none of the `summaries` are merged.
+    ///
+    /// # Errors
+    ///
+    /// If a benchmark of the `summaries` is already present or is present in the `summaries` more
+    /// than once
+    pub fn merge(&mut self, summaries: &[BenchmarkSummary]) -> Result<()> {
+        let mut merged = IndexMap::new();
+        let mut collisions = vec![];
+        for summary in summaries {
+            let key = (summary.module_path.clone(), summary.id.clone());
+            if self.0.contains_key(&key) || merged.contains_key(&key) {
+                collisions.push(format!("'{}'", summary.name()));
+            } else {
+                let value = serde_json::to_value(summary)
+                    .context("Failed to serialize the benchmark summary")?;
+                merged.insert(key, value);
+            }
+        }
+
+        if collisions.is_empty() {
+            self.0.extend(merged);
+            Ok(())
+        } else {
+            Err(anyhow!(
+                "Duplicate benchmark summaries: {}",
+                collisions.join(", ")
+            ))
+        }
+    }
+
+    /// Return the merged [`BenchmarkSummary`]s in the order in which they were merged
+    ///
+    /// # Errors
+    ///
+    /// If a merged summary is not a valid [`BenchmarkSummary`]
+    pub fn summaries(&self) -> Result<Vec<BenchmarkSummary>> {
+        self.0
+            .values()
+            .cloned()
+            .map(BenchmarkSummary::from_value)
+            .collect()
+    }
+
+    /// Return the number of merged summaries
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// Save the `MergedSummaries` as pretty json array to the file at `path`
+    ///
+    /// # Errors
+    ///
+    /// If the file could not be created or written
+    pub fn save(&self, path: &Path) -> Result<()> {
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)
+                .with_context(|| format!("Failed to create directory '{}'", parent.display()))?;
+        }
+        let file = File::create(path)
+            .with_context(|| format!("Failed to create the merged summary '{}'", path.display()))?;
+        serde_json::to_writer_pretty(file, &self.0.values().collect::<Vec<_>>())
+            .with_context(|| format!("Failed to write the merged summary '{}'", path.display()))
+    }
+}
+
+impl RegressionsReport {
+    /// Create a new and empty `RegressionsReport`
+    pub fn new() -> Self {
+        Self {
+            version: REGRESSIONS_VERSION.to_owned(),
+            regressions: Vec::default(),
+        }
+    }
+
+    /// Add all regressions of the callgrind runs of the [`BenchmarkSummary`]
+    ///
+    /// Returns true if the `summary` had any regressions.
+    pub fn add(&mut self, summary: &BenchmarkSummary) -> bool {
+        let Some(callgrind_summary) = &summary.callgrind_summary else {
+            return false;
+        };
+
+        let benchmark = summary.name();
+        let len = self.regressions.len();
+        self.regressions.extend(
+            callgrind_summary
+                .summaries
+                .iter()
+                .flat_map(|run| run.regressions.iter())
+                .map(|regression| RegressionsReportEntry {
+                    benchmark: benchmark.clone(),
+                    event: regression.event_kind,
+                    baseline: regression.old,
+                    new: regression.new,
+                    pct: regression.diff_pct,
+                }),
+        );
+
+        self.regressions.len() != len
+    }
+}
+
+impl RegressionsReportEntry {
+    /// Return the `num` worst regressions of all events of all [`BenchmarkSummary`]s
+    ///
+    /// All events of the callgrind runs with an increase of the new cost compared to the old cost
+    /// are ranked by the difference in percent and then by the absolute difference, both in
+    /// descending order. If an `event_kind` is given, only this event is ranked. Unlike the
+    /// [`CallgrindRegressionSummary`], this doesn't depend on the limits of the
+    /// [`crate::api::RegressionConfig`].
+    pub fn top(
+        summaries: &[BenchmarkSummary],
+        num: usize,
+        event_kind: Option<EventKind>,
+    ) -> Vec<Self> {
+        let mut entries = summaries
+            .iter()
+            .filter_map(|summary| {
+                summary
+                    .callgrind_summary
+                    .as_ref()
+                    .map(|callgrind_summary| (summary.name(), callgrind_summary))
+            })
+            .flat_map(|(benchmark, callgrind_summary)| {
+                callgrind_summary
+                    .summaries
+                    .iter()
+                    .flat_map(|run| run.events.all_diffs())
+                    .filter(move |(event, _)| event_kind.map_or(true, |kind| **event == kind))
+                    .filter_map(
+                        move |(event, diff)| match (diff.new, diff.old, diff.diff_pct) {
+                            (Some(new), Some(old), Some(pct)) if new > old => Some(Self {
+                                benchmark: benchmark.clone(),
+                                event: *event,
+                                baseline: old,
+                                new,
+                                pct,
+                            }),
+                            _ => None,
+                        },
+                    )
+            })
+            .collect::<Vec<_>>();
+
+        entries.sort_by(|a, b| {
+            b.pct
+                .total_cmp(&a.pct)
+                .then_with(|| (b.new - b.baseline).cmp(&(a.new - a.baseline)))
+        });
+        entries.truncate(num);
+        entries
+    }
+}
+
+impl Default for RegressionsReport {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl RegressionsOutput {
+    /// Create a new `RegressionsOutput` writing to the `regressions.json` file in `dir`
+    pub fn new(dir: &Path) -> Self {
+        Self {
+            path: dir.join("regressions.json"),
+            report: RegressionsReport::new(),
+        }
+    }
+
+    /// Initialize this `RegressionsOutput` replacing an old file with an empty report
+    pub fn init(&self) -> Result<()> {
+        if let Some(parent) = self.path.parent() {
+            std::fs::create_dir_all(parent)
+                .with_context(|| format!("Failed to create directory '{}'", parent.display()))?;
+        }
+        self.save()
+    }
+
+    /// Add the regressions of the [`BenchmarkSummary`] and update the file if there were any
+    ///
+    /// The file is updated immediately and not only after all benchmarks have finished, so the
+    /// file is complete even if the benchmark run is aborted early, for example with `fail_fast`.
+    pub fn add(&mut self, summary: &BenchmarkSummary) -> Result<()> {
+        if self.report.add(summary) {
+            self.save()?;
+        }
+        Ok(())
+    }
+
+    /// Return the [`RegressionsReport`]
+    pub fn report(&self) -> &RegressionsReport {
+        &self.report
+    }
+
+    fn save(&self) -> Result<()> {
+        let file = File::create(&self.path).with_context(|| {
+            format!(
+                "Failed to create regressions file '{}'",
+                self.path.display()
+            )
+        })?;
+        serde_json::to_writer_pretty(file, &self.report)
+            .with_context(|| format!("Failed to write regressions file '{}'", self.path.display()))
+    }
+}
+
 impl SummaryOutput {
     /// Create a new `SummaryOutput` with `dir` as base dir and an extension fitting the
     /// [`SummaryFormat`]
@@ -642,3 +1293,500 @@ impl SummaryOutput {
         File::create(&self.path).with_context(|| "Failed to create json summary file")
     }
 }
+
+/// The [`CallgrindSummary::tool`] of summaries without this field
+fn default_callgrind_summary_tool() -> ValgrindTool {
+    ValgrindTool::Callgrind
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+    use rstest::rstest;
+
+    use super::*;
+
+    fn benchmark_summary(
+        module_path: &str,
+        id: Option<&str>,
+        ir: u64,
+        regressions: Vec<CallgrindRegressionSummary>,
+    ) -> BenchmarkSummary {
+        let mut summary = BenchmarkSummary::new(
+            BenchmarkKind::LibraryBenchmark,
+            PathBuf::from("/root"),
+            PathBuf::from("/root/package"),
+            PathBuf::from("benches/bench.rs"),
+            PathBuf::from("/root/target/bench"),
+            &module_path.split("::").collect::<Vec<_>>(),
+            id.map(ToOwned::to_owned),
+            None,
+            None,
+        );
+        summary.callgrind_summary = Some(CallgrindSummary {
+            tool: ValgrindTool::Callgrind,
+            log_paths: vec![],
+            out_paths: vec![],
+            flamegraphs: vec![],
+            summaries: vec![CallgrindRunSummary {
+                command: "bench".to_owned(),
+                baseline: None,
+                events: CostsSummary::new(
+                    &Costs::with_event_kinds([(EventKind::Ir, ir), (EventKind::Dr, 1)]),
+                    None,
+                ),
+                regressions,
+                peak_rss: None,
+                valgrind_wall_time: None,
+                deterministic: None,
+                region: None,
+                is_new: true,
+            }],
+        });
+        summary
+    }
+
+    fn summary_json(version: &str, module_path: &str, id: Option<&str>, ir: u64) -> String {
+        let mut summary = benchmark_summary(module_path, id, ir, vec![]);
+        summary.version = version.to_owned();
+        serde_json::to_string(&summary).unwrap()
+    }
+
+    fn regression(event_kind: EventKind, new: u64, old: u64) -> CallgrindRegressionSummary {
+        CallgrindRegressionSummary {
+            event_kind,
+            new,
+            old,
+            diff_pct: percentage_diff(new, old),
+            limit: 5.0,
+        }
+    }
+
+    #[rstest]
+    #[case::single(summary_json("2", "bench::group::func", Some("id"), 10))]
+    #[case::array(format!(
+        "[{}, {}]",
+        summary_json("2", "bench::group::other", None, 20),
+        summary_json("2", "bench::group::func", Some("id"), 10)
+    ))]
+    #[case::concatenated(format!(
+        "{}\n{}",
+        summary_json("2", "bench::group::func", Some("id"), 10),
+        summary_json("2", "bench::group::func", None, 20)
+    ))]
+    fn test_baseline_summaries_from_reader(#[case] json: String) {
+        let summaries = BaselineSummaries::from_reader(json.as_bytes()).unwrap();
+
+        assert_eq!(
+            summaries.costs("bench::group::func", Some("id")),
+            Some(&Costs::with_event_kinds([
+                (EventKind::Ir, 10),
+                (EventKind::Dr, 1)
+            ]))
+        );
+        assert_eq!(summaries.costs("bench::group::func", Some("other")), None);
+        assert_eq!(summaries.costs("bench::group::missing", None), None);
+    }
+
+    #[rstest]
+    #[case::version_mismatch(
+        summary_json("1", "bench::group::func", None, 10),
+        "Unsupported version '1' of the summary format. Expected version '2'"
+    )]
+    #[case::missing_version(
+        r#"{"kind": "LibraryBenchmark"}"#.to_owned(),
+        "Missing version of the summary format"
+    )]
+    #[case::invalid_json("{".to_owned(), "Invalid json")]
+    fn test_baseline_summaries_from_reader_when_invalid(
+        #[case] json: String,
+        #[case] expected: &str,
+    ) {
+        assert_eq!(
+            BaselineSummaries::from_reader(json.as_bytes())
+                .unwrap_err()
+                .to_string(),
+            expected
+        );
+    }
+
+    #[test]
+    fn test_merged_summaries_merge_and_save() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("merged").join("summaries.json");
+
+        let mut merged = MergedSummaries::load(&path).unwrap();
+        assert!(merged.is_empty());
+        merged
+            .merge(&[
+                benchmark_summary("first::group::func", None, 10, vec![]),
+                benchmark_summary("first::group::func", Some("id"), 20, vec![]),
+            ])
+            .unwrap();
+        merged.save(&path).unwrap();
+
+        // A second shard with another benchmark file
+        let mut merged = MergedSummaries::load(&path).unwrap();
+        assert_eq!(merged.len(), 2);
+        merged
+            .merge(&[benchmark_summary("second::group::func", None, 30, vec![])])
+            .unwrap();
+        merged.save(&path).unwrap();
+
+        let summaries = BenchmarkSummary::read_all(File::open(&path).unwrap()).unwrap();
+        assert_eq!(merged.summaries().unwrap(), summaries);
+        assert_eq!(
+            summaries
+                .iter()
+                .map(BenchmarkSummary::name)
+                .collect::<Vec<_>>(),
+            vec![
+                "first::group::func",
+                "first::group::func id",
+                "second::group::func"
+            ]
+        );
+        assert_eq!(
+            summaries[2],
+            benchmark_summary("second::group::func", None, 30, vec![])
+        );
+
+        let baselines = BaselineSummaries::load(&path).unwrap();
+        assert_eq!(
+            baselines.costs("first::group::func", Some("id")),
+            Some(&Costs::with_event_kinds([
+                (EventKind::Ir, 20),
+                (EventKind::Dr, 1)
+            ]))
+        );
+    }
+
+    #[rstest]
+    #[case::existing(
+        vec![benchmark_summary("bench::group::func", Some("id"), 20, vec![])],
+        "Duplicate benchmark summaries: 'bench::group::func id'"
+    )]
+    #[case::within(
+        vec![
+            benchmark_summary("bench::group::other", None, 10, vec![]),
+            benchmark_summary("bench::group::other", None, 20, vec![]),
+            benchmark_summary("bench::group::func", None, 20, vec![]),
+        ],
+        "Duplicate benchmark summaries: 'bench::group::other', 'bench::group::func'"
+    )]
+    fn test_merged_summaries_merge_when_collision(
+        #[case] summaries: Vec<BenchmarkSummary>,
+        #[case] expected: &str,
+    ) {
+        let json = format!(
+            "[{}, {}]",
+            summary_json("2", "bench::group::func", None, 10),
+            summary_json("2", "bench::group::func", Some("id"), 10)
+        );
+        let mut merged = MergedSummaries::from_reader(json.as_bytes()).unwrap();
+        let expected_merged = merged.clone();
+
+        assert_eq!(merged.merge(&summaries).unwrap_err().to_string(), expected);
+        assert_eq!(merged, expected_merged);
+    }
+
+    #[rstest]
+    #[case::version_mismatch(
+        summary_json("1", "bench::group::func", None, 10),
+        "Unsupported version '1' of the summary format. Expected version '2'"
+    )]
+    #[case::duplicate(
+        format!(
+            "{}\n{}",
+            summary_json("2", "bench::group::func", None, 10),
+            summary_json("2", "bench::group::func", None, 20)
+        ),
+        "Duplicate benchmark summaries: 'bench::group::func'"
+    )]
+    fn test_merged_summaries_from_reader_when_invalid(
+        #[case] json: String,
+        #[case] expected: &str,
+    ) {
+        assert_eq!(
+            MergedSummaries::from_reader(json.as_bytes())
+                .unwrap_err()
+                .to_string(),
+            expected
+        );
+    }
+
+    #[rstest]
+    #[case::no_regressions(None, vec![], vec![])]
+    #[case::without_id(
+        None,
+        vec![regression(EventKind::Ir, 20, 10)],
+        vec![("bench::group::func", EventKind::Ir, 10, 20, 100.0_f64)]
+    )]
+    #[case::with_id(
+        Some("id"),
+        vec![regression(EventKind::Ir, 15, 10), regression(EventKind::Dr, 3, 4)],
+        vec![
+            ("bench::group::func id", EventKind::Ir, 10, 15, 50.0_f64),
+            ("bench::group::func id", EventKind::Dr, 4, 3, -25.0_f64)
+        ]
+    )]
+    fn test_regressions_report_add(
+        #[case] id: Option<&str>,
+        #[case] regressions: Vec<CallgrindRegressionSummary>,
+        #[case] expected: Vec<(&str, EventKind, u64, u64, f64)>,
+    ) {
+        let mut report = RegressionsReport::new();
+        let summary = benchmark_summary("bench::group::func", id, 10, regressions);
+
+        assert_eq!(report.add(&summary), !expected.is_empty());
+        assert_eq!(
+            report,
+            RegressionsReport {
+                version: REGRESSIONS_VERSION.to_owned(),
+                regressions: expected
+                    .into_iter()
+                    .map(
+                        |(benchmark, event, baseline, new, pct)| RegressionsReportEntry {
+                            benchmark: benchmark.to_owned(),
+                            event,
+                            baseline,
+                            new,
+                            pct
+                        }
+                    )
+                    .collect()
+            }
+        );
+    }
+
+    #[test]
+    fn test_regressions_report_serialize() {
+        let mut report = RegressionsReport::new();
+        report.add(&benchmark_summary(
+            "bench::group::func",
+            Some("id"),
+            10,
+            vec![regression(EventKind::Ir, 15, 10)],
+        ));
+
+        assert_eq!(
+            serde_json::to_string(&RegressionsReport::new()).unwrap(),
+            r#"{"version":"1","regressions":[]}"#
+        );
+        assert_eq!(
+            serde_json::to_string(&report).unwrap(),
+            r#"{"version":"1","regressions":[{"benchmark":"bench::group::func id","event":"Ir","baseline":10,"new":15,"pct":50.0}]}"#
+        );
+    }
+
+    fn summary_with_costs(
+        module_path: &str,
+        new: &[(EventKind, u64)],
+        old: &[(EventKind, u64)],
+    ) -> BenchmarkSummary {
+        let mut summary = benchmark_summary(module_path, None, 0, vec![]);
+        summary.callgrind_summary.as_mut().unwrap().summaries[0].events = CostsSummary::new(
+            &Costs::with_event_kinds(new.iter().copied()),
+            Some(&Costs::with_event_kinds(old.iter().copied())),
+        );
+        summary
+    }
+
+    #[rstest]
+    #[case::zero(0, None, vec![])]
+    #[case::one(1, None, vec![("bench::a", EventKind::Ir, 10, 20)])]
+    #[case::all(10, None, vec![
+        ("bench::a", EventKind::Ir, 10, 20),
+        ("bench::b", EventKind::Ir, 100, 150),
+        ("bench::a", EventKind::Dr, 10, 15),
+        ("bench::b", EventKind::Dr, 10, 11),
+    ])]
+    #[case::event_kind(10, Some(EventKind::Dr), vec![
+        ("bench::a", EventKind::Dr, 10, 15),
+        ("bench::b", EventKind::Dr, 10, 11),
+    ])]
+    #[case::event_kind_no_regressions(10, Some(EventKind::Dw), vec![])]
+    fn test_regressions_report_entry_top(
+        #[case] num: usize,
+        #[case] event_kind: Option<EventKind>,
+        #[case] expected: Vec<(&str, EventKind, u64, u64)>,
+    ) {
+        let summaries = [
+            summary_with_costs(
+                "bench::a",
+                &[(EventKind::Ir, 20), (EventKind::Dr, 15), (EventKind::Dw, 5)],
+                &[
+                    (EventKind::Ir, 10),
+                    (EventKind::Dr, 10),
+                    (EventKind::Dw, 10),
+                ],
+            ),
+            summary_with_costs(
+                "bench::b",
+                &[
+                    (EventKind::Ir, 150),
+                    (EventKind::Dr, 11),
+                    (EventKind::Dw, 10),
+                ],
+                &[
+                    (EventKind::Ir, 100),
+                    (EventKind::Dr, 10),
+                    (EventKind::Dw, 10),
+                ],
+            ),
+            benchmark_summary("bench::c", None, 10, vec![]),
+        ];
+
+        let expected = expected
+            .into_iter()
+            .map(|(benchmark, event, baseline, new)| RegressionsReportEntry {
+                benchmark: benchmark.to_owned(),
+                event,
+                baseline,
+                new,
+                pct: percentage_diff(new, baseline),
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            RegressionsReportEntry::top(&summaries, num, event_kind),
+            expected
+        );
+    }
+
+    #[test]
+    fn test_regressions_output_init_and_add() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("bench").join("regressions.json");
+        let read = || -> RegressionsReport {
+            serde_json::from_reader(File::open(&path).unwrap()).unwrap()
+        };
+
+        let mut output = RegressionsOutput::new(&dir.path().join("bench"));
+        output.init().unwrap();
+        assert_eq!(read(), RegressionsReport::new());
+
+        output
+            .add(&benchmark_summary("bench::group::func", None, 10, vec![]))
+            .unwrap();
+        assert_eq!(read(), RegressionsReport::new());
+
+        output
+            .add(&benchmark_summary(
+                "bench::group::func",
+                None,
+                10,
+                vec![regression(EventKind::Ir, 20, 10)],
+            ))
+            .unwrap();
+        assert_eq!(output.report().regressions.len(), 1);
+        assert_eq!(&read(), output.report());
+    }
+
+    #[rstest]
+    #[case::no_old(None, true)]
+    #[case::with_old(Some(Costs::with_event_kinds([(EventKind::Ir, 10)])), false)]
+    #[case::with_other_old(Some(Costs::with_event_kinds([(EventKind::Dr, 10)])), false)]
+    fn test_costs_summary_is_new(#[case] old: Option<Costs<EventKind>>, #[case] expected: bool) {
+        let new = Costs::with_event_kinds([(EventKind::Ir, 10)]);
+        assert_eq!(CostsSummary::new(&new, old.as_ref()).is_new(), expected);
+    }
+
+    #[test]
+    fn test_benchmark_summary_when_new_then_not_regressed() {
+        let summary = benchmark_summary("bench::group::func", None, 10, vec![]);
+        assert!(summary.is_new());
+        assert!(!summary.is_regressed());
+
+        let mut is_regressed = false;
+        summary.check_regression(&mut is_regressed, true).unwrap();
+        assert!(!is_regressed);
+        assert!(!RegressionsReport::new().add(&summary));
+    }
+
+    #[rstest]
+    #[case::no_error_exit(false)]
+    #[case::error_exit(true)]
+    fn test_benchmark_summary_check_tool_errors(#[case] is_error_exit: bool) {
+        let mut summary = benchmark_summary("bench::group::func", None, 10, vec![]);
+        summary.tool_summaries.push(ToolSummary {
+            tool: ValgrindTool::Memcheck,
+            log_paths: vec![],
+            out_paths: vec![],
+            summaries: vec![ToolRunSummary {
+                command: "bench".to_owned(),
+                old_pid: None,
+                old_parent_pid: None,
+                pid: Some(1i32),
+                parent_pid: None,
+                summary: IndexMap::new(),
+                details: None,
+                error_summary: Some(ErrorSummary {
+                    errors: 2,
+                    contexts: 1,
+                    supp_errors: 0,
+                    supp_contexts: 0,
+                }),
+                error_kinds: indexmap! {"Invalid read of size 4".to_owned() => 1},
+                costs_summary: None,
+                log_path: PathBuf::from("bench.memcheck.log"),
+            }],
+            valgrind_wall_time: None,
+            is_error_exit,
+        });
+
+        let value = serde_json::to_value(&summary).unwrap();
+        assert_eq!(
+            value["tool_summaries"][0]["summaries"][0]["error_kinds"]["Invalid read of size 4"],
+            1u64
+        );
+        assert!(value["tool_summaries"][0].get("is_error_exit").is_none());
+
+        let result = summary.check_tool_errors();
+        if is_error_exit {
+            assert_eq!(
+                result.unwrap_err().downcast::<Error>().unwrap(),
+                Error::ToolErrors((
+                    "memcheck".to_owned(),
+                    ErrorSummary {
+                        errors: 2,
+                        contexts: 1,
+                        supp_errors: 0,
+                        supp_contexts: 0,
+                    },
+                    indexmap! {"Invalid read of size 4".to_owned() => 1},
+                ))
+            );
+        } else {
+            result.unwrap();
+        }
+    }
+
+    #[test]
+    fn test_callgrind_run_summary_deserialize_without_is_new() {
+        let summary = benchmark_summary("bench::group::func", None, 10, vec![]);
+        let mut value = serde_json::to_value(&summary).unwrap();
+        value["callgrind_summary"]["summaries"][0]
+            .as_object_mut()
+            .unwrap()
+            .remove("is_new");
+
+        let deserialized: BenchmarkSummary = serde_json::from_value(value).unwrap();
+        assert!(!deserialized.is_new());
+    }
+
+    #[test]
+    fn test_callgrind_summary_deserialize_without_tool() {
+        let summary = benchmark_summary("bench::group::func", None, 10, vec![]);
+        let mut value = serde_json::to_value(&summary).unwrap();
+        value["callgrind_summary"]
+            .as_object_mut()
+            .unwrap()
+            .remove("tool");
+
+        let deserialized: BenchmarkSummary = serde_json::from_value(value).unwrap();
+        assert_eq!(
+            deserialized.callgrind_summary.unwrap().tool,
+            ValgrindTool::Callgrind
+        );
+    }
+}
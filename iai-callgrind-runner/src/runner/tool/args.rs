@@ -26,6 +26,7 @@ impl ToolArgs {
                     "201".to_owned()
                 }
                 ValgrindTool::Callgrind
+                | ValgrindTool::Cachegrind
                 | ValgrindTool::Massif
                 | ValgrindTool::DHAT
                 | ValgrindTool::BBV => "0".to_owned(),
@@ -84,7 +85,9 @@ impl ToolArgs {
         }
 
         match self.tool {
-            ValgrindTool::Callgrind => unreachable!("Callgrind is not managed here"),
+            ValgrindTool::Callgrind | ValgrindTool::Cachegrind => {
+                unreachable!("Callgrind and cachegrind are not managed here")
+            }
             ValgrindTool::Massif => {
                 let mut arg = OsString::from("--massif-out-file=");
                 let massif_out_path = if let Some(modifier) = modifier {
@@ -144,6 +147,17 @@ impl ToolArgs {
         self.log_path = Some(arg);
     }
 
+    /// Return true if valgrind exited with the `--error-exitcode` because the tool detected errors
+    ///
+    /// An `--error-exitcode` of `0` disables this exit code, so this method returns false for any
+    /// exit code.
+    pub fn is_error_exit(&self, code: Option<i32>) -> bool {
+        match (code, self.error_exitcode.parse::<i32>()) {
+            (Some(code), Ok(error_exitcode)) => error_exitcode != 0 && code == error_exitcode,
+            _ => false,
+        }
+    }
+
     pub fn to_vec(&self) -> Vec<OsString> {
         let mut vec: Vec<OsString> = vec![];
 
@@ -3,7 +3,9 @@ use std::fmt::Display;
 use anyhow::Result;
 use colored::Colorize;
 
-use crate::runner::format::{format_vertical, NOT_AVAILABLE};
+use crate::runner::format::{
+    format_vertical, DiffFormat, EventFilter, NumberFormat, NOT_AVAILABLE,
+};
 use crate::runner::summary::ToolRunSummary;
 
 pub struct ToolRunSummaryFormatter;
@@ -39,6 +41,9 @@ impl ToolRunSummaryFormatter {
         verbose: bool,
         is_multiple: bool,
         force_show_body: bool,
+        number_format: NumberFormat,
+        diff_format: DiffFormat,
+        event_filter: Option<&EventFilter>,
     ) -> Result<()> {
         if verbose || is_multiple {
             println!("  {:<18}{}", "Command:", summary.command.blue().bold());
@@ -53,7 +58,17 @@ impl ToolRunSummaryFormatter {
         }
 
         if let Some(costs) = &summary.costs_summary {
-            print!("{}", format_vertical((None, None), costs.all_diffs())?);
+            print!(
+                "{}",
+                format_vertical(
+                    (None, None),
+                    costs
+                        .all_diffs()
+                        .filter(|(_, d)| event_filter.map_or(true, |filter| filter.is_shown(d))),
+                    number_format,
+                    diff_format
+                )?
+            );
         }
 
         for field in &summary.summary {
@@ -85,6 +100,18 @@ impl ToolRunSummaryFormatter {
             );
         }
 
+        let mut error_kinds = summary.error_kinds.iter();
+        if let Some((kind, count)) = error_kinds.next() {
+            println!(
+                "  {:<18}{kind}: {}",
+                "Error Kinds:",
+                count.to_string().bold()
+            );
+            for (kind, count) in error_kinds {
+                println!("                    {kind}: {}", count.to_string().bold());
+            }
+        }
+
         if summary.costs_summary.is_none() || verbose {
             println!(
                 "  {:<18}{}",
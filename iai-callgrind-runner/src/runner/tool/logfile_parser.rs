@@ -4,6 +4,7 @@ use std::path::PathBuf;
 use std::str::FromStr;
 
 use anyhow::{Context, Result};
+use indexmap::IndexMap;
 use lazy_static::lazy_static;
 use log::debug;
 use regex::Regex;
@@ -36,6 +37,16 @@ lazy_static! {
         r"^.*?(?<err>[0-9]+).*(<?<ctxs>[0-9]+).*(<?<s_err>[0-9]+).*(<?<s_ctxs>[0-9]+)$"
     )
     .expect("Regex should compile");
+    static ref ERROR_KIND_LEAK_RE: Regex = regex::Regex::new(
+        r"^[0-9,]+ (\([0-9,]+ direct, [0-9,]+ indirect\) )?bytes in [0-9,]+ blocks are (?<kind>definitely lost|indirectly lost|possibly lost|still reachable) in loss record"
+    )
+    .expect("Regex should compile");
+    static ref ERROR_KIND_SUFFIX_RE: Regex =
+        regex::Regex::new(r"\s+(at 0x[0-9A-Fa-f]+|by thread).*$").expect("Regex should compile");
+    static ref ERROR_KIND_THREAD_RE: Regex =
+        regex::Regex::new(r"^Thread #?[0-9]+:$").expect("Regex should compile");
+    static ref ADDRESS_RE: Regex =
+        regex::Regex::new(r"0x[0-9A-Fa-f]+").expect("Regex should compile");
 }
 
 pub struct ToolLogfileParser {
@@ -50,6 +61,7 @@ pub struct LogfileSummary {
     pub fields: Vec<(String, String)>,
     pub details: Vec<String>,
     pub error_summary: Option<ErrorSummary>,
+    pub error_kinds: IndexMap<String, u64>,
     pub costs: Option<Costs<String>>,
     pub log_path: PathBuf,
 }
@@ -106,6 +118,7 @@ impl LogfileSummary {
             summary: self.fields.into_iter().collect(),
             details: (!self.details.is_empty()).then(|| self.details.join("\n")),
             error_summary: self.error_summary,
+            error_kinds: self.error_kinds,
             costs_summary: None,
             log_path: self.log_path,
         }
@@ -234,6 +247,12 @@ impl LogfileParser for ToolLogfileParser {
             }
         }
 
+        let error_kinds = if error_summary.is_some() {
+            extract_error_kinds(&details)
+        } else {
+            IndexMap::default()
+        };
+
         Ok(LogfileSummary {
             command: command.expect("A command should be present"),
             pid,
@@ -241,6 +260,7 @@ impl LogfileParser for ToolLogfileParser {
             fields: Vec::default(),
             details,
             error_summary,
+            error_kinds,
             log_path: make_relative(&self.root_dir, path),
             costs: None,
         })
@@ -268,6 +288,60 @@ pub fn extract_pid(line: &str) -> i32 {
         .expect("Pid should be valid")
 }
 
+/// Count the errors of Memcheck, Helgrind or DRD in the `details` of a log file by their kind
+///
+/// Each error is a block separated by empty lines starting with the description of the error like
+/// `Invalid read of size 4` followed by the stack trace. The kind of the error is this description
+/// without addresses and threads. Valgrind prints each error context only once, so the count of a
+/// kind is the number of error contexts of this kind and not the number of errors.
+pub fn extract_error_kinds(details: &[String]) -> IndexMap<String, u64> {
+    let mut error_kinds = IndexMap::new();
+    let mut head = None;
+    let mut is_error = false;
+    let mut is_skipped = false;
+    let mut is_announcement = false;
+    for line in details
+        .iter()
+        .map(String::as_str)
+        .chain(std::iter::once(""))
+    {
+        let trimmed = line.trim();
+        if trimmed.is_empty() || EMPTY_LINE_RE.is_match(line) || trimmed.starts_with("---") {
+            if let (Some(head), true) = (head.take(), is_error) {
+                *error_kinds.entry(error_kind(head)).or_insert(0) += 1;
+            }
+            is_error = false;
+            is_skipped = false;
+            if trimmed.starts_with("---") {
+                // Helgrind announces the threads and locks of the following errors
+                is_announcement = trimmed.contains("Announcement");
+            }
+        } else if is_announcement || is_skipped {
+            // Not the description of an error
+        } else if head.is_some() {
+            if trimmed.starts_with("at 0x") || trimmed.starts_with("by 0x") {
+                is_error = true;
+            }
+        } else if line.starts_with(char::is_whitespace) || line.starts_with("This conflicts with") {
+            // Details of the previous error like the address or the conflicting access of Helgrind
+            is_skipped = true;
+        } else if !ERROR_KIND_THREAD_RE.is_match(line) {
+            head = Some(line);
+        } else {
+            // The thread of the following error of DRD
+        }
+    }
+    error_kinds
+}
+
+fn error_kind(head: &str) -> String {
+    if let Some(caps) = ERROR_KIND_LEAK_RE.captures(head) {
+        return format!("Leak {}", caps.name("kind").unwrap().as_str());
+    }
+    let head = ERROR_KIND_SUFFIX_RE.replace(head, "");
+    ADDRESS_RE.replace_all(&head, "0x...").into_owned()
+}
+
 impl ValgrindTool {
     pub fn to_parser(self, root_dir: PathBuf) -> Box<dyn LogfileParser> {
         match self {
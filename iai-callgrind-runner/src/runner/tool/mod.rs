@@ -6,13 +6,15 @@ pub mod logfile_parser;
 use std::ffi::OsString;
 use std::fmt::Display;
 use std::fs::File;
-use std::io::{stderr, BufRead, BufReader, Write};
+use std::io::{self, stderr, BufRead, BufReader, Write};
 use std::path::{Path, PathBuf};
-use std::process::{Command, Output, Stdio};
+use std::process::{Command, ExitStatus, Output, Stdio};
+use std::time::{Duration, Instant};
 
 use anyhow::{anyhow, Context, Result};
 use colored::Colorize;
-use log::{debug, error, log_enabled, Level};
+use log::{debug, error, log_enabled, warn, Level};
+use regex::Regex;
 #[cfg(feature = "schema")]
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
@@ -20,10 +22,17 @@ use serde::{Deserialize, Serialize};
 use self::args::ToolArgs;
 use self::format::ToolRunSummaryFormatter;
 use self::logfile_parser::LogfileSummary;
-use super::format::{tool_headline, OutputFormat};
+use super::cachegrind::summary_parser::SummaryParser as CachegrindSummaryParser;
+use super::callgrind::flamegraph::Config as FlamegraphConfig;
+use super::callgrind::model::Costs;
+use super::callgrind::parser::Sentinel;
+use super::callgrind::sentinel_parser::SentinelParser;
+use super::callgrind::summary_parser::SummaryParser as CallgrindSummaryParser;
+use super::callgrind::RegressionConfig;
+use super::format::{is_inline_output, print_valgrind_time, tool_headline};
 use super::meta::Metadata;
 use super::summary::{BaselineKind, ToolRunSummary, ToolSummary};
-use crate::api::{self, ExitWith};
+use crate::api::{self, EventKind, ExitWith, FlamegraphKind};
 use crate::error::Error;
 use crate::util::{self, make_relative, resolve_binary_path, truncate_str_utf8};
 
@@ -33,9 +42,26 @@ pub struct RunOptions {
     pub current_dir: Option<PathBuf>,
     pub entry_point: Option<String>,
     pub exit_with: Option<ExitWith>,
+    pub output_assertions: Vec<OutputAssertion>,
     pub envs: Vec<(OsString, OsString)>,
 }
 
+/// The output stream of a benchmarked binary
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OutputStream {
+    Stdout,
+    Stderr,
+}
+
+/// The compiled form of an [`api::OutputAssertion`]
+#[derive(Debug, Clone)]
+pub enum OutputAssertion {
+    /// The output stream has to contain the string
+    Contains(OutputStream, String),
+    /// The output stream has to match the regex
+    Matches(OutputStream, Regex),
+}
+
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct ToolConfig {
     pub tool: ValgrindTool,
@@ -47,14 +73,24 @@ pub struct ToolConfig {
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct ToolConfigs(pub Vec<ToolConfig>);
 
+/// The delay before the first retry of a transient valgrind failure which doubles with each retry
+const RETRY_BASE_DELAY: Duration = Duration::from_millis(500);
+
 pub struct ToolCommand {
     tool: ValgrindTool,
     command: Command,
+    retries: u32,
 }
 
 pub struct ToolOutput {
     pub tool: ValgrindTool,
     pub output: Output,
+    /// The peak resident set size in kilobytes of the valgrind process if it was recorded
+    pub peak_rss: Option<u64>,
+    /// The wall-clock time of the valgrind process from the start to the exit if it was recorded
+    ///
+    /// This time includes the overhead of valgrind and is not a benchmark result.
+    pub wall_time: Option<Duration>,
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -63,6 +99,9 @@ pub struct ToolOutputPath {
     pub tool: ValgrindTool,
     pub baseline_kind: BaselineKind,
     pub dir: PathBuf,
+    /// The directory of the [`ToolOutputPathKind::Base`] and [`ToolOutputPathKind::BaseLog`]
+    /// files. This is the same directory as `dir` unless `--baseline-dir` is given.
+    pub baseline_dir: PathBuf,
     pub name: String,
     pub modifiers: Vec<String>,
 }
@@ -75,12 +114,16 @@ pub enum ToolOutputPathKind {
     OldLog,
     BaseLog(String),
     Base(String),
+    Meta,
+    OldMeta,
+    BaseMeta(String),
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 #[cfg_attr(feature = "schema", derive(JsonSchema))]
 pub enum ValgrindTool {
     Callgrind,
+    Cachegrind,
     Memcheck,
     Helgrind,
     DRD,
@@ -95,11 +138,24 @@ pub trait Parser {
     fn parse(&self, output: &ToolOutputPath) -> Result<Self::Output>;
 }
 
+/// A parser for the [`Costs`] of the valgrind tool which measures the benchmark
+///
+/// Callgrind and cachegrind write their costs to output files. All other tools report in their log
+/// files which are parsed with a [`logfile_parser::LogfileParser`].
+pub trait ToolParser {
+    /// The valgrind tool whose output is parsed
+    fn tool(&self) -> ValgrindTool;
+
+    /// Parse the [`Costs`] from the output at the `output_path`
+    fn parse_costs(&self, output_path: &ToolOutputPath) -> Result<Costs>;
+}
+
 impl ToolCommand {
     pub fn new(tool: ValgrindTool, meta: &Metadata) -> Self {
         Self {
             tool,
             command: meta.into(),
+            retries: meta.args.retries.unwrap_or_default(),
         }
     }
 
@@ -145,6 +201,7 @@ impl ToolCommand {
             env_clear,
             current_dir,
             exit_with,
+            output_assertions,
             envs,
             ..
         } = options;
@@ -167,19 +224,29 @@ impl ToolCommand {
 
         let executable = resolve_binary_path(executable)?;
 
-        let output = self
-            .command
+        self.command
             .args(tool_args.to_vec())
             .arg(&executable)
             .args(executable_args)
             .envs(envs)
             .stdout(Stdio::piped())
-            .stderr(Stdio::piped())
-            .output()
-            .map_err(|error| -> anyhow::Error {
-                Error::LaunchError(PathBuf::from("valgrind"), error.to_string()).into()
-            })
-            .and_then(|output| {
+            .stderr(Stdio::piped());
+
+        let (output, wall_time) = output_with_retries(self.tool, self.retries, || {
+            let start = Instant::now();
+            self.command
+                .output()
+                .map(|output| (output, start.elapsed()))
+        })
+        .map_err(|error| -> anyhow::Error {
+            Error::LaunchError(PathBuf::from("valgrind"), error.to_string()).into()
+        })
+        .and_then(|(output, wall_time)| {
+            // The exit code of the benchmark is replaced by the `--error-exitcode` if the tool
+            // detected errors. These errors are reported after the log file was parsed.
+            if tool_args.is_error_exit(output.status.code()) {
+                Ok(output)
+            } else {
                 check_exit(
                     self.tool,
                     &executable,
@@ -187,15 +254,95 @@ impl ToolCommand {
                     &output_path.to_log_output(),
                     exit_with.as_ref(),
                 )
-            })?;
+            }
+            .map(|output| (output, wall_time))
+        })?;
+        debug!(
+            "{}: The instrumented run of '{}' took {wall_time:?} (wall time including the \
+             overhead of valgrind)",
+            self.tool.id(),
+            executable.display()
+        );
+        check_output(self.tool, &executable, &output, &output_assertions)?;
 
         Ok(ToolOutput {
             tool: self.tool,
             output,
+            peak_rss: None,
+            wall_time: Some(wall_time),
         })
     }
 }
 
+impl OutputStream {
+    fn id(self) -> &'static str {
+        match self {
+            OutputStream::Stdout => "stdout",
+            OutputStream::Stderr => "stderr",
+        }
+    }
+
+    fn select(self, output: &Output) -> &[u8] {
+        match self {
+            OutputStream::Stdout => &output.stdout,
+            OutputStream::Stderr => &output.stderr,
+        }
+    }
+}
+
+impl OutputAssertion {
+    /// Return true if the captured `output` satisfies this assertion
+    pub fn is_satisfied(&self, output: &Output) -> bool {
+        match self {
+            OutputAssertion::Contains(stream, string) => {
+                String::from_utf8_lossy(stream.select(output)).contains(string.as_str())
+            }
+            OutputAssertion::Matches(stream, regex) => {
+                regex.is_match(&String::from_utf8_lossy(stream.select(output)))
+            }
+        }
+    }
+}
+
+impl Display for OutputAssertion {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            OutputAssertion::Contains(stream, string) => {
+                write!(f, "{} to contain '{string}'", stream.id())
+            }
+            OutputAssertion::Matches(stream, regex) => {
+                write!(f, "{} to match the regex '{regex}'", stream.id())
+            }
+        }
+    }
+}
+
+impl TryFrom<&api::OutputAssertion> for OutputAssertion {
+    type Error = anyhow::Error;
+
+    fn try_from(value: &api::OutputAssertion) -> std::result::Result<Self, Self::Error> {
+        let compile = |regex: &str| {
+            Regex::new(regex)
+                .map_err(|error| anyhow!("Invalid regex '{regex}' of an output assertion: {error}"))
+        };
+        let assertion = match value {
+            api::OutputAssertion::StdoutContains(string) => {
+                OutputAssertion::Contains(OutputStream::Stdout, string.clone())
+            }
+            api::OutputAssertion::StdoutMatches(regex) => {
+                OutputAssertion::Matches(OutputStream::Stdout, compile(regex)?)
+            }
+            api::OutputAssertion::StderrContains(string) => {
+                OutputAssertion::Contains(OutputStream::Stderr, string.clone())
+            }
+            api::OutputAssertion::StderrMatches(regex) => {
+                OutputAssertion::Matches(OutputStream::Stderr, compile(regex)?)
+            }
+        };
+        Ok(assertion)
+    }
+}
+
 impl ToolConfig {
     fn parse_load(
         &self,
@@ -211,6 +358,8 @@ impl ToolConfig {
             log_paths: log_path.real_paths()?,
             out_paths: out_path.map_or_else(|| Ok(Vec::default()), ToolOutputPath::real_paths)?,
             summaries,
+            valgrind_wall_time: None,
+            is_error_exit: false,
         };
 
         Ok(tool_summary)
@@ -230,10 +379,60 @@ impl From<api::Tool> for ToolConfig {
 }
 
 impl ToolConfigs {
+    /// Create the `ToolConfigs` of a benchmark with this `default_tool`
+    ///
+    /// If the `default_tool` doesn't report costs like callgrind and cachegrind, the benchmark is
+    /// run with these tools only. The `default_tool` is then enabled and run first, with the
+    /// arguments of a configured [`api::Tool`] of the same kind if present.
+    ///
+    /// # Errors
+    ///
+    /// If callgrind or cachegrind are configured as one of the `tools`
+    pub fn new(tools: api::Tools, default_tool: ValgrindTool) -> Result<Self> {
+        let mut tool_configs = vec![];
+        for tool in tools.0 {
+            let tool_config = ToolConfig::from(tool);
+            if tool_config.tool.has_costs() {
+                return Err(anyhow!(
+                    "{} can't be run as an additional tool. Use the `default_tool` instead",
+                    tool_config.tool.id()
+                ));
+            }
+            tool_configs.push(tool_config);
+        }
+
+        if !default_tool.has_costs() {
+            let mut tool_config =
+                if let Some(index) = tool_configs.iter().position(|t| t.tool == default_tool) {
+                    tool_configs.remove(index)
+                } else {
+                    ToolConfig {
+                        tool: default_tool,
+                        is_enabled: true,
+                        args: ToolArgs::from_raw_args(default_tool, api::RawArgs::default()),
+                        outfile_modifier: None,
+                    }
+                };
+            tool_config.is_enabled = true;
+            tool_configs.insert(0, tool_config);
+        }
+
+        Ok(Self(tool_configs))
+    }
+
     pub fn has_tools_enabled(&self) -> bool {
         self.0.iter().any(|t| t.is_enabled)
     }
 
+    /// Shift the output and log files of all enabled tools like [`ToolOutputPath::shift`]
+    pub fn shift(&self, output_path: &ToolOutputPath) -> Result<()> {
+        for path in self.output_paths(output_path) {
+            path.shift()?;
+            path.to_log_output().shift()?;
+        }
+        Ok(())
+    }
+
     pub fn output_paths(&self, output_path: &ToolOutputPath) -> Vec<ToolOutputPath> {
         self.0
             .iter()
@@ -243,7 +442,7 @@ impl ToolConfigs {
     }
 
     fn print_headline(meta: &Metadata, tool_config: &ToolConfig) {
-        if meta.args.output_format == OutputFormat::Default {
+        if is_inline_output(meta) {
             println!("{}", tool_headline(tool_config.tool));
         }
     }
@@ -254,13 +453,16 @@ impl ToolConfigs {
         logfile_summaries: &[ToolRunSummary],
         output_paths: &[PathBuf],
     ) -> Result<()> {
-        if meta.args.output_format == OutputFormat::Default {
+        if is_inline_output(meta) {
             for logfile_summary in logfile_summaries {
                 ToolRunSummaryFormatter::print(
                     logfile_summary,
                     tool_config.args.verbose,
                     logfile_summaries.len() > 1,
                     matches!(tool_config.tool, ValgrindTool::BBV),
+                    meta.args.number_format,
+                    meta.args.diff_format,
+                    meta.args.filter_event.as_ref(),
                 )?;
             }
 
@@ -294,6 +496,8 @@ impl ToolConfigs {
             log_paths: log_path.real_paths()?,
             out_paths: out_path.map_or_else(|| Ok(Vec::default()), ToolOutputPath::real_paths)?,
             summaries,
+            valgrind_wall_time: None,
+            is_error_exit: false,
         })
     }
 
@@ -351,6 +555,10 @@ impl ToolConfigs {
             let parser = tool_config.tool.to_parser(meta.project_root.clone());
 
             let old_summaries = parser.as_ref().parse(&log_path.to_base_path())?;
+            // Like the output of callgrind or cachegrind, the first run of this tool is saved as
+            // baseline with `--auto-save-baseline`. All tools have a log file.
+            let auto_save_baseline = meta.args.auto_save_baseline.unwrap_or_default()
+                && !log_path.to_base_path().exists();
 
             if save_baseline {
                 output_path.clear()?;
@@ -365,13 +573,14 @@ impl ToolConfigs {
                 &output_path,
             )?;
 
-            let tool_summary = Self::parse(
+            let mut tool_summary = Self::parse(
                 tool_config,
                 meta,
                 &log_path,
                 tool.has_output_file().then_some(&output_path),
                 old_summaries,
             )?;
+            tool_summary.valgrind_wall_time = output.wall_time.map(|time| time.as_secs_f64());
 
             Self::print(
                 meta,
@@ -379,11 +588,24 @@ impl ToolConfigs {
                 &tool_summary.summaries,
                 &tool_summary.out_paths,
             )?;
+            print_valgrind_time(meta, output.wall_time);
 
             output.dump_log(log::Level::Info);
             log_path.dump_log(log::Level::Info, &mut stderr())?;
 
+            if auto_save_baseline {
+                output_path.save_as_baseline()?;
+            }
+
+            // The errors are reported with `BenchmarkSummary::check_tool_errors` after the summary
+            // was saved
+            tool_summary.is_error_exit =
+                tool_config.args.is_error_exit(output.output.status.code());
+            let is_error_exit = tool_summary.is_error_exit;
             tool_summaries.push(tool_summary);
+            if is_error_exit {
+                break;
+            }
         }
 
         Ok(tool_summaries)
@@ -412,10 +634,10 @@ impl ToolOutputPath {
         tool: ValgrindTool,
         baseline_kind: &BaselineKind,
         base_dir: &Path,
+        baseline_base_dir: &Path,
         module: &str,
         name: &str,
     ) -> Self {
-        let current = base_dir;
         let module_path: PathBuf = module.split("::").collect();
         let sanitized_name = sanitize_filename::sanitize_with_options(
             name,
@@ -430,10 +652,8 @@ impl ToolOutputPath {
             kind,
             tool,
             baseline_kind: baseline_kind.clone(),
-            dir: current
-                .join(base_dir)
-                .join(module_path)
-                .join(sanitized_name),
+            dir: base_dir.join(&module_path).join(sanitized_name),
+            baseline_dir: baseline_base_dir.join(module_path).join(sanitized_name),
             name: sanitized_name.to_owned(),
             modifiers: vec![],
         }
@@ -447,23 +667,40 @@ impl ToolOutputPath {
         tool: ValgrindTool,
         baseline_kind: &BaselineKind,
         base_dir: &Path,
+        baseline_base_dir: &Path,
         module: &str,
         name: &str,
     ) -> Result<Self> {
-        let output = Self::new(kind, tool, baseline_kind, base_dir, module, name);
+        let output = Self::new(
+            kind,
+            tool,
+            baseline_kind,
+            base_dir,
+            baseline_base_dir,
+            module,
+            name,
+        );
         output.init()?;
         Ok(output)
     }
 
     pub fn init(&self) -> Result<()> {
-        std::fs::create_dir_all(&self.dir).with_context(|| {
-            format!(
-                "Failed to create benchmark directory: '{}'",
-                self.dir.display()
-            )
+        let dir = self.kind_dir();
+        std::fs::create_dir_all(dir).with_context(|| {
+            format!("Failed to create benchmark directory: '{}'", dir.display())
         })
     }
 
+    /// Return the directory of the files of this [`ToolOutputPathKind`]
+    pub fn kind_dir(&self) -> &Path {
+        match &self.kind {
+            ToolOutputPathKind::Base(_)
+            | ToolOutputPathKind::BaseLog(_)
+            | ToolOutputPathKind::BaseMeta(_) => &self.baseline_dir,
+            _ => &self.dir,
+        }
+    }
+
     pub fn clear(&self) -> Result<()> {
         for entry in self.real_paths()? {
             std::fs::remove_file(&entry).with_context(|| {
@@ -496,6 +733,48 @@ impl ToolOutputPath {
         }
     }
 
+    /// Copy the new output, log and meta files of this output to the baseline of the
+    /// [`BaselineKind::Name`]
+    ///
+    /// This method is used by `--auto-save-baseline` to save the first run of a benchmark as
+    /// baseline if the baseline doesn't exist yet and is meant to be called on a
+    /// [`ToolOutputPathKind::Out`]. Nothing is copied for [`BaselineKind::Old`], since the new
+    /// output becomes the old output of the next run anyway.
+    pub fn save_as_baseline(&self) -> Result<()> {
+        let BaselineKind::Name(name) = &self.baseline_kind else {
+            return Ok(());
+        };
+
+        std::fs::create_dir_all(&self.baseline_dir).with_context(|| {
+            format!(
+                "Failed to create baseline directory: '{}'",
+                self.baseline_dir.display()
+            )
+        })?;
+        for output in [self.clone(), self.to_log_output(), self.to_meta_output()] {
+            for entry in output.real_paths()? {
+                let file_name = entry
+                    .file_name()
+                    .expect("A file name should be present")
+                    .to_string_lossy();
+                let dest = self.baseline_dir.join(format!("{file_name}.base@{name}"));
+                debug!(
+                    "Saving '{}' as baseline '{}'",
+                    entry.display(),
+                    dest.display()
+                );
+                std::fs::copy(&entry, &dest).with_context(|| {
+                    format!(
+                        "Failed to copy benchmark file from '{}' to '{}'",
+                        entry.display(),
+                        dest.display()
+                    )
+                })?;
+            }
+        }
+        Ok(())
+    }
+
     pub fn exists(&self) -> bool {
         self.real_paths().map_or(false, |p| !p.is_empty())
     }
@@ -517,12 +796,18 @@ impl ToolOutputPath {
                     ToolOutputPathKind::Log | ToolOutputPathKind::BaseLog(_),
                     BaselineKind::Name(name),
                 ) => ToolOutputPathKind::BaseLog(name.to_string()),
+                (ToolOutputPathKind::Meta, BaselineKind::Old) => ToolOutputPathKind::OldMeta,
+                (
+                    ToolOutputPathKind::Meta | ToolOutputPathKind::BaseMeta(_),
+                    BaselineKind::Name(name),
+                ) => ToolOutputPathKind::BaseMeta(name.to_string()),
                 (kind, _) => kind.clone(),
             },
             tool: self.tool,
             baseline_kind: self.baseline_kind.clone(),
             name: self.name.clone(),
             dir: self.dir.clone(),
+            baseline_dir: self.baseline_dir.clone(),
             modifiers: self.modifiers.clone(),
         }
     }
@@ -534,6 +819,7 @@ impl ToolOutputPath {
             baseline_kind: self.baseline_kind.clone(),
             name: self.name.clone(),
             dir: self.dir.clone(),
+            baseline_dir: self.baseline_dir.clone(),
             modifiers: self.modifiers.clone(),
         }
     }
@@ -549,6 +835,30 @@ impl ToolOutputPath {
             baseline_kind: self.baseline_kind.clone(),
             name: self.name.clone(),
             dir: self.dir.clone(),
+            baseline_dir: self.baseline_dir.clone(),
+            modifiers: self.modifiers.clone(),
+        }
+    }
+
+    /// Return the path of the file with the [`super::callgrind::metadata_parser::RunMetadata`]
+    /// of this output
+    pub fn to_meta_output(&self) -> Self {
+        Self {
+            kind: match &self.kind {
+                ToolOutputPathKind::Out | ToolOutputPathKind::Log => ToolOutputPathKind::Meta,
+                ToolOutputPathKind::OldOut | ToolOutputPathKind::OldLog => {
+                    ToolOutputPathKind::OldMeta
+                }
+                ToolOutputPathKind::Base(name) | ToolOutputPathKind::BaseLog(name) => {
+                    ToolOutputPathKind::BaseMeta(name.clone())
+                }
+                kind => kind.clone(),
+            },
+            tool: self.tool,
+            baseline_kind: self.baseline_kind.clone(),
+            name: self.name.clone(),
+            dir: self.dir.clone(),
+            baseline_dir: self.baseline_dir.clone(),
             modifiers: self.modifiers.clone(),
         }
     }
@@ -616,6 +926,16 @@ impl ToolOutputPath {
             (ToolOutputPathKind::Base(name), false) => {
                 format!("out.{}.base@{name}", self.modifiers.join("."))
             }
+            (ToolOutputPathKind::Meta, true) => "meta".to_owned(),
+            (ToolOutputPathKind::Meta, false) => format!("meta.{}", self.modifiers.join(".")),
+            (ToolOutputPathKind::OldMeta, true) => "meta.old".to_owned(),
+            (ToolOutputPathKind::OldMeta, false) => {
+                format!("meta.{}.old", self.modifiers.join("."))
+            }
+            (ToolOutputPathKind::BaseMeta(name), true) => format!("meta.base@{name}"),
+            (ToolOutputPathKind::BaseMeta(name), false) => {
+                format!("meta.{}.base@{name}", self.modifiers.join("."))
+            }
         }
     }
 
@@ -629,13 +949,14 @@ impl ToolOutputPath {
             tool: self.tool,
             baseline_kind: self.baseline_kind.clone(),
             dir: self.dir.clone(),
+            baseline_dir: self.baseline_dir.clone(),
             name: self.name.clone(),
             modifiers: modifiers.into_iter().map(Into::into).collect(),
         }
     }
 
     pub fn to_path(&self) -> PathBuf {
-        self.dir.join(format!(
+        self.kind_dir().join(format!(
             "{}.{}.{}",
             self.tool.id(),
             self.name,
@@ -645,11 +966,9 @@ impl ToolOutputPath {
 
     pub fn real_paths(&self) -> Result<Vec<PathBuf>> {
         let mut paths = vec![];
-        for entry in std::fs::read_dir(&self.dir).with_context(|| {
-            format!(
-                "Failed opening benchmark directory: '{}'",
-                self.dir.display()
-            )
+        let dir = self.kind_dir();
+        for entry in std::fs::read_dir(dir).with_context(|| {
+            format!("Failed opening benchmark directory: '{}'", dir.display())
         })? {
             let path = entry?;
             let file_name = path.file_name().to_string_lossy().to_string();
@@ -686,6 +1005,20 @@ impl ToolOutputPath {
                         suffix.starts_with("out")
                             && suffix.ends_with(format!(".base@{name}").as_str())
                     }
+                    ToolOutputPathKind::Meta => {
+                        suffix.starts_with("meta")
+                            && !(suffix.ends_with(".old")
+                                || suffix
+                                    .rsplit_once('.')
+                                    .map_or(false, |(_, b)| b.starts_with("base@")))
+                    }
+                    ToolOutputPathKind::OldMeta => {
+                        suffix.starts_with("meta") && suffix.ends_with(".old")
+                    }
+                    ToolOutputPathKind::BaseMeta(name) => {
+                        suffix.starts_with("meta")
+                            && suffix.ends_with(format!(".base@{name}").as_str())
+                    }
                 };
 
                 if is_match {
@@ -704,11 +1037,42 @@ impl Display for ToolOutputPath {
 }
 
 impl ValgrindTool {
+    /// Return true if this tool reports the [`Costs`] of the benchmark in its output files
+    ///
+    /// Only benchmarks with callgrind or cachegrind as default tool have costs, which can be
+    /// compared and checked for regressions.
+    pub fn has_costs(&self) -> bool {
+        matches!(self, ValgrindTool::Callgrind | ValgrindTool::Cachegrind)
+    }
+
+    /// Return the [`ToolParser`] for the [`Costs`] of this tool
+    ///
+    /// If a `sentinel` is present, callgrind's costs are the costs of the sentinel function instead
+    /// of the costs of the whole benchmark run. Cachegrind doesn't support a sentinel.
+    ///
+    /// # Errors
+    ///
+    /// If this tool is not callgrind or cachegrind
+    pub fn to_costs_parser(self, sentinel: Option<&Sentinel>) -> Result<Box<dyn ToolParser>> {
+        match (self, sentinel) {
+            (ValgrindTool::Callgrind, Some(sentinel)) => {
+                Ok(Box::new(SentinelParser::new(sentinel)))
+            }
+            (ValgrindTool::Callgrind, None) => Ok(Box::new(CallgrindSummaryParser)),
+            (ValgrindTool::Cachegrind, _) => Ok(Box::new(CachegrindSummaryParser)),
+            (tool, _) => Err(anyhow!(
+                "The costs of {} are reported in the log files and not in the output files",
+                tool.id()
+            )),
+        }
+    }
+
     /// Return the id used by the `valgrind --tool` option
     pub fn id(&self) -> String {
         match self {
             ValgrindTool::DHAT => "dhat".to_owned(),
             ValgrindTool::Callgrind => "callgrind".to_owned(),
+            ValgrindTool::Cachegrind => "cachegrind".to_owned(),
             ValgrindTool::Memcheck => "memcheck".to_owned(),
             ValgrindTool::Helgrind => "helgrind".to_owned(),
             ValgrindTool::DRD => "drd".to_owned(),
@@ -720,7 +1084,11 @@ impl ValgrindTool {
     pub fn has_output_file(&self) -> bool {
         matches!(
             self,
-            ValgrindTool::Callgrind | ValgrindTool::DHAT | ValgrindTool::BBV | ValgrindTool::Massif
+            ValgrindTool::Callgrind
+                | ValgrindTool::Cachegrind
+                | ValgrindTool::DHAT
+                | ValgrindTool::BBV
+                | ValgrindTool::Massif
         )
     }
 }
@@ -728,6 +1096,8 @@ impl ValgrindTool {
 impl From<api::ValgrindTool> for ValgrindTool {
     fn from(value: api::ValgrindTool) -> Self {
         match value {
+            api::ValgrindTool::Callgrind => ValgrindTool::Callgrind,
+            api::ValgrindTool::Cachegrind => ValgrindTool::Cachegrind,
             api::ValgrindTool::Memcheck => ValgrindTool::Memcheck,
             api::ValgrindTool::Helgrind => ValgrindTool::Helgrind,
             api::ValgrindTool::DRD => ValgrindTool::DRD,
@@ -745,6 +1115,7 @@ impl TryFrom<&str> for ValgrindTool {
         match value {
             "dhat" => Ok(ValgrindTool::DHAT),
             "callgrind" => Ok(ValgrindTool::Callgrind),
+            "cachegrind" => Ok(ValgrindTool::Cachegrind),
             "memcheck" => Ok(ValgrindTool::Memcheck),
             "helgrind" => Ok(ValgrindTool::Helgrind),
             "drd" => Ok(ValgrindTool::DRD),
@@ -755,6 +1126,224 @@ impl TryFrom<&str> for ValgrindTool {
     }
 }
 
+/// Return the [`ValgrindTool`] which runs the benchmark
+///
+/// The `default_tool` takes precedence over the `cachegrind` switch. The `bench_default_tool` and
+/// `bench_cachegrind` are configured by the benchmark itself and take precedence over the
+/// `default_tool` and `cachegrind` inherited from the group or `main!` macro, so an inherited
+/// `default_tool` doesn't override the cachegrind switch of the benchmark. Without any of them,
+/// the benchmark runs with [`ValgrindTool::Callgrind`].
+pub fn resolve_tool(
+    default_tool: Option<api::ValgrindTool>,
+    cachegrind: Option<bool>,
+    bench_default_tool: Option<api::ValgrindTool>,
+    bench_cachegrind: Option<bool>,
+) -> ValgrindTool {
+    match (
+        bench_default_tool,
+        bench_cachegrind,
+        default_tool,
+        cachegrind,
+    ) {
+        (Some(tool), _, _, _) | (None, None | Some(false), Some(tool), _) => tool.into(),
+        (None, Some(true), _, _) | (None, None, None, Some(true)) => ValgrindTool::Cachegrind,
+        _ => ValgrindTool::Callgrind,
+    }
+}
+
+/// Return the [`FlamegraphConfig`] if flamegraphs can be created with this [`ValgrindTool`]
+///
+/// Only callgrind collects a call graph. A [`api::FlamegraphConfig`] with a [`FlamegraphKind`]
+/// other than `None` is rejected for all other tools if it is configured for the benchmark with
+/// the `id` itself. If the `flamegraph_config` is `inherited` from the group or `main!` macro, the
+/// flamegraphs of this benchmark are skipped with a warning instead, so a global
+/// `FlamegraphConfig` doesn't prevent running single benchmarks with another tool. Without
+/// explicit event kinds, the flamegraphs are created for the `primary_event_kind` unless it is
+/// the [`EventKind::Score`], which is not available in flamegraphs.
+///
+/// # Errors
+///
+/// If flamegraphs are configured for a tool other than callgrind by the benchmark itself or a
+/// regex of a symbol transform doesn't compile
+pub fn resolve_flamegraph_config(
+    tool: ValgrindTool,
+    flamegraph_config: Option<api::FlamegraphConfig>,
+    inherited: bool,
+    primary_event_kind: EventKind,
+    id: &str,
+) -> Result<Option<FlamegraphConfig>> {
+    match flamegraph_config {
+        Some(config) if tool != ValgrindTool::Callgrind => {
+            if config.kind == Some(FlamegraphKind::None) {
+                Ok(None)
+            } else if inherited {
+                warn!(
+                    "{id}: Skipping the creation of flamegraphs: {} doesn't collect a call graph",
+                    tool.id()
+                );
+                Ok(None)
+            } else {
+                Err(anyhow!(
+                    "Flamegraphs are not supported by {}: Only callgrind collects a call graph. \
+                     Remove the FlamegraphConfig or use FlamegraphKind::None",
+                    tool.id()
+                ))
+            }
+        }
+        flamegraph_config => flamegraph_config
+            .map(|mut config| {
+                if primary_event_kind != EventKind::Score {
+                    config
+                        .event_kinds
+                        .get_or_insert_with(|| vec![primary_event_kind]);
+                }
+                config.try_into()
+            })
+            .transpose(),
+    }
+}
+
+/// Remove the `flamegraph_config` of the benchmark with the `name` if it doesn't match the
+/// `--flamegraph-filter`
+///
+/// Return true if the benchmark would have created flamegraphs but they are skipped.
+pub fn filter_flamegraph_config(
+    flamegraph_config: &mut Option<FlamegraphConfig>,
+    flamegraph_filter: Option<&Regex>,
+    name: &str,
+) -> bool {
+    match (flamegraph_config.as_ref(), flamegraph_filter) {
+        (Some(config), Some(filter))
+            if config.kind != FlamegraphKind::None && !filter.is_match(name) =>
+        {
+            debug!("{name}: Skipping flamegraphs because of --flamegraph-filter");
+            *flamegraph_config = None;
+            true
+        }
+        _ => false,
+    }
+}
+
+/// Return the [`RegressionConfig`] of a benchmark if this [`ValgrindTool`] reports costs
+///
+/// The `regression_config` of the benchmark is updated with the `meta_regression_config` of the
+/// command-line. Without any limits, the costs of the `primary_event_kind` are limited. The
+/// regression checks of the command-line are silently skipped for tools without costs. A
+/// `regression_config` is rejected for tools without costs if it is configured for the benchmark
+/// with the `id` itself. If it is `inherited` from the group or `main!` macro, the regression
+/// checks of this benchmark are skipped with a warning instead, like with
+/// [`resolve_flamegraph_config`].
+///
+/// # Errors
+///
+/// If the benchmark itself configures regression checks for a tool without costs or the weights
+/// of the score are invalid
+pub fn resolve_regression_config(
+    tool: ValgrindTool,
+    regression_config: &Option<api::RegressionConfig>,
+    meta_regression_config: &Option<api::RegressionConfig>,
+    inherited: bool,
+    primary_event_kind: EventKind,
+    id: &str,
+) -> Result<Option<RegressionConfig>> {
+    if tool.has_costs() {
+        let regression_config = api::update_option(regression_config, meta_regression_config)
+            .map(|config| RegressionConfig::from_api(config, primary_event_kind));
+        if let Some(config) = &regression_config {
+            config.validate_score()?;
+        }
+        Ok(regression_config)
+    } else if regression_config.is_some() && inherited {
+        warn!(
+            "{id}: Skipping the regression checks: {} doesn't report costs",
+            tool.id()
+        );
+        Ok(None)
+    } else if regression_config.is_some() {
+        Err(anyhow!(
+            "Regression checks are not supported by {}: Only callgrind and cachegrind report the \
+             costs of a benchmark. Remove the RegressionConfig",
+            tool.id()
+        ))
+    } else {
+        Ok(None)
+    }
+}
+
+/// Run valgrind with `run` and retry up to `retries` times if valgrind failed for a transient
+/// reason
+///
+/// Only the failures to spawn the valgrind process and the termination of valgrind by `SIGKILL`
+/// (for example by the OOM killer) are considered transient. All other results, including a
+/// non-zero exit code or the termination by another signal, are returned without a retry. The
+/// delay before each retry doubles starting with [`RETRY_BASE_DELAY`].
+///
+/// # Errors
+///
+/// Returns the error of the last run if spawning valgrind failed
+pub fn output_with_retries<T, F>(
+    tool: ValgrindTool,
+    retries: u32,
+    run: F,
+) -> io::Result<(Output, T)>
+where
+    F: FnMut() -> io::Result<(Output, T)>,
+{
+    retry_with_delay(tool, retries, RETRY_BASE_DELAY, run)
+}
+
+fn retry_with_delay<T, F>(
+    tool: ValgrindTool,
+    retries: u32,
+    mut delay: Duration,
+    mut run: F,
+) -> io::Result<(Output, T)>
+where
+    F: FnMut() -> io::Result<(Output, T)>,
+{
+    let mut attempt = 0;
+    loop {
+        let result = run();
+        let reason = match &result {
+            Err(error) => format!("Failed to spawn valgrind: {error}"),
+            Ok((output, _)) if is_killed(output.status) => "Valgrind was killed".to_owned(),
+            Ok(_) => return result,
+        };
+        if attempt >= retries {
+            return result;
+        }
+
+        attempt += 1;
+        warn!(
+            "{}: {reason}. Retrying in {} ms ({attempt}/{retries})",
+            tool.id(),
+            delay.as_millis()
+        );
+        std::thread::sleep(delay);
+        delay = delay.saturating_mul(2);
+    }
+}
+
+/// Return true if the process was terminated by `SIGKILL`
+#[cfg(unix)]
+fn is_killed(status: ExitStatus) -> bool {
+    use std::os::unix::process::ExitStatusExt;
+
+    status.signal() == Some(libc::SIGKILL)
+}
+
+/// Return true if the process was terminated by `SIGKILL` which is never the case on this
+/// platform
+#[cfg(not(unix))]
+fn is_killed(_status: ExitStatus) -> bool {
+    false
+}
+
+/// Check the exit status of the valgrind process against the expected [`ExitWith`]
+///
+/// If the benchmarked program crashed, for example with a segmentation fault or abort, the process
+/// is terminated by a signal and this is always an error, even if the benchmark is expected to
+/// fail with [`ExitWith::Failure`].
 pub fn check_exit(
     tool: ValgrindTool,
     executable: &Path,
@@ -763,6 +1352,9 @@ pub fn check_exit(
     exit_with: Option<&ExitWith>,
 ) -> Result<Output> {
     let Some(status_code) = output.status.code() else {
+        // Valgrind terminates itself with the same signal which terminated the benchmarked
+        // program. The output files of such a crashed run are incomplete and are not parsed. The
+        // error names the signal.
         return Err(Error::ProcessError((tool.id(), output, Some(output_path.clone()))).into());
     };
 
@@ -811,3 +1403,883 @@ pub fn check_exit(
         _ => Err(Error::ProcessError((tool.id(), output, Some(output_path.clone()))).into()),
     }
 }
+
+/// Check the captured `output` of the benchmarked `executable` against the `output_assertions`
+///
+/// If one of the assertions fails, the failed assertions and the captured stdout and stderr are
+/// printed and an error is returned.
+pub fn check_output(
+    tool: ValgrindTool,
+    executable: &Path,
+    output: &Output,
+    output_assertions: &[OutputAssertion],
+) -> Result<()> {
+    let failed = output_assertions
+        .iter()
+        .filter(|assertion| !assertion.is_satisfied(output))
+        .collect::<Vec<_>>();
+    if failed.is_empty() {
+        return Ok(());
+    }
+
+    for assertion in &failed {
+        error!(
+            "{}: Expected the {assertion} of '{}'",
+            tool.id(),
+            executable.display()
+        );
+    }
+    for stream in [OutputStream::Stdout, OutputStream::Stderr] {
+        let bytes = stream.select(output);
+        if bytes.is_empty() {
+            error!("{}: The {} was empty", tool.id(), stream.id());
+        } else {
+            error!("{}: Captured {}:", tool.id(), stream.id());
+            util::write_all_to_stderr(bytes);
+        }
+    }
+
+    Err(anyhow!(
+        "{}: {} of {} output assertions of '{}' failed",
+        tool.id(),
+        failed.len(),
+        output_assertions.len(),
+        executable.display()
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use indexmap::indexmap;
+    use pretty_assertions::assert_eq;
+    use rstest::rstest;
+
+    use super::*;
+    use crate::runner::summary::ErrorSummary;
+
+    #[rstest]
+    #[case::out(ToolOutputPathKind::Out, "/target/module/bench/callgrind.bench.out")]
+    #[case::old_out(ToolOutputPathKind::OldOut, "/target/module/bench/callgrind.bench.out.old")]
+    #[case::log(ToolOutputPathKind::Log, "/target/module/bench/callgrind.bench.log")]
+    #[case::base(
+        ToolOutputPathKind::Base("foo".to_owned()),
+        "/baselines/module/bench/callgrind.bench.out.base@foo"
+    )]
+    #[case::base_log(
+        ToolOutputPathKind::BaseLog("foo".to_owned()),
+        "/baselines/module/bench/callgrind.bench.log.base@foo"
+    )]
+    fn test_tool_output_path_to_path_with_baseline_dir(
+        #[case] kind: ToolOutputPathKind,
+        #[case] expected: &str,
+    ) {
+        let output_path = ToolOutputPath::new(
+            kind,
+            ValgrindTool::Callgrind,
+            &BaselineKind::Name("foo".parse().unwrap()),
+            Path::new("/target"),
+            Path::new("/baselines"),
+            "module",
+            "bench",
+        );
+        assert_eq!(output_path.to_path(), PathBuf::from(expected));
+    }
+
+    #[test]
+    fn test_tool_output_path_to_base_path_in_baseline_dir() {
+        let output_path = ToolOutputPath::new(
+            ToolOutputPathKind::Out,
+            ValgrindTool::Callgrind,
+            &BaselineKind::Name("foo".parse().unwrap()),
+            Path::new("/target"),
+            Path::new("/baselines"),
+            "some::module",
+            "bench",
+        );
+        assert_eq!(
+            output_path.to_base_path().to_path(),
+            PathBuf::from("/baselines/some/module/bench/callgrind.bench.out.base@foo")
+        );
+        assert_eq!(
+            output_path.to_log_output().to_base_path().to_path(),
+            PathBuf::from("/baselines/some/module/bench/callgrind.bench.log.base@foo")
+        );
+    }
+
+    #[rstest]
+    #[case::old(BaselineKind::Old, false)]
+    #[case::name(BaselineKind::Name("foo".parse().unwrap()), true)]
+    fn test_tool_output_path_save_as_baseline(
+        #[case] baseline_kind: BaselineKind,
+        #[case] expected_saved: bool,
+    ) {
+        let dir = tempfile::tempdir().unwrap();
+        let output_path = ToolOutputPath::with_init(
+            ToolOutputPathKind::Out,
+            ValgrindTool::Callgrind,
+            &baseline_kind,
+            &dir.path().join("target"),
+            &dir.path().join("baselines"),
+            "module",
+            "bench",
+        )
+        .unwrap();
+        for file in [
+            output_path.to_path(),
+            output_path.to_log_output().to_path(),
+            output_path.to_meta_output().to_path(),
+        ] {
+            std::fs::write(file, "content").unwrap();
+        }
+
+        output_path.save_as_baseline().unwrap();
+
+        let base_path = ToolOutputPath::new(
+            ToolOutputPathKind::Out,
+            ValgrindTool::Callgrind,
+            &BaselineKind::Name("foo".parse().unwrap()),
+            &dir.path().join("target"),
+            &dir.path().join("baselines"),
+            "module",
+            "bench",
+        )
+        .to_base_path();
+        assert_eq!(base_path.exists(), expected_saved);
+        assert_eq!(base_path.to_log_output().exists(), expected_saved);
+        assert_eq!(base_path.to_meta_output().exists(), expected_saved);
+        if expected_saved {
+            assert_eq!(
+                std::fs::read_to_string(base_path.to_path()).unwrap(),
+                "content"
+            );
+        }
+        assert!(output_path.exists());
+    }
+
+    /// The raw wait status of a process which exited with code `1`
+    #[cfg(unix)]
+    const EXIT_FAILURE: i32 = 1_i32 << 8_i32;
+    /// Not a real wait status but the marker for a failure to spawn the process
+    #[cfg(unix)]
+    const SPAWN_ERROR: i32 = -1_i32;
+    #[cfg(unix)]
+    const SUCCESS: i32 = 0_i32;
+
+    #[cfg(unix)]
+    fn output_with_status(raw: i32) -> Output {
+        use std::os::unix::process::ExitStatusExt;
+
+        Output {
+            status: ExitStatus::from_raw(raw),
+            stdout: vec![],
+            stderr: vec![],
+        }
+    }
+
+    #[rstest]
+    #[cfg(unix)]
+    #[case::success_is_not_retried(vec![SUCCESS], 2, 1, Some(SUCCESS))]
+    #[case::exit_code_is_not_retried(vec![EXIT_FAILURE, SUCCESS], 2, 1, Some(EXIT_FAILURE))]
+    #[case::segfault_is_not_retried(vec![libc::SIGSEGV, SUCCESS], 2, 1, Some(libc::SIGSEGV))]
+    #[case::killed_is_retried(vec![libc::SIGKILL, SUCCESS], 2, 2, Some(SUCCESS))]
+    #[case::spawn_error_is_retried(vec![SPAWN_ERROR, SPAWN_ERROR, SUCCESS], 2, 3, Some(SUCCESS))]
+    #[case::no_retries(vec![libc::SIGKILL, SUCCESS], 0, 1, Some(libc::SIGKILL))]
+    #[case::retries_exhausted(vec![SPAWN_ERROR, SPAWN_ERROR, SPAWN_ERROR], 2, 3, None)]
+    fn test_retry_with_delay(
+        #[case] results: Vec<i32>,
+        #[case] retries: u32,
+        #[case] expected_runs: usize,
+        #[case] expected: Option<i32>,
+    ) {
+        use std::os::unix::process::ExitStatusExt;
+
+        let mut runs = 0_usize;
+        let result = retry_with_delay(ValgrindTool::Callgrind, retries, Duration::ZERO, || {
+            let raw = results[runs];
+            runs += 1;
+            if raw == SPAWN_ERROR {
+                Err(io::Error::new(io::ErrorKind::Other, "spawn failed"))
+            } else {
+                Ok((output_with_status(raw), ()))
+            }
+        });
+
+        assert_eq!(runs, expected_runs);
+        assert_eq!(
+            result.ok().map(|(output, ())| output.status.into_raw()),
+            expected
+        );
+    }
+
+    #[rstest]
+    #[cfg(unix)]
+    #[case::no_exit_with(None)]
+    #[case::success(Some(ExitWith::Success))]
+    #[case::failure(Some(ExitWith::Failure))]
+    #[case::code(Some(ExitWith::Code(134)))]
+    fn test_check_exit_when_crashed(#[case] exit_with: Option<ExitWith>) {
+        // A program which deliberately aborts like a benchmark calling `std::process::abort`
+        let output = Command::new("sh")
+            .args(["-c", "kill -ABRT $$"])
+            .output()
+            .unwrap();
+        let output_path = ToolOutputPath::new(
+            ToolOutputPathKind::Log,
+            ValgrindTool::Callgrind,
+            &BaselineKind::Old,
+            Path::new("/target"),
+            Path::new("/target"),
+            "module",
+            "bench",
+        );
+
+        let error = check_exit(
+            ValgrindTool::Callgrind,
+            Path::new("sh"),
+            output,
+            &output_path,
+            exit_with.as_ref(),
+        )
+        .unwrap_err();
+
+        assert_eq!(
+            error.to_string(),
+            "Error running 'callgrind': Terminated by the signal SIGABRT (6)"
+        );
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn test_check_exit_when_bad_valgrind_argument_then_stderr_in_error() {
+        // Valgrind rejects unknown arguments before the log file is created and prints the reason
+        // to stderr
+        let output = Command::new("sh")
+            .args([
+                "-c",
+                "echo 'valgrind: Bad option: --not-an-option' >&2; echo 'valgrind: Use --help for \
+                 more information or consult the user manual.' >&2; exit 1",
+            ])
+            .output()
+            .unwrap();
+        let output_path = ToolOutputPath::new(
+            ToolOutputPathKind::Log,
+            ValgrindTool::Callgrind,
+            &BaselineKind::Old,
+            Path::new("/target"),
+            Path::new("/target"),
+            "module",
+            "bench",
+        );
+
+        let error = check_exit(
+            ValgrindTool::Callgrind,
+            Path::new("sh"),
+            output,
+            &output_path,
+            None,
+        )
+        .unwrap_err();
+
+        assert_eq!(
+            error.to_string(),
+            "Error running 'callgrind': Exit code was: '1'\nstderr:\n  | valgrind: Bad option: \
+             --not-an-option\n  | valgrind: Use --help for more information or consult the user \
+             manual."
+        );
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn test_check_exit_when_long_stderr_then_only_tail_in_error() {
+        let output = Command::new("sh")
+            .args([
+                "-c",
+                "for i in $(seq 1 25); do echo \"line $i\" >&2; done; exit 2",
+            ])
+            .output()
+            .unwrap();
+        let output_path = ToolOutputPath::new(
+            ToolOutputPathKind::Log,
+            ValgrindTool::Callgrind,
+            &BaselineKind::Old,
+            Path::new("/target"),
+            Path::new("/target"),
+            "module",
+            "bench",
+        );
+
+        let error = check_exit(
+            ValgrindTool::Callgrind,
+            Path::new("sh"),
+            output,
+            &output_path,
+            None,
+        )
+        .unwrap_err()
+        .to_string();
+
+        let mut lines = error.lines();
+        assert_eq!(
+            lines.next(),
+            Some("Error running 'callgrind': Exit code was: '2'")
+        );
+        assert_eq!(
+            lines.next(),
+            Some("The last 20 lines of stderr (5 lines omitted):")
+        );
+        assert_eq!(lines.next(), Some("  | line 6"));
+        assert_eq!(lines.last(), Some("  | line 25"));
+    }
+
+    #[rstest]
+    #[cfg(unix)]
+    #[case::no_assertions(vec![], true)]
+    #[case::stdout_contains(vec![api::OutputAssertion::StdoutContains("3 files".to_owned())], true)]
+    #[case::stdout_contains_when_in_stderr(
+        vec![api::OutputAssertion::StdoutContains("warning".to_owned())],
+        false
+    )]
+    #[case::stdout_matches(
+        vec![api::OutputAssertion::StdoutMatches(r"(?m)^Processed \d+ files$".to_owned())],
+        true
+    )]
+    #[case::stdout_not_matches(
+        vec![api::OutputAssertion::StdoutMatches(r"^\d+$".to_owned())],
+        false
+    )]
+    #[case::stderr_contains(vec![api::OutputAssertion::StderrContains("warning".to_owned())], true)]
+    #[case::stderr_matches(
+        vec![api::OutputAssertion::StderrMatches("^warning:".to_owned())],
+        true
+    )]
+    #[case::one_of_many_fails(
+        vec![
+            api::OutputAssertion::StdoutContains("Processed".to_owned()),
+            api::OutputAssertion::StderrContains("error".to_owned()),
+        ],
+        false
+    )]
+    fn test_check_output(
+        #[case] assertions: Vec<api::OutputAssertion>,
+        #[case] expected_is_ok: bool,
+    ) {
+        use std::os::unix::process::ExitStatusExt;
+
+        let output = Output {
+            status: ExitStatus::from_raw(0),
+            stdout: b"Processed 3 files\n".to_vec(),
+            stderr: b"warning: file is empty\n".to_vec(),
+        };
+        let assertions = assertions
+            .iter()
+            .map(|a| OutputAssertion::try_from(a).unwrap())
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            check_output(
+                ValgrindTool::Callgrind,
+                Path::new("my-exe"),
+                &output,
+                &assertions
+            )
+            .is_ok(),
+            expected_is_ok
+        );
+    }
+
+    #[test]
+    fn test_output_assertion_when_regex_is_invalid() {
+        let error = OutputAssertion::try_from(&api::OutputAssertion::StdoutMatches("(".to_owned()))
+            .unwrap_err();
+        assert!(error
+            .to_string()
+            .starts_with("Invalid regex '(' of an output assertion: "));
+    }
+
+    fn api_tool(kind: api::ValgrindTool, enable: Option<bool>, raw_args: &[&str]) -> api::Tool {
+        api::Tool {
+            kind,
+            enable,
+            raw_args: api::RawArgs::new(raw_args.iter().map(|arg| (*arg).to_owned()).collect()),
+            outfile_modifier: None,
+            show_log: None,
+        }
+    }
+
+    #[rstest]
+    #[case::default(None, None, ValgrindTool::Callgrind)]
+    #[case::cachegrind(None, Some(true), ValgrindTool::Cachegrind)]
+    #[case::no_cachegrind(None, Some(false), ValgrindTool::Callgrind)]
+    #[case::default_tool(Some(api::ValgrindTool::DHAT), None, ValgrindTool::DHAT)]
+    #[case::default_tool_precedence(
+        Some(api::ValgrindTool::Callgrind),
+        Some(true),
+        ValgrindTool::Callgrind
+    )]
+    fn test_resolve_tool(
+        #[case] default_tool: Option<api::ValgrindTool>,
+        #[case] cachegrind: Option<bool>,
+        #[case] expected: ValgrindTool,
+    ) {
+        assert_eq!(
+            resolve_tool(
+                default_tool.clone(),
+                cachegrind,
+                default_tool.clone(),
+                cachegrind
+            ),
+            expected
+        );
+        assert_eq!(resolve_tool(default_tool, cachegrind, None, None), expected);
+    }
+
+    #[rstest]
+    #[case::inherited_default_tool(
+        Some(api::ValgrindTool::DHAT),
+        None,
+        None,
+        Some(true),
+        ValgrindTool::Cachegrind
+    )]
+    #[case::inherited_cachegrind(
+        None,
+        Some(true),
+        Some(api::ValgrindTool::DHAT),
+        None,
+        ValgrindTool::DHAT
+    )]
+    #[case::no_cachegrind(
+        Some(api::ValgrindTool::DHAT),
+        None,
+        None,
+        Some(false),
+        ValgrindTool::DHAT
+    )]
+    #[case::bench_precedence(
+        None,
+        None,
+        Some(api::ValgrindTool::DHAT),
+        Some(true),
+        ValgrindTool::DHAT
+    )]
+    fn test_resolve_tool_when_inherited(
+        #[case] inherited_default_tool: Option<api::ValgrindTool>,
+        #[case] inherited_cachegrind: Option<bool>,
+        #[case] bench_default_tool: Option<api::ValgrindTool>,
+        #[case] bench_cachegrind: Option<bool>,
+        #[case] expected: ValgrindTool,
+    ) {
+        let default_tool = bench_default_tool.clone().or(inherited_default_tool);
+        let cachegrind = bench_cachegrind.or(inherited_cachegrind);
+        assert_eq!(
+            resolve_tool(
+                default_tool,
+                cachegrind,
+                bench_default_tool,
+                bench_cachegrind
+            ),
+            expected
+        );
+    }
+
+    #[rstest]
+    #[case::callgrind(ValgrindTool::Callgrind, true)]
+    #[case::cachegrind(ValgrindTool::Cachegrind, true)]
+    #[case::memcheck(ValgrindTool::Memcheck, false)]
+    #[case::dhat(ValgrindTool::DHAT, false)]
+    fn test_to_costs_parser(#[case] tool: ValgrindTool, #[case] expected_ok: bool) {
+        assert_eq!(tool.to_costs_parser(None).is_ok(), expected_ok);
+        if expected_ok {
+            assert_eq!(tool.to_costs_parser(None).unwrap().tool(), tool);
+            assert_eq!(
+                tool.to_costs_parser(Some(&Sentinel::new("main")))
+                    .unwrap()
+                    .tool(),
+                tool
+            );
+        }
+    }
+
+    #[rstest]
+    #[case::callgrind(ValgrindTool::Callgrind, None, true)]
+    #[case::cachegrind_kind_none(ValgrindTool::Cachegrind, Some(FlamegraphKind::None), false)]
+    #[case::dhat_kind_none(ValgrindTool::DHAT, Some(FlamegraphKind::None), false)]
+    fn test_resolve_flamegraph_config(
+        #[case] tool: ValgrindTool,
+        #[case] kind: Option<FlamegraphKind>,
+        #[case] expected_some: bool,
+    ) {
+        let flamegraph_config = api::FlamegraphConfig {
+            kind,
+            ..Default::default()
+        };
+        assert_eq!(
+            resolve_flamegraph_config(tool, Some(flamegraph_config), false, EventKind::Ir, "id")
+                .unwrap()
+                .is_some(),
+            expected_some
+        );
+        assert!(
+            resolve_flamegraph_config(tool, None, false, EventKind::Ir, "id")
+                .unwrap()
+                .is_none()
+        );
+    }
+
+    #[rstest]
+    #[case::ir(EventKind::Ir, None, vec![EventKind::Ir])]
+    #[case::primary(EventKind::Dr, None, vec![EventKind::Dr])]
+    #[case::score_falls_back_to_ir(EventKind::Score, None, vec![EventKind::Ir])]
+    #[case::explicit(EventKind::Dr, Some(vec![EventKind::Dw]), vec![EventKind::Dw])]
+    fn test_resolve_flamegraph_config_event_kinds(
+        #[case] primary_event_kind: EventKind,
+        #[case] event_kinds: Option<Vec<EventKind>>,
+        #[case] expected: Vec<EventKind>,
+    ) {
+        let flamegraph_config = api::FlamegraphConfig {
+            event_kinds,
+            ..Default::default()
+        };
+        let actual = resolve_flamegraph_config(
+            ValgrindTool::Callgrind,
+            Some(flamegraph_config),
+            false,
+            primary_event_kind,
+            "id",
+        )
+        .unwrap()
+        .unwrap();
+
+        assert_eq!(actual.event_kinds, expected);
+    }
+
+    #[test]
+    fn test_resolve_flamegraph_config_compiles_symbol_transforms() {
+        let flamegraph_config = api::FlamegraphConfig {
+            symbol_transforms: Some(vec![
+                api::SymbolTransform::StripHash,
+                api::SymbolTransform::Replace("^std::".to_owned(), String::new()),
+            ]),
+            ..Default::default()
+        };
+        let actual = resolve_flamegraph_config(
+            ValgrindTool::Callgrind,
+            Some(flamegraph_config),
+            false,
+            EventKind::Ir,
+            "id",
+        )
+        .unwrap()
+        .unwrap();
+        assert_eq!(actual.symbol_transforms.len(), 2);
+
+        let flamegraph_config = api::FlamegraphConfig {
+            symbol_transforms: Some(vec![api::SymbolTransform::Replace(
+                "(".to_owned(),
+                String::new(),
+            )]),
+            ..Default::default()
+        };
+        assert!(resolve_flamegraph_config(
+            ValgrindTool::Callgrind,
+            Some(flamegraph_config),
+            false,
+            EventKind::Ir,
+            "id"
+        )
+        .unwrap_err()
+        .to_string()
+        .starts_with("Invalid regex '(' in flamegraph symbol transform: "));
+    }
+
+    #[rstest]
+    #[case::cachegrind(ValgrindTool::Cachegrind, "cachegrind")]
+    #[case::dhat(ValgrindTool::DHAT, "dhat")]
+    fn test_resolve_flamegraph_config_when_no_call_graph_then_error(
+        #[case] tool: ValgrindTool,
+        #[case] id: &str,
+    ) {
+        let error = resolve_flamegraph_config(
+            tool,
+            Some(api::FlamegraphConfig::default()),
+            false,
+            EventKind::Ir,
+            "id",
+        )
+        .unwrap_err();
+
+        assert_eq!(
+            error.to_string(),
+            format!(
+                "Flamegraphs are not supported by {id}: Only callgrind collects a call graph. \
+                 Remove the FlamegraphConfig or use FlamegraphKind::None"
+            )
+        );
+    }
+
+    #[rstest]
+    #[case::cachegrind(ValgrindTool::Cachegrind)]
+    #[case::dhat(ValgrindTool::DHAT)]
+    fn test_resolve_flamegraph_config_when_inherited_then_skip(#[case] tool: ValgrindTool) {
+        assert!(resolve_flamegraph_config(
+            tool,
+            Some(api::FlamegraphConfig::default()),
+            true,
+            EventKind::Ir,
+            "id"
+        )
+        .unwrap()
+        .is_none());
+    }
+
+    #[rstest]
+    #[case::no_filter(None, Some(FlamegraphKind::All), true, false)]
+    #[case::match_filter(Some("fib"), Some(FlamegraphKind::All), true, false)]
+    #[case::no_match_filter(Some("^fib"), Some(FlamegraphKind::All), false, true)]
+    #[case::kind_none(Some("^fib"), Some(FlamegraphKind::None), true, false)]
+    fn test_filter_flamegraph_config(
+        #[case] filter: Option<&str>,
+        #[case] kind: Option<FlamegraphKind>,
+        #[case] expected_some: bool,
+        #[case] expected_skipped: bool,
+    ) {
+        let filter = filter.map(|filter| Regex::new(filter).unwrap());
+        let mut flamegraph_config = resolve_flamegraph_config(
+            ValgrindTool::Callgrind,
+            Some(api::FlamegraphConfig {
+                kind,
+                ..Default::default()
+            }),
+            false,
+            EventKind::Ir,
+            "id",
+        )
+        .unwrap();
+
+        assert_eq!(
+            filter_flamegraph_config(
+                &mut flamegraph_config,
+                filter.as_ref(),
+                "my_bench::group::bench_fib short"
+            ),
+            expected_skipped
+        );
+        assert_eq!(flamegraph_config.is_some(), expected_some);
+    }
+
+    #[test]
+    fn test_resolve_regression_config() {
+        let regression_config = Some(api::RegressionConfig::default());
+
+        for tool in [ValgrindTool::Callgrind, ValgrindTool::Cachegrind] {
+            for inherited in [false, true] {
+                assert!(resolve_regression_config(
+                    tool,
+                    &regression_config,
+                    &None,
+                    inherited,
+                    EventKind::Ir,
+                    "id"
+                )
+                .unwrap()
+                .is_some());
+            }
+            assert!(resolve_regression_config(
+                tool,
+                &None,
+                &regression_config,
+                true,
+                EventKind::Ir,
+                "id"
+            )
+            .unwrap()
+            .is_some());
+        }
+        assert!(resolve_regression_config(
+            ValgrindTool::DHAT,
+            &None,
+            &regression_config,
+            true,
+            EventKind::Ir,
+            "id"
+        )
+        .unwrap()
+        .is_none());
+        assert_eq!(
+            resolve_regression_config(
+                ValgrindTool::DHAT,
+                &regression_config,
+                &None,
+                false,
+                EventKind::Ir,
+                "id"
+            )
+            .unwrap_err()
+            .to_string(),
+            "Regression checks are not supported by dhat: Only callgrind and cachegrind report \
+             the costs of a benchmark. Remove the RegressionConfig"
+        );
+    }
+
+    #[rstest]
+    #[case::memcheck(ValgrindTool::Memcheck)]
+    #[case::dhat(ValgrindTool::DHAT)]
+    fn test_resolve_regression_config_when_inherited_then_skip(#[case] tool: ValgrindTool) {
+        let regression_config = Some(api::RegressionConfig {
+            limits: vec![(EventKind::Ir, 5f64)],
+            ..Default::default()
+        });
+        assert!(resolve_regression_config(
+            tool,
+            &regression_config,
+            &regression_config,
+            true,
+            EventKind::Ir,
+            "id"
+        )
+        .unwrap()
+        .is_none());
+    }
+
+    #[rstest]
+    #[case::ir(EventKind::Ir, vec![], vec![(EventKind::Ir, 10f64)])]
+    #[case::primary(EventKind::Dr, vec![], vec![(EventKind::Dr, 10f64)])]
+    #[case::explicit(EventKind::Dr, vec![(EventKind::Dw, 5f64)], vec![(EventKind::Dw, 5f64)])]
+    fn test_resolve_regression_config_default_limit(
+        #[case] primary_event_kind: EventKind,
+        #[case] limits: Vec<(EventKind, f64)>,
+        #[case] expected: Vec<(EventKind, f64)>,
+    ) {
+        let regression_config = Some(api::RegressionConfig {
+            limits,
+            ..Default::default()
+        });
+        let actual = resolve_regression_config(
+            ValgrindTool::Callgrind,
+            &regression_config,
+            &None,
+            false,
+            primary_event_kind,
+            "id",
+        )
+        .unwrap()
+        .unwrap();
+
+        assert_eq!(actual.limits, expected);
+    }
+
+    #[rstest]
+    #[case::memcheck_default(ValgrindTool::Memcheck, &[], Some(201i32), true)]
+    #[case::memcheck_other_code(ValgrindTool::Memcheck, &[], Some(1i32), false)]
+    #[case::memcheck_success(ValgrindTool::Memcheck, &[], Some(0i32), false)]
+    #[case::memcheck_signal(ValgrindTool::Memcheck, &[], None, false)]
+    #[case::memcheck_custom(ValgrindTool::Memcheck, &["--error-exitcode=42"], Some(42i32), true)]
+    #[case::memcheck_disabled(ValgrindTool::Memcheck, &["--error-exitcode=0"], Some(0i32), false)]
+    #[case::helgrind(ValgrindTool::Helgrind, &[], Some(201i32), true)]
+    #[case::drd(ValgrindTool::DRD, &[], Some(201i32), true)]
+    #[case::massif(ValgrindTool::Massif, &[], Some(0i32), false)]
+    fn test_tool_args_is_error_exit(
+        #[case] tool: ValgrindTool,
+        #[case] args: &[&str],
+        #[case] code: Option<i32>,
+        #[case] expected: bool,
+    ) {
+        let tool_args = ToolArgs::from_raw_args(
+            tool,
+            api::RawArgs::new(args.iter().map(|arg| (*arg).to_owned()).collect()),
+        );
+        assert_eq!(tool_args.is_error_exit(code), expected);
+    }
+
+    #[test]
+    fn test_tool_errors_display() {
+        let error = Error::ToolErrors((
+            "memcheck".to_owned(),
+            ErrorSummary {
+                errors: 3,
+                contexts: 2,
+                supp_errors: 1,
+                supp_contexts: 1,
+            },
+            indexmap! {
+                "Invalid read of size 4".to_owned() => 1,
+                "Leak definitely lost".to_owned() => 1,
+            },
+        ));
+
+        assert_eq!(
+            error.to_string(),
+            "Error running 'memcheck': Detected 3 errors from 2 contexts (suppressed: 1 from \
+             1)\nError kinds:\n  | Invalid read of size 4: 1\n  | Leak definitely lost: 1"
+        );
+    }
+
+    #[test]
+    fn test_tool_configs_new_when_callgrind() {
+        let tools = api::Tools(vec![
+            api_tool(api::ValgrindTool::DHAT, None, &[]),
+            api_tool(api::ValgrindTool::Massif, Some(false), &[]),
+        ]);
+
+        let tool_configs = ToolConfigs::new(tools.clone(), ValgrindTool::Callgrind).unwrap();
+
+        assert_eq!(
+            tool_configs,
+            ToolConfigs(tools.0.into_iter().map(Into::into).collect())
+        );
+    }
+
+    #[test]
+    fn test_tool_configs_new_when_default_tool_not_configured() {
+        let tools = api::Tools(vec![api_tool(api::ValgrindTool::Massif, None, &[])]);
+
+        let tool_configs = ToolConfigs::new(tools, ValgrindTool::DHAT).unwrap();
+
+        assert_eq!(
+            tool_configs
+                .0
+                .iter()
+                .map(|t| (t.tool, t.is_enabled))
+                .collect::<Vec<_>>(),
+            vec![(ValgrindTool::DHAT, true), (ValgrindTool::Massif, true)]
+        );
+    }
+
+    #[test]
+    fn test_tool_configs_new_when_default_tool_configured() {
+        let tools = api::Tools(vec![
+            api_tool(api::ValgrindTool::Massif, None, &[]),
+            api_tool(api::ValgrindTool::DHAT, Some(false), &["--mode=copy"]),
+        ]);
+
+        let tool_configs = ToolConfigs::new(tools, ValgrindTool::DHAT).unwrap();
+
+        assert_eq!(tool_configs.0.len(), 2);
+        assert_eq!(
+            tool_configs.0[0],
+            ToolConfig::from(api_tool(
+                api::ValgrindTool::DHAT,
+                Some(true),
+                &["--mode=copy"]
+            ))
+        );
+        assert_eq!(tool_configs.0[1].tool, ValgrindTool::Massif);
+    }
+
+    #[rstest]
+    #[case::callgrind(api::ValgrindTool::Callgrind, "callgrind")]
+    #[case::cachegrind(api::ValgrindTool::Cachegrind, "cachegrind")]
+    fn test_tool_configs_new_when_costs_tool_then_error(
+        #[case] kind: api::ValgrindTool,
+        #[case] id: &str,
+    ) {
+        let tools = api::Tools(vec![api_tool(kind, None, &[])]);
+
+        assert_eq!(
+            ToolConfigs::new(tools, ValgrindTool::Callgrind)
+                .unwrap_err()
+                .to_string(),
+            format!("{id} can't be run as an additional tool. Use the `default_tool` instead")
+        );
+    }
+}
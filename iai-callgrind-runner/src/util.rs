@@ -1,11 +1,12 @@
 //! This module provides common utility functions
 use std::ffi::OsStr;
-use std::io::{self, BufWriter, Write};
+use std::fs::File;
+use std::io::{self, BufWriter, Read, Write};
 use std::ops::Neg;
 use std::path::{Path, PathBuf};
-use std::process::Command;
+use std::process::{Command, Output};
 
-use anyhow::{anyhow, Result};
+use anyhow::{anyhow, Context, Result};
 use log::{debug, log_enabled, trace, Level};
 use which::which;
 
@@ -45,6 +46,26 @@ pub fn truncate_str_utf8(string: &str, len: usize) -> &str {
     }
 }
 
+/// Return the last `num` non-empty lines of the `bytes` and the number of the omitted lines
+///
+/// Lines which consist only of whitespace are skipped. Invalid utf-8 is replaced with the
+/// replacement character.
+pub fn tail_lines(bytes: &[u8], num: usize) -> (Vec<String>, usize) {
+    let string = String::from_utf8_lossy(bytes);
+    let lines = string
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .collect::<Vec<_>>();
+    let omitted = lines.len().saturating_sub(num);
+    (
+        lines[omitted..]
+            .iter()
+            .map(|line| (*line).to_owned())
+            .collect(),
+        omitted,
+    )
+}
+
 /// Trim a slice of `u8` from ascii whitespace
 pub fn trim(bytes: &[u8]) -> &[u8] {
     let Some(from) = bytes.iter().position(|x| !x.is_ascii_whitespace()) else {
@@ -184,6 +205,60 @@ pub fn to_string_signed_short(n: f64) -> String {
     }
 }
 
+/// Format an unsigned integer with a `,` as separator of each group of thousands
+///
+/// For example `12345` is formatted as `12,345`.
+pub fn to_string_thousands(n: u64) -> String {
+    to_string_separated(n, ',')
+}
+
+/// Format an unsigned integer with the `separator` between each group of thousands
+///
+/// For example `12345` is formatted as `12_345` with `_` as separator.
+pub fn to_string_separated(n: u64, separator: char) -> String {
+    let digits = n.to_string();
+    let mut result = String::with_capacity(digits.len() + digits.len() / 3);
+    for (index, digit) in digits.chars().enumerate() {
+        if index > 0 && (digits.len() - index) % 3 == 0 {
+            result.push(separator);
+        }
+        result.push(digit);
+    }
+    result
+}
+
+/// Format an unsigned integer abbreviated with a SI prefix and three significant digits
+///
+/// For example `1234567890` is formatted as `1.23G`. Numbers below `1000` are not abbreviated.
+/// The rounding is done in integer arithmetic, so `999500` is formatted as `1.00M` and not as
+/// `1000k`.
+pub fn to_string_si(n: u64) -> String {
+    const PREFIXES: [&str; 6] = ["k", "M", "G", "T", "P", "E"];
+
+    if n < 1000 {
+        return n.to_string();
+    }
+
+    let mut num_digits = n.to_string().len();
+    let divisor = 10u128.pow(u32::try_from(num_digits - 3).unwrap());
+    let mut mantissa = (u128::from(n) + divisor / 2) / divisor;
+    if mantissa == 1000 {
+        num_digits += 1;
+        mantissa = 100;
+    }
+
+    let group = (num_digits - 1) / 3;
+    let integer_digits = num_digits - group * 3;
+    let digits = mantissa.to_string();
+    let (integer, fraction) = digits.split_at(integer_digits);
+    let prefix = PREFIXES[group - 1];
+    if fraction.is_empty() {
+        format!("{integer}{prefix}")
+    } else {
+        format!("{integer}.{fraction}{prefix}")
+    }
+}
+
 /// Calculate the difference between `new` and `old` as percentage
 pub fn percentage_diff(new: u64, old: u64) -> f64 {
     if new == old {
@@ -247,6 +322,170 @@ where
     }
 }
 
+/// Run the `command` like [`Command::output`] and additionally return the peak resident set size
+///
+/// The peak resident set size (`ru_maxrss`) is taken from the resource usage of the child process
+/// as reported by `wait4` and is normalized to kilobytes. On platforms without `wait4` the output
+/// is collected with [`Command::output`] and the peak resident set size is `None`.
+#[cfg(unix)]
+pub fn output_with_peak_rss(command: &mut Command) -> io::Result<(Output, Option<u64>)> {
+    use std::io::Read;
+    use std::os::unix::process::ExitStatusExt;
+    use std::process::ExitStatus;
+
+    fn read_to_end<R: Read + Send + 'static>(
+        reader: Option<R>,
+    ) -> std::thread::JoinHandle<io::Result<Vec<u8>>> {
+        std::thread::spawn(move || {
+            let mut buffer = Vec::new();
+            if let Some(mut reader) = reader {
+                reader.read_to_end(&mut buffer)?;
+            }
+            Ok(buffer)
+        })
+    }
+
+    let mut child = command.spawn()?;
+    let stdout = read_to_end(child.stdout.take());
+    let stderr = read_to_end(child.stderr.take());
+
+    let pid = libc::pid_t::try_from(child.id())
+        .map_err(|error| io::Error::new(io::ErrorKind::Other, error))?;
+    let mut status: libc::c_int = 0;
+    // SAFETY: An all-zero `rusage` is a valid value
+    let mut usage: libc::rusage = unsafe { std::mem::zeroed() };
+    loop {
+        // SAFETY: `pid` is the id of our own child process which hasn't been reaped yet and the
+        // pointers point to valid and properly aligned memory
+        let result = unsafe { libc::wait4(pid, &mut status, 0, &mut usage) };
+        if result != -1_i32 {
+            break;
+        }
+        let error = io::Error::last_os_error();
+        if error.kind() != io::ErrorKind::Interrupted {
+            return Err(error);
+        }
+    }
+
+    let join = |handle: std::thread::JoinHandle<io::Result<Vec<u8>>>| {
+        handle
+            .join()
+            .map_err(|_| io::Error::new(io::ErrorKind::Other, "Reading output of child failed"))?
+    };
+    let output = Output {
+        status: ExitStatus::from_raw(status),
+        stdout: join(stdout)?,
+        stderr: join(stderr)?,
+    };
+
+    // On macOS `ru_maxrss` is reported in bytes, on all other unix platforms in kilobytes
+    let max_rss = u64::try_from(usage.ru_maxrss).unwrap_or(0);
+    let peak_rss = if cfg!(target_os = "macos") {
+        max_rss / 1024
+    } else {
+        max_rss
+    };
+
+    Ok((output, Some(peak_rss)))
+}
+
+/// Run the `command` like [`Command::output`] and additionally return the peak resident set size
+///
+/// The peak resident set size can't be determined on this platform and is always `None`.
+#[cfg(not(unix))]
+pub fn output_with_peak_rss(command: &mut Command) -> io::Result<(Output, Option<u64>)> {
+    command.output().map(|output| (output, None))
+}
+
+/// Return the name and number of the `signal` like `SIGSEGV (11)`
+///
+/// Only the common signals which terminate a process are named. All other signals are returned
+/// as number only.
+pub fn signal_name(signal: i32) -> String {
+    let name = match signal {
+        libc::SIGABRT => "SIGABRT",
+        libc::SIGBUS => "SIGBUS",
+        libc::SIGFPE => "SIGFPE",
+        libc::SIGHUP => "SIGHUP",
+        libc::SIGILL => "SIGILL",
+        libc::SIGINT => "SIGINT",
+        libc::SIGKILL => "SIGKILL",
+        libc::SIGPIPE => "SIGPIPE",
+        libc::SIGQUIT => "SIGQUIT",
+        libc::SIGSEGV => "SIGSEGV",
+        libc::SIGSYS => "SIGSYS",
+        libc::SIGTERM => "SIGTERM",
+        libc::SIGTRAP => "SIGTRAP",
+        _ => return signal.to_string(),
+    };
+    format!("{name} ({signal})")
+}
+
+/// The 64-bit FNV-1a hash used to identify the content of files and strings
+///
+/// The hash is not cryptographically secure but stable across platforms and rust versions, so it
+/// can be stored next to the output files and compared with later runs.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ContentHasher(u64);
+
+impl ContentHasher {
+    const OFFSET_BASIS: u64 = 0xcbf2_9ce4_8422_2325;
+    const PRIME: u64 = 0x0100_0000_01b3;
+
+    pub fn new() -> Self {
+        Self(Self::OFFSET_BASIS)
+    }
+
+    pub fn update(&mut self, bytes: &[u8]) {
+        for byte in bytes {
+            self.0 ^= u64::from(*byte);
+            self.0 = self.0.wrapping_mul(Self::PRIME);
+        }
+    }
+
+    /// Return the hash as hexadecimal string with 16 digits
+    pub fn finish(&self) -> String {
+        format!("{:016x}", self.0)
+    }
+}
+
+impl Default for ContentHasher {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Return the [`ContentHasher`] hash of the `bytes`
+pub fn hash_bytes(bytes: &[u8]) -> String {
+    let mut hasher = ContentHasher::new();
+    hasher.update(bytes);
+    hasher.finish()
+}
+
+/// Return the [`ContentHasher`] hash of the content of the file at `path`
+///
+/// # Errors
+///
+/// If the file could not be opened or read
+pub fn hash_file(path: &Path) -> Result<String> {
+    let mut file = File::open(path)
+        .with_context(|| format!("Failed to open '{}' for hashing", path.display()))?;
+    let mut hasher = ContentHasher::new();
+    let mut buffer = [0; 8 * 1024];
+    loop {
+        match file.read(&mut buffer) {
+            Ok(0) => break,
+            Ok(n) => hasher.update(&buffer[..n]),
+            Err(error) if error.kind() == io::ErrorKind::Interrupted => {}
+            Err(error) => {
+                return Err(error)
+                    .with_context(|| format!("Failed to read '{}' for hashing", path.display()));
+            }
+        }
+    }
+    Ok(hasher.finish())
+}
+
 #[cfg(test)]
 mod tests {
     use rstest::rstest;
@@ -286,6 +525,25 @@ mod tests {
         assert_eq!(truncate_str_utf8(input, len), expected);
     }
 
+    #[rstest]
+    #[case::empty("", 2, &[], 0)]
+    #[case::only_whitespace("\n  \n\t\n", 2, &[], 0)]
+    #[case::less_than_num("a\nb\n", 3, &["a", "b"], 0)]
+    #[case::exactly_num("a\nb", 2, &["a", "b"], 0)]
+    #[case::more_than_num("a\nb\nc\nd\n", 2, &["c", "d"], 2)]
+    #[case::skips_empty_lines("a\n\nb\n\n\nc\n", 2, &["b", "c"], 1)]
+    #[case::zero("a\nb", 0, &[], 2)]
+    fn test_tail_lines(
+        #[case] input: &str,
+        #[case] num: usize,
+        #[case] expected: &[&str],
+        #[case] expected_omitted: usize,
+    ) {
+        let (lines, omitted) = tail_lines(input.as_bytes(), num);
+        assert_eq!(lines, expected);
+        assert_eq!(omitted, expected_omitted);
+    }
+
     #[rstest]
     #[case::zero(0, 0, 1f64)]
     #[case::infinity(1, 0, f64::INFINITY)]
@@ -296,4 +554,96 @@ mod tests {
     fn test_factor_diff_eq(#[case] a: u64, #[case] b: u64, #[case] expected: f64) {
         assert_eq!(factor_diff(a, b), expected);
     }
+
+    #[rstest]
+    #[case::zero(0, "0")]
+    #[case::three_digits(999, "999")]
+    #[case::four_digits(1000, "1,000")]
+    #[case::five_digits(12345, "12,345")]
+    #[case::seven_digits(1_234_567, "1,234,567")]
+    #[case::max(u64::MAX, "18,446,744,073,709,551,615")]
+    fn test_to_string_thousands(#[case] n: u64, #[case] expected: &str) {
+        assert_eq!(to_string_thousands(n), expected);
+    }
+
+    #[rstest]
+    #[case::zero(0, '_', "0")]
+    #[case::three_digits(999, '_', "999")]
+    #[case::four_digits(1000, '_', "1_000")]
+    #[case::seven_digits(1_234_567, '_', "1_234_567")]
+    #[case::max(u64::MAX, '_', "18_446_744_073_709_551_615")]
+    #[case::comma(1_234_567, ',', "1,234,567")]
+    fn test_to_string_separated(#[case] n: u64, #[case] separator: char, #[case] expected: &str) {
+        assert_eq!(to_string_separated(n, separator), expected);
+    }
+
+    #[rstest]
+    #[case::zero(0, "0")]
+    #[case::three_digits(999, "999")]
+    #[case::kilo(1000, "1.00k")]
+    #[case::kilo_two_digits(12_345, "12.3k")]
+    #[case::kilo_three_digits(123_456, "123k")]
+    #[case::round_down(999_499, "999k")]
+    #[case::round_up_to_next_prefix(999_500, "1.00M")]
+    #[case::mega(1_000_000, "1.00M")]
+    #[case::giga(1_234_567_890, "1.23G")]
+    #[case::round_half_up(1_235_000_000, "1.24G")]
+    #[case::tera(1_000_000_000_000, "1.00T")]
+    #[case::peta(1_000_000_000_000_000, "1.00P")]
+    #[case::exa(1_000_000_000_000_000_000, "1.00E")]
+    #[case::max(u64::MAX, "18.4E")]
+    fn test_to_string_si(#[case] n: u64, #[case] expected: &str) {
+        assert_eq!(to_string_si(n), expected);
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn test_output_with_peak_rss() {
+        let mut command = Command::new("sh");
+        command
+            .args(["-c", "echo stdout; echo stderr >&2; exit 3"])
+            .stdout(std::process::Stdio::piped())
+            .stderr(std::process::Stdio::piped());
+
+        let (output, peak_rss) = output_with_peak_rss(&mut command).unwrap();
+
+        assert_eq!(output.status.code(), Some(3_i32));
+        assert_eq!(output.stdout, b"stdout\n");
+        assert_eq!(output.stderr, b"stderr\n");
+        assert!(peak_rss.unwrap() > 0_u64);
+    }
+
+    #[rstest]
+    #[case::segfault(libc::SIGSEGV, "SIGSEGV (11)")]
+    #[case::abort(libc::SIGABRT, "SIGABRT (6)")]
+    #[case::kill(libc::SIGKILL, "SIGKILL (9)")]
+    #[case::unknown(1000_i32, "1000")]
+    fn test_signal_name(#[case] signal: i32, #[case] expected: &str) {
+        assert_eq!(signal_name(signal), expected);
+    }
+
+    // The reference values are the official FNV-1a test vectors
+    #[rstest]
+    #[case::empty(b"", "cbf29ce484222325")]
+    #[case::a(b"a", "af63dc4c8601ec8c")]
+    #[case::foobar(b"foobar", "85944171f73967e8")]
+    fn test_hash_bytes(#[case] bytes: &[u8], #[case] expected: &str) {
+        assert_eq!(hash_bytes(bytes), expected);
+    }
+
+    #[test]
+    fn test_hash_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("file");
+        let content = "0123456789".repeat(10_000);
+        std::fs::write(&path, &content).unwrap();
+
+        assert_eq!(hash_file(&path).unwrap(), hash_bytes(content.as_bytes()));
+    }
+
+    #[test]
+    fn test_hash_file_when_missing() {
+        let dir = tempfile::tempdir().unwrap();
+        assert!(hash_file(&dir.path().join("missing")).is_err());
+    }
 }
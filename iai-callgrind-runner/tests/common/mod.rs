@@ -43,6 +43,7 @@ impl Fixtures {
             tool,
             baseline_kind: BaselineKind::Old,
             dir: Fixtures::get_path().join(dir),
+            baseline_dir: Fixtures::get_path().join(dir),
             name: name.to_owned(),
             modifiers: vec![],
         }
@@ -0,0 +1,94 @@
+use iai_callgrind_runner::api::EventKind;
+use iai_callgrind_runner::runner::cachegrind::summary_parser::SummaryParser;
+use iai_callgrind_runner::runner::callgrind::model::Costs;
+use iai_callgrind_runner::runner::tool::{Parser, ToolOutputPathKind, ValgrindTool};
+use rstest::rstest;
+
+use crate::common::{assert_parse_error, Fixtures};
+
+#[rstest]
+#[case::with_summary(
+    "with_summary",
+    vec![
+        (EventKind::Ir, 325261),
+        (EventKind::I1mr, 1595),
+        (EventKind::ILmr, 1558),
+        (EventKind::Dr, 78145),
+        (EventKind::D1mr, 2119),
+        (EventKind::DLmr, 1485),
+        (EventKind::Dw, 35789),
+        (EventKind::D1mw, 850),
+        (EventKind::DLmw, 799),
+    ]
+)]
+#[case::with_branch_sim(
+    "with_branch_sim",
+    vec![
+        (EventKind::Ir, 325261),
+        (EventKind::I1mr, 1595),
+        (EventKind::ILmr, 1558),
+        (EventKind::Dr, 78145),
+        (EventKind::D1mr, 2119),
+        (EventKind::DLmr, 1485),
+        (EventKind::Dw, 35789),
+        (EventKind::D1mw, 850),
+        (EventKind::DLmw, 799),
+        (EventKind::Bc, 50120),
+        (EventKind::Bcm, 2003),
+        (EventKind::Bi, 1201),
+        (EventKind::Bim, 99),
+    ]
+)]
+#[case::no_cache_sim("no_cache_sim", vec![(EventKind::Ir, 325261)])]
+fn test_summary_parser(#[case] fixture: &str, #[case] costs: Vec<(EventKind, u64)>) {
+    let expected_costs = Costs::with_event_kinds(costs);
+
+    let cachegrind_output = Fixtures::get_tool_output_path(
+        "cachegrind.out",
+        ValgrindTool::Cachegrind,
+        ToolOutputPathKind::Out,
+        fixture,
+    );
+
+    let actual_costs = SummaryParser.parse(&cachegrind_output).unwrap();
+
+    assert_eq!(actual_costs, expected_costs);
+}
+
+#[rstest]
+#[case::no_summary("no_summary", "No summary line found")]
+#[case::no_events(
+    "no_events",
+    "Header field 'events' must be present before the summary"
+)]
+fn test_summary_parser_then_error(#[case] fixture: &str, #[case] message: &str) {
+    let cachegrind_output = Fixtures::get_tool_output_path(
+        "cachegrind.out",
+        ValgrindTool::Cachegrind,
+        ToolOutputPathKind::Out,
+        fixture,
+    );
+
+    let result = SummaryParser.parse(&cachegrind_output);
+    assert_parse_error(&cachegrind_output.to_path(), result, message);
+}
+
+#[test]
+fn test_summary_parser_when_too_many_costs_then_error() {
+    let cachegrind_output = Fixtures::get_tool_output_path(
+        "cachegrind.out",
+        ValgrindTool::Cachegrind,
+        ToolOutputPathKind::Out,
+        "invalid.too_many_costs",
+    );
+
+    let error = SummaryParser.parse(&cachegrind_output).unwrap_err();
+    assert_eq!(
+        error.to_string(),
+        format!(
+            "Error parsing file '{}' at line 6: Expected at most 1 costs as in the 'events' line \
+             but found 2",
+            cachegrind_output.to_path().display()
+        )
+    );
+}
@@ -1,8 +1,18 @@
 #[cfg(test)]
+mod test_call_graph;
+#[cfg(test)]
+mod test_cross_arch;
+#[cfg(test)]
 mod test_flamegraph_parser;
 #[cfg(test)]
 mod test_hashmap_parser;
 #[cfg(test)]
+mod test_metadata_parser;
+#[cfg(test)]
+mod test_output_parser;
+#[cfg(test)]
+mod test_self_cost_parser;
+#[cfg(test)]
 mod test_sentinel_parser;
 #[cfg(test)]
 mod test_summary_parser;
@@ -0,0 +1,191 @@
+use std::path::PathBuf;
+
+use iai_callgrind_runner::api::EventKind;
+use iai_callgrind_runner::runner::callgrind::call_graph::{Call, CallGraph, CallGraphParser};
+use iai_callgrind_runner::runner::callgrind::hashmap_parser::{Id, SourcePath};
+use iai_callgrind_runner::runner::tool::{Parser, ToolOutputPathKind, ValgrindTool};
+use pretty_assertions::assert_eq;
+use rstest::rstest;
+
+use crate::common::Fixtures;
+
+fn id(func: &str) -> Id {
+    Id {
+        obj: None,
+        file: None,
+        func: func.to_owned(),
+    }
+}
+
+fn parse_call_graph(name: &str) -> CallGraph {
+    let callgrind_output = Fixtures::get_tool_output_path(
+        "callgrind.out",
+        ValgrindTool::Callgrind,
+        ToolOutputPathKind::Out,
+        name,
+    );
+    CallGraphParser::default().parse(&callgrind_output).unwrap()
+}
+
+#[rstest]
+#[case::main("main", 5, 39)]
+#[case::foo("foo", 10, 30)]
+#[case::bar("bar", 24, 24)]
+fn test_call_graph_function_costs(
+    #[case] func: &str,
+    #[case] self_cost: u64,
+    #[case] inclusive_cost: u64,
+) {
+    let call_graph = parse_call_graph("call_graph");
+    let function = call_graph.function(&id(func)).unwrap();
+
+    assert_eq!(
+        function.self_costs.cost_by_kind(&EventKind::Ir),
+        Some(self_cost)
+    );
+    assert_eq!(
+        function.inclusive_costs.cost_by_kind(&EventKind::Ir),
+        Some(inclusive_cost)
+    );
+}
+
+#[rstest]
+#[case::main("main", &[], &[("foo", 2, 30), ("bar", 1, 4)], 0)]
+#[case::foo("foo", &[("main", 2, 30)], &[("bar", 3, 20)], 2)]
+#[case::bar("bar", &[("main", 1, 4), ("foo", 3, 20)], &[], 4)]
+fn test_call_graph_edges(
+    #[case] func: &str,
+    #[case] expected_callers: &[(&str, u64, u64)],
+    #[case] expected_callees: &[(&str, u64, u64)],
+    #[case] expected_total_calls: u64,
+) {
+    let call_graph = parse_call_graph("call_graph");
+    let func = id(func);
+    let to_tuples = |(id, call): (&Id, &Call)| {
+        (
+            id.func.clone(),
+            call.count,
+            call.costs.cost_by_kind(&EventKind::Ir).unwrap(),
+        )
+    };
+    let to_owned = |expected: &[(&str, u64, u64)]| {
+        expected
+            .iter()
+            .map(|(func, count, cost)| ((*func).to_owned(), *count, *cost))
+            .collect::<Vec<_>>()
+    };
+
+    assert_eq!(
+        call_graph.callers(&func).map(to_tuples).collect::<Vec<_>>(),
+        to_owned(expected_callers)
+    );
+    assert_eq!(
+        call_graph.callees(&func).map(to_tuples).collect::<Vec<_>>(),
+        to_owned(expected_callees)
+    );
+    assert_eq!(call_graph.total_calls(&func), expected_total_calls);
+}
+
+// The self costs of all functions have to add up to the `totals:` line of the output file
+#[rstest]
+#[case::call_graph("call_graph", 39)]
+#[case::no_entry_point("no_entry_point", 325_259)]
+fn test_call_graph_self_costs_sum_equals_totals(#[case] name: &str, #[case] expected: u64) {
+    let call_graph = parse_call_graph(name);
+
+    assert_eq!(
+        call_graph
+            .functions
+            .values()
+            .map(|function| function.self_costs.cost_by_kind(&EventKind::Ir).unwrap())
+            .sum::<u64>(),
+        expected
+    );
+}
+
+#[test]
+fn test_call_graph_when_no_records() {
+    assert!(parse_call_graph("no_records.with_summary_and_totals").is_empty());
+}
+
+#[rstest]
+#[case::too_many_costs(
+    "invalid.too_many_costs",
+    24,
+    "Expected at most 2 costs as in the 'events' line but found 4"
+)]
+#[case::not_a_cost("invalid.not_a_cost", 18, "Invalid cost: '<svg'")]
+fn test_call_graph_when_malformed_cost_line_then_error(
+    #[case] name: &str,
+    #[case] line: usize,
+    #[case] message: &str,
+) {
+    let callgrind_output = Fixtures::get_tool_output_path(
+        "callgrind.out",
+        ValgrindTool::Callgrind,
+        ToolOutputPathKind::Out,
+        name,
+    );
+
+    let error = CallGraphParser::default()
+        .parse(&callgrind_output)
+        .unwrap_err();
+    assert_eq!(
+        error.to_string(),
+        format!(
+            "Error parsing file '{}' at line {line}: {message}",
+            callgrind_output.to_path().display()
+        )
+    );
+}
+
+#[rstest]
+#[case::foo_called_by_main("foo'main", 30, 30)]
+#[case::foo_called_by_bar("foo'bar", 15, 15)]
+#[case::bar("bar'main", 5, 20)]
+fn test_call_graph_when_separate_callers(
+    #[case] func: &str,
+    #[case] self_cost: u64,
+    #[case] inclusive_cost: u64,
+) {
+    let call_graph = parse_call_graph("with_separate_callers");
+    let function = call_graph.function(&id(func)).unwrap();
+
+    assert_eq!(
+        function.self_costs.cost_by_kind(&EventKind::Ir),
+        Some(self_cost)
+    );
+    assert_eq!(
+        function.inclusive_costs.cost_by_kind(&EventKind::Ir),
+        Some(inclusive_cost)
+    );
+}
+
+#[rstest]
+#[case::function_file("src/foo.rs", 20, 40, false)]
+#[case::inlined_file("src/inlined.rs", 20, 20, true)]
+fn test_call_graph_when_inlined(
+    #[case] file: &str,
+    #[case] self_cost: u64,
+    #[case] inclusive_cost: u64,
+    #[case] inlined: bool,
+) {
+    let call_graph = parse_call_graph("with_inlined");
+    let function = call_graph
+        .function(&Id {
+            obj: None,
+            file: Some(SourcePath::Relative(PathBuf::from(file))),
+            func: "foo".to_owned(),
+        })
+        .unwrap();
+
+    assert_eq!(
+        function.self_costs.cost_by_kind(&EventKind::Ir),
+        Some(self_cost)
+    );
+    assert_eq!(
+        function.inclusive_costs.cost_by_kind(&EventKind::Ir),
+        Some(inclusive_cost)
+    );
+    assert_eq!(function.inlined, inlined);
+}
@@ -0,0 +1,94 @@
+use iai_callgrind_runner::api::EventKind;
+use iai_callgrind_runner::runner::callgrind::cross_arch::CrossArchComparison;
+use iai_callgrind_runner::runner::callgrind::output_parser::CallgrindOutput;
+use iai_callgrind_runner::runner::tool::{ToolOutputPathKind, ValgrindTool};
+use pretty_assertions::assert_eq;
+
+use crate::common::Fixtures;
+
+fn callgrind_output(name: &str) -> CallgrindOutput {
+    CallgrindOutput::new(Fixtures::get_tool_output_path(
+        "callgrind.out",
+        ValgrindTool::Callgrind,
+        ToolOutputPathKind::Out,
+        name,
+    ))
+}
+
+#[test]
+fn test_cross_arch_comparison() {
+    let comparison = CrossArchComparison::new(
+        &callgrind_output("cross_arch.aarch64"),
+        &callgrind_output("cross_arch.x86_64"),
+    )
+    .unwrap();
+
+    assert_eq!(comparison.arch, "aarch64");
+    assert_eq!(comparison.other_arch, "x86_64");
+
+    let diff = comparison
+        .costs_summary
+        .diff_by_kind(&EventKind::Ir)
+        .unwrap();
+    assert_eq!(diff.new, Some(1100));
+    assert_eq!(diff.old, Some(1000));
+    assert_eq!(diff.diff_pct, Some(10f64));
+}
+
+#[test]
+fn test_cross_arch_comparison_format() {
+    let comparison = CrossArchComparison::new(
+        &callgrind_output("cross_arch.aarch64"),
+        &callgrind_output("cross_arch.x86_64"),
+    )
+    .unwrap();
+
+    let formatted = comparison.to_string();
+    let mut lines = formatted.lines();
+    assert_eq!(
+        lines.next(),
+        Some(
+            "  Cross-architecture comparison: The costs of different architectures are not \
+             comparable as faster or slower"
+        )
+    );
+    assert_eq!(
+        lines.next(),
+        Some("  Architectures:            aarch64|x86_64")
+    );
+    assert_eq!(
+        lines.next(),
+        Some("  Instructions:                1100|1000            (+10.0000%) [+1.10000x]")
+    );
+}
+
+#[test]
+fn test_cross_arch_comparison_when_same_arch_then_error() {
+    let output = callgrind_output("cross_arch.x86_64");
+    let error = CrossArchComparison::new(&output, &output).unwrap_err();
+
+    assert_eq!(
+        error.to_string(),
+        format!(
+            "The outputs '{0}' and '{0}' were both created on 'x86_64': Comparing the costs of \
+             the same architecture is not a cross-architecture comparison",
+            output.path
+        )
+    );
+}
+
+#[test]
+fn test_cross_arch_comparison_when_arch_unknown_then_error() {
+    let output = callgrind_output("with_hashes");
+    let error =
+        CrossArchComparison::new(&output, &callgrind_output("cross_arch.x86_64")).unwrap_err();
+
+    assert_eq!(
+        error.to_string(),
+        format!(
+            "The architecture of the output '{}' is unknown: The output was created by an earlier \
+             version of iai-callgrind or the '*.meta' file is missing",
+            output.path
+        )
+    );
+}
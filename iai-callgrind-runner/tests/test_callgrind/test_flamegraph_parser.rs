@@ -1,15 +1,36 @@
-use iai_callgrind_runner::api::EventKind;
-use iai_callgrind_runner::runner::callgrind::flamegraph_parser::FlamegraphParser;
+use iai_callgrind_runner::api::{Ellipsis, EventKind, ObjectMarker, RootFrame};
+use iai_callgrind_runner::runner::callgrind::flamegraph::{
+    FlamegraphBuilder, SymbolTransform, TRUNCATED_FRAME,
+};
+use iai_callgrind_runner::runner::callgrind::flamegraph_parser::{FlamegraphMap, FlamegraphParser};
+use iai_callgrind_runner::runner::callgrind::output_parser::CallgrindOutput;
 use iai_callgrind_runner::runner::callgrind::parser::Sentinel;
 use iai_callgrind_runner::runner::tool::{Parser, ToolOutputPathKind, ValgrindTool};
+use regex::Regex;
 use rstest::rstest;
 
 use crate::common::{get_project_root, Fixtures};
 
 #[rstest]
-#[case::when_entry_point("when_entry_point", Some(Sentinel::new("benchmark_tests_exit::main")))]
-#[case::no_entry_point("no_entry_point", None)]
-fn test_flamegraph_parser(#[case] name: &str, #[case] sentinel: Option<Sentinel>) {
+#[case::when_entry_point(
+    "when_entry_point",
+    "when_entry_point",
+    Some(Sentinel::new("benchmark_tests_exit::main")),
+    false
+)]
+#[case::when_entry_point_trim_harness(
+    "when_entry_point",
+    "when_entry_point.trim_harness",
+    Some(Sentinel::new("benchmark_tests_exit::main")),
+    true
+)]
+#[case::no_entry_point("no_entry_point", "no_entry_point", None, false)]
+fn test_flamegraph_parser(
+    #[case] name: &str,
+    #[case] expected: &str,
+    #[case] sentinel: Option<Sentinel>,
+    #[case] trim_harness: bool,
+) {
     let output = Fixtures::get_tool_output_path(
         "callgrind.out",
         ValgrindTool::Callgrind,
@@ -17,11 +38,15 @@ fn test_flamegraph_parser(#[case] name: &str, #[case] sentinel: Option<Sentinel>
         name,
     );
     let expected_stacks =
-        Fixtures::load_stacks(format!("callgrind.out/callgrind.{name}.exp_stacks"));
+        Fixtures::load_stacks(format!("callgrind.out/callgrind.{expected}.exp_stacks"));
     let parser = FlamegraphParser::new(sentinel.as_ref(), get_project_root());
 
     let result = parser.parse(&output).unwrap();
-    let stacks = result.to_stack_format(&EventKind::Ir).unwrap();
+    let stacks = result
+        .to_stacks(&EventKind::Ir, trim_harness, ObjectMarker::Bracketed)
+        .unwrap()
+        .to_stack_format(&EventKind::Ir)
+        .unwrap();
 
     assert_eq!(stacks.len(), expected_stacks.len());
     // Assert line by line or else the output on error is unreadable. Also, provide an additional
@@ -47,3 +72,403 @@ fn test_flamegraph_parser(#[case] name: &str, #[case] sentinel: Option<Sentinel>
 
     assert!(!failed);
 }
+
+#[rstest]
+#[case::bracketed(
+    ObjectMarker::Bracketed,
+    "benchmark_tests_exit::main [target/release/benchmark-tests-exit];\
+     /rustc/7737e0b5/library/std/src/process.rs:std::process::exit \
+     [target/release/benchmark-tests-exit] 235"
+)]
+#[case::suffix(
+    ObjectMarker::Suffix,
+    "benchmark_tests_exit::main (in target/release/benchmark-tests-exit);\
+     /rustc/7737e0b5/library/std/src/process.rs:std::process::exit \
+     (in target/release/benchmark-tests-exit) 235"
+)]
+#[case::none(
+    ObjectMarker::None,
+    "benchmark_tests_exit::main;/rustc/7737e0b5/library/std/src/process.rs:std::process::exit 235"
+)]
+fn test_flamegraph_parser_object_marker(
+    #[case] object_marker: ObjectMarker,
+    #[case] expected: &str,
+) {
+    let output = Fixtures::get_tool_output_path(
+        "callgrind.out",
+        ValgrindTool::Callgrind,
+        ToolOutputPathKind::Out,
+        "when_entry_point",
+    );
+    let sentinel = Sentinel::new("benchmark_tests_exit::main");
+    let parser = FlamegraphParser::new(Some(&sentinel), get_project_root());
+
+    let stacks = parser
+        .parse(&output)
+        .unwrap()
+        .to_stacks(&EventKind::Ir, true, object_marker)
+        .unwrap()
+        .to_stack_format(&EventKind::Ir)
+        .unwrap();
+
+    assert_eq!(stacks[1], expected);
+}
+
+#[test]
+fn test_flamegraph_map_try_from_callgrind_output() {
+    let output = Fixtures::get_tool_output_path(
+        "callgrind.out",
+        ValgrindTool::Callgrind,
+        ToolOutputPathKind::Out,
+        "when_entry_point",
+    );
+    let sentinel = Sentinel::new("benchmark_tests_exit::main");
+    let expected = FlamegraphParser::new(Some(&sentinel), get_project_root())
+        .parse(&output)
+        .unwrap();
+
+    let map = FlamegraphMap::try_from(
+        &CallgrindOutput::new(output)
+            .sentinel(Some(&sentinel))
+            .project_root(get_project_root()),
+    )
+    .unwrap();
+
+    assert_eq!(map, expected);
+}
+
+#[test]
+fn test_flamegraph_map_data_rw_is_sum_of_data_reads_and_writes() {
+    let output = Fixtures::get_tool_output_path(
+        "callgrind.out",
+        ValgrindTool::Callgrind,
+        ToolOutputPathKind::Out,
+        "no_entry_point",
+    );
+    let mut map = FlamegraphParser::new(None, get_project_root())
+        .parse(&output)
+        .unwrap();
+    map.make_summary().unwrap();
+
+    let data_reads = map
+        .to_stacks(&EventKind::Dr, false, ObjectMarker::Bracketed)
+        .unwrap();
+    let data_writes = map
+        .to_stacks(&EventKind::Dw, false, ObjectMarker::Bracketed)
+        .unwrap();
+    let data_rw = map
+        .to_stacks(&EventKind::DataRW, false, ObjectMarker::Bracketed)
+        .unwrap();
+
+    // Stacks without costs are not included, so only the totals are comparable
+    assert!(data_rw.len() >= data_reads.len().max(data_writes.len()));
+    assert_eq!(
+        data_rw.total(&EventKind::DataRW),
+        data_reads.total(&EventKind::Dr) + data_writes.total(&EventKind::Dw)
+    );
+}
+
+#[test]
+fn test_flamegraph_to_stacks_with_max_depth_conserves_costs() {
+    let output = Fixtures::get_tool_output_path(
+        "callgrind.out",
+        ValgrindTool::Callgrind,
+        ToolOutputPathKind::Out,
+        "no_entry_point",
+    );
+    let map = FlamegraphParser::new(None, get_project_root())
+        .parse(&output)
+        .unwrap();
+
+    let expected = FlamegraphBuilder::new("heading")
+        .build()
+        .to_stacks(&map, &EventKind::Ir)
+        .unwrap();
+    let stacks = FlamegraphBuilder::new("heading")
+        .max_depth(2)
+        .build()
+        .to_stacks(&map, &EventKind::Ir)
+        .unwrap();
+
+    assert!(expected.iter().any(|stack| stack.frames.len() > 3));
+    assert!(stacks.iter().all(|stack| stack.frames.len() <= 3));
+    assert!(stacks
+        .iter()
+        .any(|stack| stack.frames.last().map(String::as_str) == Some(TRUNCATED_FRAME)));
+    assert_eq!(stacks.total(&EventKind::Ir), expected.total(&EventKind::Ir));
+}
+
+#[rstest]
+#[case::count_inline(
+    true,
+    1,
+    vec!["src/main.rs:main 5", "src/main.rs:main;... 40"]
+)]
+#[case::count_inline_deeper(
+    true,
+    2,
+    vec![
+        "src/main.rs:main 5",
+        "src/main.rs:main;src/foo.rs:foo 20",
+        "src/main.rs:main;src/foo.rs:foo;... 20"
+    ]
+)]
+#[case::no_count_inline(
+    false,
+    2,
+    vec![
+        "src/main.rs:main 5",
+        "src/main.rs:main;src/foo.rs:foo 20",
+        "src/main.rs:main;src/foo.rs:foo;src/inlined.rs:foo 20"
+    ]
+)]
+fn test_flamegraph_to_stacks_with_max_depth_count_inline(
+    #[case] count_inline: bool,
+    #[case] max_depth: usize,
+    #[case] expected: Vec<&str>,
+) {
+    let output = Fixtures::get_tool_output_path(
+        "callgrind.out",
+        ValgrindTool::Callgrind,
+        ToolOutputPathKind::Out,
+        "with_inlined",
+    );
+    let map = FlamegraphParser::new(None, get_project_root())
+        .parse(&output)
+        .unwrap();
+
+    let stacks = FlamegraphBuilder::new("heading")
+        .max_depth(max_depth)
+        .max_depth_count_inline(count_inline)
+        .build()
+        .to_stacks(&map, &EventKind::Ir)
+        .unwrap();
+
+    assert_eq!(stacks.total(&EventKind::Ir), 45);
+    assert_eq!(
+        stacks.to_stack_format(&EventKind::Ir).unwrap(),
+        expected
+            .into_iter()
+            .map(ToOwned::to_owned)
+            .collect::<Vec<String>>()
+    );
+}
+
+#[rstest]
+#[case::command(RootFrame::Command, "benchmark-tests-exit")]
+#[case::command_and_pid(RootFrame::CommandAndPid, "benchmark-tests-exit (pid 3289652)")]
+fn test_flamegraph_to_stacks_with_root_frame(
+    #[case] root_frame: RootFrame,
+    #[case] expected: &str,
+) {
+    let output = Fixtures::get_tool_output_path(
+        "callgrind.out",
+        ValgrindTool::Callgrind,
+        ToolOutputPathKind::Out,
+        "no_entry_point",
+    );
+    let without_root = FlamegraphBuilder::new("heading").build();
+    let (map, _) = without_root
+        .parse(&output, None, get_project_root(), true)
+        .unwrap();
+    let expected_stacks = without_root.to_stacks(&map, &EventKind::Ir).unwrap();
+
+    // The root frame is not changed by the symbol transforms or the truncation of the names
+    let flamegraph = FlamegraphBuilder::new("heading")
+        .root_frame(root_frame)
+        .symbol_transform(SymbolTransform::Replace(
+            Regex::new("benchmark").unwrap(),
+            "replaced".to_owned(),
+        ))
+        .max_frame_name_length(10)
+        .build();
+    let (map, _) = flamegraph
+        .parse(&output, None, get_project_root(), true)
+        .unwrap();
+    let stacks = flamegraph.to_stacks(&map, &EventKind::Ir).unwrap();
+
+    assert_eq!(stacks.len(), expected_stacks.len());
+    assert!(stacks
+        .iter()
+        .all(|stack| stack.frames.first().map(String::as_str) == Some(expected)));
+    for (stack, expected_stack) in stacks.iter().zip(expected_stacks.iter()) {
+        assert_eq!(stack.frames.len(), expected_stack.frames.len() + 1);
+        assert_eq!(stack.costs, expected_stack.costs);
+    }
+}
+
+#[rstest]
+#[case::one(1)]
+#[case::three(3)]
+fn test_flamegraph_to_stacks_with_root_frame_and_max_depth(#[case] max_depth: usize) {
+    let output = Fixtures::get_tool_output_path(
+        "callgrind.out",
+        ValgrindTool::Callgrind,
+        ToolOutputPathKind::Out,
+        "no_entry_point",
+    );
+    let without_root = FlamegraphBuilder::new("heading")
+        .max_depth(max_depth)
+        .build();
+    let (map, _) = without_root
+        .parse(&output, None, get_project_root(), true)
+        .unwrap();
+    let expected_stacks = without_root.to_stacks(&map, &EventKind::Ir).unwrap();
+
+    // The root frame doesn't count towards the max depth
+    let flamegraph = FlamegraphBuilder::new("heading")
+        .root_frame(RootFrame::Command)
+        .max_depth(max_depth)
+        .build();
+    let (map, _) = flamegraph
+        .parse(&output, None, get_project_root(), true)
+        .unwrap();
+    let stacks = flamegraph.to_stacks(&map, &EventKind::Ir).unwrap();
+
+    assert_eq!(stacks.len(), expected_stacks.len());
+    for (stack, expected_stack) in stacks.iter().zip(expected_stacks.iter()) {
+        assert_eq!(
+            stack.frames.first().map(String::as_str),
+            Some("benchmark-tests-exit")
+        );
+        assert_eq!(&stack.frames[1..], expected_stack.frames.as_slice());
+        // The root frame and the frame which marks the truncation
+        assert!(stack.frames.len() <= max_depth + 2);
+        assert_eq!(stack.costs, expected_stack.costs);
+    }
+}
+
+#[rstest]
+#[case::middle(Ellipsis::Middle)]
+#[case::end(Ellipsis::End)]
+fn test_flamegraph_to_stacks_with_max_frame_name_length(#[case] ellipsis: Ellipsis) {
+    let output = Fixtures::get_tool_output_path(
+        "callgrind.out",
+        ValgrindTool::Callgrind,
+        ToolOutputPathKind::Out,
+        "no_entry_point",
+    );
+    let map = FlamegraphParser::new(None, get_project_root())
+        .parse(&output)
+        .unwrap();
+
+    let expected = FlamegraphBuilder::new("heading")
+        .coalesce_stacks(true)
+        .build()
+        .to_stacks(&map, &EventKind::Ir)
+        .unwrap();
+    let stacks = FlamegraphBuilder::new("heading")
+        .coalesce_stacks(true)
+        .max_frame_name_length(20)
+        .frame_name_ellipsis(ellipsis)
+        .build()
+        .to_stacks(&map, &EventKind::Ir)
+        .unwrap();
+
+    assert!(expected
+        .iter()
+        .any(|stack| stack.frames.iter().any(|frame| frame.chars().count() > 20)));
+    assert!(stacks
+        .iter()
+        .all(|stack| stack.frames.iter().all(|frame| frame.chars().count() <= 20)));
+    // The truncation is display-only and doesn't merge the stacks
+    assert_eq!(stacks.len(), expected.len());
+    for (stack, expected) in stacks.iter().zip(expected.iter()) {
+        assert_eq!(stack.frames.len(), expected.frames.len());
+        assert_eq!(stack.costs, expected.costs);
+    }
+}
+
+#[rstest]
+#[case::ir(EventKind::Ir, [10, 60])]
+#[case::ge(EventKind::Ge, [0, 6])]
+fn test_flamegraph_parser_when_collect_bus(#[case] event_kind: EventKind, #[case] costs: [u64; 2]) {
+    let main = "src/main.rs:main [/some/project/target/release/deps/my-bin-3d69ca9f0e146ded]";
+    let atomic_add = "src/main.rs:my_bin::atomic_add \
+                      [/some/project/target/release/deps/my-bin-3d69ca9f0e146ded]";
+    let expected = vec![
+        format!("{main} {}", costs[0]),
+        format!("{main};{atomic_add} {}", costs[1]),
+    ];
+
+    let output = Fixtures::get_tool_output_path(
+        "callgrind.out",
+        ValgrindTool::Callgrind,
+        ToolOutputPathKind::Out,
+        "with_collect_bus",
+    );
+    let map = FlamegraphParser::new(None, get_project_root())
+        .parse(&output)
+        .unwrap();
+
+    let stacks = map
+        .to_stacks(&event_kind, false, ObjectMarker::Bracketed)
+        .unwrap()
+        .to_stack_format(&event_kind)
+        .unwrap();
+
+    assert_eq!(stacks, expected);
+}
+
+#[rstest]
+#[case::main(
+    None,
+    &[
+        "main'__libc_start_call_main 30",
+        "main'__libc_start_call_main;foo'main 10",
+        "main'__libc_start_call_main;foo'main;bar'main 5",
+        "main'__libc_start_call_main;foo'main;bar'main;foo'bar 15",
+    ]
+)]
+#[case::sentinel(Some(Sentinel::new("bar")), &["bar'main 5", "bar'main;foo'bar 15"])]
+fn test_flamegraph_parser_when_separate_callers(
+    #[case] sentinel: Option<Sentinel>,
+    #[case] expected: &[&str],
+) {
+    let output = Fixtures::get_tool_output_path(
+        "callgrind.out",
+        ValgrindTool::Callgrind,
+        ToolOutputPathKind::Out,
+        "with_separate_callers",
+    );
+    let map = FlamegraphParser::new(sentinel.as_ref(), get_project_root())
+        .parse(&output)
+        .unwrap();
+
+    let stacks = map
+        .to_stacks(&EventKind::Ir, false, ObjectMarker::Bracketed)
+        .unwrap()
+        .to_stack_format(&EventKind::Ir)
+        .unwrap();
+
+    assert_eq!(stacks, expected);
+}
+
+#[rstest]
+#[case::keep_harness(false, vec![
+    "(below main) 0",
+    "(below main);std::rt::lang_start 0",
+    "(below main);std::rt::lang_start;main 0",
+    "(below main);std::rt::lang_start;main;foo 10",
+])]
+#[case::trim_harness(true, vec!["main 0", "main;foo 10"])]
+fn test_flamegraph_map_trim_harness_keeps_callees_with_equal_cost(
+    #[case] trim_harness: bool,
+    #[case] expected: Vec<&str>,
+) {
+    let output = Fixtures::get_tool_output_path(
+        "callgrind.out",
+        ValgrindTool::Callgrind,
+        ToolOutputPathKind::Out,
+        "harness_with_equal_cost",
+    );
+
+    let stacks = FlamegraphMap::try_from(&CallgrindOutput::new(output))
+        .unwrap()
+        .to_stacks(&EventKind::Ir, trim_harness, ObjectMarker::Bracketed)
+        .unwrap()
+        .to_stack_format(&EventKind::Ir)
+        .unwrap();
+
+    assert_eq!(stacks, expected);
+}
@@ -1,4 +1,4 @@
-use iai_callgrind_runner::runner::callgrind::hashmap_parser::{CallgrindMap, HashMapParser};
+use iai_callgrind_runner::runner::callgrind::hashmap_parser::{CallgrindMap, HashMapParser, Id};
 use iai_callgrind_runner::runner::tool::{Parser, ToolOutputPathKind, ValgrindTool};
 use pretty_assertions::assert_eq;
 use rstest::rstest;
@@ -67,3 +67,27 @@ fn test_when_no_records(#[case] name: &str) {
 
     assert_eq!(actual_map, expected_map);
 }
+
+#[rstest]
+#[case::plain("foo", "foo")]
+#[case::one_caller("foo'main", "foo")]
+#[case::two_callers("foo'bar'main", "foo")]
+#[case::path("my_bin::foo'my_bin::bar", "my_bin::foo")]
+#[case::lifetime("<&'a str as my_bin::Foo>::foo", "<&'a str as my_bin::Foo>::foo")]
+#[case::higher_ranked_lifetime(
+    "<my_bin::Bar as for<'a> my_bin::Foo<'a>>::foo'main",
+    "<my_bin::Bar as for<'a> my_bin::Foo<'a>>::foo"
+)]
+#[case::closure(
+    "my_bin::foo::{{closure}}'my_bin::foo'main",
+    "my_bin::foo::{{closure}}"
+)]
+#[case::generic("my_bin::foo<my_bin::Bar>'main", "my_bin::foo<my_bin::Bar>")]
+fn test_id_name(#[case] func: &str, #[case] expected: &str) {
+    let id = Id {
+        obj: None,
+        file: None,
+        func: func.to_owned(),
+    };
+    assert_eq!(id.name(), expected);
+}
@@ -0,0 +1,135 @@
+use iai_callgrind_runner::runner::callgrind::metadata_parser::{
+    MetadataParser, OutputMetadata, RunMetadata,
+};
+use iai_callgrind_runner::runner::tool::{Parser, ToolOutputPathKind, ValgrindTool};
+use pretty_assertions::assert_eq;
+
+use crate::common::{assert_parse_error, Fixtures};
+
+#[test]
+fn test_metadata_parser_callgrind() {
+    let expected = OutputMetadata {
+        creator: Some("callgrind-3.21.0".to_owned()),
+        caches: vec![
+            "I1 cache: 32768 B, 64 B, 8-way associative".to_owned(),
+            "D1 cache: 32768 B, 64 B, 8-way associative".to_owned(),
+            "LL cache: 8388608 B, 64 B, 16-way associative".to_owned(),
+        ],
+        events: ["Ir", "Dr", "Dw", "I1mr", "D1mr", "D1mw", "ILmr", "DLmr", "DLmw"]
+            .map(ToOwned::to_owned)
+            .to_vec(),
+        cmd: Some("target/release/benchmark-tests-exit 0".to_owned()),
+        pid: Some(3_289_652),
+        run: None,
+    };
+
+    let callgrind_output = Fixtures::get_tool_output_path(
+        "callgrind.out",
+        ValgrindTool::Callgrind,
+        ToolOutputPathKind::Out,
+        "no_entry_point",
+    );
+
+    assert_eq!(MetadataParser.parse(&callgrind_output).unwrap(), expected);
+}
+
+#[test]
+fn test_metadata_parser_cachegrind() {
+    let expected = OutputMetadata {
+        creator: None,
+        caches: vec![
+            "I1 cache: 32768 B, 64 B, 8-way associative".to_owned(),
+            "D1 cache: 32768 B, 64 B, 8-way associative".to_owned(),
+            "LL cache: 8388608 B, 64 B, 16-way associative".to_owned(),
+        ],
+        events: ["Ir", "I1mr", "ILmr", "Dr", "D1mr", "DLmr", "Dw", "D1mw", "DLmw"]
+            .map(ToOwned::to_owned)
+            .to_vec(),
+        cmd: Some(
+            "/home/some/target/release/deps/bench-1234 --iai-run 0 bench::bench_fibonacci"
+                .to_owned(),
+        ),
+        pid: None,
+        run: None,
+    };
+
+    let cachegrind_output = Fixtures::get_tool_output_path(
+        "cachegrind.out",
+        ValgrindTool::Cachegrind,
+        ToolOutputPathKind::Out,
+        "with_summary",
+    );
+
+    assert_eq!(MetadataParser.parse(&cachegrind_output).unwrap(), expected);
+}
+
+#[test]
+fn test_metadata_parser_with_run_metadata() {
+    let expected = RunMetadata {
+        env_clear: true,
+        envs: vec![
+            ("FOO".to_owned(), "BAR".to_owned()),
+            ("RUST_LOG".to_owned(), "debug".to_owned()),
+        ],
+        // The run metadata of earlier versions has no hashes
+        binary_hash: None,
+        config_hash: None,
+        arch: None,
+        cpu_affinity: None,
+        executable: None,
+    };
+
+    let callgrind_output = Fixtures::get_tool_output_path(
+        "callgrind.out",
+        ValgrindTool::Callgrind,
+        ToolOutputPathKind::Out,
+        "with_run_metadata",
+    );
+
+    assert_eq!(
+        MetadataParser.parse(&callgrind_output).unwrap().run,
+        Some(expected)
+    );
+}
+
+#[test]
+fn test_metadata_parser_with_hashes() {
+    let expected = RunMetadata {
+        env_clear: true,
+        envs: vec![],
+        binary_hash: Some("af63dc4c8601ec8c".to_owned()),
+        config_hash: Some("85944171f73967e8".to_owned()),
+        arch: None,
+        cpu_affinity: None,
+        executable: None,
+    };
+
+    let callgrind_output = Fixtures::get_tool_output_path(
+        "callgrind.out",
+        ValgrindTool::Callgrind,
+        ToolOutputPathKind::Out,
+        "with_hashes",
+    );
+
+    assert_eq!(
+        MetadataParser.parse(&callgrind_output).unwrap().run,
+        Some(expected)
+    );
+}
+
+#[test]
+fn test_metadata_parser_when_no_events_then_error() {
+    let cachegrind_output = Fixtures::get_tool_output_path(
+        "cachegrind.out",
+        ValgrindTool::Cachegrind,
+        ToolOutputPathKind::Out,
+        "no_events",
+    );
+
+    let result = MetadataParser.parse(&cachegrind_output);
+    assert_parse_error(
+        &cachegrind_output.to_path(),
+        result,
+        "Header field 'events' must be present",
+    );
+}
@@ -0,0 +1,99 @@
+use iai_callgrind_runner::api::{EventKind, ObjectMarker};
+use iai_callgrind_runner::runner::callgrind::call_graph::CallGraphParser;
+use iai_callgrind_runner::runner::callgrind::flamegraph_parser::FlamegraphParser;
+use iai_callgrind_runner::runner::callgrind::output_parser::CallgrindOutput;
+use iai_callgrind_runner::runner::callgrind::parser::Sentinel;
+use iai_callgrind_runner::runner::callgrind::summary_parser::SummaryParser;
+use iai_callgrind_runner::runner::tool::{Parser, ToolOutputPathKind, ValgrindTool};
+use pretty_assertions::assert_eq;
+use rstest::rstest;
+
+use crate::common::{get_project_root, Fixtures};
+
+#[rstest]
+#[case::no_entry_point("no_entry_point", None)]
+#[case::when_entry_point("when_entry_point", Some(Sentinel::new("benchmark_tests_exit::main")))]
+#[case::call_graph("call_graph", None)]
+#[case::with_collect_bus("with_collect_bus", None)]
+fn test_callgrind_output_parse_is_equal_to_single_parsers(
+    #[case] name: &str,
+    #[case] sentinel: Option<Sentinel>,
+) {
+    let output = Fixtures::get_tool_output_path(
+        "callgrind.out",
+        ValgrindTool::Callgrind,
+        ToolOutputPathKind::Out,
+        name,
+    );
+
+    let parsed = CallgrindOutput::new(output.clone())
+        .sentinel(sentinel.as_ref())
+        .project_root(get_project_root())
+        .parse()
+        .unwrap();
+
+    assert_eq!(
+        parsed.call_graph,
+        CallGraphParser {
+            project_root: get_project_root()
+        }
+        .parse(&output)
+        .unwrap()
+    );
+    assert_eq!(
+        parsed.map,
+        FlamegraphParser::new(sentinel.as_ref(), get_project_root())
+            .parse(&output)
+            .unwrap()
+    );
+    assert_eq!(parsed.totals, Some(SummaryParser.parse(&output).unwrap()));
+}
+
+#[test]
+fn test_callgrind_output_parse_to_stacks() {
+    let output = Fixtures::get_tool_output_path(
+        "callgrind.out",
+        ValgrindTool::Callgrind,
+        ToolOutputPathKind::Out,
+        "when_entry_point",
+    );
+    let sentinel = Sentinel::new("benchmark_tests_exit::main");
+    let expected_stacks =
+        Fixtures::load_stacks("callgrind.out/callgrind.when_entry_point.exp_stacks");
+
+    let stacks = CallgrindOutput::new(output)
+        .sentinel(Some(&sentinel))
+        .project_root(get_project_root())
+        .parse()
+        .unwrap()
+        .to_stacks(&EventKind::Ir, false, ObjectMarker::Bracketed)
+        .unwrap()
+        .to_stack_format(&EventKind::Ir)
+        .unwrap();
+
+    assert_eq!(stacks, expected_stacks);
+}
+
+// The flamegraphs are created with `CallgrindOutput::parse`, so a missing summary or totals line
+// must not prevent the creation of the stacks
+#[test]
+fn test_callgrind_output_parse_when_no_summary_and_totals() {
+    let output = Fixtures::get_tool_output_path(
+        "callgrind.out",
+        ValgrindTool::Callgrind,
+        ToolOutputPathKind::Out,
+        "valid.minimal_main",
+    );
+
+    let parsed = CallgrindOutput::new(output).parse().unwrap();
+
+    assert_eq!(parsed.totals, None);
+    assert_eq!(
+        parsed
+            .to_stacks(&EventKind::Ir, false, ObjectMarker::Bracketed)
+            .unwrap()
+            .to_stack_format(&EventKind::Ir)
+            .unwrap(),
+        vec!["main 1".to_owned()]
+    );
+}
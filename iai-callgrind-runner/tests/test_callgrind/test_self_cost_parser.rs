@@ -0,0 +1,73 @@
+use iai_callgrind_runner::api::EventKind;
+use iai_callgrind_runner::runner::callgrind::self_cost_parser::SelfCostParser;
+use iai_callgrind_runner::runner::tool::{Parser, ToolOutputPathKind, ValgrindTool};
+use pretty_assertions::assert_eq;
+use rstest::rstest;
+
+use crate::common::Fixtures;
+
+// The self costs of all functions have to add up to the `totals:` line of the output file. The
+// `summary:` line of this fixture differs slightly from the `totals:` line as reported by
+// callgrind.
+#[rstest]
+#[case::ir(EventKind::Ir, 325_259)]
+#[case::dr(EventKind::Dr, 78_145)]
+#[case::dw(EventKind::Dw, 35_789)]
+#[case::d1mw(EventKind::D1mw, 850)]
+fn test_self_cost_parser_sum_equals_totals(#[case] event_kind: EventKind, #[case] expected: u64) {
+    let callgrind_output = Fixtures::get_tool_output_path(
+        "callgrind.out",
+        ValgrindTool::Callgrind,
+        ToolOutputPathKind::Out,
+        "no_entry_point",
+    );
+
+    let self_costs = SelfCostParser.parse(&callgrind_output).unwrap();
+
+    assert_eq!(
+        self_costs
+            .0
+            .values()
+            .map(|costs| costs.cost_by_kind(&event_kind).unwrap())
+            .sum::<u64>(),
+        expected
+    );
+}
+
+#[test]
+fn test_self_cost_parser_when_no_records() {
+    let callgrind_output = Fixtures::get_tool_output_path(
+        "callgrind.out",
+        ValgrindTool::Callgrind,
+        ToolOutputPathKind::Out,
+        "no_records.with_summary_and_totals",
+    );
+
+    assert!(
+        SelfCostParser
+            .parse(&callgrind_output)
+            .unwrap()
+            .0
+            .is_empty()
+    );
+}
+
+#[test]
+fn test_self_cost_parser_when_too_many_costs_then_error() {
+    let callgrind_output = Fixtures::get_tool_output_path(
+        "callgrind.out",
+        ValgrindTool::Callgrind,
+        ToolOutputPathKind::Out,
+        "invalid.too_many_costs",
+    );
+
+    let error = SelfCostParser.parse(&callgrind_output).unwrap_err();
+    assert_eq!(
+        error.to_string(),
+        format!(
+            "Error parsing file '{}' at line 24: Expected at most 2 costs as in the 'events' line \
+             but found 4",
+            callgrind_output.to_path().display()
+        )
+    );
+}
@@ -63,3 +63,26 @@ fn test_sentinel_parser_when_not_found_then_error() {
         ),
     )
 }
+
+#[test]
+fn test_sentinel_parser_when_too_many_costs_then_error() {
+    let callgrind_output = Fixtures::get_tool_output_path(
+        "callgrind.out",
+        ValgrindTool::Callgrind,
+        ToolOutputPathKind::Out,
+        "invalid.too_many_costs",
+    );
+    let sentinel = Sentinel::new("my_bin::atomic_add");
+
+    let error = SentinelParser::new(&sentinel)
+        .parse(&callgrind_output)
+        .unwrap_err();
+    assert_eq!(
+        error.to_string(),
+        format!(
+            "Error parsing file '{}' at line 24: Expected at most 2 costs as in the 'events' line \
+             but found 4",
+            callgrind_output.to_path().display()
+        )
+    );
+}
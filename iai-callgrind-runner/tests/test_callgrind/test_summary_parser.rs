@@ -36,6 +36,44 @@ fn test_sentinel_parser(#[case] fixture: &str, #[case] costs: [u64; 9]) {
     assert_eq!(actual_costs, expected_costs);
 }
 
+#[test]
+fn test_summary_parser_then_make_summary_data_rw() {
+    let callgrind_output = Fixtures::get_tool_output_path(
+        "callgrind.out",
+        ValgrindTool::Callgrind,
+        ToolOutputPathKind::Out,
+        "no_entry_point",
+    );
+
+    let mut costs = SummaryParser.parse(&callgrind_output).unwrap();
+    costs.make_summary().unwrap();
+
+    assert_eq!(costs.cost_by_kind(&EventKind::Dr), Some(78145));
+    assert_eq!(costs.cost_by_kind(&EventKind::Dw), Some(35789));
+    assert_eq!(costs.cost_by_kind(&EventKind::DataRW), Some(78145 + 35789));
+    assert_eq!(
+        costs.cost_by_kind(&EventKind::TotalRW),
+        Some(325261 + 78145 + 35789)
+    );
+}
+
+#[test]
+fn test_make_summary_when_data_writes_missing_then_error() {
+    let mut costs = Costs::with_event_kinds([
+        (EventKind::Ir, 1),
+        (EventKind::Dr, 2),
+        (EventKind::I1mr, 0),
+        (EventKind::D1mr, 0),
+        (EventKind::D1mw, 0),
+        (EventKind::ILmr, 0),
+        (EventKind::DLmr, 0),
+        (EventKind::DLmw, 0),
+    ]);
+
+    let error = costs.make_summary().unwrap_err();
+    assert_eq!(error.to_string(), "Missing event type 'Dw'");
+}
+
 #[test]
 fn test_summary_parser_when_not_found_then_error() {
     let callgrind_output = Fixtures::get_tool_output_path(
@@ -52,3 +90,40 @@ fn test_summary_parser_when_not_found_then_error() {
         "No summary or totals line found",
     )
 }
+
+#[test]
+fn test_summary_parser_when_collect_bus() {
+    let callgrind_output = Fixtures::get_tool_output_path(
+        "callgrind.out",
+        ValgrindTool::Callgrind,
+        ToolOutputPathKind::Out,
+        "with_collect_bus",
+    );
+
+    let costs = SummaryParser.parse(&callgrind_output).unwrap();
+
+    assert_eq!(
+        costs,
+        Costs::with_event_kinds([(EventKind::Ir, 70), (EventKind::Ge, 6)])
+    );
+}
+
+#[test]
+fn test_summary_parser_when_too_many_costs_then_error() {
+    let callgrind_output = Fixtures::get_tool_output_path(
+        "callgrind.out",
+        ValgrindTool::Callgrind,
+        ToolOutputPathKind::Out,
+        "invalid.summary_too_many_costs",
+    );
+
+    let error = SummaryParser.parse(&callgrind_output).unwrap_err();
+    assert_eq!(
+        error.to_string(),
+        format!(
+            "Error parsing file '{}' at line 26: Expected at most 2 costs as in the 'events' line \
+             but found 3",
+            callgrind_output.to_path().display()
+        )
+    );
+}
@@ -1,9 +1,26 @@
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
 use iai_callgrind_runner::runner::costs::Costs;
 use iai_callgrind_runner::runner::dhat::logfile_parser::DhatLogfileParser;
 use iai_callgrind_runner::runner::summary::{CostsSummary, ToolRunSummary};
-use iai_callgrind_runner::runner::tool::logfile_parser::{LogfileParser, LogfileSummary};
+use iai_callgrind_runner::runner::tool::logfile_parser::{
+    LogfileParser, LogfileSummary, ToolLogfileParser,
+};
+use indexmap::IndexMap;
+use pretty_assertions::assert_eq;
+use rstest::rstest;
+
+/// Return the path of the fixture `name`
+///
+/// This file is also compiled as a test crate of its own, so it can't use `crate::common`.
+fn fixture_path<T>(name: T) -> PathBuf
+where
+    T: AsRef<Path>,
+{
+    Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("tests/fixtures")
+        .join(name)
+}
 
 fn dummy_cost(cost: u64) -> Costs<String> {
     Costs::with_event_kinds([("cost".to_string(), cost)])
@@ -16,6 +33,7 @@ fn dummy_summary(cmd: &str, pid: i32, cost: u64) -> LogfileSummary {
         fields: vec![],
         details: vec![],
         error_summary: None,
+        error_kinds: Default::default(),
         costs: Some(dummy_cost(cost)),
         log_path: PathBuf::new(),
     }
@@ -40,6 +58,7 @@ fn dummy_tool_run_summary(
         summary: Default::default(),
         details: None,
         error_summary: None,
+        error_kinds: Default::default(),
         costs_summary,
         log_path: Default::default(),
     }
@@ -75,3 +94,91 @@ fn test_dhat_merge_logfile_summaries() {
         vec![s1bo(), s2n()]
     );
 }
+
+#[rstest]
+#[case::heap(
+    "dhat.heap.log",
+    &[
+        ("Total bytes", 1864),
+        ("Total blocks", 6),
+        ("At t-gmax bytes", 1024),
+        ("At t-gmax blocks", 1),
+        ("At t-end bytes", 0),
+        ("At t-end blocks", 0),
+        ("Reads bytes", 1606),
+        ("Writes bytes", 1007),
+    ]
+)]
+#[case::ad_hoc("dhat.ad_hoc.log", &[("Total units", 12345), ("Total events", 1010)])]
+#[case::copy("dhat.copy.log", &[("Total bytes", 4096), ("Total blocks", 4)])]
+fn test_dhat_parse_single(#[case] fixture: &str, #[case] expected: &[(&str, u64)]) {
+    let dhat = DhatLogfileParser {
+        root_dir: PathBuf::from("/home/user/project"),
+    };
+
+    let summary = dhat
+        .parse_single(fixture_path(Path::new("dhat.log").join(fixture)))
+        .unwrap();
+
+    assert_eq!(summary.pid, 1234);
+    assert_eq!(summary.parent_pid, Some(1233));
+    assert_eq!(
+        summary.command,
+        PathBuf::from("target/release/deps/bench-0123456789abcdef --iai-run")
+    );
+    assert_eq!(
+        summary.costs,
+        Some(Costs::with_event_kinds(
+            expected
+                .iter()
+                .map(|(key, cost)| ((*key).to_owned(), *cost))
+        ))
+    );
+}
+
+#[rstest]
+#[case::memcheck(
+    "memcheck.log",
+    (5, 4),
+    &[
+        ("Invalid read of size 4", 2),
+        ("Conditional jump or move depends on uninitialised value(s)", 1),
+        ("Leak definitely lost", 1),
+    ]
+)]
+#[case::memcheck_no_errors("memcheck.no_errors.log", (0, 0), &[])]
+#[case::helgrind(
+    "helgrind.log",
+    (2, 2),
+    &[
+        ("Possible data race during read of size 4", 1),
+        ("Possible data race during write of size 4", 1),
+    ]
+)]
+#[case::drd("drd.log", (2, 2), &[("Conflicting load", 1), ("Conflicting store", 1)])]
+fn test_tool_parse_single_error_kinds(
+    #[case] fixture: &str,
+    #[case] expected_errors: (u64, u64),
+    #[case] expected_kinds: &[(&str, u64)],
+) {
+    let parser = ToolLogfileParser {
+        root_dir: PathBuf::from("/home/user/project"),
+    };
+
+    let summary = parser
+        .parse_single(fixture_path(Path::new("tools.log").join(fixture)))
+        .unwrap();
+
+    let error_summary = summary.error_summary.unwrap();
+    assert_eq!(
+        (error_summary.errors, error_summary.contexts),
+        expected_errors
+    );
+    assert_eq!(
+        summary.error_kinds,
+        expected_kinds
+            .iter()
+            .map(|(kind, count)| ((*kind).to_owned(), *count))
+            .collect::<IndexMap<_, _>>()
+    );
+}
@@ -1,6 +1,8 @@
 #[cfg(test)]
 mod common;
 #[cfg(test)]
+mod test_cachegrind;
+#[cfg(test)]
 mod test_callgrind;
 
 #[cfg(test)]
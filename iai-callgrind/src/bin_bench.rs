@@ -2,7 +2,7 @@ use std::ffi::OsString;
 use std::fmt::Display;
 use std::path::PathBuf;
 
-use crate::internal;
+use crate::{internal, ValgrindTool};
 ///
 /// The arguments needed for [`Run`] which are passed to the benchmarked binary
 #[derive(Debug, Clone)]
@@ -467,6 +467,74 @@ impl BinaryBenchmarkConfig {
         self
     }
 
+    /// If true, run the benchmarks with cachegrind instead of callgrind (Default: false)
+    ///
+    /// Cachegrind is much faster than callgrind but doesn't collect a call graph. Only the
+    /// aggregate events of the whole run are reported and a configured `entry_point` is ignored.
+    /// Flamegraphs are not available and configuring them is an error unless the
+    /// [`crate::FlamegraphKind`] is `None`. The `setup`, `teardown`,
+    /// `before` and `after` functions are always run with callgrind.
+    ///
+    /// Cachegrind understands only some of the callgrind arguments (`--I1`, `--D1`, `--LL`,
+    /// `--cache-sim` and `--branch-sim`). All other arguments apart from those managed by
+    /// `iai-callgrind` (like `--toggle-collect`) are passed to cachegrind as they are.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use iai_callgrind::{binary_benchmark_group, Arg, BinaryBenchmarkGroup, Run};
+    /// # binary_benchmark_group!(
+    /// #    name = my_group;
+    /// #    benchmark = |"my-exe", group: &mut BinaryBenchmarkGroup| {});
+    /// use iai_callgrind::{main, BinaryBenchmarkConfig};
+    ///
+    /// # fn main() {
+    /// main!(
+    ///     config = BinaryBenchmarkConfig::default().cachegrind(true);
+    ///     binary_benchmark_groups = my_group
+    /// );
+    /// # }
+    /// ```
+    pub fn cachegrind(&mut self, value: bool) -> &mut Self {
+        self.0.cachegrind = Some(value);
+        self
+    }
+
+    /// Run the benchmarks with this [`ValgrindTool`] instead of callgrind (Default: Callgrind)
+    ///
+    /// This setting takes precedence over [`BinaryBenchmarkConfig::cachegrind`] unless it is
+    /// inherited from the group or the `main!` macro and the [`crate::Run`] itself switches on
+    /// cachegrind. With a tool other than [`ValgrindTool::Callgrind`] and
+    /// [`ValgrindTool::Cachegrind`], callgrind is not run at all and the benchmarks run only with
+    /// this tool and the additional [`BinaryBenchmarkConfig::tool`]s. Flamegraphs, regions and
+    /// regression checks are not available for such benchmarks and configuring them for the
+    /// [`crate::Run`] itself is an error. An inherited configuration is skipped with a warning.
+    /// Flamegraphs and regions are not available with cachegrind either. The `setup`, `teardown`,
+    /// `before` and `after` functions are always run with callgrind.
+    ///
+    /// See also [`crate::LibraryBenchmarkConfig::default_tool`]
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use iai_callgrind::{binary_benchmark_group, Arg, BinaryBenchmarkGroup, Run};
+    /// # binary_benchmark_group!(
+    /// #    name = my_group;
+    /// #    benchmark = |"my-exe", group: &mut BinaryBenchmarkGroup| {});
+    /// use iai_callgrind::{main, BinaryBenchmarkConfig, ValgrindTool};
+    ///
+    /// # fn main() {
+    /// main!(
+    ///     config = BinaryBenchmarkConfig::default().default_tool(ValgrindTool::DHAT);
+    ///     binary_benchmark_groups = my_group
+    /// );
+    /// # }
+    /// ```
+    pub fn default_tool(&mut self, tool: ValgrindTool) -> &mut Self {
+        self.0.default_tool = Some(tool);
+        self
+    }
+
     /// Set the directory of the benchmarked binary (Default: Unchanged)
     ///
     /// Unchanged means, in the case of running with the sandbox enabled, the root of the sandbox.
@@ -570,6 +638,50 @@ impl BinaryBenchmarkConfig {
         self
     }
 
+    /// Add a region of the binary benchmark run of which the costs are reported separately
+    ///
+    /// In addition to the benchmark run measuring the whole binary (or the
+    /// [`BinaryBenchmarkConfig::entry_point`]), the binary is run once more for each region. In
+    /// these runs, only the events within the function of the region (and all functions it calls)
+    /// are collected. The `region` is a function name like with the `entry_point` and wildcards
+    /// are allowed (see the `--toggle-collect` option in the [callgrind
+    /// docs](https://valgrind.org/docs/manual/cl-manual.html#cl-manual.options.collection)). The
+    /// costs of each region are shown beneath the costs of the whole run, are compared with the
+    /// costs of the region in the old run or baseline and are checked for regressions like the
+    /// costs of the whole run. The callgrind output files of a region have the region in their
+    /// file name, for example `callgrind.my_exe.empty_foo.out.region.my_exe__parse`.
+    ///
+    /// Each run of a region is prepared with the `setup` and `teardown` functions of the group like
+    /// the benchmark run. Regions are only available for callgrind and not for cachegrind. Regions
+    /// of all config levels are accumulated and duplicates are removed. Different regions with
+    /// the same name in the output files, like `a::b` and `a__b`, are an error.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use iai_callgrind::{binary_benchmark_group, Arg, BinaryBenchmarkGroup, Run};
+    /// # binary_benchmark_group!(
+    /// #    name = my_group;
+    /// #    benchmark = |"my-exe", group: &mut BinaryBenchmarkGroup| {});
+    /// use iai_callgrind::{main, BinaryBenchmarkConfig};
+    ///
+    /// # fn main() {
+    /// main!(
+    ///     config = BinaryBenchmarkConfig::default()
+    ///         .region("my_exe::parse")
+    ///         .region("my_exe::evaluate");
+    ///     binary_benchmark_groups = my_group
+    /// );
+    /// # }
+    /// ```
+    pub fn region<T>(&mut self, region: T) -> &mut Self
+    where
+        T: Into<String>,
+    {
+        self.0.regions.push(region.into());
+        self
+    }
+
     /// Set the expected exit status [`ExitWith`] of a benchmarked binary
     ///
     /// Per default, the benchmarked binary is expected to succeed which is the equivalent of
@@ -619,6 +731,107 @@ impl BinaryBenchmarkConfig {
         self
     }
 
+    /// Assert that the stdout of a benchmarked binary contains the `string`
+    ///
+    /// The output assertions are checked after each valgrind run of the benchmarked binary. If an
+    /// assertion fails, the benchmark fails and the captured stdout and stderr of the binary are
+    /// printed. Together with [`BinaryBenchmarkConfig::exit_with`], this makes a binary benchmark
+    /// double as a smoke test. This option can be specified multiple times and all assertions have
+    /// to be satisfied.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use iai_callgrind::{binary_benchmark_group, Arg, BinaryBenchmarkGroup, Run};
+    /// # binary_benchmark_group!(
+    /// #    name = my_group;
+    /// #    benchmark = |"my-exe", group: &mut BinaryBenchmarkGroup| {});
+    /// use iai_callgrind::{main, BinaryBenchmarkConfig};
+    ///
+    /// # fn main() {
+    /// main!(
+    ///     config = BinaryBenchmarkConfig::default().assert_stdout_contains("Done");
+    ///     binary_benchmark_groups = my_group
+    /// );
+    /// # }
+    /// ```
+    pub fn assert_stdout_contains<T>(&mut self, string: T) -> &mut Self
+    where
+        T: Into<String>,
+    {
+        self.0
+            .output_assertions
+            .push(internal::InternalOutputAssertion::StdoutContains(
+                string.into(),
+            ));
+        self
+    }
+
+    /// Assert that the stdout of a benchmarked binary matches the `regex`
+    ///
+    /// The `regex` uses the syntax of the [regex](https://docs.rs/regex/latest/regex/) crate and
+    /// matches anywhere in the output unless it is anchored. An invalid regex is an error. See
+    /// also [`BinaryBenchmarkConfig::assert_stdout_contains`].
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use iai_callgrind::{binary_benchmark_group, Arg, BinaryBenchmarkGroup, Run};
+    /// # binary_benchmark_group!(
+    /// #    name = my_group;
+    /// #    benchmark = |"my-exe", group: &mut BinaryBenchmarkGroup| {});
+    /// use iai_callgrind::{main, BinaryBenchmarkConfig};
+    ///
+    /// # fn main() {
+    /// main!(
+    ///     config = BinaryBenchmarkConfig::default()
+    ///         .assert_stdout_matches(r"Processed \d+ files");
+    ///     binary_benchmark_groups = my_group
+    /// );
+    /// # }
+    /// ```
+    pub fn assert_stdout_matches<T>(&mut self, regex: T) -> &mut Self
+    where
+        T: Into<String>,
+    {
+        self.0
+            .output_assertions
+            .push(internal::InternalOutputAssertion::StdoutMatches(
+                regex.into(),
+            ));
+        self
+    }
+
+    /// Assert that the stderr of a benchmarked binary contains the `string`
+    ///
+    /// See also [`BinaryBenchmarkConfig::assert_stdout_contains`].
+    pub fn assert_stderr_contains<T>(&mut self, string: T) -> &mut Self
+    where
+        T: Into<String>,
+    {
+        self.0
+            .output_assertions
+            .push(internal::InternalOutputAssertion::StderrContains(
+                string.into(),
+            ));
+        self
+    }
+
+    /// Assert that the stderr of a benchmarked binary matches the `regex`
+    ///
+    /// See also [`BinaryBenchmarkConfig::assert_stdout_matches`].
+    pub fn assert_stderr_matches<T>(&mut self, regex: T) -> &mut Self
+    where
+        T: Into<String>,
+    {
+        self.0
+            .output_assertions
+            .push(internal::InternalOutputAssertion::StderrMatches(
+                regex.into(),
+            ));
+        self
+    }
+
     /// Option to produce flamegraphs from callgrind output using the [`crate::FlamegraphConfig`]
     ///
     /// # Examples
@@ -1387,6 +1600,70 @@ impl Run {
         self
     }
 
+    /// If true, run this benchmark with cachegrind instead of callgrind (Default: false)
+    ///
+    /// See also [`BinaryBenchmarkConfig::cachegrind`]
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use iai_callgrind::main;
+    /// use iai_callgrind::{binary_benchmark_group, Arg, BinaryBenchmarkGroup, Run};
+    ///
+    /// binary_benchmark_group!(
+    ///     name = my_group;
+    ///     benchmark = |"my-exe", group: &mut BinaryBenchmarkGroup| {
+    ///         group.bench(
+    ///             Run::with_arg(Arg::empty("empty foo"))
+    ///                 .cachegrind(true)
+    ///         );
+    ///     }
+    /// );
+    /// # fn main() {
+    /// # main!(binary_benchmark_groups = my_group);
+    /// # }
+    /// ```
+    pub fn cachegrind(&mut self, value: bool) -> &mut Self {
+        self.0.config.cachegrind = Some(value);
+        self
+    }
+
+    /// Run this benchmark with this [`ValgrindTool`] instead of callgrind (Default: Callgrind)
+    ///
+    /// See also [`BinaryBenchmarkConfig::default_tool`]
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use iai_callgrind::main;
+    /// use iai_callgrind::{
+    ///     binary_benchmark_group, Arg, BinaryBenchmarkGroup, Run, ValgrindTool,
+    /// };
+    ///
+    /// binary_benchmark_group!(
+    ///     name = my_group;
+    ///     benchmark = |"my-exe", group: &mut BinaryBenchmarkGroup| {
+    ///         group
+    ///             .bench(Run::with_arg(Arg::empty("callgrind")))
+    ///             .bench(
+    ///                 Run::with_arg(Arg::empty("cachegrind"))
+    ///                     .default_tool(ValgrindTool::Cachegrind)
+    ///             )
+    ///             .bench(
+    ///                 Run::with_arg(Arg::empty("dhat"))
+    ///                     .default_tool(ValgrindTool::DHAT)
+    ///             );
+    ///     }
+    /// );
+    /// # fn main() {
+    /// # main!(binary_benchmark_groups = my_group);
+    /// # }
+    /// ```
+    pub fn default_tool(&mut self, tool: ValgrindTool) -> &mut Self {
+        self.0.config.default_tool = Some(tool);
+        self
+    }
+
     /// Set the directory of the benchmarked binary (Default: Unchanged)
     ///
     /// See also [`BinaryBenchmarkConfig::current_dir`]
@@ -1474,6 +1751,38 @@ impl Run {
         self
     }
 
+    /// Add a region of the binary benchmark run of which the costs are reported separately
+    ///
+    /// See also [`BinaryBenchmarkConfig::region`].
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use iai_callgrind::main;
+    /// use iai_callgrind::{binary_benchmark_group, Arg, BinaryBenchmarkGroup, Run};
+    ///
+    /// binary_benchmark_group!(
+    ///     name = my_group;
+    ///     benchmark = |"my-exe", group: &mut BinaryBenchmarkGroup| {
+    ///         group.bench(
+    ///             Run::with_arg(Arg::new("foo", ["foo.txt"]))
+    ///                 .region("my_exe::parse")
+    ///                 .region("my_exe::evaluate")
+    ///         );
+    ///     }
+    /// );
+    /// # fn main() {
+    /// # main!(binary_benchmark_groups = my_group);
+    /// # }
+    /// ```
+    pub fn region<T>(&mut self, region: T) -> &mut Self
+    where
+        T: Into<String>,
+    {
+        self.0.config.regions.push(region.into());
+        self
+    }
+
     /// Set the expected exit status [`ExitWith`] of a benchmarked binary
     ///
     /// See also [`BinaryBenchmarkConfig::exit_with`]
@@ -1527,6 +1836,90 @@ impl Run {
         self
     }
 
+    /// Assert that the stdout of the benchmarked binary contains the `string` at `Run` level
+    ///
+    /// See also [`BinaryBenchmarkConfig::assert_stdout_contains`]
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use iai_callgrind::main;
+    /// use iai_callgrind::{binary_benchmark_group, Arg, BinaryBenchmarkGroup, Run};
+    ///
+    /// binary_benchmark_group!(
+    ///     name = my_group;
+    ///     benchmark = |"my-exe", group: &mut BinaryBenchmarkGroup| {
+    ///         group.bench(
+    ///             Run::with_arg(Arg::new("greet", ["--name", "world"]))
+    ///                 .assert_stdout_contains("Hello, world")
+    ///         );
+    ///     }
+    /// );
+    /// # fn main() {
+    /// # main!(binary_benchmark_groups = my_group);
+    /// # }
+    /// ```
+    pub fn assert_stdout_contains<T>(&mut self, string: T) -> &mut Self
+    where
+        T: Into<String>,
+    {
+        self.0
+            .config
+            .output_assertions
+            .push(internal::InternalOutputAssertion::StdoutContains(
+                string.into(),
+            ));
+        self
+    }
+
+    /// Assert that the stdout of the benchmarked binary matches the `regex` at `Run` level
+    ///
+    /// See also [`BinaryBenchmarkConfig::assert_stdout_matches`]
+    pub fn assert_stdout_matches<T>(&mut self, regex: T) -> &mut Self
+    where
+        T: Into<String>,
+    {
+        self.0
+            .config
+            .output_assertions
+            .push(internal::InternalOutputAssertion::StdoutMatches(
+                regex.into(),
+            ));
+        self
+    }
+
+    /// Assert that the stderr of the benchmarked binary contains the `string` at `Run` level
+    ///
+    /// See also [`BinaryBenchmarkConfig::assert_stderr_contains`]
+    pub fn assert_stderr_contains<T>(&mut self, string: T) -> &mut Self
+    where
+        T: Into<String>,
+    {
+        self.0
+            .config
+            .output_assertions
+            .push(internal::InternalOutputAssertion::StderrContains(
+                string.into(),
+            ));
+        self
+    }
+
+    /// Assert that the stderr of the benchmarked binary matches the `regex` at `Run` level
+    ///
+    /// See also [`BinaryBenchmarkConfig::assert_stderr_matches`]
+    pub fn assert_stderr_matches<T>(&mut self, regex: T) -> &mut Self
+    where
+        T: Into<String>,
+    {
+        self.0
+            .config
+            .output_assertions
+            .push(internal::InternalOutputAssertion::StderrMatches(
+                regex.into(),
+            ));
+        self
+    }
+
     /// Pass arguments to valgrind's callgrind at `Run` level
     ///
     /// See also [`BinaryBenchmarkConfig::raw_callgrind_args`]
@@ -54,6 +54,23 @@ use super::{bindings, fatal_error};
 /// the user. If no meaningful `weight` argument exists, just use 1.
 ///
 /// See also [Ad hoc profiling](https://valgrind.org/docs/manual/dh-manual.html#dh-manual.ad-hoc-profiling)
+///
+/// # Examples
+///
+/// Count the reallocations of a vector with the new capacity as weight:
+///
+/// ```rust
+/// use iai_callgrind::client_requests::dhat;
+///
+/// let mut vec = Vec::new();
+/// for value in 0..100u64 {
+///     let capacity = vec.capacity();
+///     vec.push(value);
+///     if vec.capacity() != capacity {
+///         dhat::ad_hoc_event(vec.capacity());
+///     }
+/// }
+/// ```
 #[inline(always)]
 pub fn ad_hoc_event(weight: usize) {
     do_client_request!(
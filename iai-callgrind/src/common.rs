@@ -1,6 +1,9 @@
 //! Common structs for `bin_bench` and `lib_bench`
 
-use super::{internal, Direction, EventKind, FlamegraphKind, ValgrindTool};
+use super::{
+    internal, Direction, Ellipsis, EventKind, FlamegraphKind, FrameLabel, ObjectMarker, Palette,
+    RootFrame, Rounding, SymbolTransform, ValgrindTool,
+};
 
 /// The `FlamegraphConfig` which allows the customization of the created flamegraphs
 ///
@@ -60,7 +63,9 @@ pub struct RegressionConfig(internal::InternalRegressionConfig);
 
 /// Configure to run other valgrind tools like `DHAT` or `Massif` in addition to callgrind
 ///
-/// For a list of possible tools see [`ValgrindTool`].
+/// For a list of possible tools see [`ValgrindTool`]. Callgrind and cachegrind can't be run as
+/// additional tool. To replace callgrind with another tool, see
+/// [`crate::LibraryBenchmarkConfig::default_tool`].
 ///
 /// See also the [Valgrind User Manual](https://valgrind.org/docs/manual/manual.html) for details
 /// about possible tools and their command line arguments.
@@ -97,6 +102,15 @@ impl FlamegraphConfig {
     ///
     /// let config = FlamegraphConfig::default().kind(FlamegraphKind::Differential);
     /// ```
+    ///
+    /// Or to show the flamegraphs of the old and new run side by side in a html page instead of
+    /// the overlaid differential flamegraph:
+    ///
+    /// ```
+    /// use iai_callgrind::{FlamegraphConfig, FlamegraphKind};
+    ///
+    /// let config = FlamegraphConfig::default().kind(FlamegraphKind::SideBySide);
+    /// ```
     pub fn kind(&mut self, kind: FlamegraphKind) -> &mut Self {
         self.0.kind = Some(kind);
         self
@@ -174,6 +188,7 @@ impl FlamegraphConfig {
     /// * [`EventKind::LLhits`]
     /// * [`EventKind::RamHits`]
     /// * [`EventKind::TotalRW`]
+    /// * [`EventKind::DataRW`]
     /// * [`EventKind::EstimatedCycles`]
     ///
     /// # Examples
@@ -255,6 +270,533 @@ impl FlamegraphConfig {
         self.0.min_width = Some(min_width);
         self
     }
+
+    /// Set the minimum absolute cost of a stack in the flamegraphs
+    ///
+    /// In contrast to [`FlamegraphConfig::min_width`], which depends on the total cost of the
+    /// flamegraph, this is an absolute cost of the flamegraph's [`EventKind`] like the number of
+    /// instructions. This is useful to compare flamegraphs of differently sized benchmark runs.
+    /// The (self) costs of the innermost frame of a stack below this minimum are added to the
+    /// costs of its caller, so the total costs of the flamegraph don't change.
+    ///
+    /// The default is `0` which doesn't remove any stacks.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use iai_callgrind::FlamegraphConfig;
+    ///
+    /// let config = FlamegraphConfig::default().min_cost(1000);
+    /// ```
+    pub fn min_cost(&mut self, min_cost: u64) -> &mut Self {
+        self.0.min_cost = Some(min_cost);
+        self
+    }
+
+    /// Inject content right after the opening `<svg>` tag of the flamegraphs
+    ///
+    /// This is the place for a `<style>` element with custom CSS or a `<script>` element which
+    /// should be loaded before the content of the flamegraph. The xml declaration and doctype in
+    /// front of the `<svg>` element are not changed. The `header` is injected as it is, so it has
+    /// to be valid svg (xml) content to not corrupt the flamegraphs.
+    ///
+    /// The default is to not inject anything.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use iai_callgrind::FlamegraphConfig;
+    ///
+    /// let config =
+    ///     FlamegraphConfig::default().svg_header("<style>text { font-family: monospace; }</style>");
+    /// ```
+    pub fn svg_header<T>(&mut self, header: T) -> &mut Self
+    where
+        T: Into<String>,
+    {
+        self.0.svg_header = Some(header.into());
+        self
+    }
+
+    /// Inject content right before the closing `</svg>` tag of the flamegraphs
+    ///
+    /// This is the place for a `<script>` element which should run after the content of the
+    /// flamegraph is loaded. Like the [`FlamegraphConfig::svg_header`], the `footer` is injected
+    /// as it is and has to be valid svg (xml) content.
+    ///
+    /// The default is to not inject anything.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use iai_callgrind::FlamegraphConfig;
+    ///
+    /// let config = FlamegraphConfig::default()
+    ///     .svg_footer("<script type=\"text/ecmascript\">console.log(\"loaded\");</script>");
+    /// ```
+    pub fn svg_footer<T>(&mut self, footer: T) -> &mut Self
+    where
+        T: Into<String>,
+    {
+        self.0.svg_footer = Some(footer.into());
+        self
+    }
+
+    /// Set the maximum number of frames of a stack in the flamegraphs
+    ///
+    /// Deep recursions or long call chains make flamegraphs hard to read. The frames of a stack
+    /// beyond this depth are replaced by a single frame labeled `...` which receives the costs of
+    /// all the truncated frames, so the total costs of the flamegraph don't change. The code of
+    /// inlined functions is shown as a separate frame of the source file it was inlined from.
+    /// These frames count toward the depth unless switched off with
+    /// [`FlamegraphConfig::max_depth_count_inline`].
+    ///
+    /// The default is no limit.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use iai_callgrind::FlamegraphConfig;
+    ///
+    /// let config = FlamegraphConfig::default().max_depth(50);
+    /// ```
+    pub fn max_depth(&mut self, max_depth: usize) -> &mut Self {
+        self.0.max_depth = Some(max_depth);
+        self
+    }
+
+    /// If false, the frames of inlined code don't count toward the
+    /// [`FlamegraphConfig::max_depth`]
+    ///
+    /// Callgrind records the costs of code which was inlined from another source file with this
+    /// source file, so the inlined code appears as a separate frame below the frame of the
+    /// function it was inlined into. If such frames shouldn't use up the
+    /// [`FlamegraphConfig::max_depth`], set this option to false. The frames are still shown.
+    ///
+    /// The default is true.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use iai_callgrind::FlamegraphConfig;
+    ///
+    /// let config = FlamegraphConfig::default()
+    ///     .max_depth(50)
+    ///     .max_depth_count_inline(false);
+    /// ```
+    pub fn max_depth_count_inline(&mut self, value: bool) -> &mut Self {
+        self.0.max_depth_count_inline = Some(value);
+        self
+    }
+
+    /// Transform the symbol names in the stacks of the flamegraphs
+    ///
+    /// Some symbol names are noisy, like the hash suffixes of rust symbols or `{{closure}}`
+    /// suffixes. The transformations are applied in the given order to each frame of a stack
+    /// before equal stacks are merged, so frames which are equal after the transformation are
+    /// merged into a single frame and no costs get lost. The frames include the file and object
+    /// of the symbol if present. The built-in [`SymbolTransform::StripHash`] strips the hash
+    /// suffixes of rust symbols and [`SymbolTransform::Replace`] replaces all matches of a regular
+    /// expression.
+    ///
+    /// The default is to not transform any symbol names.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use iai_callgrind::{FlamegraphConfig, SymbolTransform};
+    ///
+    /// let config = FlamegraphConfig::default().symbol_transforms([
+    ///     SymbolTransform::StripHash,
+    ///     SymbolTransform::Replace(r"::\{\{closure\}\}".to_owned(), String::new()),
+    /// ]);
+    /// ```
+    pub fn symbol_transforms<T>(&mut self, transforms: T) -> &mut Self
+    where
+        T: IntoIterator<Item = SymbolTransform>,
+    {
+        self.0.symbol_transforms = Some(transforms.into_iter().collect());
+        self
+    }
+
+    /// If true, sort the stacks before the creation of the flamegraphs
+    ///
+    /// The stacks are sorted lexicographically by their frames, so stacks sharing a prefix of
+    /// frames are adjacent, and inferno, the library creating the flamegraphs, doesn't sort the
+    /// stacks again. Note the order of sibling frames in the flamegraph follows the order of the
+    /// frames and can differ from the order inferno would produce. Differential flamegraphs are
+    /// always sorted by inferno.
+    ///
+    /// Measured on a synthetic fixture with 200,000 stacks of 5 to 30 frames, inferno spends
+    /// about 20-30% less time creating the flamegraph from the sorted stacks, but sorting the
+    /// stacks takes about as much time as inferno saves. So, don't expect a considerable
+    /// speedup in total.
+    ///
+    /// The default is `false`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use iai_callgrind::FlamegraphConfig;
+    ///
+    /// let config = FlamegraphConfig::default().sort_stacks(true);
+    /// ```
+    pub fn sort_stacks(&mut self, value: bool) -> &mut Self {
+        self.0.sort_stacks = Some(value);
+        self
+    }
+
+    /// If true, merge the stacks with equal frames before the creation of the flamegraphs
+    ///
+    /// Stacks with equal frames appear for example if the
+    /// [`FlamegraphConfig::symbol_transforms`] strip the details which distinguish two stacks.
+    /// Depending on the order of the stacks, inferno, the library creating the flamegraphs, shows
+    /// such stacks as separate slivers. The merged stack has the sum of the costs of all merged
+    /// stacks.
+    ///
+    /// The default is `false`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use iai_callgrind::FlamegraphConfig;
+    ///
+    /// let config = FlamegraphConfig::default().coalesce_stacks(true);
+    /// ```
+    pub fn coalesce_stacks(&mut self, value: bool) -> &mut Self {
+        self.0.coalesce_stacks = Some(value);
+        self
+    }
+
+    /// If false, don't keep the flamegraphs of the last run as `*.old.svg` files
+    ///
+    /// Before the creation of the new flamegraphs, the flamegraphs of the last run are moved to
+    /// `*.old.svg` files. On slow file systems, like some network or overlay file systems on
+    /// CI, this backup takes noticeable time and the old flamegraphs are often not needed. With
+    /// `backup(false)`, the flamegraphs of the last run are removed instead. The differential
+    /// flamegraphs are still created, since they are based on the callgrind output files and not
+    /// on the old flamegraphs.
+    ///
+    /// If the backup fails because the file system is read-only or full, iai-callgrind logs a
+    /// warning and continues with the creation of the new flamegraphs.
+    ///
+    /// The default is `true`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use iai_callgrind::FlamegraphConfig;
+    ///
+    /// let config = FlamegraphConfig::default().backup(false);
+    /// ```
+    pub fn backup(&mut self, value: bool) -> &mut Self {
+        self.0.backup = Some(value);
+        self
+    }
+
+    /// Tag the file names of the flamegraphs, for example with a commit hash or baseline name
+    ///
+    /// The file names of the flamegraphs are the same for each run, so archived flamegraphs of
+    /// different commits overwrite each other. With a tag, the flamegraphs are named like
+    /// `callgrind.bench_name.flamegraph@tag.Ir.svg` instead of
+    /// `callgrind.bench_name.flamegraph.Ir.svg`. The rotation to the `*.old.svg` files and the
+    /// differential flamegraphs use the same tag, so the flamegraphs of a different tag are left
+    /// untouched. Characters which are invalid in file names like `/` are replaced with `_`.
+    ///
+    /// The default is no tag.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use iai_callgrind::FlamegraphConfig;
+    ///
+    /// let tag = std::env::var("GITHUB_SHA").unwrap_or_else(|_| "local".to_owned());
+    /// let config = FlamegraphConfig::default().tag(tag);
+    /// ```
+    pub fn tag<T>(&mut self, tag: T) -> &mut Self
+    where
+        T: Into<String>,
+    {
+        self.0.tag = Some(tag.into());
+        self
+    }
+
+    /// Append the cost of each frame to its label in the regular flamegraph
+    ///
+    /// The cost of a frame is the inclusive cost of this frame and all frames it calls, like the
+    /// width of the frame in the flamegraph. With [`FrameLabel::Cost`] the label of a frame looks
+    /// like `my_func (12,345)`, with [`FrameLabel::Percentage`] like `my_func (3.2%)` and with
+    /// [`FrameLabel::CostAndPercentage`] like `my_func (12,345 | 3.2%)`. The percentage is relative
+    /// to the total cost of all stacks. Differential flamegraphs are never labeled.
+    ///
+    /// The default is to not append the cost.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use iai_callgrind::{FlamegraphConfig, FrameLabel};
+    ///
+    /// let config = FlamegraphConfig::default().frame_label(FrameLabel::CostAndPercentage);
+    /// ```
+    pub fn frame_label(&mut self, frame_label: FrameLabel) -> &mut Self {
+        self.0.frame_label = Some(frame_label);
+        self
+    }
+
+    /// Scale the costs of the regular flamegraph to percentages of the total cost
+    ///
+    /// The flamegraphs are created by inferno which needs integer counts, so the percentages are
+    /// multiplied with the `scale` and rounded with the [`FlamegraphConfig::rounding`]. The
+    /// `scale` is the effective precision of the flamegraph: A `scale` of `100` resolves `0.01%`
+    /// and a `scale` of `1000` resolves `0.001%`. The total count of the flamegraph is about
+    /// `100 * scale` but, since each frame is rounded separately, the rounding errors of the frames
+    /// add up and the total may be a little more or less. The labels of the
+    /// [`FlamegraphConfig::frame_label`] still show the original costs. Differential flamegraphs
+    /// are never scaled.
+    ///
+    /// The default is to not scale the costs. A `scale` of `0` is rejected with an error.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use iai_callgrind::FlamegraphConfig;
+    ///
+    /// let config = FlamegraphConfig::default().percentage_scale(1000);
+    /// ```
+    pub fn percentage_scale(&mut self, scale: u64) -> &mut Self {
+        self.0.percentage_scale = Some(scale);
+        self
+    }
+
+    /// The [`Rounding`] of the costs scaled to percentages with the
+    /// [`FlamegraphConfig::percentage_scale`]
+    ///
+    /// With [`Rounding::Floor`] and [`Rounding::Round`] frames with a cost below the precision of
+    /// the scale vanish from the flamegraph. [`Rounding::Ceil`] keeps all frames with a cost
+    /// greater than zero.
+    ///
+    /// The default is [`Rounding::Round`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use iai_callgrind::{FlamegraphConfig, Rounding};
+    ///
+    /// let config = FlamegraphConfig::default()
+    ///     .percentage_scale(100)
+    ///     .rounding(Rounding::Ceil);
+    /// ```
+    pub fn rounding(&mut self, rounding: Rounding) -> &mut Self {
+        self.0.rounding = Some(rounding);
+        self
+    }
+
+    /// If true, remove the frames of the benchmark harness above the benchmark function
+    ///
+    /// The frames of the harness, like `(below main)` and `std::rt::lang_start`, are the callers
+    /// of the benchmark function and build a tall spine of frames without self costs on top of
+    /// every flamegraph. If set to true, these frames are removed and the flamegraph starts with
+    /// the benchmark function. For library benchmarks the benchmark function is the function
+    /// annotated with `#[library_benchmark]`, for binary benchmarks it is the `entry_point` of a
+    /// `Run` or `main` if no entry point was specified.
+    ///
+    /// The default is `true` for library benchmarks and `false` for binary benchmarks.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use iai_callgrind::FlamegraphConfig;
+    ///
+    /// let config = FlamegraphConfig::default().trim_harness(false);
+    /// ```
+    pub fn trim_harness(&mut self, value: bool) -> &mut Self {
+        self.0.trim_harness = Some(value);
+        self
+    }
+
+    /// Set how the object file of a function is marked in the frames of the flamegraphs
+    ///
+    /// The object file is the binary or shared library which contains the function. With
+    /// [`ObjectMarker::Bracketed`] a frame looks like `my_func [/path/to/my_exe]`, with
+    /// [`ObjectMarker::Suffix`] like `my_func (in /path/to/my_exe)` and with [`ObjectMarker::None`]
+    /// like `my_func`. Some tools which read the folded stacks of the flamegraphs treat brackets
+    /// specially, and the marker also clutters the flamegraphs of benchmarks running only in a
+    /// single binary.
+    ///
+    /// The default is [`ObjectMarker::Bracketed`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use iai_callgrind::{FlamegraphConfig, ObjectMarker};
+    ///
+    /// let config = FlamegraphConfig::default().object_marker(ObjectMarker::None);
+    /// ```
+    pub fn object_marker(&mut self, object_marker: ObjectMarker) -> &mut Self {
+        self.0.object_marker = Some(object_marker);
+        self
+    }
+
+    /// Color the frames of the regular flamegraphs by the density of another [`EventKind`]
+    ///
+    /// Without this option, the colors of the frames are random and carry no meaning. With this
+    /// option, the width of a frame is still determined by the [`EventKind`] of the flamegraph
+    /// but the color shows the ratio of the inclusive costs of the `color_event_kind` to the
+    /// inclusive costs of the [`EventKind`] of the flamegraph. For example, with a flamegraph of
+    /// [`EventKind::Ir`] and the `color_event_kind` [`EventKind::DLmr`] a wide light yellow
+    /// function executes many instructions with few last level cache misses and a narrow dark
+    /// red function executes few instructions but has the most cache misses per instruction.
+    /// The colors are relative to the frame with the highest density in the flamegraph. Frames
+    /// without costs are grey. The subtitle of the flamegraph names both event kinds.
+    ///
+    /// The `color_event_kind` has to be collected by callgrind, so [`EventKind::DLmr`] needs
+    /// `--cache-sim=yes`, and can't be the [`EventKind::Score`]. It doesn't apply to the
+    /// differential flamegraphs, which are colored by the difference to the baseline, to a
+    /// flamegraph of the `color_event_kind` itself and to flamegraphs with a
+    /// [`FlamegraphConfig::frame_label`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use iai_callgrind::{EventKind, FlamegraphConfig};
+    ///
+    /// let config = FlamegraphConfig::default().color_event_kind(EventKind::DLmr);
+    /// ```
+    pub fn color_event_kind(&mut self, color_event_kind: EventKind) -> &mut Self {
+        self.0.color_event_kind = Some(color_event_kind);
+        self
+    }
+
+    /// Choose the color [`Palette`] of the frames of the regular flamegraphs
+    ///
+    /// The palettes are the built-in palettes of inferno. Without this option, inferno's default
+    /// palette [`Palette::Hot`] is used. The palette can also be selected by its name, like `mem`
+    /// or `rust`, which fails with the list of all valid names if the name is unknown. Like all
+    /// other colors, the palette doesn't apply if the frames are colored with
+    /// [`FlamegraphConfig::color_event_kind`] and to the differential flamegraphs.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use iai_callgrind::{FlamegraphConfig, Palette};
+    ///
+    /// let config = FlamegraphConfig::default().palette(Palette::Mem);
+    /// let config = FlamegraphConfig::default().palette("rust".parse::<Palette>().unwrap());
+    /// ```
+    pub fn palette(&mut self, palette: Palette) -> &mut Self {
+        self.0.palette = Some(palette);
+        self
+    }
+
+    /// Truncate the names of the frames in the flamegraphs to this maximum number of characters
+    ///
+    /// The demangled names of rust functions with their full paths and generic arguments easily
+    /// overflow the frames of the flamegraphs. Longer names are cut as configured with
+    /// [`FlamegraphConfig::frame_name_ellipsis`] and the cut characters are replaced with `...`.
+    /// The truncation is applied after all other transformations of the stacks like
+    /// [`FlamegraphConfig::coalesce_stacks`] and only changes how the frames are displayed, not
+    /// which frames are coalesced or their costs.
+    ///
+    /// The default is no truncation.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use iai_callgrind::FlamegraphConfig;
+    ///
+    /// let config = FlamegraphConfig::default().max_frame_name_length(60);
+    /// ```
+    pub fn max_frame_name_length(&mut self, max_length: usize) -> &mut Self {
+        self.0.max_frame_name_length = Some(max_length);
+        self
+    }
+
+    /// Set where the frame names longer than [`FlamegraphConfig::max_frame_name_length`] are cut
+    ///
+    /// [`Ellipsis::Middle`] keeps the final path segment of the name (usually the most informative
+    /// part like `my_func` of `my_crate::my_mod::my_func`) and as much of the beginning as fits.
+    /// [`Ellipsis::End`] keeps the beginning of the name.
+    ///
+    /// The default is [`Ellipsis::Middle`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use iai_callgrind::{Ellipsis, FlamegraphConfig};
+    ///
+    /// let config = FlamegraphConfig::default()
+    ///     .max_frame_name_length(60)
+    ///     .frame_name_ellipsis(Ellipsis::End);
+    /// ```
+    pub fn frame_name_ellipsis(&mut self, ellipsis: Ellipsis) -> &mut Self {
+        self.0.frame_name_ellipsis = Some(ellipsis);
+        self
+    }
+
+    /// Add a root frame with the command (and pid) of the benchmarked process to the flamegraphs
+    ///
+    /// All stacks of the flamegraphs are grouped below this synthetic root frame, which is useful
+    /// to see which command a flamegraph belongs to. The command is the file name of the
+    /// executable like `my-bin`. The flamegraphs show the costs of the benchmarked process only,
+    /// so the root frame is always the one of this process even if it runs with
+    /// `--trace-children=yes`.
+    /// [`RootFrame::CommandAndPid`] also adds the pid of the process like `my-bin (pid 1234)`. The
+    /// root frame of the old flamegraph is the root frame of the new flamegraph, otherwise the
+    /// differing pids would break the differential flamegraphs.
+    ///
+    /// The root frame is added after all other transformations and is not changed by the
+    /// [`FlamegraphConfig::symbol_transforms`] and [`FlamegraphConfig::max_frame_name_length`]. It
+    /// doesn't count towards the [`FlamegraphConfig::max_depth`].
+    ///
+    /// The default is [`RootFrame::None`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use iai_callgrind::{FlamegraphConfig, RootFrame};
+    ///
+    /// let config = FlamegraphConfig::default().root_frame(RootFrame::CommandAndPid);
+    /// ```
+    pub fn root_frame(&mut self, root_frame: RootFrame) -> &mut Self {
+        self.0.root_frame = Some(root_frame);
+        self
+    }
+
+    /// Set the unit of the costs of an [`EventKind`] shown in the flamegraphs
+    ///
+    /// The unit is shown next to the costs when hovering over a frame, like `main (12,345
+    /// instructions, 100%)`. The `singular` is only used if the total cost of the flamegraph is
+    /// exactly `1`. Setting the unit for the same [`EventKind`] again overwrites the earlier unit.
+    /// The units of a more specific configuration, like the `FlamegraphConfig` of a benchmark,
+    /// overwrite only the units of the same [`EventKind`] of the less specific configurations.
+    ///
+    /// The default units are built-in for most `EventKinds`, like `instructions` for
+    /// [`EventKind::Ir`] or `L1 data read misses` for [`EventKind::D1mr`]. `EventKinds` without a
+    /// built-in unit use the name of the `EventKind` itself.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use iai_callgrind::{EventKind, FlamegraphConfig};
+    ///
+    /// let config = FlamegraphConfig::default().count_unit(EventKind::Ir, "instr", "instrs");
+    /// ```
+    pub fn count_unit<T, U>(&mut self, event_kind: EventKind, singular: T, plural: U) -> &mut Self
+    where
+        T: Into<String>,
+        U: Into<String>,
+    {
+        let count_units = self.0.count_units.get_or_insert_with(Vec::new);
+        count_units.retain(|(kind, _)| *kind != event_kind);
+        count_units.push((
+            event_kind,
+            internal::InternalCountUnit {
+                singular: singular.into(),
+                plural: plural.into(),
+            },
+        ));
+        self
+    }
 }
 
 impl_traits!(FlamegraphConfig, internal::InternalFlamegraphConfig);
@@ -312,6 +854,34 @@ impl RegressionConfig {
         self
     }
 
+    /// Configure the minimum absolute differences to be exceeded for a performance regression
+    ///
+    /// Percentages can be misleading for benchmarks with very small costs. For example, a change
+    /// from `4` to `6` instructions is an increase of `50%` but is most likely just noise. A
+    /// difference of an [`EventKind`] which is smaller than or equal to the minimum absolute
+    /// difference configured here is not considered to be a regression, even if the limit
+    /// percentage (see [`RegressionConfig::limits`]) is exceeded. So, a regression has to exceed
+    /// both, the limit percentage and the minimum absolute difference.
+    ///
+    /// The default minimum absolute difference of all [`EventKind`]s is `0`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use iai_callgrind::{EventKind, RegressionConfig};
+    ///
+    /// let config = RegressionConfig::default()
+    ///     .limits([(EventKind::Ir, 5f64)])
+    ///     .min_abs_diffs([(EventKind::Ir, 100)]);
+    /// ```
+    pub fn min_abs_diffs<T>(&mut self, targets: T) -> &mut Self
+    where
+        T: IntoIterator<Item = (EventKind, u64)>,
+    {
+        self.0.min_abs_diffs.extend(targets);
+        self
+    }
+
     /// If set to true, then the benchmarks fail on the first encountered regression
     ///
     /// The default is `false` and the whole benchmark run fails with a regression error after all
@@ -328,6 +898,68 @@ impl RegressionConfig {
         self.0.fail_fast = Some(value);
         self
     }
+
+    /// Remove the limits inherited from the group or the `main!` macro
+    ///
+    /// The [`RegressionConfig::limits`] of a benchmark are merged with the inherited limits per
+    /// [`EventKind`], so a benchmark can change but not remove an inherited limit. With
+    /// `clear_limits`, the inherited limits and the limits configured so far are removed and only
+    /// the limits configured afterwards are used. If no limits are left, the default limit
+    /// applies.
+    ///
+    /// # Examples
+    ///
+    /// Only check the `Dr` event kind, no matter which limits were inherited:
+    ///
+    /// ```
+    /// use iai_callgrind::{EventKind, RegressionConfig};
+    ///
+    /// let config = RegressionConfig::default()
+    ///     .clear_limits()
+    ///     .limits([(EventKind::Dr, 5f64)]);
+    /// ```
+    pub fn clear_limits(&mut self) -> &mut Self {
+        self.0.clear_limits = true;
+        self.0.limits.clear();
+        self
+    }
+
+    /// Configure the weights of the events of a combined score
+    ///
+    /// The score ([`EventKind::Score`]) is the weighted sum of the costs of the configured
+    /// [`EventKind`]s rounded to the nearest integer. Like any other event, the score is shown in
+    /// the terminal output, is part of the json output and can be used in the
+    /// [`RegressionConfig::limits`]. Derived events like [`EventKind::EstimatedCycles`] can be
+    /// weighted, too. All weighted events need to be collected and the weights need to be zero
+    /// or positive.
+    ///
+    /// Scores are only comparable if they were calculated with the same weights. The score of the
+    /// old output files of a previous run is calculated with the current weights, but the scores
+    /// stored in the `summary.json` files, like the ones compared with `--baseline-summary` or
+    /// merged with `--merge-summary`, keep the weights of the run which created them.
+    ///
+    /// Configuring the weights doesn't configure any limit. Like with an otherwise empty
+    /// `RegressionConfig`, the costs of the primary event kind are limited to `10%` if no
+    /// [`RegressionConfig::limits`] are given.
+    ///
+    /// The default is no score.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use iai_callgrind::{EventKind, RegressionConfig};
+    ///
+    /// let config = RegressionConfig::default()
+    ///     .score([(EventKind::Ir, 1f64), (EventKind::EstimatedCycles, 10f64)])
+    ///     .limits([(EventKind::Score, 5f64)]);
+    /// ```
+    pub fn score<T>(&mut self, weights: T) -> &mut Self
+    where
+        T: IntoIterator<Item = (EventKind, f64)>,
+    {
+        self.0.score.extend(weights);
+        self
+    }
 }
 
 impl_traits!(RegressionConfig, internal::InternalRegressionConfig);
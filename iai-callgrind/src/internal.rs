@@ -6,12 +6,13 @@ pub use iai_callgrind_runner::api::{
     Arg as InternalArg, Assistant as InternalAssistant, BinaryBenchmark as InternalBinaryBenchmark,
     BinaryBenchmarkConfig as InternalBinaryBenchmarkConfig,
     BinaryBenchmarkGroup as InternalBinaryBenchmarkGroup, Cmd as InternalCmd,
-    ExitWith as InternalExitWith, Fixtures as InternalFixtures,
+    CountUnit as InternalCountUnit, ExitWith as InternalExitWith, Fixtures as InternalFixtures,
     FlamegraphConfig as InternalFlamegraphConfig, LibraryBenchmark as InternalLibraryBenchmark,
     LibraryBenchmarkBench as InternalLibraryBenchmarkBench,
     LibraryBenchmarkBenches as InternalLibraryBenchmarkBenches,
     LibraryBenchmarkConfig as InternalLibraryBenchmarkConfig,
-    LibraryBenchmarkGroup as InternalLibraryBenchmarkGroup, RawArgs as InternalRawArgs,
+    LibraryBenchmarkGroup as InternalLibraryBenchmarkGroup,
+    OutputAssertion as InternalOutputAssertion, RawArgs as InternalRawArgs,
     RegressionConfig as InternalRegressionConfig, Run as InternalRun, Tool as InternalTool,
     Tools as InternalTools,
 };
@@ -23,3 +24,23 @@ pub struct InternalMacroLibBench {
     pub func: fn(),
     pub config: Option<fn() -> crate::internal::InternalLibraryBenchmarkConfig>,
 }
+
+/// Call the benchmark function `func` `iterations` times before the measured call in [`measure`]
+///
+/// The runner adds `--zero-before=iai_callgrind::measure` for benchmarks with warmup iterations.
+/// Callgrind zeroes all costs collected so far when entering [`measure`], so neither the warmup
+/// iterations nor the evaluation of their arguments and setup show up in the measured costs.
+#[inline(never)]
+pub fn warmup(func: fn(), iterations: usize) {
+    for _ in 0..iterations {
+        std::hint::black_box(func)();
+    }
+    measure(func);
+}
+
+/// The measured call of the benchmark function `func` after the [`warmup`] iterations
+#[inline(never)]
+#[export_name = "iai_callgrind::measure"]
+pub fn measure(func: fn()) {
+    std::hint::black_box(func)();
+}
@@ -400,6 +400,9 @@ pub use cty;
 #[cfg(feature = "default")]
 pub use iai_callgrind_macros::library_benchmark;
 #[cfg(feature = "default")]
-pub use iai_callgrind_runner::api::{Direction, EventKind, FlamegraphKind, ValgrindTool};
+pub use iai_callgrind_runner::api::{
+    Direction, Ellipsis, EventKind, FlamegraphKind, FrameLabel, ObjectMarker, Palette, RootFrame,
+    Rounding, SymbolTransform, ValgrindTool,
+};
 #[cfg(feature = "default")]
 pub use lib_bench::LibraryBenchmarkConfig;
@@ -1,6 +1,6 @@
 use std::ffi::OsString;
 
-use crate::internal;
+use crate::{internal, ValgrindTool};
 
 /// The main configuration of a library benchmark.
 ///
@@ -55,6 +55,9 @@ impl LibraryBenchmarkConfig {
         Self(internal::InternalLibraryBenchmarkConfig {
             env_clear: Option::default(),
             raw_callgrind_args: internal::InternalRawArgs::from_iter(args),
+            cachegrind: Option::default(),
+            default_tool: Option::default(),
+            warmup: Option::default(),
             envs: Vec::default(),
             flamegraph_config: Option::default(),
             regression_config: Option::default(),
@@ -75,7 +78,7 @@ impl LibraryBenchmarkConfig {
     /// * `--I1=32768,8,64`
     /// * `--D1=32768,8,64`
     /// * `--LL=8388608,16,64`
-    /// * `--cache-sim=yes` (can't be changed)
+    /// * `--cache-sim=yes`
     /// * `--toggle-collect=*BENCHMARK_FILE::BENCHMARK_FUNCTION` (this first toggle can't
     /// be changed)
     /// * `--collect-atstart=no` (overwriting this setting will have no effect)
@@ -170,6 +173,129 @@ impl LibraryBenchmarkConfig {
         self.0.env_clear = Some(value);
         self
     }
+
+    /// If true, run the benchmark with cachegrind instead of callgrind (Default: false)
+    ///
+    /// Cachegrind is much faster than callgrind but doesn't collect a call graph. Only the
+    /// aggregate events of the whole benchmark run are reported. Note that this includes the
+    /// costs of the benchmark harness, so the results are not comparable to the results of a
+    /// callgrind run. Flamegraphs are not available and configuring them is an error unless the
+    /// [`crate::FlamegraphKind`] is `None`.
+    ///
+    /// Cachegrind understands only some of the callgrind arguments (`--I1`, `--D1`, `--LL`,
+    /// `--cache-sim` and `--branch-sim`). All other arguments apart from those managed by
+    /// `iai-callgrind` (like `--toggle-collect`) are passed to cachegrind as they are.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use iai_callgrind::{library_benchmark, library_benchmark_group};
+    /// # #[library_benchmark]
+    /// # fn some_func() {}
+    /// # library_benchmark_group!(name = some_group; benchmarks = some_func);
+    /// use iai_callgrind::{LibraryBenchmarkConfig, main};
+    ///
+    /// # fn main() {
+    /// main!(
+    ///     config = LibraryBenchmarkConfig::default().cachegrind(true);
+    ///     library_benchmark_groups = some_group
+    /// );
+    /// # }
+    /// ```
+    pub fn cachegrind(&mut self, value: bool) -> &mut Self {
+        self.0.cachegrind = Some(value);
+        self
+    }
+
+    /// Run the benchmark with this [`ValgrindTool`] instead of callgrind (Default: Callgrind)
+    ///
+    /// This setting takes precedence over [`LibraryBenchmarkConfig::cachegrind`] unless it is
+    /// inherited from the group or the `main!` macro and the benchmark itself switches on
+    /// cachegrind. [`ValgrindTool::Callgrind`] and [`ValgrindTool::Cachegrind`] report the costs
+    /// of the benchmark. With any other tool, like [`ValgrindTool::DHAT`] if you're only
+    /// interested in the heap usage, callgrind is not run at all and the benchmark runs only with
+    /// this tool and the additional [`LibraryBenchmarkConfig::tool`]s. A [`crate::Tool`] of the
+    /// same kind configures the arguments of the default tool.
+    ///
+    /// Only callgrind creates flamegraphs and only callgrind and cachegrind check for
+    /// regressions. Configuring a [`crate::FlamegraphConfig`] or [`crate::RegressionConfig`]
+    /// for a benchmark with another default tool is an error. A configuration inherited from the
+    /// group or the `main!` macro is skipped with a warning.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use iai_callgrind::{library_benchmark, library_benchmark_group};
+    /// use iai_callgrind::{main, LibraryBenchmarkConfig, ValgrindTool};
+    ///
+    /// #[library_benchmark]
+    /// fn with_callgrind() -> u64 {
+    ///     std::hint::black_box(42)
+    /// }
+    ///
+    /// #[library_benchmark(
+    ///     config = LibraryBenchmarkConfig::default().default_tool(ValgrindTool::Cachegrind)
+    /// )]
+    /// fn with_cachegrind() -> u64 {
+    ///     std::hint::black_box(42)
+    /// }
+    ///
+    /// #[library_benchmark(
+    ///     config = LibraryBenchmarkConfig::default().default_tool(ValgrindTool::DHAT)
+    /// )]
+    /// fn with_dhat() -> Vec<u64> {
+    ///     std::hint::black_box(vec![42; 1000])
+    /// }
+    ///
+    /// library_benchmark_group!(
+    ///     name = some_group;
+    ///     benchmarks = with_callgrind, with_cachegrind, with_dhat
+    /// );
+    ///
+    /// # fn main() {
+    /// main!(library_benchmark_groups = some_group);
+    /// # }
+    /// ```
+    pub fn default_tool(&mut self, tool: ValgrindTool) -> &mut Self {
+        self.0.default_tool = Some(tool);
+        self
+    }
+
+    /// Run the benchmark function this many times without collecting any events before the
+    /// measured run (Default: 0)
+    ///
+    /// Lazy initializations on the first call of a function, like of a `OnceCell` or a lazy
+    /// static, inflate the costs of the benchmark function although they occur only once in the
+    /// lifetime of a program. With warmup iterations, the benchmark function is called with the
+    /// same arguments but with the event collection switched off before the actual benchmark
+    /// run, so only the final call of the benchmark function is measured. Note that the setup
+    /// function is called again for each warmup iteration.
+    ///
+    /// The costs of the warmup iterations are discarded with callgrind's `--zero-before` before
+    /// the measured call. The warmup iterations have no effect on the costs of cachegrind (see
+    /// [`LibraryBenchmarkConfig::cachegrind`]) which always include the whole benchmark run.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use iai_callgrind::{library_benchmark, library_benchmark_group};
+    /// # #[library_benchmark]
+    /// # fn some_func() {}
+    /// # library_benchmark_group!(name = some_group; benchmarks = some_func);
+    /// use iai_callgrind::{LibraryBenchmarkConfig, main};
+    ///
+    /// # fn main() {
+    /// main!(
+    ///     config = LibraryBenchmarkConfig::default().warmup(1);
+    ///     library_benchmark_groups = some_group
+    /// );
+    /// # }
+    /// ```
+    pub fn warmup(&mut self, iterations: usize) -> &mut Self {
+        self.0.warmup = Some(iterations);
+        self
+    }
+
     /// Add an environment variables which will be available in library benchmarks
     ///
     /// These environment variables are available independently of the setting of
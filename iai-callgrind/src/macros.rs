@@ -370,7 +370,14 @@ macro_rules! main {
                                     .parse::<usize>()
                                     .expect("Expecting a valid bench index")
                             );
-                            $group::run(group_index, bench_index);
+                            let warmup = std::hint::black_box(
+                                args_iter
+                                    .next()
+                                    .expect("Expecting the number of warmup iterations")
+                                    .parse::<usize>()
+                                    .expect("Expecting a valid number of warmup iterations")
+                            );
+                            $group::run(group_index, bench_index, warmup);
                         }
                     )+
                     name => panic!("function '{}' not found in this scope", name)
@@ -810,8 +817,13 @@ macro_rules! library_benchmark_group {
             }
 
             #[inline(never)]
-            pub fn run(group_index: usize, bench_index: usize) {
-                (BENCHES[group_index].2[bench_index].func)();
+            pub fn run(group_index: usize, bench_index: usize, warmup: usize) {
+                let func = BENCHES[group_index].2[bench_index].func;
+                if warmup > 0 {
+                    $crate::internal::warmup(func, warmup);
+                } else {
+                    func();
+                }
             }
         }
     };